@@ -0,0 +1,312 @@
+//! Benchmarks of the model checker itself.
+//!
+//! Each benchmark explores a representative model and reports two numbers:
+//!
+//! - executions per second, measured over a fixed number of executions (see
+//!   `Builder::max_iterations`), which tracks the cost of the runtime. The
+//!   median of several samples is reported along with their range, after a
+//!   warm-up sample.
+//! - executions needed to fully explore the model, which tracks how much the
+//!   exploration is pruned, for example by DPOR.
+//!
+//! Compare a change against a saved baseline with:
+//!
+//! ```text
+//! cargo bench --bench model -- --save-baseline main
+//! # apply the change
+//! cargo bench --bench model -- --baseline main
+//! ```
+//!
+//! Any other argument filters the benchmarks by name.
+
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::model::{Builder, ExplorationStats};
+use loom::sync::atomic::{AtomicBool, AtomicUsize};
+use loom::sync::{mpsc, Arc, Mutex};
+use loom::thread;
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
+
+/// Executions run by each sample.
+const ITERATIONS: usize = 2_000;
+
+/// Samples taken of each benchmark, the median is reported.
+const SAMPLES: usize = 10;
+
+/// Samples discarded before measuring each benchmark.
+const WARM_UP_SAMPLES: usize = 1;
+
+struct Bench {
+    name: &'static str,
+    model: fn(),
+}
+
+const BENCHES: &[Bench] = &[
+    Bench {
+        name: "mutex_ping_pong",
+        model: mutex_ping_pong,
+    },
+    Bench {
+        name: "atomic_message_passing",
+        model: atomic_message_passing,
+    },
+    Bench {
+        name: "mpsc_queue",
+        model: mpsc_queue,
+    },
+];
+
+/// Two threads take turns incrementing a counter behind a mutex.
+fn mutex_ping_pong() {
+    let turn = Arc::new(Mutex::new(0));
+
+    let th = {
+        let turn = turn.clone();
+        thread::spawn(move || {
+            let mut done = 0;
+            while done < 4 {
+                let mut turn = turn.lock().unwrap();
+                if *turn % 2 == 1 {
+                    *turn += 1;
+                    done += 1;
+                } else {
+                    drop(turn);
+                    thread::yield_now();
+                }
+            }
+        })
+    };
+
+    let mut done = 0;
+    while done < 4 {
+        let mut turn = turn.lock().unwrap();
+        if *turn % 2 == 0 {
+            *turn += 1;
+            done += 1;
+        } else {
+            drop(turn);
+            thread::yield_now();
+        }
+    }
+
+    th.join().unwrap();
+    assert_eq!(8, *turn.lock().unwrap());
+}
+
+/// Three producers publish values with release stores, read by an acquire
+/// load.
+fn atomic_message_passing() {
+    let data = Arc::new([
+        AtomicUsize::new(0),
+        AtomicUsize::new(0),
+        AtomicUsize::new(0),
+    ]);
+    let ready = Arc::new([
+        AtomicBool::new(false),
+        AtomicBool::new(false),
+        AtomicBool::new(false),
+    ]);
+
+    let ths: Vec<_> = (0..3)
+        .map(|i| {
+            let data = data.clone();
+            let ready = ready.clone();
+            thread::spawn(move || {
+                data[i].store(i + 1, Relaxed);
+                ready[i].store(true, Release);
+            })
+        })
+        .collect();
+
+    for i in 0..3 {
+        if ready[i].load(Acquire) {
+            assert_eq!(i + 1, data[i].load(Relaxed));
+        }
+    }
+
+    for th in ths {
+        th.join().unwrap();
+    }
+}
+
+/// Three producers send to a single consumer through a channel.
+fn mpsc_queue() {
+    let (tx, rx) = mpsc::channel();
+
+    let ths: Vec<_> = (0..3)
+        .map(|i| {
+            let tx = tx.clone();
+            thread::spawn(move || {
+                tx.send(i).unwrap();
+                tx.send(i + 3).unwrap();
+            })
+        })
+        .collect();
+
+    drop(tx);
+
+    let mut sum = 0;
+    while let Ok(i) = rx.recv() {
+        sum += i;
+    }
+    assert_eq!(15, sum);
+
+    for th in ths {
+        th.join().unwrap();
+    }
+}
+
+fn builder() -> Builder {
+    let mut builder = Builder::new();
+    builder.preemption_bound = None;
+    // Only report the results of the benchmarks
+    builder.checkpoint_interval = usize::MAX;
+    builder
+}
+
+/// Runs `ITERATIONS` executions of the model, returns the executions per
+/// second.
+fn sample(model: fn()) -> f64 {
+    let mut builder = builder();
+    builder.max_iterations = Some(ITERATIONS);
+
+    let stats: ExplorationStats = builder.check_returning_stats(model);
+    stats.iterations as f64 / stats.duration.as_secs_f64()
+}
+
+struct Result {
+    per_sec: f64,
+    iterations: usize,
+}
+
+/// Slowest and fastest samples.
+struct Range {
+    min: f64,
+    max: f64,
+}
+
+fn run(bench: &Bench) -> (Result, Range) {
+    for _ in 0..WARM_UP_SAMPLES {
+        sample(bench.model);
+    }
+
+    let mut samples: Vec<f64> = (0..SAMPLES).map(|_| sample(bench.model)).collect();
+    samples.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    let iterations = builder().check_returning_stats(bench.model).iterations;
+
+    let result = Result {
+        per_sec: samples[SAMPLES / 2],
+        iterations,
+    };
+
+    let range = Range {
+        min: samples[0],
+        max: samples[SAMPLES - 1],
+    };
+
+    (result, range)
+}
+
+fn baseline_path(name: &str) -> PathBuf {
+    let target = env::var_os("CARGO_TARGET_DIR").unwrap_or_else(|| "target".into());
+    PathBuf::from(target).join("loom-bench").join(name)
+}
+
+fn load_baseline(name: &str) -> HashMap<String, Result> {
+    let path = baseline_path(name);
+    let contents = fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("failed to read baseline {}: {}", path.display(), e));
+
+    contents
+        .lines()
+        .map(|line| {
+            let mut fields = line.split_whitespace();
+            let mut next = || fields.next().expect("invalid baseline");
+            let name = next().to_string();
+            let per_sec = next().parse().expect("invalid baseline");
+            let iterations = next().parse().expect("invalid baseline");
+            (
+                name,
+                Result {
+                    per_sec,
+                    iterations,
+                },
+            )
+        })
+        .collect()
+}
+
+fn save_baseline(name: &str, results: &[(&str, Result)]) {
+    let path = baseline_path(name);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+
+    let contents: String = results
+        .iter()
+        .map(|(name, result)| format!("{} {} {}\n", name, result.per_sec, result.iterations))
+        .collect();
+    fs::write(&path, contents).unwrap();
+
+    println!("saved baseline to {}", path.display());
+}
+
+fn change(new: f64, old: f64) -> String {
+    format!("{:+.1}%", (new - old) / old * 100.0)
+}
+
+fn main() {
+    let mut save = None;
+    let mut baseline = None;
+    let mut filters = vec![];
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match &arg[..] {
+            "--save-baseline" => save = Some(args.next().expect("missing baseline name")),
+            "--baseline" => baseline = Some(args.next().expect("missing baseline name")),
+            // Passed by `cargo bench`
+            "--bench" => {}
+            _ => filters.push(arg),
+        }
+    }
+
+    let baseline = baseline.as_deref().map(load_baseline);
+    let mut results = vec![];
+
+    for bench in BENCHES {
+        if !filters.is_empty() && !filters.iter().any(|f| bench.name.contains(&f[..])) {
+            continue;
+        }
+
+        let (result, range) = run(bench);
+
+        print!(
+            "{:<24} {:>10.0} executions/s ({} .. {}) {:>8} executions to explore",
+            bench.name,
+            result.per_sec,
+            change(range.min, result.per_sec),
+            change(range.max, result.per_sec),
+            result.iterations
+        );
+
+        if let Some(old) = baseline.as_ref().and_then(|b| b.get(bench.name)) {
+            print!(
+                "  ({} executions/s, {} executions)",
+                change(result.per_sec, old.per_sec),
+                change(result.iterations as f64, old.iterations as f64)
+            );
+        }
+
+        println!();
+        results.push((bench.name, result));
+    }
+
+    if let Some(name) = save {
+        save_baseline(&name, &results);
+    }
+}
@@ -0,0 +1,255 @@
+//! Benchmarks of the representation of version vectors.
+//!
+//! `VersionVec` holds an array of `u16` versions, one per thread, joined and
+//! compared lane by lane. This compares it with versions packed four to a
+//! 64-bit word, joined and compared a word at a time (SWAR), for several
+//! thread counts since `MAX_THREADS` is a constant of the crate. Each
+//! benchmark joins and compares pairs of vectors and reports the median time
+//! per pair.
+//!
+//! ```text
+//! cargo bench --bench version_vec
+//! ```
+
+#![deny(warnings, rust_2018_idioms)]
+
+use std::cmp::{self, Ordering};
+use std::hint::black_box;
+use std::time::Instant;
+
+/// Vectors joined and compared by each sample.
+const VECTORS: usize = 1_024;
+
+/// Passes over the vectors by each sample.
+const PASSES: usize = 100;
+
+/// Samples taken of each benchmark, after as many warm-up ones. The median is
+/// reported.
+const SAMPLES: usize = 10;
+
+/// Arithmetic on versions packed in 64-bit words, one 16-bit lane per
+/// version. Each operation handles all the lanes of a word at once, without
+/// branches.
+mod lanes {
+    /// Versions packed in a word.
+    pub const LANES: usize = 4;
+
+    /// Bits of a lane.
+    pub const LANE_BITS: usize = 16;
+
+    /// The high bit of each lane.
+    const HIGH: u64 = 0x8000_8000_8000_8000;
+
+    /// Returns a word with the high bit set in each lane where `a` is lower
+    /// than `b`.
+    pub fn less(a: u64, b: u64) -> u64 {
+        // The high bit of a lane is set if the low bits of `a` are at least
+        // those of `b`. A lane of `a | HIGH` is larger than the one of
+        // `b & !HIGH`, the subtraction never borrows from the next lane.
+        let low_ge = (a | HIGH) - (b & !HIGH);
+
+        // Either the high bit of `a` is set and not the one of `b`, or they
+        // are equal and the low bits decide.
+        let ge = (a & !b) | (!(a ^ b) & low_ge);
+
+        !ge & HIGH
+    }
+
+    /// Returns the maximum of each lane of `a` and `b`.
+    pub fn max(a: u64, b: u64) -> u64 {
+        // Widen the high bits to whole lanes: `0x8000 - 1` does not borrow
+        // from the next lane.
+        let less = less(a, b);
+        let take_b = (less - (less >> (LANE_BITS - 1))) | less;
+
+        (a & !take_b) | (b & take_b)
+    }
+}
+
+trait Repr: Copy {
+    fn from_versions(versions: &[u16]) -> Self;
+
+    fn join(&mut self, other: &Self);
+
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering>;
+}
+
+/// Versions compared lane by lane, as by `VersionVec`.
+#[derive(Copy, Clone)]
+struct Array<const N: usize>([u16; N]);
+
+impl<const N: usize> Repr for Array<N> {
+    fn from_versions(versions: &[u16]) -> Self {
+        let mut array = [0; N];
+        array.copy_from_slice(versions);
+        Array(array)
+    }
+
+    fn join(&mut self, other: &Self) {
+        for (version, &other) in self.0.iter_mut().zip(&other.0) {
+            *version = cmp::max(*version, other);
+        }
+    }
+
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        let any_less =
+            |a: &[u16; N], b: &[u16; N]| a.iter().zip(b).fold(false, |less, (a, b)| less | (a < b));
+
+        match (any_less(&self.0, &other.0), any_less(&other.0, &self.0)) {
+            (false, false) => Some(Ordering::Equal),
+            (true, false) => Some(Ordering::Less),
+            (false, true) => Some(Ordering::Greater),
+            (true, true) => None,
+        }
+    }
+}
+
+/// Versions packed in words for each join and comparison.
+#[derive(Copy, Clone)]
+struct Packed<const N: usize>([u16; N]);
+
+impl<const N: usize> Packed<N> {
+    /// Returns the versions packed in words, the version of thread `i` in lane
+    /// `i % LANES` of word `i / LANES`.
+    fn words(&self) -> impl Iterator<Item = u64> + '_ {
+        self.0.chunks(lanes::LANES).map(|chunk| {
+            chunk.iter().rev().fold(0, |word, &version| {
+                word << lanes::LANE_BITS | version as u64
+            })
+        })
+    }
+}
+
+impl<const N: usize> Repr for Packed<N> {
+    fn from_versions(versions: &[u16]) -> Self {
+        Packed(Array::from_versions(versions).0)
+    }
+
+    fn join(&mut self, other: &Self) {
+        let joined = self
+            .words()
+            .zip(other.words())
+            .map(|(a, b)| lanes::max(a, b));
+        let mut versions = [0; N];
+
+        for (chunk, word) in versions.chunks_mut(lanes::LANES).zip(joined) {
+            for (i, version) in chunk.iter_mut().enumerate() {
+                *version = (word >> (i * lanes::LANE_BITS)) as u16;
+            }
+        }
+
+        self.0 = versions;
+    }
+
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        let any_less = |a: &Self, b: &Self| {
+            a.words()
+                .zip(b.words())
+                .fold(0, |less, (a, b)| less | lanes::less(a, b))
+                != 0
+        };
+
+        match (any_less(self, other), any_less(other, self)) {
+            (false, false) => Some(Ordering::Equal),
+            (true, false) => Some(Ordering::Less),
+            (false, true) => Some(Ordering::Greater),
+            (true, true) => None,
+        }
+    }
+}
+
+/// Returns `VECTORS` vectors of `threads` versions. As in a model, each
+/// vector is an earlier one with the version of a pseudo-random thread
+/// incremented, so that most pairs are ordered.
+fn versions(threads: usize) -> Vec<Vec<u16>> {
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    let mut random = move |n: usize| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state as usize % n
+    };
+
+    let mut vectors = vec![vec![0; threads]];
+
+    while vectors.len() < VECTORS {
+        let earlier = vectors.len() - 1 - random(vectors.len().min(4));
+        let mut vector = vectors[earlier].clone();
+        vector[random(threads)] += 1;
+        vectors.push(vector);
+    }
+
+    vectors
+}
+
+/// Joins and compares each pair of consecutive vectors, returning the
+/// duration of a pair in nanoseconds.
+fn sample<R: Repr>(vectors: &[R]) -> f64 {
+    let start = Instant::now();
+
+    for _ in 0..PASSES {
+        for pair in vectors.windows(2) {
+            let mut joined = pair[0];
+            joined.join(&pair[1]);
+            black_box(joined);
+            black_box(pair[0].partial_cmp(&pair[1]));
+        }
+    }
+
+    start.elapsed().as_nanos() as f64 / (PASSES * (vectors.len() - 1)) as f64
+}
+
+fn run<R: Repr>(versions: &[Vec<u16>]) -> f64 {
+    let vectors: Vec<R> = versions.iter().map(|v| R::from_versions(v)).collect();
+
+    for _ in 0..SAMPLES {
+        sample(&vectors);
+    }
+
+    let mut samples: Vec<_> = (0..SAMPLES).map(|_| sample(&vectors)).collect();
+    samples.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    samples[SAMPLES / 2]
+}
+
+/// Checks that both representations agree on the vectors.
+fn check<const N: usize>(versions: &[Vec<u16>]) {
+    for pair in versions.windows(2) {
+        let (mut a, b) = (
+            Array::<N>::from_versions(&pair[0]),
+            Array::<N>::from_versions(&pair[1]),
+        );
+        let (mut c, d) = (
+            Packed::<N>::from_versions(&pair[0]),
+            Packed::<N>::from_versions(&pair[1]),
+        );
+
+        assert_eq!(a.partial_cmp(&b), c.partial_cmp(&d));
+
+        a.join(&b);
+        c.join(&d);
+        assert_eq!(a.0, c.0);
+    }
+}
+
+fn bench<const N: usize>() {
+    let versions = versions(N);
+    check::<N>(&versions);
+
+    let array = run::<Array<N>>(&versions);
+    let packed = run::<Packed<N>>(&versions);
+
+    println!(
+        "{:>2} threads: array {:>5.2}ns, packed {:>5.2}ns per join and comparison ({:+.1}%)",
+        N,
+        array,
+        packed,
+        (packed - array) / array * 100.0
+    );
+}
+
+fn main() {
+    bench::<4>();
+    bench::<8>();
+    bench::<16>();
+    bench::<32>();
+}
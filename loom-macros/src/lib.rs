@@ -0,0 +1,255 @@
+//! Attribute macros for loom, re-exported by `loom` with the `macros`
+//! feature. See [`loom::test`](https://docs.rs/loom/latest/loom/attr.test.html).
+
+#![deny(missing_debug_implementations, missing_docs, rust_2018_idioms)]
+
+use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+
+use std::iter::FromIterator;
+
+/// Marks a function as a test checked by loom.
+///
+/// See the documentation of `loom::test`.
+#[proc_macro_attribute]
+pub fn test(args: TokenStream, item: TokenStream) -> TokenStream {
+    match expand(args, item) {
+        Ok(tokens) => tokens,
+        Err((msg, span)) => compile_error(&msg, span),
+    }
+}
+
+type Error = (String, Span);
+
+/// A `name = value` argument of the attribute, setting a field of the
+/// `Builder`.
+struct Setting {
+    name: Ident,
+    value: Vec<TokenTree>,
+}
+
+fn expand(args: TokenStream, item: TokenStream) -> Result<TokenStream, Error> {
+    let settings = parse_settings(args)?;
+
+    let mut item: Vec<TokenTree> = item.into_iter().collect();
+
+    let body = match item.pop() {
+        Some(TokenTree::Group(body)) if body.delimiter() == Delimiter::Brace => body,
+        _ => {
+            return Err((
+                "`#[loom::test]` can only be applied to functions".to_string(),
+                Span::call_site(),
+            ))
+        }
+    };
+
+    check_signature(&item)?;
+
+    // let mut builder = ::loom::model::Builder::new();
+    let mut model: Vec<TokenTree> = vec![];
+    model.extend(tokens("let mut builder ="));
+    model.extend(path(&["loom", "model", "Builder", "new"]));
+    model.push(group(Delimiter::Parenthesis, vec![]));
+    model.push(punct(';'));
+
+    // builder.name = ::loom::__macro_support::Setting::into_setting(value);
+    for setting in settings {
+        model.push(ident("builder"));
+        model.push(punct('.'));
+        model.push(TokenTree::Ident(setting.name));
+        model.push(punct('='));
+        model.extend(path(&[
+            "loom",
+            "__macro_support",
+            "Setting",
+            "into_setting",
+        ]));
+        model.push(group(Delimiter::Parenthesis, setting.value));
+        model.push(punct(';'));
+    }
+
+    // builder.check(|| body);
+    model.extend(tokens("builder.check"));
+    let mut closure = vec![punct_joint('|'), punct('|')];
+    closure.push(TokenTree::Group(body.clone()));
+    model.push(group(Delimiter::Parenthesis, closure));
+    model.push(punct(';'));
+
+    // #[cfg(loom)] { model } #[cfg(not(loom))] body
+    let mut new_body: Vec<TokenTree> = vec![];
+    new_body.extend(attribute(tokens("cfg(loom)")));
+    new_body.push(group(Delimiter::Brace, model));
+    new_body.extend(attribute(tokens("cfg(not(loom))")));
+    new_body.push(TokenTree::Group(body));
+
+    let mut test: Vec<TokenTree> = vec![];
+    test.push(punct_joint(':'));
+    test.push(punct(':'));
+    test.extend(tokens("core::prelude::v1::test"));
+
+    let mut out = attribute(test);
+    out.extend(item);
+    out.push(group(Delimiter::Brace, new_body));
+
+    Ok(TokenStream::from_iter(out))
+}
+
+/// Parses the `name = value, ...` arguments of the attribute.
+fn parse_settings(args: TokenStream) -> Result<Vec<Setting>, Error> {
+    let mut settings = vec![];
+    let mut args = args.into_iter();
+
+    while let Some(token) = args.next() {
+        let name = match token {
+            TokenTree::Ident(name) => name,
+            token => {
+                return Err((
+                    "expected a `Builder` setting, such as `preemption_bound = 3`".to_string(),
+                    token.span(),
+                ))
+            }
+        };
+
+        match args.next() {
+            Some(TokenTree::Punct(ref eq)) if eq.as_char() == '=' => {}
+            _ => return Err((format!("expected `=` after `{}`", name), name.span())),
+        }
+
+        let mut value = vec![];
+
+        for token in &mut args {
+            match token {
+                TokenTree::Punct(ref comma) if comma.as_char() == ',' => break,
+                token => value.push(token),
+            }
+        }
+
+        if value.is_empty() {
+            return Err((format!("expected a value for `{}`", name), name.span()));
+        }
+
+        settings.push(Setting { name, value });
+    }
+
+    Ok(settings)
+}
+
+/// Checks that the function takes no arguments and returns nothing, as loom
+/// runs it as the model closure.
+fn check_signature(item: &[TokenTree]) -> Result<(), Error> {
+    let mut tokens = item.iter();
+
+    loop {
+        match tokens.next() {
+            Some(TokenTree::Ident(ident)) if ident.to_string() == "fn" => break,
+            Some(TokenTree::Ident(ident)) if ident.to_string() == "async" => {
+                return Err((
+                    "`#[loom::test]` cannot be applied to `async` functions, use \
+                     `loom::future::block_on` in the body instead"
+                        .to_string(),
+                    ident.span(),
+                ))
+            }
+            Some(_) => {}
+            None => {
+                return Err((
+                    "`#[loom::test]` can only be applied to functions".to_string(),
+                    Span::call_site(),
+                ))
+            }
+        }
+    }
+
+    let name = tokens.next();
+
+    match tokens.next() {
+        Some(TokenTree::Group(args)) if args.delimiter() == Delimiter::Parenthesis => {
+            if !args.stream().is_empty() {
+                return Err((
+                    "`#[loom::test]` functions cannot take arguments".to_string(),
+                    args.span(),
+                ));
+            }
+        }
+        _ => {
+            let span = name.map_or_else(Span::call_site, TokenTree::span);
+            return Err((
+                "`#[loom::test]` functions cannot be generic".to_string(),
+                span,
+            ));
+        }
+    }
+
+    if let Some(token) = tokens.next() {
+        return Err((
+            "`#[loom::test]` functions cannot return a value".to_string(),
+            token.span(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Tokens of a snippet made of identifiers and simple punctuation.
+fn tokens(src: &str) -> Vec<TokenTree> {
+    src.parse::<TokenStream>()
+        .expect("invalid snippet")
+        .into_iter()
+        .collect()
+}
+
+/// `::a::b::c`
+fn path(segments: &[&str]) -> Vec<TokenTree> {
+    let mut out = vec![];
+
+    for segment in segments {
+        out.push(punct_joint(':'));
+        out.push(punct(':'));
+        out.push(ident(segment));
+    }
+
+    out
+}
+
+/// `#[attr]`
+fn attribute(attr: Vec<TokenTree>) -> Vec<TokenTree> {
+    vec![punct('#'), group(Delimiter::Bracket, attr)]
+}
+
+fn group(delimiter: Delimiter, tokens: Vec<TokenTree>) -> TokenTree {
+    TokenTree::Group(Group::new(delimiter, TokenStream::from_iter(tokens)))
+}
+
+fn ident(name: &str) -> TokenTree {
+    TokenTree::Ident(Ident::new(name, Span::call_site()))
+}
+
+fn punct(ch: char) -> TokenTree {
+    TokenTree::Punct(Punct::new(ch, Spacing::Alone))
+}
+
+fn punct_joint(ch: char) -> TokenTree {
+    TokenTree::Punct(Punct::new(ch, Spacing::Joint))
+}
+
+/// `compile_error!("msg");`, reported at `span`.
+fn compile_error(msg: &str, span: Span) -> TokenStream {
+    let mut msg = Literal::string(msg);
+    msg.set_span(span);
+
+    let mut args = Group::new(
+        Delimiter::Parenthesis,
+        TokenStream::from(TokenTree::Literal(msg)),
+    );
+    args.set_span(span);
+
+    let mut out = path(&["core", "compile_error"]);
+    out.push(punct('!'));
+    out.push(TokenTree::Group(args));
+    out.push(punct(';'));
+
+    for token in &mut out {
+        token.set_span(span);
+    }
+
+    TokenStream::from_iter(out)
+}
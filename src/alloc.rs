@@ -1,30 +1,105 @@
 //! Memory allocation APIs
+//!
+//! Allocations made through these functions are tracked for leaks. They can
+//! also be made to fail, to check the paths handling allocation failure:
+//! [`fail_next_alloc`] makes the next allocation fail, and
+//! [`Builder::alloc_failures`](crate::model::Builder::alloc_failures)
+//! explores both the success and the failure of every allocation.
 
 use crate::rt;
 
+use std::ptr;
+
 pub use std::alloc::Layout;
 
 /// Allocate memory with the global allocator.
+///
+/// Returns a null pointer when the allocation is made to fail.
+#[track_caller]
 pub unsafe fn alloc(layout: Layout) -> *mut u8 {
+    if rt::alloc_fails() {
+        return ptr::null_mut();
+    }
+
     let ptr = std::alloc::alloc(layout);
-    rt::alloc(ptr);
+
+    if !ptr.is_null() {
+        rt::alloc(ptr, layout.size(), location!());
+    }
+
     ptr
 }
 
 /// Allocate zero-initialized memory with the global allocator.
+///
+/// Returns a null pointer when the allocation is made to fail.
+#[track_caller]
 pub unsafe fn alloc_zeroed(layout: Layout) -> *mut u8 {
+    if rt::alloc_fails() {
+        return ptr::null_mut();
+    }
+
     let ptr = std::alloc::alloc_zeroed(layout);
-    rt::alloc(ptr);
+
+    if !ptr.is_null() {
+        rt::alloc(ptr, layout.size(), location!());
+    }
+
     ptr
 }
 
+/// Reallocate memory with the global allocator.
+///
+/// The allocation tracked for `ptr` moves to the returned pointer. If
+/// reallocation fails, `ptr` is still allocated and remains tracked.
+///
+/// # Safety
+///
+/// See [`std::alloc::realloc`].
+#[track_caller]
+pub unsafe fn realloc(ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+    if rt::alloc_fails() {
+        return ptr::null_mut();
+    }
+
+    let new_ptr = std::alloc::realloc(ptr, layout, new_size);
+
+    if !new_ptr.is_null() {
+        rt::realloc(ptr, new_ptr, new_size, location!());
+    }
+
+    new_ptr
+}
+
+/// Make the next call to [`alloc`], [`alloc_zeroed`] or [`realloc`] on the
+/// current thread fail, returning a null pointer.
+pub fn fail_next_alloc() {
+    rt::fail_next_alloc();
+}
+
 /// Deallocate memory with the global allocator.
+///
+/// The memory is only released once the execution completes. Until then,
+/// accessing a [`cell`](crate::cell) stored in it fails the execution as a
+/// use after free.
+#[track_caller]
 pub unsafe fn dealloc(ptr: *mut u8, layout: Layout) {
-    rt::dealloc(ptr);
-    std::alloc::dealloc(ptr, layout)
+    rt::dealloc(ptr, layout, location!());
 }
 
 /// Track allocations, detecting leaks
+///
+/// A tracked value can also check the hand-off of its ownership between
+/// threads, such as a node passed through a queue by raw pointer. Once
+/// [`publish`](Track::publish)ed, the value is owned by a single thread at a
+/// time: the publishing thread gives it up, and the thread calling
+/// [`acquire`](Track::acquire) takes it over. Accessing or dropping the value
+/// from any other thread, acquiring it twice, dropping it twice or accessing
+/// it once dropped fails the execution.
+///
+/// Store values accessed through raw pointers in memory allocated with
+/// [`alloc`]: the memory is only released once the execution completes, so
+/// accessing a dropped value is reliably detected.
 #[derive(Debug)]
 pub struct Track<T> {
     value: T,
@@ -33,25 +108,61 @@ pub struct Track<T> {
 
 impl<T> Track<T> {
     /// Track a value for leaks
+    #[track_caller]
     pub fn new(value: T) -> Track<T> {
         Track {
             value,
-            obj: rt::Allocation::new(),
+            obj: rt::Allocation::new(std::any::type_name::<T>(), location!()),
         }
     }
 
     /// Get a reference to the value
+    #[track_caller]
     pub fn get_ref(&self) -> &T {
+        self.obj.access(location!());
         &self.value
     }
 
     /// Get a mutable reference to the value
+    #[track_caller]
     pub fn get_mut(&mut self) -> &mut T {
+        self.obj.access(location!());
         &mut self.value
     }
 
     /// Stop tracking the value for leaks
+    #[track_caller]
     pub fn into_inner(self) -> T {
+        self.obj.access(location!());
         self.value
     }
+
+    /// Give up ownership of the value, for another thread to
+    /// [`acquire`](Track::acquire) it.
+    ///
+    /// This acts as a release: the thread acquiring the value observes the
+    /// writes made before publishing it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value was handed off and the current thread does not
+    /// own it.
+    #[track_caller]
+    pub fn publish(&self) {
+        self.obj.publish(location!());
+    }
+
+    /// Take ownership of the value published by another thread.
+    ///
+    /// This acts as an acquire, synchronizing with the thread that published
+    /// the value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is not published, for example because another
+    /// thread already acquired it.
+    #[track_caller]
+    pub fn acquire(&self) {
+        self.obj.acquire(location!());
+    }
 }
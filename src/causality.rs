@@ -0,0 +1,167 @@
+//! Assertions on the causality of a model.
+//!
+//! A [`Marker`] records a point in the execution of a thread. Once marked,
+//! two markers can be compared with [`happens_before`] or
+//! [`assert_happens_before!`](crate::assert_happens_before!), which check
+//! whether the first point is ordered before the second one by the
+//! synchronization of the model. As loom checks every execution, this
+//! encodes the ordering guarantees of a protocol directly in the model.
+//!
+//! # Examples
+//!
+//! ```
+//! use loom::causality::Marker;
+//! use loom::sync::atomic::AtomicBool;
+//! use loom::sync::Arc;
+//! use loom::thread;
+//!
+//! use std::sync::atomic::Ordering::{Acquire, Release};
+//!
+//! loom::model(|| {
+//!     let ready = Arc::new(AtomicBool::new(false));
+//!     let published = Arc::new(Marker::new("published"));
+//!
+//!     let th = {
+//!         let ready = ready.clone();
+//!         let published = published.clone();
+//!
+//!         thread::spawn(move || {
+//!             published.mark();
+//!             ready.store(true, Release);
+//!         })
+//!     };
+//!
+//!     if ready.load(Acquire) {
+//!         let consumed = Marker::new("consumed");
+//!         consumed.mark();
+//!
+//!         loom::assert_happens_before!(published, consumed);
+//!     }
+//!
+//!     th.join().unwrap();
+//! });
+//! ```
+
+use crate::rt::{self, thread, Location, VersionVec};
+
+use std::fmt;
+use std::sync::Mutex;
+
+/// A named point in the execution of a thread.
+pub struct Marker {
+    name: &'static str,
+    mark: Mutex<Option<Mark>>,
+}
+
+#[derive(Clone, Copy)]
+struct Mark {
+    thread: thread::Id,
+    causality: VersionVec,
+    location: Location,
+}
+
+impl Marker {
+    /// Creates a new marker, which is not marked yet.
+    pub fn new(name: &'static str) -> Marker {
+        Marker {
+            name,
+            mark: Mutex::new(None),
+        }
+    }
+
+    /// Records the current point of the current thread. Marking again moves
+    /// the marker to the new point.
+    #[track_caller]
+    pub fn mark(&self) {
+        let location = location!();
+
+        let mark = rt::execution(|execution| {
+            execution.threads.active_causality_inc();
+
+            let thread = execution.threads.active();
+            Mark {
+                thread: execution.threads.active_id(),
+                causality: thread.causality,
+                location,
+            }
+        });
+
+        *self.mark.lock().unwrap() = Some(mark);
+    }
+
+    /// Returns `true` if the marker was marked.
+    pub fn is_marked(&self) -> bool {
+        self.mark.lock().unwrap().is_some()
+    }
+
+    /// Returns the name of the marker.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn get(&self) -> Mark {
+        match *self.mark.lock().unwrap() {
+            Some(mark) => mark,
+            None => panic!("marker `{}` was not marked", self.name),
+        }
+    }
+}
+
+impl fmt::Debug for Marker {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Marker")
+            .field("name", &self.name)
+            .field("marked", &self.is_marked())
+            .finish()
+    }
+}
+
+/// Returns `true` if the point marked by `a` happens before the point marked
+/// by `b`.
+///
+/// Points marked by the same thread are ordered by program order. Points
+/// marked by different threads are ordered if the thread marking `b`
+/// synchronized with the thread marking `a` after it did.
+///
+/// # Panics
+///
+/// Panics if either marker was not marked.
+pub fn happens_before(a: &Marker, b: &Marker) -> bool {
+    let (a, b) = (a.get(), b.get());
+    a.causality[a.thread] <= b.causality[a.thread]
+}
+
+/// Asserts that the point marked by the first marker happens before the point
+/// marked by the second one. See [`happens_before`].
+#[macro_export]
+macro_rules! assert_happens_before {
+    ($a:expr, $b:expr $(,)?) => {
+        $crate::causality::__assert_happens_before(&$a, &$b)
+    };
+}
+
+#[doc(hidden)]
+pub fn __assert_happens_before(a: &Marker, b: &Marker) {
+    if happens_before(a, b) {
+        return;
+    }
+
+    let describe = |marker: &Marker| {
+        let mark = marker.get();
+
+        if mark.location.is_captured() {
+            format!(
+                "`{}` (thread #{} @ {})",
+                marker.name, mark.thread, mark.location
+            )
+        } else {
+            format!("`{}` (thread #{})", marker.name, mark.thread)
+        }
+    };
+
+    rt::fail(format!(
+        "{} does not happen before {}",
+        describe(a),
+        describe(b)
+    ));
+}
@@ -0,0 +1,119 @@
+use crate::rt;
+
+use std::fmt;
+
+/// Mock implementation of `std::cell::Cell`.
+///
+/// Like `std`, a `Cell` is not `Sync`. Sharing one between threads requires
+/// an `unsafe impl Sync` on a containing type; every access is then checked
+/// against accesses from other threads, and an unsynchronized pair fails the
+/// execution.
+pub struct Cell<T> {
+    /// Causality associated with the cell
+    state: rt::Cell,
+    data: std::cell::UnsafeCell<T>,
+}
+
+impl<T> Cell<T> {
+    /// Creates a new `Cell` containing the given value.
+    #[track_caller]
+    pub fn new(value: T) -> Cell<T> {
+        Cell {
+            state: rt::Cell::new("Cell", location!()),
+            data: std::cell::UnsafeCell::new(value),
+        }
+    }
+
+    /// Sets the contained value.
+    #[track_caller]
+    pub fn set(&self, value: T) {
+        drop(self.replace(value));
+    }
+
+    /// Swaps the values of two cells.
+    #[track_caller]
+    pub fn swap(&self, other: &Cell<T>) {
+        if std::ptr::eq(self, other) {
+            return;
+        }
+
+        let location = location!();
+        self.state.with_mut(self.data(), location, || {
+            other.state.with_mut(other.data(), location, || unsafe {
+                std::ptr::swap(self.data.get(), other.data.get())
+            })
+        })
+    }
+
+    /// Replaces the contained value with `value`, and returns the old
+    /// contained value.
+    #[track_caller]
+    pub fn replace(&self, value: T) -> T {
+        self.state.with_mut(self.data(), location!(), || unsafe {
+            std::mem::replace(&mut *self.data.get(), value)
+        })
+    }
+
+    /// Unwraps the value.
+    pub fn into_inner(self) -> T {
+        self.data.into_inner()
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// This call borrows `Cell` mutably, which guarantees that we possess the
+    /// only reference.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.data.get_mut()
+    }
+
+    fn data(&self) -> *const () {
+        self.data.get() as *const ()
+    }
+}
+
+impl<T: Copy> Cell<T> {
+    /// Returns a copy of the contained value.
+    #[track_caller]
+    pub fn get(&self) -> T {
+        self.state
+            .with(self.data(), location!(), || unsafe { *self.data.get() })
+    }
+}
+
+impl<T: Default> Cell<T> {
+    /// Takes the value of the cell, leaving `Default::default()` in its place.
+    #[track_caller]
+    pub fn take(&self) -> T {
+        self.replace(Default::default())
+    }
+}
+
+impl<T: Copy> Clone for Cell<T> {
+    #[track_caller]
+    fn clone(&self) -> Cell<T> {
+        Cell::new(self.get())
+    }
+}
+
+impl<T: Default> Default for Cell<T> {
+    #[track_caller]
+    fn default() -> Cell<T> {
+        Cell::new(Default::default())
+    }
+}
+
+impl<T> From<T> for Cell<T> {
+    #[track_caller]
+    fn from(value: T) -> Cell<T> {
+        Cell::new(value)
+    }
+}
+
+impl<T: Copy + fmt::Debug> fmt::Debug for Cell<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Cell")
+            .field("value", &self.get())
+            .finish()
+    }
+}
@@ -1,5 +1,13 @@
 //! Shareable mutable containers.
 
-mod unsafe_cell;
+#[allow(clippy::module_inception)]
+mod cell;
+pub use self::cell::Cell;
+
+mod ref_cell;
+pub use self::ref_cell::{Ref, RefCell, RefMut};
 
+mod unsafe_cell;
 pub use self::unsafe_cell::UnsafeCell;
+
+pub use std::cell::{BorrowError, BorrowMutError};
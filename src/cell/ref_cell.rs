@@ -0,0 +1,296 @@
+use crate::rt;
+
+use std::cell::{BorrowError, BorrowMutError};
+use std::fmt;
+use std::ops;
+
+/// Mock implementation of `std::cell::RefCell`.
+///
+/// Borrows are tracked by a `std` `RefCell`, a borrow conflicting with one
+/// held by the same thread fails like with `std`. Like `std`, a `RefCell` is
+/// not `Sync`. Sharing one between threads requires an `unsafe impl Sync` on
+/// a containing type; each borrow is then an access checked against the
+/// accesses of other threads, and a borrow conflicting with a borrow held by
+/// another thread is reported as a concurrent access.
+pub struct RefCell<T: ?Sized> {
+    /// Causality associated with the cell
+    state: rt::Cell,
+    data: std::cell::RefCell<T>,
+}
+
+/// Wraps a borrowed reference to a value in a `RefCell` box.
+pub struct Ref<'b, T: ?Sized> {
+    value: std::cell::Ref<'b, T>,
+    state: &'b rt::Cell,
+    /// Address of the borrowed `RefCell` data, kept as an integer so that
+    /// `Ref` stays `Sync`
+    data: usize,
+    _access: rt::cell::Read,
+}
+
+/// A wrapper type for a mutably borrowed value from a `RefCell<T>`.
+pub struct RefMut<'b, T: ?Sized> {
+    value: std::cell::RefMut<'b, T>,
+    _access: rt::cell::Write,
+}
+
+impl<T> RefCell<T> {
+    /// Creates a new `RefCell` containing `value`.
+    #[track_caller]
+    pub fn new(value: T) -> RefCell<T> {
+        RefCell {
+            state: rt::Cell::new("RefCell", location!()),
+            data: std::cell::RefCell::new(value),
+        }
+    }
+
+    /// Consumes the `RefCell`, returning the wrapped value.
+    pub fn into_inner(self) -> T {
+        self.data.into_inner()
+    }
+
+    /// Replaces the wrapped value with a new one, returning the old value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently borrowed.
+    #[track_caller]
+    pub fn replace(&self, value: T) -> T {
+        std::mem::replace(&mut *self.borrow_mut(), value)
+    }
+
+    /// Swaps the wrapped value of `self` with the wrapped value of `other`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value in either `RefCell` is currently borrowed.
+    #[track_caller]
+    pub fn swap(&self, other: &RefCell<T>) {
+        std::mem::swap(&mut *self.borrow_mut(), &mut *other.borrow_mut())
+    }
+}
+
+impl<T: Default> RefCell<T> {
+    /// Takes the wrapped value, leaving `Default::default()` in its place.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently borrowed.
+    #[track_caller]
+    pub fn take(&self) -> T {
+        self.replace(Default::default())
+    }
+}
+
+impl<T: ?Sized> RefCell<T> {
+    /// Immutably borrows the wrapped value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently mutably borrowed by the current
+    /// thread, or if the borrow is a concurrent access.
+    #[track_caller]
+    pub fn borrow(&self) -> Ref<'_, T> {
+        match self.try_borrow() {
+            Ok(value) => value,
+            Err(_) => panic!("already mutably borrowed"),
+        }
+    }
+
+    /// Immutably borrows the wrapped value, returning an error if the value is
+    /// currently mutably borrowed by the current thread.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the borrow is a concurrent access.
+    #[track_caller]
+    pub fn try_borrow(&self) -> Result<Ref<'_, T>, BorrowError> {
+        let location = location!();
+
+        match self.data.try_borrow() {
+            Ok(value) => Ok(Ref {
+                value,
+                state: &self.state,
+                data: self.data() as usize,
+                _access: self.state.read(self.data(), location),
+            }),
+            Err(e) => {
+                self.state.check_borrow_conflict(location, false);
+                Err(e)
+            }
+        }
+    }
+
+    /// Mutably borrows the wrapped value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is currently borrowed by the current thread, or if
+    /// the borrow is a concurrent access.
+    #[track_caller]
+    pub fn borrow_mut(&self) -> RefMut<'_, T> {
+        match self.try_borrow_mut() {
+            Ok(value) => value,
+            Err(_) => panic!("already borrowed"),
+        }
+    }
+
+    /// Mutably borrows the wrapped value, returning an error if the value is
+    /// currently borrowed by the current thread.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the borrow is a concurrent access.
+    #[track_caller]
+    pub fn try_borrow_mut(&self) -> Result<RefMut<'_, T>, BorrowMutError> {
+        let location = location!();
+
+        match self.data.try_borrow_mut() {
+            Ok(value) => Ok(RefMut {
+                value,
+                _access: self.state.write(self.data(), location),
+            }),
+            Err(e) => {
+                self.state.check_borrow_conflict(location, true);
+                Err(e)
+            }
+        }
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    ///
+    /// This call borrows `RefCell` mutably, which guarantees that we possess
+    /// the only reference.
+    pub fn get_mut(&mut self) -> &mut T {
+        self.data.get_mut()
+    }
+
+    fn data(&self) -> *const () {
+        &self.data as *const std::cell::RefCell<T> as *const ()
+    }
+}
+
+impl<T: Clone> Clone for RefCell<T> {
+    #[track_caller]
+    fn clone(&self) -> RefCell<T> {
+        RefCell::new(self.borrow().clone())
+    }
+}
+
+impl<T: Default> Default for RefCell<T> {
+    #[track_caller]
+    fn default() -> RefCell<T> {
+        RefCell::new(Default::default())
+    }
+}
+
+impl<T> From<T> for RefCell<T> {
+    #[track_caller]
+    fn from(value: T) -> RefCell<T> {
+        RefCell::new(value)
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for RefCell<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.try_borrow() {
+            Ok(value) => fmt
+                .debug_struct("RefCell")
+                .field("value", &&*value)
+                .finish(),
+            Err(_) => fmt
+                .debug_struct("RefCell")
+                .field("value", &format_args!("<borrowed>"))
+                .finish(),
+        }
+    }
+}
+
+impl<'b, T: ?Sized> Ref<'b, T> {
+    /// Copies a `Ref`.
+    ///
+    /// This is an associated function that needs to be used as
+    /// `Ref::clone(...)`, like with `std`.
+    #[track_caller]
+    #[allow(clippy::should_implement_trait)]
+    pub fn clone(orig: &Ref<'b, T>) -> Ref<'b, T> {
+        Ref {
+            value: std::cell::Ref::clone(&orig.value),
+            state: orig.state,
+            data: orig.data,
+            _access: orig.state.read(orig.data as *const (), location!()),
+        }
+    }
+
+    /// Makes a new `Ref` for a component of the borrowed data.
+    pub fn map<U: ?Sized, F>(orig: Ref<'b, T>, f: F) -> Ref<'b, U>
+    where
+        F: FnOnce(&T) -> &U,
+    {
+        Ref {
+            value: std::cell::Ref::map(orig.value, f),
+            state: orig.state,
+            data: orig.data,
+            _access: orig._access,
+        }
+    }
+}
+
+impl<T: ?Sized> ops::Deref for Ref<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for Ref<'_, T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(fmt)
+    }
+}
+
+impl<T: ?Sized + fmt::Display> fmt::Display for Ref<'_, T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(fmt)
+    }
+}
+
+impl<'b, T: ?Sized> RefMut<'b, T> {
+    /// Makes a new `RefMut` for a component of the borrowed data.
+    pub fn map<U: ?Sized, F>(orig: RefMut<'b, T>, f: F) -> RefMut<'b, U>
+    where
+        F: FnOnce(&mut T) -> &mut U,
+    {
+        RefMut {
+            value: std::cell::RefMut::map(orig.value, f),
+            _access: orig._access,
+        }
+    }
+}
+
+impl<T: ?Sized> ops::Deref for RefMut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T: ?Sized> ops::DerefMut for RefMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for RefMut<'_, T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(fmt)
+    }
+}
+
+impl<T: ?Sized + fmt::Display> fmt::Display for RefMut<'_, T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(fmt)
+    }
+}
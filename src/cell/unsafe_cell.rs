@@ -16,7 +16,7 @@ impl<T> UnsafeCell<T> {
     /// Constructs a new instance of `UnsafeCell` which will wrap the specified value.
     #[track_caller]
     pub fn new(data: T) -> UnsafeCell<T> {
-        let state = rt::Cell::new(location!());
+        let state = rt::Cell::new("UnsafeCell", location!());
 
         UnsafeCell {
             state,
@@ -35,8 +35,9 @@ impl<T> UnsafeCell<T> {
     where
         F: FnOnce(*const T) -> R,
     {
+        let data = self.data.get();
         self.state
-            .with(location!(), || f(self.data.get() as *const T))
+            .with(data as *const (), location!(), || f(data as *const T))
     }
 
     /// Get a mutable pointer to the wrapped value.
@@ -50,7 +51,9 @@ impl<T> UnsafeCell<T> {
     where
         F: FnOnce(*mut T) -> R,
     {
-        self.state.with_mut(location!(), || f(self.data.get()))
+        let data = self.data.get();
+        self.state
+            .with_mut(data as *const (), location!(), || f(data))
     }
 }
 
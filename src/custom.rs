@@ -0,0 +1,145 @@
+//! Register custom synchronization primitives with loom.
+//!
+//! Primitives built from loom's mocks are checked through the operations of
+//! those mocks. A primitive modeled directly, on top of non-loom state, can
+//! instead register an [`Object`] and tell loom before each of its
+//! operations. Loom then treats these operations like the ones of its own
+//! primitives: each one is a point where threads may be switched, and
+//! operations that do not commute, as told by [`Action::is_dependent`], are
+//! explored in every order.
+//!
+//! The object also transfers causality between threads, with
+//! [`Object::release`] and [`Object::acquire`].
+//!
+//! This API is unstable and requires the `custom` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use loom::custom::{Action, Object};
+//! use loom::thread;
+//!
+//! use std::sync::{Arc, Mutex};
+//!
+//! #[derive(Debug, Clone, Copy, PartialEq)]
+//! enum Op {
+//!     Push,
+//!     Len,
+//! }
+//!
+//! impl Action for Op {
+//!     const ALL: &'static [Op] = &[Op::Push, Op::Len];
+//!
+//!     fn is_dependent(self, other: Op) -> bool {
+//!         // Reading the length commutes with itself
+//!         self == Op::Push || other == Op::Push
+//!     }
+//! }
+//!
+//! struct Stack {
+//!     object: Object<Op>,
+//!     items: Mutex<Vec<usize>>,
+//! }
+//!
+//! impl Stack {
+//!     fn push(&self, item: usize) {
+//!         self.object.branch(Op::Push);
+//!         self.items.lock().unwrap().push(item);
+//!     }
+//!
+//!     fn len(&self) -> usize {
+//!         self.object.branch(Op::Len);
+//!         self.items.lock().unwrap().len()
+//!     }
+//! }
+//!
+//! loom::model(|| {
+//!     let stack = Arc::new(Stack {
+//!         object: Object::new("Stack"),
+//!         items: Mutex::new(vec![]),
+//!     });
+//!
+//!     let th = {
+//!         let stack = stack.clone();
+//!         thread::spawn(move || stack.push(1))
+//!     };
+//!
+//!     let len = stack.len();
+//!     assert!(len <= 1);
+//!
+//!     th.join().unwrap();
+//! });
+//! ```
+
+use crate::rt;
+
+use std::fmt;
+use std::marker::PhantomData;
+
+/// Operations of a custom object.
+pub trait Action: Copy + PartialEq + fmt::Debug + 'static {
+    /// All the operations of the object.
+    const ALL: &'static [Self];
+
+    /// Returns `true` if performing the two operations in a different order
+    /// may lead to a different outcome.
+    ///
+    /// Loom only explores both orders of dependent operations. Returning
+    /// `true` is always correct, but explores more executions.
+    fn is_dependent(self, other: Self) -> bool;
+}
+
+/// A custom object, the operations of which loom explores.
+pub struct Object<A> {
+    object: rt::Custom,
+    _p: PhantomData<fn(A)>,
+}
+
+impl<A: Action> Object<A> {
+    /// Registers a new object. `name` is used in diagnostics.
+    #[track_caller]
+    pub fn new(name: &'static str) -> Object<A> {
+        Object {
+            object: rt::Custom::new(name, A::ALL.len(), dependent::<A>, location!()),
+            _p: PhantomData,
+        }
+    }
+
+    /// Tells loom the current thread is about to perform `action`.
+    ///
+    /// Loom may switch to another thread before the operation is performed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `action` is not part of [`Action::ALL`].
+    pub fn branch(&self, action: A) {
+        let index = A::ALL
+            .iter()
+            .position(|a| *a == action)
+            .unwrap_or_else(|| panic!("{:?} is not part of `Action::ALL`", action));
+
+        self.object.branch(index);
+    }
+
+    /// Publishes the causality of the current thread to the threads later
+    /// calling [`acquire`](Object::acquire), as a release store.
+    pub fn release(&self) {
+        self.object.release();
+    }
+
+    /// Acquires the causality published by the threads which called
+    /// [`release`](Object::release) before, as an acquire load.
+    pub fn acquire(&self) {
+        self.object.acquire();
+    }
+}
+
+impl<A> fmt::Debug for Object<A> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Object").finish()
+    }
+}
+
+fn dependent<A: Action>(a: usize, b: usize) -> bool {
+    A::ALL[a].is_dependent(A::ALL[b])
+}
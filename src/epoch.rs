@@ -0,0 +1,639 @@
+//! Mock implementation of `crossbeam_epoch`.
+//!
+//! An object passed to [`Guard::defer_destroy`] is destroyed as soon as every
+//! thread that was pinned when it was deferred has unpinned. This is the
+//! earliest point at which `crossbeam_epoch` may destroy it, so premature
+//! reclamation shows up in the model:
+//!
+//! - Dereferencing a [`Shared`] pointer to a destroyed object panics.
+//! - Destroying an object is a write access to it, so an access that is not
+//!   ordered with the destruction is reported like any other `UnsafeCell`
+//!   race.
+//! - Objects that are never destroyed are reported as leaks.
+//!
+//! Only the most common parts of the `crossbeam_epoch` API are provided.
+//! Pointer tags are not supported.
+
+use crate::cell::UnsafeCell;
+use crate::lazy_static::Lazy;
+use crate::rt::{self, Location};
+use crate::sync::atomic::AtomicUsize;
+use crate::sync::Mutex;
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem::{self, ManuallyDrop};
+use std::ops;
+use std::sync::atomic::Ordering;
+
+/// Tracks pinned threads and deferred garbage for an execution.
+static COLLECTOR: Lazy<Collector> = Lazy {
+    init: Collector::new,
+    _p: PhantomData,
+};
+
+struct Collector {
+    state: Mutex<State>,
+
+    /// Destroyed objects. Their memory is kept until the end of the execution
+    /// so that accessing them is detected.
+    destroyed: std::sync::Mutex<Vec<Garbage>>,
+}
+
+#[derive(Default)]
+struct State {
+    /// Pin state of each thread, by thread index.
+    locals: Vec<Local>,
+
+    /// Deferred functions, waiting for the threads pinned at the time to
+    /// unpin.
+    garbage: Vec<(Vec<(usize, usize)>, Garbage)>,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Local {
+    /// Number of live guards.
+    guards: usize,
+
+    /// Number of times the thread was pinned.
+    pins: usize,
+}
+
+/// A type-erased function run on an object.
+struct Garbage {
+    data: *mut u8,
+    location: Location,
+    call: unsafe fn(*mut u8, Location),
+}
+
+// Garbage is only ever handled by the threads of an execution, which loom runs
+// one at a time.
+unsafe impl Send for Garbage {}
+
+/// A guard that keeps the current thread pinned.
+///
+/// Objects deferred while a thread is pinned are not destroyed until the
+/// thread unpins.
+pub struct Guard {
+    thread: usize,
+    _p: PhantomData<*mut ()>,
+}
+
+/// An atomic pointer that can be safely shared between threads.
+pub struct Atomic<T> {
+    data: AtomicUsize,
+    _p: PhantomData<*mut T>,
+}
+
+/// An owned heap-allocated object.
+pub struct Owned<T> {
+    node: *mut Node<T>,
+}
+
+/// A pointer to an object protected by the epoch GC.
+///
+/// The pointer is valid for use only during the lifetime `'g`.
+pub struct Shared<'g, T> {
+    node: *mut Node<T>,
+    _p: PhantomData<(&'g (), *const T)>,
+}
+
+/// The error returned on failed compare-and-swap operation.
+pub struct CompareExchangeError<'g, T, P: Pointer<T>> {
+    /// The value in the atomic pointer at the time of the failed operation.
+    pub current: Shared<'g, T>,
+
+    /// The new value, which the operation failed to store.
+    pub new: P,
+}
+
+/// Types that are pointed to by a single word.
+pub trait Pointer<T> {
+    /// Returns the machine representation of the pointer.
+    fn into_usize(self) -> usize;
+
+    /// Returns a new pointer pointing to the tagged pointer `data`.
+    ///
+    /// # Safety
+    ///
+    /// The given `data` should have been created by `Pointer::into_usize()`,
+    /// and one `data` should not be converted back by `Pointer::from_usize()`
+    /// multiple times.
+    unsafe fn from_usize(data: usize) -> Self;
+}
+
+/// The heap allocation backing `Owned` and `Shared`.
+struct Node<T> {
+    value: UnsafeCell<ManuallyDrop<T>>,
+
+    /// Tracks the object for leaks, `None` once destroyed.
+    allocation: std::sync::Mutex<Option<rt::Allocation>>,
+
+    /// Where the object was deferred for destruction, once destroyed.
+    destroyed: std::sync::Mutex<Option<Location>>,
+}
+
+/// Pins the current thread.
+pub fn pin() -> Guard {
+    let thread = current_thread();
+    let mut state = COLLECTOR.get().state.lock().unwrap();
+
+    let local = state.local(thread);
+
+    if local.guards == 0 {
+        local.pins += 1;
+    }
+
+    local.guards += 1;
+
+    Guard {
+        thread,
+        _p: PhantomData,
+    }
+}
+
+/// Returns `true` if the current thread is pinned.
+pub fn is_pinned() -> bool {
+    let thread = current_thread();
+    let mut state = COLLECTOR.get().state.lock().unwrap();
+    state.local(thread).guards > 0
+}
+
+fn current_thread() -> usize {
+    rt::execution(|execution| execution.threads.active_id().as_usize())
+}
+
+impl Collector {
+    fn new() -> Collector {
+        Collector {
+            state: Mutex::new(State::default()),
+            destroyed: std::sync::Mutex::new(vec![]),
+        }
+    }
+
+    fn defer(&self, garbage: Garbage) {
+        let mut state = self.state.lock().unwrap();
+
+        let pinned = state
+            .locals
+            .iter()
+            .enumerate()
+            .filter(|(_, local)| local.guards > 0)
+            .map(|(thread, local)| (thread, local.pins))
+            .collect();
+
+        state.garbage.push((pinned, garbage));
+    }
+
+    /// Unpins `thread`, running the garbage no pinned thread can still access.
+    fn unpin(&self, thread: usize) {
+        let mut state = self.state.lock().unwrap();
+
+        let local = state.local(thread);
+        local.guards -= 1;
+
+        if local.guards > 0 {
+            return;
+        }
+
+        let locals = mem::take(&mut state.locals);
+
+        let (ready, garbage) = mem::take(&mut state.garbage)
+            .into_iter()
+            .partition::<Vec<_>, _>(|(pinned, _)| {
+                pinned.iter().all(|&(thread, pins)| {
+                    let local = locals[thread];
+                    local.guards == 0 || local.pins != pins
+                })
+            });
+
+        state.locals = locals;
+        state.garbage = garbage;
+
+        // Run the garbage without holding the lock, it may pin again.
+        drop(state);
+
+        for (_, garbage) in ready {
+            unsafe { (garbage.call)(garbage.data, garbage.location) };
+        }
+    }
+}
+
+impl Drop for Collector {
+    fn drop(&mut self) {
+        // Garbage still deferred is leaked, as `crossbeam_epoch` does with the
+        // garbage of the global collector. Only free the destroyed objects.
+        for garbage in self.destroyed.get_mut().unwrap().drain(..) {
+            unsafe { (garbage.call)(garbage.data, garbage.location) };
+        }
+    }
+}
+
+impl State {
+    fn local(&mut self, thread: usize) -> &mut Local {
+        if self.locals.len() <= thread {
+            self.locals.resize(thread + 1, Local::default());
+        }
+
+        &mut self.locals[thread]
+    }
+}
+
+impl Guard {
+    /// Stores a function so that it can be executed at some point after all
+    /// currently pinned threads get unpinned.
+    pub fn defer<F, R>(&self, f: F)
+    where
+        F: FnOnce() -> R + Send + 'static,
+    {
+        unsafe fn call<F: FnOnce() -> R, R>(data: *mut u8, _: Location) {
+            let f = Box::from_raw(data as *mut F);
+            f();
+        }
+
+        COLLECTOR.get().defer(Garbage {
+            data: Box::into_raw(Box::new(f)) as *mut u8,
+            location: Location::disabled(),
+            call: call::<F, R>,
+        });
+    }
+
+    /// Stores a destructor for an object so that it can be deallocated and
+    /// dropped at some point after all currently pinned threads get unpinned.
+    ///
+    /// # Safety
+    ///
+    /// The object must not be reachable by other threads anymore, otherwise
+    /// it might be still in use when the destructor runs.
+    #[track_caller]
+    pub unsafe fn defer_destroy<T>(&self, ptr: Shared<'_, T>) {
+        unsafe fn call<T>(data: *mut u8, location: Location) {
+            Node::destroy(data as *mut Node<T>, location);
+        }
+
+        assert!(!ptr.is_null(), "cannot destroy a null pointer");
+
+        COLLECTOR.get().defer(Garbage {
+            data: ptr.node as *mut u8,
+            location: location!(),
+            call: call::<T>,
+        });
+    }
+
+    /// Clears up the thread-local cache of deferred functions.
+    ///
+    /// Deferred functions are never cached, this does nothing.
+    pub fn flush(&self) {}
+
+    /// Unpins and then immediately re-pins the thread.
+    pub fn repin(&mut self) {
+        COLLECTOR.get().unpin(self.thread);
+        mem::forget(mem::replace(self, pin()));
+    }
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        COLLECTOR.get().unpin(self.thread);
+    }
+}
+
+impl fmt::Debug for Guard {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.pad("Guard { .. }")
+    }
+}
+
+impl<T> Node<T> {
+    #[track_caller]
+    fn new(value: T) -> *mut Node<T> {
+        Box::into_raw(Box::new(Node {
+            value: UnsafeCell::new(ManuallyDrop::new(value)),
+            allocation: std::sync::Mutex::new(Some(rt::Allocation::new(
+                std::any::type_name::<T>(),
+                location!(),
+            ))),
+            destroyed: std::sync::Mutex::new(None),
+        }))
+    }
+
+    /// Fails the execution if the object was destroyed.
+    #[track_caller]
+    fn check(&self) {
+        if let Some(location) = *self.destroyed.lock().unwrap() {
+            if location.is_captured() {
+                rt::fail(format!(
+                    "use after free; object of type `{}` was destroyed, deferred at {}",
+                    std::any::type_name::<T>(),
+                    location
+                ));
+            } else {
+                rt::fail(format!(
+                    "use after free; object of type `{}` was destroyed",
+                    std::any::type_name::<T>()
+                ));
+            }
+        }
+    }
+
+    /// Drops the object, keeping its memory until the end of the execution.
+    unsafe fn destroy(node: *mut Node<T>, location: Location) {
+        unsafe fn free<T>(data: *mut u8, _: Location) {
+            drop(Box::from_raw(data as *mut Node<T>));
+        }
+
+        let this = &*node;
+
+        this.value.with_mut(|value| ManuallyDrop::drop(&mut *value));
+        *this.destroyed.lock().unwrap() = Some(location);
+        drop(this.allocation.lock().unwrap().take());
+
+        COLLECTOR.get().destroyed.lock().unwrap().push(Garbage {
+            data: node as *mut u8,
+            location,
+            call: free::<T>,
+        });
+    }
+
+    /// Drops a node that was never shared.
+    unsafe fn drop_owned(node: *mut Node<T>) {
+        let mut node = Box::from_raw(node);
+        node.value.with_mut(|value| ManuallyDrop::drop(&mut *value));
+        drop(node.allocation.get_mut().unwrap().take());
+    }
+}
+
+impl<T> Atomic<T> {
+    /// Allocates `value` on the heap and returns a new atomic pointer pointing
+    /// to it.
+    #[track_caller]
+    pub fn new(value: T) -> Atomic<T> {
+        Atomic::from(Owned::new(value))
+    }
+
+    /// Returns a new null atomic pointer.
+    #[track_caller]
+    pub fn null() -> Atomic<T> {
+        Atomic {
+            data: AtomicUsize::new(0),
+            _p: PhantomData,
+        }
+    }
+
+    /// Loads a `Shared` from the atomic pointer.
+    #[track_caller]
+    pub fn load<'g>(&self, ord: Ordering, _: &'g Guard) -> Shared<'g, T> {
+        unsafe { Shared::from_usize(self.data.load(ord)) }
+    }
+
+    /// Stores a `Shared` or `Owned` pointer into the atomic pointer.
+    #[track_caller]
+    pub fn store<P: Pointer<T>>(&self, new: P, ord: Ordering) {
+        self.data.store(new.into_usize(), ord);
+    }
+
+    /// Stores a `Shared` or `Owned` pointer into the atomic pointer, returning
+    /// the previous `Shared`.
+    #[track_caller]
+    pub fn swap<'g, P: Pointer<T>>(&self, new: P, ord: Ordering, _: &'g Guard) -> Shared<'g, T> {
+        unsafe { Shared::from_usize(self.data.swap(new.into_usize(), ord)) }
+    }
+
+    /// Stores the pointer `new` into the atomic pointer if the current value
+    /// is the same as `current`.
+    ///
+    /// The return value is a result indicating whether the new pointer was
+    /// written. On success the pointer that was written is returned. On
+    /// failure the actual current value and `new` are returned.
+    #[track_caller]
+    pub fn compare_exchange<'g, P: Pointer<T>>(
+        &self,
+        current: Shared<'_, T>,
+        new: P,
+        success: Ordering,
+        failure: Ordering,
+        _: &'g Guard,
+    ) -> Result<Shared<'g, T>, CompareExchangeError<'g, T, P>> {
+        let new = new.into_usize();
+
+        match self
+            .data
+            .compare_exchange(current.into_usize(), new, success, failure)
+        {
+            Ok(_) => Ok(unsafe { Shared::from_usize(new) }),
+            Err(current) => Err(CompareExchangeError {
+                current: unsafe { Shared::from_usize(current) },
+                new: unsafe { P::from_usize(new) },
+            }),
+        }
+    }
+
+    /// Takes ownership of the pointee.
+    ///
+    /// # Safety
+    ///
+    /// This method may be called only if the pointer is valid and nobody else
+    /// is holding a reference to the same object.
+    #[track_caller]
+    pub unsafe fn into_owned(mut self) -> Owned<T> {
+        Owned::from_usize(self.data.with_mut(|data| *data))
+    }
+}
+
+impl<T> Default for Atomic<T> {
+    #[track_caller]
+    fn default() -> Atomic<T> {
+        Atomic::null()
+    }
+}
+
+impl<T> From<Owned<T>> for Atomic<T> {
+    #[track_caller]
+    fn from(owned: Owned<T>) -> Atomic<T> {
+        Atomic {
+            data: AtomicUsize::new(owned.into_usize()),
+            _p: PhantomData,
+        }
+    }
+}
+
+impl<T> fmt::Debug for Atomic<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.pad("Atomic { .. }")
+    }
+}
+
+unsafe impl<T: Send + Sync> Send for Atomic<T> {}
+unsafe impl<T: Send + Sync> Sync for Atomic<T> {}
+
+impl<T> Owned<T> {
+    /// Allocates `value` on the heap and returns a new owned pointer pointing
+    /// to it.
+    #[track_caller]
+    pub fn new(value: T) -> Owned<T> {
+        Owned {
+            node: Node::new(value),
+        }
+    }
+
+    /// Converts the owned pointer into a `Shared`.
+    pub fn into_shared(self, _: &Guard) -> Shared<'_, T> {
+        unsafe { Shared::from_usize(self.into_usize()) }
+    }
+
+    /// Converts the owned pointer into a `Box`.
+    pub fn into_box(self) -> Box<T> {
+        let mut node = unsafe { Box::from_raw(self.node) };
+        mem::forget(self);
+
+        drop(node.allocation.get_mut().unwrap().take());
+
+        let value = node
+            .value
+            .with_mut(|value| unsafe { ManuallyDrop::take(&mut *value) });
+
+        Box::new(value)
+    }
+}
+
+impl<T> ops::Deref for Owned<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { (*self.node).value.with(|value| &**value) }
+    }
+}
+
+impl<T> ops::DerefMut for Owned<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { (*self.node).value.with_mut(|value| &mut **value) }
+    }
+}
+
+impl<T> Drop for Owned<T> {
+    fn drop(&mut self) {
+        unsafe { Node::drop_owned(self.node) };
+    }
+}
+
+impl<T> Pointer<T> for Owned<T> {
+    fn into_usize(self) -> usize {
+        let data = self.node as usize;
+        mem::forget(self);
+        data
+    }
+
+    unsafe fn from_usize(data: usize) -> Owned<T> {
+        assert!(data != 0, "converting null into `Owned`");
+
+        Owned {
+            node: data as *mut Node<T>,
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Owned<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_tuple("Owned").field(&**self).finish()
+    }
+}
+
+impl<'g, T> Shared<'g, T> {
+    /// Returns a new null pointer.
+    pub fn null() -> Shared<'g, T> {
+        Shared {
+            node: std::ptr::null_mut(),
+            _p: PhantomData,
+        }
+    }
+
+    /// Returns `true` if the pointer is null.
+    pub fn is_null(&self) -> bool {
+        self.node.is_null()
+    }
+
+    /// Dereferences the pointer.
+    ///
+    /// # Safety
+    ///
+    /// The pointer must not be null, and the object must not have been
+    /// destroyed. Accessing a destroyed object panics.
+    #[track_caller]
+    pub unsafe fn deref(&self) -> &'g T {
+        let node = &*self.node;
+
+        node.check();
+        node.value.with(|value| &**value)
+    }
+
+    /// Converts the pointer to a reference, returning `None` if it is null.
+    ///
+    /// # Safety
+    ///
+    /// The object must not have been destroyed. Accessing a destroyed object
+    /// panics.
+    #[track_caller]
+    pub unsafe fn as_ref(&self) -> Option<&'g T> {
+        if self.is_null() {
+            None
+        } else {
+            Some(self.deref())
+        }
+    }
+
+    /// Takes ownership of the pointee.
+    ///
+    /// # Safety
+    ///
+    /// This method may be called only if the pointer is valid and nobody else
+    /// is holding a reference to the same object.
+    #[track_caller]
+    pub unsafe fn into_owned(self) -> Owned<T> {
+        (*self.node).check();
+        Owned::from_usize(self.into_usize())
+    }
+}
+
+impl<T> Clone for Shared<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Shared<'_, T> {}
+
+impl<T> PartialEq for Shared<'_, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.node == other.node
+    }
+}
+
+impl<T> Eq for Shared<'_, T> {}
+
+impl<T> Pointer<T> for Shared<'_, T> {
+    fn into_usize(self) -> usize {
+        self.node as usize
+    }
+
+    unsafe fn from_usize(data: usize) -> Self {
+        Shared {
+            node: data as *mut Node<T>,
+            _p: PhantomData,
+        }
+    }
+}
+
+impl<T> fmt::Debug for Shared<'_, T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_tuple("Shared").field(&self.node).finish()
+    }
+}
+
+impl<T, P: Pointer<T> + fmt::Debug> fmt::Debug for CompareExchangeError<'_, T, P> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("CompareExchangeError")
+            .field("current", &self.current)
+            .field("new", &self.new)
+            .finish()
+    }
+}
@@ -0,0 +1,163 @@
+//! Building blocks for modeling schedulers, such as async executors.
+//!
+//! An executor decides which task runs next and when a task gives control
+//! back, decisions a model should explore rather than fix. Driving them with
+//! atomics makes loom explore the atomics' interleavings instead, and hides
+//! the decision behind unrelated branch points. The functions of this module
+//! are branch points of their own:
+//!
+//! * [`one_of`] and [`take`] pick among ready tasks, exploring every pick,
+//!   like [`loom::choose`](crate::choose).
+//! * A [`Budget`] bounds the number of polls a task may run before it yields,
+//!   and may be explored over every budget up to a maximum.
+//! * A [`PollBoundary`] marks where a worker starts or ends a poll. Loom may
+//!   switch threads there, and explores the order in which the workers cross
+//!   their boundaries.
+//!
+//! Each branch point multiplies the executions to explore: pick among the
+//! ready tasks only where the order matters to the property checked.
+//!
+//! # Examples
+//!
+//! A worker polls tasks in an order it picks, each task running until it
+//! completes or its budget runs out.
+//!
+//! ```
+//! use loom::explore::choice::{self, Budget, PollBoundary};
+//!
+//! struct Task {
+//!     id: usize,
+//!     polls_left: usize,
+//! }
+//!
+//! loom::model(|| {
+//!     let boundary = PollBoundary::new();
+//!     let mut ready = vec![Task { id: 0, polls_left: 2 }, Task { id: 1, polls_left: 1 }];
+//!     let mut completed = vec![];
+//!
+//!     while let Some(mut task) = choice::take(&mut ready) {
+//!         let mut budget = Budget::new(1);
+//!
+//!         while task.polls_left > 0 && budget.consume() {
+//!             boundary.reached();
+//!             task.polls_left -= 1;
+//!         }
+//!
+//!         if task.polls_left == 0 {
+//!             completed.push(task.id);
+//!         } else {
+//!             ready.push(task);
+//!         }
+//!     }
+//!
+//!     assert_eq!(completed.len(), 2);
+//! });
+//! ```
+
+use crate::rt;
+
+/// Returns one of `items`, exploring every one of them.
+///
+/// # Panics
+///
+/// Panics if `items` is empty, or if called outside of a loom model.
+pub fn one_of<T>(items: &[T]) -> &T {
+    &items[rt::choose(items.len())]
+}
+
+/// Removes one of `items` and returns it, exploring every one of them. The
+/// order of the remaining items is preserved.
+///
+/// Returns `None`, without branching, if `items` is empty.
+///
+/// # Panics
+///
+/// Panics if called outside of a loom model.
+pub fn take<T>(items: &mut Vec<T>) -> Option<T> {
+    if items.is_empty() {
+        return None;
+    }
+
+    Some(items.remove(rt::choose(items.len())))
+}
+
+/// Number of polls a task may run before yielding to the other tasks.
+///
+/// Cooperative executors bound how long a task runs so that a task always
+/// ready does not starve the others. A budget created with
+/// [`explore`](Budget::explore) checks every budget at once.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Budget {
+    remaining: usize,
+}
+
+impl Budget {
+    /// Returns a budget of `polls` polls.
+    pub fn new(polls: usize) -> Budget {
+        Budget { remaining: polls }
+    }
+
+    /// Returns a budget of 1 to `max` polls, exploring each of them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max` is zero, or if called outside of a loom model.
+    pub fn explore(max: usize) -> Budget {
+        assert!(max > 0, "a budget allows at least one poll");
+
+        Budget::new(1 + rt::choose(max))
+    }
+
+    /// Consumes one poll of the budget. Returns `false`, once the budget is
+    /// exhausted, when the task must yield instead.
+    pub fn consume(&mut self) -> bool {
+        if self.remaining == 0 {
+            return false;
+        }
+
+        self.remaining -= 1;
+        true
+    }
+
+    /// Returns the number of polls left.
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+}
+
+/// Poll boundaries of the workers of an executor.
+///
+/// Calling [`reached`](PollBoundary::reached) where a worker starts or ends a
+/// poll makes the boundary a branch point: loom may run another thread there,
+/// and explores every order in which the workers reach the boundaries of the
+/// same `PollBoundary`, even when the tasks share no loom object. Boundaries
+/// of different objects are independent.
+#[derive(Debug)]
+pub struct PollBoundary {
+    object: rt::Custom,
+}
+
+impl PollBoundary {
+    /// Registers a new set of poll boundaries.
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a loom model.
+    #[track_caller]
+    pub fn new() -> PollBoundary {
+        PollBoundary {
+            object: rt::Custom::new("PollBoundary", 1, |_, _| true, location!()),
+        }
+    }
+
+    /// Tells loom the current thread reached a poll boundary.
+    pub fn reached(&self) {
+        self.object.branch(0);
+    }
+}
+
+impl Default for PollBoundary {
+    fn default() -> PollBoundary {
+        PollBoundary::new()
+    }
+}
@@ -0,0 +1,247 @@
+//! Coverage marks, checking that a model explores a scenario.
+//!
+//! A model only finds bugs in the interleavings it explores. A test asserting
+//! on the value a thread observes passes vacuously if loom never makes the
+//! thread observe the interesting one, for example because added
+//! synchronization rules it out. Calling [`mark`] where the scenario happens
+//! records it, and [`Builder::require_marks`] fails the check if a mark is
+//! never hit in any execution.
+//!
+//! The number of executions hitting each mark is reported in
+//! [`ExplorationStats::marks`]. Along with the branch points caused by each
+//! object, the marks make up the [`Coverage`] of a check. With the `coverage`
+//! feature, it is written as JSON to [`Builder::coverage_file`], so the
+//! coverage of two commits can be compared, catching a change silently
+//! reducing the behaviors explored.
+//!
+//! When the number of executions blows up, the [`ExplorationTree`] recorded
+//! with [`Builder::record_tree`] shows where: how many options of each branch
+//! point were explored, and how many were pruned by partial order reduction,
+//! sleep sets or the preemption bound.
+//!
+//! The [`choice`] module provides branch points for modeling schedulers, such
+//! as picking the next task an executor polls.
+//!
+//! [`Builder::record_tree`]: crate::model::Builder::record_tree
+//! [`Builder::require_marks`]: crate::model::Builder::require_marks
+//! [`Builder::coverage_file`]: crate::model::Builder::coverage_file
+//! [`ExplorationStats::marks`]: crate::model::ExplorationStats::marks
+//!
+//! # Examples
+//!
+//! ```
+//! use loom::model::Builder;
+//! use loom::sync::atomic::AtomicUsize;
+//! use loom::sync::Arc;
+//! use loom::thread;
+//!
+//! use std::sync::atomic::Ordering::SeqCst;
+//!
+//! let mut builder = Builder::new();
+//! builder.require_marks(&["observed intermediate value"]);
+//!
+//! builder.check(|| {
+//!     let num = Arc::new(AtomicUsize::new(0));
+//!     let num2 = num.clone();
+//!
+//!     let th = thread::spawn(move || {
+//!         num2.store(1, SeqCst);
+//!         num2.store(2, SeqCst);
+//!     });
+//!
+//!     if num.load(SeqCst) == 1 {
+//!         loom::explore::mark("observed intermediate value");
+//!     }
+//!
+//!     th.join().unwrap();
+//! });
+//! ```
+
+pub mod choice;
+
+use crate::observe::BranchKind;
+use crate::rt;
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::{fs, io, path::Path};
+
+#[cfg(feature = "coverage")]
+use serde::{Deserialize, Serialize};
+
+/// What a check explored: the coverage marks hit and the objects whose
+/// accesses caused branch points.
+///
+/// Returned by [`ExplorationStats::coverage`](crate::model::ExplorationStats::coverage).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "coverage", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "coverage", serde(default))]
+#[non_exhaustive]
+pub struct Coverage {
+    /// Number of executions of the model.
+    pub iterations: usize,
+
+    /// Number of successful executions that hit each mark.
+    pub marks: BTreeMap<String, usize>,
+
+    /// Number of branch points caused by each object, named like `atomic #2`.
+    /// Only recorded with
+    /// [`profile_branches`](crate::model::Builder::profile_branches).
+    pub branches: BTreeMap<String, usize>,
+}
+
+/// The tree of the branch points explored by a check.
+///
+/// Returned by [`ExplorationStats::tree`](crate::model::ExplorationStats::tree)
+/// when [`record_tree`](crate::model::Builder::record_tree) is set. Random
+/// schedules are not recorded.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ExplorationTree {
+    /// The branch points, in pre-order: each node is followed by the subtrees
+    /// of the options explored from it, in the order they were explored.
+    pub nodes: Vec<TreeNode>,
+}
+
+/// A branch point of the [`ExplorationTree`].
+///
+/// `explored`, `dpor_pruned`, `sleep_pruned` and `bound_pruned` add up to
+/// `options`, except for a check stopped early, whose last branch points
+/// have options left to explore.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct TreeNode {
+    /// Position of the branch point in the executions reaching it, the depth
+    /// of the node.
+    pub depth: usize,
+
+    /// What is decided at the branch point.
+    pub kind: BranchKind,
+
+    /// Number of options: the threads that can run, the stores a load can
+    /// read, or the values that can be picked. Threads that yielded are not
+    /// counted.
+    pub options: usize,
+
+    /// Number of options explored.
+    pub explored: usize,
+
+    /// Number of threads not run because partial order reduction found the
+    /// resulting executions equivalent to explored ones.
+    pub dpor_pruned: usize,
+
+    /// Number of threads not run because they were in the sleep set.
+    pub sleep_pruned: usize,
+
+    /// Number of threads not run because it would exceed the preemption,
+    /// context switch or delay bound.
+    pub bound_pruned: usize,
+}
+
+/// Records that the current execution hit the mark `name`.
+///
+/// Hitting a mark does not affect the exploration. An execution hitting the
+/// same mark several times counts once.
+///
+/// # Panics
+///
+/// Panics if called outside of a loom model.
+pub fn mark(name: &'static str) {
+    rt::execution(|execution| {
+        execution.marks.insert(name);
+    });
+}
+
+impl Coverage {
+    /// Returns what `baseline` covers and `self` does not: the marks it hit
+    /// and the objects that caused branch points.
+    ///
+    /// Comparing against the coverage of a previous commit catches changes,
+    /// such as added synchronization, that make a model explore fewer
+    /// behaviors.
+    pub fn lost(&self, baseline: &Coverage) -> Vec<String> {
+        let marks = baseline
+            .marks
+            .keys()
+            .filter(|mark| !self.marks.contains_key(*mark))
+            .map(|mark| format!("mark `{}`", mark));
+
+        let branches = baseline
+            .branches
+            .keys()
+            .filter(|object| !self.branches.contains_key(*object))
+            .map(|object| format!("branches of `{}`", object));
+
+        marks.chain(branches).collect()
+    }
+}
+
+#[cfg(feature = "coverage")]
+impl Coverage {
+    /// Reads the coverage written to `path` by [`store`](Coverage::store).
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Coverage> {
+        let contents = fs::read(path)?;
+        serde_json::from_slice(&contents).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Writes the coverage to `path` as JSON.
+    ///
+    /// Marks and objects are sorted by name, so the files of two checks can
+    /// be compared with a textual diff.
+    pub fn store(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut contents = serde_json::to_vec_pretty(self)?;
+        contents.push(b'\n');
+        fs::write(path, contents)
+    }
+}
+
+impl ExplorationTree {
+    /// Writes the tree to `path`, in the format of its `Display`
+    /// implementation.
+    pub fn store(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+}
+
+/// Writes one line per node, in pre-order: the depth, the kind (`S` for
+/// schedule, `L` for load, `P` for spurious and `C` for choose) and
+/// `explored/options`, followed by the non-zero pruned counts, as `dpor=N`,
+/// `sleep=N` and `bound=N`.
+///
+/// ```text
+/// 0 S 2/2
+/// 1 S 1/2 dpor=1
+/// 2 L 2/2
+/// ```
+impl fmt::Display for ExplorationTree {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for node in &self.nodes {
+            let kind = match node.kind {
+                BranchKind::Schedule => 'S',
+                BranchKind::Load => 'L',
+                BranchKind::Spurious => 'P',
+                BranchKind::Choose => 'C',
+            };
+
+            write!(
+                fmt,
+                "{} {} {}/{}",
+                node.depth, kind, node.explored, node.options
+            )?;
+
+            for &(name, count) in &[
+                ("dpor", node.dpor_pruned),
+                ("sleep", node.sleep_pruned),
+                ("bound", node.bound_pruned),
+            ] {
+                if count > 0 {
+                    write!(fmt, " {}={}", name, count)?;
+                }
+            }
+
+            writeln!(fmt)?;
+        }
+
+        Ok(())
+    }
+}
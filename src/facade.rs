@@ -0,0 +1,228 @@
+//! Switch between the loom mocks and the `std` types with `cfg(loom)`.
+//!
+//! Crates tested with loom usually define a module re-exporting either the
+//! loom mocks or the real types depending on `cfg(loom)`. Forgetting one of
+//! the re-exports silently makes the code use a real primitive while checked
+//! by loom. This module is a complete version of that shim: when built with
+//! `RUSTFLAGS="--cfg loom"`, it re-exports the loom modules, otherwise the
+//! `std` ones.
+//!
+//! ```
+//! use loom::facade::sync::atomic::{AtomicUsize, Ordering::SeqCst};
+//! use loom::facade::sync::Arc;
+//! use loom::facade::thread;
+//!
+//! loom::facade::model(|| {
+//!     let num = Arc::new(AtomicUsize::new(0));
+//!     let th = {
+//!         let num = num.clone();
+//!         thread::spawn(move || num.fetch_add(1, SeqCst))
+//!     };
+//!     num.fetch_add(1, SeqCst);
+//!     th.join().unwrap();
+//!     assert_eq!(2, num.load(SeqCst));
+//! });
+//! ```
+//!
+//! Only the API shared by both sides can be used by code that builds both
+//! ways. Loom specific types, such as [`Notify`](crate::sync::Notify), do
+//! not exist without `cfg(loom)`. [`cell::UnsafeCell`] is the exception: as
+//! the loom version has a different API, a `std` based version of it is
+//! provided when not using loom. The same goes for [`alloc::Track`],
+//! [`lazy_static!`](crate::lazy_static!) and [`model`], which runs the
+//! closure once.
+//!
+//! Loom must be a regular dependency (or a dev-dependency, for tests only) to
+//! use this module, instead of a dependency only enabled with `cfg(loom)`.
+
+#[cfg(loom)]
+#[doc(no_inline)]
+pub use crate::{alloc, cell, hint, lazy_static, model, sync, thread, thread_local};
+
+#[cfg(not(loom))]
+pub use std::{hint, sync, thread, thread_local};
+
+#[cfg(not(loom))]
+#[doc(no_inline)]
+pub use crate::__facade_lazy_static as lazy_static;
+
+/// Runs the closure once, `loom::model` is used with `cfg(loom)`.
+#[cfg(not(loom))]
+pub fn model<F>(f: F)
+where
+    F: Fn() + Sync + Send + 'static,
+{
+    f()
+}
+
+/// `std::alloc`, with a passthrough `Track`.
+#[cfg(not(loom))]
+pub mod alloc {
+    pub use std::alloc::{alloc, alloc_zeroed, dealloc, realloc, Layout};
+
+    /// Passthrough version of [`loom::alloc::Track`](crate::alloc::Track).
+    #[derive(Debug)]
+    pub struct Track<T> {
+        value: T,
+    }
+
+    impl<T> Track<T> {
+        /// Track a value for leaks
+        pub fn new(value: T) -> Track<T> {
+            Track { value }
+        }
+
+        /// Get a reference to the value
+        pub fn get_ref(&self) -> &T {
+            &self.value
+        }
+
+        /// Get a mutable reference to the value
+        pub fn get_mut(&mut self) -> &mut T {
+            &mut self.value
+        }
+
+        /// Stop tracking the value for leaks
+        pub fn into_inner(self) -> T {
+            self.value
+        }
+    }
+}
+
+/// `std::cell`, with an `UnsafeCell` mirroring the loom API.
+#[cfg(not(loom))]
+pub mod cell {
+    pub use std::cell::{BorrowError, BorrowMutError, Cell, Ref, RefCell, RefMut};
+
+    /// Version of `std::cell::UnsafeCell` with the API of
+    /// [`loom::cell::UnsafeCell`](crate::cell::UnsafeCell).
+    #[derive(Debug)]
+    pub struct UnsafeCell<T>(std::cell::UnsafeCell<T>);
+
+    impl<T> UnsafeCell<T> {
+        /// Constructs a new instance of `UnsafeCell` which will wrap the specified value.
+        pub fn new(data: T) -> UnsafeCell<T> {
+            UnsafeCell(std::cell::UnsafeCell::new(data))
+        }
+
+        /// Get an immutable pointer to the wrapped value.
+        pub fn with<F, R>(&self, f: F) -> R
+        where
+            F: FnOnce(*const T) -> R,
+        {
+            f(self.0.get())
+        }
+
+        /// Get a mutable pointer to the wrapped value.
+        pub fn with_mut<F, R>(&self, f: F) -> R
+        where
+            F: FnOnce(*mut T) -> R,
+        {
+            f(self.0.get())
+        }
+    }
+
+    impl<T: Default> Default for UnsafeCell<T> {
+        fn default() -> UnsafeCell<T> {
+            UnsafeCell::new(Default::default())
+        }
+    }
+
+    impl<T> From<T> for UnsafeCell<T> {
+        fn from(src: T) -> UnsafeCell<T> {
+            UnsafeCell::new(src)
+        }
+    }
+}
+
+/// Backing of the `lazy_static!` macro without loom.
+#[cfg(not(loom))]
+pub mod lazy_static {
+    use std::cell::UnsafeCell;
+    use std::fmt;
+    use std::sync::Once;
+
+    /// Version of [`loom::lazy_static::Lazy`](crate::lazy_static::Lazy)
+    /// backed by `std::sync::Once`.
+    pub struct Lazy<T> {
+        // See `loom::lazy_static::Lazy` for why the fields are public.
+        #[doc(hidden)]
+        pub init: fn() -> T,
+        #[doc(hidden)]
+        pub once: Once,
+        #[doc(hidden)]
+        pub value: UnsafeCell<Option<T>>,
+    }
+
+    unsafe impl<T: Send + Sync> Sync for Lazy<T> {}
+
+    impl<T: 'static> Lazy<T> {
+        /// Returns the value, initializing it on first access.
+        pub fn get(&'static self) -> &'static T {
+            self.once.call_once(|| {
+                let value = (self.init)();
+                // Safety: `call_once` runs at most once and excludes readers.
+                unsafe { *self.value.get() = Some(value) };
+            });
+
+            // Safety: the value is never written again once initialized.
+            unsafe { (*self.value.get()).as_ref().unwrap() }
+        }
+    }
+
+    impl<T: 'static> fmt::Debug for Lazy<T> {
+        fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+            fmt.pad("Lazy { .. }")
+        }
+    }
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __facade_lazy_static {
+    ($(#[$attr:meta])* static ref $N:ident : $T:ty = $e:expr; $($t:tt)*) => {
+        $crate::__facade_lazy_static_internal!($(#[$attr])* () static ref $N : $T = $e; $($t)*);
+    };
+    ($(#[$attr:meta])* pub static ref $N:ident : $T:ty = $e:expr; $($t:tt)*) => {
+        $crate::__facade_lazy_static_internal!($(#[$attr])* (pub) static ref $N : $T = $e; $($t)*);
+    };
+    ($(#[$attr:meta])* pub ($($vis:tt)+) static ref $N:ident : $T:ty = $e:expr; $($t:tt)*) => {
+        $crate::__facade_lazy_static_internal!($(#[$attr])* (pub ($($vis)+)) static ref $N : $T = $e; $($t)*);
+    };
+    () => ()
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __facade_lazy_static_internal {
+    ($(#[$attr:meta])* ($($vis:tt)*) static ref $N:ident : $T:ty = $init:expr; $($t:tt)*) => {
+        #[allow(missing_copy_implementations)]
+        #[allow(non_camel_case_types)]
+        #[allow(dead_code)]
+        $(#[$attr])*
+        $($vis)* struct $N {__private_field: ()}
+        #[doc(hidden)]
+        $($vis)* static $N: $N = $N {__private_field: ()};
+        impl ::core::ops::Deref for $N {
+            type Target = $T;
+            fn deref(&self) -> &$T {
+                #[inline(always)]
+                fn __static_ref_initialize() -> $T { $init }
+
+                #[inline(always)]
+                fn __stability() -> &'static $T {
+                    static LAZY: $crate::facade::lazy_static::Lazy<$T> =
+                        $crate::facade::lazy_static::Lazy {
+                            init: __static_ref_initialize,
+                            once: std::sync::Once::new(),
+                            value: std::cell::UnsafeCell::new(None),
+                        };
+                    LAZY.get()
+                }
+                __stability()
+            }
+        }
+        $crate::__facade_lazy_static!($($t)*);
+    };
+    () => ()
+}
@@ -16,13 +16,13 @@ impl AtomicWaker {
     pub fn new() -> AtomicWaker {
         AtomicWaker {
             waker: Mutex::new(None),
-            object: rt::Mutex::new(false),
+            object: rt::Mutex::new(false, location!()),
         }
     }
 
     /// Registers the current task to be notified on calls to `wake`.
     pub fn register(&self, waker: Waker) {
-        if dbg!(!self.object.try_acquire_lock()) {
+        if dbg!(!self.object.try_acquire_lock(location!())) {
             waker.wake();
             // yield the task and try again... this is a spin lock.
             thread::yield_now();
@@ -48,7 +48,7 @@ impl AtomicWaker {
     /// Attempts to take the `Waker` value out of the `AtomicWaker` with the
     /// intention that the caller will wake the task later.
     pub fn take_waker(&self) -> Option<Waker> {
-        dbg!(self.object.acquire_lock());
+        dbg!(self.object.acquire_lock(location!()));
 
         let ret = self.waker.lock().unwrap().take();
 
@@ -1,8 +1,15 @@
 //! Future related synchronization primitives.
 
 mod atomic_waker;
+mod mutex;
+pub mod oneshot;
+mod task;
+mod waker;
 
 pub use self::atomic_waker::AtomicWaker;
+pub use self::mutex::{Mutex, MutexGuard, MutexLockFuture};
+pub use self::task::{spawn, JoinHandle};
+pub use self::waker::{count_waker, noop_waker, WakeCount};
 
 use crate::rt;
 use crate::sync::Arc;
@@ -13,13 +20,14 @@ use std::mem;
 use std::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
 
 /// Block the current thread, driving `f` to completion.
+#[track_caller]
 pub fn block_on<F>(f: F) -> F::Output
 where
     F: Future,
 {
     pin_mut!(f);
 
-    let notify = Arc::new(rt::Notify::new(false, true));
+    let notify = Arc::new(rt::Notify::new(false, true, location!()));
 
     let mut waker = unsafe {
         mem::ManuallyDrop::new(Waker::from_raw(RawWaker::new(
@@ -52,6 +60,8 @@ pub(super) fn waker_vtable() -> &'static RawWakerVTable {
 unsafe fn increase_refcount(data: *const ()) {
     // Retain Arc, but don't touch refcount by wrapping in ManuallyDrop
     let arc = mem::ManuallyDrop::new(Arc::<rt::Notify>::from_raw(data as *const _));
+    // Cloning the waker races with waking it and with polling the future.
+    arc.branch_opaque();
     // Now increase refcount, but don't drop new refcount either
     let _arc_clone: mem::ManuallyDrop<_> = arc.clone();
 }
@@ -73,5 +83,8 @@ unsafe fn wake_by_ref_arc_raw(data: *const ()) {
 }
 
 unsafe fn drop_arc_raw(data: *const ()) {
-    drop(Arc::<rt::Notify>::from_raw(data as *const _))
+    let arc = Arc::<rt::Notify>::from_raw(data as *const _);
+    // Dropping the waker races with waking it and with polling the future.
+    arc.branch_opaque();
+    drop(arc)
 }
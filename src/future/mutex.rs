@@ -0,0 +1,244 @@
+use crate::rt::{self, Location};
+use crate::sync;
+
+use std::cell::UnsafeCell;
+use std::fmt;
+use std::future::Future;
+use std::ops;
+use std::pin::Pin;
+use std::task::{Context, Poll, Waker};
+
+/// Mock implementation of a futures-aware mutex, such as
+/// `futures::lock::Mutex`.
+///
+/// Acquiring the lock returns a future instead of blocking the thread. When
+/// the lock is released, one of the tasks waiting for it is woken, it then
+/// competes with the tasks newly acquiring the lock.
+///
+/// Like real implementations, the waiting tasks are tracked in a list guarded
+/// by a lock. A task registers its waker before trying the lock, so releasing
+/// the lock in between wakes it. Loom explores the wake-ups racing with the
+/// other tasks.
+pub struct Mutex<T> {
+    object: rt::Mutex,
+    waiters: sync::Mutex<Waiters>,
+    data: UnsafeCell<T>,
+}
+
+/// A future which resolves when the lock is acquired.
+///
+/// Dropping the future before it completes stops waiting for the lock. If the
+/// future was woken, the wake-up is passed on to another waiting task.
+pub struct MutexLockFuture<'a, T> {
+    mutex: &'a Mutex<T>,
+    key: Option<usize>,
+    location: Location,
+}
+
+/// Releases the lock when dropped, waking a task waiting for it.
+pub struct MutexGuard<'a, T> {
+    mutex: &'a Mutex<T>,
+}
+
+/// Tasks waiting for the lock, in the order they started waiting.
+#[derive(Debug, Default)]
+struct Waiters {
+    /// The waker of each waiting lock future, `None` once woken.
+    waiters: Vec<(usize, Option<Waker>)>,
+
+    next_key: usize,
+}
+
+unsafe impl<T: Send> Send for Mutex<T> {}
+unsafe impl<T: Send> Sync for Mutex<T> {}
+unsafe impl<T: Sync> Sync for MutexGuard<'_, T> {}
+
+impl<T> Mutex<T> {
+    /// Creates a new mutex in an unlocked state ready for use.
+    #[track_caller]
+    pub fn new(data: T) -> Mutex<T> {
+        Mutex {
+            object: rt::Mutex::new(true, location!()),
+            waiters: sync::Mutex::new(Waiters::default()),
+            data: UnsafeCell::new(data),
+        }
+    }
+
+    /// Acquires the lock, returning a future resolving to the guard.
+    #[track_caller]
+    pub fn lock(&self) -> MutexLockFuture<'_, T> {
+        MutexLockFuture {
+            mutex: self,
+            key: None,
+            location: location!(),
+        }
+    }
+
+    /// Attempts to acquire the lock without waiting.
+    #[track_caller]
+    pub fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
+        if self.object.try_acquire_lock(location!()) {
+            Some(MutexGuard { mutex: self })
+        } else {
+            None
+        }
+    }
+
+    /// Returns a mutable reference to the underlying data.
+    pub fn get_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.data.get() }
+    }
+
+    /// Consumes this mutex, returning the underlying data.
+    pub fn into_inner(self) -> T {
+        self.data.into_inner()
+    }
+
+    fn waiters(&self) -> sync::MutexGuard<'_, Waiters> {
+        self.waiters.lock().unwrap()
+    }
+}
+
+impl<T: Default> Default for Mutex<T> {
+    /// Creates a `Mutex<T>`, with the `Default` value for T.
+    fn default() -> Self {
+        Self::new(Default::default())
+    }
+}
+
+impl<T> From<T> for Mutex<T> {
+    /// Creates a new mutex in an unlocked state ready for use.
+    /// This is equivalent to [`Mutex::new`].
+    fn from(t: T) -> Self {
+        Self::new(t)
+    }
+}
+
+impl<T> fmt::Debug for Mutex<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.pad("Mutex { .. }")
+    }
+}
+
+impl<'a, T> Future for MutexLockFuture<'a, T> {
+    type Output = MutexGuard<'a, T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mutex = self.mutex;
+        let waker = cx.waker().clone();
+
+        {
+            let mut waiters = mutex.waiters();
+
+            match self.key {
+                Some(key) => waiters.register(key, waker),
+                None => self.key = Some(waiters.push(waker)),
+            }
+        }
+
+        if mutex.object.try_acquire_lock(self.location) {
+            if let Some(key) = self.key.take() {
+                mutex.waiters().remove(key);
+            }
+
+            return Poll::Ready(MutexGuard { mutex });
+        }
+
+        Poll::Pending
+    }
+}
+
+impl<T> Drop for MutexLockFuture<'_, T> {
+    fn drop(&mut self) {
+        let key = match self.key {
+            Some(key) => key,
+            None => return,
+        };
+
+        let mut waiters = self.mutex.waiters();
+
+        // The future was woken to acquire the lock, but gives up on it. Wake
+        // another task in its place, or it may wait forever.
+        let next = match waiters.remove(key) {
+            Some(None) => waiters.take_next(),
+            _ => None,
+        };
+
+        drop(waiters);
+
+        if let Some(waker) = next {
+            waker.wake();
+        }
+    }
+}
+
+impl<T> fmt::Debug for MutexLockFuture<'_, T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("MutexLockFuture")
+            .field("waiting", &self.key.is_some())
+            .finish()
+    }
+}
+
+impl<T> ops::Deref for MutexGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.mutex.data.get() }
+    }
+}
+
+impl<T> ops::DerefMut for MutexGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.mutex.data.get() }
+    }
+}
+
+impl<T> Drop for MutexGuard<'_, T> {
+    fn drop(&mut self) {
+        self.mutex.object.release_lock();
+
+        let waker = self.mutex.waiters().take_next();
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for MutexGuard<'_, T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, fmt)
+    }
+}
+
+impl Waiters {
+    /// Adds a waiting task, returning its key.
+    fn push(&mut self, waker: Waker) -> usize {
+        let key = self.next_key;
+        self.next_key += 1;
+        self.waiters.push((key, Some(waker)));
+        key
+    }
+
+    /// Updates the waker of a waiting task, which waits again if it was
+    /// woken.
+    fn register(&mut self, key: usize, waker: Waker) {
+        if let Some((_, slot)) = self.waiters.iter_mut().find(|(k, _)| *k == key) {
+            *slot = Some(waker);
+        }
+    }
+
+    /// Removes a task from the waiters, returning its waker. The waker is
+    /// `None` if the task was woken.
+    fn remove(&mut self, key: usize) -> Option<Option<Waker>> {
+        let i = self.waiters.iter().position(|(k, _)| *k == key)?;
+        Some(self.waiters.remove(i).1)
+    }
+
+    /// Takes the waker of the first task waiting for the lock and not woken
+    /// yet.
+    fn take_next(&mut self) -> Option<Waker> {
+        self.waiters.iter_mut().find_map(|(_, slot)| slot.take())
+    }
+}
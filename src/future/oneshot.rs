@@ -0,0 +1,174 @@
+//! Mock implementation of a oneshot channel, such as
+//! `futures::channel::oneshot`.
+//!
+//! The receiver is a future, resolving to the value sent, or to an error if
+//! the sender is dropped without sending one. Completing the channel, from
+//! either side, and registering the receiver's waker are modeled with loom
+//! atomics and locks, so a completion racing with the receiver polling is
+//! explored like any other.
+
+use crate::sync::atomic::AtomicBool;
+use crate::sync::{Arc, Mutex};
+
+use std::error::Error;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::Ordering::SeqCst;
+use std::task::{Context, Poll, Waker};
+
+/// Creates a new oneshot channel, returning the sender and receiver halves.
+#[track_caller]
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let inner = Arc::new(Inner {
+        complete: AtomicBool::new(false),
+        data: Mutex::new(None),
+        rx_task: Mutex::new(None),
+    });
+
+    let sender = Sender {
+        inner: inner.clone(),
+    };
+
+    (sender, Receiver { inner })
+}
+
+/// Sends a value to the associated [`Receiver`].
+pub struct Sender<T> {
+    inner: Arc<Inner<T>>,
+}
+
+/// A future resolving to the value sent by the associated [`Sender`].
+pub struct Receiver<T> {
+    inner: Arc<Inner<T>>,
+}
+
+/// Error returned by a [`Receiver`] whose [`Sender`] was dropped without
+/// sending a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Canceled;
+
+struct Inner<T> {
+    /// Set once the value is sent, or either half is dropped.
+    complete: AtomicBool,
+
+    /// The value sent, until received.
+    data: Mutex<Option<T>>,
+
+    /// Waker of the receiving task, registered when the channel is polled.
+    rx_task: Mutex<Option<Waker>>,
+}
+
+impl<T> Sender<T> {
+    /// Sends `value`, completing the channel.
+    ///
+    /// Returns the value back if the receiver was dropped.
+    pub fn send(self, value: T) -> Result<(), T> {
+        if self.inner.complete.load(SeqCst) {
+            return Err(value);
+        }
+
+        *self.inner.data.lock().unwrap() = Some(value);
+
+        // The receiver may have been dropped in the meantime, in which case
+        // the value is taken back, unless the receiver already did.
+        if self.inner.complete.load(SeqCst) {
+            if let Some(value) = self.inner.data.lock().unwrap().take() {
+                return Err(value);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns `true` if the receiver was dropped.
+    pub fn is_canceled(&self) -> bool {
+        self.inner.complete.load(SeqCst)
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        self.inner.complete.store(true, SeqCst);
+
+        let task = self.inner.rx_task.lock().unwrap().take();
+
+        if let Some(task) = task {
+            task.wake();
+        }
+    }
+}
+
+impl<T> fmt::Debug for Sender<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Sender").finish()
+    }
+}
+
+impl<T> Receiver<T> {
+    /// Attempts to receive the value without waiting.
+    ///
+    /// Returns `Ok(None)` if the value was not sent yet, and an error if the
+    /// sender was dropped without sending one.
+    pub fn try_recv(&mut self) -> Result<Option<T>, Canceled> {
+        if !self.inner.complete.load(SeqCst) {
+            return Ok(None);
+        }
+
+        match self.inner.data.lock().unwrap().take() {
+            Some(value) => Ok(Some(value)),
+            None => Err(Canceled),
+        }
+    }
+}
+
+impl<T> Future for Receiver<T> {
+    type Output = Result<T, Canceled>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<T, Canceled>> {
+        let inner = &self.inner;
+
+        // The waker is registered before checking for completion again, the
+        // sender completing the channel in between wakes it.
+        let mut done = inner.complete.load(SeqCst);
+
+        if !done {
+            let waker = cx.waker().clone();
+            *inner.rx_task.lock().unwrap() = Some(waker);
+
+            done = inner.complete.load(SeqCst);
+        }
+
+        if !done {
+            return Poll::Pending;
+        }
+
+        match inner.data.lock().unwrap().take() {
+            Some(value) => Poll::Ready(Ok(value)),
+            None => Poll::Ready(Err(Canceled)),
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        self.inner.complete.store(true, SeqCst);
+
+        let task = self.inner.rx_task.lock().unwrap().take();
+        drop(task);
+    }
+}
+
+impl<T> fmt::Debug for Receiver<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Receiver").finish()
+    }
+}
+
+impl fmt::Display for Canceled {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str("oneshot canceled")
+    }
+}
+
+impl Error for Canceled {}
@@ -0,0 +1,83 @@
+use super::{block_on, AtomicWaker};
+use crate::sync::{Arc, Mutex};
+use crate::thread;
+
+use std::fmt;
+use std::future::Future;
+use std::panic;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Spawn a new task, driving `f` to completion concurrently with the
+/// spawning task.
+///
+/// Each task is driven by a dedicated thread, so loom explores all
+/// interleavings of the tasks' polls and wake-ups, just as it does for
+/// threads. This also means spawned tasks count towards
+/// [`MAX_THREADS`](crate::MAX_THREADS).
+pub fn spawn<F>(f: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let inner = Arc::new(Inner {
+        output: Mutex::new(None),
+        waker: AtomicWaker::new(),
+    });
+
+    let task = inner.clone();
+
+    let thread = thread::spawn(move || {
+        let output = block_on(f);
+
+        *task.output.lock().unwrap() = Some(output);
+        task.waker.wake();
+    });
+
+    JoinHandle {
+        inner,
+        thread: Some(thread),
+    }
+}
+
+/// An owned permission to join on a task, awaiting its output.
+pub struct JoinHandle<T> {
+    inner: Arc<Inner<T>>,
+
+    /// Thread driving the task, joined once the output is taken so that the
+    /// task does not count as running under `MainExit::RequireJoined`.
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+struct Inner<T> {
+    output: Mutex<Option<T>>,
+    waker: AtomicWaker,
+}
+
+impl<T> Future for JoinHandle<T> {
+    type Output = T;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        self.inner.waker.register_by_ref(cx.waker());
+
+        let output = match self.inner.output.lock().unwrap().take() {
+            Some(output) => output,
+            None => return Poll::Pending,
+        };
+
+        // The thread exits right after publishing the output.
+        if let Some(thread) = self.thread.take() {
+            if let Err(panic) = thread.join() {
+                panic::resume_unwind(panic);
+            }
+        }
+
+        Poll::Ready(output)
+    }
+}
+
+impl<T> fmt::Debug for JoinHandle<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("JoinHandle").finish()
+    }
+}
@@ -0,0 +1,74 @@
+use crate::sync::atomic::AtomicUsize;
+use crate::sync::Arc;
+
+use std::mem;
+use std::sync::atomic::Ordering::SeqCst;
+use std::task::{RawWaker, RawWakerVTable, Waker};
+
+/// Returns a waker that does nothing when woken.
+///
+/// Useful for manually polling a future that is expected to complete, or
+/// whose wake-ups are not relevant to the test.
+pub fn noop_waker() -> Waker {
+    unsafe { Waker::from_raw(noop_raw_waker()) }
+}
+
+fn noop_raw_waker() -> RawWaker {
+    RawWaker::new(std::ptr::null(), noop_vtable())
+}
+
+fn noop_vtable() -> &'static RawWakerVTable {
+    &RawWakerVTable::new(|_| noop_raw_waker(), |_| {}, |_| {}, |_| {})
+}
+
+/// Returns a waker that counts the number of times it is woken, along with a
+/// handle to read the count.
+///
+/// Waking the waker is a branch point, so loom explores the orders in which
+/// wake-ups race with the rest of the model.
+pub fn count_waker() -> (Waker, WakeCount) {
+    let count = Arc::new(AtomicUsize::new(0));
+    let data = Arc::into_raw(count.clone()) as *const ();
+
+    let waker = unsafe { Waker::from_raw(RawWaker::new(data, count_vtable())) };
+
+    (waker, WakeCount { count })
+}
+
+/// Number of times a waker returned by [`count_waker`] was woken.
+#[derive(Debug, Clone)]
+pub struct WakeCount {
+    count: Arc<AtomicUsize>,
+}
+
+impl WakeCount {
+    /// Returns the number of times the waker was woken.
+    pub fn get(&self) -> usize {
+        self.count.load(SeqCst)
+    }
+}
+
+fn count_vtable() -> &'static RawWakerVTable {
+    &RawWakerVTable::new(clone_count, wake_count, wake_by_ref_count, drop_count)
+}
+
+unsafe fn clone_count(data: *const ()) -> RawWaker {
+    let count = mem::ManuallyDrop::new(Arc::<AtomicUsize>::from_raw(data as *const _));
+    let _count_clone: mem::ManuallyDrop<_> = count.clone();
+
+    RawWaker::new(data, count_vtable())
+}
+
+unsafe fn wake_count(data: *const ()) {
+    let count = Arc::<AtomicUsize>::from_raw(data as *const _);
+    count.fetch_add(1, SeqCst);
+}
+
+unsafe fn wake_by_ref_count(data: *const ()) {
+    let count = mem::ManuallyDrop::new(Arc::<AtomicUsize>::from_raw(data as *const _));
+    count.fetch_add(1, SeqCst);
+}
+
+unsafe fn drop_count(data: *const ()) {
+    drop(Arc::<AtomicUsize>::from_raw(data as *const _))
+}
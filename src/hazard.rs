@@ -0,0 +1,237 @@
+//! Mock implementation of hazard pointers.
+//!
+//! A thread protects an object by publishing its pointer in a
+//! [`HazardPointer`] before accessing it. An object passed to [`retire`] is
+//! reclaimed, dropped and deallocated, as soon as a scan of the hazard
+//! pointers finds it unprotected. Scans run when an object is retired and on
+//! [`reclaim`]. This is the earliest point at which a hazard pointer domain
+//! may reclaim it, so premature reclamation shows up in the model:
+//!
+//! - Objects are allocated with [`alloc`], which tracks them like
+//!   [`loom::alloc`](crate::alloc). Accessing a [`cell`](crate::cell) of a
+//!   reclaimed object fails the execution as a use after free.
+//! - Reclaiming an object drops it, which is a write access to its cells, so
+//!   an access that is not ordered with the reclamation is reported like any
+//!   other `UnsafeCell` race.
+//! - Objects that are never reclaimed are reported as leaks.
+//!
+//! Publishing a hazard pointer and scanning them are modeled with loom
+//! atomics and fences, so the ordering of a protection with the scans is
+//! explored like any other. There is a single, global domain.
+
+use crate::lazy_static::Lazy;
+use crate::rt::{self, Location};
+use crate::sync::atomic::{fence, AtomicPtr, AtomicUsize};
+use crate::sync::Mutex;
+
+use std::alloc::Layout;
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem;
+use std::ptr;
+use std::sync::atomic::Ordering::{Acquire, Relaxed, Release, SeqCst};
+use std::sync::Arc;
+
+/// Tracks the hazard pointers and retired objects of an execution.
+static DOMAIN: Lazy<Domain> = Lazy {
+    init: Domain::new,
+    _p: PhantomData,
+};
+
+struct Domain {
+    /// The published pointer of each hazard pointer, null when unused.
+    slots: Mutex<Vec<Arc<AtomicUsize>>>,
+
+    /// Objects retired but still protected when last scanned. The ones left
+    /// once the execution completes are leaked, and reported as such by the
+    /// allocation tracker.
+    retired: Mutex<Vec<Retired>>,
+}
+
+/// A type-erased retired object.
+struct Retired {
+    ptr: usize,
+    location: Location,
+    reclaim: unsafe fn(usize, Location),
+}
+
+/// A hazard pointer, protecting at most one object at a time.
+///
+/// The protection is released when the hazard pointer is reset or dropped.
+pub struct HazardPointer {
+    slot: Arc<AtomicUsize>,
+}
+
+/// Moves `value` to a new heap allocation, returning a pointer to it.
+///
+/// The allocation is tracked for leaks, it is released by [`retire`].
+///
+/// # Panics
+///
+/// Panics if `T` is zero-sized.
+#[track_caller]
+pub fn alloc<T>(value: T) -> *mut T {
+    let layout = Layout::new::<T>();
+    assert!(layout.size() != 0, "cannot allocate a zero-sized object");
+
+    unsafe {
+        let ptr = crate::alloc::alloc(layout) as *mut T;
+
+        if ptr.is_null() {
+            std::alloc::handle_alloc_error(layout);
+        }
+
+        ptr.write(value);
+        ptr
+    }
+}
+
+/// Retires the object, to be reclaimed once no hazard pointer protects it.
+///
+/// The hazard pointers are scanned right away, an unprotected object is
+/// reclaimed before `retire` returns.
+///
+/// # Safety
+///
+/// `ptr` must have been returned by [`alloc`] and not be retired yet. The
+/// object must not be reachable by other threads anymore, otherwise a thread
+/// may protect it after it is reclaimed.
+#[track_caller]
+pub unsafe fn retire<T>(ptr: *mut T) {
+    unsafe fn reclaim<T>(ptr: usize, location: Location) {
+        ptr::drop_in_place(ptr as *mut T);
+        rt::dealloc(ptr as *mut u8, Layout::new::<T>(), location);
+    }
+
+    assert!(!ptr.is_null(), "cannot retire a null pointer");
+
+    let domain = DOMAIN.get();
+
+    domain.retired.lock().unwrap().push(Retired {
+        ptr: ptr as usize,
+        location: location!(),
+        reclaim: reclaim::<T>,
+    });
+
+    domain.reclaim();
+}
+
+/// Scans the hazard pointers, reclaiming the retired objects they do not
+/// protect. Returns the number of objects reclaimed.
+///
+/// Call it once the threads are joined to reclaim the objects retired while
+/// protected, which are otherwise reported as leaks.
+pub fn reclaim() -> usize {
+    DOMAIN.get().reclaim()
+}
+
+impl Domain {
+    fn new() -> Domain {
+        Domain {
+            slots: Mutex::new(vec![]),
+            retired: Mutex::new(vec![]),
+        }
+    }
+
+    fn reclaim(&self) -> usize {
+        let retired = mem::take(&mut *self.retired.lock().unwrap());
+
+        if retired.is_empty() {
+            return 0;
+        }
+
+        // Pairs with the fence following the publication of a hazard pointer:
+        // either the scan sees the hazard pointer, or the protecting thread
+        // sees that the object was unlinked.
+        fence(SeqCst);
+
+        let slots = self.slots.lock().unwrap().clone();
+        let protected: Vec<_> = slots.iter().map(|slot| slot.load(Acquire)).collect();
+
+        let (reclaimable, kept): (Vec<_>, Vec<_>) = retired
+            .into_iter()
+            .partition(|retired| !protected.contains(&retired.ptr));
+
+        self.retired.lock().unwrap().extend(kept);
+
+        for retired in &reclaimable {
+            unsafe { (retired.reclaim)(retired.ptr, retired.location) };
+        }
+
+        reclaimable.len()
+    }
+}
+
+impl HazardPointer {
+    /// Creates a hazard pointer, not protecting any object.
+    #[track_caller]
+    pub fn new() -> HazardPointer {
+        let slot = Arc::new(AtomicUsize::new(0));
+        DOMAIN.get().slots.lock().unwrap().push(slot.clone());
+
+        HazardPointer { slot }
+    }
+
+    /// Protects the object `src` points to, returning the pointer.
+    ///
+    /// The object is not reclaimed until the protection is released, it may
+    /// be accessed until then. Any object protected before is released.
+    #[track_caller]
+    pub fn protect<T>(&mut self, src: &AtomicPtr<T>) -> *mut T {
+        let mut ptr = src.load(Relaxed);
+
+        loop {
+            match self.try_protect(ptr, src) {
+                Ok(ptr) => return ptr,
+                Err(current) => ptr = current,
+            }
+        }
+    }
+
+    /// Protects the object `ptr` points to, if `src` still points to it.
+    ///
+    /// On success, the object is not reclaimed until the protection is
+    /// released. On failure, no object is protected and the current value of
+    /// `src` is returned.
+    #[track_caller]
+    pub fn try_protect<T>(&mut self, ptr: *mut T, src: &AtomicPtr<T>) -> Result<*mut T, *mut T> {
+        self.slot.store(ptr as usize, Relaxed);
+
+        // Pairs with the fence of the scans.
+        fence(SeqCst);
+
+        let current = src.load(Acquire);
+
+        if current == ptr {
+            Ok(ptr)
+        } else {
+            self.reset_protection();
+            Err(current)
+        }
+    }
+
+    /// Releases the protected object, if any.
+    #[track_caller]
+    pub fn reset_protection(&mut self) {
+        self.slot.store(0, Release);
+    }
+}
+
+impl Default for HazardPointer {
+    #[track_caller]
+    fn default() -> HazardPointer {
+        HazardPointer::new()
+    }
+}
+
+impl Drop for HazardPointer {
+    fn drop(&mut self) {
+        self.reset_protection();
+    }
+}
+
+impl fmt::Debug for HazardPointer {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.pad("HazardPointer { .. }")
+    }
+}
@@ -25,6 +25,12 @@ pub struct Lazy<T> {
 
 impl<T: 'static> Lazy<T> {
     /// Mock implementation of `lazy_static::Lazy::get`.
+    ///
+    /// Like the `std::sync::Once` used by `lazy_static`, the first thread
+    /// accessing the static runs its initializer while the others wait for
+    /// it. The initializer may access other statics. If it panics, the static
+    /// is poisoned and accessing it again panics.
+    #[track_caller]
     pub fn get(&'static self) -> &'static T {
         // This is not great. Specifically, we're returning a 'static reference to a value that
         // only lives for the duration of the execution. Unfortunately, the semantics of lazy
@@ -39,31 +45,71 @@ impl<T: 'static> Lazy<T> {
         // users sometimes _rely_ on the returned reference being 'static. If we provided something
         // that used a closure to give the user a non-`'static` reference, we wouldn't be all that
         // much further along.
-        match unsafe { self.try_get() } {
-            Some(v) => v,
-            None => {
-                // Init the value out of the `rt::execution`
-                let sv = crate::rt::lazy_static::StaticValue::new((self.init)());
-
-                // While calling init, we may have yielded to the scheduler, in which case some
-                // _other_ thread may have initialized the static. The real lazy_static does not
-                // have this issue, since it takes a lock before initializing the new value, and
-                // readers wait on that lock if they encounter it. We could implement that here
-                // too, but for simplicity's sake, we just do another try_get here for now.
-                if let Some(v) = unsafe { self.try_get() } {
-                    return v;
-                }
+        if let Some(v) = unsafe { self.try_get() } {
+            return v;
+        }
+
+        let lock = self.lock();
 
-                rt::execution(|execution| {
-                    let sv = execution.lazy_statics.init_static(self, sv);
+        if lock.is_held() {
+            panic!("lazy static accessed recursively by its own initializer");
+        }
+
+        lock.acquire_lock(location!());
 
-                    // lazy_static uses std::sync::Once, which does a swap(AcqRel) to set
-                    sv.sync.sync_store(&mut execution.threads, Ordering::AcqRel);
-                });
+        // Another thread initialized the static while this one waited.
+        if let Some(v) = unsafe { self.try_get() } {
+            lock.release_lock();
+            return v;
+        }
+
+        if lock.is_poisoned() {
+            lock.release_lock();
+            panic!("Once instance has previously been poisoned");
+        }
+
+        // Releases the lock once the static is initialized, poisoning it if
+        // the initializer panics.
+        struct Init(rt::Mutex);
+
+        impl Drop for Init {
+            fn drop(&mut self) {
+                if panicking() {
+                    self.0.poison();
+                }
 
-                unsafe { self.try_get() }.expect("bug")
+                self.0.release_lock();
             }
         }
+
+        let init = Init(lock);
+
+        // Init the value out of the `rt::execution`
+        let sv = crate::rt::lazy_static::StaticValue::new((self.init)());
+
+        rt::execution(|execution| {
+            let sv = execution.lazy_statics.init_static(self, sv);
+
+            // lazy_static uses std::sync::Once, which does a swap(AcqRel) to set
+            sv.sync.sync_store(&mut execution.threads, Ordering::AcqRel);
+        });
+
+        drop(init);
+
+        unsafe { self.try_get() }.expect("bug")
+    }
+
+    /// Returns the lock serializing the initialization of the static in the
+    /// current execution, creating it on first use.
+    #[track_caller]
+    fn lock(&'static self) -> rt::Mutex {
+        if let Some(lock) = rt::execution(|execution| execution.lazy_statics.lock(self)) {
+            return lock;
+        }
+
+        let lock = rt::Mutex::new(false, location!());
+        rt::execution(|execution| execution.lazy_statics.set_lock(self, lock));
+        lock
     }
 
     unsafe fn try_get(&'static self) -> Option<&'static T> {
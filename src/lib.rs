@@ -99,7 +99,9 @@
 //! Test cases using loom must be fully determinstic. All sources of non-determism must be via loom
 //! types so that loom can expose different possible values on each execution of the test closure.
 //! Other sources of non-determinism like random number generation or system calls cannot be
-//! modeled directly by loom, and must be mocked to be testable by loom.
+//! modeled directly by loom, and must be mocked to be testable by loom. Code that reads the
+//! time should use [`time::Instant`], which is backed by a virtual clock. Setting
+//! `LOOM_CHECK_DETERMINISM` makes loom panic when a model turns out not to be deterministic.
 //!
 //! To model synchronization non-determinism, tests must use the loom synchronization types, such
 //! as [`Atomic*`](sync::atomic), [`Mutex`](sync::Mutex), [`RwLock`](sync::RwLock),
@@ -107,6 +109,8 @@
 //! [`UnsafeCell`](cell::UnsafeCell), and [`lazy_static!`]. However, when **not** running loom
 //! tests, the `std` should be used, since the loom runtime won't be active. This means that
 //! library code will need to use conditional compilation to decide which types to use.
+//! Loom cannot tell when a model uses a `std` primitive instead, only a loom type used from a
+//! thread spawned with `std::thread::spawn` is reported.
 //!
 //! It is recommended to use a `loom` cfg flag to signal using the loom types. You can do this by
 //! passing `RUSTFLAGS="--cfg loom"` as part of the command when you want to run the loom tests.
@@ -227,10 +231,16 @@
 //! - `LOOM_LOCATION`
 //!
 //! The first environment variable, `LOOM_LOG`, outputs a marker on every thread switch. This helps
-//! with tracing the exact steps in a threaded environment that results in the test failure.
+//! with tracing the exact steps in a threaded environment that results in the test failure. Its
+//! value selects what else is logged, such as `LOOM_LOG=debug,mutex=off` to also log the
+//! operations on atomics and other primitives except mutexes, see
+//! [`Builder::log_filter`](model::Builder::log_filter). Setting `LOOM_LOG_FAILING` only logs the
+//! executions that fail.
 //!
 //! The second, `LOOM_LOCATION`, enables location tracking. This includes additional information in
-//! panic messages that helps identify which specific field resulted in the error.
+//! panic messages that helps identify which specific field resulted in the error. As it slows down
+//! every execution, `LOOM_LOCATE_FAILURES` can be set instead: loom then replays only the failing
+//! execution with location tracking, and reports the failure of that run.
 //!
 //! Put together, the command becomes (yes, we know this is not great... but it works):
 //!
@@ -247,6 +257,11 @@
 //! This should provide you with a trace of all the concurrency events leading up to the failure,
 //! which should allow you to identify how the bug is triggered.
 //!
+//! Failing executions found deep into the exploration often involve many more thread switches than
+//! needed to trigger the bug. Setting `LOOM_MINIMIZE` makes loom shrink the failing execution
+//! first: it prints the few choices that still lead to the failure and reports the failure of the
+//! shrunk execution instead.
+//!
 //! # Limitations and Caveats
 //!
 //! ## Intrusive Implementation
@@ -378,23 +393,92 @@ mod rt;
 pub use rt::MAX_THREADS;
 
 pub mod alloc;
+pub mod causality;
 pub mod cell;
+#[cfg(feature = "custom")]
+pub mod custom;
+#[cfg(feature = "epoch")]
+pub mod epoch;
+pub mod explore;
+pub mod facade;
+#[cfg(feature = "hazard")]
+pub mod hazard;
 pub mod hint;
 pub mod lazy_static;
+pub mod litmus;
 pub mod model;
+pub mod observe;
 pub mod sync;
 pub mod thread;
+pub mod time;
 
 #[doc(inline)]
-pub use crate::model::model;
+pub use crate::model::{
+    atomic_section, branch, choose, model, model_each, model_each_shrinking, model_outcomes,
+    model_returning,
+};
 
 if_futures! {
     pub mod future;
 }
 
+/// Marks a function as a test checked by loom.
+///
+/// With `cfg(loom)`, the body of the function runs as the closure of a
+/// [`model::Builder`] check. Otherwise, the function is a plain `#[test]`
+/// running the body once, like with [`facade::model`]. The arguments of the
+/// attribute set fields of the `Builder`, an optional setting takes the
+/// value directly:
+///
+/// ```
+/// use loom::facade::sync::atomic::{AtomicUsize, Ordering::SeqCst};
+/// use loom::facade::sync::Arc;
+/// use loom::facade::thread;
+///
+/// #[loom::test(preemption_bound = 3, check_determinism = true)]
+/// fn concurrent_increments() {
+///     let num = Arc::new(AtomicUsize::new(0));
+///     let th = {
+///         let num = num.clone();
+///         thread::spawn(move || num.fetch_add(1, SeqCst))
+///     };
+///     num.fetch_add(1, SeqCst);
+///     th.join().unwrap();
+///     assert_eq!(2, num.load(SeqCst));
+/// }
+/// ```
+///
+/// The function cannot take arguments nor return a value.
+///
+/// Requires the `macros` feature.
+#[cfg(feature = "macros")]
+pub use loom_macros::test;
+
+#[cfg(feature = "macros")]
+#[doc(hidden)]
+pub mod __macro_support {
+    /// Converts the value of a `#[loom::test]` argument to the type of the
+    /// `Builder` field, wrapping it in `Some` for optional settings.
+    pub trait Setting<T> {
+        fn into_setting(self) -> T;
+    }
+
+    impl<T> Setting<T> for T {
+        fn into_setting(self) -> T {
+            self
+        }
+    }
+
+    impl<T> Setting<Option<T>> for T {
+        fn into_setting(self) -> Option<T> {
+            Some(self)
+        }
+    }
+}
+
 #[doc(hidden)]
 pub fn __debug_enabled() -> bool {
-    rt::execution(|e| e.log)
+    rt::execution(|e| e.log.is_enabled())
 }
 
 /// Mock version of `std::thread_local!`.
@@ -408,6 +492,17 @@ macro_rules! thread_local {
     // empty (base case for the recursion)
     () => {};
 
+    // process multiple declarations, with a const initializer
+    ($(#[$attr:meta])* $vis:vis static $name:ident: $t:ty = const $init:block; $($rest:tt)*) => (
+        $crate::__thread_local_inner!($(#[$attr])* $vis $name, $t, $init);
+        $crate::thread_local!($($rest)*);
+    );
+
+    // handle a single declaration, with a const initializer
+    ($(#[$attr:meta])* $vis:vis static $name:ident: $t:ty = const $init:block) => (
+        $crate::__thread_local_inner!($(#[$attr])* $vis $name, $t, $init);
+    );
+
     // process multiple declarations
     ($(#[$attr:meta])* $vis:vis static $name:ident: $t:ty = $init:expr; $($rest:tt)*) => (
         $crate::__thread_local_inner!($(#[$attr])* $vis $name, $t, $init);
@@ -484,3 +579,86 @@ macro_rules! __lazy_static_internal {
     };
     () => ()
 }
+
+/// Check a memory model litmus test.
+///
+/// Each `thread` block runs on its own thread and shares the listed atomics,
+/// all `AtomicUsize` initialized to zero. The values returned by each thread
+/// (`()`, a `usize`, or a tuple of up to three `usize`), concatenated in thread
+/// order, make up the outcome of an execution.
+///
+/// The test fails if a `forbid` outcome is observed by any execution, or if an
+/// `expect` outcome is not observed by at least one execution. The macro
+/// evaluates to the set of all observed outcomes.
+///
+/// # Examples
+///
+/// Store buffering, with `SeqCst` fences:
+///
+/// ```
+/// use loom::sync::atomic::fence;
+/// use std::sync::atomic::Ordering::{Relaxed, SeqCst};
+///
+/// loom::litmus! {
+///     atomics: [x, y];
+///     thread {
+///         x.store(1, Relaxed);
+///         fence(SeqCst);
+///         y.load(Relaxed)
+///     }
+///     thread {
+///         y.store(1, Relaxed);
+///         fence(SeqCst);
+///         x.load(Relaxed)
+///     }
+///     forbid (0, 0);
+///     expect (1, 1);
+/// };
+/// ```
+#[macro_export]
+macro_rules! litmus {
+    (
+        atomics: $atomics:tt;
+        $( thread $body:block )+
+        $( forbid ( $($forbid:expr),* ); )*
+        $( expect ( $($expect:expr),* ); )*
+    ) => {
+        $crate::litmus::__check(
+            || {
+                $crate::__litmus_atomics!($atomics);
+
+                let mut threads: std::vec::Vec<$crate::litmus::__Thread> = std::vec::Vec::new();
+
+                $(
+                    $crate::__litmus_thread!(threads, $atomics, $body);
+                )+
+
+                threads
+            },
+            vec![$( vec![$($forbid),*] ),*],
+            vec![$( vec![$($expect),*] ),*],
+        )
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __litmus_atomics {
+    ([ $($atomic:ident),* ]) => {
+        $(
+            let $atomic = std::sync::Arc::new($crate::sync::atomic::AtomicUsize::new(0));
+        )*
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __litmus_thread {
+    ($threads:ident, [ $($atomic:ident),* ], $body:block) => {{
+        $(
+            let $atomic = $atomic.clone();
+        )*
+
+        $threads.push(std::boxed::Box::new(move || $crate::litmus::__registers($body)));
+    }};
+}
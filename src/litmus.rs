@@ -0,0 +1,278 @@
+//! Memory model litmus tests.
+//!
+//! A litmus test runs a few short threads operating on shared atomics and
+//! asserts which outcomes may, or may not, be observed across all executions.
+//! Litmus tests are written using the [`litmus!`](crate::litmus!) macro, or
+//! with [`two_threads`] and [`three_threads`], whose threads share the atomics
+//! of [`Shared`].
+//!
+//! # Examples
+//!
+//! Message passing, with a release store and an acquire load:
+//!
+//! ```
+//! use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
+//!
+//! loom::litmus::two_threads(
+//!     |t| {
+//!         t.x.store(1, Relaxed);
+//!         t.y.store(1, Release);
+//!     },
+//!     |t| (t.y.load(Acquire), t.x.load(Relaxed)),
+//! )
+//! .allowed_outcomes(vec![((), (0, 0)), ((), (0, 1)), ((), (1, 1))]);
+//! ```
+
+use crate::sync::atomic::AtomicUsize;
+use crate::thread;
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::sync::Arc;
+
+/// Values returned by a litmus test thread.
+///
+/// The values returned by all threads, in order, make up the outcome of an
+/// execution.
+pub trait Registers {
+    /// Append the register values to `dst`.
+    fn push_to(self, dst: &mut Vec<usize>);
+}
+
+impl Registers for () {
+    fn push_to(self, _: &mut Vec<usize>) {}
+}
+
+impl Registers for usize {
+    fn push_to(self, dst: &mut Vec<usize>) {
+        dst.push(self);
+    }
+}
+
+impl Registers for (usize, usize) {
+    fn push_to(self, dst: &mut Vec<usize>) {
+        dst.push(self.0);
+        dst.push(self.1);
+    }
+}
+
+impl Registers for (usize, usize, usize) {
+    fn push_to(self, dst: &mut Vec<usize>) {
+        dst.push(self.0);
+        dst.push(self.1);
+        dst.push(self.2);
+    }
+}
+
+/// The atomics shared by the threads of a litmus test, all initialized to
+/// zero.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct Shared {
+    /// First shared atomic.
+    pub x: AtomicUsize,
+
+    /// Second shared atomic.
+    pub y: AtomicUsize,
+
+    /// Third shared atomic.
+    pub z: AtomicUsize,
+}
+
+/// A litmus test, checked by [`outcomes`](Litmus::outcomes) or
+/// [`allowed_outcomes`](Litmus::allowed_outcomes).
+///
+/// The outcome of an execution is the tuple of the values returned by each
+/// thread.
+pub struct Litmus<O> {
+    run: Arc<dyn Fn() -> O + Sync + Send>,
+}
+
+/// Creates a litmus test running `a` and `b` concurrently.
+///
+/// `a` runs on a spawned thread, `b` on the main thread.
+pub fn two_threads<A, B, RA, RB>(a: A, b: B) -> Litmus<(RA, RB)>
+where
+    A: Fn(&Shared) -> RA + Sync + Send + 'static,
+    B: Fn(&Shared) -> RB + Sync + Send + 'static,
+    RA: Send + 'static,
+    RB: 'static,
+{
+    let a = Arc::new(a);
+
+    Litmus {
+        run: Arc::new(move || {
+            let shared = Arc::new(Shared::new());
+
+            let th = spawn(&a, &shared);
+            let rb = b(&shared);
+
+            (th.join().unwrap(), rb)
+        }),
+    }
+}
+
+/// Creates a litmus test running `a`, `b` and `c` concurrently.
+///
+/// `a` and `b` run on spawned threads, `c` on the main thread.
+pub fn three_threads<A, B, C, RA, RB, RC>(a: A, b: B, c: C) -> Litmus<(RA, RB, RC)>
+where
+    A: Fn(&Shared) -> RA + Sync + Send + 'static,
+    B: Fn(&Shared) -> RB + Sync + Send + 'static,
+    C: Fn(&Shared) -> RC + Sync + Send + 'static,
+    RA: Send + 'static,
+    RB: Send + 'static,
+    RC: 'static,
+{
+    let a = Arc::new(a);
+    let b = Arc::new(b);
+
+    Litmus {
+        run: Arc::new(move || {
+            let shared = Arc::new(Shared::new());
+
+            let th_a = spawn(&a, &shared);
+            let th_b = spawn(&b, &shared);
+            let rc = c(&shared);
+
+            (th_a.join().unwrap(), th_b.join().unwrap(), rc)
+        }),
+    }
+}
+
+fn spawn<F, R>(f: &Arc<F>, shared: &Arc<Shared>) -> thread::JoinHandle<R>
+where
+    F: Fn(&Shared) -> R + Sync + Send + 'static,
+    R: Send + 'static,
+{
+    let f = f.clone();
+    let shared = shared.clone();
+
+    thread::spawn(move || f(&shared))
+}
+
+impl Shared {
+    fn new() -> Shared {
+        Shared {
+            x: AtomicUsize::new(0),
+            y: AtomicUsize::new(0),
+            z: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl<O> Litmus<O>
+where
+    O: Ord + fmt::Debug + Send + Sync + 'static,
+{
+    /// Checks the litmus test, returning the outcomes observed across all
+    /// executions.
+    pub fn outcomes(self) -> BTreeSet<O> {
+        let run = self.run;
+        crate::model_outcomes(move || run())
+    }
+
+    /// Checks that the outcomes observed across all executions are exactly
+    /// `allowed`.
+    ///
+    /// An execution observing an outcome that is not allowed fails like any
+    /// other assertion of the model. Once all executions are explored, every
+    /// allowed outcome must have been observed by at least one of them.
+    pub fn allowed_outcomes<I>(self, allowed: I) -> BTreeSet<O>
+    where
+        I: IntoIterator<Item = O>,
+    {
+        let allowed: Arc<BTreeSet<O>> = Arc::new(allowed.into_iter().collect());
+        let run = self.run;
+
+        let observed = {
+            let allowed = allowed.clone();
+
+            crate::model_outcomes(move || {
+                let outcome = run();
+
+                assert!(
+                    allowed.contains(&outcome),
+                    "litmus test observed outcome {:?}, which is not allowed",
+                    outcome
+                );
+
+                outcome
+            })
+        };
+
+        let missing: Vec<_> = allowed.difference(&observed).collect();
+
+        assert!(
+            missing.is_empty(),
+            "litmus test never observed allowed outcomes {:?}; observed outcomes: {:?}",
+            missing,
+            observed
+        );
+
+        observed
+    }
+}
+
+impl<O> fmt::Debug for Litmus<O> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.pad("Litmus { .. }")
+    }
+}
+
+#[doc(hidden)]
+pub type __Thread = Box<dyn FnOnce() -> Vec<usize> + Send>;
+
+#[doc(hidden)]
+pub fn __registers<R: Registers>(registers: R) -> Vec<usize> {
+    let mut dst = vec![];
+    registers.push_to(&mut dst);
+    dst
+}
+
+/// Checks a litmus test, returning all observed outcomes.
+///
+/// `f` creates the test's threads. The last thread runs on the main thread,
+/// all others are spawned.
+#[doc(hidden)]
+pub fn __check<F>(f: F, forbid: Vec<Vec<usize>>, expect: Vec<Vec<usize>>) -> BTreeSet<Vec<usize>>
+where
+    F: Fn() -> Vec<__Thread> + Sync + Send + 'static,
+{
+    let observed = crate::model_outcomes(move || {
+        let mut threads = f();
+        let main = threads
+            .pop()
+            .expect("a litmus test requires at least one thread");
+
+        let handles: Vec<_> = threads.into_iter().map(crate::thread::spawn).collect();
+        let last = main();
+
+        let mut outcome = vec![];
+
+        for handle in handles {
+            outcome.extend(handle.join().unwrap());
+        }
+
+        outcome.extend(last);
+
+        assert!(
+            !forbid.contains(&outcome),
+            "litmus test observed forbidden outcome {:?}",
+            outcome
+        );
+
+        outcome
+    });
+
+    for outcome in &expect {
+        assert!(
+            observed.contains(outcome),
+            "litmus test never observed expected outcome {:?}; observed outcomes: {:?}",
+            outcome,
+            observed
+        );
+    }
+
+    observed
+}
@@ -1,20 +1,176 @@
 //! Model concurrent programs.
 
-use crate::rt::{self, Execution, Scheduler};
+use crate::explore::{Coverage, ExplorationTree};
+use crate::observe::{BranchKind, ExecutionObserver, Observer};
+use crate::rt::{self, Bound, Choice, Execution, Scheduler};
+use std::any::Any;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt;
+use std::mem::{self, ManuallyDrop};
+use std::panic::{self, AssertUnwindSafe};
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 const DEFAULT_MAX_THREADS: usize = 4;
 const DEFAULT_MAX_BRANCHES: usize = 1_000;
+const DEFAULT_FIBER_STACK_SIZE: usize = 0x1000 * mem::size_of::<usize>();
+const DEFAULT_OS_THREAD_STACK_SIZE: usize = 2 * 1024 * 1024;
+
+/// What to do when an execution exceeds
+/// [`max_branches`](Builder::max_branches).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BranchLimit {
+    /// Fail the check. This is the default.
+    Panic,
+
+    /// Finish the execution without exploring its remaining branches, and
+    /// print a warning once the check completes. The check is incomplete.
+    Warn,
+
+    /// Double the limit and keep exploring.
+    Grow,
+}
+
+/// Order in which the options of each branch point are explored.
+///
+/// Every order explores the same executions, only the order they are reached
+/// in changes. Rotating through orders across runs finds bugs that sit late
+/// in the default order sooner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "checkpoint", derive(serde::Serialize, serde::Deserialize))]
+pub enum Order {
+    /// Explore options in the order they are discovered. This is the default.
+    Forward,
+
+    /// Explore options in the reverse order.
+    Reverse,
+
+    /// Explore options in an order shuffled by the given seed. The same seed
+    /// always produces the same order.
+    RandomizedSeed(u64),
+}
+
+/// What happens to the spawned threads still running when the model closure
+/// returns.
+///
+/// With `std`, returning from `main` exits the process and terminates the
+/// threads still running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MainExit {
+    /// Keep running the threads until they complete. This is the default.
+    Wait,
+
+    /// Fail the execution, listing the threads still running. Every thread
+    /// must be joined before the model closure returns.
+    RequireJoined,
+
+    /// Terminate the threads, like the process exiting would. Each thread is
+    /// unwound the next time it is scheduled, so its destructors still run
+    /// and leak checking keeps working.
+    Terminate,
+}
+
+/// Memory model under which atomic accesses are checked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryModel {
+    /// Every atomic access is checked as if it were `SeqCst`, whatever its
+    /// ordering. Loads always read the latest store, so only
+    /// thread interleavings are explored.
+    ///
+    /// Much faster, this finds the logical races of a model, but misses every
+    /// bug caused by a weaker ordering: a model passing under `SeqCst` must
+    /// still be checked under [`Weak`](MemoryModel::Weak).
+    SeqCst,
+
+    /// Atomic accesses are checked with the ordering they are given: loads may
+    /// read older stores, as the C++20 memory model allows. This is the
+    /// default.
+    Weak,
+}
+
+/// How [`yield_now`](crate::thread::yield_now) is modeled.
+///
+/// A yield is a hint, the scheduler may run the yielding thread again right
+/// away. Modeling it that way is sound but spin loops never make progress:
+/// every iteration is another branch point. The other modes assume the
+/// scheduler lets other threads run, and miss the executions that do not.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum YieldMode {
+    /// The yielding thread does not run again until another thread made a
+    /// step, unless no other thread can run, and its loads no longer return
+    /// the stores it observed before yielding. Alternative schedules are
+    /// explored from the thread that runs instead. Past
+    /// [`max_yields`](Builder::max_yields), the thread is parked. This is the
+    /// default.
+    ///
+    /// Executions where the yielding thread keeps running, or keeps reading
+    /// a stale value, are not explored.
+    Heuristic,
+
+    /// The yield is an ordinary branch point: the yielding thread stays
+    /// runnable and keeps running by default, switching to any other thread
+    /// is explored as a preemption.
+    ///
+    /// Explores every execution, but a spin loop exceeds
+    /// [`max_branches`](Builder::max_branches) unless bounded, for example
+    /// with [`preemption_bound`](Builder::preemption_bound) or
+    /// [`weak_fairness`](Builder::weak_fairness).
+    /// [`max_yields`](Builder::max_yields) does not apply.
+    Preemption,
+
+    /// Like [`Heuristic`](YieldMode::Heuristic), but the yield is not a
+    /// branch point: the next runnable thread in thread id order, wrapping
+    /// around, runs.
+    ///
+    /// Explores the fewest executions and misses every execution where
+    /// another thread runs after the yield.
+    RoundRobin,
+}
+
+/// How the threads of a model are run.
+///
+/// Only one thread runs at a time either way, the runner only decides how
+/// switching between threads is implemented.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Runner {
+    /// Run the threads as generators, switching stacks on the thread checking
+    /// the model. This is the default.
+    Fibers {
+        /// Size of the stack of each thread, in bytes. Defaults to 32 KiB on
+        /// 64-bit platforms.
+        stack_size: usize,
+    },
+
+    /// Run each thread on its own OS thread, handing control from one to the
+    /// other. Slower than fibers, but works on platforms or with code that
+    /// does not support switching stacks.
+    OsThreads {
+        /// Size of the stack of each OS thread, in bytes.
+        stack_size: usize,
+    },
+}
+
+/// Encoding of the checkpoint file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckpointFormat {
+    /// Human readable JSON.
+    Json,
+
+    /// Compact binary encoding. Recommended for long runs, where JSON
+    /// checkpoints grow large.
+    Binary,
+}
 
 /// Configure a model
 #[derive(Debug)]
 pub struct Builder {
     /// Max number of threads to check as part of the execution.
     ///
-    /// This should be set as low as possible and must be less than
+    /// This should be set as low as possible and must be at most
     /// [`MAX_THREADS`](crate::MAX_THREADS).
+    ///
+    /// Defaults to `LOOM_MAX_THREADS` environment variable.
     pub max_threads: usize,
 
     /// Maximum number of thread switches per permutation.
@@ -22,11 +178,91 @@ pub struct Builder {
     /// Defaults to `LOOM_MAX_BRANCHES` environment variable.
     pub max_branches: usize,
 
+    /// What to do when an execution exceeds `max_branches`.
+    ///
+    /// Defaults to `LOOM_ON_BRANCH_LIMIT` environment variable, one of
+    /// `panic`, `warn` or `grow`.
+    pub on_branch_limit: BranchLimit,
+
+    /// Order in which the branches of the model are explored.
+    ///
+    /// Does not apply to [`random_schedules`](Builder::random_schedules). When
+    /// resuming from a checkpoint, the order the checkpoint was written with
+    /// is used instead.
+    ///
+    /// Defaults to `LOOM_EXPLORATION_ORDER` environment variable, one of
+    /// `forward`, `reverse` or a seed for a randomized order.
+    pub exploration_order: Order,
+
+    /// When `true`, schedules where a thread runs far ahead of the others are
+    /// explored first.
+    ///
+    /// At each thread switch, loom first tries the thread that ran the most so
+    /// far instead of following `exploration_order`. Like the exploration
+    /// order, this only changes the order executions are explored in.
+    ///
+    /// Defaults to existance of `LOOM_PREFER_UNFAIR_SCHEDULES` environment
+    /// variable.
+    pub prefer_unfair_schedules: bool,
+
+    /// When `true`, executions that exceed
+    /// [`max_branches`](Builder::max_branches) only because of an unfair
+    /// schedule are pruned.
+    ///
+    /// Loom explores unfair schedules, where an enabled thread is never
+    /// scheduled. A thread spinning until another one makes progress then
+    /// spins forever, and the execution exceeds the branch limit even though
+    /// any real scheduler eventually runs the other thread. With weak
+    /// fairness, an execution reaching the limit continues scheduling the
+    /// threads round-robin, without being explored further. If it completes,
+    /// it is pruned and counted in [`ExplorationStats::unfair_pruned`].
+    /// Otherwise, once it ran for `max_branches` more branches, the model is
+    /// reported as livelocking. This replaces
+    /// [`on_branch_limit`](Builder::on_branch_limit).
+    ///
+    /// Defaults to existance of `LOOM_WEAK_FAIRNESS` environment variable.
+    pub weak_fairness: bool,
+
+    /// What happens to the spawned threads still running when the model
+    /// closure returns.
+    ///
+    /// Defaults to `LOOM_MAIN_EXIT` environment variable, one of `wait`,
+    /// `require_joined` or `terminate`.
+    pub main_exit: MainExit,
+
+    /// Memory model under which atomic accesses are checked.
+    ///
+    /// Defaults to `LOOM_MEMORY_MODEL` environment variable, either `seq_cst`
+    /// or `weak`.
+    pub memory_model: MemoryModel,
+
+    /// How [`yield_now`](crate::thread::yield_now) is modeled.
+    ///
+    /// Defaults to `LOOM_YIELD_MODE` environment variable, one of
+    /// `heuristic`, `preemption` or `round_robin`.
+    pub yield_mode: YieldMode,
+
+    /// How the threads of the model are run.
+    ///
+    /// Defaults to `LOOM_RUNNER` environment variable, either `fibers` or
+    /// `os_threads`, with a stack size of `LOOM_STACK_SIZE` bytes.
+    pub runner: Runner,
+
     /// Maximum number of permutations to explore.
     ///
     /// Defaults to `LOOM_MAX_PERMUTATIONS` environment variable.
     pub max_permutations: Option<usize>,
 
+    /// When set, stop after exactly this many executions, even if the model
+    /// was not fully explored.
+    ///
+    /// Unlike [`max_permutations`](Builder::max_permutations), which is only
+    /// checked at each checkpoint interval, the limit is checked after every
+    /// execution. An exhaustive check explores the executions in the same
+    /// order every time, so this runs a fixed amount of work, which is useful
+    /// to benchmark loom itself.
+    pub max_iterations: Option<usize>,
+
     /// Maximum amount of time to spend on checking
     ///
     /// Defaults to `LOOM_MAX_DURATION` environment variable.
@@ -37,17 +273,109 @@ pub struct Builder {
     /// Defaults to `LOOM_MAX_PREEMPTIONS` environment variable.
     pub preemption_bound: Option<usize>,
 
-    /// When doing an exhaustive check, uses the file to store and load the
-    /// check progress
+    /// Maximum number of context switches to explore.
+    ///
+    /// Unlike [`preemption_bound`](Builder::preemption_bound), switching
+    /// threads when the running thread blocks, yields or terminates counts
+    /// against the bound. Once reached, executions only make the switches
+    /// they cannot avoid. At most one of the bounds can be set.
+    ///
+    /// Defaults to `LOOM_MAX_CONTEXT_SWITCHES` environment variable.
+    pub context_bound: Option<usize>,
+
+    /// Maximum number of delays to explore.
+    ///
+    /// A delay is a deviation from a default scheduler that keeps running the
+    /// current thread and otherwise runs threads round-robin: running another
+    /// thread costs the number of runnable threads skipped to reach it. At
+    /// most one of the bounds can be set.
+    ///
+    /// Defaults to `LOOM_MAX_DELAYS` environment variable.
+    pub delay_bound: Option<usize>,
+
+    /// Maximum number of yields of a thread, per execution, after which the
+    /// thread is parked when it yields.
+    ///
+    /// A thread spinning in a [`yield_now`](crate::thread::yield_now) loop
+    /// runs again as soon as another thread made a step, and every iteration
+    /// of the loop multiplies the schedules to explore and may exhaust
+    /// [`max_branches`](Builder::max_branches). Past the limit, a yielding
+    /// thread only runs again once no other thread can run, because they are
+    /// blocked, parked or terminated. This bounds long yield loops at the cost
+    /// of exploring fewer interleavings.
+    ///
+    /// Defaults to `LOOM_MAX_YIELDS` environment variable.
+    pub max_yields: Option<usize>,
+
+    /// When `true`, switching threads because the running thread yielded
+    /// counts against the [`preemption_bound`](Builder::preemption_bound).
+    ///
+    /// By default such switches are free, like switches made when the running
+    /// thread blocks, so a yield loop never runs out of preemptions. Counting
+    /// them stops exploring alternative schedules once a yield loop used the
+    /// bound up. Yields always count against the
+    /// [`context_bound`](Builder::context_bound).
+    ///
+    /// Defaults to existance of `LOOM_PREEMPTIVE_YIELDS` environment variable.
+    pub preemptive_yields: bool,
+
+    /// Uses the file to store and load the check progress.
+    ///
+    /// Along with the progress, the checkpoint stores the exploration
+    /// strategy: the kind of scheduling bound, the exploration order and, for
+    /// [`random_schedules`](Builder::random_schedules), the state of the random
+    /// generator. A resumed check continues with the strategy it was started
+    /// with. Random executions can only be resumed by a random check, and
+    /// exhaustive ones by an exhaustive check.
     ///
     /// Defaults to `LOOM_CHECKPOINT_FILE` environment variable.
     pub checkpoint_file: Option<PathBuf>,
 
+    /// Encoding used when writing the checkpoint file.
+    ///
+    /// When `None`, the binary encoding is used if the checkpoint file has a
+    /// `.bin` extension and JSON is used otherwise. Checkpoints are always
+    /// loaded in the format they were written in.
+    ///
+    /// Defaults to `LOOM_CHECKPOINT_FORMAT` environment variable (`json` or
+    /// `binary`).
+    pub checkpoint_format: Option<CheckpointFormat>,
+
+    /// When set, the [`Coverage`] of a successful check is written to this
+    /// file as JSON. Requires the `coverage` feature.
+    ///
+    /// Defaults to `LOOM_COVERAGE_FILE` environment variable.
+    pub coverage_file: Option<PathBuf>,
+
+    /// When `true`, the tree of the branch points explored is recorded and
+    /// returned in [`ExplorationStats::tree`], see
+    /// [`ExplorationTree`](crate::explore::ExplorationTree).
+    ///
+    /// Defaults to existance of `LOOM_RECORD_TREE` environment variable.
+    pub record_tree: bool,
+
+    /// When set, the tree of the branch points explored is recorded and
+    /// written to this file once the check completes, one line per branch
+    /// point.
+    ///
+    /// Defaults to `LOOM_TREE_FILE` environment variable.
+    pub tree_file: Option<PathBuf>,
+
     /// How often to write the checkpoint file
     ///
     /// Defaults to `LOOM_CHECKPOINT_INTERVAL` environment variable.
     pub checkpoint_interval: usize,
 
+    /// When `true`, the checkpoint file is also written when the check fails.
+    ///
+    /// Once the failure is fixed, the check then resumes at the failing
+    /// iteration instead of at the last checkpoint interval. Note that
+    /// interrupting the process (e.g. with Ctrl-C) does not give loom a chance
+    /// to write the checkpoint; use `max_duration` to bound long runs instead.
+    ///
+    /// Defaults to existance of `LOOM_CHECKPOINT_ON_EXIT` environment variable.
+    pub checkpoint_on_exit: bool,
+
     /// When `true`, locations are captured on each loom operation.
     ///
     /// Note that is is **very** expensive. It is recommended to first isolate a
@@ -59,13 +387,216 @@ pub struct Builder {
 
     /// Log execution output to stdout.
     ///
+    /// The events logged are selected by [`log_filter`](Builder::log_filter).
+    ///
     /// Defaults to existance of `LOOM_LOG` environment variable.
     pub log: bool,
 
+    /// Selects the events logged when [`log`](Builder::log) is set.
+    ///
+    /// A comma-separated list of directives, each either a level applying to
+    /// all targets, or `target=level` for a single target, such as
+    /// `debug,mutex=off`. The level of a target overrides the level applying
+    /// to all targets. The targets are `thread`,
+    /// `atomic`, `mutex`, `rwlock` and `condvar`. The levels are, from least to
+    /// most verbose:
+    ///
+    /// - `off`: nothing is logged.
+    /// - `info`: thread switches.
+    /// - `debug`: operations, such as atomic loads and stores or acquiring a
+    ///   lock.
+    /// - `trace`: the alternatives explored by the operations, such as the
+    ///   stores an atomic load may read.
+    ///
+    /// Defaults to the value of the `LOOM_LOG` environment variable, or
+    /// `info` if it is not a list of directives, such as `1` or `true`.
+    pub log_filter: String,
+
+    /// When `true`, only log the executions that fail.
+    ///
+    /// The events of each execution are buffered, and printed once the
+    /// execution fails. This requires [`log`](Builder::log).
+    ///
+    /// Defaults to existance of `LOOM_LOG_FAILING` environment variable.
+    pub log_failing: bool,
+
+    /// When `true`, the branch points explored are attributed to the objects
+    /// whose accesses caused them, and a table ranking the objects is printed
+    /// once the check completes. The ranking is also returned in
+    /// [`ExplorationStats::branch_profile`].
+    ///
+    /// The objects causing the most branches are the first to look at when
+    /// shrinking a model. Locations are captured to name the objects, which is
+    /// expensive, see [`location`](Builder::location).
+    ///
+    /// Defaults to existance of `LOOM_PROFILE_BRANCHES` environment variable.
+    pub profile_branches: bool,
+
+    /// When `true`, a failing execution is shrunk before being reported.
+    ///
+    /// Loom replays the failing execution while undoing one of its choices at
+    /// a time: a thread switch, the store an atomic load read or a spurious
+    /// failure. Changes that keep the model failing with the same message are
+    /// kept. The smallest failing execution found is printed and reported.
+    ///
+    /// Defaults to existance of `LOOM_MINIMIZE` environment variable.
+    pub minimize: bool,
+
+    /// When `true`, a failing execution is run once more with locations
+    /// captured, and the failure of that run is reported.
+    ///
+    /// Capturing locations on every operation of every execution is
+    /// expensive, see [`location`](Builder::location). Replaying only the
+    /// failing execution reports where the failing operations were made at
+    /// the cost of a single execution. This has no effect when `location` is
+    /// set, as the failing execution already captured them.
+    ///
+    /// Defaults to existance of `LOOM_LOCATE_FAILURES` environment variable.
+    pub locate_failures: bool,
+
+    /// When `true`, skip schedules that only differ from an explored one by
+    /// the order of independent operations, using sleep sets.
+    ///
+    /// Sleep sets complement the dynamic partial order reduction loom always
+    /// performs and can greatly reduce the number of executions. They are
+    /// only used when exploring exhaustively without a preemption bound.
+    /// Disabling them allows comparing the number of executions explored.
+    ///
+    /// Defaults to `true`, unless the `LOOM_NO_SLEEP_SETS` environment
+    /// variable is set.
+    pub sleep_sets: bool,
+
+    /// When `true`, check that the model is deterministic.
+    ///
+    /// Each execution replays the branch points of a previous execution
+    /// before exploring new ones. Loom records the state of the threads at
+    /// each branch point and panics if a replayed execution reaches a
+    /// different one, which happens when the model uses real randomness, real
+    /// time or state leaking between executions through a real static.
+    ///
+    /// Defaults to existance of `LOOM_CHECK_DETERMINISM` environment variable.
+    pub check_determinism: bool,
+
+    /// When `true`, check that executions do not leak state to each other.
+    ///
+    /// The first execution is run a second time, right after it completes,
+    /// and loom panics if it reaches different branch points, spawns a
+    /// different number of threads or creates different loom objects. This
+    /// catches state shared between executions that the model does not reset,
+    /// such as an `Arc<std::sync::Mutex<_>>` captured by the model closure or
+    /// a real static, which makes the exploration depend on the executions
+    /// that ran before.
+    ///
+    /// Defaults to existance of `LOOM_CHECK_ISOLATION` environment variable.
+    pub check_isolation: bool,
+
+    /// When `true`, every allocation made with [`loom::alloc`](crate::alloc)
+    /// may fail.
+    ///
+    /// Each allocation becomes a branch point: loom explores executions where
+    /// it succeeds and executions where it returns a null pointer.
+    ///
+    /// Defaults to existance of `LOOM_ALLOC_FAILURES` environment variable.
+    pub alloc_failures: bool,
+
+    /// When `true`, every thread spawned with
+    /// [`thread::Builder::spawn`](crate::thread::Builder::spawn) may fail to
+    /// spawn.
+    ///
+    /// Each spawn becomes a branch point: loom explores executions where the
+    /// thread is spawned and executions where an error is returned.
+    ///
+    /// Defaults to existance of `LOOM_SPAWN_FAILURES` environment variable.
+    pub spawn_failures: bool,
+
+    /// When `true`, a spawned thread may be killed at any of its branch
+    /// points, to check that the rest of the model copes with a thread
+    /// disappearing.
+    ///
+    /// Each branch point of a spawned thread branches on killing it, at most
+    /// one thread is killed per execution. The killed thread unwinds as if it
+    /// panicked: its locks are poisoned and joining it returns an error, with
+    /// a `&str` payload. Threads are not killed while dropping a loom `Arc` or
+    /// channel handle, as the unwinding would skip releasing it. A killed
+    /// thread that is never joined does not fail the execution, but the
+    /// objects it leaks or the threads left waiting on it do, and the report
+    /// names the killed thread.
+    ///
+    /// This multiplies the number of executions, use it on small models.
+    ///
+    /// Defaults to existance of `LOOM_INJECT_THREAD_FAILURE` environment
+    /// variable.
+    pub inject_thread_failure: bool,
+
+    /// When `true`, fail an execution in which a compare-and-exchange
+    /// succeeds although other threads changed the value it expected, and
+    /// changed it back, since the thread last observed it.
+    ///
+    /// This is the ABA problem of lock-free data structures: a pointer read
+    /// by a thread is popped, freed and a new node is pushed at the same
+    /// address, so the thread's compare-and-exchange succeeds with stale
+    /// assumptions. Detecting it flags designs susceptible to ABA even when
+    /// the execution does not cause memory unsafety. A thread's own stores
+    /// do not count, nor do stores the thread read in between.
+    ///
+    /// Defaults to existance of `LOOM_DETECT_ABA` environment variable.
+    pub detect_aba: bool,
+
+    /// When set, panic if an execution holds more than this many loom
+    /// objects (atomics, mutexes, arcs, ...) at once. Atomics, mutexes,
+    /// rwlocks and condvars dropped by the model are released and no longer
+    /// count, unless they may still affect the execution.
+    ///
+    /// Defaults to `LOOM_MAX_OBJECTS` environment variable.
+    pub max_objects: Option<usize>,
+
+    /// When set, panic if an execution holds more than this many bytes of
+    /// allocations made with [`loom::alloc`](crate::alloc) at once.
+    ///
+    /// Defaults to `LOOM_MAX_ALLOC_BYTES` environment variable.
+    pub max_alloc_bytes: Option<usize>,
+
+    /// When set, run this many randomly scheduled executions instead of
+    /// exhaustively checking the model.
+    ///
+    /// Scheduling decisions and the values returned by atomic loads are picked
+    /// at random, seeded by [`random_seed`](Builder::random_seed). This does
+    /// not provide the guarantees of an exhaustive check, but is a practical
+    /// middle ground for models too large to check exhaustively.
+    ///
+    /// Defaults to `LOOM_RANDOM_SCHEDULES` environment variable.
+    pub random_schedules: Option<usize>,
+
+    /// Seed used to pick the random executions.
+    ///
+    /// When a random execution fails, the seed of that execution is printed.
+    /// Running a single random execution with that seed replays the failing
+    /// execution exactly.
+    ///
+    /// Defaults to `LOOM_RANDOM_SEED` environment variable, or a seed derived
+    /// from the current time.
+    pub random_seed: u64,
+
+    /// Invoked after each execution of the model.
+    after_execution: Option<AfterExecution>,
+
+    /// Coverage marks that must be hit by at least one execution.
+    required_marks: Vec<String>,
+
+    /// Told about the events of each execution.
+    observer: Option<Observer>,
+
     // Support adding more fields in the future
     _p: (),
 }
 
+struct AfterExecution(Box<dyn Fn(&ExecutionSummary) + Send + Sync>);
+
+// Keep `Builder` unwind safe. The hook is only invoked between executions and
+// is told when an execution panicked.
+impl panic::UnwindSafe for AfterExecution {}
+impl panic::RefUnwindSafe for AfterExecution {}
+
 impl Builder {
     /// Create a new `Builder` instance with default values.
     pub fn new() -> Builder {
@@ -79,6 +610,14 @@ impl Builder {
             })
             .unwrap_or(20_000);
 
+        let max_threads = match env::var("LOOM_MAX_THREADS") {
+            Ok(v) => match v.parse() {
+                Ok(max) => max,
+                Err(_) => panic!("invalid value for `LOOM_MAX_THREADS`"),
+            },
+            Err(_) => DEFAULT_MAX_THREADS,
+        };
+
         let max_branches = env::var("LOOM_MAX_BRANCHES")
             .map(|v| {
                 v.parse()
@@ -87,10 +626,123 @@ impl Builder {
             })
             .unwrap_or(DEFAULT_MAX_BRANCHES);
 
+        let on_branch_limit = env::var("LOOM_ON_BRANCH_LIMIT")
+            .map(|v| match &v[..] {
+                "panic" => BranchLimit::Panic,
+                "warn" => BranchLimit::Warn,
+                "grow" => BranchLimit::Grow,
+                _ => panic!("invalid value for `LOOM_ON_BRANCH_LIMIT`"),
+            })
+            .unwrap_or(BranchLimit::Panic);
+
+        let exploration_order = env::var("LOOM_EXPLORATION_ORDER")
+            .map(|v| match &v[..] {
+                "forward" => Order::Forward,
+                "reverse" => Order::Reverse,
+                seed => match seed.parse() {
+                    Ok(seed) => Order::RandomizedSeed(seed),
+                    Err(_) => panic!("invalid value for `LOOM_EXPLORATION_ORDER`"),
+                },
+            })
+            .unwrap_or(Order::Forward);
+
+        let prefer_unfair_schedules = env::var("LOOM_PREFER_UNFAIR_SCHEDULES").is_ok();
+
+        let main_exit = env::var("LOOM_MAIN_EXIT")
+            .map(|v| match &v[..] {
+                "wait" => MainExit::Wait,
+                "require_joined" => MainExit::RequireJoined,
+                "terminate" => MainExit::Terminate,
+                _ => panic!("invalid value for `LOOM_MAIN_EXIT`"),
+            })
+            .unwrap_or(MainExit::Wait);
+
+        let memory_model = env::var("LOOM_MEMORY_MODEL")
+            .map(|v| match &v[..] {
+                "seq_cst" => MemoryModel::SeqCst,
+                "weak" => MemoryModel::Weak,
+                _ => panic!("invalid value for `LOOM_MEMORY_MODEL`"),
+            })
+            .unwrap_or(MemoryModel::Weak);
+
+        let yield_mode = env::var("LOOM_YIELD_MODE")
+            .map(|v| match &v[..] {
+                "heuristic" => YieldMode::Heuristic,
+                "preemption" => YieldMode::Preemption,
+                "round_robin" => YieldMode::RoundRobin,
+                _ => panic!("invalid value for `LOOM_YIELD_MODE`"),
+            })
+            .unwrap_or(YieldMode::Heuristic);
+
+        let stack_size = match env::var("LOOM_STACK_SIZE") {
+            Ok(v) => match v.parse() {
+                Ok(size) => Some(size),
+                Err(_) => panic!("invalid value for `LOOM_STACK_SIZE`"),
+            },
+            Err(_) => None,
+        };
+
+        let runner = match env::var("LOOM_RUNNER").as_deref() {
+            Ok("fibers") | Err(_) => Runner::Fibers {
+                stack_size: stack_size.unwrap_or(DEFAULT_FIBER_STACK_SIZE),
+            },
+            Ok("os_threads") => Runner::OsThreads {
+                stack_size: stack_size.unwrap_or(DEFAULT_OS_THREAD_STACK_SIZE),
+            },
+            Ok(_) => panic!("invalid value for `LOOM_RUNNER`"),
+        };
+
+        let checkpoint_on_exit = env::var("LOOM_CHECKPOINT_ON_EXIT").is_ok();
+
         let location = env::var("LOOM_LOCATION").is_ok();
 
         let log = env::var("LOOM_LOG").is_ok();
 
+        let log_filter = match env::var("LOOM_LOG") {
+            Ok(v) => rt::log::env_directives(&v),
+            Err(_) => "info".to_string(),
+        };
+
+        let log_failing = env::var("LOOM_LOG_FAILING").is_ok();
+
+        let profile_branches = env::var("LOOM_PROFILE_BRANCHES").is_ok();
+
+        let minimize = env::var("LOOM_MINIMIZE").is_ok();
+
+        let check_determinism = env::var("LOOM_CHECK_DETERMINISM").is_ok();
+
+        let check_isolation = env::var("LOOM_CHECK_ISOLATION").is_ok();
+
+        let locate_failures = env::var("LOOM_LOCATE_FAILURES").is_ok();
+
+        let sleep_sets = env::var("LOOM_NO_SLEEP_SETS").is_err();
+
+        let alloc_failures = env::var("LOOM_ALLOC_FAILURES").is_ok();
+
+        let spawn_failures = env::var("LOOM_SPAWN_FAILURES").is_ok();
+
+        let inject_thread_failure = env::var("LOOM_INJECT_THREAD_FAILURE").is_ok();
+
+        let detect_aba = env::var("LOOM_DETECT_ABA").is_ok();
+
+        let weak_fairness = env::var("LOOM_WEAK_FAIRNESS").is_ok();
+
+        let max_objects = match env::var("LOOM_MAX_OBJECTS") {
+            Ok(v) => match v.parse() {
+                Ok(max) => Some(max),
+                Err(_) => panic!("invalid value for `LOOM_MAX_OBJECTS`"),
+            },
+            Err(_) => None,
+        };
+
+        let max_alloc_bytes = match env::var("LOOM_MAX_ALLOC_BYTES") {
+            Ok(v) => match v.parse() {
+                Ok(max) => Some(max),
+                Err(_) => panic!("invalid value for `LOOM_MAX_ALLOC_BYTES`"),
+            },
+            Err(_) => None,
+        };
+
         let max_duration = env::var("LOOM_MAX_DURATION")
             .map(|v| {
                 let secs = v
@@ -117,6 +769,32 @@ impl Builder {
             })
             .ok();
 
+        let context_bound = match env::var("LOOM_MAX_CONTEXT_SWITCHES") {
+            Ok(v) => match v.parse() {
+                Ok(max) => Some(max),
+                Err(_) => panic!("invalid value for `LOOM_MAX_CONTEXT_SWITCHES`"),
+            },
+            Err(_) => None,
+        };
+
+        let delay_bound = match env::var("LOOM_MAX_DELAYS") {
+            Ok(v) => match v.parse() {
+                Ok(max) => Some(max),
+                Err(_) => panic!("invalid value for `LOOM_MAX_DELAYS`"),
+            },
+            Err(_) => None,
+        };
+
+        let max_yields = match env::var("LOOM_MAX_YIELDS") {
+            Ok(v) => match v.parse() {
+                Ok(max) => Some(max),
+                Err(_) => panic!("invalid value for `LOOM_MAX_YIELDS`"),
+            },
+            Err(_) => None,
+        };
+
+        let preemptive_yields = env::var("LOOM_PREEMPTIVE_YIELDS").is_ok();
+
         let checkpoint_file = env::var("LOOM_CHECKPOINT_FILE")
             .map(|v| {
                 v.parse()
@@ -125,98 +803,2076 @@ impl Builder {
             })
             .ok();
 
+        let checkpoint_format = env::var("LOOM_CHECKPOINT_FORMAT")
+            .map(|v| match &v[..] {
+                "json" => CheckpointFormat::Json,
+                "binary" => CheckpointFormat::Binary,
+                _ => panic!("invalid value for `LOOM_CHECKPOINT_FORMAT`"),
+            })
+            .ok();
+
+        let coverage_file = env::var_os("LOOM_COVERAGE_FILE").map(PathBuf::from);
+
+        let record_tree = env::var("LOOM_RECORD_TREE").is_ok();
+
+        let tree_file = env::var_os("LOOM_TREE_FILE").map(PathBuf::from);
+
+        let random_schedules = env::var("LOOM_RANDOM_SCHEDULES")
+            .map(|v| {
+                v.parse()
+                    .ok()
+                    .expect("invalid value for `LOOM_RANDOM_SCHEDULES`")
+            })
+            .ok();
+
+        let random_seed = env::var("LOOM_RANDOM_SEED")
+            .map(|v| {
+                v.parse()
+                    .ok()
+                    .expect("invalid value for `LOOM_RANDOM_SEED`")
+            })
+            .unwrap_or_else(|_| {
+                use std::time::SystemTime;
+
+                SystemTime::now()
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .map(|d| d.as_nanos() as u64)
+                    .unwrap_or(0)
+            });
+
         Builder {
-            max_threads: DEFAULT_MAX_THREADS,
+            max_threads,
             max_branches,
+            on_branch_limit,
+            exploration_order,
+            prefer_unfair_schedules,
+            weak_fairness,
+            main_exit,
+            memory_model,
+            yield_mode,
+            runner,
             max_duration,
             max_permutations,
+            max_iterations: None,
             preemption_bound,
+            context_bound,
+            delay_bound,
+            max_yields,
+            preemptive_yields,
             checkpoint_file,
+            checkpoint_format,
+            coverage_file,
+            record_tree,
+            tree_file,
             checkpoint_interval,
+            checkpoint_on_exit,
             location,
             log,
+            log_filter,
+            log_failing,
+            profile_branches,
+            minimize,
+            locate_failures,
+            sleep_sets,
+            check_determinism,
+            check_isolation,
+            alloc_failures,
+            spawn_failures,
+            inject_thread_failure,
+            detect_aba,
+            max_objects,
+            max_alloc_bytes,
+            random_schedules,
+            random_seed,
+            after_execution: None,
+            observer: None,
+            required_marks: vec![],
             _p: (),
         }
     }
 
-    /// Set the checkpoint file.
-    pub fn checkpoint_file(&mut self, file: &str) -> &mut Self {
-        self.checkpoint_file = Some(file.into());
+    /// Set what to do when an execution exceeds `max_branches`.
+    pub fn on_branch_limit(&mut self, limit: BranchLimit) -> &mut Self {
+        self.on_branch_limit = limit;
         self
     }
 
-    /// Check the provided model.
-    pub fn check<F>(&self, f: F)
-    where
-        F: Fn() + Sync + Send + 'static,
-    {
-        let mut execution =
-            Execution::new(self.max_threads, self.max_branches, self.preemption_bound);
-        let mut scheduler = Scheduler::new(self.max_threads);
+    /// Set the order in which the branches of the model are explored.
+    pub fn exploration_order(&mut self, order: Order) -> &mut Self {
+        self.exploration_order = order;
+        self
+    }
 
-        if let Some(ref path) = self.checkpoint_file {
-            if path.exists() {
-                execution.path = checkpoint::load_execution_path(path);
-                execution.path.set_max_branches(self.max_branches);
-            }
-        }
+    /// Bound the exploration by the number of context switches, see
+    /// [`context_bound`](Builder::context_bound).
+    pub fn context_bound(&mut self, bound: usize) -> &mut Self {
+        self.context_bound = Some(bound);
+        self
+    }
 
-        execution.log = self.log;
-        execution.location = self.location;
+    /// Bound the exploration by the number of delays, see
+    /// [`delay_bound`](Builder::delay_bound).
+    pub fn delay_bound(&mut self, bound: usize) -> &mut Self {
+        self.delay_bound = Some(bound);
+        self
+    }
 
-        let f = Arc::new(f);
+    /// Explore schedules where a thread runs far ahead of the others first.
+    pub fn prefer_unfair_schedules(&mut self, prefer: bool) -> &mut Self {
+        self.prefer_unfair_schedules = prefer;
+        self
+    }
 
-        let mut i = 0;
+    /// Prune executions exceeding the branch limit because of an unfair
+    /// schedule, see [`weak_fairness`](Builder::weak_fairness).
+    pub fn weak_fairness(&mut self, weak_fairness: bool) -> &mut Self {
+        self.weak_fairness = weak_fairness;
+        self
+    }
 
-        let start = Instant::now();
+    /// Kill a spawned thread at one of its branch points, see
+    /// [`inject_thread_failure`](Builder::inject_thread_failure).
+    pub fn inject_thread_failure(&mut self, inject: bool) -> &mut Self {
+        self.inject_thread_failure = inject;
+        self
+    }
 
-        loop {
-            i += 1;
+    /// Fail executions in which a compare-and-exchange succeeds after an ABA,
+    /// see [`detect_aba`](Builder::detect_aba).
+    pub fn detect_aba(&mut self, detect: bool) -> &mut Self {
+        self.detect_aba = detect;
+        self
+    }
 
-            if i % self.checkpoint_interval == 0 {
-                println!("");
-                println!(" ================== Iteration {} ==================", i);
-                println!("");
+    /// Set what happens to the spawned threads still running when the model
+    /// closure returns.
+    pub fn main_exit(&mut self, main_exit: MainExit) -> &mut Self {
+        self.main_exit = main_exit;
+        self
+    }
 
-                if let Some(ref path) = self.checkpoint_file {
-                    checkpoint::store_execution_path(&execution.path, path);
-                }
+    /// Set the memory model under which atomic accesses are checked.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use loom::model::{Builder, MemoryModel};
+    /// use loom::sync::atomic::AtomicUsize;
+    /// use loom::sync::Arc;
+    /// use loom::thread;
+    ///
+    /// use std::sync::atomic::Ordering::Relaxed;
+    ///
+    /// let mut builder = Builder::new();
+    /// builder.memory_model(MemoryModel::SeqCst);
+    ///
+    /// builder.check(|| {
+    ///     let data = Arc::new(AtomicUsize::new(0));
+    ///     let flag = Arc::new(AtomicUsize::new(0));
+    ///
+    ///     let th = {
+    ///         let (data, flag) = (data.clone(), flag.clone());
+    ///         thread::spawn(move || {
+    ///             data.store(1, Relaxed);
+    ///             flag.store(1, Relaxed);
+    ///         })
+    ///     };
+    ///
+    ///     // Only holds under sequential consistency.
+    ///     if flag.load(Relaxed) == 1 {
+    ///         assert_eq!(data.load(Relaxed), 1);
+    ///     }
+    ///
+    ///     th.join().unwrap();
+    /// });
+    /// ```
+    pub fn memory_model(&mut self, memory_model: MemoryModel) -> &mut Self {
+        self.memory_model = memory_model;
+        self
+    }
 
-                if let Some(max_permutations) = self.max_permutations {
-                    if i >= max_permutations {
-                        return;
-                    }
-                }
+    /// Set how [`yield_now`](crate::thread::yield_now) is modeled.
+    pub fn yield_mode(&mut self, yield_mode: YieldMode) -> &mut Self {
+        self.yield_mode = yield_mode;
+        self
+    }
 
-                if let Some(max_duration) = self.max_duration {
-                    if start.elapsed() >= max_duration {
-                        return;
-                    }
-                }
-            }
+    /// Set how the threads of the model are run.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use loom::model::{Builder, Runner};
+    ///
+    /// let mut builder = Builder::new();
+    /// builder.runner(Runner::OsThreads {
+    ///     stack_size: 8 * 1024 * 1024,
+    /// });
+    ///
+    /// builder.check(|| {
+    ///     loom::thread::spawn(|| {}).join().unwrap();
+    /// });
+    /// ```
+    pub fn runner(&mut self, runner: Runner) -> &mut Self {
+        self.runner = runner;
+        self
+    }
 
-            let f = f.clone();
+    /// Set the checkpoint file.
+    pub fn checkpoint_file(&mut self, file: &str) -> &mut Self {
+        self.checkpoint_file = Some(file.into());
+        self
+    }
+
+    /// Set the encoding used to write the checkpoint file.
+    pub fn checkpoint_format(&mut self, format: CheckpointFormat) -> &mut Self {
+        self.checkpoint_format = Some(format);
+        self
+    }
+
+    /// Set the file the coverage of the check is written to.
+    pub fn coverage_file(&mut self, file: &str) -> &mut Self {
+        self.coverage_file = Some(file.into());
+        self
+    }
+
+    /// Set the file the tree of the branch points explored is written to.
+    pub fn tree_file(&mut self, file: &str) -> &mut Self {
+        self.tree_file = Some(file.into());
+        self
+    }
+
+    /// Write the checkpoint file when the check fails.
+    pub fn checkpoint_on_exit(&mut self, enabled: bool) -> &mut Self {
+        self.checkpoint_on_exit = enabled;
+        self
+    }
+
+    /// Invoke `f` after each execution of the model, including an execution
+    /// that fails.
+    ///
+    /// This can be used to check custom invariants or to aggregate statistics
+    /// across executions.
+    pub fn after_execution<F>(&mut self, f: F) -> &mut Self
+    where
+        F: Fn(&ExecutionSummary) + Send + Sync + 'static,
+    {
+        self.after_execution = Some(AfterExecution(Box::new(f)));
+        self
+    }
+
+    /// Report the events of each execution of the model to `observer`, see
+    /// [`observe`](crate::observe).
+    ///
+    /// The executions replayed to minimize or locate a failure are not
+    /// reported.
+    pub fn instrument(&mut self, observer: Box<dyn ExecutionObserver + Send>) -> &mut Self {
+        self.observer = Some(Observer::new(observer));
+        self
+    }
+
+    /// Fail the check if any of the coverage `marks` is not hit by at least
+    /// one execution, see [`explore::mark`](crate::explore::mark).
+    ///
+    /// This guards against vacuous models, which never explore the scenario
+    /// they are meant to check. Calling it again adds to the required marks.
+    pub fn require_marks<I>(&mut self, marks: I) -> &mut Self
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        self.required_marks
+            .extend(marks.into_iter().map(|mark| mark.as_ref().to_string()));
+        self
+    }
+
+    /// Run `n` randomly scheduled executions, seeded with `seed`, instead of
+    /// exhaustively checking the model.
+    pub fn random_schedules(&mut self, n: usize, seed: u64) -> &mut Self {
+        self.random_schedules = Some(n);
+        self.random_seed = seed;
+        self
+    }
+
+    /// Check the provided model.
+    pub fn check<F>(&self, f: F)
+    where
+        F: Fn() + Sync + Send + 'static,
+    {
+        self.check_returning_stats(f);
+    }
+
+    /// Check the provided model once for each of the `inputs`, in order.
+    ///
+    /// Checking stops at the first input the model fails for, which is
+    /// printed along with the failure. If `minimize` is set, the schedule of
+    /// the failing execution is minimized, see
+    /// [`check_each_shrinking`](Builder::check_each_shrinking) to also shrink
+    /// the input.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use loom::model::Builder;
+    /// use loom::sync::atomic::AtomicUsize;
+    /// use loom::thread;
+    ///
+    /// use std::sync::atomic::Ordering::SeqCst;
+    /// use std::sync::Arc;
+    ///
+    /// Builder::new().check_each(vec![1, 2, 3], |&n| {
+    ///     let num = Arc::new(AtomicUsize::new(0));
+    ///     let num2 = num.clone();
+    ///
+    ///     let th = thread::spawn(move || num2.fetch_add(n, SeqCst));
+    ///     num.fetch_add(n, SeqCst);
+    ///
+    ///     th.join().unwrap();
+    ///     assert_eq!(2 * n, num.load(SeqCst));
+    /// });
+    /// ```
+    pub fn check_each<I, T, F>(&self, inputs: I, f: F)
+    where
+        I: IntoIterator<Item = T>,
+        T: fmt::Debug + Sync + Send + 'static,
+        F: Fn(&T) + Sync + Send + 'static,
+    {
+        let f = Arc::new(f);
+
+        for input in inputs {
+            let input = Arc::new(input);
+
+            // Report the input if the model fails.
+            let _report = ReportInput(&*input);
+
+            let f = f.clone();
+            let input = input.clone();
+
+            self.check(move || f(&input));
+        }
+    }
+
+    /// Check the provided model once for each of the `inputs`, shrinking the
+    /// first input the model fails for.
+    ///
+    /// The inputs are usually generated randomly, for example with
+    /// `quickcheck`'s `Arbitrary::arbitrary` or by sampling a `proptest`
+    /// strategy. Once the model fails for an input, `shrink` is called with it
+    /// and the model is checked for each of the simpler inputs it returns, in
+    /// order. The first of them the model also fails for replaces the input,
+    /// until none does. `quickcheck`'s `Arbitrary::shrink` can be passed
+    /// directly: `|input| input.shrink()`.
+    ///
+    /// The model is then checked once more for the shrunk input, which is
+    /// printed along with the failure. If `minimize` is set, the schedule of
+    /// that failure is minimized as well.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use loom::model::Builder;
+    /// use loom::sync::atomic::AtomicUsize;
+    /// use loom::thread;
+    ///
+    /// use std::sync::atomic::Ordering::SeqCst;
+    /// use std::sync::Arc;
+    ///
+    /// let shrink = |&n: &usize| vec![n / 2, n.saturating_sub(1)];
+    ///
+    /// Builder::new().check_each_shrinking(vec![3, 20], shrink, |&n| {
+    ///     let num = Arc::new(AtomicUsize::new(0));
+    ///     let num2 = num.clone();
+    ///
+    ///     let th = thread::spawn(move || num2.fetch_add(n, SeqCst));
+    ///     num.fetch_add(n, SeqCst);
+    ///
+    ///     th.join().unwrap();
+    ///     assert_eq!(2 * n, num.load(SeqCst));
+    /// });
+    /// ```
+    pub fn check_each_shrinking<I, T, S, C, F>(&self, inputs: I, shrink: S, f: F)
+    where
+        I: IntoIterator<Item = T>,
+        T: fmt::Debug + Sync + Send + 'static,
+        S: Fn(&T) -> C,
+        C: IntoIterator<Item = T>,
+        F: Fn(&T) + Sync + Send + 'static,
+    {
+        let f = Arc::new(f);
+
+        let fails = |input: &Arc<T>| {
+            let f = f.clone();
+            let input = input.clone();
+
+            self.try_explore(move || f(&input), &mut || {}, false)
+                .is_err()
+        };
+
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(|_| {}));
+
+        let failing = inputs.into_iter().map(Arc::new).find(|input| fails(input));
+
+        let mut failing = match failing {
+            Some(failing) => failing,
+            None => {
+                panic::set_hook(hook);
+                return;
+            }
+        };
+
+        let original = format!("{:?}", failing);
+        let mut runs = 0;
+
+        'shrink: while runs < MAX_SHRINK_RUNS {
+            for candidate in shrink(&failing) {
+                let candidate = Arc::new(candidate);
+                runs += 1;
+
+                if fails(&candidate) {
+                    failing = candidate;
+                    continue 'shrink;
+                }
+
+                if runs >= MAX_SHRINK_RUNS {
+                    break;
+                }
+            }
+
+            break;
+        }
+
+        panic::set_hook(hook);
+
+        println!(
+            "shrunk failing input from {} to {:?} in {} runs",
+            original, failing, runs
+        );
+
+        // Report the input if the model fails.
+        let _report = ReportInput(&*failing);
+
+        let input = failing.clone();
+        self.check(move || f(&input));
+    }
+
+    /// Check the provided model, running `setup` only once.
+    ///
+    /// The state returned by `setup` is captured along with the state of the
+    /// runtime once `setup` returns. The following executions start from
+    /// there: they receive a clone of the state instead of running `setup`
+    /// again. This saves running an expensive setup, such as building a data
+    /// structure, once per execution.
+    ///
+    /// `setup` runs on the main thread before any thread is spawned and must
+    /// not spawn threads, access thread locals or lazy statics. Objects
+    /// created by `setup` are not checked for leaks.
+    ///
+    /// **The state must be deep-copied by `S::clone`.** Loom restores its
+    /// runtime state, such as the values of atomics and which thread holds a
+    /// lock, but not the data stored outside of it: the contents of a
+    /// `Mutex`, an `RwLock`, an `UnsafeCell` or a `RefCell`. If `S::clone`
+    /// shares that data, as cloning an `Arc` does, each execution observes the
+    /// changes made by the previous ones, and the check reports bugs that do
+    /// not exist or misses real ones. Implement `Clone` to copy such data, as
+    /// in the second example below.
+    ///
+    /// If `setup` branches, for example by calling [`choose`] or taking a
+    /// spurious failure, `setup` runs again whenever the exploration moves to
+    /// another of its branches. With [`random_schedules`], `setup` runs again
+    /// unless it does not branch.
+    ///
+    /// [`random_schedules`]: Builder::random_schedules
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use loom::model::Builder;
+    /// use loom::sync::atomic::AtomicUsize;
+    /// use loom::thread;
+    ///
+    /// use std::sync::atomic::Ordering::SeqCst;
+    /// use std::sync::Arc;
+    ///
+    /// Builder::new().check_with_snapshot(
+    ///     || {
+    ///         let num = Arc::new(AtomicUsize::new(0));
+    ///         // Expensive setup...
+    ///         num.store(1, SeqCst);
+    ///         num
+    ///     },
+    ///     |num| {
+    ///         let num2 = num.clone();
+    ///
+    ///         let th = thread::spawn(move || num2.fetch_add(1, SeqCst));
+    ///         num.fetch_add(1, SeqCst);
+    ///
+    ///         th.join().unwrap();
+    ///         assert_eq!(3, num.load(SeqCst));
+    ///     },
+    /// );
+    /// ```
+    ///
+    /// The contents of a mutex are copied by the state's `Clone`
+    /// implementation, not shared with the snapshot:
+    ///
+    /// ```
+    /// use loom::model::Builder;
+    /// use loom::sync::{Arc, Mutex};
+    /// use loom::thread;
+    ///
+    /// struct State {
+    ///     values: Arc<Mutex<Vec<usize>>>,
+    /// }
+    ///
+    /// impl Clone for State {
+    ///     fn clone(&self) -> State {
+    ///         let values = self.values.lock().unwrap().clone();
+    ///
+    ///         State {
+    ///             values: Arc::new(Mutex::new(values)),
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// Builder::new().check_with_snapshot(
+    ///     || State {
+    ///         values: Arc::new(Mutex::new(vec![0])),
+    ///     },
+    ///     |state| {
+    ///         let values = state.values.clone();
+    ///
+    ///         let th = thread::spawn(move || values.lock().unwrap().push(1));
+    ///         state.values.lock().unwrap().push(2);
+    ///
+    ///         th.join().unwrap();
+    ///         assert_eq!(3, state.values.lock().unwrap().len());
+    ///     },
+    /// );
+    /// ```
+    pub fn check_with_snapshot<S, I, F>(&self, setup: I, f: F)
+    where
+        S: Clone + Send + 'static,
+        I: Fn() -> S + Sync + Send + 'static,
+        F: Fn(S) + Sync + Send + 'static,
+    {
+        // The state holds objects of the execution that took the snapshot. It
+        // is never dropped, as they cannot be dropped outside of an execution.
+        let snapshot = Arc::new(std::sync::Mutex::new(None::<ManuallyDrop<S>>));
+
+        self.explore(
+            move || {
+                let state = if rt::is_restored() {
+                    let snapshot = snapshot.lock().unwrap();
+                    S::clone(snapshot.as_ref().expect("missing snapshot"))
+                } else {
+                    let state = setup();
+                    rt::take_snapshot();
+
+                    let clone = state.clone();
+                    *snapshot.lock().unwrap() = Some(ManuallyDrop::new(state));
+                    clone
+                };
+
+                f(state);
+            },
+            &mut || {},
+        );
+    }
+
+    /// Check the provided model, returning the set of values it returned
+    /// across all executions.
+    pub fn check_outcomes<F, T>(&self, f: F) -> BTreeSet<T>
+    where
+        F: Fn() -> T + Sync + Send + 'static,
+        T: Ord + Send + 'static,
+    {
+        let outcomes = Arc::new(std::sync::Mutex::new(BTreeSet::new()));
+        let observed = outcomes.clone();
+
+        self.check(move || {
+            let outcome = f();
+            observed.lock().unwrap().insert(outcome);
+        });
+
+        let mut outcomes = outcomes.lock().unwrap();
+        std::mem::take(&mut *outcomes)
+    }
+
+    /// Check the provided model, passing the value it returned to `g` after
+    /// each execution.
+    ///
+    /// `g` runs on the thread checking the model, between executions, and
+    /// can therefore accumulate state across executions without resorting to
+    /// statics or locks.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use loom::model::Builder;
+    /// use loom::sync::atomic::AtomicUsize;
+    /// use loom::thread;
+    ///
+    /// use std::sync::atomic::Ordering::SeqCst;
+    /// use std::sync::Arc;
+    ///
+    /// let mut second = 0;
+    ///
+    /// Builder::new().check_mut(
+    ///     || {
+    ///         let num = Arc::new(AtomicUsize::new(0));
+    ///         let num2 = num.clone();
+    ///
+    ///         let th = thread::spawn(move || num2.fetch_add(1, SeqCst));
+    ///         let prev = num.fetch_add(1, SeqCst);
+    ///
+    ///         th.join().unwrap();
+    ///         prev
+    ///     },
+    ///     |prev| second += prev,
+    /// );
+    ///
+    /// // The spawned thread incremented first in some executions.
+    /// assert!(second > 0);
+    /// ```
+    pub fn check_mut<F, T, G>(&self, f: F, mut g: G)
+    where
+        F: Fn() -> T + Sync + Send + 'static,
+        T: Send + 'static,
+        G: FnMut(T),
+    {
+        let slot = Arc::new(std::sync::Mutex::new(None));
+        let returned = slot.clone();
+
+        self.explore(
+            move || {
+                let value = f();
+                *returned.lock().unwrap() = Some(value);
+            },
+            &mut || {
+                if let Some(value) = slot.lock().unwrap().take() {
+                    g(value);
+                }
+            },
+        );
+    }
+
+    /// Check the provided model, returning the value it returned.
+    ///
+    /// Every execution must return the same value, the check fails as soon as
+    /// an execution returns a different one. This catches results depending on
+    /// the schedule.
+    pub fn check_returning<F, T>(&self, f: F) -> T
+    where
+        F: Fn() -> T + Sync + Send + 'static,
+        T: PartialEq + fmt::Debug + Send + 'static,
+    {
+        let mut returned = None;
+
+        self.check_mut(f, |value| match &returned {
+            None => returned = Some(value),
+            Some(first) => assert!(
+                *first == value,
+                "model returned different values across executions: `{:?}` and `{:?}`",
+                first,
+                value,
+            ),
+        });
+
+        returned.expect("the model was not executed")
+    }
+
+    /// Check the provided model, returning statistics about the exploration.
+    pub fn check_returning_stats<F>(&self, f: F) -> ExplorationStats
+    where
+        F: Fn() + Sync + Send + 'static,
+    {
+        self.explore(f, &mut || {})
+    }
+
+    /// Check the provided model, expecting it to fail.
+    ///
+    /// Succeeds only if an execution panics with a message containing
+    /// `expected`, pass `""` to accept any panic. The failure is returned,
+    /// and the choices made by the failing execution are printed. Unlike
+    /// wrapping [`check`](Builder::check) in `#[should_panic]`, a model that
+    /// fails with another message, or a misconfigured `Builder`, is reported
+    /// as an error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use loom::model::Builder;
+    /// use loom::sync::atomic::AtomicUsize;
+    /// use loom::thread;
+    ///
+    /// use std::sync::atomic::Ordering::SeqCst;
+    /// use std::sync::Arc;
+    ///
+    /// let failure = Builder::new().check_expect_failure("lost update", || {
+    ///     let num = Arc::new(AtomicUsize::new(0));
+    ///     let num2 = num.clone();
+    ///
+    ///     let th = thread::spawn(move || {
+    ///         let val = num2.load(SeqCst);
+    ///         num2.store(val + 1, SeqCst);
+    ///     });
+    ///
+    ///     let val = num.load(SeqCst);
+    ///     num.store(val + 1, SeqCst);
+    ///
+    ///     th.join().unwrap();
+    ///     assert_eq!(2, num.load(SeqCst), "lost update");
+    /// });
+    ///
+    /// assert!(failure.message.unwrap().contains("lost update"));
+    /// ```
+    pub fn check_expect_failure<F>(&self, expected: &str, f: F) -> Failure
+    where
+        F: Fn() + Sync + Send + 'static,
+    {
+        let failed = match self.try_explore(f, &mut || {}, true) {
+            Ok(stats) => panic!(
+                "model was expected to fail, but all {} executions succeeded",
+                stats.iterations
+            ),
+            Err(failed) => failed,
+        };
+
+        let message = panic_str(&failed.panic).map(str::to_string);
+
+        if !matches!(&message, Some(message) if message.contains(expected)) {
+            let _report = failed.random_seed.map(ReportSeed);
+
+            panic!(
+                "model failed, but not with the expected message\n  \
+                 message: {:?}\n  \
+                 expected substring: {:?}",
+                message.as_deref().unwrap_or("<non-string panic payload>"),
+                expected,
+            );
+        }
+
+        let schedule: Vec<_> = failed
+            .choices
+            .iter()
+            .enumerate()
+            .filter_map(|(i, choice)| choice.map(|choice| format!("branch {}: {}", i, choice)))
+            .collect();
+
+        println!(
+            "model failed as expected in iteration {}, with the choices:",
+            failed.iteration
+        );
+
+        for choice in &schedule {
+            println!("    {}", choice);
+        }
+
+        Failure {
+            message,
+            iteration: failed.iteration,
+            schedule,
+            choices: Schedule {
+                choices: failed.choices,
+            },
+            random_seed: failed.random_seed,
+        }
+    }
+
+    /// Runs a single execution of the model, following `schedule`.
+    ///
+    /// The execution is checked like the ones of [`check`](Builder::check),
+    /// for causality and access violations, leaks and deadlocks. Branch
+    /// points without a choice in the schedule take the branch exhaustive
+    /// exploration tries first.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the execution fails, or if it could not follow the schedule:
+    /// a choice was not possible, or the execution ended before reaching it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use loom::model::{Builder, Schedule};
+    /// use loom::sync::atomic::AtomicUsize;
+    /// use loom::sync::Arc;
+    /// use loom::thread;
+    ///
+    /// use std::sync::atomic::Ordering::SeqCst;
+    ///
+    /// // Run the spawned thread as soon as it is spawned.
+    /// let mut schedule = Schedule::new();
+    /// schedule.switch_to(1, 1);
+    ///
+    /// Builder::new().check_schedule(&schedule, || {
+    ///     let num = Arc::new(AtomicUsize::new(0));
+    ///     let num2 = num.clone();
+    ///
+    ///     let th = thread::spawn(move || num2.store(1, SeqCst));
+    ///
+    ///     assert_eq!(num.load(SeqCst), 1);
+    ///     th.join().unwrap();
+    /// });
+    /// ```
+    pub fn check_schedule<F>(&self, schedule: &Schedule, f: F)
+    where
+        F: Fn() + Sync + Send + 'static,
+    {
+        Scheduler::assert_not_nested();
+
+        let mut execution = Execution::new(self.max_threads, self.max_branches, None);
+        execution.path.set_guide(schedule.choices.clone());
+        self.configure(&mut execution);
+
+        let mut scheduler = Scheduler::new(self.max_threads, self.runner);
+
+        if let Err(panic) = run(&mut scheduler, &mut execution, Arc::new(f)) {
+            panic::resume_unwind(panic);
+        }
+
+        let taken = execution.path.choices();
+
+        for (branch, choice) in schedule.choices.iter().enumerate() {
+            let choice = match choice {
+                Some(choice) => choice,
+                None => continue,
+            };
+
+            match taken.get(branch) {
+                Some(taken) if taken.as_ref() == Some(choice) => {}
+                Some(_) => panic!(
+                    "the execution could not follow the schedule: branch {} could not {}",
+                    branch, choice
+                ),
+                None => panic!(
+                    "the execution could not follow the schedule: it ended after {} branch \
+                     points, before branch {}",
+                    taken.len(),
+                    branch
+                ),
+            }
+        }
+    }
+
+    /// Explores the model, calling `between` after each successful execution.
+    fn explore<F>(&self, f: F, between: &mut dyn FnMut()) -> ExplorationStats
+    where
+        F: Fn() + Sync + Send + 'static,
+    {
+        match self.try_explore(f, between, true) {
+            Ok(stats) => {
+                self.check_marks(&stats);
+                self.store_coverage(&stats);
+                self.store_tree(&stats);
+                stats
+            }
+            Err(failed) => {
+                // Report the seed of a failing random execution so it can be
+                // replayed.
+                let _report = failed.random_seed.map(ReportSeed);
+                panic::resume_unwind(failed.panic)
+            }
+        }
+    }
+
+    /// Explores the model until an execution fails, returning the failure.
+    ///
+    /// The failing execution is minimized or replayed as configured only if
+    /// `report` is set.
+    fn try_explore<F>(
+        &self,
+        f: F,
+        between: &mut dyn FnMut(),
+        report: bool,
+    ) -> Result<ExplorationStats, Failed>
+    where
+        F: Fn() + Sync + Send + 'static,
+    {
+        let (bound, max) = self.bound();
+        Scheduler::assert_not_nested();
+
+        let mut execution = Execution::new(self.max_threads, self.max_branches, max);
+        execution.path.set_bound(bound);
+        execution.path.set_branch_limit(self.on_branch_limit);
+        execution.path.set_order(self.exploration_order);
+        execution
+            .path
+            .set_prefer_unfair(self.prefer_unfair_schedules);
+        execution.path.set_preemptive_yields(self.preemptive_yields);
+        execution.path.set_weak_fairness(self.weak_fairness);
+        let mut scheduler = Scheduler::new(self.max_threads, self.runner);
+
+        if self.random_schedules.is_some() {
+            execution.path.set_random(self.random_seed);
+        }
+
+        // The checkpoint restores the exploration strategy it was written with.
+        if let Some(ref path) = self.checkpoint_file {
+            if path.exists() {
+                execution.path = checkpoint::load_execution_path(path);
+                execution.path.set_max_branches(self.max_branches);
+                execution.path.set_branch_limit(self.on_branch_limit);
+                execution.path.set_preemptive_yields(self.preemptive_yields);
+                execution.path.set_weak_fairness(self.weak_fairness);
+
+                let random = execution.path.random_seed().is_some();
+                if random != self.random_schedules.is_some() {
+                    panic!(
+                        "checkpoint file `{}` was written by {} check; {} `random_schedules` \
+                         to resume it, or delete the file to restart the check",
+                        path.display(),
+                        if random { "a random" } else { "an exhaustive" },
+                        if random { "set" } else { "unset" },
+                    );
+                }
+            }
+        }
+
+        execution.path.set_check_determinism(self.check_determinism);
+        self.configure(&mut execution);
+        execution.location = self.location || self.profile_branches;
+        execution.sleep_sets = self.sleep_sets;
+        execution.observer = self.observer.clone();
+
+        let mut profile = None;
+
+        if self.profile_branches {
+            execution.branch_counts = Some(Default::default());
+            profile = Some(rt::Profile::default());
+        }
+
+        let mut tree = None;
+
+        if (self.record_tree || self.tree_file.is_some()) && self.random_schedules.is_none() {
+            tree = Some(rt::Tree::default());
+        }
+
+        let f = Arc::new(f);
+
+        let mut i = 0;
+
+        let start = Instant::now();
+
+        let mut stats = ExplorationStats {
+            iterations: 0,
+            unique_schedules: 0,
+            max_threads: 0,
+            duration: Duration::default(),
+            preemption_pruned: 0,
+            truncated: 0,
+            unfair_pruned: 0,
+            max_objects: ObjectCounts::default(),
+            max_alloc_bytes: 0,
+            branch_profile: vec![],
+            marks: BTreeMap::new(),
+            tree: None,
+            _p: (),
+        };
+
+        // Random executions may repeat a schedule, track the ones seen.
+        let mut seen_schedules = HashSet::new();
+
+        loop {
+            i += 1;
+
+            if i % self.checkpoint_interval == 0 {
+                println!("");
+                println!(" ================== Iteration {} ==================", i);
+                println!("");
+
+                self.store_checkpoint(&execution.path);
+
+                if let Some(max_permutations) = self.max_permutations {
+                    if i >= max_permutations {
+                        return Ok(stats.finish(start, profile.as_ref(), tree.take()));
+                    }
+                }
+
+                if let Some(max_duration) = self.max_duration {
+                    if start.elapsed() >= max_duration {
+                        return Ok(stats.finish(start, profile.as_ref(), tree.take()));
+                    }
+                }
+            }
+
+            let res = run(&mut scheduler, &mut execution, f.clone());
+
+            let mut objects = ObjectCounts::default();
+            execution.count_objects(&mut objects);
+
+            if self.after_execution.is_some() || self.observer.is_some() {
+                let summary = ExecutionSummary {
+                    iteration: i,
+                    panicked: res.is_err(),
+                    branches: execution.path.pos(),
+                    threads: execution.threads.iter().len(),
+                    max_threads: execution.threads.max_concurrent(),
+                    objects: objects.clone(),
+                    alloc_bytes: execution.peak_alloc_bytes,
+                    _p: (),
+                };
+
+                // A failing execution stops before reporting all its events.
+                execution.observe_pending();
+                execution.observe(|o| o.execution_finished(&summary));
+
+                if let Some(AfterExecution(f)) = &self.after_execution {
+                    f(&summary);
+                }
+            }
+
+            if let Err(panic) = res {
+                if self.checkpoint_on_exit {
+                    // Resume at the failing iteration
+                    execution.path.rewind();
+                    self.store_checkpoint(&execution.path);
+                }
+
+                let mut failed = Failed {
+                    iteration: i,
+                    choices: execution.path.choices(),
+                    random_seed: execution.path.random_seed(),
+                    panic,
+                };
+
+                drop(execution);
+                drop(scheduler);
+
+                if report && self.minimize {
+                    if let Some((choices, panic)) =
+                        self.minimize(&f, failed.choices.clone(), &failed.panic)
+                    {
+                        failed.choices = choices;
+                        failed.panic = panic;
+                    }
+                } else if report && self.locate_failures && !self.location {
+                    println!("replaying the failing execution to report source locations");
+
+                    // The events of the failing execution were already logged.
+                    if let Some((_, panic)) = self.replay(&f, failed.choices.clone(), true, false) {
+                        failed.panic = panic;
+                    }
+                }
+
+                return Err(failed);
+            }
+
+            between();
+
+            if let Some(profile) = &mut profile {
+                profile.record(&execution);
+            }
+
+            if let Some(tree) = &mut tree {
+                tree.record(&execution.path);
+            }
+
+            if i == 1 && self.check_isolation {
+                self.assert_isolated(&f, &execution, objects.clone());
+            }
+
+            stats.iterations = i;
+            stats.max_threads = stats.max_threads.max(execution.threads.max_concurrent());
+            stats.max_objects.max_assign(&objects);
+            stats.max_alloc_bytes = stats.max_alloc_bytes.max(execution.peak_alloc_bytes);
+
+            for &mark in &execution.marks {
+                *stats.marks.entry(mark).or_insert(0) += 1;
+            }
+
+            if self.random_schedules.is_some() {
+                seen_schedules.insert(execution.path.schedule_hash());
+                stats.unique_schedules = seen_schedules.len();
+            } else {
+                // Each exhaustive execution follows a new schedule
+                stats.unique_schedules = i;
+            }
+
+            if let Some(n) = self.random_schedules {
+                if i >= n {
+                    println!("Completed {} random iterations", i);
+                    return Ok(stats.finish(start, profile.as_ref(), tree.take()));
+                }
+            }
+
+            if execution.path.is_truncated() {
+                stats.truncated += 1;
+            }
+
+            if execution.path.is_unfair() {
+                stats.unfair_pruned += 1;
+            }
+
+            if let Some(n) = self.max_iterations {
+                if i >= n {
+                    return Ok(stats.finish(start, profile.as_ref(), tree.take()));
+                }
+            }
+
+            let preemption_bound = execution.path.preemption_bound();
+            let max_branches = execution.path.max_branches();
+
+            let preemption_pruned = execution.path.preemption_pruned();
+            stats.preemption_pruned = preemption_pruned;
+
+            let next = execution.step();
+
+            if let Some(tree) = &mut tree {
+                tree.step(next.as_ref().map(|next| next.path.diverged()));
+            }
+
+            if let Some(next) = next {
+                execution = next;
+            } else {
+                println!("Completed in {} iterations", i);
+
+                if let Some(max) = preemption_bound.filter(|_| preemption_pruned > 0) {
+                    let (name, var) = match bound {
+                        Bound::Preemptions => ("preemption", "LOOM_MAX_PREEMPTIONS"),
+                        Bound::ContextSwitches => ("context switch", "LOOM_MAX_CONTEXT_SWITCHES"),
+                        Bound::Delays => ("delay", "LOOM_MAX_DELAYS"),
+                    };
+
+                    println!(
+                        "warning: {} branch points were not explored due to the \
+                         {} bound ({}); consider raising the bound \
+                         (`{}`) to check them",
+                        preemption_pruned, name, max, var,
+                    );
+                }
+
+                if stats.truncated > 0 {
+                    println!(
+                        "warning: {} executions exceeded the maximum number of \
+                         branches ({}) and were not fully explored; consider \
+                         raising `LOOM_MAX_BRANCHES`",
+                        stats.truncated, max_branches,
+                    );
+                }
+
+                return Ok(stats.finish(start, profile.as_ref(), tree.take()));
+            }
+        }
+    }
+
+    /// Runs `execution` a second time, panicking if it does not behave the
+    /// same.
+    fn assert_isolated<F>(&self, f: &Arc<F>, execution: &Execution, objects: ObjectCounts)
+    where
+        F: Fn() + Sync + Send + 'static,
+    {
+        let expected = Fingerprint::of(execution, objects);
+
+        let mut second = Execution::new(self.max_threads, self.max_branches, None);
+        second.path.set_guide(execution.path.choices());
+        self.configure(&mut second);
+        second.log = rt::Log::default();
+
+        let mut scheduler = Scheduler::new(self.max_threads, self.runner);
+
+        if let Err(panic) = run(&mut scheduler, &mut second, f.clone()) {
+            panic!(
+                "model is not isolated: the first execution failed when run a second time: {}",
+                panic_message(&panic).unwrap_or_else(|| "<non-string panic payload>".to_string())
+            );
+        }
+
+        let mut objects = ObjectCounts::default();
+        second.count_objects(&mut objects);
+
+        if let Some(difference) = expected.difference(&Fingerprint::of(&second, objects)) {
+            panic!(
+                "model is not isolated: the first execution {} when run a second time; \
+                 state shared between executions, such as a `std::sync::Mutex` captured \
+                 by the model or a static, must be reset by each execution",
+                difference
+            );
+        }
+    }
+
+    /// Applies the settings checked by every execution of the model.
+    fn configure(&self, execution: &mut Execution) {
+        execution.log = self.log();
+        execution.location = self.location;
+        execution.alloc_failures = self.alloc_failures;
+        execution.spawn_failures = self.spawn_failures;
+        execution.inject_thread_failure = self.inject_thread_failure;
+        execution.detect_aba = self.detect_aba;
+        execution.max_yields = self.max_yields;
+        execution.max_objects = self.max_objects;
+        execution.max_alloc_bytes = self.max_alloc_bytes;
+        execution.main_exit = self.main_exit;
+        execution.memory_model = self.memory_model;
+        execution.yield_mode = self.yield_mode;
+    }
+
+    /// Panics if a required coverage mark was not hit.
+    fn check_marks(&self, stats: &ExplorationStats) {
+        let missing: Vec<_> = self
+            .required_marks
+            .iter()
+            .filter(|mark| !stats.marks.contains_key(mark.as_str()))
+            .collect();
+
+        if !missing.is_empty() {
+            panic!(
+                "coverage marks {:?} were not hit in any of the {} executions",
+                missing, stats.iterations
+            );
+        }
+    }
+
+    fn store_coverage(&self, stats: &ExplorationStats) {
+        let path = match self.coverage_file {
+            Some(ref path) => path,
+            None => return,
+        };
+
+        #[cfg(feature = "coverage")]
+        stats.coverage().store(path).unwrap_or_else(|e| {
+            panic!("failed to write coverage file `{}`: {}", path.display(), e)
+        });
+
+        #[cfg(not(feature = "coverage"))]
+        {
+            let _ = (path, stats);
+            panic!("not compiled with `coverage` feature")
+        }
+    }
+
+    fn store_tree(&self, stats: &ExplorationStats) {
+        if let (Some(path), Some(tree)) = (&self.tree_file, &stats.tree) {
+            tree.store(path).unwrap_or_else(|e| {
+                panic!("failed to write tree file `{}`: {}", path.display(), e)
+            });
+        }
+    }
+
+    /// Returns the log of the executions.
+    fn log(&self) -> rt::Log {
+        if self.log {
+            rt::Log::new(&self.log_filter, self.log_failing)
+        } else {
+            rt::Log::default()
+        }
+    }
+
+    /// Returns the kind and value of the scheduling bound.
+    fn bound(&self) -> (Bound, Option<usize>) {
+        match (self.preemption_bound, self.context_bound, self.delay_bound) {
+            (max, None, None) => (Bound::Preemptions, max),
+            (None, Some(max), None) => (Bound::ContextSwitches, Some(max)),
+            (None, None, Some(max)) => (Bound::Delays, Some(max)),
+            _ => panic!(
+                "only one of `preemption_bound`, `context_bound` and `delay_bound` can be set"
+            ),
+        }
+    }
+
+    /// Shrinks the failing execution that made `choices`, returning the
+    /// choices and panic of the smallest failing execution found.
+    fn minimize<F>(
+        &self,
+        f: &Arc<F>,
+        choices: Vec<Option<Choice>>,
+        panic: &Panic,
+    ) -> Option<(Vec<Option<Choice>>, Panic)>
+    where
+        F: Fn() + Sync + Send + 'static,
+    {
+        let message = panic_message(panic);
+        let replay = |choices| self.replay(f, choices, self.location, false);
+
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(|_| {}));
+
+        let mut best = replay(choices).filter(|(_, panic)| panic_message(panic) == message);
+        let mut runs = 1;
+
+        if let Some((choices, _)) = &best {
+            let original = count_choices(choices);
+            let mut choices = choices.clone();
+            let mut i = 0;
+
+            while i < choices.len() && runs < MAX_MINIMIZE_RUNS {
+                if choices[i].is_none() {
+                    i += 1;
+                    continue;
+                }
+
+                // First try undoing all choices from `i` on, then only the
+                // choice at `i`.
+                let mut suffix = choices.clone();
+                suffix[i..].iter_mut().for_each(|choice| *choice = None);
+
+                let mut single = choices.clone();
+                single[i] = None;
+
+                let mut shrunk = None;
+
+                let mut candidates = vec![suffix, single];
+                candidates.dedup();
+
+                for candidate in candidates {
+                    if runs >= MAX_MINIMIZE_RUNS {
+                        break;
+                    }
+
+                    runs += 1;
+
+                    shrunk = replay(candidate).filter(|(candidate, panic)| {
+                        panic_message(panic) == message
+                            && count_choices(candidate) < count_choices(&choices)
+                    });
+
+                    if shrunk.is_some() {
+                        break;
+                    }
+                }
+
+                match shrunk {
+                    Some((candidate, panic)) => {
+                        choices = candidate.clone();
+                        best = Some((candidate, panic));
+
+                        // Undoing a choice changes the rest of the execution,
+                        // start over.
+                        i = 0;
+                    }
+                    None => i += 1,
+                }
+            }
+
+            panic::set_hook(hook);
+
+            println!(
+                "minimized failing execution from {} to {} choices in {} runs",
+                original,
+                count_choices(&choices),
+                runs,
+            );
+
+            for (i, choice) in choices.iter().enumerate() {
+                if let Some(choice) = choice {
+                    println!("    branch {}: {}", i, choice);
+                }
+            }
+
+            // Run the minimized execution once more so it is reported like any
+            // other failure.
+            let location = self.location || self.locate_failures;
+
+            if let Some((_, panic)) = self.replay(f, choices.clone(), location, true) {
+                return Some((choices, panic));
+            }
+        } else {
+            panic::set_hook(hook);
+        }
+
+        best
+    }
+
+    /// Replays the execution that made `choices`, returning the choices it
+    /// made and its panic if it failed.
+    ///
+    /// The execution captures locations if `location` is set, and is logged
+    /// if `log` is set.
+    fn replay<F>(
+        &self,
+        f: &Arc<F>,
+        choices: Vec<Option<Choice>>,
+        location: bool,
+        log: bool,
+    ) -> Option<(Vec<Option<Choice>>, Panic)>
+    where
+        F: Fn() + Sync + Send + 'static,
+    {
+        let mut execution = Execution::new(self.max_threads, self.max_branches, None);
+        self.configure(&mut execution);
+        execution.path.set_guide(choices);
+        execution.location = location;
+
+        if !log {
+            execution.log = rt::Log::default();
+        }
+
+        let mut scheduler = Scheduler::new(self.max_threads, self.runner);
+        let res = run(&mut scheduler, &mut execution, f.clone());
+
+        match res {
+            Ok(()) => None,
+            Err(panic) => {
+                let choices = execution.path.guided_choices().unwrap().to_vec();
+                Some((choices, panic))
+            }
+        }
+    }
+
+    fn store_checkpoint(&self, execution_path: &rt::Path) {
+        let path = match self.checkpoint_file {
+            Some(ref path) => path,
+            None => return,
+        };
+
+        let format = self.checkpoint_format.unwrap_or_else(|| {
+            if path.extension().and_then(|ext| ext.to_str()) == Some("bin") {
+                CheckpointFormat::Binary
+            } else {
+                CheckpointFormat::Json
+            }
+        });
+
+        checkpoint::store_execution_path(execution_path, path, format);
+    }
+}
+
+type Panic = Box<dyn Any + Send + 'static>;
+
+/// A failed execution, see `Builder::try_explore`.
+struct Failed {
+    iteration: usize,
+    choices: Vec<Option<Choice>>,
+    random_seed: Option<u64>,
+    panic: Panic,
+}
+
+/// What an execution did, compared by `Builder::assert_isolated`.
+struct Fingerprint {
+    kinds: Vec<BranchKind>,
+    choices: Vec<Option<Choice>>,
+    threads: usize,
+    objects: ObjectCounts,
+}
+
+impl Fingerprint {
+    fn of(execution: &Execution, objects: ObjectCounts) -> Fingerprint {
+        Fingerprint {
+            kinds: (0..execution.path.pos())
+                .map(|index| execution.path.branch_kind(index))
+                .collect(),
+            choices: execution.path.choices(),
+            threads: execution.threads.iter().len(),
+            objects,
+        }
+    }
+
+    /// Describes how `other` differs from `self`.
+    fn difference(&self, other: &Fingerprint) -> Option<String> {
+        let branch = self
+            .kinds
+            .iter()
+            .zip(&other.kinds)
+            .zip(self.choices.iter().zip(&other.choices))
+            .position(|((a, b), (c, d))| a != b || c != d);
+
+        if let Some(branch) = branch {
+            Some(format!("took a different branch at branch {}", branch))
+        } else if self.kinds.len() != other.kinds.len() {
+            Some(format!(
+                "reached {} branch points instead of {}",
+                other.kinds.len(),
+                self.kinds.len()
+            ))
+        } else if self.threads != other.threads {
+            Some(format!(
+                "spawned {} threads instead of {}",
+                other.threads, self.threads
+            ))
+        } else if self.objects.total != other.objects.total {
+            Some(format!(
+                "created {} loom objects instead of {}",
+                other.objects.total, self.objects.total
+            ))
+        } else if self.objects != other.objects {
+            Some("created different kinds of loom objects".to_string())
+        } else {
+            None
+        }
+    }
+}
+
+/// Maximum number of executions run to minimize a failing execution.
+const MAX_MINIMIZE_RUNS: usize = 1_000;
+
+/// Maximum number of shrunk inputs checked to shrink a failing input.
+const MAX_SHRINK_RUNS: usize = 1_000;
+
+/// Runs a single execution of the model.
+fn run<F>(scheduler: &mut Scheduler, execution: &mut Execution, f: Arc<F>) -> Result<(), Panic>
+where
+    F: Fn() + Sync + Send + 'static,
+{
+    let res = panic::catch_unwind(AssertUnwindSafe(|| {
+        scheduler.run(execution, move || {
+            f();
 
-            scheduler.run(&mut execution, move || {
-                f();
+            rt::main_returned();
 
-                let lazy_statics = rt::execution(|execution| execution.lazy_statics.drop());
+            // drop outside of execution, most recently initialized first
+            while let Some(value) = rt::execution(|execution| execution.lazy_statics.pop()) {
+                drop(value);
+            }
 
-                // drop outside of execution
-                drop(lazy_statics);
+            rt::thread_done();
+        });
 
-                rt::thread_done();
-            });
+        // A panic is only reported to the thread joining the panicking one.
+        if let Some(panic) = execution.take_unjoined_panic() {
+            panic::resume_unwind(panic);
+        }
 
-            execution.check_for_leaks();
+        execution.check_for_leaks();
+    }));
 
-            if let Some(next) = execution.step() {
-                execution = next;
+    if res.is_err() {
+        execution.log.flush();
+    }
+
+    res
+}
+
+/// Returns the first line of a panic message, used to tell whether two
+/// executions failed the same way.
+fn panic_message(panic: &Panic) -> Option<String> {
+    panic_str(panic)?.lines().next().map(str::to_string)
+}
+
+/// Returns the message of a panic, if it is a string.
+fn panic_str(panic: &Panic) -> Option<&str> {
+    match panic.downcast_ref::<String>() {
+        Some(message) => Some(&message[..]),
+        None => panic.downcast_ref::<&'static str>().copied(),
+    }
+}
+
+/// Returns the number of branch points that did not take the default branch.
+fn count_choices(choices: &[Option<Choice>]) -> usize {
+    choices.iter().filter(|choice| choice.is_some()).count()
+}
+
+/// Run all concurrent permutations of the provided closure, returning the set
+/// of values it returned across all executions.
+///
+/// This makes it possible to assert on the outcomes of a model as a whole, for
+/// example that a value is observed by at least one execution.
+///
+/// Uses a default [`Builder`](crate::model::Builder) which can be affected
+/// by environment variables.
+///
+/// # Examples
+///
+/// ```
+/// use loom::sync::atomic::AtomicUsize;
+/// use loom::thread;
+///
+/// use std::sync::atomic::Ordering::SeqCst;
+/// use std::sync::Arc;
+///
+/// let outcomes = loom::model_outcomes(|| {
+///     let num = Arc::new(AtomicUsize::new(0));
+///     let num2 = num.clone();
+///
+///     let th = thread::spawn(move || num2.store(1, SeqCst));
+///     let val = num.load(SeqCst);
+///
+///     th.join().unwrap();
+///     val
+/// });
+///
+/// assert!(outcomes.iter().all(|&val| val == 0 || val == 1));
+/// assert!(outcomes.contains(&1));
+/// ```
+pub fn model_outcomes<F, T>(f: F) -> BTreeSet<T>
+where
+    F: Fn() -> T + Sync + Send + 'static,
+    T: Ord + Send + 'static,
+{
+    Builder::new().check_outcomes(f)
+}
+
+/// Run all concurrent permutations of the provided closure, returning the
+/// value it returned.
+///
+/// Fails if executions return different values, see
+/// [`Builder::check_returning`](crate::model::Builder::check_returning).
+///
+/// Uses a default [`Builder`](crate::model::Builder) which can be affected
+/// by environment variables.
+///
+/// # Examples
+///
+/// ```
+/// use loom::sync::atomic::AtomicUsize;
+/// use loom::thread;
+///
+/// use std::sync::atomic::Ordering::SeqCst;
+/// use std::sync::Arc;
+///
+/// let total = loom::model_returning(|| {
+///     let num = Arc::new(AtomicUsize::new(0));
+///     let num2 = num.clone();
+///
+///     let th = thread::spawn(move || num2.fetch_add(1, SeqCst));
+///     num.fetch_add(1, SeqCst);
+///
+///     th.join().unwrap();
+///     num.load(SeqCst)
+/// });
+///
+/// assert_eq!(total, 2);
+/// ```
+pub fn model_returning<F, T>(f: F) -> T
+where
+    F: Fn() -> T + Sync + Send + 'static,
+    T: PartialEq + fmt::Debug + Send + 'static,
+{
+    Builder::new().check_returning(f)
+}
+
+/// Summary of a single execution of a model.
+///
+/// Passed to the [`after_execution`](Builder::after_execution) hook and to
+/// the [`ExecutionObserver`].
+#[derive(Debug)]
+pub struct ExecutionSummary {
+    /// Iteration number of the execution, starting at 1.
+    pub iteration: usize,
+
+    /// `true` if the execution panicked.
+    pub panicked: bool,
+
+    /// Number of branch points the execution went through.
+    pub branches: usize,
+
+    /// Number of threads, including the main thread, spawned by the execution.
+    pub threads: usize,
+
+    /// Maximum number of threads, including the main thread, alive at the
+    /// same time.
+    pub max_threads: usize,
+
+    /// Number of loom objects created by the execution.
+    pub objects: ObjectCounts,
+
+    /// Most bytes of allocations made with [`loom::alloc`](crate::alloc) held
+    /// at once.
+    pub alloc_bytes: usize,
+
+    // Support adding more fields in the future
+    _p: (),
+}
+
+/// A failing execution of a model.
+///
+/// Returned by [`Builder::check_expect_failure`].
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct Failure {
+    /// Message the execution panicked with, `None` if the panic payload is not
+    /// a string.
+    pub message: Option<String>,
+
+    /// Iteration number of the failing execution, starting at 1.
+    pub iteration: usize,
+
+    /// The choices made by the failing execution, one line per branch point,
+    /// such as `branch 3: switch to thread #1`.
+    ///
+    /// With [`minimize`](Builder::minimize), these are the choices of the
+    /// minimized execution, where only the choices that differ from the
+    /// default branch are listed.
+    pub schedule: Vec<String>,
+
+    /// The choices made by the failing execution, to run it again with
+    /// [`Builder::check_schedule`].
+    pub choices: Schedule,
+
+    /// Seed of the failing execution when exploring random schedules.
+    pub random_seed: Option<u64>,
+}
+
+/// The choices an execution makes at its branch points.
+///
+/// Branch points are numbered from 0, in the order the execution reaches
+/// them, as in loom's failure reports. Run with [`Builder::check_schedule`].
+///
+/// A schedule can also be parsed from the choices of a [`Failure`], one per
+/// line, such as `branch 3: switch to thread #1`. Its `Display`
+/// implementation writes this format.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Schedule {
+    /// Choice made at each branch point, `None` takes the default branch.
+    choices: Vec<Option<Choice>>,
+}
+
+impl Schedule {
+    /// Returns a schedule taking the default branch everywhere: the running
+    /// thread keeps running, loads read the first candidate store and nothing
+    /// fails spuriously.
+    pub fn new() -> Schedule {
+        Schedule::default()
+    }
+
+    /// Runs the thread with the index `thread` at the branch point `branch`.
+    /// The main thread has the index 0, spawned threads are numbered in
+    /// order.
+    pub fn switch_to(&mut self, branch: usize, thread: usize) -> &mut Self {
+        assert!(thread < rt::MAX_THREADS, "thread index out of bounds");
+        self.set(branch, Choice::Thread(thread as u8))
+    }
+
+    /// Reads the store with the index `store` at the branch point `branch`,
+    /// which must be an atomic load.
+    pub fn read_store(&mut self, branch: usize, store: usize) -> &mut Self {
+        assert!(store < usize::from(u8::MAX), "store index out of bounds");
+        self.set(branch, Choice::Load(store as u8))
+    }
+
+    /// Fails the wait at the branch point `branch` spuriously.
+    pub fn fail_spuriously(&mut self, branch: usize) -> &mut Self {
+        self.set(branch, Choice::Spurious)
+    }
+
+    /// Returns `value` from [`choose`](crate::model::choose) at the branch
+    /// point `branch`.
+    pub fn choose(&mut self, branch: usize, value: usize) -> &mut Self {
+        self.set(branch, Choice::Choose(value))
+    }
+
+    fn set(&mut self, branch: usize, choice: Choice) -> &mut Self {
+        if self.choices.len() <= branch {
+            self.choices.resize(branch + 1, None);
+        }
+
+        self.choices[branch] = Some(choice);
+        self
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (branch, choice) in self.choices.iter().enumerate() {
+            if let Some(choice) = choice {
+                writeln!(fmt, "branch {}: {}", branch, choice)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for Schedule {
+    type Err = ParseScheduleError;
+
+    fn from_str(s: &str) -> Result<Schedule, ParseScheduleError> {
+        let mut schedule = Schedule::new();
+
+        for line in s.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let err = || ParseScheduleError(line.to_string());
+
+            let rest = line.strip_prefix("branch ").ok_or_else(err)?;
+            let colon = rest.find(": ").ok_or_else(err)?;
+            let branch = rest[..colon].parse().map_err(|_| err())?;
+            let choice = &rest[colon + 2..];
+
+            let number = |prefix: &str| -> Result<usize, ParseScheduleError> {
+                let number = choice.strip_prefix(prefix).ok_or_else(err)?;
+                number.parse().map_err(|_| err())
+            };
+
+            if choice == "fail spuriously" {
+                schedule.fail_spuriously(branch);
+            } else if let Ok(thread) = number("switch to thread #") {
+                if thread >= rt::MAX_THREADS {
+                    return Err(err());
+                }
+
+                schedule.switch_to(branch, thread);
+            } else if let Ok(store) = number("read store #") {
+                if store >= usize::from(u8::MAX) {
+                    return Err(err());
+                }
+
+                schedule.read_store(branch, store);
             } else {
-                println!("Completed in {} iterations", i);
-                return;
+                schedule.choose(branch, number("choose #")?);
             }
         }
+
+        Ok(schedule)
+    }
+}
+
+/// Error returned when parsing a [`Schedule`] fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseScheduleError(String);
+
+impl fmt::Display for ParseScheduleError {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "invalid schedule choice `{}`", self.0)
+    }
+}
+
+impl std::error::Error for ParseScheduleError {}
+
+/// Statistics about the exploration of a model.
+///
+/// Returned by [`Builder::check_returning_stats`].
+#[derive(Debug, Clone)]
+pub struct ExplorationStats {
+    /// Number of executions of the model.
+    pub iterations: usize,
+
+    /// Number of distinct schedules explored.
+    ///
+    /// Exhaustive checks never repeat a schedule, so this is equal to
+    /// `iterations`. Random executions may repeat schedules.
+    pub unique_schedules: usize,
+
+    /// Maximum number of threads, including the main thread, that were alive
+    /// at the same time in any execution.
+    pub max_threads: usize,
+
+    /// Wall-clock time spent checking the model.
+    pub duration: Duration,
+
+    /// Number of schedule branch points that were not explored due to the
+    /// preemption, context switch or delay bound.
+    pub preemption_pruned: usize,
+
+    /// Number of executions that exceeded the maximum number of branches and
+    /// were not fully explored. Only non-zero with [`BranchLimit::Warn`].
+    pub truncated: usize,
+
+    /// Number of executions that exceeded the maximum number of branches
+    /// because of an unfair schedule and were pruned. Only non-zero with
+    /// [`weak_fairness`](Builder::weak_fairness).
+    pub unfair_pruned: usize,
+
+    /// Most loom objects of each kind created by a single execution.
+    ///
+    /// Each count is maximized separately, they may come from different
+    /// executions.
+    pub max_objects: ObjectCounts,
+
+    /// Most bytes of allocations made with [`loom::alloc`](crate::alloc) held
+    /// at once by any execution.
+    pub max_alloc_bytes: usize,
+
+    /// Objects whose accesses caused branch points, most branches first. Only
+    /// recorded with [`profile_branches`](Builder::profile_branches).
+    pub branch_profile: Vec<ObjectBranches>,
+
+    /// Number of successful executions that hit each coverage mark, see
+    /// [`explore::mark`](crate::explore::mark). Marks never hit are absent.
+    pub marks: BTreeMap<&'static str, usize>,
+
+    /// The tree of the branch points explored. Only recorded with
+    /// [`record_tree`](Builder::record_tree) or
+    /// [`tree_file`](Builder::tree_file).
+    pub tree: Option<ExplorationTree>,
+
+    // Support adding more fields in the future
+    _p: (),
+}
+
+/// Branch points caused by the accesses to an object, see
+/// [`Builder::profile_branches`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ObjectBranches {
+    /// Kind of the object, such as `"atomic"` or `"mutex"`.
+    pub kind: &'static str,
+
+    /// Index of the object, the `N` of `object #N` in loom's messages.
+    /// Executions creating the same objects in the same order give them the
+    /// same index.
+    pub object: usize,
+
+    /// Where the object was created.
+    pub location: Option<&'static panic::Location<'static>>,
+
+    /// Number of alternatives explored because of accesses to the object:
+    /// thread schedules for racing accesses and values read by atomic loads.
+    pub branches: usize,
+}
+
+/// Number of loom objects created by an execution, by kind.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ObjectCounts {
+    /// Atomics, including the ones backing `AtomicCell` and `lazy_static!`.
+    pub atomics: usize,
+
+    /// Mutexes.
+    pub mutexes: usize,
+
+    /// Read-write locks.
+    pub rwlocks: usize,
+
+    /// Condition variables.
+    pub condvars: usize,
+
+    /// `Notify` instances.
+    pub notifies: usize,
+
+    /// Channels.
+    pub channels: usize,
+
+    /// Semaphores.
+    pub semaphores: usize,
+
+    /// `Arc` allocations.
+    pub arcs: usize,
+
+    /// `UnsafeCell` instances.
+    pub cells: usize,
+
+    /// Tracked allocations, made with [`loom::alloc`](crate::alloc) or
+    /// [`Track`](crate::alloc::Track).
+    pub allocations: usize,
+
+    /// Objects registered by custom primitives.
+    pub custom: usize,
+
+    /// Total number of objects.
+    pub total: usize,
+}
+
+impl ObjectCounts {
+    /// Adds the counts of `other`.
+    pub(crate) fn add(&mut self, other: &ObjectCounts) {
+        self.atomics += other.atomics;
+        self.mutexes += other.mutexes;
+        self.rwlocks += other.rwlocks;
+        self.condvars += other.condvars;
+        self.notifies += other.notifies;
+        self.channels += other.channels;
+        self.semaphores += other.semaphores;
+        self.arcs += other.arcs;
+        self.cells += other.cells;
+        self.allocations += other.allocations;
+        self.custom += other.custom;
+        self.total += other.total;
+    }
+
+    /// Raises each count to the one of `other` if larger.
+    fn max_assign(&mut self, other: &ObjectCounts) {
+        self.atomics = self.atomics.max(other.atomics);
+        self.mutexes = self.mutexes.max(other.mutexes);
+        self.rwlocks = self.rwlocks.max(other.rwlocks);
+        self.condvars = self.condvars.max(other.condvars);
+        self.notifies = self.notifies.max(other.notifies);
+        self.channels = self.channels.max(other.channels);
+        self.semaphores = self.semaphores.max(other.semaphores);
+        self.arcs = self.arcs.max(other.arcs);
+        self.cells = self.cells.max(other.cells);
+        self.allocations = self.allocations.max(other.allocations);
+        self.custom = self.custom.max(other.custom);
+        self.total = self.total.max(other.total);
+    }
+}
+
+impl ExplorationStats {
+    /// Returns the coverage marks hit and the objects that caused branch
+    /// points, to compare with the coverage of another check.
+    pub fn coverage(&self) -> Coverage {
+        Coverage {
+            iterations: self.iterations,
+            marks: self
+                .marks
+                .iter()
+                .map(|(&mark, &count)| (mark.to_string(), count))
+                .collect(),
+            branches: self
+                .branch_profile
+                .iter()
+                .map(|object| {
+                    (
+                        format!("{} #{}", object.kind, object.object),
+                        object.branches,
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    fn finish(
+        mut self,
+        start: Instant,
+        profile: Option<&rt::Profile>,
+        tree: Option<rt::Tree>,
+    ) -> ExplorationStats {
+        self.duration = start.elapsed();
+        self.tree = tree.map(rt::Tree::finish);
+
+        if let Some(profile) = profile {
+            self.branch_profile = profile.ranked();
+            print_branch_profile(&self.branch_profile);
+        }
+
+        self
+    }
+}
+
+impl fmt::Debug for AfterExecution {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("AfterExecution").finish()
+    }
+}
+
+/// Prints the objects causing the most branch points, with their share of
+/// all branch points.
+fn print_branch_profile(objects: &[ObjectBranches]) {
+    let total: usize = objects.iter().map(|object| object.branches).sum();
+
+    println!("Branch points by object:");
+
+    if total == 0 {
+        println!("    none");
+        return;
+    }
+
+    for object in objects {
+        let share = object.branches as f64 * 100.0 / total as f64;
+
+        print!(
+            "    {:>5.1}% {:>8}  {} #{}",
+            share, object.branches, object.kind, object.object
+        );
+
+        match object.location {
+            Some(location) => println!(" created at {}", location),
+            None => println!(),
+        }
+    }
+}
+
+/// Prints the seed of the current random execution if it fails.
+struct ReportSeed(u64);
+
+impl Drop for ReportSeed {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            eprintln!(
+                "random execution failed; replay it with `random_schedules(1, {})` \
+                 or `LOOM_RANDOM_SCHEDULES=1 LOOM_RANDOM_SEED={}`",
+                self.0, self.0
+            );
+        }
+    }
+}
+
+/// Prints the input of a model if checking it fails.
+struct ReportInput<'a, T: fmt::Debug>(&'a T);
+
+impl<T: fmt::Debug> Drop for ReportInput<'_, T> {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            eprintln!("model failed for input: {:?}", self.0);
+        }
     }
 }
 
@@ -231,36 +2887,267 @@ where
     Builder::new().check(f)
 }
 
+/// Returns a value in `0..n`, exploring every value.
+///
+/// This models nondeterminism loom does not know about, such as the result
+/// of simulated I/O or whether a timeout fires: each execution reaching the
+/// call continues with one of the values, and the check explores all of
+/// them.
+///
+/// # Panics
+///
+/// Panics if `n` is zero.
+///
+/// # Examples
+///
+/// ```
+/// loom::model(|| {
+///     let result = match loom::choose(3) {
+///         0 => Ok(()),
+///         1 => Err("timed out"),
+///         _ => Err("connection reset"),
+///     };
+///
+///     // Handle every outcome.
+///     let _ = result;
+/// });
+/// ```
+pub fn choose(n: usize) -> usize {
+    rt::choose(n)
+}
+
+/// Returns `false` or `true`, exploring both.
+///
+/// This is a shorthand for `loom::choose(2) == 1`. See [`choose`].
+pub fn branch() -> bool {
+    choose(2) == 1
+}
+
+/// Run `f` as if no other thread could run until it returns.
+///
+/// Loom does not switch threads inside `f`, cutting the number of
+/// interleavings to explore. This is meant for regions that run under a lock
+/// external to the code being tested, or that are irrelevant to the property
+/// being checked.
+///
+/// # Soundness
+///
+/// Interleavings that could happen inside `f` in production are **not**
+/// explored, so loom may miss bugs if the region is not actually atomic.
+/// Other threads still observe the effects of `f` one operation at a time:
+/// the atomic section does not prevent them from observing intermediate
+/// states, it only means they do not run while `f` runs.
+///
+/// If the current thread blocks inside `f`, for example on a lock, other
+/// threads run until it is unblocked.
+///
+/// # Examples
+///
+/// ```
+/// use loom::sync::atomic::AtomicUsize;
+/// use loom::sync::Arc;
+/// use loom::thread;
+/// use std::sync::atomic::Ordering::SeqCst;
+///
+/// loom::model(|| {
+///     let num = Arc::new(AtomicUsize::new(0));
+///     let num2 = num.clone();
+///
+///     let th = thread::spawn(move || {
+///         loom::atomic_section(|| {
+///             let n = num2.load(SeqCst);
+///             num2.store(n + 1, SeqCst);
+///         })
+///     });
+///
+///     loom::atomic_section(|| {
+///         let n = num.load(SeqCst);
+///         num.store(n + 1, SeqCst);
+///     });
+///
+///     th.join().unwrap();
+///     assert_eq!(2, num.load(SeqCst));
+/// });
+/// ```
+pub fn atomic_section<F, R>(f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    rt::critical(f)
+}
+
+/// Run all concurrent permutations of the provided closure once for each of
+/// the `inputs`.
+///
+/// See [`Builder::check_each`](crate::model::Builder::check_each).
+///
+/// Uses a default [`Builder`](crate::model::Builder) which can be affected
+/// by environment variables.
+pub fn model_each<I, T, F>(inputs: I, f: F)
+where
+    I: IntoIterator<Item = T>,
+    T: fmt::Debug + Sync + Send + 'static,
+    F: Fn(&T) + Sync + Send + 'static,
+{
+    Builder::new().check_each(inputs, f)
+}
+
+/// Run all concurrent permutations of the provided closure once for each of
+/// the `inputs`, shrinking the first input it fails for.
+///
+/// See [`Builder::check_each_shrinking`](crate::model::Builder::check_each_shrinking).
+///
+/// Uses a default [`Builder`](crate::model::Builder) which can be affected
+/// by environment variables.
+pub fn model_each_shrinking<I, T, S, C, F>(inputs: I, shrink: S, f: F)
+where
+    I: IntoIterator<Item = T>,
+    T: fmt::Debug + Sync + Send + 'static,
+    S: Fn(&T) -> C,
+    C: IntoIterator<Item = T>,
+    F: Fn(&T) + Sync + Send + 'static,
+{
+    Builder::new().check_each_shrinking(inputs, shrink, f)
+}
+
 #[cfg(feature = "checkpoint")]
 mod checkpoint {
+    use super::CheckpointFormat;
+    use crate::rt::codec::Codec;
+    use crate::rt::StrategyState;
+
+    use serde::Serialize;
     use std::fs::File;
     use std::io::prelude::*;
     use std::path::Path;
 
+    /// Version of the checkpoint format.
+    ///
+    /// This must be bumped whenever the serialized representation of
+    /// `rt::Path` changes, so that stale checkpoints are rejected instead of
+    /// being misinterpreted.
+    const VERSION: u32 = 3;
+
+    /// Header identifying binary checkpoint files.
+    const MAGIC: &[u8] = b"LOOMCKPT";
+
+    #[derive(Serialize)]
+    struct Checkpoint<'a> {
+        version: u32,
+        path: &'a crate::rt::Path,
+        strategy: StrategyState,
+    }
+
     pub(crate) fn load_execution_path(fs_path: &Path) -> crate::rt::Path {
         let mut file = File::open(fs_path).unwrap();
-        let mut contents = String::new();
-        file.read_to_string(&mut contents).unwrap();
-        serde_json::from_str(&contents).unwrap()
+        let mut contents = Vec::new();
+        file.read_to_end(&mut contents).unwrap();
+
+        match contents.strip_prefix(MAGIC) {
+            Some(mut src) => {
+                let version = u32::decode(&mut src).ok();
+                check_version(fs_path, version);
+
+                let decoded = crate::rt::Path::decode(&mut src)
+                    .and_then(|path| Ok((path, StrategyState::decode(&mut src)?)));
+
+                match decoded {
+                    Ok((mut path, strategy)) if src.is_empty() => {
+                        path.set_strategy(strategy);
+                        path
+                    }
+                    Ok(_) => corrupt(fs_path, "trailing data"),
+                    Err(e) => corrupt(fs_path, e),
+                }
+            }
+            None => {
+                let mut value: serde_json::Value = match serde_json::from_slice(&contents) {
+                    Ok(value) => value,
+                    Err(e) => corrupt(fs_path, e),
+                };
+
+                let version = value
+                    .get("version")
+                    .and_then(|v| v.as_u64())
+                    .map(|v| v as u32);
+                check_version(fs_path, version);
+
+                let decoded = serde_json::from_value::<crate::rt::Path>(value["path"].take())
+                    .and_then(|path| Ok((path, serde_json::from_value(value["strategy"].take())?)));
+
+                match decoded {
+                    Ok((mut path, strategy)) => {
+                        path.set_strategy(strategy);
+                        path
+                    }
+                    Err(e) => corrupt(fs_path, e),
+                }
+            }
+        }
     }
 
-    pub(crate) fn store_execution_path(path: &crate::rt::Path, fs_path: &Path) {
-        let serialized = serde_json::to_string(path).unwrap();
+    pub(crate) fn store_execution_path(
+        path: &crate::rt::Path,
+        fs_path: &Path,
+        format: CheckpointFormat,
+    ) {
+        let serialized = match format {
+            CheckpointFormat::Json => serde_json::to_vec(&Checkpoint {
+                version: VERSION,
+                path,
+                strategy: path.strategy(),
+            })
+            .unwrap(),
+            CheckpointFormat::Binary => {
+                let mut dst = MAGIC.to_vec();
+                VERSION.encode(&mut dst);
+                path.encode(&mut dst);
+                path.strategy().encode(&mut dst);
+                dst
+            }
+        };
 
         let mut file = File::create(fs_path).unwrap();
-        file.write_all(serialized.as_bytes()).unwrap();
+        file.write_all(&serialized).unwrap();
+    }
+
+    fn check_version(fs_path: &Path, version: Option<u32>) {
+        if version != Some(VERSION) {
+            let found = version.map_or("unknown".to_string(), |v| v.to_string());
+
+            panic!(
+                "checkpoint file `{}` was written by an incompatible version of loom \
+                 (format version {}, expected {}); delete the file to restart the check",
+                fs_path.display(),
+                found,
+                VERSION
+            );
+        }
+    }
+
+    fn corrupt(fs_path: &Path, err: impl std::fmt::Display) -> ! {
+        panic!(
+            "checkpoint file `{}` is corrupt: {}; delete the file to restart the check",
+            fs_path.display(),
+            err
+        );
     }
 }
 
 #[cfg(not(feature = "checkpoint"))]
 mod checkpoint {
+    use super::CheckpointFormat;
     use std::path::Path;
 
     pub(crate) fn load_execution_path(_fs_path: &Path) -> crate::rt::Path {
         panic!("not compiled with `checkpoint` feature")
     }
 
-    pub(crate) fn store_execution_path(_path: &crate::rt::Path, _fs_path: &Path) {
+    pub(crate) fn store_execution_path(
+        _path: &crate::rt::Path,
+        _fs_path: &Path,
+        _format: CheckpointFormat,
+    ) {
         panic!("not compiled with `checkpoint` feature")
     }
 }
@@ -0,0 +1,136 @@
+//! Observing the executions of a model, for custom instrumentation.
+//!
+//! An [`ExecutionObserver`] registered with [`Builder::instrument`] is told
+//! about the events of each execution: threads being spawned, scheduled and
+//! terminated, objects being created, branch points and the end of the
+//! execution. This can be used to record traces or metrics, for example to
+//! study the schedules explored.
+//!
+//! The observer is invoked by the runtime, while it is modeling the
+//! execution: its callbacks must not use loom types, nor panic.
+//!
+//! Objects and branch points are reported by the thread creating or reaching
+//! them, before the next schedule branch point. Threads are identified by
+//! their index, starting at 0 for the main thread, and objects by their index
+//! in the execution, as in loom's failure reports.
+//!
+//! [`Builder::instrument`]: crate::model::Builder::instrument
+//!
+//! # Examples
+//!
+//! ```
+//! use loom::model::Builder;
+//! use loom::observe::ExecutionObserver;
+//! use loom::thread;
+//!
+//! use std::sync::atomic::{AtomicUsize, Ordering::Relaxed};
+//! use std::sync::Arc;
+//!
+//! struct CountSwitches(Arc<AtomicUsize>);
+//!
+//! impl ExecutionObserver for CountSwitches {
+//!     fn thread_switched(&mut self, _from: usize, _to: usize) {
+//!         self.0.fetch_add(1, Relaxed);
+//!     }
+//! }
+//!
+//! let switches = Arc::new(AtomicUsize::new(0));
+//!
+//! let mut builder = Builder::new();
+//! builder.instrument(Box::new(CountSwitches(switches.clone())));
+//!
+//! builder.check(|| {
+//!     let th = thread::spawn(|| {});
+//!     th.join().unwrap();
+//! });
+//!
+//! assert!(switches.load(Relaxed) > 0);
+//! ```
+
+use crate::model::ExecutionSummary;
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+/// Receives the events of the executions of a model.
+///
+/// All methods default to doing nothing, an observer only implements the ones
+/// it is interested in.
+pub trait ExecutionObserver {
+    /// The thread `thread` was spawned by the thread `parent`.
+    fn thread_spawned(&mut self, thread: usize, parent: usize) {
+        let _ = (thread, parent);
+    }
+
+    /// The scheduler switched from running the thread `from` to the thread
+    /// `to`.
+    fn thread_switched(&mut self, from: usize, to: usize) {
+        let _ = (from, to);
+    }
+
+    /// The thread `thread` terminated.
+    fn thread_terminated(&mut self, thread: usize) {
+        let _ = thread;
+    }
+
+    /// The thread `thread` created the object `object`, of the given kind,
+    /// such as `atomic` or `mutex`.
+    fn object_created(&mut self, thread: usize, object: usize, kind: &'static str) {
+        let _ = (thread, object, kind);
+    }
+
+    /// The thread `thread` reached a branch point, the `position`-th of the
+    /// execution.
+    fn branch(&mut self, thread: usize, position: usize, kind: BranchKind) {
+        let _ = (thread, position, kind);
+    }
+
+    /// The execution ended, successfully or not.
+    fn execution_finished(&mut self, summary: &ExecutionSummary) {
+        let _ = summary;
+    }
+}
+
+/// What is decided at a branch point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum BranchKind {
+    /// Which thread runs next.
+    Schedule,
+
+    /// Which store an atomic load reads.
+    Load,
+
+    /// Whether a wait wakes up spuriously.
+    Spurious,
+
+    /// Which value is picked by the model, or which order the runtime uses,
+    /// such as the order thread locals are destroyed in.
+    Choose,
+}
+
+/// The observer of a `Builder`, shared with the executions it runs.
+#[derive(Clone)]
+pub(crate) struct Observer(Arc<Mutex<Box<dyn ExecutionObserver + Send>>>);
+
+impl Observer {
+    pub(crate) fn new(observer: Box<dyn ExecutionObserver + Send>) -> Observer {
+        Observer(Arc::new(Mutex::new(observer)))
+    }
+
+    pub(crate) fn with<R>(&self, f: impl FnOnce(&mut dyn ExecutionObserver) -> R) -> R {
+        // Keep observing after a callback panicked, failing the check.
+        let mut observer = match self.0.lock() {
+            Ok(observer) => observer,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        f(&mut **observer)
+    }
+}
+
+impl fmt::Debug for Observer {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("Observer").finish()
+    }
+}
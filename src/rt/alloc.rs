@@ -1,5 +1,9 @@
 use crate::rt;
-use crate::rt::object;
+use crate::rt::location;
+use crate::rt::{object, thread, Execution, Location, Synchronize};
+
+use std::alloc::Layout;
+use std::sync::atomic::Ordering::{Acquire, Release};
 
 /// Tracks an allocation
 #[derive(Debug)]
@@ -7,58 +11,367 @@ pub(crate) struct Allocation {
     state: object::Ref<State>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(super) struct State {
     is_dropped: bool,
+
+    /// Name of the tracked type, `None` for raw allocations
+    type_name: Option<&'static str>,
+
+    /// Where the allocation was made
+    allocated: Location,
+
+    /// Size of a raw allocation, in bytes
+    size: usize,
+
+    /// Where the raw allocation was deallocated
+    deallocated: Location,
+
+    /// Thread owning a tracked value, once handed off between threads
+    owner: Owner,
+
+    /// Causality of the thread that last published the tracked value
+    handoff: Synchronize,
+
+    /// Where the tracked value was last published or acquired
+    handed_off: Location,
+}
+
+/// Ownership of a tracked value, see `Track::publish`.
+#[derive(Debug, Clone, Copy)]
+enum Owner {
+    /// Never published, any thread may access the value
+    Any,
+
+    /// Acquired by the thread, or created by it and published since
+    Thread(thread::Id),
+
+    /// Published by the thread, not acquired yet
+    Published(thread::Id),
+}
+
+/// Raw allocations deallocated by the current execution.
+///
+/// The memory is only released once the execution completes. It cannot be
+/// handed out again in the meantime, accesses to it are detected instead of
+/// accessing memory reused by another allocation.
+#[derive(Debug, Default)]
+pub(crate) struct Quarantine {
+    freed: Vec<Freed>,
+}
+
+#[derive(Debug)]
+struct Freed {
+    ptr: usize,
+    layout: Layout,
+    state: object::Ref<State>,
+}
+
+/// Returns `true` if the raw allocation about to be made should fail.
+pub(crate) fn alloc_fails() -> bool {
+    let (fail, branch) = rt::execution(|execution| {
+        let thread = execution.threads.active_mut();
+        let fail = std::mem::replace(&mut thread.fail_next_alloc, false);
+
+        (fail, execution.alloc_failures)
+    });
+
+    fail || (branch && rt::choose(2) == 1)
+}
+
+/// Make the next raw allocation of the current thread fail.
+pub(crate) fn fail_next_alloc() {
+    rt::execution(|execution| execution.threads.active_mut().fail_next_alloc = true);
 }
 
 /// Track a raw allocation
-pub(crate) fn alloc(ptr: *mut u8) {
+pub(crate) fn alloc(ptr: *mut u8, size: usize, location: Location) {
     rt::execution(|execution| {
-        let state = execution.objects.insert(State { is_dropped: false });
+        let state = execution.objects.insert(State {
+            is_dropped: false,
+            type_name: None,
+            allocated: location,
+            size,
+            deallocated: Location::disabled(),
+            owner: Owner::Any,
+            handoff: Synchronize::new(),
+            handed_off: Location::disabled(),
+        });
 
-        let allocation = Allocation { state };
+        let prev = execution.raw_allocations.insert(ptr as usize, state);
+        assert!(prev.is_none(), "pointer already tracked");
 
-        let prev = execution.raw_allocations.insert(ptr as usize, allocation);
+        track_bytes(execution, 0, size);
+    });
+}
+
+/// Move a tracked raw allocation to the pointer returned by `realloc`, made
+/// at `location`
+pub(crate) fn realloc(ptr: *mut u8, new_ptr: *mut u8, new_size: usize, location: Location) {
+    rt::execution(|execution| {
+        let state = match execution.raw_allocations.remove(&(ptr as usize)) {
+            Some(state) => state,
+            None => panic!("pointer not tracked"),
+        };
+
+        let prev = execution.raw_allocations.insert(new_ptr as usize, state);
         assert!(prev.is_none(), "pointer already tracked");
+
+        let state = state.get_mut(&mut execution.objects);
+        state.allocated = location;
+
+        let size = std::mem::replace(&mut state.size, new_size);
+        track_bytes(execution, size, new_size);
+    });
+}
+
+/// Updates the bytes held by the execution as an allocation of `old` bytes
+/// now holds `new` bytes.
+fn track_bytes(execution: &mut Execution, old: usize, new: usize) {
+    execution.alloc_bytes = execution.alloc_bytes - old + new;
+    execution.peak_alloc_bytes = execution.peak_alloc_bytes.max(execution.alloc_bytes);
+
+    if let Some(max) = execution.max_alloc_bytes {
+        if execution.alloc_bytes > max {
+            panic!(
+                "execution holds {} bytes of raw allocations, more than the maximum of {}; \
+                 raise `max_alloc_bytes` (`LOOM_MAX_ALLOC_BYTES`) if this is expected",
+                execution.alloc_bytes, max
+            );
+        }
+    }
+}
+
+/// Track a raw deallocation. The memory is released once the execution
+/// completes, see `Quarantine`.
+pub(crate) fn dealloc(ptr: *mut u8, layout: Layout, location: Location) {
+    rt::execution(|execution| {
+        let state_ref = match execution.raw_allocations.remove(&(ptr as usize)) {
+            Some(state) => state,
+            None => panic!("pointer not tracked"),
+        };
+
+        let state = state_ref.get_mut(&mut execution.objects);
+        state.is_dropped = true;
+        state.deallocated = location;
+
+        let size = state.size;
+        track_bytes(execution, size, 0);
+
+        execution.quarantine.freed.push(Freed {
+            ptr: ptr as usize,
+            layout,
+            state: state_ref,
+        });
     });
 }
 
-/// Track a raw deallocation
-pub(crate) fn dealloc(ptr: *mut u8) {
-    let allocation =
-        rt::execution(
-            |execution| match execution.raw_allocations.remove(&(ptr as usize)) {
-                Some(allocation) => allocation,
-                None => panic!("pointer not tracked"),
-            },
-        );
+/// Fails the execution if `addr`, the address of the data of a cell named
+/// `name`, is in a deallocated raw allocation.
+pub(super) fn check_use_after_free(
+    execution: &Execution,
+    addr: usize,
+    name: &str,
+    location: Location,
+) {
+    let freed = execution
+        .quarantine
+        .freed
+        .iter()
+        .find(|freed| freed.ptr <= addr && addr < freed.ptr + freed.layout.size());
+
+    if let Some(freed) = freed {
+        let state = freed.state.get(&execution.objects);
 
-    // Drop outside of the `rt::execution` block
-    drop(allocation);
+        location::panic(format!(
+            "Use after free: `{}` accessed after the raw allocation containing it was \
+             deallocated.",
+            name
+        ))
+        .location("allocated", state.allocated)
+        .location("deallocated", state.deallocated)
+        .thread("accessed", execution.threads.active_id(), location)
+        .fire();
+    }
 }
 
 impl Allocation {
-    pub(crate) fn new() -> Allocation {
+    pub(crate) fn new(type_name: &'static str, location: Location) -> Allocation {
         rt::execution(|execution| {
-            let state = execution.objects.insert(State { is_dropped: false });
+            let state = execution.objects.insert(State {
+                is_dropped: false,
+                type_name: Some(type_name),
+                allocated: location,
+                size: 0,
+                deallocated: Location::disabled(),
+                owner: Owner::Any,
+                handoff: Synchronize::new(),
+                handed_off: Location::disabled(),
+            });
 
             Allocation { state }
         })
     }
+
+    /// Checks that the current thread owns the tracked value.
+    pub(crate) fn access(&self, location: Location) {
+        rt::execution(|execution| {
+            let thread = execution.threads.active_id();
+            let state = self.state.get(&execution.objects);
+            state.check_owned(thread, "accessed", location);
+        });
+    }
+
+    /// Releases the tracked value to the thread acquiring it next.
+    pub(crate) fn publish(&self, location: Location) {
+        rt::execution(|execution| {
+            let thread = execution.threads.active_id();
+            let state = self.state.get_mut(&mut execution.objects);
+            state.check_owned(thread, "published", location);
+
+            state.handoff.sync_store(&mut execution.threads, Release);
+            state.owner = Owner::Published(thread);
+            state.handed_off = location;
+        });
+    }
+
+    /// Takes ownership of the published value, synchronizing with the thread
+    /// that published it.
+    pub(crate) fn acquire(&self, location: Location) {
+        rt::execution(|execution| {
+            let thread = execution.threads.active_id();
+            let state = self.state.get_mut(&mut execution.objects);
+            state.check_not_dropped(thread, "acquired", location);
+
+            if let Owner::Published(_) = state.owner {
+                state.handoff.sync_load(&mut execution.threads, Acquire);
+                state.owner = Owner::Thread(thread);
+                state.handed_off = location;
+                return;
+            }
+
+            let mut panic = location::panic(format!(
+                "{} acquired by thread #{}, but it was not published.",
+                state.name(),
+                thread
+            ));
+
+            panic.location("created", state.allocated);
+
+            if let Owner::Thread(owner) = state.owner {
+                panic.thread("owned", owner, state.handed_off);
+            }
+
+            panic.thread("acquired", thread, location).fire();
+        });
+    }
 }
 
 impl Drop for Allocation {
     fn drop(&mut self) {
         rt::execution(|execution| {
+            let thread = execution.threads.active_id();
             let state = self.state.get_mut(&mut execution.objects);
+
+            // Do not panic again while unwinding from a failure.
+            if !std::thread::panicking() {
+                if state.is_dropped {
+                    location::panic(format!("Double free: {} dropped twice.", state.name()))
+                        .location("created", state.allocated)
+                        .thread("dropped", thread, Location::disabled())
+                        .fire();
+                }
+
+                state.check_owned(thread, "dropped", Location::disabled());
+            }
+
             state.is_dropped = true;
         });
     }
 }
 
+impl Quarantine {
+    /// Releases the memory of the deallocated raw allocations.
+    pub(super) fn release(&mut self) {
+        for freed in self.freed.drain(..) {
+            // Safety: the allocation was deallocated with this layout, the
+            // memory was only held back.
+            unsafe { std::alloc::dealloc(freed.ptr as *mut u8, freed.layout) };
+        }
+    }
+}
+
+impl Drop for Quarantine {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
 impl State {
-    pub(super) fn check_for_leaks(&self) {
-        assert!(self.is_dropped, "object leaked");
+    pub(super) fn location(&self) -> Location {
+        self.allocated
+    }
+
+    /// Describes the allocation if it leaked.
+    pub(super) fn leak(&self) -> Option<String> {
+        if self.is_dropped {
+            return None;
+        }
+
+        Some(self.name())
+    }
+
+    fn name(&self) -> String {
+        match self.type_name {
+            Some(type_name) => format!("`Track<{}>`", type_name),
+            None => "raw allocation".to_string(),
+        }
+    }
+
+    /// Fails the execution if the tracked value was dropped.
+    fn check_not_dropped(&self, thread: thread::Id, action: &str, location: Location) {
+        if self.is_dropped {
+            location::panic(format!(
+                "Use after free: {} {} after it was dropped.",
+                self.name(),
+                action
+            ))
+            .location("created", self.allocated)
+            .thread(action, thread, location)
+            .fire();
+        }
+    }
+
+    /// Fails the execution if the tracked value was dropped, or was handed
+    /// off and is not owned by `thread`.
+    fn check_owned(&self, thread: thread::Id, action: &str, location: Location) {
+        self.check_not_dropped(thread, action, location);
+
+        let msg = match self.owner {
+            Owner::Any => return,
+            Owner::Thread(owner) if owner == thread => return,
+            Owner::Thread(owner) => format!(
+                "{} {} by thread #{}, but it is owned by thread #{}; hand it off with \
+                 `publish` and `acquire`.",
+                self.name(),
+                action,
+                thread,
+                owner
+            ),
+            Owner::Published(publisher) => format!(
+                "{} {} by thread #{}, but it was published by thread #{} and not acquired \
+                 since.",
+                self.name(),
+                action,
+                thread,
+                publisher
+            ),
+        };
+
+        location::panic(msg)
+            .location("created", self.allocated)
+            .location("handed off", self.handed_off)
+            .thread(action, thread, location)
+            .fire();
     }
 }
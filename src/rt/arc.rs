@@ -1,3 +1,4 @@
+use crate::rt::location::{self, LocationSet};
 use crate::rt::object;
 use crate::rt::{self, Access, Location, Synchronize, VersionVec};
 
@@ -8,11 +9,14 @@ pub(crate) struct Arc {
     state: object::Ref<State>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(super) struct State {
     /// Reference count
     ref_cnt: usize,
 
+    /// Name of the type held by the arc
+    type_name: &'static str,
+
     /// Location where the arc was allocated
     allocated: Location,
 
@@ -24,6 +28,20 @@ pub(super) struct State {
     /// Tracks access to the arc object
     last_ref_inc: Option<Access>,
     last_ref_dec: Option<Access>,
+
+    /// `true` for a reference count implemented by the model itself, see
+    /// `loom::sync::RefCount`. The reference count is only checked, it does
+    /// not synchronize threads.
+    checked: bool,
+
+    /// Set once a checked reference count frees the value it counts.
+    freed: bool,
+
+    /// Causality of the threads when they dropped a checked reference.
+    dropped: VersionVec,
+
+    /// Where each thread last dropped a checked reference.
+    dropped_locations: LocationSet,
 }
 
 /// Actions performed on the Arc
@@ -45,14 +63,28 @@ pub(super) enum Action {
 }
 
 impl Arc {
-    pub(crate) fn new(location: Location) -> Arc {
+    pub(crate) fn new(type_name: &'static str, location: Location) -> Arc {
+        Arc::create(type_name, location, false)
+    }
+
+    /// Create a checked reference count, holding one reference.
+    pub(crate) fn new_checked(type_name: &'static str, location: Location) -> Arc {
+        Arc::create(type_name, location, true)
+    }
+
+    fn create(type_name: &'static str, location: Location, checked: bool) -> Arc {
         rt::execution(|execution| {
             let state = execution.objects.insert(State {
                 ref_cnt: 1,
+                type_name,
                 allocated: location,
                 synchronize: Synchronize::new(),
                 last_ref_inc: None,
                 last_ref_dec: None,
+                checked,
+                freed: false,
+                dropped: VersionVec::new(),
+                dropped_locations: LocationSet::new(),
             });
 
             Arc { state }
@@ -118,6 +150,71 @@ impl Arc {
         })
     }
 
+    /// Record a new reference of a checked reference count.
+    pub(crate) fn checked_ref_inc(&self) {
+        rt::execution(|execution| {
+            let state = self.state.get_mut(&mut execution.objects);
+
+            assert!(
+                !state.freed,
+                "`{}` incremented after being freed",
+                state.type_name
+            );
+            state.ref_cnt = state.ref_cnt.checked_add(1).expect("overflow");
+        })
+    }
+
+    /// Record dropping a reference of a checked reference count.
+    pub(crate) fn checked_ref_dec(&self, location: Location) {
+        rt::execution(|execution| {
+            let state = self.state.get_mut(&mut execution.objects);
+
+            assert!(
+                state.ref_cnt >= 1 && !state.freed,
+                "`{}` decremented more times than it was incremented",
+                state.type_name
+            );
+
+            state.ref_cnt -= 1;
+            state.dropped.join(&execution.threads.active().causality);
+            state.dropped_locations.track(location, &execution.threads);
+        })
+    }
+
+    /// Record freeing the value counted by a checked reference count.
+    ///
+    /// All references must have been dropped, and the drops must happen
+    /// before the value is freed.
+    pub(crate) fn checked_free(&self, location: Location) {
+        rt::execution(|execution| {
+            let state = self.state.get_mut(&mut execution.objects);
+
+            assert!(!state.freed, "`{}` freed twice", state.type_name);
+            assert!(
+                state.ref_cnt == 0,
+                "`{}` freed with {} outstanding reference{}",
+                state.type_name,
+                state.ref_cnt,
+                if state.ref_cnt == 1 { "" } else { "s" }
+            );
+
+            let current = &execution.threads.active().causality;
+
+            if let Some(dropped) = current.ahead(&state.dropped) {
+                location::panic(format!(
+                    "Causality violation: `{}` freed concurrently with dropping a reference.",
+                    state.type_name
+                ))
+                .location("created", state.allocated)
+                .thread("dropped", dropped, state.dropped_locations[dropped])
+                .thread("freed", execution.threads.active_id(), location)
+                .fire();
+            }
+
+            state.freed = true;
+        })
+    }
+
     fn branch(&self, action: Action) {
         let r = self.state;
         r.branch_action(action);
@@ -131,14 +228,35 @@ impl Arc {
 }
 
 impl State {
-    pub(super) fn check_for_leaks(&self) {
-        if self.ref_cnt != 0 {
-            if self.allocated.is_captured() {
-                panic!("Arc leaked.\n  Allocated: {}", self.allocated);
-            } else {
-                panic!("Arc leaked.");
-            }
+    pub(super) fn location(&self) -> Location {
+        self.allocated
+    }
+
+    /// Describes the arc if it leaked.
+    pub(super) fn leak(&self) -> Option<String> {
+        if self.checked {
+            return match (self.ref_cnt, self.freed) {
+                (_, true) => None,
+                (0, false) => Some(format!("`{}` never freed", self.type_name)),
+                (n, false) => Some(format!(
+                    "`{}` with {} outstanding reference{}",
+                    self.type_name,
+                    n,
+                    if n == 1 { "" } else { "s" }
+                )),
+            };
         }
+
+        if self.ref_cnt == 0 {
+            return None;
+        }
+
+        Some(format!(
+            "`Arc<{}>` with {} outstanding reference{}",
+            self.type_name,
+            self.ref_cnt,
+            if self.ref_cnt == 1 { "" } else { "s" }
+        ))
     }
 
     pub(super) fn last_dependent_access(&self, action: Action) -> Option<&Access> {
@@ -28,16 +28,37 @@
 //!   The `modification_order` is initialized to the thread's causality. Any
 //!   store that happened in the thread causality will be earlier in the
 //!   modification order.
-
+//!
+//! # Release sequences
+//!
+//! Each store tracks, in `sync`, the causality released to the loads acquiring
+//! it. A release store heads a release sequence: it releases the causality of
+//! the storing thread. Following the C++20 rules, the sequence continues
+//! through the RMW operations reading from it, by any thread, as an RMW starts
+//! from the `sync` of the store it reads. Any other store, including a relaxed
+//! store of the thread heading the sequence, starts from an empty `sync` and
+//! ends the sequence. A failed compare-and-exchange does not store, it
+//! neither continues nor ends the sequence.
+//!
+//! An RMW reads the store preceding it in the modification order: either the
+//! latest store, see `match_rmw_to_stores`, or an older store a load could
+//! read and no other RMW read, the RMW then being ordered before the stores
+//! following it. No store is ever ordered between them, see `order_rmws`.
+//! Chains of RMWs by different threads continue the sequence transitively. A
+//! failing compare-and-exchange is a load, it may also read an older store,
+//! see `Atomic::compare_exchange`.
+
+use crate::model::MemoryModel;
 use crate::rt::location::{self, Location, LocationSet};
 use crate::rt::object;
+use crate::rt::synchronize;
 use crate::rt::{
     self, thread, Access, Numeric, Synchronize, VersionVec, MAX_ATOMIC_HISTORY, MAX_THREADS,
 };
 
 use std::cmp;
 use std::marker::PhantomData;
-use std::sync::atomic::Ordering;
+use std::sync::atomic::Ordering::{self, *};
 use std::u16;
 
 #[derive(Debug)]
@@ -46,7 +67,7 @@ pub(crate) struct Atomic<T> {
     _p: PhantomData<fn() -> T>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(super) struct State {
     /// Where the atomic was created
     created_location: Location,
@@ -83,19 +104,34 @@ pub(super) struct State {
     /// access to the cell.
     is_mutating: bool,
 
-    /// Last time the atomic was accessed. This tracks the dependent access for
-    /// the DPOR algorithm.
-    last_access: Option<Access>,
+    /// `true` when the atomic is never accessed concurrently. Accesses are not
+    /// branch points and conflicting accesses must happen-before each other.
+    no_concurrent_access: bool,
 
-    /// Last time the atomic was accessed for a store or rmw operation.
+    /// Last time the atomic was accessed for a store or rmw operation. This
+    /// tracks the dependent access for the DPOR algorithm.
     last_non_load_access: Option<Access>,
 
-    /// Currently tracked stored values. This is the `MAX_ATOMIC_HISTORY` most
-    /// recent stores to the atomic cell in loom execution order.
+    /// Loads since the last store or rmw operation, at most one per thread.
+    /// Loads are independent of each other, the next store depends on all of
+    /// them.
+    last_loads: Vec<Access>,
+
+    /// Currently tracked stored values. Once `MAX_ATOMIC_HISTORY` stores are
+    /// tracked, a new store replaces one that no thread can observe anymore,
+    /// or the oldest one if all of them may still be observed.
     stores: [Store; MAX_ATOMIC_HISTORY],
 
+    /// Index of the most recent store in `stores` not followed by another
+    /// store in modification order.
+    last: usize,
+
     /// The total number of stores to the cell.
     cnt: u16,
+
+    /// Number and value of the last store each thread read or made, used to
+    /// detect ABAs.
+    last_seen: [Option<(u16, u64)>; MAX_THREADS],
 }
 
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -110,7 +146,7 @@ pub(super) enum Action {
     Rmw,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct Store {
     /// The stored value. All atomic types can be converted to `u64`.
     value: u64,
@@ -128,38 +164,158 @@ struct Store {
     /// Tracks when each thread first saw value
     first_seen: FirstSeen,
 
-    /// True when the store was done with `SeqCst` ordering
-    seq_cst: bool,
+    /// When the store was done with `SeqCst` ordering, its position in the
+    /// total order of `SeqCst` operations.
+    seq_cst: Option<usize>,
+
+    /// Number of the store, in the order the stores to the cell happened.
+    number: u16,
+
+    /// When the store is made by an RMW, the number of the store it read.
+    rmw_of: Option<u16>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct FirstSeen([u16; MAX_THREADS]);
 
+/// Why a store cannot be returned by an atomic load. Stores are referred to
+/// by their index in the history.
+#[derive(Debug, Clone, Copy)]
+enum Hidden {
+    /// The load is `SeqCst` and the store is ordered before the given, more
+    /// recent, `SeqCst` store.
+    SeqCst(usize),
+
+    /// The given store is later in the modification order and was observed
+    /// in the causality of the loading thread.
+    Overwritten(usize),
+
+    /// The store was observed before the loading thread last yielded.
+    /// Returning it again would not advance the model.
+    SeenBeforeYield,
+
+    /// The given store is later in the modification order and precedes a
+    /// `SeqCst` fence of the loading thread.
+    SeqCstFence(usize),
+}
+
+/// Orders `SeqCst` fences.
+///
+/// All `SeqCst` fences of an execution share a single `Fence` object. Fences
+/// are dependent on each other, which lets DPOR explore the different orders
+/// they may execute in.
+#[derive(Debug, Clone)]
+pub(super) struct Fence {
+    last_access: Option<Access>,
+}
+
+/// Orderings of each kind of atomic operation, as accepted by `std`.
+const LOAD_ORDERINGS: &[Ordering] = &[Relaxed, Acquire, SeqCst];
+const STORE_ORDERINGS: &[Ordering] = &[Relaxed, Release, SeqCst];
+const RMW_ORDERINGS: &[Ordering] = &[Relaxed, Acquire, Release, AcqRel, SeqCst];
+const FAILURE_ORDERINGS: &[Ordering] = &[Relaxed, Acquire, SeqCst];
+const FENCE_ORDERINGS: &[Ordering] = &[Acquire, Release, AcqRel, SeqCst];
+
+/// Panics if `ordering` is not one of the `allowed` orderings of the `op`
+/// operation, naming the operation and where it was attempted.
+///
+/// Every ordering passed to the runtime is checked here first, so that an
+/// invalid ordering fails like it does with `std`, rather than being silently
+/// weakened or strengthened. Orderings loom does not know about, see
+/// `synchronize::unsupported`, are rejected too.
+fn check_ordering(op: &str, ordering: Ordering, allowed: &[Ordering], location: Location) {
+    if allowed.contains(&ordering) {
+        return;
+    }
+
+    let allowed: Vec<_> = allowed.iter().map(|o| format!("`{:?}`", o)).collect();
+
+    rt::execution(|execution| {
+        location::panic(format!(
+            "invalid memory ordering `{:?}` for {}; expected one of {}",
+            ordering,
+            op,
+            allowed.join(", ")
+        ))
+        .thread("attempted", execution.threads.active_id(), location)
+        .fire();
+    });
+}
+
+/// Prints the stores each atomic load of `f` may observe, and why the other
+/// ones are hidden from it.
+pub(crate) fn explain<R>(f: impl FnOnce() -> R) -> R {
+    struct Reset(bool);
+
+    impl Drop for Reset {
+        fn drop(&mut self) {
+            let prev = self.0;
+            rt::execution(|execution| execution.threads.active_mut().explain = prev);
+        }
+    }
+
+    let prev = rt::execution(|execution| {
+        std::mem::replace(&mut execution.threads.active_mut().explain, true)
+    });
+
+    let _reset = Reset(prev);
+    f()
+}
+
 /// Implements atomic fence behavior
-pub(crate) fn fence(ordering: Ordering) {
-    use std::sync::atomic::Ordering::Acquire;
-
-    assert_eq!(
-        ordering, Acquire,
-        "only Acquire fences are currently supported"
-    );
-
-    rt::synchronize(|execution| {
-        // Find all stores for all atomic objects and, if they have been read by
-        // the current thread, establish an acquire synchronization.
-        for state in execution.objects.iter_mut::<State>() {
-            // Iterate all the stores
-            for store in state.stores_mut() {
-                if !store.first_seen.is_seen_by_current(&execution.threads) {
-                    continue;
-                }
+pub(crate) fn fence(ordering: Ordering, location: Location) {
+    check_ordering("a fence", ordering, FENCE_ORDERINGS, location);
 
-                store.sync.sync_load(&mut execution.threads, ordering);
-            }
+    if ordering == SeqCst {
+        let fence = rt::execution(|execution| {
+            let fence = execution.objects.iter_ref::<Fence>().next();
+
+            fence.unwrap_or_else(|| execution.objects.insert(Fence { last_access: None }))
+        });
+
+        fence.branch_opaque();
+    }
+
+    rt::synchronize(|execution| match ordering {
+        Acquire => acquire_fence(execution),
+        Release => release_fence(execution),
+        AcqRel => {
+            acquire_fence(execution);
+            release_fence(execution);
+        }
+        SeqCst => {
+            acquire_fence(execution);
+            release_fence(execution);
+            execution.threads.seq_cst_fence();
         }
+        order => synchronize::unsupported(order),
     });
 }
 
+fn acquire_fence(execution: &mut rt::Execution) {
+    // Find all stores for all atomic objects and, if they have been read by
+    // the current thread, establish an acquire synchronization.
+    for state in execution.objects.iter_mut::<State>() {
+        // Iterate all the stores
+        for store in state.stores_mut() {
+            if !store.first_seen.is_seen_by_current(&execution.threads) {
+                continue;
+            }
+
+            store
+                .sync
+                .sync_load(&mut execution.threads, Ordering::Acquire);
+        }
+    }
+}
+
+fn release_fence(execution: &mut rt::Execution) {
+    // Any store following the fence releases the thread's causality as of the
+    // fence. See `Synchronize::sync_store`.
+    let active = execution.threads.active_mut();
+    active.released = active.causality;
+}
+
 impl<T: Numeric> Atomic<T> {
     /// Create a new, atomic cell initialized with the provided value
     pub(crate) fn new(value: T, location: Location) -> Atomic<T> {
@@ -174,26 +330,49 @@ impl<T: Numeric> Atomic<T> {
         })
     }
 
+    /// Returns an owner releasing the atomic when dropped.
+    pub(crate) fn owner(&self) -> object::Owner {
+        rt::execution(|execution| self.state.owner(execution))
+    }
+
     /// Loads a value from the atomic cell.
     pub(crate) fn load(&self, location: Location, ordering: Ordering) -> T {
+        check_ordering("an atomic load", ordering, LOAD_ORDERINGS, location);
         self.branch(Action::Load);
 
         super::synchronize(|execution| {
-            let state = self.state.get_mut(&mut execution.objects);
-
-            // If necessary, generate the list of stores to permute through
-            if execution.path.is_traversed() {
-                let mut seed = [0; MAX_ATOMIC_HISTORY];
+            let ordering = checked_ordering(execution, ordering);
+            let mut seed = [0; MAX_ATOMIC_HISTORY];
+            let n = self.state.get(&execution.objects).match_load_to_stores(
+                &execution.threads,
+                &mut seed[..],
+                ordering,
+            );
 
-                let n = state.match_load_to_stores(&execution.threads, &mut seed[..], ordering);
+            let index = self.pick_store(execution, &seed[..n], "load");
+            let state = self.state.get_mut(&mut execution.objects);
 
-                execution.path.push_load(&seed[..n]);
+            if execution.threads.active().explain {
+                print!(
+                    "{}",
+                    state.explain_load(&execution.threads, location, ordering, index)
+                );
             }
 
-            // Get the store to return from this load.
-            let index = execution.path.branch_load();
+            let value = state.load(&mut execution.threads, index, location, ordering);
+
+            log!(
+                execution.log,
+                Debug,
+                "atomic",
+                "thread #{}: atomic #{} load({:?}) -> {}",
+                execution.threads.active_id(),
+                self.state.erase().as_usize(),
+                ordering,
+                value
+            );
 
-            T::from_u64(state.load(&mut execution.threads, index, location, ordering))
+            T::from_u64(value)
         })
     }
 
@@ -210,16 +389,18 @@ impl<T: Numeric> Atomic<T> {
             state.track_unsync_load(&execution.threads);
 
             // Return the value
-            let index = index(state.cnt - 1);
+            let index = state.last;
             T::from_u64(state.stores[index].value)
         })
     }
 
     /// Stores a value into the atomic cell.
     pub(crate) fn store(&self, location: Location, val: T, ordering: Ordering) {
+        check_ordering("an atomic store", ordering, STORE_ORDERINGS, location);
         self.branch(Action::Store);
 
         super::synchronize(|execution| {
+            let ordering = checked_ordering(execution, ordering);
             let state = self.state.get_mut(&mut execution.objects);
 
             state.stored_locations.track(location, &execution.threads);
@@ -228,12 +409,26 @@ impl<T: Numeric> Atomic<T> {
             // cell.
             state.track_store(&execution.threads);
 
+            let value = val.into_u64();
+
             // Do the store
             state.store(
                 &mut execution.threads,
                 Synchronize::new(),
-                val.into_u64(),
+                value,
+                ordering,
+                None,
+            );
+
+            log!(
+                execution.log,
+                Debug,
+                "atomic",
+                "thread #{}: atomic #{} store({:?}) <- {}",
+                execution.threads.active_id(),
+                self.state.erase().as_usize(),
                 ordering,
+                value
             );
         })
     }
@@ -248,32 +443,188 @@ impl<T: Numeric> Atomic<T> {
     where
         F: FnOnce(T) -> Result<T, E>,
     {
+        self.try_rmw(location, success, failure, None, f)
+    }
+
+    /// Stores `new` if the value of the atomic cell is `current`, returning
+    /// the value read.
+    ///
+    /// Like any RMW, a successful compare-and-exchange may read an older
+    /// store, see `try_rmw`. A failing compare-and-exchange does not store
+    /// though, it is a load with the `failure` ordering: it may also read an
+    /// older store, as long as its value is not `current`.
+    pub(crate) fn compare_exchange(
+        &self,
+        location: Location,
+        current: T,
+        new: T,
+        success: Ordering,
+        failure: Ordering,
+    ) -> Result<T, T> {
+        self.try_rmw(
+            location,
+            success,
+            failure,
+            Some(current.into_u64()),
+            |actual| {
+                if actual == current {
+                    Ok(new)
+                } else {
+                    Err(actual)
+                }
+            },
+        )
+    }
+
+    /// Read-modify-write.
+    ///
+    /// The read portion may return any store a load with the `success`
+    /// ordering could, unless another RMW already read it. The new store is
+    /// then ordered right after the store read, before the stores following
+    /// it in modification order.
+    ///
+    /// When `expected` is set, `f` fails on any other value and the operation
+    /// may read the older stores a load with the `failure` ordering could,
+    /// failing.
+    fn try_rmw<F, E>(
+        &self,
+        location: Location,
+        success: Ordering,
+        failure: Ordering,
+        expected: Option<u64>,
+        f: F,
+    ) -> Result<T, E>
+    where
+        F: FnOnce(T) -> Result<T, E>,
+    {
+        check_ordering(
+            "an atomic read-modify-write",
+            success,
+            RMW_ORDERINGS,
+            location,
+        );
+        check_ordering(
+            "the failure of a compare-and-exchange",
+            failure,
+            FAILURE_ORDERINGS,
+            location,
+        );
         self.branch(Action::Rmw);
 
         super::synchronize(|execution| {
+            let success = checked_ordering(execution, success);
+            let failure = checked_ordering(execution, failure);
+            let state = self.state.get(&execution.objects);
+
+            // The read portion of the rmw operation reads the latest stores
+            // in modification order, or an older store a load could read.
+            let mut latest = [0; MAX_ATOMIC_HISTORY];
+            let n_latest = state.match_rmw_to_stores(&mut latest[..]);
+
+            let mut seed = latest;
+            let mut n = n_latest;
+
+            let mut visible = [0; MAX_ATOMIC_HISTORY];
+            let n_visible =
+                state.match_load_to_stores(&execution.threads, &mut visible[..], success);
+
+            for &i in &visible[..n_visible] {
+                let value = state.stores[usize::from(i)].value;
+
+                if !latest[..n_latest].contains(&i)
+                    && expected.unwrap_or(value) == value
+                    && !state.is_read_by_rmw(usize::from(i))
+                {
+                    seed[n] = i;
+                    n += 1;
+                }
+            }
+
+            // A failing compare-and-exchange is a load with the `failure`
+            // ordering.
+            let n_rmw = n;
+
+            if let Some(expected) = expected {
+                let mut stale = [0; MAX_ATOMIC_HISTORY];
+                let n_stale =
+                    state.match_load_to_stores(&execution.threads, &mut stale[..], failure);
+
+                for &i in &stale[..n_stale] {
+                    if !seed[..n].contains(&i) && state.stores[usize::from(i)].value != expected {
+                        seed[n] = i;
+                        n += 1;
+                    }
+                }
+            }
+
+            let index = self.pick_store(execution, &seed[..n], "rmw");
             let state = self.state.get_mut(&mut execution.objects);
 
-            // If necessary, generate the list of stores to permute through
-            if execution.path.is_traversed() {
-                let mut seed = [0; MAX_ATOMIC_HISTORY];
+            if !seed[..n_rmw].contains(&(index as u8)) {
+                let value = state.load(&mut execution.threads, index, location, failure);
+
+                log!(
+                    execution.log,
+                    Debug,
+                    "atomic",
+                    "thread #{}: atomic #{} rmw({:?}) read stale {}, failed",
+                    execution.threads.active_id(),
+                    self.state.erase().as_usize(),
+                    failure,
+                    value
+                );
 
-                let n = state.match_rmw_to_stores(&mut seed[..]);
-                execution.path.push_load(&seed[..n]);
+                return match f(T::from_u64(value)) {
+                    Err(e) => Err(e),
+                    Ok(_) => panic!("[loom internal bug] rmw succeeded on a stale store"),
+                };
             }
 
-            // Get the store to use for the read portion of the rmw operation.
-            let index = execution.path.branch_load();
+            let prev = state.stores[index].value;
+            let mut next = None;
 
-            state
-                .rmw(
-                    &mut execution.threads,
-                    index,
-                    location,
+            if execution.detect_aba && expected == Some(prev) {
+                state.check_aba(&execution.threads, index, location);
+            }
+
+            let res = state.rmw(
+                &mut execution.threads,
+                index,
+                location,
+                success,
+                failure,
+                |num| {
+                    let res = f(T::from_u64(num)).map(T::into_u64);
+                    next = res.as_ref().ok().copied();
+                    res
+                },
+            );
+
+            match next {
+                Some(next) => log!(
+                    execution.log,
+                    Debug,
+                    "atomic",
+                    "thread #{}: atomic #{} rmw({:?}) {} -> {}",
+                    execution.threads.active_id(),
+                    self.state.erase().as_usize(),
                     success,
+                    prev,
+                    next
+                ),
+                None => log!(
+                    execution.log,
+                    Debug,
+                    "atomic",
+                    "thread #{}: atomic #{} rmw({:?}) read {}, failed",
+                    execution.threads.active_id(),
+                    self.state.erase().as_usize(),
                     failure,
-                    |num| f(T::from_u64(num)).map(T::into_u64),
-                )
-                .map(T::from_u64)
+                    prev
+                ),
+            }
+
+            res.map(T::from_u64)
         })
     }
 
@@ -292,7 +643,7 @@ impl<T: Numeric> Atomic<T> {
             state.is_mutating = true;
 
             // Return the value of the most recent store
-            let index = index(state.cnt - 1);
+            let index = state.last;
             T::from_u64(state.stores[index].value)
         });
 
@@ -309,7 +660,7 @@ impl<T: Numeric> Atomic<T> {
 
                     // The value may have been mutated, so it must be placed
                     // back.
-                    let index = index(state.cnt - 1);
+                    let index = state.last;
                     state.stores[index].value = T::into_u64(self.0);
 
                     if !std::thread::panicking() {
@@ -324,8 +675,76 @@ impl<T: Numeric> Atomic<T> {
         f(&mut reset.0)
     }
 
+    /// Stores a value into the atomic cell without performing synchronization.
+    ///
+    /// Like `with_mut`, the store must happen-after all other accesses to the
+    /// cell, and all following accesses must happen-after it.
+    pub(crate) fn unsync_store(&self, location: Location, val: T) {
+        rt::execution(|execution| {
+            let state = self.state.get_mut(&mut execution.objects);
+
+            state
+                .unsync_mut_locations
+                .track(location, &execution.threads);
+            state.track_unsync_mut(&execution.threads);
+
+            // Replace the value of the most recent store, which happens
+            // before this one.
+            let index = state.last;
+            state.stores[index].value = val.into_u64();
+        })
+    }
+
+    /// Marks the atomic as never accessed concurrently.
+    pub(crate) fn no_concurrent_access(&self, location: Location) {
+        rt::execution(|execution| {
+            let state = self.state.get_mut(&mut execution.objects);
+
+            // Accesses made so far must happen-before the ones that follow.
+            state.stored_locations.track(location, &execution.threads);
+            state.track_store(&execution.threads);
+
+            state.no_concurrent_access = true;
+        })
+    }
+
+    /// Returns the store read by an operation of the active thread, among the
+    /// `seed` candidates. When only one store may be read, there is nothing
+    /// to explore and no branch is added to the path.
+    fn pick_store(&self, execution: &mut rt::Execution, seed: &[u8], op: &str) -> usize {
+        if seed.len() == 1 {
+            return usize::from(seed[0]);
+        }
+
+        if execution.path.is_traversed() {
+            execution.path.push_load(seed);
+
+            if let Some(counts) = &mut execution.branch_counts {
+                counts.add(self.state.erase(), seed.len().saturating_sub(1));
+            }
+        }
+
+        log!(
+            execution.log,
+            Trace,
+            "atomic",
+            "thread #{}: atomic #{} {} may read {} stores",
+            execution.threads.active_id(),
+            self.state.erase().as_usize(),
+            op,
+            seed.len()
+        );
+
+        execution.path.branch_load()
+    }
+
     fn branch(&self, action: Action) {
         let r = self.state;
+
+        if rt::execution(|execution| r.get(&execution.objects).no_concurrent_access) {
+            return;
+        }
+
         r.branch_action(action);
         assert!(
             r.ref_eq(self.state),
@@ -339,6 +758,10 @@ impl<T: Numeric> Atomic<T> {
 // ===== impl State =====
 
 impl State {
+    pub(super) fn location(&self) -> Location {
+        self.created_location
+    }
+
     fn new(threads: &mut thread::Set, value: u64, location: Location) -> State {
         let mut state = State {
             created_location: location,
@@ -351,9 +774,12 @@ impl State {
             unsync_mut_at: VersionVec::new(),
             unsync_mut_locations: LocationSet::new(),
             is_mutating: false,
-            last_access: None,
+            no_concurrent_access: false,
             last_non_load_access: None,
+            last_loads: Vec::new(),
+            last_seen: [None; MAX_THREADS],
             stores: Default::default(),
+            last: 0,
             cnt: 0,
         };
 
@@ -367,7 +793,7 @@ impl State {
         // creation of this atomic cell.
         //
         // This is verified using `cell`.
-        state.store(threads, Synchronize::new(), value, Ordering::Release);
+        state.store(threads, Synchronize::new(), value, Ordering::Release, None);
 
         state
     }
@@ -384,12 +810,15 @@ impl State {
         self.track_load(threads);
 
         // Apply coherence rules
-        self.apply_load_coherence(threads, index);
+        let view = coherence_view(threads, ordering);
+        self.check_coherence(threads, &view, index, location, false);
+        self.apply_load_coherence(threads, &view, index);
 
         let store = &mut self.stores[index];
 
         store.first_seen.touch(threads);
         store.sync.sync_load(threads, ordering);
+        self.last_seen[threads.active_id().as_usize()] = Some((store.number, store.value));
         store.value
     }
 
@@ -399,8 +828,16 @@ impl State {
         mut sync: Synchronize,
         value: u64,
         ordering: Ordering,
+        rmw_of: Option<u16>,
     ) {
-        let index = index(self.cnt);
+        let index = if (self.cnt as usize) < MAX_ATOMIC_HISTORY {
+            self.cnt as usize
+        } else {
+            self.evict(threads)
+        };
+
+        let number = self.cnt;
+        let last = self.last;
 
         // Increment the count
         self.cnt += 1;
@@ -413,10 +850,12 @@ impl State {
         // Starting with the thread's causality covers WRITE-WRITE coherence
         let mut modification_order = happens_before;
 
+        let view = coherence_view(threads, ordering);
+
         // Apply coherence rules
         for i in 0..self.stores.len() {
             // READ-WRITE coherence
-            if self.stores[i].first_seen.is_seen_by_current(threads) {
+            if self.stores[i].first_seen.is_seen_by(&view, threads) {
                 let mo = self.stores[i].modification_order;
                 modification_order.join(&mo);
             }
@@ -434,8 +873,132 @@ impl State {
             modification_order,
             sync,
             first_seen,
-            seq_cst: is_seq_cst(ordering),
+            seq_cst: if is_seq_cst(ordering) {
+                Some(threads.next_seq_cst())
+            } else {
+                None
+            },
+            number,
+            rmw_of,
         };
+
+        self.last_seen[threads.active_id().as_usize()] = Some((number, value));
+        self.order_rmws();
+
+        // An RMW reading an older store is not the latest store in
+        // modification order.
+        let mo = self.stores[index].modification_order;
+
+        if mo.partial_cmp(&self.stores[last].modification_order) != Some(cmp::Ordering::Less) {
+            self.last = index;
+        }
+    }
+
+    /// Returns `true` if an RMW already read the store at index `i`. Each
+    /// store is read by at most one RMW.
+    fn is_read_by_rmw(&self, i: usize) -> bool {
+        let number = self.stores[i].number;
+
+        self.stores[..cmp::min(self.cnt as usize, MAX_ATOMIC_HISTORY)]
+            .iter()
+            .any(|store| store.rmw_of == Some(number))
+    }
+
+    /// Keeps each RMW immediately after the store it read in modification
+    /// order: a store ordered after the read store is ordered after the RMW,
+    /// and a store ordered before the RMW is ordered before the read store.
+    ///
+    /// Ordering a store may order others, the rules are applied until no
+    /// order changes.
+    fn order_rmws(&mut self) {
+        let cnt = cmp::min(self.cnt as usize, MAX_ATOMIC_HISTORY);
+        let precedes =
+            |a: VersionVec, b: VersionVec| a.partial_cmp(&b) == Some(cmp::Ordering::Less);
+
+        loop {
+            let mut changed = false;
+
+            for rmw in 0..cnt {
+                let read = match self.stores[rmw].rmw_of {
+                    Some(number) => number,
+                    None => continue,
+                };
+
+                // The read store may have been evicted from the history.
+                let read = match (0..cnt).find(|&i| self.stores[i].number == read) {
+                    Some(read) => read,
+                    None => continue,
+                };
+
+                for other in 0..cnt {
+                    if other == rmw || other == read {
+                        continue;
+                    }
+
+                    let mo_read = self.stores[read].modification_order;
+                    let mo_rmw = self.stores[rmw].modification_order;
+                    let mo_other = self.stores[other].modification_order;
+
+                    if precedes(mo_read, mo_other) && !precedes(mo_rmw, mo_other) {
+                        self.stores[other].modification_order.join(&mo_rmw);
+                        changed = true;
+                    } else if precedes(mo_other, mo_rmw) && !precedes(mo_other, mo_read) {
+                        self.stores[read].modification_order.join(&mo_other);
+                        changed = true;
+                    }
+                }
+            }
+
+            if !changed {
+                return;
+            }
+        }
+    }
+
+    /// Returns the index of the store to replace with a new one, when the
+    /// history is full.
+    ///
+    /// A store is replaced once no thread can observe it anymore, keeping the
+    /// history of hot atomics to the stores the model may still return.
+    /// Otherwise, the oldest store is replaced.
+    fn evict(&self, threads: &thread::Set) -> usize {
+        let last_seq_cst = self.last_seq_cst(Ordering::SeqCst);
+
+        let mut stores: Vec<_> = (0..MAX_ATOMIC_HISTORY).collect();
+        stores.sort_by_key(|&i| self.stores[i].number);
+
+        stores
+            .iter()
+            .copied()
+            .find(|&i| Some(i) != last_seq_cst && self.is_unobservable(threads, i))
+            .unwrap_or(stores[0])
+    }
+
+    /// Returns `true` if no load can return the store at index `i` anymore,
+    /// and no acquire fence can synchronize with it.
+    ///
+    /// Each thread still running must have observed a store that is later in
+    /// the modification order. Causality only grows, and threads spawned
+    /// later start with the causality of their parent, so the store is never
+    /// observable again.
+    fn is_unobservable(&self, threads: &thread::Set, i: usize) -> bool {
+        let store_i = &self.stores[i];
+
+        threads
+            .iter()
+            .filter(|(_, thread)| !thread.is_terminated())
+            .all(|(_, thread)| {
+                let causality = &thread.causality;
+
+                if store_i.sync.releases_to(causality) {
+                    return false;
+                }
+
+                self.stores.iter().any(|store_j| {
+                    store_i.modification_order < store_j.modification_order
+                        && store_j.first_seen.is_seen_by(causality, threads)
+                })
+            })
     }
 
     fn rmw<E>(
@@ -454,11 +1017,14 @@ impl State {
         self.track_load(threads);
 
         // Apply coherence rules.
-        self.apply_load_coherence(threads, index);
+        let view = coherence_view(threads, success);
+        self.check_coherence(threads, &view, index, location, true);
+        self.apply_load_coherence(threads, &view, index);
 
         self.stores[index].first_seen.touch(threads);
 
         let prev = self.stores[index].value;
+        self.last_seen[threads.active_id().as_usize()] = Some((self.stores[index].number, prev));
 
         match f(prev) {
             Ok(next) => {
@@ -470,10 +1036,11 @@ impl State {
                 self.stores[index].sync.sync_load(threads, success);
 
                 // Store the new value, initializing with the `sync` value from
-                // the load. This is our (hacky) way to establish a release
-                // sequence.
+                // the load. This continues the release sequences the loaded
+                // store is part of, see the module documentation.
                 let sync = self.stores[index].sync;
-                self.store(threads, sync, next, success);
+                let read = self.stores[index].number;
+                self.store(threads, sync, next, success, Some(read));
 
                 Ok(prev)
             }
@@ -484,7 +1051,99 @@ impl State {
         }
     }
 
-    fn apply_load_coherence(&mut self, threads: &mut thread::Set, index: usize) {
+    /// Fails the execution if a compare-and-exchange reading the store at
+    /// `index` succeeds while other threads stored to the cell since the
+    /// active thread last read or stored the expected value: the value was
+    /// changed and changed back, an ABA.
+    fn check_aba(&self, threads: &thread::Set, index: usize, location: Location) {
+        let thread = threads.active_id();
+        let store = &self.stores[index];
+
+        let (number, value) = match self.last_seen[thread.as_usize()] {
+            Some(last_seen) => last_seen,
+            None => return,
+        };
+
+        if store.number == number || store.value != value {
+            return;
+        }
+
+        location::panic(format!(
+            "ABA detected: a compare-and-exchange succeeded reading the value {}, \
+             but other threads stored to the atomic {} time(s) since the thread \
+             observed it.",
+            value,
+            store.number - number
+        ))
+        .location("created", self.created_location)
+        .thread("compare-and-exchange", thread, location)
+        .fire();
+    }
+
+    /// Checks that reading the store at `index` respects the coherence rules.
+    ///
+    /// The store must not precede, in modification order, a store the thread
+    /// already observed (READ-READ) or a store happening before the load
+    /// (WRITE-READ). An rmw must also read a store no other rmw read. Stores
+    /// not ordered yet are ordered by the load, see `apply_load_coherence`.
+    ///
+    /// Loads only pick stores passing these rules, a failure is a bug in loom.
+    fn check_coherence(
+        &self,
+        threads: &thread::Set,
+        view: &VersionVec,
+        index: usize,
+        location: Location,
+        rmw: bool,
+    ) {
+        let loaded = &self.stores[index];
+
+        if rmw && self.is_read_by_rmw(index) {
+            location::panic(
+                "[loom internal bug] coherence violation: an rmw operation read a store \
+                 already read by another rmw operation",
+            )
+            .location("created", self.created_location)
+            .thread("load", threads.active_id(), location)
+            .fire();
+        }
+
+        for (i, store) in self.stores[..cmp::min(self.cnt as usize, MAX_ATOMIC_HISTORY)]
+            .iter()
+            .enumerate()
+        {
+            let older = matches!(
+                loaded
+                    .modification_order
+                    .partial_cmp(&store.modification_order),
+                Some(cmp::Ordering::Less)
+            );
+
+            if i == index || !older {
+                continue;
+            }
+
+            let reason = if store.first_seen.is_seen_by(view, threads) {
+                "an atomic load read a store older, in modification order, than a store \
+                 the thread already observed"
+            } else if store.happens_before < *view {
+                "an atomic load read a store older, in modification order, than a store \
+                 happening before the load"
+            } else {
+                continue;
+            };
+
+            location::panic(format!(
+                "[loom internal bug] coherence violation: {}",
+                reason
+            ))
+            .location("created", self.created_location)
+            .thread("load", threads.active_id(), location)
+            .fire();
+        }
+    }
+
+    fn apply_load_coherence(&mut self, threads: &thread::Set, view: &VersionVec, index: usize) {
         for i in 0..self.stores.len() {
             // Skip if the is current.
             if index == i {
@@ -492,17 +1151,19 @@ impl State {
             }
 
             // READ-READ coherence
-            if self.stores[i].first_seen.is_seen_by_current(threads) {
+            if self.stores[i].first_seen.is_seen_by(view, threads) {
                 let mo = self.stores[i].modification_order;
                 self.stores[index].modification_order.join(&mo);
             }
 
             // WRITE-READ coherence
-            if self.stores[i].happens_before < threads.active().causality {
+            if self.stores[i].happens_before < *view {
                 let mo = self.stores[i].modification_order;
                 self.stores[index].modification_order.join(&mo);
             }
         }
+
+        self.order_rmws();
     }
 
     /// Track an atomic load
@@ -514,11 +1175,24 @@ impl State {
         if let Some(mut_at) = current.ahead(&self.unsync_mut_at) {
             location::panic("Causality violation: Concurrent load and mut accesses.")
                 .location("created", self.created_location)
-                .thread("with_mut", mut_at, self.unsync_mut_locations[mut_at])
+                .thread("unsync mut", mut_at, self.unsync_mut_locations[mut_at])
                 .thread("load", threads.active_id(), self.loaded_locations[threads])
                 .fire();
         }
 
+        if self.no_concurrent_access {
+            if let Some(stored) = current.ahead(&self.stored_at) {
+                location::panic(
+                    "Causality violation: Concurrent atomic store and load on an atomic marked \
+                     `no_concurrent_access`.",
+                )
+                .location("created", self.created_location)
+                .thread("atomic store", stored, self.stored_locations[stored])
+                .thread("load", threads.active_id(), self.loaded_locations[threads])
+                .fire();
+            }
+        }
+
         self.loaded_at.join(current);
     }
 
@@ -531,7 +1205,7 @@ impl State {
         if let Some(mut_at) = current.ahead(&self.unsync_mut_at) {
             location::panic("Causality violation: Concurrent `unsync_load` and mut accesses.")
                 .location("created", self.created_location)
-                .thread("with_mut", mut_at, self.unsync_mut_locations[mut_at])
+                .thread("unsync mut", mut_at, self.unsync_mut_locations[mut_at])
                 .thread(
                     "unsync_load",
                     threads.active_id(),
@@ -564,7 +1238,7 @@ impl State {
         if let Some(mut_at) = current.ahead(&self.unsync_mut_at) {
             location::panic("Causality violation: Concurrent atomic store and mut accesses.")
                 .location("created", self.created_location)
-                .thread("with_mut", mut_at, self.unsync_mut_locations[mut_at])
+                .thread("unsync mut", mut_at, self.unsync_mut_locations[mut_at])
                 .thread(
                     "atomic store",
                     threads.active_id(),
@@ -587,6 +1261,38 @@ impl State {
             .fire();
         }
 
+        if self.no_concurrent_access {
+            if let Some(loaded) = current.ahead(&self.loaded_at) {
+                location::panic(
+                    "Causality violation: Concurrent atomic load and store on an atomic marked \
+                     `no_concurrent_access`.",
+                )
+                .location("created", self.created_location)
+                .thread("load", loaded, self.loaded_locations[loaded])
+                .thread(
+                    "atomic store",
+                    threads.active_id(),
+                    self.stored_locations[threads],
+                )
+                .fire();
+            }
+
+            if let Some(stored) = current.ahead(&self.stored_at) {
+                location::panic(
+                    "Causality violation: Concurrent atomic stores on an atomic marked \
+                     `no_concurrent_access`.",
+                )
+                .location("created", self.created_location)
+                .thread("atomic store one", stored, self.stored_locations[stored])
+                .thread(
+                    "atomic store two",
+                    threads.active_id(),
+                    self.stored_locations[threads],
+                )
+                .fire();
+            }
+        }
+
         self.stored_at.join(current);
     }
 
@@ -601,7 +1307,7 @@ impl State {
                 .location("created", self.created_location)
                 .thread("atomic load", loaded, self.loaded_locations[loaded])
                 .thread(
-                    "with_mut",
+                    "unsync mut",
                     threads.active_id(),
                     self.unsync_mut_locations[threads],
                 )
@@ -615,7 +1321,7 @@ impl State {
             .location("created", self.created_location)
             .thread("unsync_load", loaded, self.unsync_loaded_locations[loaded])
             .thread(
-                "with_mut",
+                "unsync mut",
                 threads.active_id(),
                 self.unsync_mut_locations[threads],
             )
@@ -629,7 +1335,7 @@ impl State {
             .location("created", self.created_location)
             .thread("atomic store", stored, self.stored_locations[stored])
             .thread(
-                "with_mut",
+                "unsync mut",
                 threads.active_id(),
                 self.unsync_mut_locations[threads],
             )
@@ -639,9 +1345,9 @@ impl State {
         if let Some(mut_at) = current.ahead(&self.unsync_mut_at) {
             location::panic("Causality violation: Concurrent unsync mut accesses.")
                 .location("created", self.created_location)
-                .thread("with_mut one", mut_at, self.unsync_mut_locations[mut_at])
+                .thread("unsync mut one", mut_at, self.unsync_mut_locations[mut_at])
                 .thread(
-                    "with_mut two",
+                    "unsync mut two",
                     threads.active_id(),
                     self.unsync_mut_locations[threads],
                 )
@@ -659,62 +1365,195 @@ impl State {
         ordering: Ordering,
     ) -> usize {
         let mut n = 0;
+        let view = coherence_view(threads, ordering);
+        let last_seq_cst = self.last_seq_cst(ordering);
+
+        for i in 0..cmp::min(self.cnt as usize, self.stores.len()) {
+            if self.hidden(threads, &view, last_seq_cst, i).is_none() {
+                // The load may return this store
+                dst[n] = i as u8;
+                n += 1;
+            }
+        }
+
+        n
+    }
+
+    /// A SeqCst load observes the most recent SeqCst store or a store that
+    /// is not SeqCst and does not happen before it.
+    fn last_seq_cst(&self, ordering: Ordering) -> Option<usize> {
+        if !is_seq_cst(ordering) {
+            return None;
+        }
+
+        self.stores[..cmp::min(self.cnt as usize, MAX_ATOMIC_HISTORY)]
+            .iter()
+            .enumerate()
+            .filter_map(|(i, store)| store.seq_cst.map(|order| (order, i)))
+            .max()
+            .map(|(_, i)| i)
+    }
+
+    /// Returns why the store at index `i` cannot be returned by a load of the
+    /// active thread, or `None` if it can.
+    ///
+    /// We only need to consider loads as old as the **most** recent load
+    /// seen by each thread in the current causality.
+    ///
+    /// This probably isn't the smartest way to implement this, but someone
+    /// else can figure out how to improve on it if it turns out to be a
+    /// bottleneck.
+    ///
+    /// All stores are returned **unless** a newer store has already been seen
+    /// by the current thread's causality.
+    fn hidden(
+        &self,
+        threads: &thread::Set,
+        view: &VersionVec,
+        last_seq_cst: Option<usize>,
+        i: usize,
+    ) -> Option<Hidden> {
         let cnt = self.cnt as usize;
+        let store_i = &self.stores[i];
 
-        // We only need to consider loads as old as the **most** recent load
-        // seen by each thread in the current causality.
-        //
-        // This probably isn't the smartest way to implement this, but someone
-        // else can figure out how to improve on it if it turns out to be a
-        // bottleneck.
-        //
-        // Add all stores **unless** a newer store has already been seen by the
-        // current thread's causality.
-        'outer: for i in 0..self.stores.len() {
-            let store_i = &self.stores[i];
+        if let Some(last) = last_seq_cst {
+            let last_store = &self.stores[last];
 
-            if i >= cnt {
-                // Not a real store
-                continue;
+            if i != last
+                && (store_i.seq_cst.is_some() || store_i.happens_before < last_store.happens_before)
+            {
+                // There is a newer SeqCst store
+                return Some(Hidden::SeqCst(last));
             }
+        }
 
-            for j in 0..self.stores.len() {
-                let store_j = &self.stores[j];
+        for j in 0..self.stores.len() {
+            let store_j = &self.stores[j];
 
-                if i == j || j >= cnt {
-                    continue;
-                }
+            if i == j || j >= cnt {
+                continue;
+            }
 
-                let mo_i = store_i.modification_order;
-                let mo_j = store_j.modification_order;
+            let mo_i = store_i.modification_order;
+            let mo_j = store_j.modification_order;
 
-                assert_ne!(mo_i, mo_j);
+            assert_ne!(mo_i, mo_j);
 
-                if mo_i < mo_j {
-                    if store_j.first_seen.is_seen_by_current(threads) {
-                        // Store `j` is newer, so don't store the current one.
-                        continue 'outer;
-                    }
+            if mo_i < mo_j {
+                if store_j.first_seen.is_seen_by(view, threads) {
+                    // Store `j` is newer, so don't store the current one.
+                    return Some(Hidden::Overwritten(j));
+                }
 
-                    if store_i.first_seen.is_seen_before_yield(threads) {
-                        // Saw this load before the previous yield. In order to
-                        // advance the model, don't return it again.
-                        continue 'outer;
-                    }
+                if store_i.first_seen.is_seen_before_yield(threads) {
+                    // Saw this load before the previous yield. In order to
+                    // advance the model, don't return it again.
+                    return Some(Hidden::SeenBeforeYield);
+                }
 
-                    if is_seq_cst(ordering) && store_i.seq_cst && store_j.seq_cst {
-                        // There is a newer SeqCst store
-                        continue 'outer;
+                if let (Some(stored), Some(fenced)) =
+                    (store_j.seq_cst, threads.active().seq_cst_fence)
+                {
+                    if stored < fenced {
+                        // A newer SeqCst store precedes a SeqCst fence
+                        // issued by the current thread.
+                        return Some(Hidden::SeqCstFence(j));
                     }
                 }
             }
+        }
 
-            // The load may return this store
-            dst[n] = i as u8;
-            n += 1;
+        None
+    }
+
+    /// Describes which stores a load of the active thread may observe and
+    /// why, see [`explain`].
+    fn explain_load(
+        &self,
+        threads: &thread::Set,
+        location: Location,
+        ordering: Ordering,
+        index: usize,
+    ) -> String {
+        use std::fmt::Write;
+
+        let view = coherence_view(threads, ordering);
+        let last_seq_cst = self.last_seq_cst(ordering);
+        let number = |i| self.number(i);
+
+        let mut out = format!(
+            "==== explain: {:?} load by thread #{}",
+            ordering,
+            threads.active_id()
+        );
+
+        if location.is_captured() {
+            write!(out, " @ {}", location).unwrap();
         }
 
-        n
+        out.push_str(" ====\n");
+
+        let mut stores: Vec<_> = (0..cmp::min(self.cnt as usize, self.stores.len())).collect();
+        stores.sort_by_key(|&i| number(i));
+
+        for i in stores {
+            let store = &self.stores[i];
+
+            write!(out, "  store #{} = {}", number(i), store.value).unwrap();
+
+            if let Some(order) = store.seq_cst {
+                write!(out, " (SeqCst #{})", order).unwrap();
+            }
+
+            match self.hidden(threads, &view, last_seq_cst, i) {
+                Some(Hidden::SeqCst(last)) => write!(
+                    out,
+                    ": hidden, a SeqCst load observes the latest SeqCst store #{} or a newer \
+                     store not ordered before it",
+                    number(last)
+                ),
+                Some(Hidden::Overwritten(j)) => write!(
+                    out,
+                    ": hidden, store #{} is later in the modification order and already \
+                     observed by the loading thread (coherence)",
+                    number(j)
+                ),
+                Some(Hidden::SeenBeforeYield) => write!(
+                    out,
+                    ": hidden, already observed before the last yield of the loading thread"
+                ),
+                Some(Hidden::SeqCstFence(j)) => write!(
+                    out,
+                    ": hidden, the later SeqCst store #{} precedes a SeqCst fence of the \
+                     loading thread",
+                    number(j)
+                ),
+                None if !store.sync.releases(threads) => write!(out, ": visible"),
+                None if is_acquire(ordering) => write!(
+                    out,
+                    ": visible, synchronizes with the load (release or release sequence)"
+                ),
+                None => write!(
+                    out,
+                    ": visible, released but not acquired by a {:?} load",
+                    ordering
+                ),
+            }
+            .unwrap();
+
+            if i == index {
+                out.push_str(" <= returned");
+            }
+
+            out.push('\n');
+        }
+
+        out
+    }
+
+    /// Number of the store at index `i`, in the order the stores happened.
+    fn number(&self, i: usize) -> usize {
+        self.stores[i].number as usize
     }
 
     fn match_rmw_to_stores(&self, dst: &mut [u8]) -> usize {
@@ -757,36 +1596,73 @@ impl State {
         n
     }
 
-    fn stores_mut(&mut self) -> impl DoubleEndedIterator<Item = &mut Store> {
-        let (start, end) = range(self.cnt);
-        let (two, one) = self.stores[..end].split_at_mut(start);
+    /// Returns `true` if a thread that saw one of the stores may still
+    /// synchronize with it with an acquire fence, which would not be possible
+    /// once the atomic is released.
+    pub(super) fn is_fence_synchronizable(&self, threads: &thread::Set) -> bool {
+        let cnt = cmp::min(self.cnt as usize, MAX_ATOMIC_HISTORY);
+
+        self.stores[..cnt].iter().any(|store| {
+            threads.iter().any(|(id, thread)| {
+                store.first_seen.0[id.as_usize()] != u16::MAX
+                    && store.sync.releases_to(&thread.causality)
+            })
+        })
+    }
 
-        one.iter_mut().chain(two.iter_mut())
+    fn stores_mut(&mut self) -> impl Iterator<Item = &mut Store> {
+        let cnt = cmp::min(self.cnt as usize, MAX_ATOMIC_HISTORY);
+        self.stores[..cnt].iter_mut()
     }
 
     /// Returns the last dependent access
-    pub(super) fn last_dependent_access(&self, action: Action) -> Option<&Access> {
+    pub(super) fn last_dependent_access(&self) -> Option<&Access> {
+        self.last_non_load_access.as_ref()
+    }
+
+    /// Returns the loads an operation depends on, in addition to the last
+    /// dependent access.
+    ///
+    /// A store races with every load since the previous store, not only the
+    /// most recent one. Otherwise, a store ordered after a load of its own
+    /// thread is never explored before the loads of other threads.
+    pub(super) fn dependent_loads(&self, action: Action) -> &[Access] {
         match action {
-            Action::Load => self.last_non_load_access.as_ref(),
-            _ => self.last_access.as_ref(),
+            Action::Load => &[],
+            _ => &self.last_loads,
         }
     }
 
     /// Sets the last dependent access
     pub(super) fn set_last_access(&mut self, action: Action, path_id: usize, version: &VersionVec) {
-        // Always set `last_access`
-        Access::set_or_create(&mut self.last_access, path_id, version);
-
         match action {
-            Action::Load => {}
+            Action::Load => {
+                // Earlier loads of the thread happen before this one
+                self.last_loads
+                    .retain(|access| !access.happens_before(version));
+                self.last_loads.push(Access::new(path_id, version));
+            }
             _ => {
                 // Stores / RMWs
+                self.last_loads.clear();
                 Access::set_or_create(&mut self.last_non_load_access, path_id, version);
             }
         }
     }
 }
 
+// ===== impl Fence =====
+
+impl Fence {
+    pub(super) fn last_dependent_access(&self) -> Option<&Access> {
+        self.last_access.as_ref()
+    }
+
+    pub(super) fn set_last_access(&mut self, path_id: usize, version: &VersionVec) {
+        Access::set_or_create(&mut self.last_access, path_id, version);
+    }
+}
+
 // ===== impl Store =====
 
 impl Default for Store {
@@ -797,7 +1673,9 @@ impl Default for Store {
             modification_order: VersionVec::new(),
             sync: Synchronize::new(),
             first_seen: FirstSeen::new(),
-            seq_cst: false,
+            seq_cst: None,
+            number: 0,
+            rmw_of: None,
         }
     }
 }
@@ -816,7 +1694,11 @@ impl FirstSeen {
     }
 
     fn is_seen_by_current(&self, threads: &thread::Set) -> bool {
-        for (thread_id, version) in threads.active().causality.versions(threads.execution_id()) {
+        self.is_seen_by(&threads.active().causality, threads)
+    }
+
+    fn is_seen_by(&self, view: &VersionVec, threads: &thread::Set) -> bool {
+        for (thread_id, version) in view.versions(threads.execution_id()) {
             match self.0[thread_id.as_usize()] {
                 u16::MAX => {}
                 v if v <= version => return true,
@@ -842,32 +1724,42 @@ impl FirstSeen {
     }
 }
 
-fn is_seq_cst(order: Ordering) -> bool {
-    match order {
-        Ordering::SeqCst => true,
-        _ => false,
+/// Returns the ordering an atomic access with the given ordering is checked
+/// with, under the memory model of the execution.
+fn checked_ordering(execution: &rt::Execution, ordering: Ordering) -> Ordering {
+    match execution.memory_model {
+        MemoryModel::SeqCst => SeqCst,
+        MemoryModel::Weak => ordering,
     }
 }
 
-fn range(cnt: u16) -> (usize, usize) {
-    let start = index(cnt.saturating_sub(MAX_ATOMIC_HISTORY as u16));
-    let mut end = index(cmp::min(cnt, MAX_ATOMIC_HISTORY as u16));
-
-    if end == 0 {
-        end = MAX_ATOMIC_HISTORY;
+/// Returns the causality used to apply the coherence rules to an operation by
+/// the active thread.
+///
+/// `SeqCst` fences do not synchronize threads, but all stores that happen
+/// before a fence are visible to the coherence rules of operations following
+/// any later fence. `SeqCst` operations additionally observe all stores that
+/// happen before any earlier fence.
+fn coherence_view(threads: &thread::Set, ordering: Ordering) -> VersionVec {
+    let active = threads.active();
+    let mut view = active.causality;
+
+    view.join(&active.seq_cst_view);
+
+    if is_seq_cst(ordering) {
+        view.join(&threads.seq_cst_causality);
     }
 
-    assert!(
-        start <= end,
-        "[loom internal bug] cnt = {}; start = {}; end = {}",
-        cnt,
-        start,
-        end
-    );
+    view
+}
 
-    (start, end)
+fn is_acquire(order: Ordering) -> bool {
+    matches!(order, Acquire | AcqRel | SeqCst)
 }
 
-fn index(cnt: u16) -> usize {
-    cnt as usize % MAX_ATOMIC_HISTORY as usize
+fn is_seq_cst(order: Ordering) -> bool {
+    match order {
+        Ordering::SeqCst => true,
+        _ => false,
+    }
 }
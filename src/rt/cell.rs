@@ -1,5 +1,5 @@
 use crate::rt::location::{self, Location, LocationSet};
-use crate::rt::{self, object, thread, VersionVec};
+use crate::rt::{self, alloc, object, thread, VersionVec};
 
 /// Tracks immutable and mutable access to a single memory cell.
 #[derive(Debug)]
@@ -7,16 +7,19 @@ pub(crate) struct Cell {
     state: object::Ref<State>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(super) struct State {
+    /// Type of the cell, used in causality violation messages
+    name: &'static str,
+
     /// Where the cell was created
     created_location: Location,
 
-    /// Number of threads currently reading the cell
-    is_reading: usize,
+    /// Threads currently in a `with` closure, once per outstanding read.
+    readers: Vec<thread::Id>,
 
-    /// `true` if in a `with_mut` closure.
-    is_writing: bool,
+    /// The thread currently in a `with_mut` closure.
+    writer: Option<thread::Id>,
 
     /// The transitive closure of all immutable accessses of `data`.
     read_access: VersionVec,
@@ -32,9 +35,10 @@ pub(super) struct State {
 }
 
 impl Cell {
-    pub(crate) fn new(location: Location) -> Cell {
+    /// Tracks a new cell, `name` is the type reported in causality violations.
+    pub(crate) fn new(name: &'static str, location: Location) -> Cell {
         rt::execution(|execution| {
-            let state = State::new(&execution.threads, location);
+            let state = State::new(name, &execution.threads, location);
 
             Cell {
                 state: execution.objects.insert(state),
@@ -42,90 +46,136 @@ impl Cell {
         })
     }
 
-    pub(crate) fn with<R>(&self, location: Location, f: impl FnOnce() -> R) -> R {
-        struct Reset {
-            state: object::Ref<State>,
-        }
+    pub(crate) fn with<R>(&self, data: *const (), location: Location, f: impl FnOnce() -> R) -> R {
+        let _read = self.read(data, location);
+        f()
+    }
 
-        impl Drop for Reset {
-            fn drop(&mut self) {
-                rt::execution(|execution| {
-                    let state = self.state.get_mut(&mut execution.objects);
+    pub(crate) fn with_mut<R>(
+        &self,
+        data: *const (),
+        location: Location,
+        f: impl FnOnce() -> R,
+    ) -> R {
+        let _write = self.write(data, location);
+        f()
+    }
 
-                    assert!(state.is_reading > 0);
-                    assert!(!state.is_writing);
+    /// Starts an immutable access of the cell's `data`, which lasts until the
+    /// returned guard is dropped.
+    pub(crate) fn read(&self, data: *const (), location: Location) -> Read {
+        rt::synchronize(|execution| {
+            self.check_use_after_free(execution, data, location);
 
-                    state.is_reading -= 1;
+            let state = self.state.get_mut(&mut execution.objects);
 
-                    if !std::thread::panicking() {
-                        state.track_read(&execution.threads);
-                    }
-                })
-            }
-        }
+            state.read_locations.track(location, &execution.threads);
+            state.enter_read(&execution.threads);
+            state.track_read(&execution.threads);
+
+            Read { state: self.state }
+        })
+    }
+
+    /// Starts a mutable access of the cell's `data`, which lasts until the
+    /// returned guard is dropped.
+    pub(crate) fn write(&self, data: *const (), location: Location) -> Write {
+        rt::synchronize(|execution| {
+            self.check_use_after_free(execution, data, location);
 
-        // Enter the read closure
-        let _reset = rt::synchronize(|execution| {
             let state = self.state.get_mut(&mut execution.objects);
 
-            assert!(!state.is_writing, "currently writing to cell");
+            state.write_locations.track(location, &execution.threads);
+            state.enter_write(&execution.threads);
+            state.track_write(&execution.threads);
 
-            state.is_reading += 1;
-            state.read_locations.track(location, &execution.threads);
-            state.track_read(&execution.threads);
+            Write { state: self.state }
+        })
+    }
 
-            Reset { state: self.state }
-        });
+    fn check_use_after_free(&self, execution: &rt::Execution, data: *const (), location: Location) {
+        let name = self.state.get(&execution.objects).name;
+        alloc::check_use_after_free(execution, data as usize, name, location);
+    }
 
-        f()
+    /// Called when a `RefCell` borrow fails. Borrows held by the current
+    /// thread make the borrow fail, borrows held by another thread are
+    /// concurrent accesses.
+    pub(crate) fn check_borrow_conflict(&self, location: Location, mutable: bool) {
+        rt::execution(|execution| {
+            let state = self.state.get_mut(&mut execution.objects);
+
+            if mutable {
+                state.write_locations.track(location, &execution.threads);
+            } else {
+                state.read_locations.track(location, &execution.threads);
+            }
+
+            state.check_borrow_conflict(&execution.threads, mutable);
+        })
     }
+}
 
-    pub(crate) fn with_mut<R>(&self, location: Location, f: impl FnOnce() -> R) -> R {
-        struct Reset(object::Ref<State>);
+/// An immutable access in progress.
+#[derive(Debug)]
+pub(crate) struct Read {
+    state: object::Ref<State>,
+}
 
-        impl Drop for Reset {
-            fn drop(&mut self) {
-                rt::execution(|execution| {
-                    let state = self.0.get_mut(&mut execution.objects);
+impl Drop for Read {
+    fn drop(&mut self) {
+        rt::execution(|execution| {
+            let state = self.state.get_mut(&mut execution.objects);
+            let thread_id = execution.threads.active_id();
 
-                    assert!(state.is_writing);
-                    assert!(state.is_reading == 0);
+            let pos = state.readers.iter().position(|&id| id == thread_id);
+            state.readers.swap_remove(pos.unwrap());
 
-                    state.is_writing = false;
+            assert!(state.writer.is_none());
 
-                    if !std::thread::panicking() {
-                        state.track_write(&execution.threads);
-                    }
-                })
+            if !std::thread::panicking() {
+                state.track_read(&execution.threads);
             }
-        }
+        })
+    }
+}
 
-        // Enter the read closure
-        let _reset = rt::synchronize(|execution| {
-            let state = self.state.get_mut(&mut execution.objects);
+/// A mutable access in progress.
+#[derive(Debug)]
+pub(crate) struct Write {
+    state: object::Ref<State>,
+}
 
-            assert!(state.is_reading == 0, "currently reading from cell");
-            assert!(!state.is_writing, "currently writing to cell");
+impl Drop for Write {
+    fn drop(&mut self) {
+        rt::execution(|execution| {
+            let state = self.state.get_mut(&mut execution.objects);
 
-            state.is_writing = true;
-            state.write_locations.track(location, &execution.threads);
-            state.track_write(&execution.threads);
+            assert!(state.writer.is_some());
+            assert!(state.readers.is_empty());
 
-            Reset(self.state)
-        });
+            state.writer = None;
 
-        f()
+            if !std::thread::panicking() {
+                state.track_write(&execution.threads);
+            }
+        })
     }
 }
 
 impl State {
-    fn new(threads: &thread::Set, location: Location) -> State {
+    pub(super) fn location(&self) -> Location {
+        self.created_location
+    }
+
+    fn new(name: &'static str, threads: &thread::Set, location: Location) -> State {
         let version = threads.active().causality.clone();
 
         State {
+            name,
             created_location: location,
-            is_reading: 0,
-            is_writing: false,
+            readers: Vec::new(),
+            writer: None,
             read_access: version.clone(),
             read_locations: LocationSet::new(),
             write_access: version.clone(),
@@ -133,6 +183,49 @@ impl State {
         }
     }
 
+    /// Enter a `with` closure, checking for a `with_mut` closure that has not
+    /// yet returned.
+    fn enter_read(&mut self, threads: &thread::Set) {
+        if let Some(writer) = self.writer {
+            self.race_read_write(threads.active_id(), writer);
+        }
+
+        self.readers.push(threads.active_id());
+    }
+
+    /// Enter a `with_mut` closure, checking for any `with` or `with_mut`
+    /// closure that has not yet returned.
+    fn enter_write(&mut self, threads: &thread::Set) {
+        if let Some(&reader) = self.readers.first() {
+            self.race_read_write(reader, threads.active_id());
+        }
+
+        if let Some(writer) = self.writer {
+            self.race_write_write(writer, threads.active_id());
+        }
+
+        self.writer = Some(threads.active_id());
+    }
+
+    /// Check a failed `RefCell` borrow for borrows held by other threads.
+    fn check_borrow_conflict(&self, threads: &thread::Set, mutable: bool) {
+        let active = threads.active_id();
+
+        if let Some(writer) = self.writer.filter(|&writer| writer != active) {
+            if mutable {
+                self.race_write_write(writer, active);
+            } else {
+                self.race_read_write(active, writer);
+            }
+        }
+
+        if mutable {
+            if let Some(&reader) = self.readers.iter().find(|&&reader| reader != active) {
+                self.race_read_write(reader, active);
+            }
+        }
+    }
+
     /// Perform a read access
     fn track_read(&mut self, threads: &thread::Set) {
         let current = &threads.active().causality;
@@ -140,11 +233,7 @@ impl State {
         // Check that there is no concurrent mutable access, i.e., the last
         // mutable access must happen-before this immutable access.
         if let Some(writer) = current.ahead(&self.write_access) {
-            location::panic("Causality violation: Concurrent read and write accesses.")
-                .location("created", self.created_location)
-                .thread("read", threads.active_id(), self.read_locations[threads])
-                .thread("write", writer, self.write_locations[writer])
-                .fire();
+            self.race_read_write(threads.active_id(), writer);
         }
 
         self.read_access.join(current);
@@ -156,29 +245,41 @@ impl State {
         // Check that there is no concurrent mutable access, i.e., the last
         // mutable access must happen-before this mutable access.
         if let Some(other) = current.ahead(&self.write_access) {
-            location::panic("Causality violation: Concurrent write accesses to `UnsafeCell`.")
-                .location("created", self.created_location)
-                .thread("write one", other, self.write_locations[other])
-                .thread(
-                    "write two",
-                    threads.active_id(),
-                    self.write_locations[threads],
-                )
-                .fire();
+            self.race_write_write(other, threads.active_id());
         }
 
         // Check that there are no concurrent immutable accesss, i.e., every
         // immutable access must happen-before this mutable access.
         if let Some(reader) = current.ahead(&self.read_access) {
-            location::panic(
-                "Causality violation: Concurrent read and write accesses to `UnsafeCell`.",
-            )
-            .location("created", self.created_location)
-            .thread("read", reader, self.read_locations[reader])
-            .thread("write", threads.active_id(), self.write_locations[threads])
-            .fire();
+            self.race_read_write(reader, threads.active_id());
         }
 
         self.write_access.join(current);
     }
+
+    fn race_read_write(&self, reader: impl Into<usize>, writer: impl Into<usize>) {
+        let (reader, writer) = (reader.into(), writer.into());
+
+        location::panic(format!(
+            "Causality violation: Concurrent read and write accesses to `{}`.",
+            self.name
+        ))
+        .location("created", self.created_location)
+        .thread("read", reader, self.read_locations[reader])
+        .thread("write", writer, self.write_locations[writer])
+        .fire();
+    }
+
+    fn race_write_write(&self, one: impl Into<usize>, two: impl Into<usize>) {
+        let (one, two) = (one.into(), two.into());
+
+        location::panic(format!(
+            "Causality violation: Concurrent write accesses to `{}`.",
+            self.name
+        ))
+        .location("created", self.created_location)
+        .thread("write one", one, self.write_locations[one])
+        .thread("write two", two, self.write_locations[two])
+        .fire();
+    }
 }
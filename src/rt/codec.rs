@@ -0,0 +1,181 @@
+//! Compact binary encoding of the exploration state.
+//!
+//! Used by the binary checkpoint format. Integers are encoded as LEB128
+//! varints, which keeps the long lists of small values that make up a `Path`
+//! small.
+//!
+//! Any change to the encoding of a type, including adding fields to `Path`,
+//! requires bumping the checkpoint format version.
+
+use std::fmt;
+
+pub(crate) trait Codec: Sized {
+    /// Append the encoded value to `dst`.
+    fn encode(&self, dst: &mut Vec<u8>);
+
+    /// Decode a value from the front of `src`, advancing it.
+    fn decode(src: &mut &[u8]) -> Result<Self, Error>;
+}
+
+#[derive(Debug)]
+pub(crate) struct Error(&'static str);
+
+impl Error {
+    pub(crate) fn new(msg: &'static str) -> Error {
+        Error(msg)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.write_str(self.0)
+    }
+}
+
+fn take_byte(src: &mut &[u8]) -> Result<u8, Error> {
+    let (&byte, rem) = src.split_first().ok_or(Error("unexpected end of input"))?;
+    *src = rem;
+    Ok(byte)
+}
+
+impl Codec for u64 {
+    fn encode(&self, dst: &mut Vec<u8>) {
+        let mut v = *self;
+
+        loop {
+            let byte = (v & 0x7f) as u8;
+            v >>= 7;
+
+            if v == 0 {
+                dst.push(byte);
+                return;
+            }
+
+            dst.push(byte | 0x80);
+        }
+    }
+
+    fn decode(src: &mut &[u8]) -> Result<u64, Error> {
+        let mut ret = 0;
+        let mut shift = 0;
+
+        loop {
+            let byte = take_byte(src)?;
+
+            if shift >= 64 {
+                return Err(Error("varint overflow"));
+            }
+
+            ret |= u64::from(byte & 0x7f) << shift;
+            shift += 7;
+
+            if byte & 0x80 == 0 {
+                return Ok(ret);
+            }
+        }
+    }
+}
+
+macro_rules! impl_codec_int {
+    ( $($t:ty),* ) => {
+        $(
+            impl Codec for $t {
+                fn encode(&self, dst: &mut Vec<u8>) {
+                    (*self as u64).encode(dst);
+                }
+
+                fn decode(src: &mut &[u8]) -> Result<$t, Error> {
+                    let v = u64::decode(src)?;
+
+                    if v > <$t>::MAX as u64 {
+                        return Err(Error("integer out of range"));
+                    }
+
+                    Ok(v as $t)
+                }
+            }
+        )*
+    };
+}
+
+impl_codec_int!(u8, u16, u32, usize);
+
+impl Codec for bool {
+    fn encode(&self, dst: &mut Vec<u8>) {
+        dst.push(*self as u8);
+    }
+
+    fn decode(src: &mut &[u8]) -> Result<bool, Error> {
+        match take_byte(src)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(Error("invalid bool")),
+        }
+    }
+}
+
+impl<T: Codec> Codec for Option<T> {
+    fn encode(&self, dst: &mut Vec<u8>) {
+        match self {
+            None => dst.push(0),
+            Some(v) => {
+                dst.push(1);
+                v.encode(dst);
+            }
+        }
+    }
+
+    fn decode(src: &mut &[u8]) -> Result<Option<T>, Error> {
+        match take_byte(src)? {
+            0 => Ok(None),
+            1 => Ok(Some(T::decode(src)?)),
+            _ => Err(Error("invalid option tag")),
+        }
+    }
+}
+
+impl<T: Codec> Codec for Vec<T> {
+    fn encode(&self, dst: &mut Vec<u8>) {
+        self.len().encode(dst);
+
+        for v in self {
+            v.encode(dst);
+        }
+    }
+
+    fn decode(src: &mut &[u8]) -> Result<Vec<T>, Error> {
+        let len = usize::decode(src)?;
+
+        // Every value takes at least one byte, don't trust lengths longer than
+        // the input.
+        if len > src.len() {
+            return Err(Error("invalid length"));
+        }
+
+        let mut ret = Vec::with_capacity(len);
+
+        for _ in 0..len {
+            ret.push(T::decode(src)?);
+        }
+
+        Ok(ret)
+    }
+}
+
+impl<T: Codec + Copy + Default, const N: usize> Codec for [T; N] {
+    fn encode(&self, dst: &mut Vec<u8>) {
+        for v in self {
+            v.encode(dst);
+        }
+    }
+
+    fn decode(src: &mut &[u8]) -> Result<[T; N], Error> {
+        let mut ret = [T::default(); N];
+
+        for v in &mut ret {
+            *v = T::decode(src)?;
+        }
+
+        Ok(ret)
+    }
+}
@@ -1,5 +1,5 @@
 use crate::rt::object;
-use crate::rt::{self, thread, Access, Mutex, VersionVec};
+use crate::rt::{self, deadlock, thread, Access, Location, Mutex, VersionVec};
 
 use std::collections::VecDeque;
 
@@ -8,30 +8,39 @@ pub(crate) struct Condvar {
     state: object::Ref<State>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(super) struct State {
     /// Tracks access to the mutex
     last_access: Option<Access>,
 
     /// Threads waiting on the condvar
     waiters: VecDeque<thread::Id>,
+
+    /// Where the condvar was created
+    created: Location,
 }
 
 impl Condvar {
     /// Create a new condition variable object
-    pub(crate) fn new() -> Condvar {
+    pub(crate) fn new(location: Location) -> Condvar {
         super::execution(|execution| {
             let state = execution.objects.insert(State {
                 last_access: None,
                 waiters: VecDeque::new(),
+                created: location,
             });
 
             Condvar { state }
         })
     }
 
+    /// Returns an owner releasing the condvar when dropped.
+    pub(crate) fn owner(&self) -> object::Owner {
+        super::execution(|execution| self.state.owner(execution))
+    }
+
     /// Blocks the current thread until this condition variable receives a notification.
-    pub(crate) fn wait(&self, mutex: &Mutex) {
+    pub(crate) fn wait(&self, mutex: &Mutex, location: Location) {
         self.state.branch_opaque();
 
         rt::execution(|execution| {
@@ -39,6 +48,15 @@ impl Condvar {
 
             // Track the current thread as a waiter
             state.waiters.push_back(execution.threads.active_id());
+
+            log!(
+                execution.log,
+                Debug,
+                "condvar",
+                "thread #{}: condvar #{} wait",
+                execution.threads.active_id(),
+                self.state.erase().as_usize()
+            );
         });
 
         // Release the lock
@@ -48,40 +66,100 @@ impl Condvar {
         rt::park();
 
         // Acquire the lock again
-        mutex.acquire_lock();
+        mutex.acquire_lock(location);
     }
 
-    /// Wakes up one blocked thread on this condvar.
-    pub(crate) fn notify_one(&self) {
+    /// Wakes up one blocked thread on this condvar, returning `true` if a
+    /// thread was woken up.
+    ///
+    /// Waiters are not woken in FIFO order, every waiter is explored.
+    pub(crate) fn notify_one(&self) -> bool {
         self.state.branch_opaque();
 
         rt::execution(|execution| {
             let state = self.state.get_mut(&mut execution.objects);
 
-            // Notify the first waiter
-            let thread = state.waiters.pop_front();
+            let thread = match state.waiters.len() {
+                0 | 1 => state.waiters.pop_front(),
+                n => {
+                    let i = execution.path.branch_choose(n);
+                    let state = self.state.get_mut(&mut execution.objects);
+                    state.waiters.remove(i)
+                }
+            };
 
             if let Some(thread) = thread {
                 execution.threads.unpark(thread);
             }
+
+            log!(
+                execution.log,
+                Debug,
+                "condvar",
+                "thread #{}: condvar #{} notify_one woke {}",
+                execution.threads.active_id(),
+                self.state.erase().as_usize(),
+                thread.map_or("no thread".to_string(), |thread| format!(
+                    "thread #{}",
+                    thread
+                ))
+            );
+
+            thread.is_some()
         })
     }
 
-    /// Wakes up all blocked threads on this condvar.
-    pub(crate) fn notify_all(&self) {
+    /// Wakes up all blocked threads on this condvar, returning the number of
+    /// threads woken up.
+    ///
+    /// The order in which the threads run next, and reacquire the mutex, is
+    /// explored by the scheduler.
+    pub(crate) fn notify_all(&self) -> usize {
         self.state.branch_opaque();
 
         rt::execution(|execution| {
             let state = self.state.get_mut(&mut execution.objects);
 
+            let waiters = state.waiters.len();
+
             for thread in state.waiters.drain(..) {
                 execution.threads.unpark(thread);
             }
+
+            log!(
+                execution.log,
+                Debug,
+                "condvar",
+                "thread #{}: condvar #{} notify_all woke {} threads",
+                execution.threads.active_id(),
+                self.state.erase().as_usize(),
+                waiters
+            );
+
+            waiters
         })
     }
 }
 
 impl State {
+    pub(super) fn location(&self) -> Location {
+        self.created
+    }
+
+    /// Returns `true` if `thread` is waiting to be notified.
+    pub(super) fn is_waiting(&self, thread: thread::Id) -> bool {
+        self.waiters.contains(&thread)
+    }
+
+    /// Describes the condvar for deadlock reports.
+    pub(super) fn resource(&self) -> deadlock::Resource {
+        deadlock::Resource {
+            kind: "be notified by Condvar",
+            created: self.created,
+            holders: vec![],
+        }
+    }
+
     pub(super) fn last_dependent_access(&self) -> Option<&Access> {
         self.last_access.as_ref()
     }
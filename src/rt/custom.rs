@@ -0,0 +1,109 @@
+use crate::rt::object;
+use crate::rt::{self, Access, Location, Synchronize, VersionVec};
+
+use std::sync::atomic::Ordering::{Acquire, Release};
+
+/// Object registered by a synchronization primitive external to loom.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Custom {
+    state: object::Ref<State>,
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct State {
+    /// Name of the object, used in diagnostics
+    name: &'static str,
+
+    /// Where the object was created
+    created: Location,
+
+    /// Returns `true` if the actions with the given indices are dependent
+    dependent: fn(usize, usize) -> bool,
+
+    /// Last access of each action, by index
+    last_access: Vec<Option<Access>>,
+
+    /// Causality transfers between threads
+    synchronize: Synchronize,
+}
+
+/// Index of an action on a custom object
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(super) struct Action(usize);
+
+impl Custom {
+    pub(crate) fn new(
+        name: &'static str,
+        actions: usize,
+        dependent: fn(usize, usize) -> bool,
+        location: Location,
+    ) -> Custom {
+        rt::execution(|execution| {
+            let state = execution.objects.insert(State {
+                name,
+                created: location,
+                dependent,
+                last_access: vec![None; actions],
+                synchronize: Synchronize::new(),
+            });
+
+            Custom { state }
+        })
+    }
+
+    /// Branch before performing the action with the given index.
+    pub(crate) fn branch(&self, action: usize) {
+        rt::execution(|execution| {
+            let state = self.state.get(&execution.objects);
+
+            assert!(
+                action < state.last_access.len(),
+                "unknown action {} of `{}`",
+                action,
+                state.name
+            );
+        });
+
+        self.state.branch_action(Action(action));
+    }
+
+    /// Publish the causality of the current thread to the threads acquiring
+    /// the object.
+    pub(crate) fn release(&self) {
+        rt::execution(|execution| {
+            let state = self.state.get_mut(&mut execution.objects);
+            state
+                .synchronize
+                .sync_store(&mut execution.threads, Release);
+        })
+    }
+
+    /// Acquire the causality published by the threads releasing the object.
+    pub(crate) fn acquire(&self) {
+        rt::execution(|execution| {
+            let state = self.state.get_mut(&mut execution.objects);
+            state.synchronize.sync_load(&mut execution.threads, Acquire);
+        })
+    }
+}
+
+impl State {
+    pub(super) fn location(&self) -> Location {
+        self.created
+    }
+
+    /// Returns the last accesses of the actions `action` depends on.
+    pub(super) fn dependent_accesses(&self, action: Action) -> impl Iterator<Item = &Access> {
+        let dependent = self.dependent;
+
+        self.last_access
+            .iter()
+            .enumerate()
+            .filter(move |(other, _)| dependent(action.0, *other))
+            .filter_map(|(_, access)| access.as_ref())
+    }
+
+    pub(super) fn set_last_access(&mut self, action: Action, path_id: usize, version: &VersionVec) {
+        Access::set_or_create(&mut self.last_access[action.0], path_id, version);
+    }
+}
@@ -0,0 +1,117 @@
+//! Explains why an execution deadlocked.
+//!
+//! When no thread can make progress, each blocked thread is paired with the
+//! object it is waiting on and the threads that must act before it can be
+//! unblocked. Following those edges yields the wait-for graph, and any cycle in
+//! it is reported.
+
+use crate::rt::{thread, Execution, Location};
+
+use std::fmt::Write;
+
+/// An object a blocked thread is waiting on.
+pub(super) struct Resource {
+    /// What the thread is trying to do, e.g. "lock Mutex".
+    pub(super) kind: &'static str,
+
+    /// Where the object was created.
+    pub(super) created: Location,
+
+    /// Threads the waiter depends on, with where they acquired the object.
+    pub(super) holders: Vec<(thread::Id, Location)>,
+}
+
+/// Builds the panic message for a deadlocked execution.
+pub(super) fn report(execution: &Execution) -> String {
+    let mut msg = "deadlock; all threads are blocked".to_string();
+    let mut edges = vec![vec![]; execution.threads.iter().len()];
+
+    for (id, th) in execution.threads.iter() {
+        let _ = write!(msg, "\n    thread #{}: ", id.as_usize());
+
+        if th.is_terminated() {
+            if execution.killed == Some(id) {
+                msg.push_str("killed by an injected failure");
+            } else {
+                msg.push_str("terminated");
+            }
+            continue;
+        }
+
+        if !th.is_blocked() {
+            let _ = write!(msg, "{:?}", th.state);
+            continue;
+        }
+
+        let resource = match &th.operation {
+            Some(operation) => execution.objects.resource(operation.object()),
+            None => execution.objects.condvar_waited_on(id),
+        };
+
+        let mut resource = match resource {
+            Some(resource) => resource,
+            None => {
+                msg.push_str("parked");
+                continue;
+            }
+        };
+
+        let _ = write!(msg, "waiting to {}", resource.kind);
+
+        if resource.created.is_captured() {
+            let _ = write!(msg, " created at {}", resource.created);
+        }
+
+        resource.holders.sort_by_key(|(id, _)| id.as_usize());
+
+        for (i, (holder, location)) in resource.holders.iter().enumerate() {
+            let sep = if i == 0 { "; blocked by" } else { "," };
+            let _ = write!(msg, "{} thread #{}", sep, holder.as_usize());
+
+            if location.is_captured() {
+                let _ = write!(msg, " (acquired at {})", location);
+            }
+
+            edges[id.as_usize()].push(holder.as_usize());
+        }
+    }
+
+    if let Some(cycle) = find_cycle(&edges) {
+        let cycle: Vec<_> = cycle.iter().map(|th| format!("thread #{}", th)).collect();
+        let _ = write!(msg, "\n    wait-for cycle: {}", cycle.join(" -> "));
+    }
+
+    if !execution.location {
+        msg.push_str("\n\nset `LOOM_LOCATION=1` to include source locations");
+    }
+
+    msg
+}
+
+/// Returns a cycle in the wait-for graph, starting and ending with the same
+/// thread.
+fn find_cycle(edges: &[Vec<usize>]) -> Option<Vec<usize>> {
+    fn visit(edges: &[Vec<usize>], stack: &mut Vec<usize>) -> Option<Vec<usize>> {
+        let last = *stack.last().unwrap();
+
+        for &next in &edges[last] {
+            if let Some(pos) = stack.iter().position(|&th| th == next) {
+                let mut cycle = stack[pos..].to_vec();
+                cycle.push(next);
+                return Some(cycle);
+            }
+
+            stack.push(next);
+
+            if let Some(cycle) = visit(edges, stack) {
+                return Some(cycle);
+            }
+
+            stack.pop();
+        }
+
+        None
+    }
+
+    (0..edges.len()).find_map(|start| visit(edges, &mut vec![start]))
+}
@@ -1,9 +1,15 @@
-use crate::rt::alloc::Allocation;
-use crate::rt::{lazy_static, object, thread, Path};
-
-use std::collections::HashMap;
+use crate::model::{MainExit, MemoryModel, ObjectCounts, YieldMode};
+use crate::observe::{BranchKind, ExecutionObserver, Observer};
+use crate::rt::path::Step;
+use crate::rt::{
+    alloc, atomic, deadlock, lazy_static, location, object, profile, thread, Location, Log, Path,
+    MAX_THREADS,
+};
+
+use std::any::Any;
+use std::collections::{HashMap, HashSet};
 use std::convert::TryInto;
-use std::fmt;
+use std::fmt::{self, Write};
 
 pub(crate) struct Execution {
     /// Uniquely identifies an execution
@@ -19,8 +25,11 @@ pub(crate) struct Execution {
     /// All loom aware objects part of this execution run.
     pub(super) objects: object::Store,
 
-    /// Maps raw allocations to LeakTrack objects
-    pub(super) raw_allocations: HashMap<usize, Allocation>,
+    /// Maps raw allocations to the objects tracking them for leaks
+    pub(super) raw_allocations: HashMap<usize, object::Ref<alloc::State>>,
+
+    /// Raw allocations deallocated during the execution
+    pub(super) quarantine: alloc::Quarantine,
 
     /// Maximum number of concurrent threads
     pub(super) max_threads: usize,
@@ -30,8 +39,104 @@ pub(crate) struct Execution {
     /// Capture locations for significant events
     pub(crate) location: bool,
 
-    /// Log execution output to STDOUT
-    pub(crate) log: bool,
+    /// Logs the events of the execution
+    pub(crate) log: Log,
+
+    /// When `true`, every raw allocation branches on failing
+    pub(crate) alloc_failures: bool,
+
+    /// When `true`, every `thread::Builder::spawn` branches on failing
+    pub(crate) spawn_failures: bool,
+
+    /// When `true`, the branch points of spawned threads branch on killing
+    /// the thread
+    pub(crate) inject_thread_failure: bool,
+
+    /// When `true`, a compare-and-exchange succeeding after the value it
+    /// expected was changed and changed back fails the execution
+    pub(crate) detect_aba: bool,
+
+    /// The thread killed by an injected failure, if any
+    pub(super) killed: Option<thread::Id>,
+
+    /// Yields of a thread past this number switch threads without branching
+    pub(crate) max_yields: Option<usize>,
+
+    /// When `true`, prune schedules equivalent to explored ones with sleep
+    /// sets
+    pub(crate) sleep_sets: bool,
+
+    /// Threads that do not need to be scheduled at the next branch point, as
+    /// a schedule running them first was already explored
+    sleep: [bool; MAX_THREADS],
+
+    /// When set, the branch points caused by each object, see
+    /// `Builder::profile_branches`
+    pub(crate) branch_counts: Option<profile::Counts>,
+
+    /// When set, the maximum number of objects an execution may create
+    pub(crate) max_objects: Option<usize>,
+
+    /// When set, the maximum number of bytes of raw allocations an execution
+    /// may hold at once
+    pub(crate) max_alloc_bytes: Option<usize>,
+
+    /// Bytes of raw allocations currently held
+    pub(super) alloc_bytes: usize,
+
+    /// Most bytes of raw allocations held at once
+    pub(crate) peak_alloc_bytes: usize,
+
+    /// Coverage marks hit by the execution, see `explore::mark`
+    pub(crate) marks: HashSet<&'static str>,
+
+    /// When set, told about the events of the execution, see
+    /// `Builder::instrument`
+    pub(crate) observer: Option<Observer>,
+
+    /// Number of objects and branches reported to the observer
+    observed_objects: usize,
+    observed_branches: usize,
+
+    /// What happens to the threads still running when the main thread returns
+    pub(crate) main_exit: MainExit,
+
+    /// Memory model under which atomic accesses are checked
+    pub(crate) memory_model: MemoryModel,
+
+    /// How `yield_now` is modeled
+    pub(crate) yield_mode: YieldMode,
+
+    /// True once a panic unwound out of the runtime, such as a detected bug.
+    /// The execution failed, even if the panicking thread is joined.
+    pub(crate) failed: bool,
+
+    /// State of the runtime after the setup of the model, restored by the
+    /// following executions while their branches do not diverge before it
+    snapshot: Option<Box<Snapshot>>,
+
+    /// `true` if the execution started from the snapshot
+    restored: bool,
+
+    /// Number of objects created before the snapshot. They may be retained by
+    /// the setup's state and are not checked for leaks.
+    snapshot_objects: usize,
+}
+
+/// State of the runtime at the end of the setup of a model.
+struct Snapshot {
+    /// Execution that took the snapshot, threads are identified by it
+    id: Id,
+
+    /// Number of branches the setup went through
+    pos: usize,
+
+    threads: thread::Set,
+    objects: object::Store,
+    raw_allocations: HashMap<usize, object::Ref<alloc::State>>,
+    alloc_bytes: usize,
+    peak_alloc_bytes: usize,
+    marks: HashSet<&'static str>,
 }
 
 #[derive(Debug, Eq, PartialEq, Hash, Clone, Copy)]
@@ -47,11 +152,18 @@ impl Execution {
         max_branches: usize,
         preemption_bound: Option<usize>,
     ) -> Execution {
+        assert!(
+            max_threads > 0 && max_threads <= MAX_THREADS,
+            "`max_threads` must be between 1 and {}, got {}",
+            MAX_THREADS,
+            max_threads
+        );
+
         let id = Id::new();
         let threads = thread::Set::new(id, max_threads);
 
         let preemption_bound =
-            preemption_bound.map(|bound| bound.try_into().expect("preemption_bound too big"));
+            preemption_bound.map(|bound| bound.try_into().expect("scheduling bound too big"));
 
         Execution {
             id,
@@ -60,19 +172,49 @@ impl Execution {
             lazy_statics: lazy_static::Set::new(),
             objects: object::Store::with_capacity(max_branches),
             raw_allocations: HashMap::new(),
+            quarantine: alloc::Quarantine::default(),
             max_threads,
             max_history: 7,
             location: false,
-            log: false,
+            log: Log::default(),
+            alloc_failures: false,
+            spawn_failures: false,
+            inject_thread_failure: false,
+            detect_aba: false,
+            killed: None,
+            max_yields: None,
+            sleep_sets: false,
+            sleep: [false; MAX_THREADS],
+            branch_counts: None,
+            max_objects: None,
+            max_alloc_bytes: None,
+            alloc_bytes: 0,
+            peak_alloc_bytes: 0,
+            marks: HashSet::new(),
+            observer: None,
+            observed_objects: 0,
+            observed_branches: 0,
+            main_exit: MainExit::Wait,
+            memory_model: MemoryModel::Weak,
+            yield_mode: YieldMode::Heuristic,
+            failed: false,
+            snapshot: None,
+            restored: false,
+            snapshot_objects: 0,
         }
     }
 
     /// Create state to track a new thread
-    pub(crate) fn new_thread(&mut self) -> thread::Id {
+    pub(crate) fn new_thread(&mut self, location: Location) -> thread::Id {
+        if self.threads.iter().len() >= self.max_threads {
+            self.too_many_threads(location);
+        }
+
         let thread_id = self.threads.new_thread();
         let active_id = self.threads.active_id();
 
         let (active, new) = self.threads.active2_mut(thread_id);
+        new.spawned_at = location;
 
         new.causality.join(&active.causality);
         new.dpor_vv.join(&active.dpor_vv);
@@ -82,20 +224,73 @@ impl Execution {
         new.causality[thread_id] += 1;
         active.causality[active_id] += 1;
 
+        self.observe_pending();
+        self.observe(|o| o.thread_spawned(thread_id.as_usize(), active_id.as_usize()));
+
         thread_id
     }
 
+    /// Fails the execution as spawning one more thread exceeds `max_threads`.
+    fn too_many_threads(&self, location: Location) -> ! {
+        let alive = self
+            .threads
+            .iter()
+            .filter(|(_, thread)| !thread.is_terminated())
+            .count();
+
+        let mut panic = location::panic(format!(
+            "Model exceeded the maximum number of threads: {} threads were spawned, \
+             {} of them still alive, and `max_threads` is {}. Join threads before spawning \
+             new ones, or raise the limit with `LOOM_MAX_THREADS` (at most {}).",
+            self.threads.iter().len(),
+            alive,
+            self.max_threads,
+            MAX_THREADS,
+        ));
+
+        for (id, thread) in self.threads.iter().skip(1) {
+            let key = if thread.is_terminated() {
+                "spawned (terminated)"
+            } else {
+                "spawned"
+            };
+
+            panic.thread(key, id, thread.spawned_at);
+        }
+
+        panic
+            .thread("spawning", self.threads.active_id(), location)
+            .fire();
+
+        unreachable!()
+    }
+
     /// Resets the execution state for the next execution run
     pub(crate) fn step(self) -> Option<Self> {
         let id = Id::new();
         let max_threads = self.max_threads;
         let max_history = self.max_history;
         let location = self.location;
-        let log = self.log;
+        let mut log = self.log;
+        let alloc_failures = self.alloc_failures;
+        let spawn_failures = self.spawn_failures;
+        let inject_thread_failure = self.inject_thread_failure;
+        let detect_aba = self.detect_aba;
+        let max_yields = self.max_yields;
+        let sleep_sets = self.sleep_sets;
+        let mut branch_counts = self.branch_counts;
+        let max_objects = self.max_objects;
+        let max_alloc_bytes = self.max_alloc_bytes;
+        let observer = self.observer;
+        let main_exit = self.main_exit;
+        let memory_model = self.memory_model;
+        let yield_mode = self.yield_mode;
         let mut path = self.path;
         let mut objects = self.objects;
         let mut lazy_statics = self.lazy_statics;
         let mut raw_allocations = self.raw_allocations;
+        let mut quarantine = self.quarantine;
+        let snapshot = self.snapshot;
 
         let mut threads = self.threads;
 
@@ -105,22 +300,107 @@ impl Execution {
 
         objects.clear();
         lazy_statics.reset();
+        log.clear();
         raw_allocations.clear();
+        quarantine.release();
+
+        if let Some(counts) = &mut branch_counts {
+            counts.clear();
+        }
 
         threads.clear(id);
 
-        Some(Execution {
+        let mut next = Execution {
             id,
             path,
             threads,
             objects,
             lazy_statics,
             raw_allocations,
+            quarantine,
             max_threads,
             max_history,
             location,
             log,
-        })
+            alloc_failures,
+            spawn_failures,
+            inject_thread_failure,
+            detect_aba,
+            killed: None,
+            max_yields,
+            sleep_sets,
+            sleep: [false; MAX_THREADS],
+            branch_counts,
+            max_objects,
+            max_alloc_bytes,
+            alloc_bytes: 0,
+            peak_alloc_bytes: 0,
+            marks: HashSet::new(),
+            observer,
+            observed_objects: 0,
+            observed_branches: 0,
+            main_exit,
+            memory_model,
+            yield_mode,
+            failed: false,
+            snapshot: None,
+            restored: false,
+            snapshot_objects: 0,
+        };
+
+        // Start from the snapshot if the setup takes the same branches.
+        if let Some(snapshot) = snapshot {
+            if next.path.diverged() >= snapshot.pos {
+                next.restore(snapshot);
+            }
+        }
+
+        Some(next)
+    }
+
+    /// Capture the state of the runtime, so that the following executions can
+    /// start from here instead of running the setup of the model again.
+    pub(crate) fn take_snapshot(&mut self) {
+        assert!(
+            self.threads.iter().len() == 1,
+            "threads cannot be spawned before the snapshot is taken"
+        );
+        assert!(
+            self.lazy_statics.is_empty(),
+            "lazy statics cannot be accessed before the snapshot is taken"
+        );
+
+        self.snapshot = Some(Box::new(Snapshot {
+            id: self.id,
+            pos: self.path.pos(),
+            threads: self.threads.snapshot(),
+            objects: self.objects.clone(),
+            raw_allocations: self.raw_allocations.clone(),
+            alloc_bytes: self.alloc_bytes,
+            peak_alloc_bytes: self.peak_alloc_bytes,
+            marks: self.marks.clone(),
+        }));
+
+        self.snapshot_objects = self.objects.len();
+    }
+
+    /// Returns `true` if the execution started from a snapshot.
+    pub(crate) fn is_restored(&self) -> bool {
+        self.restored
+    }
+
+    fn restore(&mut self, snapshot: Box<Snapshot>) {
+        self.id = snapshot.id;
+        self.threads.restore(&snapshot.threads);
+        self.objects.clone_from(&snapshot.objects);
+        self.raw_allocations.clone_from(&snapshot.raw_allocations);
+        self.alloc_bytes = snapshot.alloc_bytes;
+        self.peak_alloc_bytes = snapshot.peak_alloc_bytes;
+        self.marks.clone_from(&snapshot.marks);
+        self.path.skip_to(snapshot.pos);
+        self.snapshot_objects = self.objects.len();
+        self.restored = true;
+        self.snapshot = Some(snapshot);
     }
 
     /// Returns `true` if a switch is required
@@ -131,13 +411,16 @@ impl Execution {
 
         let curr_thread = self.threads.active_id();
 
+        self.check_max_objects();
+        self.observe_pending();
+
         for (th_id, th) in self.threads.iter() {
             let operation = match th.operation {
                 Some(operation) => operation,
                 None => continue,
             };
 
-            if let Some(access) = self.objects.last_dependent_access(operation) {
+            for access in self.objects.dependent_accesses(operation) {
                 if access.happens_before(&th.dpor_vv) {
                     // The previous access happened before this access, thus
                     // there is no race.
@@ -148,20 +431,37 @@ impl Execution {
                 let point = access.path_id();
 
                 // Track backtracking point
-                self.path.backtrack(point, th_id);
+                let explored = self.path.backtrack(point, th_id);
+
+                if let Some(counts) = &mut self.branch_counts {
+                    counts.add(operation.object(), explored);
+                }
+            }
+        }
+
+        // A thread in an atomic section keeps running, without a branch
+        // point, for as long as it can. Its accesses are attributed to the
+        // branch point that scheduled it, so that DPOR backtracks before the
+        // section.
+        if self.threads.active().is_runnable() {
+            if let Some(path_id) = self.threads.active().critical_branch {
+                self.track_access(path_id);
+                return false;
             }
         }
 
+        let sleeping = self.sleeping();
+
         // It's important to avoid pre-emption as much as possible
         let mut initial = Some(self.threads.active_id());
 
         // If the thread is not runnable, then we can pick any arbitrary other
         // runnable thread.
-        if !self.threads.active().is_runnable() {
+        if !self.threads.active().is_runnable() || sleeping[curr_thread.as_usize()] {
             initial = None;
 
             for (i, th) in self.threads.iter() {
-                if !th.is_runnable() {
+                if !th.is_runnable() || sleeping[i.as_usize()] {
                     continue;
                 }
 
@@ -177,26 +477,73 @@ impl Execution {
 
         let path_id = self.path.pos();
 
-        let next = self.path.branch_thread(self.id, {
-            self.threads.iter().map(|(i, th)| {
-                if initial.is_none() && th.is_runnable() {
-                    initial = Some(i);
-                }
+        let mut threads = [Thread::Disabled; MAX_THREADS];
+        let num_threads = self.threads.iter().count();
 
-                if initial == Some(i) {
-                    Thread::Active
-                } else if th.is_yield() {
-                    Thread::Yield
-                } else if !th.is_runnable() {
-                    Thread::Disabled
-                } else {
-                    Thread::Skip
+        for ((i, th), state) in self.threads.iter().zip(threads.iter_mut()) {
+            if initial.is_none() && th.is_runnable() {
+                initial = Some(i);
+            }
+
+            *state = if initial == Some(i) {
+                Thread::Active
+            } else if th.is_yield() {
+                Thread::Yield
+            } else if !th.is_runnable() {
+                Thread::Disabled
+            } else if sleeping[i.as_usize()] {
+                Thread::Sleep
+            } else {
+                Thread::Skip
+            };
+        }
+
+        // After a yield, the next thread in round-robin order runs, without
+        // exploring the others.
+        if self.yield_mode == YieldMode::RoundRobin && self.threads.active().is_yield() {
+            let next = (1..=num_threads)
+                .map(|offset| (curr_thread.as_usize() + offset) % num_threads)
+                .find(|&i| threads[i] != Thread::Disabled && threads[i] != Thread::Yield);
+
+            if let Some(next) = next {
+                for (i, state) in threads[..num_threads].iter_mut().enumerate() {
+                    *state = if i == next {
+                        Thread::Active
+                    } else {
+                        Thread::Disabled
+                    };
                 }
-            })
-        });
+            }
+        }
+
+        let step = Step {
+            thread: curr_thread.as_usize(),
+            operation: self.threads.active().operation,
+            threads,
+        };
+
+        if let Err(recorded) = self.path.check_step(step) {
+            panic!(
+                "model is non-deterministic: execution diverged from the recorded path at step {}\n    \
+                 recorded: {}\n    \
+                 found:    {}",
+                path_id,
+                self.describe_step(&recorded, num_threads),
+                self.describe_step(&step, num_threads),
+            );
+        }
+
+        let next = self
+            .path
+            .branch_thread(self.id, threads[..num_threads].iter().copied());
 
         let switched = Some(self.threads.active_id()) != next;
 
+        if self.path.pos() > path_id {
+            self.observe(|o| o.branch(curr_thread.as_usize(), path_id, BranchKind::Schedule));
+            self.observed_branches = self.path.pos();
+        }
+
         self.threads.set_active(next);
 
         // There is no active thread. Unless all threads have terminated, the
@@ -204,24 +551,109 @@ impl Execution {
         if !self.threads.is_active() {
             let terminal = self.threads.iter().all(|(_, th)| th.is_terminated());
 
-            assert!(
-                terminal,
-                "deadlock; threads = {:?}",
-                self.threads
-                    .iter()
-                    .map(|(i, th)| { (i, th.state) })
-                    .collect::<Vec<_>>()
-            );
+            if !terminal {
+                let msg = deadlock::report(self);
+
+                // The current thread unwinds from here, running destructors
+                // that may call back into loom. Let it keep running so they do
+                // not hit the deadlock again.
+                self.threads.set_active(Some(curr_thread));
+                self.threads.active_mut().set_runnable();
+
+                panic!("{}", msg);
+            }
 
             return true;
         }
 
+        if self.threads.active().critical {
+            self.threads.active_mut().critical_branch = Some(path_id);
+        }
+
+        if self.sleep_sets {
+            // Threads explored first from this branch point, or already
+            // asleep, need not run until a dependent operation is performed.
+            self.sleep = self.path.sleep_set(path_id);
+        }
+
+        self.track_access(path_id);
+
+        // Reactivate yielded threads, but only if the current active thread is
+        // not yielded. Threads that yielded more than `max_yields` times stay
+        // parked until no other thread could run.
+        let all_yielded = self.threads.active().is_yield();
+        let max_yields = self.max_yields;
+
+        for (id, th) in self.threads.iter_mut() {
+            let parked = matches!(max_yields, Some(max) if th.yield_count > max) && !all_yielded;
+
+            if th.is_yield() && Some(id) != next && !parked {
+                th.set_runnable();
+            }
+        }
+
+        if switched {
+            let next = self.threads.active_id().as_usize();
+            self.observe(|o| o.thread_switched(curr_thread.as_usize(), next));
+
+            log!(
+                self.log,
+                Info,
+                "thread",
+                "switch to thread #{}",
+                self.threads.active_id()
+            );
+        }
+
+        curr_thread != self.threads.active_id()
+    }
+
+    /// Invokes the observer, if any.
+    pub(crate) fn observe(&self, f: impl FnOnce(&mut dyn ExecutionObserver)) {
+        if let Some(observer) = &self.observer {
+            observer.with(f);
+        }
+    }
+
+    /// Reports the objects created and the branch points reached by the
+    /// active thread since the last report to the observer, if any.
+    pub(crate) fn observe_pending(&mut self) {
+        let observer = match &self.observer {
+            Some(observer) if self.threads.is_active() => observer,
+            _ => return,
+        };
+
+        let thread = self.threads.active_id().as_usize();
+        let objects = &self.objects;
+        let path = &self.path;
+
+        observer.with(|o| {
+            for object in self.observed_objects..objects.len() {
+                o.object_created(
+                    thread,
+                    object,
+                    objects.kind(object::Ref::from_usize(object)),
+                );
+            }
+
+            for position in self.observed_branches..path.pos() {
+                o.branch(thread, position, path.branch_kind(position));
+            }
+        });
+
+        self.observed_objects = self.objects.len();
+        self.observed_branches = self.path.pos();
+    }
+
+    /// Record the operation of the active thread as the last access to its
+    /// object, made at the branch point `path_id`.
+    fn track_access(&mut self, path_id: usize) {
         // TODO: refactor
         if let Some(operation) = self.threads.active().operation {
             let threads = &mut self.threads;
             let th_id = threads.active_id();
 
-            if let Some(access) = self.objects.last_dependent_access(operation) {
+            for access in self.objects.dependent_accesses(operation) {
                 threads.active_mut().dpor_vv.join(access.version());
             }
 
@@ -231,24 +663,185 @@ impl Execution {
                 .set_last_access(operation, path_id, &threads.active().dpor_vv);
         }
 
-        // Reactivate yielded threads, but only if the current active thread is
-        // not yielded.
-        for (id, th) in self.threads.iter_mut() {
-            if th.is_yield() && Some(id) != next {
-                th.set_runnable();
+        self.wake_dependent();
+    }
+
+    /// Returns the runnable threads that are asleep.
+    ///
+    /// Sleep sets are only used when exploring exhaustively. When all runnable
+    /// threads are asleep, the execution is redundant but must still
+    /// complete: no thread is considered asleep.
+    fn sleeping(&self) -> [bool; MAX_THREADS] {
+        let mut sleeping = [false; MAX_THREADS];
+
+        if !self.sleep_sets || !self.path.is_exhaustive() {
+            return sleeping;
+        }
+
+        let mut awake = false;
+
+        for (i, th) in self.threads.iter() {
+            if !th.is_runnable() {
+                continue;
+            }
+
+            if self.sleep[i.as_usize()] {
+                sleeping[i.as_usize()] = true;
+            } else {
+                awake = true;
             }
         }
 
-        if self.log && switched {
-            println!("~~~~~~~~ THREAD {} ~~~~~~~~", self.threads.active_id());
+        if awake {
+            sleeping
+        } else {
+            [false; MAX_THREADS]
         }
+    }
 
-        curr_thread != self.threads.active_id()
+    /// Wakes the sleeping threads whose operation depends on the one the
+    /// active thread performs.
+    fn wake_dependent(&mut self) {
+        let operation = self.threads.active().operation;
+
+        for (i, th) in self.threads.iter() {
+            let asleep = &mut self.sleep[i.as_usize()];
+
+            if !*asleep {
+                continue;
+            }
+
+            // The operation of a thread that is not runnable may change, wake
+            // it up.
+            *asleep = th.is_runnable()
+                && match (operation, th.operation) {
+                    (Some(a), Some(b)) => !a.is_dependent(&b),
+                    _ => false,
+                };
+        }
+    }
+
+    /// Counts the objects created by the execution by kind.
+    pub(crate) fn count_objects(&self, counts: &mut ObjectCounts) {
+        self.objects.count_objects(counts);
+    }
+
+    /// Releases `obj`, whose owner was dropped, if it cannot affect the rest
+    /// of the execution anymore.
+    ///
+    /// The object is kept when:
+    ///
+    /// * the branch points are profiled, as they are attributed to objects by
+    ///   index at the end of the execution;
+    /// * it was created before the snapshot, as the following executions
+    ///   restore it;
+    /// * another thread is about to access it, as DPOR checks the pending
+    ///   operations of all threads at each branch point;
+    /// * it is an atomic a thread may still synchronize with using an acquire
+    ///   fence.
+    pub(super) fn release(&mut self, obj: object::Ref) {
+        if self.branch_counts.is_some()
+            || obj.as_usize() < self.snapshot_objects
+            || self.objects.is_released(obj)
+        {
+            return;
+        }
+
+        let active = self.threads.active_id();
+
+        let pending = self.threads.iter().any(|(id, th)| {
+            id != active && matches!(th.operation, Some(operation) if operation.object() == obj)
+        });
+
+        if pending {
+            return;
+        }
+
+        if let Some(atomic) = obj.downcast::<atomic::State>(&self.objects) {
+            if atomic
+                .get(&self.objects)
+                .is_fence_synchronizable(&self.threads)
+            {
+                return;
+            }
+        }
+
+        let thread = self.threads.active_mut();
+
+        if matches!(thread.operation, Some(operation) if operation.object() == obj) {
+            thread.operation = None;
+        }
+
+        self.observe_pending();
+        self.objects.release(obj);
+        self.observed_objects = self.observed_objects.min(self.objects.len());
+    }
+
+    /// Panics if the execution holds more objects than allowed.
+    fn check_max_objects(&self) {
+        if let Some(max) = self.max_objects {
+            let live = self.objects.live();
+
+            if live > max {
+                panic!(
+                    "execution holds {} loom objects, more than the maximum of {}; raise \
+                     `max_objects` (`LOOM_MAX_OBJECTS`) if this is expected",
+                    live, max
+                );
+            }
+        }
+    }
+
+    fn describe_step(&self, step: &Step, num_threads: usize) -> String {
+        let mut msg = format!("thread #{} ", step.thread);
+
+        match step.operation {
+            Some(operation) => {
+                let obj = operation.object();
+                let _ = write!(
+                    msg,
+                    "performing {:?} on object #{}",
+                    operation.action(),
+                    obj.as_usize()
+                );
+
+                let location = self.objects.location(obj);
+
+                if location.is_captured() {
+                    let _ = write!(msg, " created at {}", location);
+                }
+            }
+            None => msg.push_str("with no pending operation"),
+        }
+
+        let _ = write!(msg, "; threads: {:?}", &step.threads[..num_threads]);
+        msg
     }
 
     /// Panics if any leaks were detected
+    /// Returns the panic of a thread that was never joined.
+    ///
+    /// A thread killed by an injected failure need not be joined.
+    pub(crate) fn take_unjoined_panic(&mut self) -> Option<Box<dyn Any + Send>> {
+        let killed = self.killed;
+
+        self.threads
+            .iter_mut()
+            .filter(|(id, _)| Some(*id) != killed)
+            .find_map(|(_, thread)| thread.panic.take())
+    }
+
     pub(crate) fn check_for_leaks(&self) {
-        self.objects.check_for_leaks();
+        let note = match self.killed {
+            Some(thread) => format!(
+                "\n\nthread #{} was killed by an injected failure, the objects may have been \
+                 orphaned by it",
+                thread.as_usize()
+            ),
+            None => String::new(),
+        };
+
+        self.objects.check_for_leaks(self.snapshot_objects, &note);
     }
 }
 
@@ -1,9 +1,20 @@
 use crate::rt::synchronize::Synchronize;
+use crate::rt::Mutex;
 use std::{any::Any, collections::HashMap};
 
 pub(crate) struct Set {
     /// Registered statics.
     statics: Option<HashMap<StaticKeyId, StaticValue>>,
+
+    /// Registered statics, in the order their initialization completed.
+    order: Vec<StaticKeyId>,
+
+    /// Locks serializing the initialization of each static, like the
+    /// `std::sync::Once` used by `lazy_static`.
+    locks: HashMap<StaticKeyId, Mutex>,
+
+    /// `true` once the statics are being dropped.
+    shutdown: bool,
 }
 
 #[derive(Eq, PartialEq, Hash, Copy, Clone)]
@@ -19,6 +30,9 @@ impl Set {
     pub(crate) fn new() -> Set {
         Set {
             statics: Some(HashMap::new()),
+            order: vec![],
+            locks: HashMap::new(),
+            shutdown: false,
         }
     }
 
@@ -28,22 +42,57 @@ impl Set {
             "lazy_static was not dropped during execution"
         );
         self.statics = Some(HashMap::new());
+        self.order.clear();
+        self.locks.clear();
+        self.shutdown = false;
     }
 
-    pub(crate) fn drop(&mut self) -> HashMap<StaticKeyId, StaticValue> {
-        self.statics
-            .take()
-            .expect("lazy_statics were dropped twice in one execution")
+    /// Returns `true` if no static was accessed.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.locks.is_empty() && self.statics.as_ref().map(HashMap::is_empty).unwrap_or(true)
+    }
+
+    /// Removes the static initialized last, to be dropped, or returns `None`
+    /// once all statics were dropped.
+    ///
+    /// Statics are dropped in the reverse order of their initialization, a
+    /// static whose initializer used another one may still use it when
+    /// dropped. Accessing a static that was dropped panics.
+    pub(crate) fn pop(&mut self) -> Option<StaticValue> {
+        self.shutdown = true;
+
+        let statics = self
+            .statics
+            .as_mut()
+            .expect("lazy_statics were dropped twice in one execution");
+
+        match self.order.pop() {
+            Some(key) => statics.remove(&key),
+            None => {
+                self.statics = None;
+                None
+            }
+        }
     }
 
     pub(crate) fn get_static<T: 'static>(
         &mut self,
         key: &'static crate::lazy_static::Lazy<T>,
     ) -> Option<&mut StaticValue> {
-        self.statics
+        let shutdown = self.shutdown;
+
+        let value = self
+            .statics
             .as_mut()
             .expect("attempted to access lazy_static during shutdown")
-            .get_mut(&StaticKeyId::new(key))
+            .get_mut(&StaticKeyId::new(key));
+
+        assert!(
+            value.is_some() || !shutdown,
+            "attempted to access lazy_static during shutdown"
+        );
+
+        value
     }
 
     pub(crate) fn init_static<T: 'static>(
@@ -51,18 +100,43 @@ impl Set {
         key: &'static crate::lazy_static::Lazy<T>,
         value: StaticValue,
     ) -> &mut StaticValue {
+        assert!(
+            !self.shutdown,
+            "attempted to access lazy_static during shutdown"
+        );
+
+        let key = StaticKeyId::new(key);
+
         let v = self
             .statics
             .as_mut()
             .expect("attempted to access lazy_static during shutdown")
-            .entry(StaticKeyId::new(key));
+            .entry(key);
 
         if let std::collections::hash_map::Entry::Occupied(_) = v {
             unreachable!("told to init static, but it was already init'd");
         }
 
+        self.order.push(key);
         v.or_insert(value)
     }
+
+    /// Returns the lock serializing the initialization of the static, if it
+    /// was created.
+    pub(crate) fn lock<T: 'static>(
+        &self,
+        key: &'static crate::lazy_static::Lazy<T>,
+    ) -> Option<Mutex> {
+        self.locks.get(&StaticKeyId::new(key)).copied()
+    }
+
+    pub(crate) fn set_lock<T: 'static>(
+        &mut self,
+        key: &'static crate::lazy_static::Lazy<T>,
+        lock: Mutex,
+    ) {
+        self.locks.insert(StaticKeyId::new(key), lock);
+    }
 }
 
 impl StaticKeyId {
@@ -18,7 +18,7 @@ use crate::rt::{thread, MAX_THREADS};
 
 use std::ops;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(super) struct LocationSet {
     locations: [Location; MAX_THREADS],
 }
@@ -92,24 +92,34 @@ impl PanicBuilder {
         let width = self
             .locations
             .iter()
-            .filter(|(_, _, location)| location.is_captured())
+            .filter(|(_, thread, location)| thread.is_some() || location.is_captured())
             .map(|(key, ..)| key.len())
             .max();
 
         if let Some(width) = width {
             msg = format!("\n{}", msg);
             for (key, thread, location) in &self.locations {
-                if !location.is_captured() {
+                if thread.is_none() && !location.is_captured() {
                     continue;
                 }
                 let spaces: String = (0..width - key.len()).map(|_| " ").collect();
 
-                let th = thread
-                    .map(|th| format!("thread #{} @ ", th))
-                    .unwrap_or("".to_string());
+                let th = match thread {
+                    Some(th) if location.is_captured() => format!("thread #{} @ ", th),
+                    Some(th) => format!("thread #{}", th),
+                    None => "".to_string(),
+                };
 
                 msg.push_str(&format!("\n    {}{}: {}{}", spaces, key, th, location));
             }
+
+            if self
+                .locations
+                .iter()
+                .any(|(.., location)| !location.is_captured())
+            {
+                msg.push_str("\n\nRun with `LOOM_LOCATION=1` to report source locations.");
+            }
         }
 
         panic!("{}\n", msg);
@@ -136,6 +146,10 @@ mod cfg {
         pub(crate) fn is_captured(&self) -> bool {
             self.0.is_some()
         }
+
+        pub(crate) fn get(&self) -> Option<&'static std::panic::Location<'static>> {
+            self.0
+        }
     }
 
     impl fmt::Display for Location {
@@ -0,0 +1,177 @@
+//! Logs the events of executions, see `Builder::log`.
+
+use std::fmt::{self, Write};
+
+/// Logs the events of an execution allowed by a filter.
+#[derive(Debug, Default)]
+pub(crate) struct Log {
+    /// Levels of the logged targets, `None` when logging is disabled
+    filter: Option<Filter>,
+
+    /// When set, the events of the current execution are buffered and only
+    /// printed if it fails
+    buffer: Option<String>,
+}
+
+/// Verbosity of an event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Level {
+    Off,
+    Info,
+    Debug,
+    Trace,
+}
+
+/// Levels enabled for each target, parsed from directives such as
+/// `debug,mutex=off`.
+#[derive(Debug, Clone)]
+struct Filter {
+    /// Level of the targets without a directive
+    default: Level,
+
+    targets: Vec<(String, Level)>,
+}
+
+/// Targets events are logged for.
+const TARGETS: &[&str] = &["thread", "atomic", "mutex", "rwlock", "condvar"];
+
+/// Logs an event of `target` at `level`, if the filter allows it.
+macro_rules! log {
+    ($log:expr, $level:ident, $target:literal, $($arg:tt)*) => {
+        if $log.enabled($target, $crate::rt::log::Level::$level) {
+            $log.write($target, format_args!($($arg)*));
+        }
+    };
+}
+
+impl Log {
+    /// Logs the events allowed by `directives`, only printing the events of
+    /// failing executions if `failing_only` is set.
+    ///
+    /// Panics if the directives are invalid.
+    pub(crate) fn new(directives: &str, failing_only: bool) -> Log {
+        Log {
+            filter: Some(Filter::parse(directives)),
+            buffer: if failing_only {
+                Some(String::new())
+            } else {
+                None
+            },
+        }
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.filter.is_some()
+    }
+
+    pub(crate) fn enabled(&self, target: &str, level: Level) -> bool {
+        match &self.filter {
+            Some(filter) => level <= filter.level(target),
+            None => false,
+        }
+    }
+
+    pub(crate) fn write(&mut self, target: &str, args: fmt::Arguments<'_>) {
+        match &mut self.buffer {
+            Some(buffer) => {
+                let _ = writeln!(buffer, "[{}] {}", target, args);
+            }
+            None => println!("[{}] {}", target, args),
+        }
+    }
+
+    /// Prints the buffered events of the failed execution.
+    pub(crate) fn flush(&mut self) {
+        if let Some(buffer) = &mut self.buffer {
+            print!("{}", buffer);
+            buffer.clear();
+        }
+    }
+
+    /// Discards the buffered events, before the next execution.
+    pub(super) fn clear(&mut self) {
+        if let Some(buffer) = &mut self.buffer {
+            buffer.clear();
+        }
+    }
+}
+
+impl Filter {
+    fn parse(directives: &str) -> Filter {
+        let mut filter = Filter {
+            default: Level::Off,
+            targets: vec![],
+        };
+
+        for directive in directives.split(',').map(str::trim) {
+            if directive.is_empty() {
+                continue;
+            }
+
+            match directive.find('=') {
+                Some(i) => {
+                    let target = directive[..i].trim();
+
+                    assert!(
+                        TARGETS.contains(&target),
+                        "invalid `LOOM_LOG` target `{}`, expected one of {:?}",
+                        target,
+                        TARGETS
+                    );
+
+                    let level = Level::parse(directive[i + 1..].trim());
+                    filter.targets.push((target.to_string(), level));
+                }
+                None => filter.default = Level::parse(directive),
+            }
+        }
+
+        filter
+    }
+
+    fn level(&self, target: &str) -> Level {
+        // The last directive for a target wins.
+        self.targets
+            .iter()
+            .rev()
+            .find(|(t, _)| t == target)
+            .map_or(self.default, |(_, level)| *level)
+    }
+}
+
+/// Returns the directives selected by the value of the `LOOM_LOG` environment
+/// variable.
+///
+/// `LOOM_LOG` used to only enable logging: a value that is not a list of
+/// directives, such as `1` or `true`, logs at the `info` level.
+pub(crate) fn env_directives(value: &str) -> String {
+    let is_directives =
+        value.contains('=') || value.contains(',') || Level::try_parse(value.trim()).is_some();
+
+    if is_directives {
+        value.to_string()
+    } else {
+        "info".to_string()
+    }
+}
+
+impl Level {
+    fn parse(level: &str) -> Level {
+        Level::try_parse(level).unwrap_or_else(|| {
+            panic!(
+                "invalid `LOOM_LOG` level `{}`, expected one of `off`, `info`, `debug` or `trace`",
+                level
+            )
+        })
+    }
+
+    fn try_parse(level: &str) -> Option<Level> {
+        match level {
+            "off" => Some(Level::Off),
+            "info" => Some(Level::Info),
+            "debug" => Some(Level::Debug),
+            "trace" => Some(Level::Trace),
+            _ => None,
+        }
+    }
+}
@@ -1,25 +1,38 @@
+#[macro_use]
+pub(crate) mod log;
+pub(crate) use self::log::Log;
+
 mod access;
 use self::access::Access;
 
 mod alloc;
-pub(crate) use self::alloc::{alloc, dealloc, Allocation};
+pub(crate) use self::alloc::{alloc, alloc_fails, dealloc, fail_next_alloc, realloc, Allocation};
 
 mod arc;
 pub(crate) use self::arc::Arc;
 
 mod atomic;
-pub(crate) use self::atomic::{fence, Atomic};
+pub(crate) use self::atomic::{explain, fence, Atomic};
 
 #[macro_use]
 mod location;
 pub(crate) use self::location::Location;
 
-mod cell;
+pub(crate) mod cell;
 pub(crate) use self::cell::Cell;
 
+#[cfg(feature = "checkpoint")]
+pub(crate) mod codec;
+
 mod condvar;
 pub(crate) use self::condvar::Condvar;
 
+#[cfg_attr(not(feature = "custom"), allow(dead_code))]
+pub(crate) mod custom;
+pub(crate) use self::custom::Custom;
+
+mod deadlock;
+
 mod execution;
 pub(crate) use self::execution::Execution;
 
@@ -33,13 +46,20 @@ pub(crate) use self::num::Numeric;
 pub(crate) mod object;
 
 mod mpsc;
-pub(crate) use self::mpsc::Channel;
+pub(crate) use self::mpsc::{Channel, TryRecvError, TrySendError};
 
 mod mutex;
 pub(crate) use self::mutex::Mutex;
 
 mod path;
-pub(crate) use self::path::Path;
+#[cfg(feature = "checkpoint")]
+pub(crate) use self::path::StrategyState;
+pub(crate) use self::path::{Bound, Choice, Path};
+
+pub(crate) mod profile;
+pub(crate) use self::profile::Profile;
+
+mod rng;
 
 mod rwlock;
 pub(crate) use self::rwlock::RwLock;
@@ -47,35 +67,230 @@ pub(crate) use self::rwlock::RwLock;
 mod scheduler;
 pub(crate) use self::scheduler::Scheduler;
 
-mod synchronize;
+mod semaphore;
+pub(crate) use self::semaphore::Semaphore;
+
+pub(crate) mod synchronize;
 pub(crate) use self::synchronize::Synchronize;
 
 pub(crate) mod lazy_static;
 pub(crate) mod thread;
+pub(crate) mod time;
+
+mod tree;
+pub(crate) use self::tree::Tree;
 
 mod vv;
 pub(crate) use self::vv::VersionVec;
 
+use crate::model::{MainExit, YieldMode};
+
+use std::any::Any;
+use std::mem;
+use std::panic::{self, AssertUnwindSafe};
+
 /// Maximum number of threads that can be included in a model.
 pub const MAX_THREADS: usize = 4;
 
 /// Maximum number of atomic store history to track per-cell.
 pub(crate) const MAX_ATOMIC_HISTORY: usize = 7;
 
-pub(crate) fn spawn<F>(f: F) -> crate::rt::thread::Id
+pub(crate) fn spawn<F>(f: F, location: Location) -> crate::rt::thread::Id
 where
     F: FnOnce() + 'static,
 {
-    let id = execution(|execution| execution.new_thread());
+    let id = execution(|execution| execution.new_thread(location));
 
     Scheduler::spawn(Box::new(move || {
-        f();
+        // A thread terminated before it started does not run at all.
+        if !execution(|execution| execution.threads.active().terminate) {
+            if let Err(panic) = panic::catch_unwind(AssertUnwindSafe(f)) {
+                if !panic.is::<Terminated>() {
+                    panic::resume_unwind(panic);
+                }
+            }
+        }
+
         thread_done();
     }));
 
     id
 }
 
+/// Returns `true` if the thread about to be spawned should fail to spawn.
+pub(crate) fn spawn_fails() -> bool {
+    execution(|execution| execution.spawn_failures) && choose(2) == 1
+}
+
+/// Payload of the panic unwinding a thread terminated by the main thread
+/// returning.
+struct Terminated;
+
+/// Records the panic of the active thread, to be returned when joining it.
+///
+/// Panics raised by loom itself, such as a detected bug, fail the execution
+/// instead of being handed to the joining thread.
+pub(crate) fn thread_panicked(panic: Box<dyn Any + Send>) {
+    if panic.is::<Terminated>() || execution(|execution| execution.failed) {
+        panic::resume_unwind(panic);
+    }
+
+    execution(|execution| execution.threads.active_mut().panic = Some(panic));
+}
+
+/// Fails the execution with a bug loom detected outside of the runtime.
+///
+/// A plain panic of a spawned thread is handed to the thread joining it,
+/// which may ignore it. Raised from the runtime, the panic fails the
+/// execution instead.
+pub(crate) fn fail(message: String) -> ! {
+    execution(|_| panic!("{}", message));
+    unreachable!()
+}
+
+/// Takes the panic the thread terminated with, if any.
+pub(crate) fn take_panic(thread: thread::Id) -> Option<Box<dyn Any + Send>> {
+    execution(|execution| execution.threads[thread].panic.take())
+}
+
+/// Applies the [`MainExit`] policy once the closure of the main thread
+/// returned.
+pub(crate) fn main_returned() {
+    let running = execution(|execution| {
+        let active = execution.threads.active_id();
+
+        let running: Vec<_> = execution
+            .threads
+            .iter_mut()
+            .filter(|(id, thread)| *id != active && !thread.is_terminated())
+            .map(|(id, thread)| (id.as_usize(), thread))
+            .collect();
+
+        match execution.main_exit {
+            MainExit::Wait => vec![],
+            MainExit::RequireJoined => running.into_iter().map(|(id, _)| id).collect(),
+            MainExit::Terminate => {
+                for (_, thread) in running {
+                    thread.terminate = true;
+                    thread.set_runnable();
+                }
+
+                vec![]
+            }
+        }
+    });
+
+    if !running.is_empty() {
+        let running: Vec<_> = running.iter().map(|id| format!("thread #{}", id)).collect();
+
+        panic!(
+            "the model returned while threads were still running: {}; join the threads \
+             before returning",
+            running.join(", ")
+        );
+    }
+}
+
+/// Unwinds the active thread if it was terminated when the main thread
+/// returned.
+fn check_terminated() {
+    // A thread already unwinding is left to complete.
+    let terminate = execution(|execution| {
+        let thread = execution.threads.active_mut();
+        let terminate = mem::replace(&mut thread.terminate, false) && !std::thread::panicking();
+        thread.exiting |= terminate;
+        terminate
+    });
+
+    if terminate {
+        panic::resume_unwind(Box::new(Terminated));
+    }
+}
+
+/// Kills the active thread in the executions where the failure of a spawned
+/// thread is injected at this branch point, see
+/// `Builder::inject_thread_failure`.
+///
+/// Threads are not killed in the destructors of loom objects, which would not
+/// complete as the thread unwinds.
+fn check_killed() {
+    let candidate = execution(|execution| {
+        let thread = execution.threads.active();
+
+        execution.inject_thread_failure
+            && execution.killed.is_none()
+            && thread.killable
+            && !matches!(thread.operation, Some(operation) if operation.is_drop())
+    });
+
+    if !candidate || std::thread::panicking() || choose(2) == 0 {
+        return;
+    }
+
+    execution(|execution| execution.killed = Some(execution.threads.active_id()));
+    panic::resume_unwind(Box::new("thread killed by an injected failure"));
+}
+
+/// Sets whether the active thread may be killed by an injected failure.
+pub(crate) fn set_killable(killable: bool) {
+    execution(|execution| execution.threads.active_mut().killable = killable);
+}
+
+/// Returns `true` if the active thread is unwinding from a panic, in which
+/// case the locks it releases are poisoned.
+pub(crate) fn panicking() -> bool {
+    std::thread::panicking() && !execution(|execution| execution.threads.active().exiting)
+}
+
+/// Branch on a value in `0..n`.
+pub(crate) fn choose(n: usize) -> usize {
+    assert!(n > 0, "cannot choose among zero options");
+
+    if n == 1 {
+        return 0;
+    }
+
+    execution(|execution| execution.path.branch_choose(n))
+}
+
+/// Capture the state of the runtime, see `Execution::take_snapshot`.
+pub(crate) fn take_snapshot() {
+    execution(|execution| execution.take_snapshot());
+}
+
+/// Returns `true` if the current execution started from a snapshot.
+pub(crate) fn is_restored() -> bool {
+    execution(|execution| execution.is_restored())
+}
+
+/// Run `f` without preempting the current thread.
+pub(crate) fn critical<F, R>(f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    struct Reset(bool);
+
+    impl Drop for Reset {
+        fn drop(&mut self) {
+            execution(|execution| {
+                let thread = execution.threads.active_mut();
+                thread.critical = self.0;
+
+                if !thread.critical {
+                    thread.critical_branch = None;
+                }
+            });
+        }
+    }
+
+    let prev = execution(|execution| {
+        std::mem::replace(&mut execution.threads.active_mut().critical, true)
+    });
+
+    let _reset = Reset(prev);
+    f()
+}
+
 /// Marks the current thread as blocked
 pub fn park() {
     execution(|execution| {
@@ -85,6 +300,7 @@ pub fn park() {
     });
 
     Scheduler::switch();
+    check_terminated();
 }
 
 /// Add an execution branch point.
@@ -101,6 +317,8 @@ where
         Scheduler::switch();
     }
 
+    check_terminated();
+    check_killed();
     ret
 }
 
@@ -121,7 +339,11 @@ where
 /// progress.
 pub fn yield_now() {
     let switch = execution(|execution| {
-        execution.threads.active_mut().set_yield();
+        // As a preemption point, the thread stays runnable.
+        if execution.yield_mode != YieldMode::Preemption {
+            execution.threads.active_mut().set_yield();
+        }
+
         execution.threads.active_mut().operation = None;
         execution.schedule()
     });
@@ -129,6 +351,8 @@ pub fn yield_now() {
     if switch {
         Scheduler::switch();
     }
+
+    check_terminated();
 }
 
 pub(crate) fn execution<F, R>(f: F) -> R
@@ -139,14 +363,37 @@ where
 }
 
 pub fn thread_done() {
-    let locals = execution(|execution| execution.threads.active_mut().drop_locals());
-
-    // Drop outside of the execution context
-    drop(locals);
+    // Destroy the thread locals one at a time. The order is unspecified, every
+    // order is explored as a destructor may access a thread local that was
+    // already destroyed.
+    loop {
+        let local = execution(|execution| {
+            let live = execution.threads.active().live_locals();
+
+            let i = match live.len() {
+                0 => return None,
+                1 => 0,
+                n => execution.path.branch_choose(n),
+            };
+
+            Some(execution.threads.active_mut().take_local(live[i]))
+        });
+
+        match local {
+            // Drop outside of the execution context
+            Some(local) => drop(local),
+            None => break,
+        }
+    }
 
     execution(|execution| {
         execution.threads.active_mut().operation = None;
         execution.threads.active_mut().set_terminated();
+        execution.observe_pending();
+
+        let thread = execution.threads.active_id().as_usize();
+        execution.observe(|o| o.thread_terminated(thread));
+
         execution.schedule();
     });
 }
@@ -1,5 +1,5 @@
-use crate::rt::{object, Access, Synchronize, VersionVec};
-use std::collections::VecDeque;
+use crate::rt::{deadlock, object, thread, Access, Execution, Location, Synchronize, VersionVec};
+use std::collections::{HashMap, VecDeque};
 use std::sync::atomic::Ordering::{Acquire, Release};
 
 #[derive(Debug)]
@@ -7,15 +7,43 @@ pub(crate) struct Channel {
     state: object::Ref<State>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(super) struct State {
     /// Count of messages in the channel.
     msg_cnt: usize,
 
-    /// Last access that was a send operation.
+    /// Maximum number of buffered messages, `None` if the channel is
+    /// unbounded. A capacity of zero makes every send a rendezvous with the
+    /// receiver.
+    capacity: Option<usize>,
+
+    /// Count of messages ever sent, used to number them.
+    sent_cnt: usize,
+
+    /// Count of messages ever received.
+    recv_cnt: usize,
+
+    /// Senders of a rendezvous channel waiting for their message, identified
+    /// by its number, to be received.
+    rendezvous: HashMap<thread::Id, usize>,
+
+    /// Count of live senders.
+    senders: usize,
+
+    /// `true` while the receiver is alive.
+    receiver: bool,
+
+    /// Where the channel was created
+    created: Location,
+
+    /// Last access that was a send operation, or an operation depending on
+    /// all other operations.
     last_send_access: Option<Access>,
-    /// Last access that was a receive operation.
+    /// Last access that was a receive operation, or an operation depending on
+    /// all other operations.
     last_recv_access: Option<Access>,
+    /// Last access of any kind.
+    last_access: Option<Access>,
 
     /// A synchronization point for synchronizing the sending threads and the
     /// channel.
@@ -36,98 +64,326 @@ pub(super) struct State {
     /// A synchronization point per message synchronizing the receiving thread
     /// with the channel state at the point when the received message was sent.
     receiver_synchronize: VecDeque<Synchronize>,
+
+    /// Released by every dropped sender, acquired by the receiver observing
+    /// the channel is disconnected.
+    senders_dropped: Synchronize,
+    /// Released by the dropped receiver, acquired by senders observing the
+    /// channel is disconnected.
+    receiver_dropped: Synchronize,
 }
 
 /// Actions performed on the Channel.
+///
+/// Blocking sends only depend on other sends, and blocking receives on other
+/// receives: the outcome of a send does not depend on receives (and vice
+/// versa) other than by blocking the thread, which the scheduler already
+/// accounts for. All other actions observe the channel without blocking and
+/// depend on every operation.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub(super) enum Action {
     /// Send a message
     MsgSend,
     /// Receive a message
     MsgRecv,
+    /// Send a message if the channel has room for it
+    MsgTrySend,
+    /// Receive a message if the channel has one
+    MsgTryRecv,
+    /// Drop the last sender or the receiver
+    Disconnect,
+}
+
+/// Reason for failing to send a message without blocking.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum TrySendError {
+    Full,
+    Disconnected,
+}
+
+/// Reason for failing to receive a message without blocking.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum TryRecvError {
+    Empty,
+    Disconnected,
 }
 
 impl Channel {
-    pub(crate) fn new() -> Self {
+    /// Creates a channel buffering up to `capacity` messages, or an unbounded
+    /// channel if `capacity` is `None`.
+    pub(crate) fn new(capacity: Option<usize>, location: Location) -> Self {
         super::execution(|execution| {
             let state = execution.objects.insert(State {
                 msg_cnt: 0,
+                capacity,
+                sent_cnt: 0,
+                recv_cnt: 0,
+                rendezvous: HashMap::new(),
+                senders: 1,
+                receiver: true,
+                created: location,
                 last_send_access: None,
                 last_recv_access: None,
+                last_access: None,
                 sender_synchronize: Synchronize::new(),
                 receiver_synchronize: VecDeque::new(),
+                senders_dropped: Synchronize::new(),
+                receiver_dropped: Synchronize::new(),
             });
             Self { state }
         })
     }
 
-    pub(crate) fn send(&self) {
-        self.state.branch_action(Action::MsgSend);
+    /// Sends a message, blocking while the channel is full.
+    ///
+    /// Returns `false` if the receiver has been dropped, in which case the
+    /// message must be handed back to the caller.
+    ///
+    /// On a rendezvous channel, the caller must then wait for the message to
+    /// be received using `wait_received`.
+    pub(crate) fn send(&self) -> bool {
+        let full = super::execution(|execution| !self.get_state(&mut execution.objects).can_send());
+        self.state.branch_disable(Action::MsgSend, full);
         super::execution(|execution| {
             let state = self.state.get_mut(&mut execution.objects);
-            state.msg_cnt = state.msg_cnt.checked_add(1).expect("overflow");
 
-            state
-                .sender_synchronize
-                .sync_store(&mut execution.threads, Release);
-            state
-                .receiver_synchronize
-                .push_back(state.sender_synchronize.clone());
-
-            if state.msg_cnt == 1 {
-                // Unblock all threads that are blocked waiting on this channel
-                let thread_id = execution.threads.active_id();
-                for (id, thread) in execution.threads.iter_mut() {
-                    if id == thread_id {
-                        continue;
-                    }
-
-                    let obj = thread
-                        .operation
-                        .as_ref()
-                        .map(|operation| operation.object());
-
-                    if obj == Some(self.state.erase()) {
-                        thread.set_runnable();
-                    }
-                }
+            if !state.receiver {
+                state
+                    .receiver_dropped
+                    .sync_load(&mut execution.threads, Acquire);
+                return false;
+            }
+
+            let seq = state.push(&mut execution.threads);
+
+            if state.capacity == Some(0) {
+                state.rendezvous.insert(execution.threads.active_id(), seq);
             }
+
+            self.update_waiters(execution);
+            true
         })
     }
 
-    pub(crate) fn recv(&self) {
-        self.state.branch_disable(Action::MsgRecv, self.is_empty());
+    /// Blocks until the message sent by the active thread on a rendezvous
+    /// channel is received. Does nothing on other channels.
+    ///
+    /// Returns `false` if the receiver has been dropped before receiving the
+    /// message, in which case the message must be handed back to the caller.
+    pub(crate) fn wait_received(&self) -> bool {
+        let pending = super::execution(|execution| {
+            let state = self.state.get(&execution.objects);
+            let seq = state.rendezvous.get(&execution.threads.active_id())?;
+            Some(state.recv_cnt <= *seq && state.receiver)
+        });
+
+        let pending = match pending {
+            Some(pending) => pending,
+            None => return true,
+        };
+
+        self.state.branch_disable(Action::MsgSend, pending);
         super::execution(|execution| {
             let state = self.state.get_mut(&mut execution.objects);
-            let thread_id = execution.threads.active_id();
-            state.msg_cnt = state
-                .msg_cnt
-                .checked_sub(1)
-                .expect("expected to be able to read the message");
-            let mut synchronize = state.receiver_synchronize.pop_front().unwrap();
-            dbg!(synchronize.sync_load(&mut execution.threads, Acquire));
+            let seq = state
+                .rendezvous
+                .remove(&execution.threads.active_id())
+                .unwrap();
+
+            if state.recv_cnt <= seq {
+                state
+                    .receiver_dropped
+                    .sync_load(&mut execution.threads, Acquire);
+                return false;
+            }
+
+            true
+        })
+    }
+
+    /// Sends a message if it can be done without blocking.
+    pub(crate) fn try_send(&self) -> Result<(), TrySendError> {
+        self.state.branch_action(Action::MsgTrySend);
+        super::execution(|execution| {
+            let state = self.state.get_mut(&mut execution.objects);
+
+            if !state.receiver {
+                state
+                    .receiver_dropped
+                    .sync_load(&mut execution.threads, Acquire);
+                return Err(TrySendError::Disconnected);
+            }
+
+            let has_room = match state.capacity {
+                // A rendezvous only succeeds if the receiver is waiting for
+                // the message.
+                Some(0) => state.msg_cnt == 0 && self.receiver_waiting(execution),
+                Some(capacity) => state.msg_cnt < capacity,
+                None => true,
+            };
+
+            if !has_room {
+                return Err(TrySendError::Full);
+            }
+
+            let state = self.state.get_mut(&mut execution.objects);
+            state.push(&mut execution.threads);
+            self.update_waiters(execution);
+            Ok(())
+        })
+    }
+
+    /// Receives a message, blocking while the channel is empty.
+    ///
+    /// Returns `false` if the channel is empty and all senders have been
+    /// dropped.
+    pub(crate) fn recv(&self) -> bool {
+        let empty =
+            super::execution(|execution| !self.get_state(&mut execution.objects).can_recv());
+        self.state.branch_disable(Action::MsgRecv, empty);
+        super::execution(|execution| {
+            let state = self.state.get_mut(&mut execution.objects);
+
             if state.msg_cnt == 0 {
-                // Block all **other** threads attempting to read from the channel
-                for (id, thread) in execution.threads.iter_mut() {
-                    if id == thread_id {
-                        continue;
-                    }
-
-                    if let Some(operation) = thread.operation.as_ref() {
-                        if operation.object() == self.state.erase()
-                            && operation.action() == object::Action::Channel(Action::MsgRecv)
-                        {
-                            thread.set_blocked();
-                        }
-                    }
-                }
+                assert_eq!(state.senders, 0, "expected to be able to read the message");
+                state
+                    .senders_dropped
+                    .sync_load(&mut execution.threads, Acquire);
+                return false;
+            }
+
+            self.take(execution);
+            true
+        })
+    }
+
+    /// Receives a message if it can be done without blocking.
+    pub(crate) fn try_recv(&self) -> Result<(), TryRecvError> {
+        self.state.branch_action(Action::MsgTryRecv);
+        super::execution(|execution| {
+            let state = self.state.get_mut(&mut execution.objects);
+
+            if state.msg_cnt > 0 {
+                self.take(execution);
+                return Ok(());
+            }
+
+            if state.senders == 0 {
+                state
+                    .senders_dropped
+                    .sync_load(&mut execution.threads, Acquire);
+                return Err(TryRecvError::Disconnected);
             }
+
+            Err(TryRecvError::Empty)
+        })
+    }
+
+    /// Takes the first message out of the channel.
+    fn take(&self, execution: &mut Execution) {
+        let state = self.state.get_mut(&mut execution.objects);
+        state.msg_cnt -= 1;
+        state.recv_cnt += 1;
+        let mut synchronize = state.receiver_synchronize.pop_front().unwrap();
+        synchronize.sync_load(&mut execution.threads, Acquire);
+        self.update_waiters(execution);
+    }
+
+    /// Tracks a new sender.
+    pub(crate) fn clone_sender(&self) {
+        super::execution(|execution| {
+            let state = self.state.get_mut(&mut execution.objects);
+            state.senders = state.senders.checked_add(1).expect("overflow");
+        })
+    }
+
+    /// Tracks a dropped sender, disconnecting the channel if it was the last
+    /// one.
+    pub(crate) fn drop_sender(&self) {
+        let last = super::execution(|execution| {
+            let state = self.state.get_mut(&mut execution.objects);
+            state
+                .senders_dropped
+                .sync_store(&mut execution.threads, Release);
+            state.senders == 1
+        });
+
+        if last {
+            self.state.branch_action(Action::Disconnect);
+        }
+
+        super::execution(|execution| {
+            let state = self.state.get_mut(&mut execution.objects);
+            state.senders -= 1;
+            self.update_waiters(execution);
         })
     }
 
-    /// Returns `true` if the channel is currently empty
-    pub(crate) fn is_empty(&self) -> bool {
-        super::execution(|execution| self.get_state(&mut execution.objects).msg_cnt == 0)
+    /// Tracks the dropped receiver, disconnecting the channel and discarding
+    /// any buffered messages.
+    pub(crate) fn drop_receiver(&self) {
+        self.state.branch_action(Action::Disconnect);
+        super::execution(|execution| {
+            let state = self.state.get_mut(&mut execution.objects);
+            state.receiver = false;
+            state.msg_cnt = 0;
+
+            // The receiver drops the buffered messages.
+            for mut synchronize in state.receiver_synchronize.drain(..) {
+                synchronize.sync_load(&mut execution.threads, Acquire);
+            }
+
+            state
+                .receiver_dropped
+                .sync_store(&mut execution.threads, Release);
+            self.update_waiters(execution);
+        })
+    }
+
+    /// Returns `true` if a thread other than the active one is blocked
+    /// receiving from the channel.
+    fn receiver_waiting(&self, execution: &Execution) -> bool {
+        let thread_id = execution.threads.active_id();
+
+        execution.threads.iter().any(|(id, th)| {
+            id != thread_id
+                && th.is_blocked()
+                && matches!(th.operation.as_ref(), Some(op) if op.object() == self.state.erase()
+                    && op.action() == object::Action::Channel(Action::MsgRecv))
+        })
+    }
+
+    /// Blocks or unblocks the threads waiting on the channel according to its
+    /// new state.
+    fn update_waiters(&self, execution: &mut Execution) {
+        let thread_id = execution.threads.active_id();
+        let state = self.state.get(&execution.objects);
+
+        for (id, th) in execution.threads.iter_mut() {
+            if id == thread_id {
+                continue;
+            }
+
+            let action = match th.operation.as_ref() {
+                Some(op) if op.object() == self.state.erase() => op.action(),
+                _ => continue,
+            };
+
+            let ready = match action.into() {
+                Action::MsgSend => match state.rendezvous.get(&id) {
+                    Some(&seq) => state.recv_cnt > seq || !state.receiver,
+                    None => state.can_send(),
+                },
+                Action::MsgRecv => state.can_recv(),
+                Action::MsgTrySend | Action::MsgTryRecv | Action::Disconnect => continue,
+            };
+
+            if ready {
+                th.set_runnable();
+            } else {
+                th.set_blocked();
+            }
+        }
     }
 
     fn get_state<'a>(&self, objects: &'a mut object::Store) -> &'a mut State {
@@ -136,21 +392,83 @@ impl Channel {
 }
 
 impl State {
-    pub(super) fn check_for_leaks(&self) {
-        assert_eq!(0, self.msg_cnt, "Messages leaked");
+    /// Returns `true` if a blocking send would not block.
+    fn can_send(&self) -> bool {
+        let capacity = match self.capacity {
+            Some(capacity) => capacity.max(1),
+            None => return true,
+        };
+
+        self.msg_cnt < capacity || !self.receiver
+    }
+
+    /// Returns `true` if a blocking receive would not block.
+    fn can_recv(&self) -> bool {
+        self.msg_cnt > 0 || self.senders == 0
+    }
+
+    /// Puts a message in the channel, returning its number.
+    fn push(&mut self, threads: &mut thread::Set) -> usize {
+        self.msg_cnt = self.msg_cnt.checked_add(1).expect("overflow");
+        self.sent_cnt += 1;
+
+        self.sender_synchronize.sync_store(threads, Release);
+        self.receiver_synchronize.push_back(self.sender_synchronize);
+
+        self.sent_cnt - 1
+    }
+
+    /// Describes the channel for deadlock reports.
+    pub(super) fn resource(&self) -> deadlock::Resource {
+        deadlock::Resource {
+            kind: if self.capacity.is_some() {
+                "send or receive on channel"
+            } else {
+                "receive on channel"
+            },
+            created: self.created,
+            holders: vec![],
+        }
+    }
+
+    pub(super) fn location(&self) -> Location {
+        self.created
+    }
+
+    /// Describes the channel if messages leaked.
+    pub(super) fn leak(&self) -> Option<String> {
+        if self.msg_cnt == 0 {
+            return None;
+        }
+
+        Some(format!(
+            "channel with {} unreceived message{}",
+            self.msg_cnt,
+            if self.msg_cnt == 1 { "" } else { "s" }
+        ))
     }
 
     pub(super) fn last_dependent_access(&self, action: Action) -> Option<&Access> {
         match action {
             Action::MsgSend => self.last_send_access.as_ref(),
             Action::MsgRecv => self.last_recv_access.as_ref(),
+            Action::MsgTrySend | Action::MsgTryRecv | Action::Disconnect => {
+                self.last_access.as_ref()
+            }
         }
     }
 
     pub(super) fn set_last_access(&mut self, action: Action, path_id: usize, version: &VersionVec) {
+        // Always set `last_access`
+        Access::set_or_create(&mut self.last_access, path_id, version);
+
         match action {
             Action::MsgSend => Access::set_or_create(&mut self.last_send_access, path_id, version),
             Action::MsgRecv => Access::set_or_create(&mut self.last_recv_access, path_id, version),
+            Action::MsgTrySend | Action::MsgTryRecv | Action::Disconnect => {
+                Access::set_or_create(&mut self.last_send_access, path_id, version);
+                Access::set_or_create(&mut self.last_recv_access, path_id, version);
+            }
         }
     }
 }
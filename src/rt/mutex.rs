@@ -1,5 +1,6 @@
+use crate::rt::location::{self, Location, LocationSet};
 use crate::rt::object;
-use crate::rt::{thread, Access, Synchronize, VersionVec};
+use crate::rt::{deadlock, thread, Access, Synchronize, VersionVec};
 
 use std::sync::atomic::Ordering::{Acquire, Release};
 
@@ -8,7 +9,7 @@ pub(crate) struct Mutex {
     state: object::Ref<State>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(super) struct State {
     /// If the mutex should establish sequential consistency.
     seq_cst: bool,
@@ -17,6 +18,15 @@ pub(super) struct State {
     /// references the thread that currently holds the mutex.
     lock: Option<thread::Id>,
 
+    /// Where the mutex was created
+    created: Location,
+
+    /// Set when a thread panics while holding the lock
+    poisoned: bool,
+
+    /// Where each thread last acquired the mutex
+    acquired: LocationSet,
+
     /// Tracks access to the mutex
     last_access: Option<Access>,
 
@@ -25,11 +35,14 @@ pub(super) struct State {
 }
 
 impl Mutex {
-    pub(crate) fn new(seq_cst: bool) -> Mutex {
+    pub(crate) fn new(seq_cst: bool, location: Location) -> Mutex {
         super::execution(|execution| {
             let state = execution.objects.insert(State {
                 seq_cst,
                 lock: None,
+                created: location,
+                poisoned: false,
+                acquired: LocationSet::new(),
                 last_access: None,
                 synchronize: Synchronize::new(),
             });
@@ -38,16 +51,31 @@ impl Mutex {
         })
     }
 
-    pub(crate) fn acquire_lock(&self) {
+    /// Returns an owner releasing the mutex when dropped.
+    pub(crate) fn owner(&self) -> object::Owner {
+        super::execution(|execution| self.state.owner(execution))
+    }
+
+    pub(crate) fn acquire_lock(&self, location: Location) {
+        self.check_not_held(location);
+
         self.state.branch_acquire(self.is_locked());
-        assert!(self.post_acquire(), "expected to be able to acquire lock");
+        assert!(
+            self.post_acquire(location),
+            "expected to be able to acquire lock"
+        );
     }
 
-    pub(crate) fn try_acquire_lock(&self) -> bool {
+    pub(crate) fn try_acquire_lock(&self, location: Location) -> bool {
         self.state.branch_opaque();
-        self.post_acquire()
+        self.post_acquire(location)
     }
 
+    /// Release the lock, making every thread waiting for it runnable.
+    ///
+    /// Waiters are not handed the lock: they compete with the threads newly
+    /// acquiring it, including the releasing thread. The scheduler explores
+    /// both the handoff to any of the waiters and barging by a newcomer.
     pub(crate) fn release_lock(&self) {
         super::execution(|execution| {
             let state = self.state.get_mut(&mut execution.objects);
@@ -55,6 +83,15 @@ impl Mutex {
             // Release the lock flag
             state.lock = None;
 
+            log!(
+                execution.log,
+                Debug,
+                "mutex",
+                "thread #{}: mutex #{} unlocked",
+                execution.threads.active_id(),
+                self.state.erase().as_usize()
+            );
+
             state
                 .synchronize
                 .sync_store(&mut execution.threads, Release);
@@ -83,7 +120,7 @@ impl Mutex {
         });
     }
 
-    fn post_acquire(&self) -> bool {
+    fn post_acquire(&self, location: Location) -> bool {
         super::execution(|execution| {
             let state = self.state.get_mut(&mut execution.objects);
             let thread_id = execution.threads.active_id();
@@ -94,6 +131,16 @@ impl Mutex {
 
             // Set the lock to the current thread
             state.lock = Some(thread_id);
+            state.acquired.track(location, &execution.threads);
+
+            log!(
+                execution.log,
+                Debug,
+                "mutex",
+                "thread #{}: mutex #{} locked",
+                thread_id,
+                self.state.erase().as_usize()
+            );
 
             dbg!(state.synchronize.sync_load(&mut execution.threads, Acquire));
 
@@ -122,6 +169,50 @@ impl Mutex {
         })
     }
 
+    /// Panics if the current thread already holds the lock, as acquiring it
+    /// would never succeed.
+    fn check_not_held(&self, location: Location) {
+        super::execution(|execution| {
+            let state = self.state.get(&execution.objects);
+            let thread_id = execution.threads.active_id();
+
+            if state.lock == Some(thread_id) {
+                location::panic(format!(
+                    "thread #{} attempted to acquire a Mutex it already holds",
+                    thread_id
+                ))
+                .location("created", state.created)
+                .thread("acquired", thread_id, state.acquired[&execution.threads])
+                .thread("attempted", thread_id, location)
+                .fire();
+            }
+        })
+    }
+
+    /// Poison the lock, as the thread holding it panicked.
+    pub(crate) fn poison(&self) {
+        super::execution(|execution| {
+            self.state.get_mut(&mut execution.objects).poisoned = true;
+        });
+    }
+
+    pub(crate) fn is_poisoned(&self) -> bool {
+        super::execution(|execution| self.state.get(&execution.objects).poisoned)
+    }
+
+    pub(crate) fn clear_poison(&self) {
+        super::execution(|execution| {
+            self.state.get_mut(&mut execution.objects).poisoned = false;
+        });
+    }
+
+    /// Returns `true` if the current thread holds the lock.
+    pub(crate) fn is_held(&self) -> bool {
+        super::execution(|execution| {
+            self.state.get(&execution.objects).lock == Some(execution.threads.active_id())
+        })
+    }
+
     /// Returns `true` if the mutex is currently locked
     fn is_locked(&self) -> bool {
         super::execution(|execution| self.state.get(&execution.objects).lock.is_some())
@@ -129,6 +220,23 @@ impl Mutex {
 }
 
 impl State {
+    pub(super) fn location(&self) -> Location {
+        self.created
+    }
+
+    /// Describes the mutex for deadlock reports.
+    pub(super) fn resource(&self) -> deadlock::Resource {
+        deadlock::Resource {
+            kind: "lock Mutex",
+            created: self.created,
+            holders: self
+                .lock
+                .iter()
+                .map(|&id| (id, self.acquired[id.as_usize()]))
+                .collect(),
+        }
+    }
+
     pub(crate) fn last_dependent_access(&self) -> Option<&Access> {
         self.last_access.as_ref()
     }
@@ -1,5 +1,5 @@
 use crate::rt::object;
-use crate::rt::{self, Access, Synchronize, VersionVec};
+use crate::rt::{self, deadlock, thread, Access, Location, Synchronize, VersionVec};
 
 use std::sync::atomic::Ordering::{Acquire, Release};
 
@@ -8,7 +8,7 @@ pub(crate) struct Notify {
     state: object::Ref<State>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(super) struct State {
     /// If true, spurious notifications are possible
     spurious: bool,
@@ -22,6 +22,12 @@ pub(super) struct State {
     /// `true` if there is a pending notification to consume.
     notified: bool,
 
+    /// Where the notify was created
+    created: Location,
+
+    /// The thread expected to send the notification, if known.
+    notifier: Option<thread::Id>,
+
     /// Tracks access to the notify object
     last_access: Option<Access>,
 
@@ -30,13 +36,15 @@ pub(super) struct State {
 }
 
 impl Notify {
-    pub(crate) fn new(seq_cst: bool, spurious: bool) -> Notify {
+    pub(crate) fn new(seq_cst: bool, spurious: bool, location: Location) -> Notify {
         super::execution(|execution| {
             let state = execution.objects.insert(State {
                 spurious,
                 did_spur: false,
                 seq_cst,
                 notified: false,
+                created: location,
+                notifier: None,
                 last_access: None,
                 synchronize: Synchronize::new(),
             });
@@ -45,6 +53,15 @@ impl Notify {
         })
     }
 
+    /// Record that `thread` is the one expected to send the notification.
+    ///
+    /// Only used to explain deadlocks.
+    pub(crate) fn set_notifier(self, thread: thread::Id) {
+        rt::execution(|execution| {
+            self.state.get_mut(&mut execution.objects).notifier = Some(thread);
+        });
+    }
+
     pub(crate) fn notify(self) {
         self.state.branch_opaque();
 
@@ -76,6 +93,12 @@ impl Notify {
         });
     }
 
+    /// Add a branch point dependent with notifying and waiting.
+    #[cfg(feature = "futures")]
+    pub(crate) fn branch_opaque(self) {
+        self.state.branch_opaque();
+    }
+
     pub(crate) fn wait(self) {
         let (notified, spurious) = rt::execution(|execution| {
             let spurious = if self.state.get(&execution.objects).might_spur() {
@@ -128,6 +151,27 @@ impl State {
         self.spurious && !self.did_spur
     }
 
+    /// Describes the notify for deadlock reports.
+    pub(super) fn resource(&self) -> deadlock::Resource {
+        deadlock::Resource {
+            kind: if self.notifier.is_some() {
+                "join thread"
+            } else {
+                "be notified"
+            },
+            created: self.created,
+            holders: self
+                .notifier
+                .iter()
+                .map(|&id| (id, Location::disabled()))
+                .collect(),
+        }
+    }
+
+    pub(super) fn location(&self) -> Location {
+        self.created
+    }
+
     pub(crate) fn last_dependent_access(&self) -> Option<&Access> {
         self.last_access.as_ref()
     }
@@ -1,9 +1,12 @@
+use crate::model::ObjectCounts;
 use crate::rt;
-use crate::rt::{Access, Execution, VersionVec};
+use crate::rt::{deadlock, thread, Access, Execution, Location, VersionVec};
 
 use std::fmt;
 use std::marker::PhantomData;
 
+#[cfg(feature = "checkpoint")]
+use crate::rt::codec::{self, Codec};
 #[cfg(feature = "checkpoint")]
 use serde::{Deserialize, Serialize};
 
@@ -13,6 +16,26 @@ use serde::{Deserialize, Serialize};
 pub(super) struct Store<T = Entry> {
     /// Stored state for all objects.
     entries: Vec<T>,
+
+    /// Objects released before the end of the execution, by kind.
+    #[cfg_attr(feature = "checkpoint", serde(skip))]
+    released: ObjectCounts,
+}
+
+impl<T: Clone> Clone for Store<T> {
+    fn clone(&self) -> Self {
+        Store {
+            entries: self.entries.clone(),
+            released: self.released.clone(),
+        }
+    }
+
+    /// Reuses the allocation of the entries, restoring a snapshot does not
+    /// allocate a new store.
+    fn clone_from(&mut self, source: &Self) {
+        self.entries.clone_from(&source.entries);
+        self.released.clone_from(&source.released);
+    }
 }
 
 pub(super) trait Object: Sized {
@@ -41,6 +64,18 @@ pub(super) struct Ref<T = ()> {
     _p: PhantomData<T>,
 }
 
+/// Releases an object of the execution that created it once dropped, see
+/// `Execution::release`. Held by the public types owning an object.
+#[derive(Debug)]
+pub(crate) struct Owner {
+    obj: Ref,
+    execution: rt::execution::Id,
+}
+
+/// Takes the place of a released object in the store.
+#[derive(Debug, Clone)]
+pub(super) struct Released;
+
 // TODO: mov to separate file
 #[derive(Debug, Copy, Clone)]
 pub(super) struct Operation {
@@ -48,6 +83,12 @@ pub(super) struct Operation {
     action: Action,
 }
 
+impl PartialEq for Operation {
+    fn eq(&self, other: &Operation) -> bool {
+        self.obj.ref_eq(other.obj) && self.action == other.action
+    }
+}
+
 // TODO: move to separate file
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub(super) enum Action {
@@ -60,6 +101,9 @@ pub(super) enum Action {
     /// Action on a channel
     Channel(rt::mpsc::Action),
 
+    /// Action on a custom object
+    Custom(rt::custom::Action),
+
     /// Action on a RwLock
     RwLock(rt::rwlock::Action),
 
@@ -105,7 +149,7 @@ macro_rules! objects {
 }
 
 objects! {
-    #[derive(Debug)]
+    #[derive(Debug, Clone)]
     Entry,
 
     // State tracking allocations. Used for leak detection.
@@ -117,6 +161,9 @@ objects! {
     // State associated with an atomic cell
     Atomic(rt::atomic::State),
 
+    // Orders `SeqCst` fences
+    Fence(rt::atomic::Fence),
+
     // State associated with a mutex.
     Mutex(rt::mutex::State),
 
@@ -132,8 +179,20 @@ objects! {
     // State associated with a modeled channel.
     Channel(rt::mpsc::State),
 
+    // State associated with a modeled semaphore.
+    Semaphore(rt::semaphore::State),
+
     // Tracks access to a memory cell
     Cell(rt::cell::State),
+
+    // The virtual clock
+    Clock(rt::time::Clock),
+
+    // State associated with an object registered by a custom primitive.
+    Custom(rt::custom::State),
+
+    // An object released before the end of the execution.
+    Released(rt::object::Released),
 }
 
 impl<T> Store<T> {
@@ -141,6 +200,7 @@ impl<T> Store<T> {
     pub(super) fn with_capacity(capacity: usize) -> Store<T> {
         Store {
             entries: Vec::with_capacity(capacity),
+            released: ObjectCounts::default(),
         }
     }
 
@@ -148,10 +208,6 @@ impl<T> Store<T> {
         self.entries.len()
     }
 
-    pub(super) fn capacity(&self) -> usize {
-        self.entries.capacity()
-    }
-
     pub(super) fn reserve_exact(&mut self, additional: usize) {
         self.entries.reserve_exact(additional);
     }
@@ -177,6 +233,11 @@ impl<T> Store<T> {
 
     pub(crate) fn clear(&mut self) {
         self.entries.clear();
+        self.released = ObjectCounts::default();
+    }
+
+    pub(super) fn iter(&self) -> impl DoubleEndedIterator<Item = &T> {
+        self.entries.iter()
     }
 
     pub(super) fn iter_ref<'a, O>(&'a self) -> impl DoubleEndedIterator<Item = Ref<O>> + 'a
@@ -202,18 +263,79 @@ impl<T> Store<T> {
 }
 
 impl Store {
-    pub(super) fn last_dependent_access(&self, operation: Operation) -> Option<&Access> {
+    /// Counts the objects of the store by kind, including the released ones.
+    pub(super) fn count_objects(&self, counts: &mut ObjectCounts) {
+        counts.add(&self.released);
+
+        for entry in &self.entries {
+            entry.count(counts);
+        }
+    }
+
+    /// Returns the number of objects not released.
+    pub(super) fn live(&self) -> usize {
+        self.entries
+            .iter()
+            .filter(|entry| !matches!(entry, Entry::Released(_)))
+            .count()
+    }
+
+    /// Returns `true` if `obj` was released.
+    pub(super) fn is_released(&self, obj: Ref) -> bool {
+        matches!(self.entries.get(obj.index), None | Some(Entry::Released(_)))
+    }
+
+    /// Releases `obj`, which must not be accessed anymore.
+    ///
+    /// The entry is replaced by a tombstone, so that the other objects keep
+    /// their index, and the tombstones ending the store are dropped: the
+    /// index of an object released last is reused by the next one created.
+    pub(super) fn release(&mut self, obj: Ref) {
+        let entry = std::mem::replace(&mut self.entries[obj.index], Entry::Released(Released));
+        entry.count(&mut self.released);
+
+        while let Some(Entry::Released(_)) = self.entries.last() {
+            self.entries.pop();
+        }
+    }
+
+    /// Returns the accesses `operation` depends on.
+    pub(super) fn dependent_accesses(&self, operation: Operation) -> impl Iterator<Item = &Access> {
+        let (loads, custom) = match &self.entries[operation.obj.index] {
+            Entry::Atomic(entry) => (entry.dependent_loads(operation.action.into()), None),
+            Entry::Custom(entry) => (&[][..], Some((entry, operation.action.into()))),
+            _ => (&[][..], None),
+        };
+
+        self.last_dependent_access(operation)
+            .into_iter()
+            .chain(loads)
+            .chain(
+                custom
+                    .into_iter()
+                    .flat_map(|(entry, action)| entry.dependent_accesses(action)),
+            )
+    }
+
+    fn last_dependent_access(&self, operation: Operation) -> Option<&Access> {
         match &self.entries[operation.obj.index] {
             Entry::Arc(entry) => entry.last_dependent_access(operation.action.into()),
-            Entry::Atomic(entry) => entry.last_dependent_access(operation.action.into()),
+            Entry::Atomic(entry) => entry.last_dependent_access(),
+            Entry::Fence(entry) => entry.last_dependent_access(),
             Entry::Mutex(entry) => entry.last_dependent_access(),
             Entry::Condvar(entry) => entry.last_dependent_access(),
             Entry::Notify(entry) => entry.last_dependent_access(),
             Entry::RwLock(entry) => entry.last_dependent_access(),
+            Entry::Semaphore(entry) => entry.last_dependent_access(),
             Entry::Channel(entry) => entry.last_dependent_access(operation.action.into()),
+            Entry::Clock(entry) => entry.last_dependent_access(),
+            // See `dependent_accesses`
+            Entry::Custom(_) => None,
             obj => panic!(
-                "object is not branchable {:?}; ref = {:?}",
-                obj, operation.obj
+                "object is not branchable {:?}; ref = {:?}{}",
+                obj,
+                operation.obj,
+                self.created_at(operation.obj)
             ),
         }
     }
@@ -229,30 +351,180 @@ impl Store {
             Entry::Atomic(entry) => {
                 entry.set_last_access(operation.action.into(), path_id, dpor_vv)
             }
+            Entry::Fence(entry) => entry.set_last_access(path_id, dpor_vv),
             Entry::Mutex(entry) => entry.set_last_access(path_id, dpor_vv),
             Entry::Condvar(entry) => entry.set_last_access(path_id, dpor_vv),
             Entry::Notify(entry) => entry.set_last_access(path_id, dpor_vv),
             Entry::RwLock(entry) => entry.set_last_access(path_id, dpor_vv),
+            Entry::Semaphore(entry) => entry.set_last_access(path_id, dpor_vv),
             Entry::Channel(entry) => {
                 entry.set_last_access(operation.action.into(), path_id, dpor_vv)
             }
-            _ => panic!("object is not branchable"),
+            Entry::Clock(entry) => entry.set_last_access(path_id, dpor_vv),
+            Entry::Custom(entry) => {
+                entry.set_last_access(operation.action.into(), path_id, dpor_vv)
+            }
+            _ => panic!(
+                "object is not branchable; ref = {:?}{}",
+                operation.obj,
+                self.created_at(operation.obj)
+            ),
         }
     }
 
-    /// Panics if any leaks were detected
-    pub(crate) fn check_for_leaks(&self) {
-        for entry in &self.entries[..] {
-            match entry {
-                Entry::Alloc(entry) => entry.check_for_leaks(),
-                Entry::Arc(entry) => entry.check_for_leaks(),
-                Entry::Channel(entry) => entry.check_for_leaks(),
-                _ => {}
-            }
+    /// Returns where the object was created, if known.
+    pub(super) fn location(&self, obj: Ref) -> Location {
+        self.entries
+            .get(obj.index)
+            .map(Entry::location)
+            .unwrap_or_else(Location::disabled)
+    }
+
+    /// Returns the kind of the object, as reported to users.
+    pub(super) fn kind(&self, obj: Ref) -> &'static str {
+        match &self.entries[obj.index] {
+            Entry::Alloc(_) => "allocation",
+            Entry::Arc(_) => "arc",
+            Entry::Atomic(_) => "atomic",
+            Entry::Fence(_) => "fence",
+            Entry::Mutex(_) => "mutex",
+            Entry::Condvar(_) => "condvar",
+            Entry::Notify(_) => "notify",
+            Entry::RwLock(_) => "rwlock",
+            Entry::Channel(_) => "channel",
+            Entry::Semaphore(_) => "semaphore",
+            Entry::Cell(_) => "cell",
+            Entry::Custom(_) => "custom",
+            Entry::Clock(_) => "clock",
+            Entry::Released(_) => "released",
+        }
+    }
+
+    fn created_at(&self, obj: Ref) -> String {
+        let location = self.location(obj);
+
+        if location.is_captured() {
+            format!("; created at {}", location)
+        } else {
+            String::new()
+        }
+    }
+
+    /// Describes the object a blocked thread is waiting on.
+    pub(super) fn resource(&self, obj: Ref) -> Option<deadlock::Resource> {
+        match &self.entries[obj.index] {
+            Entry::Mutex(entry) => Some(entry.resource()),
+            Entry::Condvar(entry) => Some(entry.resource()),
+            Entry::Notify(entry) => Some(entry.resource()),
+            Entry::RwLock(entry) => Some(entry.resource()),
+            Entry::Semaphore(entry) => Some(entry.resource()),
+            Entry::Channel(entry) => Some(entry.resource()),
+            _ => None,
+        }
+    }
+
+    /// Finds the condvar `thread` is waiting on, if any.
+    pub(super) fn condvar_waited_on(&self, thread: thread::Id) -> Option<deadlock::Resource> {
+        self.entries.iter().find_map(|entry| match entry {
+            Entry::Condvar(entry) if entry.is_waiting(thread) => Some(entry.resource()),
+            _ => None,
+        })
+    }
+
+    /// Panics if any leaks were detected, listing every leaked object.
+    ///
+    /// The first `retained` objects are not checked.
+    /// Panics if objects leaked, appending `note` to the report.
+    pub(crate) fn check_for_leaks(&self, retained: usize, note: &str) {
+        let leaks: Vec<_> = self
+            .entries
+            .iter()
+            .skip(retained)
+            .filter_map(|entry| {
+                let leak = match entry {
+                    Entry::Alloc(entry) => entry.leak(),
+                    Entry::Arc(entry) => entry.leak(),
+                    Entry::Channel(entry) => entry.leak(),
+                    _ => None,
+                }?;
+
+                let location = entry.location();
+
+                if location.is_captured() {
+                    Some(format!("{}, created at {}", leak, location))
+                } else {
+                    Some(leak)
+                }
+            })
+            .collect();
+
+        if !leaks.is_empty() {
+            panic!(
+                "{} object{} leaked:\n    {}{}",
+                leaks.len(),
+                if leaks.len() == 1 { "" } else { "s" },
+                leaks.join("\n    "),
+                note,
+            );
         }
     }
 }
 
+impl Entry {
+    /// Counts the object by kind.
+    fn count(&self, counts: &mut ObjectCounts) {
+        let count = match self {
+            Entry::Alloc(_) => &mut counts.allocations,
+            Entry::Arc(_) => &mut counts.arcs,
+            Entry::Atomic(_) => &mut counts.atomics,
+            Entry::Mutex(_) => &mut counts.mutexes,
+            Entry::Condvar(_) => &mut counts.condvars,
+            Entry::Notify(_) => &mut counts.notifies,
+            Entry::RwLock(_) => &mut counts.rwlocks,
+            Entry::Channel(_) => &mut counts.channels,
+            Entry::Semaphore(_) => &mut counts.semaphores,
+            Entry::Cell(_) => &mut counts.cells,
+            Entry::Custom(_) => &mut counts.custom,
+            // Internal to loom, or already counted when released
+            Entry::Fence(_) | Entry::Clock(_) | Entry::Released(_) => return,
+        };
+
+        *count += 1;
+        counts.total += 1;
+    }
+
+    fn location(&self) -> Location {
+        match self {
+            Entry::Alloc(entry) => entry.location(),
+            Entry::Arc(entry) => entry.location(),
+            Entry::Atomic(entry) => entry.location(),
+            Entry::Fence(_) | Entry::Clock(_) | Entry::Released(_) => Location::disabled(),
+            Entry::Mutex(entry) => entry.location(),
+            Entry::Condvar(entry) => entry.location(),
+            Entry::Notify(entry) => entry.location(),
+            Entry::RwLock(entry) => entry.location(),
+            Entry::Channel(entry) => entry.location(),
+            Entry::Semaphore(entry) => entry.location(),
+            Entry::Cell(entry) => entry.location(),
+            Entry::Custom(entry) => entry.location(),
+        }
+    }
+}
+
+#[cfg(feature = "checkpoint")]
+impl<T: Codec> Codec for Store<T> {
+    fn encode(&self, dst: &mut Vec<u8>) {
+        self.entries.encode(dst);
+    }
+
+    fn decode(src: &mut &[u8]) -> Result<Store<T>, codec::Error> {
+        Ok(Store {
+            entries: Codec::decode(src)?,
+            released: ObjectCounts::default(),
+        })
+    }
+}
+
 impl<T> Ref<T> {
     /// Erase the type marker
     pub(super) fn erase(self) -> Ref<()> {
@@ -265,6 +537,34 @@ impl<T> Ref<T> {
     pub(super) fn ref_eq(self, other: Ref<T>) -> bool {
         self.index == other.index
     }
+
+    pub(super) fn as_usize(self) -> usize {
+        self.index
+    }
+
+    /// Returns an owner releasing the object when dropped.
+    pub(super) fn owner(self, execution: &Execution) -> Owner {
+        Owner {
+            obj: self.erase(),
+            execution: execution.id,
+        }
+    }
+}
+
+impl Drop for Owner {
+    fn drop(&mut self) {
+        // Releasing is an optimization, an owner dropped while unwinding, or
+        // outside of the execution that created the object, leaves it be.
+        if std::thread::panicking() {
+            return;
+        }
+
+        rt::Scheduler::try_with_execution(|execution| {
+            if execution.id == self.execution {
+                execution.release(self.obj);
+            }
+        });
+    }
 }
 
 impl<T: Object> Ref<T> {
@@ -313,6 +613,20 @@ impl<T> Clone for Ref<T> {
 
 impl<T> Copy for Ref<T> {}
 
+#[cfg(feature = "checkpoint")]
+impl<T> Codec for Ref<T> {
+    fn encode(&self, dst: &mut Vec<u8>) {
+        self.index.encode(dst);
+    }
+
+    fn decode(src: &mut &[u8]) -> Result<Ref<T>, codec::Error> {
+        Ok(Ref {
+            index: Codec::decode(src)?,
+            _p: PhantomData,
+        })
+    }
+}
+
 impl<T> fmt::Debug for Ref<T> {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
         use std::any::type_name;
@@ -374,9 +688,28 @@ impl Operation {
     pub(super) fn object(&self) -> Ref {
         self.obj
     }
+
+    /// Returns `true` if executing the operations in a different order may
+    /// lead to a different outcome.
+    ///
+    /// Operations on distinct objects commute, operations on the same object
+    /// are conservatively considered dependent.
+    pub(super) fn is_dependent(&self, other: &Operation) -> bool {
+        self.obj.ref_eq(other.obj)
+    }
+
     pub(super) fn action(&self) -> Action {
         self.action
     }
+
+    /// Returns `true` if the operation is performed by the destructor of a
+    /// handle to the object, such as an `Arc` or a channel `Sender`.
+    pub(super) fn is_drop(&self) -> bool {
+        matches!(
+            self.action,
+            Action::Arc(rt::arc::Action::RefDec) | Action::Channel(rt::mpsc::Action::Disconnect)
+        )
+    }
 }
 
 impl Into<rt::arc::Action> for Action {
@@ -406,6 +739,30 @@ impl Into<rt::mpsc::Action> for Action {
     }
 }
 
+impl From<Action> for rt::custom::Action {
+    fn from(action: Action) -> rt::custom::Action {
+        match action {
+            Action::Custom(action) => action,
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl From<rt::custom::Action> for Action {
+    fn from(action: rt::custom::Action) -> Action {
+        Action::Custom(action)
+    }
+}
+
+impl From<Action> for rt::rwlock::Action {
+    fn from(action: Action) -> rt::rwlock::Action {
+        match action {
+            Action::RwLock(action) => action,
+            _ => unreachable!(),
+        }
+    }
+}
+
 impl Into<Action> for rt::arc::Action {
     fn into(self) -> Action {
         Action::Arc(self)
@@ -1,5 +1,13 @@
+use crate::explore::TreeNode;
+use crate::model::{BranchLimit, Order};
+use crate::observe::BranchKind;
+use crate::rt::rng::Rng;
 use crate::rt::{execution, object, thread, MAX_ATOMIC_HISTORY, MAX_THREADS};
 
+use std::fmt;
+
+#[cfg(feature = "checkpoint")]
+use crate::rt::codec::{self, Codec};
 #[cfg(feature = "checkpoint")]
 use serde::{Deserialize, Serialize};
 
@@ -7,8 +15,19 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug)]
 #[cfg_attr(feature = "checkpoint", derive(Serialize, Deserialize))]
 pub(crate) struct Path {
+    /// Maximum cost of an execution, as counted by `bound`.
     preemption_bound: Option<u8>,
 
+    /// What the cost of an execution counts.
+    #[cfg_attr(feature = "checkpoint", serde(skip, default = "default_bound"))]
+    bound: Bound,
+
+    /// Index of the first branch that changed when stepping to the current
+    /// execution. Branches before it are the same as in the previous
+    /// execution.
+    #[cfg_attr(feature = "checkpoint", serde(skip))]
+    diverged: usize,
+
     /// Current execution's position in the branches vec.
     ///
     /// When the execution starts, this is zero, but `branches` might not be
@@ -22,23 +41,177 @@ pub(crate) struct Path {
     ///
     /// A branch is of type `Schedule`, `Load`, or `Spurious`
     branches: object::Store<Entry>,
+
+    /// When set, branches are picked at random instead of being explored
+    /// exhaustively.
+    #[cfg_attr(feature = "checkpoint", serde(skip))]
+    random: Option<Random>,
+
+    /// When set, the execution replays a list of choices instead of being
+    /// explored exhaustively.
+    #[cfg_attr(feature = "checkpoint", serde(skip))]
+    guide: Option<Guide>,
+
+    /// Number of schedule branch points that were not explored because doing
+    /// so would exceed the preemption bound.
+    preemption_pruned: usize,
+
+    /// Maximum number of branches in an execution.
+    #[cfg_attr(feature = "checkpoint", serde(skip))]
+    max_branches: usize,
+
+    /// What to do when an execution reaches `max_branches`.
+    #[cfg_attr(feature = "checkpoint", serde(skip, default = "default_branch_limit"))]
+    branch_limit: BranchLimit,
+
+    /// Set to the length of the path when the current execution reached
+    /// `max_branches` with `BranchLimit::Warn`. Branches past this point are
+    /// not explored.
+    #[cfg_attr(feature = "checkpoint", serde(skip))]
+    truncated_at: Option<usize>,
+
+    /// When `true`, an execution reaching `max_branches` continues with a
+    /// fair schedule instead of applying `branch_limit`, and is pruned if it
+    /// completes.
+    #[cfg_attr(feature = "checkpoint", serde(skip))]
+    weak_fairness: bool,
+
+    /// `true` if the current execution was pruned as unfair.
+    #[cfg_attr(feature = "checkpoint", serde(skip))]
+    unfair: bool,
+
+    /// Order in which the options of each branch are explored.
+    #[cfg_attr(feature = "checkpoint", serde(skip, default = "default_order"))]
+    order: Order,
+
+    /// When `true`, schedule branch points first switch to the thread that
+    /// ran the most so far, regardless of `order`.
+    #[cfg_attr(feature = "checkpoint", serde(skip))]
+    prefer_unfair: bool,
+
+    /// When `true`, switching threads because the running thread yielded
+    /// costs a preemption.
+    #[cfg_attr(feature = "checkpoint", serde(skip))]
+    preemptive_yields: bool,
+
+    /// When set, the thread scheduling branch points of the path, indexed by
+    /// their position. Replayed executions are checked against them to
+    /// detect models that are not deterministic.
+    #[cfg_attr(feature = "checkpoint", serde(skip))]
+    steps: Option<Vec<Option<Step>>>,
+}
+
+/// What is counted against the scheduling bound of a path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "checkpoint", derive(Serialize, Deserialize))]
+pub(crate) enum Bound {
+    /// Switches away from a thread that could keep running.
+    Preemptions,
+
+    /// Every switch between threads, including when the running thread
+    /// blocks, yields or terminates. Once the bound is reached, switches the
+    /// execution cannot avoid are still made.
+    ContextSwitches,
+
+    /// Deviations from the default, round-robin, scheduler: running a thread
+    /// other than the default one costs the number of runnable threads
+    /// skipped to reach it.
+    Delays,
+}
+
+/// The state of the execution at a thread scheduling branch point.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) struct Step {
+    /// Index of the thread reaching the branch point.
+    pub(super) thread: usize,
+
+    /// The operation the thread is about to perform.
+    pub(super) operation: Option<object::Operation>,
+
+    /// State of every thread when reaching the branch point.
+    pub(super) threads: [Thread; MAX_THREADS],
+}
+
+/// A choice made at a branch point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Choice {
+    /// Run the thread with the given index.
+    Thread(u8),
+
+    /// Read the atomic store with the given index.
+    Load(u8),
+
+    /// Fail spuriously.
+    Spurious,
+
+    /// Return the given value from `loom::choose`.
+    Choose(usize),
+}
+
+/// State for replaying an execution from a list of choices.
+///
+/// Branch points without a choice, or where the choice is not possible, take
+/// the branch that exhaustive exploration tries first: the running thread
+/// keeps running, loads read the first candidate store and nothing fails
+/// spuriously.
+#[derive(Debug)]
+struct Guide {
+    /// Choice to make at each branch point, `None` takes the default branch.
+    choices: Vec<Option<Choice>>,
+
+    /// Choices made by the current execution, `None` where the default branch
+    /// was taken.
+    taken: Vec<Option<Choice>>,
+}
+
+/// State of the strategy exploring a path, other than its branches.
+///
+/// Checkpoints store it alongside the path, so that an exploration resumes
+/// with the strategy it was started with, whichever it is.
+#[cfg(feature = "checkpoint")]
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct StrategyState {
+    bound: Bound,
+    order: Order,
+    prefer_unfair: bool,
+    random: Option<Random>,
+}
+
+/// State for randomized exploration.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "checkpoint", derive(Serialize, Deserialize))]
+struct Random {
+    /// Seed used for the current execution. Running a single random execution
+    /// with this seed replays the current execution exactly.
+    seed: u64,
+
+    /// Generator driving the current execution's branch choices.
+    rng: Rng,
 }
 
 #[derive(Debug)]
 #[cfg_attr(feature = "checkpoint", derive(Serialize, Deserialize))]
 pub(crate) struct Schedule {
-    /// Number of times the thread leading to this branch point has been
-    /// pre-empted.
+    /// Cost, as counted by the path's bound, of the execution leading to
+    /// this branch point.
     preemptions: u8,
 
-    /// The thread that was active first
+    /// The thread that was active first, `None` if it did not continue the
+    /// thread active at the previous branch point.
     initial_active: Option<u8>,
 
+    /// The thread that was active first
+    default_active: Option<u8>,
+
     /// State of each thread
     threads: [Thread; MAX_THREADS],
 
     /// The previous schedule branch
     prev: Option<object::Ref<Schedule>>,
+
+    /// Threads whose exploration at this branch point was skipped due to the
+    /// preemption bound.
+    bounded: [bool; MAX_THREADS],
 }
 
 #[derive(Debug)]
@@ -58,6 +231,16 @@ pub(crate) struct Load {
 #[cfg_attr(feature = "checkpoint", derive(Serialize, Deserialize))]
 pub(crate) struct Spurious(bool);
 
+#[derive(Debug)]
+#[cfg_attr(feature = "checkpoint", derive(Serialize, Deserialize))]
+pub(crate) struct Choose {
+    /// Current option
+    pos: usize,
+
+    /// Number of options
+    len: usize,
+}
+
 objects! {
     #[derive(Debug)]
     #[cfg_attr(feature = "checkpoint", derive(Serialize, Deserialize))]
@@ -65,6 +248,7 @@ objects! {
     Schedule(Schedule),
     Load(Load),
     Spurious(Spurious),
+    Choose(Choose),
 }
 
 #[derive(Debug, Eq, PartialEq, Clone, Copy)]
@@ -73,6 +257,10 @@ pub(crate) enum Thread {
     /// The thread is currently disabled
     Disabled,
 
+    /// The thread is in the sleep set: schedules running it first from this
+    /// point are equivalent to explored ones.
+    Sleep,
+
     /// The thread should not be explored
     Skip,
 
@@ -89,15 +277,28 @@ pub(crate) enum Thread {
     Visited,
 }
 
-macro_rules! assert_path_len {
-    ($branches:expr) => {{
-        assert!(
-            $branches.len() < $branches.capacity(),
-            "Model exeeded maximum number of branches. This is often caused \
-             by an algorithm requiring the processor to make progress, e.g. \
-             spin locks.",
-        );
-    }};
+#[cfg(feature = "checkpoint")]
+fn default_branch_limit() -> BranchLimit {
+    BranchLimit::Panic
+}
+
+#[cfg(feature = "checkpoint")]
+fn default_bound() -> Bound {
+    Bound::Preemptions
+}
+
+#[cfg(feature = "checkpoint")]
+fn default_order() -> Order {
+    Order::Forward
+}
+
+/// Returns the generator used to pick the next option of the branch at
+/// `index` when exploring in randomized order. Picks only depend on the seed
+/// and the state of the branch, so an execution can be replayed.
+fn order_rng(seed: u64, index: usize, remaining: usize) -> Rng {
+    let mut rng = Rng::new(seed ^ ((index as u64) << 8) ^ remaining as u64);
+    rng.next_u64();
+    rng
 }
 
 impl Path {
@@ -106,14 +307,248 @@ impl Path {
     pub(crate) fn new(max_branches: usize, preemption_bound: Option<u8>) -> Path {
         Path {
             preemption_bound,
+            bound: Bound::Preemptions,
+            diverged: 0,
             pos: 0,
             branches: object::Store::with_capacity(max_branches),
+            random: None,
+            guide: None,
+            preemption_pruned: 0,
+            max_branches,
+            branch_limit: BranchLimit::Panic,
+            truncated_at: None,
+            weak_fairness: false,
+            unfair: false,
+            order: Order::Forward,
+            prefer_unfair: false,
+            preemptive_yields: false,
+            steps: None,
+        }
+    }
+
+    /// Set what to do when an execution reaches the maximum number of
+    /// branches.
+    pub(crate) fn set_branch_limit(&mut self, branch_limit: BranchLimit) {
+        self.branch_limit = branch_limit;
+    }
+
+    /// Set what the bound given to `new` counts.
+    pub(crate) fn set_bound(&mut self, bound: Bound) {
+        self.bound = bound;
+    }
+
+    /// Returns the state of the exploration strategy, see `StrategyState`.
+    #[cfg(feature = "checkpoint")]
+    pub(crate) fn strategy(&self) -> StrategyState {
+        StrategyState {
+            bound: self.bound,
+            order: self.order,
+            prefer_unfair: self.prefer_unfair,
+            random: self.random.clone(),
+        }
+    }
+
+    /// Resume the exploration strategy saved by `strategy`.
+    #[cfg(feature = "checkpoint")]
+    pub(crate) fn set_strategy(&mut self, strategy: StrategyState) {
+        self.bound = strategy.bound;
+        self.order = strategy.order;
+        self.prefer_unfair = strategy.prefer_unfair;
+        self.random = strategy.random;
+    }
+
+    /// Set the order in which the options of each branch are explored.
+    pub(crate) fn set_order(&mut self, order: Order) {
+        self.order = order;
+    }
+
+    /// Explore the schedules letting one thread run far ahead of the others
+    /// first.
+    pub(crate) fn set_prefer_unfair(&mut self, prefer_unfair: bool) {
+        self.prefer_unfair = prefer_unfair;
+    }
+
+    /// Count switching threads because the running thread yielded as a
+    /// preemption.
+    pub(crate) fn set_preemptive_yields(&mut self, preemptive_yields: bool) {
+        self.preemptive_yields = preemptive_yields;
+    }
+
+    /// Check that replayed executions reach the same branch points as the
+    /// execution that first explored them.
+    pub(crate) fn set_check_determinism(&mut self, check: bool) {
+        self.steps = if check { Some(vec![]) } else { None };
+    }
+
+    /// Record the thread scheduling branch point at the current position.
+    ///
+    /// If the position is being replayed, the step is compared with the one
+    /// recorded when the branch point was first reached instead. When they
+    /// differ, the recorded step is returned.
+    pub(super) fn check_step(&mut self, step: Step) -> Result<(), Step> {
+        let pos = self.pos;
+        let traversed = self.is_traversed();
+
+        let steps = match &mut self.steps {
+            // Destructors run while unwinding from a divergence diverge too.
+            Some(_) if std::thread::panicking() => return Ok(()),
+            Some(steps) => steps,
+            None => return Ok(()),
+        };
+
+        if !traversed {
+            return match steps.get(pos) {
+                Some(&Some(recorded)) if recorded != step => Err(recorded),
+                _ => Ok(()),
+            };
         }
+
+        steps.resize(pos, None);
+        steps.push(Some(step));
+
+        Ok(())
+    }
+
+    pub(crate) fn max_branches(&self) -> usize {
+        self.max_branches
+    }
+
+    /// Returns `true` if the current execution reached the maximum number of
+    /// branches and the rest of it was not explored.
+    pub(crate) fn is_truncated(&self) -> bool {
+        self.truncated_at.is_some() && !self.unfair
+    }
+
+    /// Returns `true` if the current execution reached the maximum number of
+    /// branches only because of an unfair schedule and was pruned.
+    pub(crate) fn is_unfair(&self) -> bool {
+        self.unfair
+    }
+
+    /// Prune executions reaching the maximum number of branches that complete
+    /// once scheduled fairly.
+    pub(crate) fn set_weak_fairness(&mut self, weak_fairness: bool) {
+        self.weak_fairness = weak_fairness;
+    }
+
+    /// Pick branches at random, starting with `seed`, instead of exploring
+    /// exhaustively.
+    ///
+    /// The preemption bound does not apply to random exploration.
+    pub(crate) fn set_random(&mut self, seed: u64) {
+        self.preemption_bound = None;
+        self.random = Some(Random {
+            seed,
+            rng: Rng::new(seed),
+        });
+    }
+
+    /// Replay `choices` instead of exploring exhaustively.
+    ///
+    /// The preemption bound does not apply to replayed executions.
+    pub(crate) fn set_guide(&mut self, choices: Vec<Option<Choice>>) {
+        self.preemption_bound = None;
+        self.guide = Some(Guide {
+            choices,
+            taken: vec![],
+        });
+    }
+
+    /// Returns the choices made by the current replayed execution.
+    pub(crate) fn guided_choices(&self) -> Option<&[Option<Choice>]> {
+        self.guide.as_ref().map(|guide| &guide.taken[..])
+    }
+
+    /// Returns the choice made at each branch point of the current execution.
+    pub(crate) fn choices(&self) -> Vec<Option<Choice>> {
+        self.branches
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| match entry {
+                Entry::Schedule(schedule) => schedule.active_thread_index().map(Choice::Thread),
+                Entry::Load(load) => Some(Choice::Load(load.values[load.pos as usize])),
+                Entry::Spurious(spurious) => Some(Choice::Spurious).filter(|_| spurious.0),
+                Entry::Choose(choose) => Some(Choice::Choose(
+                    self.choose_value(index, choose.pos, choose.len),
+                )),
+            })
+            .collect()
+    }
+
+    /// Returns the number of schedule branch points that have not been
+    /// explored due to the preemption bound.
+    pub(crate) fn preemption_pruned(&self) -> usize {
+        self.preemption_pruned
+    }
+
+    /// Returns the configured preemption bound.
+    pub(crate) fn preemption_bound(&self) -> Option<usize> {
+        self.preemption_bound.map(usize::from)
+    }
+
+    /// Returns the seed of the current execution when exploring randomly.
+    pub(crate) fn random_seed(&self) -> Option<u64> {
+        self.random.as_ref().map(|random| random.seed)
+    }
+
+    /// Rewind to the start of the current execution, so that it is explored
+    /// again.
+    pub(crate) fn rewind(&mut self) {
+        self.pos = 0;
     }
 
     pub(crate) fn set_max_branches(&mut self, max_branches: usize) {
+        self.max_branches = max_branches;
         self.branches
-            .reserve_exact(max_branches - self.branches.len());
+            .reserve_exact(max_branches.saturating_sub(self.branches.len()));
+    }
+
+    /// Called before adding a branch to the path, applies the branch limit.
+    fn reserve_branch(&mut self) {
+        let len = self.branches.len();
+
+        // Destructors running while unwinding may still branch, let them.
+        if len < self.max_branches || std::thread::panicking() {
+            return;
+        }
+
+        if self.weak_fairness {
+            match self.truncated_at {
+                // Keep running the execution with a fair, round-robin,
+                // schedule. If it completes, it only went on because of the
+                // unfair schedule, it is pruned.
+                None => {
+                    self.truncated_at = Some(len);
+                    self.unfair = true;
+                }
+                Some(truncated_at) if len - truncated_at >= self.max_branches => panic!(
+                    "Model exceeded maximum number of branches with a fair schedule: the \
+                     execution kept running after scheduling every thread round-robin for \
+                     {} more branches, the model likely livelocks.",
+                    self.max_branches
+                ),
+                Some(_) => {}
+            }
+
+            return;
+        }
+
+        match self.branch_limit {
+            BranchLimit::Panic => panic!(
+                "Model exeeded maximum number of branches. This is often caused \
+                 by an algorithm requiring the processor to make progress, e.g. \
+                 spin locks."
+            ),
+            BranchLimit::Warn => {
+                // Keep running the execution, but stop exploring it.
+                if self.truncated_at.is_none() {
+                    self.truncated_at = Some(len);
+                }
+            }
+            BranchLimit::Grow => {
+                self.set_max_branches(len * 2);
+            }
+        }
     }
 
     /// Returns `true` if the execution has reached a point where the known path
@@ -122,14 +557,101 @@ impl Path {
         self.pos == self.branches.len()
     }
 
-    pub(super) fn pos(&self) -> usize {
+    pub(crate) fn pos(&self) -> usize {
         self.pos
     }
 
+    /// Returns what is decided by the branch at `index`.
+    pub(crate) fn branch_kind(&self, index: usize) -> BranchKind {
+        let branch = object::Ref::from_usize(index);
+
+        if branch.downcast::<Schedule>(&self.branches).is_some() {
+            BranchKind::Schedule
+        } else if branch.downcast::<Load>(&self.branches).is_some() {
+            BranchKind::Load
+        } else if branch.downcast::<Spurious>(&self.branches).is_some() {
+            BranchKind::Spurious
+        } else {
+            BranchKind::Choose
+        }
+    }
+
+    /// Returns the number of branch points of the path.
+    pub(super) fn len(&self) -> usize {
+        self.branches.len()
+    }
+
+    /// Returns how the options of the branch at `index` have been explored so
+    /// far, including the option taken by the current execution.
+    pub(super) fn tree_node(&self, index: usize) -> TreeNode {
+        let mut node = TreeNode {
+            depth: index,
+            kind: self.branch_kind(index),
+            options: 0,
+            explored: 0,
+            dpor_pruned: 0,
+            sleep_pruned: 0,
+            bound_pruned: 0,
+        };
+
+        match self
+            .branches
+            .iter()
+            .nth(index)
+            .expect("[loom internal bug]")
+        {
+            Entry::Schedule(schedule) => {
+                for (i, th) in schedule.threads.iter().enumerate() {
+                    match th {
+                        Thread::Disabled | Thread::Yield => continue,
+                        Thread::Active | Thread::Visited => node.explored += 1,
+                        Thread::Sleep => node.sleep_pruned += 1,
+                        Thread::Skip if schedule.bounded[i] => node.bound_pruned += 1,
+                        Thread::Skip => node.dpor_pruned += 1,
+                        Thread::Pending => {}
+                    }
+
+                    node.options += 1;
+                }
+            }
+            Entry::Load(load) => {
+                node.options = usize::from(load.len);
+                node.explored = usize::from(load.pos) + 1;
+            }
+            Entry::Spurious(spurious) => {
+                node.options = 2;
+                node.explored = if spurious.0 == self.first_spurious(index) {
+                    1
+                } else {
+                    2
+                };
+            }
+            Entry::Choose(choose) => {
+                node.options = choose.len;
+                node.explored = choose.pos + 1;
+            }
+        }
+
+        node
+    }
+
+    /// Returns the index of the first branch that differs from the previous
+    /// execution.
+    pub(crate) fn diverged(&self) -> usize {
+        self.diverged
+    }
+
+    /// Skip the first `pos` branches, which a restored snapshot already went
+    /// through.
+    pub(super) fn skip_to(&mut self, pos: usize) {
+        self.pos = pos;
+    }
+
     /// Push a new atomic-load branch
     pub(super) fn push_load(&mut self, seed: &[u8]) {
-        assert_path_len!(self.branches);
+        self.reserve_branch();
 
+        let index = self.branches.len();
         let load_ref = self.branches.insert(Load {
             values: [0; MAX_ATOMIC_HISTORY],
             pos: 0,
@@ -155,6 +677,40 @@ impl Path {
             load.values[i] = store as u8;
             load.len += 1;
         }
+
+        if let Some(random) = &mut self.random {
+            load.pos = random.rng.gen_range(load.len as usize) as u8;
+            return;
+        }
+
+        if let Some(guide) = &mut self.guide {
+            if let Some(Choice::Load(store)) = guide.next() {
+                let values = &load.values[..load.len as usize];
+
+                if let Some(pos) = values.iter().position(|&value| value == store) {
+                    load.pos = pos as u8;
+                }
+            }
+
+            let choice = Some(Choice::Load(load.values[load.pos as usize]));
+            guide.taken.push(choice.filter(|_| load.pos != 0));
+            return;
+        }
+
+        // The branch is explored in the order of `values`, permute them.
+        let values = &mut load.values[..load.len as usize];
+
+        match self.order {
+            Order::Forward => {}
+            Order::Reverse => values.reverse(),
+            Order::RandomizedSeed(seed) => {
+                let mut rng = order_rng(seed, index, 0);
+
+                for i in (1..values.len()).rev() {
+                    values.swap(i, rng.gen_range(i + 1));
+                }
+            }
+        }
     }
 
     /// Returns the atomic write to read
@@ -174,9 +730,21 @@ impl Path {
     /// Branch on spurious notifications
     pub(super) fn branch_spurious(&mut self) -> bool {
         if self.is_traversed() {
-            assert_path_len!(self.branches);
+            self.reserve_branch();
+
+            let spurious = if let Some(random) = &mut self.random {
+                random.rng.gen_range(2) == 1
+            } else if let Some(guide) = &mut self.guide {
+                let spurious = guide.next() == Some(Choice::Spurious);
+                guide
+                    .taken
+                    .push(Some(Choice::Spurious).filter(|_| spurious));
+                spurious
+            } else {
+                self.first_spurious(self.branches.len())
+            };
 
-            self.branches.insert(Spurious(false));
+            self.branches.insert(Spurious(spurious));
         }
 
         let spurious = object::Ref::from_usize(self.pos)
@@ -189,6 +757,51 @@ impl Path {
         spurious
     }
 
+    /// Branch on a value in `0..len` picked by the model
+    pub(super) fn branch_choose(&mut self, len: usize) -> usize {
+        if self.is_traversed() {
+            self.reserve_branch();
+
+            let index = self.branches.len();
+
+            // Options are explored in order, the value returned for each one
+            // depends on the exploration order.
+            let pos = if let Some(random) = &mut self.random {
+                random.rng.gen_range(len)
+            } else if let Some(guide) = &self.guide {
+                let pos = match guide.next() {
+                    Some(Choice::Choose(value)) => (0..len)
+                        .find(|&pos| self.choose_value(index, pos, len) == value)
+                        .unwrap_or(0),
+                    _ => 0,
+                };
+
+                let choice = Choice::Choose(self.choose_value(index, pos, len));
+                let guide = self.guide.as_mut().unwrap();
+                guide.taken.push(Some(choice).filter(|_| pos != 0));
+                pos
+            } else {
+                0
+            };
+
+            self.branches.insert(Choose { pos, len });
+        }
+
+        let index = self.pos;
+        let choose = object::Ref::from_usize(index)
+            .downcast::<Choose>(&self.branches)
+            .expect("Reached unexpected exploration state. Is the model fully determistic?")
+            .get(&self.branches);
+
+        assert_eq!(
+            choose.len, len,
+            "Reached unexpected exploration state. Is the model fully determistic?"
+        );
+
+        self.pos += 1;
+        self.choose_value(index, choose.pos, choose.len)
+    }
+
     /// Returns the thread identifier to schedule
     pub(super) fn branch_thread(
         &mut self,
@@ -196,7 +809,7 @@ impl Path {
         seed: impl ExactSizeIterator<Item = Thread>,
     ) -> Option<thread::Id> {
         if self.is_traversed() {
-            assert_path_len!(self.branches);
+            self.reserve_branch();
 
             // Find the last thread scheduling branch in the path
             let prev = self.last_schedule();
@@ -208,8 +821,10 @@ impl Path {
             let schedule_ref = self.branches.insert(Schedule {
                 preemptions: 0,
                 initial_active: None,
+                default_active: None,
                 threads: [Thread::Disabled; MAX_THREADS],
                 prev,
+                bounded: [false; MAX_THREADS],
             });
 
             // Get a reference to the branch in the object store.
@@ -245,18 +860,118 @@ impl Path {
                 }
             }
 
+            // When exploring randomly, run any of the runnable threads.
+            if let Some(random) = &mut self.random {
+                let runnable = schedule
+                    .threads
+                    .iter()
+                    .filter(|th| th.is_active() || **th == Thread::Skip)
+                    .count();
+
+                if runnable > 0 {
+                    let pick = random.rng.gen_range(runnable);
+
+                    for (i, th) in schedule
+                        .threads
+                        .iter_mut()
+                        .filter(|th| th.is_active() || **th == Thread::Skip)
+                        .enumerate()
+                    {
+                        *th = if i == pick {
+                            Thread::Active
+                        } else {
+                            Thread::Skip
+                        };
+                    }
+
+                    active = schedule.active_thread_index();
+                }
+            } else if let Some(guide) = &mut self.guide {
+                let default = active;
+
+                if let Some(Choice::Thread(pick)) = guide.next() {
+                    let pick = usize::from(pick);
+                    let runnable = |th: &Thread| th.is_active() || *th == Thread::Skip;
+
+                    if pick < MAX_THREADS && runnable(&schedule.threads[pick]) {
+                        for (i, th) in schedule.threads.iter_mut().enumerate() {
+                            if runnable(th) {
+                                *th = if i == pick {
+                                    Thread::Active
+                                } else {
+                                    Thread::Skip
+                                };
+                            }
+                        }
+
+                        active = Some(pick as u8);
+                    }
+                }
+
+                let choice = active.map(Choice::Thread);
+                guide.taken.push(choice.filter(|_| active != default));
+            } else if self.truncated_at.is_some() {
+                // Past the branch limit, schedule runnable threads round-robin
+                // so that threads waiting on each other make progress.
+                let prev_active = prev
+                    .and_then(|prev| prev.get(&self.branches).active_thread_index())
+                    .map(usize::from);
+
+                let schedule = schedule_ref.get_mut(&mut self.branches);
+
+                let runnable = |th: &Thread| th.is_active() || *th == Thread::Skip;
+                let start = prev_active.map(|i| i + 1).unwrap_or(0);
+
+                let next = (0..MAX_THREADS)
+                    .map(|i| (start + i) % MAX_THREADS)
+                    .find(|&i| runnable(&schedule.threads[i]));
+
+                if let Some(next) = next {
+                    for (i, th) in schedule.threads.iter_mut().enumerate() {
+                        if runnable(th) {
+                            *th = if i == next {
+                                Thread::Active
+                            } else {
+                                Thread::Skip
+                            };
+                        }
+                    }
+
+                    active = Some(next as u8);
+                }
+            }
+
             let mut initial_active = active;
+            let mut yielded = false;
 
             if let Some(prev) = prev {
-                if initial_active != prev.get(&self.branches).active_thread_index() {
+                let prev_active = prev.get(&self.branches).active_thread_index();
+
+                if initial_active != prev_active {
                     initial_active = None;
+
+                    let threads = &schedule_ref.get(&self.branches).threads;
+                    yielded =
+                        matches!(prev_active, Some(i) if threads[usize::from(i)] == Thread::Yield);
                 }
             }
 
-            let preemptions = prev
-                .map(|prev| prev.get(&self.branches).preemptions())
+            let mut preemptions = prev
+                .map(|prev| {
+                    prev.get(&self.branches)
+                        .preemptions(self.bound, self.preemption_bound)
+                })
                 .unwrap_or(0);
 
+            // The context bound already counts the switch.
+            if yielded && self.preemptive_yields && self.bound == Bound::Preemptions {
+                preemptions = preemptions.saturating_add(1);
+
+                if let Some(max) = self.preemption_bound {
+                    preemptions = preemptions.min(max);
+                }
+            }
+
             debug_assert!(
                 self.preemption_bound.is_none() || Some(preemptions) <= self.preemption_bound,
                 "[loom internal bug] max = {:?}; curr = {}",
@@ -266,6 +981,7 @@ impl Path {
 
             let schedule = schedule_ref.get_mut(&mut self.branches);
             schedule.initial_active = initial_active;
+            schedule.default_active = active;
             schedule.preemptions = preemptions;
         }
 
@@ -284,19 +1000,53 @@ impl Path {
             .map(|(i, _)| thread::Id::new(execution_id, i))
     }
 
-    pub(super) fn backtrack(&mut self, point: usize, thread_id: thread::Id) {
+    /// Returns `true` when every branch is explored, in which case sleep sets
+    /// can be used to skip schedules equivalent to explored ones.
+    pub(super) fn is_exhaustive(&self) -> bool {
+        // Sleep sets are not sound when combined with a preemption bound.
+        self.random.is_none()
+            && self.guide.is_none()
+            && self.truncated_at.is_none()
+            && self.preemption_bound.is_none()
+    }
+
+    /// Returns the sleep set of the thread scheduled at the branch point
+    /// `point`: the threads explored first from that point, or asleep there.
+    pub(super) fn sleep_set(&self, point: usize) -> [bool; MAX_THREADS] {
+        let schedule = object::Ref::from_usize(point)
+            .downcast::<Schedule>(&self.branches)
+            .unwrap()
+            .get(&self.branches);
+
+        let mut sleep = [false; MAX_THREADS];
+
+        for (asleep, th) in sleep.iter_mut().zip(schedule.threads.iter()) {
+            *asleep = matches!(th, Thread::Sleep | Thread::Visited);
+        }
+
+        sleep
+    }
+
+    /// Returns the number of threads newly marked for exploration.
+    pub(super) fn backtrack(&mut self, point: usize, thread_id: thread::Id) -> usize {
         let schedule = object::Ref::from_usize(point)
             .downcast::<Schedule>(&self.branches)
             .unwrap()
             .get_mut(&mut self.branches);
 
+        let pending = schedule.pending();
+
         // Exhaustive DPOR only requires adding this backtrack point
-        schedule.backtrack(thread_id, self.preemption_bound);
+        if schedule.backtrack(thread_id, self.bound, self.preemption_bound) {
+            self.preemption_pruned += 1;
+        }
+
+        let explored = schedule.pending() - pending;
 
         let mut curr = if let Some(curr) = schedule.prev {
             curr
         } else {
-            return;
+            return explored;
         };
 
         if self.preemption_bound.is_some() {
@@ -308,20 +1058,36 @@ impl Path {
                     let active_b = prev.get(&self.branches).active_thread_index();
 
                     if active_a != active_b {
-                        curr.get_mut(&mut self.branches)
-                            .backtrack(thread_id, self.preemption_bound);
-                        return;
+                        return explored + self.backtrack_conservative(curr, thread_id);
                     }
 
                     curr = prev;
                 } else {
                     // This is the very first schedule
-                    curr.get_mut(&mut self.branches)
-                        .backtrack(thread_id, self.preemption_bound);
-                    return;
+                    return explored + self.backtrack_conservative(curr, thread_id);
                 }
             }
         }
+
+        explored
+    }
+
+    fn backtrack_conservative(
+        &mut self,
+        schedule: object::Ref<Schedule>,
+        thread_id: thread::Id,
+    ) -> usize {
+        let bound = self.bound;
+        let preemption_bound = self.preemption_bound;
+
+        let schedule = schedule.get_mut(&mut self.branches);
+        let pending = schedule.pending();
+
+        if schedule.backtrack(thread_id, bound, preemption_bound) {
+            self.preemption_pruned += 1;
+        }
+
+        schedule.pending() - pending
     }
 
     /// Reset the path to prepare for the next exploration of the model.
@@ -332,19 +1098,45 @@ impl Path {
         // Reset the position to zero, the path will start traversing from the
         // beginning
         self.pos = 0;
+        self.diverged = 0;
+        self.unfair = false;
+
+        // Drop the branches past the branch limit, they were not explored.
+        if let Some(len) = self.truncated_at.take() {
+            match len.checked_sub(1) {
+                Some(last) => self.branches.truncate(object::Ref::from_usize(last)),
+                None => self.branches.clear(),
+            }
+        }
+
+        // Random exploration does not track which branches were explored.
+        // Start over with a fresh path seeded by the previous execution.
+        if let Some(random) = &mut self.random {
+            random.seed = random.rng.next_u64();
+            random.rng = Rng::new(random.seed);
+            self.branches.clear();
+
+            return true;
+        }
 
         // Set the final branch to try the next option. If all options have been
         // traversed, pop the final branch and try again w/ the one under it.
         //
         // This is depth-first tree traversal.
         //
-        for last in (0..self.branches.len()).rev() {
-            let last = object::Ref::from_usize(last);
+        for index in (0..self.branches.len()).rev() {
+            let last = object::Ref::from_usize(index);
 
             // Remove all objects that were created **after** this branch
             self.branches.truncate(last);
 
             if let Some(schedule_ref) = last.downcast::<Schedule>(&self.branches) {
+                let runs = if self.prefer_unfair {
+                    Some(self.thread_runs(index))
+                } else {
+                    None
+                };
+
                 let schedule = schedule_ref.get_mut(&mut self.branches);
 
                 // Transition the active thread to visited.
@@ -355,16 +1147,32 @@ impl Path {
                     .map(|th| *th = Thread::Visited);
 
                 // Find a pending thread and transition it to active
-                let rem = schedule
+                let mut pending = schedule
                     .threads
                     .iter_mut()
-                    .find(|th| th.is_pending())
-                    .map(|th| {
-                        *th = Thread::Active;
-                    })
-                    .is_some();
+                    .enumerate()
+                    .filter(|(_, th)| th.is_pending())
+                    .collect::<Vec<_>>();
+
+                let next = match (self.order, runs) {
+                    _ if pending.is_empty() => None,
+                    // Let the thread furthest ahead run further, ties go to
+                    // the lowest thread index.
+                    (_, Some(runs)) => (0..pending.len()).max_by_key(|&i| {
+                        let thread = pending[i].0;
+                        (runs[thread], std::cmp::Reverse(thread))
+                    }),
+                    (Order::Forward, None) => Some(0),
+                    (Order::Reverse, None) => Some(pending.len() - 1),
+                    (Order::RandomizedSeed(seed), None) => {
+                        let mut rng = order_rng(seed, index, pending.len());
+                        Some(rng.gen_range(pending.len()))
+                    }
+                };
 
-                if rem {
+                if let Some(next) = next {
+                    *pending[next].1 = Thread::Active;
+                    self.diverged = index;
                     return true;
                 }
             } else if let Some(load_ref) = last.downcast::<Load>(&self.branches) {
@@ -373,13 +1181,25 @@ impl Path {
                 load.pos += 1;
 
                 if load.pos < load.len {
+                    self.diverged = index;
                     return true;
                 }
             } else if let Some(spurious_ref) = last.downcast::<Spurious>(&self.branches) {
+                let first = self.first_spurious(index);
                 let spurious = spurious_ref.get_mut(&mut self.branches);
 
-                if !spurious.0 {
-                    spurious.0 = true;
+                if spurious.0 == first {
+                    spurious.0 = !first;
+                    self.diverged = index;
+                    return true;
+                }
+            } else if let Some(choose_ref) = last.downcast::<Choose>(&self.branches) {
+                let choose = choose_ref.get_mut(&mut self.branches);
+
+                choose.pos += 1;
+
+                if choose.pos < choose.len {
+                    self.diverged = index;
                     return true;
                 }
             } else {
@@ -390,11 +1210,109 @@ impl Path {
         false
     }
 
+    /// Returns a hash of the branches taken by the current execution.
+    ///
+    /// Two executions with the same hash made the same choices at every branch
+    /// point.
+    pub(crate) fn schedule_hash(&self) -> u64 {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::Hasher;
+
+        let mut hasher = DefaultHasher::new();
+
+        for entry in self.branches.iter() {
+            match entry {
+                Entry::Schedule(schedule) => {
+                    hasher.write_u8(0);
+                    hasher.write_u8(schedule.active_thread_index().unwrap_or(u8::MAX));
+                }
+                Entry::Load(load) => {
+                    hasher.write_u8(1);
+                    hasher.write_u8(load.values[load.pos as usize]);
+                }
+                Entry::Spurious(spurious) => {
+                    hasher.write_u8(2);
+                    hasher.write_u8(spurious.0 as u8);
+                }
+                Entry::Choose(choose) => {
+                    hasher.write_u8(3);
+                    hasher.write_usize(choose.pos);
+                }
+            }
+        }
+
+        hasher.finish()
+    }
+
+    /// Returns how many of the schedule branch points before `index` ran each
+    /// thread.
+    fn thread_runs(&self, index: usize) -> [usize; MAX_THREADS] {
+        let mut runs = [0; MAX_THREADS];
+
+        for schedule in self.branches.iter_ref::<Schedule>() {
+            if schedule.as_usize() >= index {
+                break;
+            }
+
+            if let Some(active) = schedule.get(&self.branches).active_thread_index() {
+                runs[usize::from(active)] += 1;
+            }
+        }
+
+        runs
+    }
+
+    /// Returns the value returned by the choose branch at `index` when at its
+    /// `pos`-th option.
+    fn choose_value(&self, index: usize, pos: usize, len: usize) -> usize {
+        match self.order {
+            Order::Forward => pos,
+            Order::Reverse => len - 1 - pos,
+            Order::RandomizedSeed(seed) => {
+                let mut values: Vec<_> = (0..len).collect();
+                let mut rng = order_rng(seed, index, 0);
+
+                for i in (1..len).rev() {
+                    values.swap(i, rng.gen_range(i + 1));
+                }
+
+                values[pos]
+            }
+        }
+    }
+
+    /// Returns the value explored first by the spurious branch at `index`.
+    fn first_spurious(&self, index: usize) -> bool {
+        match self.order {
+            Order::Forward => false,
+            Order::Reverse => true,
+            Order::RandomizedSeed(seed) => order_rng(seed, index, 0).gen_range(2) == 1,
+        }
+    }
+
     fn last_schedule(&self) -> Option<object::Ref<Schedule>> {
         self.branches.iter_ref::<Schedule>().rev().next()
     }
 }
 
+impl Guide {
+    /// Returns the choice to make at the next branch point.
+    fn next(&self) -> Option<Choice> {
+        self.choices.get(self.taken.len()).copied().flatten()
+    }
+}
+
+impl fmt::Display for Choice {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Choice::Thread(index) => write!(fmt, "switch to thread #{}", index),
+            Choice::Load(store) => write!(fmt, "read store #{}", store),
+            Choice::Spurious => write!(fmt, "fail spuriously"),
+            Choice::Choose(value) => write!(fmt, "choose #{}", value),
+        }
+    }
+}
+
 impl Schedule {
     /// Returns the index of the currently active thread
     fn active_thread_index(&self) -> Option<u8> {
@@ -405,44 +1323,108 @@ impl Schedule {
             .map(|(index, _)| index as u8)
     }
 
-    /// Compute the number of preemptions for the current state of the branch
-    fn preemptions(&self) -> u8 {
-        if self.initial_active.is_some() {
-            if self.initial_active != self.active_thread_index() {
-                return self.preemptions + 1;
+    /// Compute the cost of the path, including the current state of the
+    /// branch. The cost does not exceed `max`, switches that cannot be avoided
+    /// are made once the bound is reached.
+    fn preemptions(&self, bound: Bound, max: Option<u8>) -> u8 {
+        let cost = match self.active_thread_index() {
+            Some(thread) => self.cost(bound, usize::from(thread)),
+            None => 0,
+        };
+
+        let preemptions = self.preemptions.saturating_add(cost);
+
+        match max {
+            Some(max) => preemptions.min(max),
+            None => preemptions,
+        }
+    }
+
+    /// Returns the cost of running `thread` at this branch point.
+    fn cost(&self, bound: Bound, thread: usize) -> u8 {
+        match bound {
+            Bound::Preemptions => match self.initial_active {
+                Some(initial) if usize::from(initial) != thread => 1,
+                _ => 0,
+            },
+            Bound::ContextSwitches => match self.initial_active {
+                Some(initial) if usize::from(initial) == thread => 0,
+                _ => 1,
+            },
+            Bound::Delays => {
+                let default = match self.default_active {
+                    Some(default) => usize::from(default),
+                    None => return 0,
+                };
+
+                // Count the runnable threads skipped, in round-robin order
+                // from the default thread.
+                let skipped = (0..MAX_THREADS)
+                    .map(|i| (default + i) % MAX_THREADS)
+                    .take_while(|&i| i != thread)
+                    .filter(|&i| self.threads[i].is_enabled() && self.threads[i] != Thread::Yield)
+                    .count();
+
+                skipped.min(u8::MAX as usize) as u8
             }
         }
+    }
 
-        self.preemptions
+    /// Number of threads marked for exploration at this branch point, not
+    /// explored yet.
+    fn pending(&self) -> usize {
+        self.threads.iter().filter(|th| th.is_pending()).count()
     }
 
-    fn backtrack(&mut self, thread_id: thread::Id, preemption_bound: Option<u8>) {
-        if let Some(bound) = preemption_bound {
+    /// Mark `thread_id` for exploration at this branch point.
+    ///
+    /// Returns `true` if the branch point is newly pruned by the bound.
+    fn backtrack(&mut self, thread_id: thread::Id, bound: Bound, max: Option<u8>) -> bool {
+        let thread_id = thread_id.as_usize();
+
+        if thread_id >= self.threads.len() {
+            return false;
+        }
+
+        if let Some(max) = max {
             assert!(
-                self.preemptions <= bound,
+                self.preemptions <= max,
                 "[loom internal bug] actual = {}, bound = {}",
                 self.preemptions,
-                bound
+                max
             );
-
-            if self.preemptions == bound {
-                return;
-            }
         }
 
-        let thread_id = thread_id.as_usize();
+        let pruned = self.bounded.iter().any(|&bounded| bounded);
 
-        if thread_id >= self.threads.len() {
-            return;
-        }
+        for i in 0..self.threads.len() {
+            // When the thread is disabled, explore all threads.
+            if i != thread_id && self.threads[thread_id].is_enabled() {
+                continue;
+            }
 
-        if self.threads[thread_id].is_enabled() {
-            self.threads[thread_id].explore();
-        } else {
-            for th in &mut self.threads {
-                th.explore();
+            if self.threads[i] != Thread::Skip {
+                continue;
             }
+
+            if let Some(max) = max {
+                // Exploring any other thread is conservatively considered a
+                // preemption.
+                let cost = match bound {
+                    Bound::Preemptions => 1,
+                    _ => self.cost(bound, i),
+                };
+
+                if self.preemptions.saturating_add(cost) > max {
+                    self.bounded[i] = true;
+                    continue;
+                }
+            }
+
+            self.threads[i].explore();
         }
+
+        !pruned && self.bounded.iter().any(|&bounded| bounded)
     }
 }
 
@@ -478,3 +1460,236 @@ impl Thread {
         *self == Thread::Disabled
     }
 }
+
+// ===== impl Codec =====
+
+#[cfg(feature = "checkpoint")]
+impl Codec for Path {
+    fn encode(&self, dst: &mut Vec<u8>) {
+        self.preemption_bound.encode(dst);
+        self.pos.encode(dst);
+        self.branches.encode(dst);
+        self.preemption_pruned.encode(dst);
+    }
+
+    fn decode(src: &mut &[u8]) -> Result<Path, codec::Error> {
+        Ok(Path {
+            preemption_bound: Codec::decode(src)?,
+            bound: Bound::Preemptions,
+            diverged: 0,
+            pos: Codec::decode(src)?,
+            branches: Codec::decode(src)?,
+            random: None,
+            guide: None,
+            preemption_pruned: Codec::decode(src)?,
+            max_branches: 0,
+            branch_limit: BranchLimit::Panic,
+            truncated_at: None,
+            weak_fairness: false,
+            unfair: false,
+            order: Order::Forward,
+            prefer_unfair: false,
+            preemptive_yields: false,
+            steps: None,
+        })
+    }
+}
+
+#[cfg(feature = "checkpoint")]
+impl Codec for StrategyState {
+    fn encode(&self, dst: &mut Vec<u8>) {
+        self.bound.encode(dst);
+        self.order.encode(dst);
+        self.prefer_unfair.encode(dst);
+        self.random.encode(dst);
+    }
+
+    fn decode(src: &mut &[u8]) -> Result<StrategyState, codec::Error> {
+        Ok(StrategyState {
+            bound: Codec::decode(src)?,
+            order: Codec::decode(src)?,
+            prefer_unfair: Codec::decode(src)?,
+            random: Codec::decode(src)?,
+        })
+    }
+}
+
+#[cfg(feature = "checkpoint")]
+impl Codec for Bound {
+    fn encode(&self, dst: &mut Vec<u8>) {
+        dst.push(match self {
+            Bound::Preemptions => 0,
+            Bound::ContextSwitches => 1,
+            Bound::Delays => 2,
+        });
+    }
+
+    fn decode(src: &mut &[u8]) -> Result<Bound, codec::Error> {
+        match u8::decode(src)? {
+            0 => Ok(Bound::Preemptions),
+            1 => Ok(Bound::ContextSwitches),
+            2 => Ok(Bound::Delays),
+            _ => Err(codec::Error::new("invalid bound")),
+        }
+    }
+}
+
+#[cfg(feature = "checkpoint")]
+impl Codec for Order {
+    fn encode(&self, dst: &mut Vec<u8>) {
+        match self {
+            Order::Forward => dst.push(0),
+            Order::Reverse => dst.push(1),
+            Order::RandomizedSeed(seed) => {
+                dst.push(2);
+                seed.encode(dst);
+            }
+        }
+    }
+
+    fn decode(src: &mut &[u8]) -> Result<Order, codec::Error> {
+        match u8::decode(src)? {
+            0 => Ok(Order::Forward),
+            1 => Ok(Order::Reverse),
+            2 => Ok(Order::RandomizedSeed(Codec::decode(src)?)),
+            _ => Err(codec::Error::new("invalid exploration order")),
+        }
+    }
+}
+
+#[cfg(feature = "checkpoint")]
+impl Codec for Random {
+    fn encode(&self, dst: &mut Vec<u8>) {
+        self.seed.encode(dst);
+        self.rng.encode(dst);
+    }
+
+    fn decode(src: &mut &[u8]) -> Result<Random, codec::Error> {
+        Ok(Random {
+            seed: Codec::decode(src)?,
+            rng: Codec::decode(src)?,
+        })
+    }
+}
+
+#[cfg(feature = "checkpoint")]
+impl Codec for Entry {
+    fn encode(&self, dst: &mut Vec<u8>) {
+        match self {
+            Entry::Schedule(schedule) => {
+                dst.push(0);
+                schedule.encode(dst);
+            }
+            Entry::Load(load) => {
+                dst.push(1);
+                load.encode(dst);
+            }
+            Entry::Spurious(spurious) => {
+                dst.push(2);
+                spurious.0.encode(dst);
+            }
+            Entry::Choose(choose) => {
+                dst.push(3);
+                choose.pos.encode(dst);
+                choose.len.encode(dst);
+            }
+        }
+    }
+
+    fn decode(src: &mut &[u8]) -> Result<Entry, codec::Error> {
+        match u8::decode(src)? {
+            0 => Ok(Entry::Schedule(Codec::decode(src)?)),
+            1 => Ok(Entry::Load(Codec::decode(src)?)),
+            2 => Ok(Entry::Spurious(Spurious(Codec::decode(src)?))),
+            3 => Ok(Entry::Choose(Choose {
+                pos: Codec::decode(src)?,
+                len: Codec::decode(src)?,
+            })),
+            _ => Err(codec::Error::new("invalid branch kind")),
+        }
+    }
+}
+
+#[cfg(feature = "checkpoint")]
+impl Codec for Schedule {
+    fn encode(&self, dst: &mut Vec<u8>) {
+        self.preemptions.encode(dst);
+        self.initial_active.encode(dst);
+        self.default_active.encode(dst);
+
+        for th in &self.threads {
+            th.encode(dst);
+        }
+
+        self.prev.encode(dst);
+        self.bounded.encode(dst);
+    }
+
+    fn decode(src: &mut &[u8]) -> Result<Schedule, codec::Error> {
+        let preemptions = Codec::decode(src)?;
+        let initial_active = Codec::decode(src)?;
+        let default_active = Codec::decode(src)?;
+
+        let mut threads = [Thread::Disabled; MAX_THREADS];
+
+        for th in &mut threads {
+            *th = Codec::decode(src)?;
+        }
+
+        Ok(Schedule {
+            preemptions,
+            initial_active,
+            default_active,
+            threads,
+            prev: Codec::decode(src)?,
+            bounded: Codec::decode(src)?,
+        })
+    }
+}
+
+#[cfg(feature = "checkpoint")]
+impl Codec for Load {
+    fn encode(&self, dst: &mut Vec<u8>) {
+        self.values.encode(dst);
+        self.pos.encode(dst);
+        self.len.encode(dst);
+    }
+
+    fn decode(src: &mut &[u8]) -> Result<Load, codec::Error> {
+        Ok(Load {
+            values: Codec::decode(src)?,
+            pos: Codec::decode(src)?,
+            len: Codec::decode(src)?,
+        })
+    }
+}
+
+#[cfg(feature = "checkpoint")]
+impl Codec for Thread {
+    fn encode(&self, dst: &mut Vec<u8>) {
+        let tag: u8 = match self {
+            Thread::Disabled => 0,
+            Thread::Skip => 1,
+            Thread::Yield => 2,
+            Thread::Pending => 3,
+            Thread::Active => 4,
+            Thread::Visited => 5,
+            Thread::Sleep => 6,
+        };
+
+        tag.encode(dst);
+    }
+
+    fn decode(src: &mut &[u8]) -> Result<Thread, codec::Error> {
+        match u8::decode(src)? {
+            0 => Ok(Thread::Disabled),
+            1 => Ok(Thread::Skip),
+            2 => Ok(Thread::Yield),
+            3 => Ok(Thread::Pending),
+            4 => Ok(Thread::Active),
+            5 => Ok(Thread::Visited),
+            6 => Ok(Thread::Sleep),
+            _ => Err(codec::Error::new("invalid thread state")),
+        }
+    }
+}
@@ -0,0 +1,78 @@
+//! Attributes the branch points explored by a model to the objects causing
+//! them, see [`Builder::profile_branches`](crate::model::Builder::profile_branches).
+
+use crate::model::ObjectBranches;
+use crate::rt::{object, Execution, Location};
+
+use std::collections::HashMap;
+
+/// Branch points caused by each object of the current execution, indexed by
+/// object.
+#[derive(Debug, Default)]
+pub(crate) struct Counts(Vec<usize>);
+
+/// Branch points caused by objects across executions.
+#[derive(Debug, Default)]
+pub(crate) struct Profile {
+    /// Objects are identified by their index and kind, as executions
+    /// creating the same objects in the same order create them at the same
+    /// index.
+    objects: HashMap<(usize, &'static str), (Location, usize)>,
+}
+
+impl Counts {
+    /// Records that accessing `obj` added `branches` alternatives to explore.
+    pub(super) fn add(&mut self, obj: object::Ref, branches: usize) {
+        let index = obj.as_usize();
+
+        if self.0.len() <= index {
+            self.0.resize(index + 1, 0);
+        }
+
+        self.0[index] += branches;
+    }
+
+    pub(super) fn clear(&mut self) {
+        self.0.clear();
+    }
+}
+
+impl Profile {
+    /// Adds the branch points caused by the objects of `execution`.
+    pub(crate) fn record(&mut self, execution: &Execution) {
+        let counts = match &execution.branch_counts {
+            Some(counts) => counts,
+            None => return,
+        };
+
+        for (index, &branches) in counts.0.iter().enumerate() {
+            if branches == 0 {
+                continue;
+            }
+
+            let obj = object::Ref::from_usize(index);
+            let kind = execution.objects.kind(obj);
+            let location = execution.objects.location(obj);
+
+            let entry = self.objects.entry((index, kind)).or_insert((location, 0));
+            entry.1 += branches;
+        }
+    }
+
+    /// Returns the objects that caused branch points, most branches first.
+    pub(crate) fn ranked(&self) -> Vec<ObjectBranches> {
+        let mut ranked: Vec<_> = self
+            .objects
+            .iter()
+            .map(|(&(object, kind), &(location, branches))| ObjectBranches {
+                kind,
+                object,
+                location: location.get(),
+                branches,
+            })
+            .collect();
+
+        ranked.sort_by(|a, b| b.branches.cmp(&a.branches).then(a.object.cmp(&b.object)));
+        ranked
+    }
+}
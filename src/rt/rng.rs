@@ -0,0 +1,49 @@
+#[cfg(feature = "checkpoint")]
+use crate::rt::codec::{self, Codec};
+#[cfg(feature = "checkpoint")]
+use serde::{Deserialize, Serialize};
+
+/// A small, seedable pseudo-random number generator.
+///
+/// Used to pick branches when exploring randomly instead of exhaustively. The
+/// algorithm is `splitmix64`, which is plenty for picking branches and is
+/// trivially reproducible from its seed.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "checkpoint", derive(Serialize, Deserialize))]
+pub(crate) struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub(crate) fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a value in `0..n`.
+    pub(crate) fn gen_range(&mut self, n: usize) -> usize {
+        assert!(n > 0, "[loom internal bug] empty range");
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+#[cfg(feature = "checkpoint")]
+impl Codec for Rng {
+    fn encode(&self, dst: &mut Vec<u8>) {
+        self.state.encode(dst);
+    }
+
+    fn decode(src: &mut &[u8]) -> Result<Rng, codec::Error> {
+        Ok(Rng {
+            state: u64::decode(src)?,
+        })
+    }
+}
@@ -1,5 +1,6 @@
+use crate::rt::location::{Location, LocationSet};
 use crate::rt::object;
-use crate::rt::{thread, Access, Execution, Synchronize, VersionVec};
+use crate::rt::{deadlock, thread, Access, Execution, Synchronize, VersionVec};
 
 use std::collections::HashSet;
 use std::sync::atomic::Ordering::{Acquire, Release};
@@ -9,7 +10,7 @@ pub(crate) struct RwLock {
     state: object::Ref<State>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 enum Locked {
     Read(HashSet<thread::Id>),
     Write(thread::Id),
@@ -22,14 +23,32 @@ pub(super) enum Action {
 
     /// Write lock
     Write,
+
+    /// Upgradable read lock
+    Upgradable,
+
+    /// Upgrade an upgradable read lock to a write lock
+    Upgrade,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(super) struct State {
     /// A single `thread::Id` when Write locked.
     /// A set of `thread::Id` when Read locked.
     lock: Option<Locked>,
 
+    /// The thread holding the upgradable read lock, if any.
+    upgradable: Option<thread::Id>,
+
+    /// Where the rwlock was created
+    created: Location,
+
+    /// Set when a thread panics while holding the lock
+    poisoned: bool,
+
+    /// Where each thread last acquired the rwlock
+    acquired: LocationSet,
+
     /// Tracks write access to the rwlock.
     last_access: Option<Access>,
 
@@ -39,10 +58,14 @@ pub(super) struct State {
 
 impl RwLock {
     /// Common RwLock function
-    pub(crate) fn new() -> RwLock {
+    pub(crate) fn new(location: Location) -> RwLock {
         super::execution(|execution| {
             let state = execution.objects.insert(State {
                 lock: None,
+                upgradable: None,
+                created: location,
+                poisoned: false,
+                acquired: LocationSet::new(),
                 last_access: None,
                 synchronize: Synchronize::new(),
             });
@@ -51,40 +74,72 @@ impl RwLock {
         })
     }
 
+    /// Returns an owner releasing the rwlock when dropped.
+    pub(crate) fn owner(&self) -> object::Owner {
+        super::execution(|execution| self.state.owner(execution))
+    }
+
     /// Acquire the read lock.
     /// Fail to acquire read lock if already *write* locked.
-    pub(crate) fn acquire_read_lock(&self) {
-        self.state
-            .branch_disable(Action::Read, self.is_write_locked());
-
-        assert!(
-            self.post_acquire_read_lock(),
-            "expected to be able to acquire read lock"
-        );
+    pub(crate) fn acquire_read_lock(&self, location: Location) {
+        self.acquire(Action::Read, location);
     }
 
     /// Acquire write lock.
     /// Fail to acquire write lock if either read or write locked.
-    pub(crate) fn acquire_write_lock(&self) {
-        self.state.branch_disable(
-            Action::Write,
-            self.is_write_locked() || self.is_read_locked(),
-        );
+    pub(crate) fn acquire_write_lock(&self, location: Location) {
+        self.acquire(Action::Write, location);
+    }
 
-        assert!(
-            self.post_acquire_write_lock(),
-            "expected to be able to acquire write lock"
-        );
+    /// Acquire the upgradable read lock.
+    /// Fail to acquire if write locked or another thread holds the upgradable
+    /// read lock.
+    pub(crate) fn acquire_upgradable_read_lock(&self, location: Location) {
+        self.acquire(Action::Upgradable, location);
     }
 
-    pub(crate) fn try_acquire_read_lock(&self) -> bool {
-        self.state.branch_action(Action::Read);
-        self.post_acquire_read_lock()
+    /// Upgrade the upgradable read lock held by the current thread to a write
+    /// lock, waiting for the other readers to release the lock.
+    pub(crate) fn upgrade(&self, location: Location) {
+        self.acquire(Action::Upgrade, location);
+    }
+
+    pub(crate) fn try_acquire_read_lock(&self, location: Location) -> bool {
+        self.try_acquire(Action::Read, location)
+    }
+
+    pub(crate) fn try_acquire_write_lock(&self, location: Location) -> bool {
+        self.try_acquire(Action::Write, location)
+    }
+
+    pub(crate) fn try_acquire_upgradable_read_lock(&self, location: Location) -> bool {
+        self.try_acquire(Action::Upgradable, location)
+    }
+
+    pub(crate) fn try_upgrade(&self, location: Location) -> bool {
+        self.try_acquire(Action::Upgrade, location)
+    }
+
+    fn acquire(&self, action: Action, location: Location) {
+        let can_acquire = super::execution(|execution| {
+            let thread_id = execution.threads.active_id();
+            self.state
+                .get(&execution.objects)
+                .can_acquire(action, thread_id)
+        });
+
+        self.state.branch_disable(action, !can_acquire);
+
+        assert!(
+            self.post_acquire(action, location),
+            "expected to be able to acquire {:?} lock",
+            action
+        );
     }
 
-    pub(crate) fn try_acquire_write_lock(&self) -> bool {
-        self.state.branch_action(Action::Write);
-        self.post_acquire_write_lock()
+    fn try_acquire(&self, action: Action, location: Location) -> bool {
+        self.state.branch_action(action);
+        self.post_acquire(action, location)
     }
 
     pub(crate) fn release_read_lock(&self) {
@@ -106,19 +161,38 @@ impl RwLock {
 
             readers.remove(&thread_id);
 
+            log!(
+                execution.log,
+                Debug,
+                "rwlock",
+                "thread #{}: rwlock #{} read unlocked",
+                thread_id,
+                self.state.erase().as_usize()
+            );
+
             if readers.is_empty() {
                 state.lock = None;
-
-                self.unlock_threads(execution, thread_id);
             }
+
+            self.update_waiters(execution);
         });
     }
 
-    pub(crate) fn release_write_lock(&self) {
+    /// Release the upgradable read lock held by the current thread.
+    pub(crate) fn release_upgradable_read_lock(&self) {
         super::execution(|execution| {
             let state = self.state.get_mut(&mut execution.objects);
 
-            state.lock = None;
+            assert!(state.upgradable.take().is_some());
+
+            log!(
+                execution.log,
+                Debug,
+                "rwlock",
+                "thread #{}: rwlock #{} upgradable read unlocked",
+                execution.threads.active_id(),
+                self.state.erase().as_usize()
+            );
 
             state
                 .synchronize
@@ -127,140 +201,221 @@ impl RwLock {
             // Establish sequential consistency between the lock's operations.
             execution.threads.seq_cst();
 
-            let thread_id = execution.threads.active_id();
-
-            self.unlock_threads(execution, thread_id);
+            self.update_waiters(execution);
         });
     }
 
-    fn unlock_threads(&self, execution: &mut Execution, thread_id: thread::Id) {
-        // TODO: This and the above function look very similar.
-        // Refactor the two to DRY the code.
-        for (id, thread) in execution.threads.iter_mut() {
-            if id == thread_id {
-                continue;
-            }
+    pub(crate) fn release_write_lock(&self) {
+        super::execution(|execution| {
+            let state = self.state.get_mut(&mut execution.objects);
+
+            state.lock = None;
 
-            let obj = thread
-                .operation
-                .as_ref()
-                .map(|operation| operation.object());
+            log!(
+                execution.log,
+                Debug,
+                "rwlock",
+                "thread #{}: rwlock #{} write unlocked",
+                execution.threads.active_id(),
+                self.state.erase().as_usize()
+            );
 
-            if obj == Some(self.state.erase()) {
-                thread.set_runnable();
-            }
-        }
+            state
+                .synchronize
+                .sync_store(&mut execution.threads, Release);
+
+            // Establish sequential consistency between the lock's operations.
+            execution.threads.seq_cst();
+
+            self.update_waiters(execution);
+        });
     }
 
-    /// Returns `true` if RwLock is read locked
-    fn is_read_locked(&self) -> bool {
-        super::execution(
-            |execution| match self.state.get(&mut execution.objects).lock {
-                Some(Locked::Read(_)) => true,
-                _ => false,
-            },
-        )
+    /// Poison the lock, as the thread holding it panicked.
+    pub(crate) fn poison(&self) {
+        super::execution(|execution| {
+            self.state.get_mut(&mut execution.objects).poisoned = true;
+        });
     }
 
-    /// Returns `true` if RwLock is write locked.
-    fn is_write_locked(&self) -> bool {
-        super::execution(
-            |execution| match self.state.get(&mut execution.objects).lock {
-                Some(Locked::Write(_)) => true,
-                _ => false,
-            },
-        )
+    pub(crate) fn is_poisoned(&self) -> bool {
+        super::execution(|execution| self.state.get(&execution.objects).poisoned)
     }
 
-    fn post_acquire_read_lock(&self) -> bool {
+    pub(crate) fn clear_poison(&self) {
         super::execution(|execution| {
-            let mut state = self.state.get_mut(&mut execution.objects);
+            self.state.get_mut(&mut execution.objects).poisoned = false;
+        });
+    }
+
+    /// Atomically turn the write lock, or the upgradable read lock, held by
+    /// the current thread into a read lock.
+    ///
+    /// When `upgradable` is `true`, the write lock is turned into an
+    /// upgradable read lock instead.
+    pub(crate) fn downgrade(&self, upgradable: bool) {
+        self.state.branch_opaque();
+
+        super::execution(|execution| {
+            let state = self.state.get_mut(&mut execution.objects);
             let thread_id = execution.threads.active_id();
 
-            // Set the lock to the current thread
-            let mut already_locked = false;
-            state.lock = match state.lock.take() {
-                None => {
-                    let mut threads: HashSet<thread::Id> = HashSet::new();
-                    threads.insert(thread_id);
-                    Some(Locked::Read(threads))
-                }
-                Some(Locked::Read(mut threads)) => {
-                    threads.insert(thread_id);
-                    Some(Locked::Read(threads))
-                }
-                Some(Locked::Write(writer)) => {
-                    already_locked = true;
-                    Some(Locked::Write(writer))
-                }
-            };
+            if let Some(Locked::Write(_)) = state.lock {
+                state.lock = None;
+            }
 
-            // The RwLock is already Write locked, so we cannot acquire a read lock on it.
-            if already_locked {
-                return false;
+            if upgradable {
+                state.upgradable = Some(thread_id);
+            } else {
+                state.upgradable = None;
+                state.insert_reader(thread_id);
             }
 
-            dbg!(state.synchronize.sync_load(&mut execution.threads, Acquire));
+            // Writes made under the write lock are visible to readers
+            // acquiring the lock after the downgrade.
+            state
+                .synchronize
+                .sync_store(&mut execution.threads, Release);
 
             execution.threads.seq_cst();
 
-            // Block all writer threads from attempting to acquire the RwLock
-            for (id, th) in execution.threads.iter_mut() {
-                if id == thread_id {
-                    continue;
-                }
-
-                let op = match th.operation.as_ref() {
-                    Some(op) if op.object() == self.state.erase() => op,
-                    _ => continue,
-                };
-
-                if op.action() == Action::Write {
-                    th.set_blocked();
-                }
-            }
-
-            true
-        })
+            self.update_waiters(execution);
+        });
     }
 
-    fn post_acquire_write_lock(&self) -> bool {
+    fn post_acquire(&self, action: Action, location: Location) -> bool {
         super::execution(|execution| {
             let state = self.state.get_mut(&mut execution.objects);
             let thread_id = execution.threads.active_id();
 
-            // Set the lock to the current thread
-            state.lock = match state.lock {
-                Some(Locked::Read(_)) => return false,
-                _ => Some(Locked::Write(thread_id)),
-            };
+            if !state.can_acquire(action, thread_id) {
+                return false;
+            }
+
+            match action {
+                Action::Read => state.insert_reader(thread_id),
+                Action::Upgradable => state.upgradable = Some(thread_id),
+                Action::Write => state.lock = Some(Locked::Write(thread_id)),
+                Action::Upgrade => {
+                    state.lock = Some(Locked::Write(thread_id));
+                    state.upgradable = None;
+                }
+            }
+
+            state.acquired.track(location, &execution.threads);
+
+            log!(
+                execution.log,
+                Debug,
+                "rwlock",
+                "thread #{}: rwlock #{} {}",
+                thread_id,
+                self.state.erase().as_usize(),
+                match action {
+                    Action::Read => "read locked",
+                    Action::Upgradable => "upgradable read locked",
+                    Action::Write => "write locked",
+                    Action::Upgrade => "upgraded to write locked",
+                }
+            );
 
             state.synchronize.sync_load(&mut execution.threads, Acquire);
 
             // Establish sequential consistency between locks
             execution.threads.seq_cst();
 
-            // Block all other threads attempting to acquire rwlock
-            // Block all writer threads from attempting to acquire the RwLock
-            for (id, th) in execution.threads.iter_mut() {
-                if id == thread_id {
-                    continue;
-                }
-
-                match th.operation.as_ref() {
-                    Some(op) if op.object() == self.state.erase() => {
-                        th.set_blocked();
-                    }
-                    _ => continue,
-                };
-            }
+            self.update_waiters(execution);
 
             true
         })
     }
+
+    /// Block the threads waiting on the rwlock that cannot acquire it, and
+    /// unblock the others.
+    fn update_waiters(&self, execution: &mut Execution) {
+        let thread_id = execution.threads.active_id();
+        let state = self.state.get(&execution.objects);
+
+        for (id, th) in execution.threads.iter_mut() {
+            if id == thread_id {
+                continue;
+            }
+
+            let action = match th.operation.as_ref() {
+                Some(op) if op.object() == self.state.erase() => op.action(),
+                _ => continue,
+            };
+
+            if state.can_acquire(action.into(), id) {
+                th.set_runnable();
+            } else {
+                th.set_blocked();
+            }
+        }
+    }
 }
 
 impl State {
+    /// Returns `true` if `thread_id` can perform `action` on the rwlock.
+    ///
+    /// The upgradable read lock is tracked apart from the other readers: it
+    /// only excludes writers and other upgradable readers.
+    fn can_acquire(&self, action: Action, thread_id: thread::Id) -> bool {
+        match action {
+            Action::Read => !matches!(self.lock, Some(Locked::Write(_))),
+            Action::Write => self.lock.is_none() && self.upgradable.is_none(),
+            Action::Upgradable => {
+                !matches!(self.lock, Some(Locked::Write(_))) && self.upgradable.is_none()
+            }
+            Action::Upgrade => {
+                assert_eq!(
+                    self.upgradable,
+                    Some(thread_id),
+                    "upgrading without holding the upgradable read lock"
+                );
+
+                self.lock.is_none()
+            }
+        }
+    }
+
+    fn insert_reader(&mut self, thread_id: thread::Id) {
+        match &mut self.lock {
+            Some(Locked::Read(readers)) => {
+                readers.insert(thread_id);
+            }
+            lock => {
+                let mut readers = HashSet::new();
+                readers.insert(thread_id);
+                *lock = Some(Locked::Read(readers));
+            }
+        }
+    }
+
+    pub(super) fn location(&self) -> Location {
+        self.created
+    }
+
+    /// Describes the rwlock for deadlock reports.
+    pub(super) fn resource(&self) -> deadlock::Resource {
+        let mut holders: Vec<_> = match &self.lock {
+            Some(Locked::Read(readers)) => readers.iter().copied().collect(),
+            Some(Locked::Write(writer)) => vec![*writer],
+            None => vec![],
+        };
+
+        holders.extend(self.upgradable);
+
+        deadlock::Resource {
+            kind: "lock RwLock",
+            created: self.created,
+            holders: holders
+                .into_iter()
+                .map(|id: thread::Id| (id, self.acquired[id.as_usize()]))
+                .collect(),
+        }
+    }
+
     pub(crate) fn last_dependent_access(&self) -> Option<&Access> {
         self.last_access.as_ref()
     }
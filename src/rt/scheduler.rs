@@ -1,12 +1,17 @@
 #![allow(deprecated)]
 
+use crate::model::Runner;
 use crate::rt::{thread, Execution};
 
 use generator::{self, Generator, Gn};
-use scoped_tls::scoped_thread_local;
-use std::cell::RefCell;
+use std::any::Any;
+use std::cell::{Cell, RefCell};
 use std::collections::VecDeque;
 use std::fmt;
+use std::mem;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::sync::mpsc;
 
 pub(crate) struct Scheduler {
     /// Threads
@@ -17,10 +22,78 @@ pub(crate) struct Scheduler {
     queued_spawn: VecDeque<Box<dyn FnOnce()>>,
 }
 
-type Thread = Generator<'static, Option<Box<dyn FnOnce()>>, ()>;
+/// Runs loom threads, switching between them when told to.
+enum Thread {
+    /// Generator resumed on the thread running the scheduler.
+    Fiber(Fiber),
 
-scoped_thread_local! {
-    static STATE: RefCell<State<'_>>
+    /// OS thread waiting for its turn.
+    Os(OsThread),
+}
+
+/// Generator running loom threads. It yields `true` when it switches to
+/// another thread in the middle of a loom thread, `false` once the loom
+/// thread completed.
+struct Fiber {
+    generator: Generator<'static, Option<Box<dyn FnOnce()>>, bool>,
+
+    /// `true` while the fiber is in the middle of a loom thread.
+    running: bool,
+}
+
+/// Handle to an OS thread running loom threads.
+struct OsThread {
+    tx: Option<mpsc::Sender<Message>>,
+    rx: mpsc::Receiver<Reply>,
+
+    /// `true` while the OS thread is in the middle of a loom thread.
+    running: bool,
+
+    handle: Option<std::thread::JoinHandle<()>>,
+}
+
+/// Sent by the scheduler to an OS thread.
+enum Message {
+    /// Run this loom thread the next time the OS thread is resumed.
+    Start(Unsync<Box<dyn FnOnce()>>),
+
+    /// Run until the next context switch.
+    Resume(Unsync<*const RefCell<State<'static>>>),
+}
+
+/// Sent back to the scheduler when an OS thread stops running.
+enum Reply {
+    /// The loom thread switched to another one.
+    Switched,
+
+    /// The loom thread completed.
+    Done,
+
+    /// The loom thread panicked.
+    Panicked(Box<dyn Any + Send>),
+}
+
+/// The scheduler runs one thread at a time, values which are not `Send` can
+/// therefore be handed to the OS threads.
+struct Unsync<T>(T);
+
+unsafe impl<T> Send for Unsync<T> {}
+
+/// Channels of an OS thread running loom threads.
+struct Worker {
+    tx: mpsc::Sender<Reply>,
+    rx: mpsc::Receiver<Message>,
+}
+
+// `const` initializers require Rust 1.59.
+thread_local! {
+    /// Scheduler state, set while a loom thread runs.
+    #[allow(clippy::missing_const_for_thread_local)]
+    static STATE: Cell<*const RefCell<State<'static>>> = Cell::new(ptr::null());
+
+    /// Set on the OS threads running loom threads.
+    #[allow(clippy::missing_const_for_thread_local)]
+    static WORKER: RefCell<Option<Worker>> = RefCell::new(None);
 }
 
 struct State<'a> {
@@ -30,8 +103,10 @@ struct State<'a> {
 
 impl Scheduler {
     /// Create an execution
-    pub(crate) fn new(capacity: usize) -> Scheduler {
-        let threads = spawn_threads(capacity);
+    pub(crate) fn new(capacity: usize, runner: Runner) -> Scheduler {
+        Scheduler::assert_not_nested();
+
+        let threads = spawn_threads(capacity, runner);
 
         Scheduler {
             threads,
@@ -40,45 +115,96 @@ impl Scheduler {
         }
     }
 
+    /// Panics if called from within a loom thread: models cannot be nested.
+    ///
+    /// Also called by the `Builder` before creating the execution, as
+    /// checking a model from a loom thread may overflow its stack before the
+    /// scheduler is created.
+    pub(crate) fn assert_not_nested() {
+        assert!(
+            STATE.with(Cell::get).is_null(),
+            "loom models cannot be nested: a model was checked from within an execution of \
+             another model; is `loom::model` called by a helper that already runs in a model?"
+        );
+    }
+
     /// Access the execution
     pub(crate) fn with_execution<F, R>(f: F) -> R
     where
         F: FnOnce(&mut Execution) -> R,
     {
-        STATE.with(|state| f(&mut state.borrow_mut().execution))
+        let state = STATE.with(Cell::get);
+
+        assert!(
+            !state.is_null(),
+            "cannot access loom execution state from outside a loom model; is a loom type used \
+             outside of `loom::model`, or from a thread spawned with `std::thread::spawn`?"
+        );
+
+        // Safety: the state is only set during the turn of the loom thread.
+        let state = unsafe { &*state };
+
+        // Marks the execution as failed if `f` panics.
+        struct Failed<'a> {
+            state: &'a RefCell<State<'static>>,
+            panicking: bool,
+        }
+
+        impl Drop for Failed<'_> {
+            fn drop(&mut self) {
+                if !self.panicking && std::thread::panicking() {
+                    self.state.borrow_mut().execution.failed = true;
+                }
+            }
+        }
+
+        let _failed = Failed {
+            state,
+            panicking: std::thread::panicking(),
+        };
+
+        // Dropped before `_failed`
+        let mut state = state.borrow_mut();
+        f(state.execution)
     }
 
-    /// Perform a context switch
-    pub(crate) fn switch() {
-        use std::future::Future;
-        use std::pin::Pin;
-        use std::ptr;
-        use std::task::{Context, RawWaker, RawWakerVTable, Waker};
+    /// Access the execution, if called from within a loom model while the
+    /// execution is not already accessed. Used by destructors, which may run
+    /// anywhere.
+    pub(crate) fn try_with_execution<F, R>(f: F) -> Option<R>
+    where
+        F: FnOnce(&mut Execution) -> R,
+    {
+        let state = STATE.with(Cell::get);
 
-        unsafe fn noop_clone(_: *const ()) -> RawWaker {
-            unreachable!()
+        if state.is_null() {
+            return None;
         }
-        unsafe fn noop(_: *const ()) {}
 
-        // Wrapping with an async block deals with the thread-local context
-        // `std` uses to manage async blocks
-        let mut switch = async { generator::yield_with(()) };
-        let switch = unsafe { Pin::new_unchecked(&mut switch) };
+        // Safety: the state is only set during the turn of the loom thread.
+        let state = unsafe { &*state };
+        let mut state = state.try_borrow_mut().ok()?;
+        Some(f(state.execution))
+    }
 
-        let raw_waker = RawWaker::new(
-            ptr::null(),
-            &RawWakerVTable::new(noop_clone, noop, noop, noop),
-        );
-        let mut waker = unsafe { Waker::from_raw(raw_waker) };
-        let mut cx = Context::from_waker(&mut waker);
+    /// Perform a context switch
+    pub(crate) fn switch() {
+        let os_thread = WORKER.with(|worker| worker.borrow().is_some());
 
-        assert!(switch.poll(&mut cx).is_ready());
+        if os_thread {
+            switch_os_thread();
+        } else {
+            switch_fiber();
+        }
     }
 
     pub(crate) fn spawn(f: Box<dyn FnOnce()>) {
-        STATE.with(|state| {
-            state.borrow_mut().queued_spawn.push_back(f);
-        });
+        let state = STATE.with(Cell::get);
+        assert!(!state.is_null());
+
+        // Safety: the state is only set during the turn of the loom thread.
+        let state = unsafe { &*state };
+        state.borrow_mut().queued_spawn.push_back(f);
     }
 
     pub(crate) fn run<F>(&mut self, execution: &mut Execution, f: F)
@@ -86,8 +212,7 @@ impl Scheduler {
         F: FnOnce() + Send + 'static,
     {
         self.next_thread = 1;
-        self.threads[0].set_para(Some(Box::new(f)));
-        self.threads[0].resume();
+        self.threads[0].start(Box::new(f));
 
         loop {
             if !execution.threads.is_active() {
@@ -102,8 +227,7 @@ impl Scheduler {
                 let thread_id = self.next_thread;
                 self.next_thread += 1;
 
-                self.threads[thread_id].set_para(Some(th));
-                self.threads[thread_id].resume();
+                self.threads[thread_id].start(th);
             }
         }
     }
@@ -114,11 +238,29 @@ impl Scheduler {
             queued_spawn: &mut self.queued_spawn,
         });
 
-        let threads = &mut self.threads;
+        self.threads[thread.as_usize()].resume(unsafe { transmute_lt(&state) });
+    }
+}
+
+impl Drop for Scheduler {
+    fn drop(&mut self) {
+        // The closures of the threads that did not start, and the stacks of
+        // the threads suspended in the middle of a failed execution, hold
+        // objects of the execution. They cannot be dropped outside of it and
+        // are leaked, the other threads are released.
+        for f in self.queued_spawn.drain(..) {
+            mem::forget(f);
+        }
 
-        STATE.set(unsafe { transmute_lt(&state) }, || {
-            threads[thread.as_usize()].resume();
-        });
+        for thread in self.threads.drain(..) {
+            match thread {
+                Thread::Fiber(fiber) if fiber.running && !fiber.generator.is_done() => {
+                    mem::forget(fiber)
+                }
+                // A suspended OS thread stays blocked, see `OsThread::drop`.
+                thread => drop(thread),
+            }
+        }
     }
 }
 
@@ -130,20 +272,238 @@ impl fmt::Debug for Scheduler {
     }
 }
 
-fn spawn_threads(n: usize) -> Vec<Thread> {
-    (0..n)
-        .map(|_| {
-            let mut g = Gn::new(move || {
-                loop {
-                    let f: Option<Box<dyn FnOnce()>> = generator::yield_(()).unwrap();
-                    generator::yield_with(());
-                    f.unwrap()();
+impl Thread {
+    /// Hand a loom thread over, it runs the next time it is resumed.
+    fn start(&mut self, f: Box<dyn FnOnce()>) {
+        match self {
+            Thread::Fiber(fiber) => {
+                fiber.running = true;
+                fiber.generator.set_para(Some(f));
+                fiber.generator.resume();
+            }
+            Thread::Os(os) => {
+                os.running = true;
+                os.send(Message::Start(Unsync(f)));
+            }
+        }
+    }
+
+    /// Run the loom thread until it switches to another one.
+    fn resume(&mut self, state: &RefCell<State<'static>>) {
+        match self {
+            Thread::Fiber(fiber) => {
+                let prev = STATE.with(|cell| cell.replace(state));
+
+                struct Reset(*const RefCell<State<'static>>);
+
+                impl Drop for Reset {
+                    fn drop(&mut self) {
+                        STATE.with(|cell| cell.set(self.0));
+                    }
+                }
+
+                let _reset = Reset(prev);
+                fiber.running = fiber.generator.resume() == Some(true);
+            }
+            Thread::Os(os) => {
+                os.send(Message::Resume(Unsync(state)));
+
+                match os.rx.recv().expect("loom OS thread exited") {
+                    Reply::Switched => {}
+                    Reply::Done => os.running = false,
+                    Reply::Panicked(panic) => {
+                        os.running = false;
+                        panic::resume_unwind(panic);
+                    }
                 }
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Thread {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Thread::Fiber(fiber) => fmt
+                .debug_struct("Fiber")
+                .field("running", &fiber.running)
+                .finish(),
+            Thread::Os(os) => fmt
+                .debug_struct("OsThread")
+                .field("running", &os.running)
+                .finish(),
+        }
+    }
+}
+
+impl OsThread {
+    fn spawn(stack_size: usize) -> OsThread {
+        let (tx, worker_rx) = mpsc::channel();
+        let (worker_tx, rx) = mpsc::channel();
+
+        let handle = std::thread::Builder::new()
+            .name("loom".to_string())
+            .stack_size(stack_size)
+            .spawn(move || {
+                WORKER.with(|worker| {
+                    *worker.borrow_mut() = Some(Worker {
+                        tx: worker_tx,
+                        rx: worker_rx,
+                    });
+                });
+
+                run_os_thread();
+            })
+            .expect("failed to spawn loom OS thread");
+
+        OsThread {
+            tx: Some(tx),
+            rx,
+            running: false,
+            handle: Some(handle),
+        }
+    }
+
+    fn send(&self, message: Message) {
+        let tx = self.tx.as_ref().unwrap();
+        if tx.send(message).is_err() {
+            panic!("loom OS thread exited");
+        }
+    }
+}
+
+impl Drop for OsThread {
+    fn drop(&mut self) {
+        // Closing the channel stops the thread once it waits for a new loom
+        // thread. A thread suspended in the middle of a loom thread cannot be
+        // unwound outside of its execution and stays blocked, like a
+        // suspended generator is leaked.
+        self.tx.take();
+
+        if let Some(handle) = self.handle.take() {
+            if !self.running {
+                let _ = handle.join();
+            }
+        }
+    }
+}
+
+/// Main loop of an OS thread running loom threads.
+fn run_os_thread() {
+    loop {
+        let f = match recv_message() {
+            Some(Message::Start(f)) => f.0,
+            _ => return,
+        };
+
+        // Wait for the turn of the loom thread
+        if !wait_turn() {
+            return;
+        }
+
+        let res = panic::catch_unwind(AssertUnwindSafe(f));
+
+        STATE.with(|cell| cell.set(ptr::null()));
 
-                // done!();
-            });
-            g.resume();
-            g
+        let panicked = res.is_err();
+        let reply = match res {
+            Ok(()) => Reply::Done,
+            Err(panic) => Reply::Panicked(panic),
+        };
+
+        WORKER.with(|worker| {
+            let _ = worker.borrow().as_ref().unwrap().tx.send(reply);
+        });
+
+        // The execution failed, it is not resumed.
+        if panicked {
+            return;
+        }
+    }
+}
+
+fn recv_message() -> Option<Message> {
+    WORKER.with(|worker| worker.borrow().as_ref().unwrap().rx.recv().ok())
+}
+
+/// Block until the scheduler resumes the OS thread. Returns `false` if the
+/// scheduler was dropped.
+fn wait_turn() -> bool {
+    match recv_message() {
+        Some(Message::Resume(state)) => {
+            STATE.with(|cell| cell.set(state.0));
+            true
+        }
+        _ => false,
+    }
+}
+
+fn switch_os_thread() {
+    STATE.with(|cell| cell.set(ptr::null()));
+
+    WORKER.with(|worker| {
+        let _ = worker.borrow().as_ref().unwrap().tx.send(Reply::Switched);
+    });
+
+    if !wait_turn() {
+        // The scheduler is gone, the loom thread can neither continue nor be
+        // unwound.
+        loop {
+            std::thread::park();
+        }
+    }
+}
+
+fn switch_fiber() {
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::task::{Context, RawWaker, RawWakerVTable, Waker};
+
+    unsafe fn noop_clone(_: *const ()) -> RawWaker {
+        unreachable!()
+    }
+    unsafe fn noop(_: *const ()) {}
+
+    // Wrapping with an async block deals with the thread-local context
+    // `std` uses to manage async blocks
+    let mut switch = async { generator::yield_with(true) };
+    let switch = unsafe { Pin::new_unchecked(&mut switch) };
+
+    let raw_waker = RawWaker::new(
+        ptr::null(),
+        &RawWakerVTable::new(noop_clone, noop, noop, noop),
+    );
+    let mut waker = unsafe { Waker::from_raw(raw_waker) };
+    let mut cx = Context::from_waker(&mut waker);
+
+    assert!(switch.poll(&mut cx).is_ready());
+}
+
+fn spawn_threads(n: usize, runner: Runner) -> Vec<Thread> {
+    (0..n)
+        .map(|_| match runner {
+            Runner::Fibers { stack_size } => {
+                // The generator stack size is in words, an odd size also
+                // enables tracking the stack usage.
+                let words = (stack_size / std::mem::size_of::<usize>()) & !1;
+
+                let mut generator = Gn::new_opt(words, move || {
+                    loop {
+                        let f: Option<Box<dyn FnOnce()>> = generator::yield_(false).unwrap();
+                        generator::yield_with(false);
+                        f.unwrap()();
+                    }
+
+                    // done!();
+                });
+                generator.resume();
+
+                Thread::Fiber(Fiber {
+                    generator,
+                    running: false,
+                })
+            }
+            Runner::OsThreads { stack_size } => Thread::Os(OsThread::spawn(stack_size)),
         })
         .collect()
 }
@@ -0,0 +1,138 @@
+use crate::rt::object;
+use crate::rt::{deadlock, Access, Location, Synchronize, VersionVec};
+
+use std::sync::atomic::Ordering::{Acquire, Release};
+
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Semaphore {
+    state: object::Ref<State>,
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct State {
+    /// Number of permits available to acquire.
+    permits: usize,
+
+    /// Where the semaphore was created
+    created: Location,
+
+    /// Tracks access to the semaphore
+    last_access: Option<Access>,
+
+    /// Causality transfers between threads
+    synchronize: Synchronize,
+}
+
+impl Semaphore {
+    pub(crate) fn new(permits: usize, location: Location) -> Semaphore {
+        super::execution(|execution| {
+            let state = execution.objects.insert(State {
+                permits,
+                created: location,
+                last_access: None,
+                synchronize: Synchronize::new(),
+            });
+
+            Semaphore { state }
+        })
+    }
+
+    /// Acquire `n` permits, blocking until they are available.
+    pub(crate) fn acquire(&self, n: usize) {
+        loop {
+            // Block until a release makes permits available. Another thread
+            // may acquire them first, in which case try again.
+            self.state.branch_acquire(!self.is_available(n));
+
+            if self.post_acquire(n) {
+                return;
+            }
+        }
+    }
+
+    pub(crate) fn try_acquire(&self, n: usize) -> bool {
+        self.state.branch_opaque();
+        self.post_acquire(n)
+    }
+
+    pub(crate) fn release(&self, n: usize) {
+        self.state.branch_opaque();
+
+        super::execution(|execution| {
+            let state = self.state.get_mut(&mut execution.objects);
+
+            state.permits = state
+                .permits
+                .checked_add(n)
+                .expect("semaphore permits overflow");
+
+            state
+                .synchronize
+                .sync_store(&mut execution.threads, Release);
+
+            let thread_id = execution.threads.active_id();
+
+            // Unblock all threads waiting to acquire permits
+            for (id, thread) in execution.threads.iter_mut() {
+                if id == thread_id {
+                    continue;
+                }
+
+                let obj = thread
+                    .operation
+                    .as_ref()
+                    .map(|operation| operation.object());
+
+                if obj == Some(self.state.erase()) {
+                    thread.set_runnable();
+                }
+            }
+        });
+    }
+
+    pub(crate) fn available_permits(&self) -> usize {
+        super::execution(|execution| self.state.get(&execution.objects).permits)
+    }
+
+    fn post_acquire(&self, n: usize) -> bool {
+        super::execution(|execution| {
+            let state = self.state.get_mut(&mut execution.objects);
+
+            if state.permits < n {
+                return false;
+            }
+
+            state.permits -= n;
+            state.synchronize.sync_load(&mut execution.threads, Acquire);
+
+            true
+        })
+    }
+
+    fn is_available(&self, n: usize) -> bool {
+        self.available_permits() >= n
+    }
+}
+
+impl State {
+    /// Describes the semaphore for deadlock reports.
+    pub(super) fn resource(&self) -> deadlock::Resource {
+        deadlock::Resource {
+            kind: "acquire Semaphore",
+            created: self.created,
+            holders: vec![],
+        }
+    }
+
+    pub(super) fn location(&self) -> Location {
+        self.created
+    }
+
+    pub(crate) fn last_dependent_access(&self) -> Option<&Access> {
+        self.last_access.as_ref()
+    }
+
+    pub(crate) fn set_last_access(&mut self, path_id: usize, version: &VersionVec) {
+        Access::set_or_create(&mut self.last_access, path_id, version);
+    }
+}
@@ -32,14 +32,15 @@ impl Synchronize {
                 self.sync_acq(threads);
                 threads.seq_cst();
             }
-            order => unimplemented!("unimplemented ordering {:?}", order),
+            order => unsupported(order),
         }
     }
 
     pub fn sync_store(&mut self, threads: &mut thread::Set, order: Ordering) {
         match order {
             Relaxed | Acquire => {
-                // Nothing happens!
+                // Only synchronizes if the store follows a release fence.
+                self.happens_before.join(&threads.active().released);
             }
             Release | AcqRel => {
                 self.sync_rel(threads);
@@ -48,10 +49,26 @@ impl Synchronize {
                 self.sync_rel(threads);
                 threads.seq_cst();
             }
-            order => unimplemented!("unimplemented ordering {:?}", order),
+            order => unsupported(order),
         }
     }
 
+    /// Returns `true` if acquiring this point updates the causality of the
+    /// active thread.
+    pub fn releases(&self, threads: &thread::Set) -> bool {
+        self.releases_to(&threads.active().causality)
+    }
+
+    /// Returns `true` if acquiring this point updates `causality`.
+    pub fn releases_to(&self, causality: &VersionVec) -> bool {
+        use std::cmp::Ordering::{Equal, Less};
+
+        !matches!(
+            self.happens_before.partial_cmp(causality),
+            Some(Less) | Some(Equal)
+        )
+    }
+
     fn sync_acq(&mut self, threads: &mut thread::Set) {
         threads.active_mut().causality.join(&self.happens_before);
     }
@@ -60,3 +77,17 @@ impl Synchronize {
         self.happens_before.join(&threads.active().causality);
     }
 }
+
+/// Panics on an ordering loom does not know about.
+///
+/// `Ordering` is `#[non_exhaustive]`: orderings added to `std` after loom was
+/// written, such as a consume ordering, end up here. The orderings passed to
+/// the atomic operations are checked before reaching this point, see
+/// `rt::atomic::check_ordering`.
+pub(crate) fn unsupported(order: Ordering) -> ! {
+    panic!(
+        "`Ordering::{:?}` is not supported by loom; use `Relaxed`, `Acquire`, `Release`, \
+         `AcqRel` or `SeqCst`",
+        order
+    )
+}
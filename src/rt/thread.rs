@@ -1,8 +1,9 @@
 use crate::rt::execution;
 use crate::rt::object::Operation;
 use crate::rt::vv::VersionVec;
+use crate::rt::Location;
 
-use std::{any::Any, collections::HashMap, fmt, ops};
+use std::{any::Any, collections::HashMap, fmt, mem, ops};
 pub(crate) struct Thread {
     pub id: Id,
 
@@ -12,6 +13,10 @@ pub(crate) struct Thread {
     /// True if the thread is in a critical section
     pub critical: bool,
 
+    /// The branch point at which the thread was last scheduled while in a
+    /// critical section.
+    pub(super) critical_branch: Option<usize>,
+
     /// The operation the thread is about to take
     pub(super) operation: Option<Operation>,
 
@@ -21,12 +26,49 @@ pub(crate) struct Thread {
     /// Tracks DPOR relations
     pub dpor_vv: VersionVec,
 
+    /// Causality as of the most recent release fence. Stores following the
+    /// fence release this causality.
+    pub released: VersionVec,
+
+    /// Causality of all `SeqCst` fences preceding the thread's most recent
+    /// `SeqCst` fence. Used to apply coherence rules.
+    pub seq_cst_view: VersionVec,
+
+    /// Position of the thread's most recent `SeqCst` fence in the total order
+    /// of `SeqCst` operations.
+    pub seq_cst_fence: Option<usize>,
+
     /// Version at which the thread last yielded
     pub last_yield: Option<u16>,
 
     /// Number of times the thread yielded
     pub yield_count: usize,
 
+    /// True if the next raw allocation made by the thread fails
+    pub fail_next_alloc: bool,
+
+    /// True while the thread explains the stores its atomic loads observe
+    pub explain: bool,
+
+    /// True if the thread must unwind the next time it runs, as the main
+    /// thread returned
+    pub terminate: bool,
+
+    /// True while the thread unwinds after being terminated. Unlike a panic,
+    /// this does not poison the locks the thread holds.
+    pub exiting: bool,
+
+    /// True while a spawned thread runs its closure, where an injected
+    /// failure may kill it
+    pub killable: bool,
+
+    /// Payload of the panic the thread terminated with, until the thread is
+    /// joined.
+    pub panic: Option<Box<dyn Any + Send>>,
+
+    /// Where the thread was spawned, if locations are tracked.
+    pub spawned_at: Location,
+
     locals: LocalMap,
 }
 
@@ -38,6 +80,9 @@ pub(crate) struct Set {
     /// Set of threads
     threads: Vec<Thread>,
 
+    /// Maximum number of threads that were alive at the same time.
+    max_concurrent: usize,
+
     /// Currently scheduled thread.
     ///
     /// `None` signifies that no thread is runnable.
@@ -46,6 +91,14 @@ pub(crate) struct Set {
     /// Sequential consistency causality. All sequentially consistent operations
     /// synchronize with this causality.
     pub seq_cst_causality: VersionVec,
+
+    /// Number of `SeqCst` operations performed so far. Used to order `SeqCst`
+    /// stores and fences.
+    seq_cst_count: usize,
+
+    /// Threads of previous executions, reused by the next ones instead of
+    /// allocating new threads.
+    spare: Vec<Thread>,
 }
 
 #[derive(Eq, PartialEq, Hash, Copy, Clone)]
@@ -72,10 +125,12 @@ pub(crate) enum State {
 
 type LocalMap = HashMap<LocalKeyId, LocalValue>;
 
-#[derive(Eq, PartialEq, Hash, Copy, Clone)]
-struct LocalKeyId(usize);
+#[derive(Eq, PartialEq, Ord, PartialOrd, Hash, Copy, Clone)]
+pub(crate) struct LocalKeyId(usize);
 
-struct LocalValue(Option<Box<dyn Any>>);
+/// The value of a thread local, `None` once destroyed, and the order in which
+/// the thread local was initialized.
+struct LocalValue(Option<Box<dyn Any>>, usize);
 
 impl Thread {
     fn new(id: Id) -> Thread {
@@ -83,11 +138,22 @@ impl Thread {
             id,
             state: State::Runnable,
             critical: false,
+            critical_branch: None,
             operation: None,
             causality: VersionVec::new(),
             dpor_vv: VersionVec::new(),
+            released: VersionVec::new(),
+            seq_cst_view: VersionVec::new(),
+            seq_cst_fence: None,
             last_yield: None,
             yield_count: 0,
+            fail_next_alloc: false,
+            explain: false,
+            terminate: false,
+            exiting: false,
+            killable: false,
+            panic: None,
+            spawned_at: Location::disabled(),
             locals: HashMap::new(),
         }
     }
@@ -138,15 +204,87 @@ impl Thread {
         self.state = State::Terminated;
     }
 
-    pub(crate) fn drop_locals(&mut self) -> Box<dyn std::any::Any> {
-        let mut locals = Vec::with_capacity(self.locals.len());
+    /// Returns the thread locals not yet destroyed, in initialization order.
+    ///
+    /// The handle returned by `thread::current` is only destroyed once all
+    /// other thread locals are.
+    pub(crate) fn live_locals(&self) -> Vec<LocalKeyId> {
+        let current = LocalKeyId::new(&crate::thread::CURRENT_THREAD_KEY);
 
-        // run the Drop impls of any mock thread-locals created by this thread.
-        for (_, local) in &mut self.locals {
-            locals.push(local.0.take());
+        let mut live: Vec<_> = self
+            .locals
+            .iter()
+            .filter(|(key, local)| local.0.is_some() && **key != current)
+            .map(|(key, local)| (local.1, *key))
+            .collect();
+
+        if live.is_empty() && matches!(self.locals.get(&current), Some(LocalValue(Some(_), _))) {
+            return vec![current];
         }
 
-        Box::new(locals)
+        live.sort_unstable();
+        live.into_iter().map(|(_, key)| key).collect()
+    }
+
+    /// Destroys the thread local, returning its value to be dropped outside of
+    /// the execution. Accessing it afterwards fails with `AccessError`.
+    pub(crate) fn take_local(&mut self, key: LocalKeyId) -> Box<dyn std::any::Any> {
+        self.locals.get_mut(&key).unwrap().0.take().unwrap()
+    }
+
+    /// Returns a copy of the thread, without its thread locals.
+    ///
+    /// The handle returned by `thread::current` is initialized again when
+    /// needed, other thread locals cannot be copied.
+    fn snapshot(&self) -> Thread {
+        let mut thread = Thread::new(self.id);
+        thread.restore(self);
+        thread
+    }
+
+    /// Resets the thread to the state of `snapshot`, see `Thread::snapshot`.
+    ///
+    /// The thread locals map is cleared, keeping its allocation.
+    fn restore(&mut self, snapshot: &Thread) {
+        let current = LocalKeyId::new(&crate::thread::CURRENT_THREAD_KEY);
+
+        assert!(
+            snapshot.locals.keys().all(|key| *key == current),
+            "thread locals cannot be accessed before the snapshot is taken"
+        );
+
+        self.id = snapshot.id;
+        self.state = snapshot.state;
+        self.critical = snapshot.critical;
+        self.critical_branch = snapshot.critical_branch;
+        self.operation = snapshot.operation;
+        self.causality = snapshot.causality;
+        self.dpor_vv = snapshot.dpor_vv;
+        self.released = snapshot.released;
+        self.seq_cst_view = snapshot.seq_cst_view;
+        self.seq_cst_fence = snapshot.seq_cst_fence;
+        self.last_yield = snapshot.last_yield;
+        self.yield_count = snapshot.yield_count;
+        self.fail_next_alloc = snapshot.fail_next_alloc;
+        self.explain = snapshot.explain;
+        self.spawned_at = snapshot.spawned_at;
+        self.terminate = snapshot.terminate;
+        self.exiting = snapshot.exiting;
+        self.killable = snapshot.killable;
+        self.panic = None;
+        self.locals.clear();
+    }
+
+    /// Resets the thread to the state of a new thread, keeping the allocation
+    /// of its thread locals map.
+    fn reset(&mut self, id: Id) {
+        let mut locals = mem::take(&mut self.locals);
+        locals.clear();
+
+        *self = Thread {
+            locals,
+            ..Thread::new(id)
+        };
     }
 
     pub(crate) fn unpark(&mut self, unparker: &Thread) {
@@ -189,8 +327,11 @@ impl Set {
         Set {
             execution_id,
             threads,
+            max_concurrent: 1,
             active: Some(0),
             seq_cst_causality: VersionVec::new(),
+            seq_cst_count: 0,
+            spare: Vec::new(),
         }
     }
 
@@ -200,14 +341,17 @@ impl Set {
 
     /// Create a new thread
     pub(crate) fn new_thread(&mut self) -> Id {
-        assert!(self.threads.len() < self.max());
+        assert!(self.threads.len() < self.max(), "[loom internal bug]");
 
         // Get the identifier for the thread about to be created
         let id = self.threads.len();
 
         // Push the thread onto the stack
-        self.threads
-            .push(Thread::new(Id::new(self.execution_id, id)));
+        let thread = self.spare_thread(Id::new(self.execution_id, id));
+        self.threads.push(thread);
+
+        let alive = self.threads.iter().filter(|th| !th.is_terminated()).count();
+        self.max_concurrent = self.max_concurrent.max(alive);
 
         Id::new(self.execution_id, id)
     }
@@ -216,6 +360,12 @@ impl Set {
         self.threads.capacity()
     }
 
+    /// Returns the maximum number of threads that were alive at the same time
+    /// during the execution.
+    pub(crate) fn max_concurrent(&self) -> usize {
+        self.max_concurrent
+    }
+
     pub(crate) fn is_active(&self) -> bool {
         self.active.is_some()
     }
@@ -279,13 +429,99 @@ impl Set {
         // but will not silently allow bugs.
     }
 
+    /// Insert a `SeqCst` fence.
+    ///
+    /// The fence does not synchronize with other threads. Instead, operations
+    /// following the fence apply the coherence rules as if all stores that
+    /// happen before an earlier fence were observed.
+    pub(crate) fn seq_cst_fence(&mut self) {
+        let causality = self.active().causality;
+        self.seq_cst_causality.join(&causality);
+
+        let seq_cst_causality = self.seq_cst_causality;
+        let order = self.next_seq_cst();
+
+        let active = self.active_mut();
+        active.seq_cst_view.join(&seq_cst_causality);
+        active.seq_cst_fence = Some(order);
+    }
+
+    /// Returns the position of the next `SeqCst` operation in the total order
+    /// of `SeqCst` operations.
+    pub(crate) fn next_seq_cst(&mut self) -> usize {
+        self.seq_cst_count += 1;
+        self.seq_cst_count
+    }
+
     pub(crate) fn clear(&mut self, execution_id: execution::Id) {
-        self.threads.clear();
-        self.threads.push(Thread::new(Id::new(execution_id, 0)));
+        self.recycle_threads();
+
+        let main = self.spare_thread(Id::new(execution_id, 0));
+        self.threads.push(main);
 
         self.execution_id = execution_id;
+        self.max_concurrent = 1;
         self.active = Some(0);
         self.seq_cst_causality = VersionVec::new();
+        self.seq_cst_count = 0;
+    }
+
+    /// Returns a copy of the set, see `Thread::snapshot`.
+    pub(crate) fn snapshot(&self) -> Set {
+        // The capacity of the vec is the maximum number of threads.
+        let mut threads = Vec::with_capacity(self.max());
+        threads.extend(self.threads.iter().map(Thread::snapshot));
+
+        Set {
+            execution_id: self.execution_id,
+            threads,
+            max_concurrent: self.max_concurrent,
+            active: self.active,
+            seq_cst_causality: self.seq_cst_causality,
+            seq_cst_count: self.seq_cst_count,
+            spare: Vec::new(),
+        }
+    }
+
+    /// Resets the set to the state of `snapshot`, see `Set::snapshot`. Unlike
+    /// taking a new snapshot, this reuses the threads of the set.
+    pub(crate) fn restore(&mut self, snapshot: &Set) {
+        self.recycle_threads();
+
+        for thread in &snapshot.threads {
+            let mut restored = self.spare_thread(thread.id);
+            restored.restore(thread);
+            self.threads.push(restored);
+        }
+
+        self.execution_id = snapshot.execution_id;
+        self.max_concurrent = snapshot.max_concurrent;
+        self.active = snapshot.active;
+        self.seq_cst_causality = snapshot.seq_cst_causality;
+        self.seq_cst_count = snapshot.seq_cst_count;
+    }
+
+    /// Moves the threads to the spare threads, the next execution reuses them.
+    /// Their thread locals and panic payloads are dropped now, as when the
+    /// threads were dropped.
+    fn recycle_threads(&mut self) {
+        for mut thread in self.threads.drain(..).rev() {
+            thread.locals.clear();
+            thread.panic = None;
+            self.spare.push(thread);
+        }
+    }
+
+    /// Returns a new thread, reusing a thread of a previous execution if
+    /// possible.
+    fn spare_thread(&mut self, id: Id) -> Thread {
+        match self.spare.pop() {
+            Some(mut thread) => {
+                thread.reset(id);
+                thread
+            }
+            None => Thread::new(id),
+        }
     }
 
     pub(crate) fn iter<'a>(&'a self) -> impl ExactSizeIterator<Item = (Id, &'a Thread)> + 'a {
@@ -333,10 +569,11 @@ impl Set {
         key: &'static crate::thread::LocalKey<T>,
         value: T,
     ) {
-        assert!(self
-            .active_mut()
-            .locals
-            .insert(LocalKeyId::new(key), LocalValue::new(value))
+        let locals = &mut self.active_mut().locals;
+        let order = locals.len();
+
+        assert!(locals
+            .insert(LocalKeyId::new(key), LocalValue::new(value, order))
             .is_none())
     }
 }
@@ -390,8 +627,8 @@ impl LocalKeyId {
 }
 
 impl LocalValue {
-    fn new<T: 'static>(value: T) -> Self {
-        Self(Some(Box::new(value)))
+    fn new<T: 'static>(value: T, order: usize) -> Self {
+        Self(Some(Box::new(value)), order)
     }
 
     fn get<T: 'static>(&self) -> Result<&T, AccessError> {
@@ -0,0 +1,67 @@
+use crate::rt::{self, object, Access, VersionVec};
+
+use std::time::Duration;
+
+/// The virtual clock of an execution.
+///
+/// Time starts at zero and only moves when a thread advances it. Reading and
+/// advancing the clock are dependent operations, so the model explores a read
+/// happening both before and after a concurrent advance.
+#[derive(Debug, Clone)]
+pub(super) struct Clock {
+    /// Time elapsed since the start of the execution
+    now: Duration,
+
+    /// Tracks access to the clock
+    last_access: Option<Access>,
+}
+
+/// Returns the current virtual time, as the duration since the start of the
+/// execution.
+pub(crate) fn now() -> Duration {
+    let clock = clock();
+
+    clock.branch_opaque();
+
+    rt::execution(|execution| clock.get(&execution.objects).now)
+}
+
+/// Moves the virtual clock forward by `dur`.
+pub(crate) fn advance(dur: Duration) {
+    let clock = clock();
+
+    clock.branch_opaque();
+
+    rt::execution(|execution| {
+        let clock = clock.get_mut(&mut execution.objects);
+
+        clock.now = clock
+            .now
+            .checked_add(dur)
+            .expect("overflow when advancing the clock");
+    });
+}
+
+/// All threads of an execution share a single clock, created on first use.
+fn clock() -> object::Ref<Clock> {
+    rt::execution(|execution| {
+        let clock = execution.objects.iter_ref::<Clock>().next();
+
+        clock.unwrap_or_else(|| {
+            execution.objects.insert(Clock {
+                now: Duration::from_secs(0),
+                last_access: None,
+            })
+        })
+    })
+}
+
+impl Clock {
+    pub(super) fn last_dependent_access(&self) -> Option<&Access> {
+        self.last_access.as_ref()
+    }
+
+    pub(super) fn set_last_access(&mut self, path_id: usize, version: &VersionVec) {
+        Access::set_or_create(&mut self.last_access, path_id, version);
+    }
+}
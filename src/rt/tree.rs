@@ -0,0 +1,42 @@
+use crate::explore::{ExplorationTree, TreeNode};
+use crate::rt::Path;
+
+/// Records the tree of branch points explored by a check.
+///
+/// Exhaustive exploration is a depth-first traversal of the tree, its nodes
+/// are opened in pre-order. A node stays open, and its counts are refreshed
+/// after each execution, until the path steps past its branch point.
+#[derive(Debug, Default)]
+pub(crate) struct Tree {
+    /// Nodes in pre-order.
+    nodes: Vec<TreeNode>,
+
+    /// Index in `nodes` of the node of each branch point of the path.
+    open: Vec<usize>,
+}
+
+impl Tree {
+    /// Records the branch points reached by the execution that just ran.
+    pub(crate) fn record(&mut self, path: &Path) {
+        // Branch points first reached by this execution.
+        for index in self.open.len()..path.len() {
+            self.open.push(self.nodes.len());
+            self.nodes.push(path.tree_node(index));
+        }
+
+        for (index, &node) in self.open.iter().enumerate() {
+            self.nodes[node] = path.tree_node(index);
+        }
+    }
+
+    /// Closes the nodes the path stepped past. `diverged` is the branch point
+    /// the next execution takes a new option at, `None` once the exploration
+    /// is complete.
+    pub(crate) fn step(&mut self, diverged: Option<usize>) {
+        self.open.truncate(diverged.map_or(0, |index| index + 1));
+    }
+
+    pub(crate) fn finish(self) -> ExplorationTree {
+        ExplorationTree { nodes: self.nodes }
+    }
+}
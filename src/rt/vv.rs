@@ -33,20 +33,32 @@ impl VersionVec {
     }
 
     pub(crate) fn join(&mut self, other: &VersionVec) {
-        for (i, &version) in other.versions.iter().enumerate() {
-            self.versions[i] = cmp::max(self.versions[i], version);
+        // Branch free over the fixed size array, which the compiler
+        // vectorizes.
+        for (version, &other) in self.versions.iter_mut().zip(&other.versions) {
+            *version = cmp::max(*version, other);
         }
     }
 
     /// Returns the thread ID, if any, that is ahead of the current version.
     pub(crate) fn ahead(&self, other: &VersionVec) -> Option<usize> {
-        for (i, &version) in other.versions.iter().enumerate() {
-            if self.versions[i] < version {
-                return Some(i);
-            }
+        // Most calls find no thread ahead, check all of them at once first.
+        if !self.any_less(other) {
+            return None;
         }
 
-        None
+        self.versions
+            .iter()
+            .zip(&other.versions)
+            .position(|(a, b)| a < b)
+    }
+
+    /// Returns `true` if any version is lower than the one of `other`.
+    fn any_less(&self, other: &VersionVec) -> bool {
+        self.versions
+            .iter()
+            .zip(&other.versions)
+            .fold(false, |less, (a, b)| less | (a < b))
     }
 }
 
@@ -54,30 +66,14 @@ impl cmp::PartialOrd for VersionVec {
     fn partial_cmp(&self, other: &VersionVec) -> Option<cmp::Ordering> {
         use cmp::Ordering::*;
 
-        let mut ret = Equal;
-
-        for i in 0..MAX_THREADS {
-            let a = self.versions[i];
-            let b = other.versions[i];
-
-            if a == b {
-                // Keep checking
-            } else if a < b {
-                if ret == Greater {
-                    return None;
-                }
-
-                ret = Less;
-            } else {
-                if ret == Less {
-                    return None;
-                }
-
-                ret = Greater;
-            }
+        // Comparing every version without early returns lets the compiler
+        // vectorize the loop, version vectors are small.
+        match (self.any_less(other), other.any_less(self)) {
+            (false, false) => Some(Equal),
+            (true, false) => Some(Less),
+            (false, true) => Some(Greater),
+            (true, true) => None,
         }
-
-        Some(ret)
     }
 }
 
@@ -22,7 +22,7 @@ impl<T> Arc<T> {
     pub fn new(value: T) -> Arc<T> {
         let inner = std::sync::Arc::new(Inner {
             value,
-            obj: rt::Arc::new(location!()),
+            obj: rt::Arc::new(std::any::type_name::<T>(), location!()),
         });
 
         Arc { inner }
@@ -1,3 +1,4 @@
+use super::ExternalOp;
 use crate::rt;
 
 use std::sync::atomic::Ordering;
@@ -6,6 +7,9 @@ use std::sync::atomic::Ordering;
 pub(crate) struct Atomic<T> {
     /// Atomic object
     state: rt::Atomic<T>,
+
+    /// Releases the object when the atomic is dropped
+    _owner: rt::object::Owner,
 }
 
 impl<T> Atomic<T>
@@ -14,8 +18,12 @@ where
 {
     pub(crate) fn new(value: T, location: rt::Location) -> Atomic<T> {
         let state = rt::Atomic::new(value, location);
+        let owner = state.owner();
 
-        Atomic { state }
+        Atomic {
+            state,
+            _owner: owner,
+        }
     }
 
     #[track_caller]
@@ -23,6 +31,16 @@ where
         self.state.unsync_load(location!())
     }
 
+    #[track_caller]
+    pub(crate) unsafe fn unsync_store(&self, value: T) {
+        self.state.unsync_store(location!(), value)
+    }
+
+    #[track_caller]
+    pub(crate) fn no_concurrent_access(&self) {
+        self.state.no_concurrent_access(location!())
+    }
+
     #[track_caller]
     pub(crate) fn load(&self, order: Ordering) -> T {
         self.state.load(location!(), order)
@@ -33,6 +51,18 @@ where
         self.state.store(location!(), value, order)
     }
 
+    #[track_caller]
+    pub(crate) fn record_external_op(&self, op: ExternalOp<T>, order: Ordering) -> T {
+        match op {
+            ExternalOp::Load => self.load(order),
+            ExternalOp::Store(value) => {
+                self.store(value, order);
+                value
+            }
+            ExternalOp::Swap(value) => self.swap(value, order),
+        }
+    }
+
     #[track_caller]
     pub(crate) fn with_mut<R>(&mut self, f: impl FnOnce(&mut T) -> R) -> R {
         self.state.with_mut(location!(), f)
@@ -46,7 +76,8 @@ where
     where
         F: FnOnce(T) -> T,
     {
-        self.try_rmw::<_, ()>(order, order, |v| Ok(f(v))).unwrap()
+        self.try_rmw::<_, ()>(order, failure_ordering(order), |v| Ok(f(v)))
+            .unwrap()
     }
 
     #[track_caller]
@@ -64,15 +95,7 @@ where
 
     #[track_caller]
     pub(crate) fn compare_and_swap(&self, current: T, new: T, order: Ordering) -> T {
-        use self::Ordering::*;
-
-        let failure = match order {
-            Relaxed | Release => Relaxed,
-            Acquire | AcqRel => Acquire,
-            _ => SeqCst,
-        };
-
-        match self.compare_exchange(current, new, order, failure) {
+        match self.compare_exchange(current, new, order, failure_ordering(order)) {
             Ok(v) => v,
             Err(v) => v,
         }
@@ -86,13 +109,8 @@ where
         success: Ordering,
         failure: Ordering,
     ) -> Result<T, T> {
-        self.try_rmw(success, failure, |actual| {
-            if actual == current {
-                Ok(new)
-            } else {
-                Err(actual)
-            }
-        })
+        self.state
+            .compare_exchange(location!(), current, new, success, failure)
     }
 
     #[track_caller]
@@ -115,3 +133,15 @@ where
         Err(prev)
     }
 }
+
+/// Returns the strongest failure ordering allowed with the `success` ordering
+/// of a compare-and-exchange.
+fn failure_ordering(success: Ordering) -> Ordering {
+    use self::Ordering::*;
+
+    match success {
+        Relaxed | Release => Relaxed,
+        Acquire | AcqRel => Acquire,
+        order => order,
+    }
+}
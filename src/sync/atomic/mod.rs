@@ -1,4 +1,29 @@
 //! Mock implementation of `std::sync::atomic`.
+//!
+//! [`Ordering`] is re-exported from `std`, code can use either path.
+//!
+//! Like `std`, an operation given an ordering it does not accept panics, for
+//! example a `Release` load, an `AcqRel` compare-and-exchange failure ordering
+//! or a `Relaxed` fence. The panic names the operation, the ordering and the
+//! location of the call. Orderings loom does not model, such as a consume
+//! ordering, are rejected the same way.
+//!
+//! # Memory model
+//!
+//! Loom models a subset of the C++11 memory model. A load returns a value
+//! written by a store that already executed, out-of-thin-air values are never
+//! produced. Only the most recent stores of each atomic are kept, older ones
+//! can no longer be observed. [`explain`] prints which stores a load may
+//! observe and the rule hiding the others.
+//!
+//! # Wrapping arithmetic
+//!
+//! Like `std`, `fetch_add` and `fetch_sub` wrap around on overflow, in debug
+//! builds too. Loom tracks each store rather than each value: a value stored
+//! again after the atomic wrapped around is a different store, and which of
+//! the two a load observes is explored. Index based protocols, such as the
+//! head and tail of a ring buffer, are checked across the wraparound by
+//! starting the indices just below the maximum value.
 
 mod atomic;
 use self::atomic::Atomic;
@@ -15,12 +40,85 @@ pub use self::ptr::AtomicPtr;
 
 pub use std::sync::atomic::Ordering;
 
+/// An atomic operation performed outside of Rust, recorded with
+/// `record_external_op`.
+///
+/// Code handing the address of an atomic to foreign code, which operates on
+/// it natively, cannot be checked by loom as is: a loom atomic has no memory
+/// foreign code could access. Calling `record_external_op` where the foreign
+/// code accesses the atomic makes the operation part of the model, with the
+/// same ordering semantics as the equivalent Rust operation.
+///
+/// ```
+/// use loom::sync::atomic::{AtomicUsize, ExternalOp};
+///
+/// use std::sync::atomic::Ordering::{Acquire, Release};
+///
+/// loom::model(|| {
+///     let num = AtomicUsize::new(0);
+///
+///     // The foreign code performs a release store of 1.
+///     num.record_external_op(ExternalOp::Store(1), Release);
+///
+///     assert_eq!(1, num.load(Acquire));
+/// });
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExternalOp<T> {
+    /// The foreign code loaded the value of the atomic.
+    Load,
+
+    /// The foreign code stored the value.
+    Store(T),
+
+    /// The foreign code swapped the value with a read-modify-write.
+    Swap(T),
+}
+
 /// Signals the processor that it is entering a busy-wait spin-loop.
 pub fn spin_loop_hint() {
     crate::thread::yield_now();
 }
 
+/// Runs `f`, explaining the atomic loads it performs.
+///
+/// For each load, the stores in the history of the atomic are printed, in the
+/// order they happened, along with whether the load may observe them. Stores
+/// the load cannot observe come with the rule hiding them: coherence with the
+/// stores the thread already observed, the total order of `SeqCst` operations
+/// or `SeqCst` fences. Visible stores note whether they synchronize with the
+/// load, either because they are release stores or part of a release sequence.
+/// The store returned in the current execution is marked.
+///
+/// This is meant to understand why loom considers an execution legal, loads
+/// are explained on every execution reaching them.
+///
+/// ```
+/// use loom::sync::atomic::{self, AtomicUsize};
+/// use loom::sync::Arc;
+/// use loom::thread;
+///
+/// use std::sync::atomic::Ordering::{Acquire, Release};
+///
+/// loom::model(|| {
+///     let num = Arc::new(AtomicUsize::new(0));
+///     let num2 = num.clone();
+///
+///     let th = thread::spawn(move || num2.store(1, Release));
+///
+///     atomic::explain(|| num.load(Acquire));
+///     th.join().unwrap();
+/// });
+/// ```
+pub fn explain<F, R>(f: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    crate::rt::explain(f)
+}
+
 /// An atomic fence.
+#[track_caller]
 pub fn fence(order: Ordering) {
-    crate::rt::fence(order);
+    crate::rt::fence(order, location!());
 }
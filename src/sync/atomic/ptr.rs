@@ -1,4 +1,4 @@
-use super::Atomic;
+use super::{Atomic, ExternalOp};
 
 use std::sync::atomic::Ordering;
 
@@ -14,16 +14,66 @@ impl<T> AtomicPtr<T> {
     }
 
     /// Load the value without any synchronization.
+    ///
+    /// This is a plain read of the memory backing the atomic, as done by code
+    /// reading the atomic while no other thread can access it, such as in a
+    /// `Drop` implementation.
+    ///
+    /// # Safety
+    ///
+    /// All stores to the atomic must happen-before the load. Loom checks this
+    /// and panics otherwise, naming the threads making the racing accesses
+    /// and, with `LOOM_LOCATION=1`, their source locations.
+    #[track_caller]
     pub unsafe fn unsync_load(&self) -> *mut T {
         self.0.unsync_load()
     }
 
+    /// Store a value without any synchronization.
+    ///
+    /// This is a plain write of the memory backing the atomic, as done when
+    /// initializing an atomic before it is shared with other threads.
+    ///
+    /// # Safety
+    ///
+    /// All other accesses to the atomic must happen-before the store, and all
+    /// following accesses must happen-after it. Loom checks this and panics
+    /// otherwise, naming the threads making the racing accesses and, with
+    /// `LOOM_LOCATION=1`, their source locations.
+    #[track_caller]
+    pub unsafe fn unsync_store(&self, val: *mut T) {
+        self.0.unsync_store(val)
+    }
+
     /// Get access to a mutable reference to the inner value.
     #[track_caller]
     pub fn with_mut<R>(&mut self, f: impl FnOnce(&mut *mut T) -> R) -> R {
         self.0.with_mut(f)
     }
 
+    /// Hints that this atomic is never accessed concurrently, for
+    /// instance because it is only accessed with a lock held.
+    ///
+    /// Loom stops exploring interleavings at the accesses to the
+    /// atomic, which shrinks the number of executions. Loom still
+    /// checks the hint: it panics if a store is not ordered by
+    /// happens-before with another access to the atomic.
+    #[track_caller]
+    pub fn no_concurrent_access(&self) {
+        self.0.no_concurrent_access()
+    }
+
+    /// Records an operation performed on the atomic by foreign code, see
+    /// [`ExternalOp`].
+    ///
+    /// Returns the value loaded by a `Load`, the previous value for a `Swap`
+    /// and the stored value for a `Store`. Like the equivalent operation, panics
+    /// if the ordering is not valid for it.
+    #[track_caller]
+    pub fn record_external_op(&self, op: ExternalOp<*mut T>, order: Ordering) -> *mut T {
+        self.0.record_external_op(op, order)
+    }
+
     /// Loads a value from the pointer.
     #[track_caller]
     pub fn load(&self, order: Ordering) -> *mut T {
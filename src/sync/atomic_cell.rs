@@ -0,0 +1,208 @@
+use crate::sync::atomic::AtomicUsize;
+use crate::sync::Mutex;
+
+use std::fmt;
+use std::mem;
+use std::sync::atomic::Ordering::SeqCst;
+
+/// Mock implementation of `crossbeam_utils::atomic::AtomicCell`.
+///
+/// All operations are sequentially consistent. When `T` fits in a native
+/// atomic, operations are modeled as atomic operations, otherwise they are
+/// modeled as taking a lock, like `crossbeam` does for large types.
+pub struct AtomicCell<T> {
+    inner: Inner<T>,
+}
+
+enum Inner<T> {
+    /// Every value stored in the cell, the atomic holds the index of the
+    /// current one. Loads are modeled by loading the index.
+    LockFree {
+        index: AtomicUsize,
+        values: std::sync::Mutex<Vec<T>>,
+    },
+
+    Locked(Mutex<T>),
+}
+
+impl<T> AtomicCell<T> {
+    /// Creates a new atomic cell initialized with `val`.
+    #[track_caller]
+    pub fn new(val: T) -> AtomicCell<T> {
+        let inner = if Self::is_lock_free() {
+            Inner::LockFree {
+                index: AtomicUsize::new(0),
+                values: std::sync::Mutex::new(vec![val]),
+            }
+        } else {
+            Inner::Locked(Mutex::new(val))
+        };
+
+        AtomicCell { inner }
+    }
+
+    /// Consumes the atomic and returns the contained value.
+    #[track_caller]
+    pub fn into_inner(self) -> T {
+        match self.inner {
+            Inner::LockFree { mut index, values } => {
+                let index = index.with_mut(|index| *index);
+                values.into_inner().unwrap().swap_remove(index)
+            }
+            Inner::Locked(lock) => lock.into_inner().unwrap(),
+        }
+    }
+
+    /// Returns `true` if operations on values of this type are lock-free.
+    ///
+    /// Lock-free cells are modeled as atomics, others as a lock.
+    pub fn is_lock_free() -> bool {
+        let size = mem::size_of::<T>();
+
+        size == 0
+            || (size <= mem::size_of::<usize>()
+                && size.is_power_of_two()
+                && mem::align_of::<T>() >= size)
+    }
+}
+
+impl<T: Copy> AtomicCell<T> {
+    /// Loads a value from the atomic cell.
+    #[track_caller]
+    pub fn load(&self) -> T {
+        match &self.inner {
+            Inner::LockFree { index, values } => {
+                let index = index.load(SeqCst);
+                values.lock().unwrap()[index]
+            }
+            Inner::Locked(lock) => *lock.lock().unwrap(),
+        }
+    }
+
+    /// Stores `val` into the atomic cell.
+    #[track_caller]
+    pub fn store(&self, val: T) {
+        match &self.inner {
+            Inner::LockFree { index, values } => {
+                let new = push(values, val);
+                index.store(new, SeqCst);
+            }
+            Inner::Locked(lock) => *lock.lock().unwrap() = val,
+        }
+    }
+
+    /// Stores `val` into the atomic cell and returns the previous value.
+    #[track_caller]
+    pub fn swap(&self, val: T) -> T {
+        match &self.inner {
+            Inner::LockFree { index, values } => {
+                let new = push(values, val);
+                let prev = index.swap(new, SeqCst);
+                values.lock().unwrap()[prev]
+            }
+            Inner::Locked(lock) => mem::replace(&mut *lock.lock().unwrap(), val),
+        }
+    }
+}
+
+impl<T: Copy + Default> AtomicCell<T> {
+    /// Takes the value of the atomic cell, leaving `Default::default()` in its
+    /// place.
+    #[track_caller]
+    pub fn take(&self) -> T {
+        self.swap(T::default())
+    }
+}
+
+impl<T: Copy + Eq> AtomicCell<T> {
+    /// If the current value equals `current`, stores `new` into the atomic
+    /// cell.
+    ///
+    /// The return value is a result indicating whether the new value was
+    /// written and containing the previous value. On success this value is
+    /// guaranteed to be equal to `current`.
+    #[track_caller]
+    pub fn compare_exchange(&self, current: T, new: T) -> Result<T, T> {
+        match &self.inner {
+            Inner::LockFree { index, values } => {
+                let new = push(values, new);
+
+                loop {
+                    let prev_index = index.load(SeqCst);
+                    let prev = values.lock().unwrap()[prev_index];
+
+                    if prev != current {
+                        return Err(prev);
+                    }
+
+                    // The value may have been replaced with an equal one in the
+                    // meantime, compare again.
+                    if index
+                        .compare_exchange(prev_index, new, SeqCst, SeqCst)
+                        .is_ok()
+                    {
+                        return Ok(prev);
+                    }
+                }
+            }
+            Inner::Locked(lock) => {
+                let mut value = lock.lock().unwrap();
+
+                if *value == current {
+                    Ok(mem::replace(&mut *value, new))
+                } else {
+                    Err(*value)
+                }
+            }
+        }
+    }
+
+    /// Fetches the value, and applies a function to it that returns an
+    /// optional new value. Returns a `Result` of `Ok(previous_value)` if the
+    /// function returned `Some(_)`, else `Err(previous_value)`.
+    #[track_caller]
+    pub fn fetch_update<F>(&self, mut f: F) -> Result<T, T>
+    where
+        F: FnMut(T) -> Option<T>,
+    {
+        let mut prev = self.load();
+
+        while let Some(next) = f(prev) {
+            match self.compare_exchange(prev, next) {
+                Ok(prev) => return Ok(prev),
+                Err(actual) => prev = actual,
+            }
+        }
+
+        Err(prev)
+    }
+}
+
+/// Adds `val` to the values of a lock-free cell, returning its index.
+fn push<T>(values: &std::sync::Mutex<Vec<T>>, val: T) -> usize {
+    let mut values = values.lock().unwrap();
+    values.push(val);
+    values.len() - 1
+}
+
+impl<T: Default> Default for AtomicCell<T> {
+    #[track_caller]
+    fn default() -> AtomicCell<T> {
+        AtomicCell::new(T::default())
+    }
+}
+
+impl<T> From<T> for AtomicCell<T> {
+    #[track_caller]
+    fn from(val: T) -> AtomicCell<T> {
+        AtomicCell::new(val)
+    }
+}
+
+impl<T: Copy + fmt::Debug> fmt::Debug for AtomicCell<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("AtomicCell")
+            .field("value", &self.load())
+            .finish()
+    }
+}
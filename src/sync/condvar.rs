@@ -8,6 +8,7 @@ use std::time::Duration;
 #[derive(Debug)]
 pub struct Condvar {
     object: rt::Condvar,
+    _owner: rt::object::Owner,
 }
 
 /// A type indicating whether a timed wait on a condition variable returned due
@@ -17,29 +18,39 @@ pub struct WaitTimeoutResult(bool);
 
 impl Condvar {
     /// Creates a new condition variable which is ready to be waited on and notified.
+    #[track_caller]
     pub fn new() -> Condvar {
+        let object = rt::Condvar::new(location!());
+
         Condvar {
-            object: rt::Condvar::new(),
+            object,
+            _owner: object.owner(),
         }
     }
 
     /// Blocks the current thread until this condition variable receives a notification.
+    #[track_caller]
     pub fn wait<'a, T>(&self, mut guard: MutexGuard<'a, T>) -> LockResult<MutexGuard<'a, T>> {
         // Release the RefCell borrow guard allowing another thread to lock the
         // data
         guard.unborrow();
 
         // Wait until notified
-        self.object.wait(guard.rt());
+        self.object.wait(guard.rt(), location!());
 
         // Borrow the mutex guarded data again
         guard.reborrow();
 
-        Ok(guard)
+        if guard.rt().is_poisoned() {
+            Err(PoisonError::new(guard))
+        } else {
+            Ok(guard)
+        }
     }
 
     /// Waits on this condition variable for a notification, timing out after a
     /// specified duration.
+    #[track_caller]
     pub fn wait_timeout<'a, T>(
         &self,
         guard: MutexGuard<'a, T>,
@@ -52,6 +63,9 @@ impl Condvar {
     }
 
     /// Wakes up one blocked thread on this condvar.
+    ///
+    /// Like with `std`, the woken thread is not necessarily the one that
+    /// waited first, loom explores waking each of the waiting threads.
     pub fn notify_one(&self) {
         self.object.notify_one();
     }
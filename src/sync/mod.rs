@@ -2,18 +2,26 @@
 
 mod arc;
 pub mod atomic;
+mod atomic_cell;
 mod barrier;
 mod condvar;
 pub mod mpsc;
 mod mutex;
 mod notify;
+#[cfg(feature = "parking_lot")]
+pub mod parking_lot;
+mod refcount;
 mod rwlock;
+mod semaphore;
 
 pub use self::arc::Arc;
+pub use self::atomic_cell::AtomicCell;
 pub use self::barrier::Barrier;
 pub use self::condvar::{Condvar, WaitTimeoutResult};
 pub use self::mutex::{Mutex, MutexGuard};
 pub use self::notify::Notify;
-pub use self::rwlock::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+pub use self::refcount::RefCount;
+pub use self::rwlock::{RwLock, RwLockReadGuard, RwLockUpgradableReadGuard, RwLockWriteGuard};
+pub use self::semaphore::Semaphore;
 
 pub use std::sync::{LockResult, TryLockResult};
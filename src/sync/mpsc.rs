@@ -2,75 +2,273 @@
 
 use crate::rt;
 
+use std::collections::VecDeque;
+use std::mem;
+
+pub use std::sync::mpsc::{RecvError, RecvTimeoutError, SendError, TryRecvError, TrySendError};
+
 /// Mock implementation of `std::sync::mpsc::channel`.
+#[track_caller]
 pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
-    let (sender_channel, receiver_channel) = std::sync::mpsc::channel();
-    let channel = std::sync::Arc::new(rt::Channel::new());
+    let chan = Chan::new(None, location!());
     let sender = Sender {
-        object: std::sync::Arc::clone(&channel),
-        sender: sender_channel,
+        chan: std::sync::Arc::clone(&chan),
     };
-    let receiver = Receiver {
-        object: std::sync::Arc::clone(&channel),
-        receiver: receiver_channel,
+    let receiver = Receiver { chan };
+    (sender, receiver)
+}
+
+/// Mock implementation of `std::sync::mpsc::sync_channel`.
+///
+/// Sends block while `bound` messages are buffered. A `bound` of zero makes
+/// each send block until the message is received.
+#[track_caller]
+pub fn sync_channel<T>(bound: usize) -> (SyncSender<T>, Receiver<T>) {
+    let chan = Chan::new(Some(bound), location!());
+    let sender = SyncSender {
+        chan: std::sync::Arc::clone(&chan),
     };
+    let receiver = Receiver { chan };
     (sender, receiver)
 }
 
+/// State shared by both halves of a channel.
+#[derive(Debug)]
+struct Chan<T> {
+    object: rt::Channel,
+    rendezvous: bool,
+
+    /// Messages sent but not yet received. The channel is only accessed by
+    /// the thread loom schedules, the lock is never contended.
+    buffer: std::sync::Mutex<VecDeque<T>>,
+}
+
 #[derive(Debug)]
 /// Mock implementation of `std::sync::mpsc::Sender`.
 pub struct Sender<T> {
-    object: std::sync::Arc<rt::Channel>,
-    sender: std::sync::mpsc::Sender<T>,
+    chan: std::sync::Arc<Chan<T>>,
+}
+
+#[derive(Debug)]
+/// Mock implementation of `std::sync::mpsc::SyncSender`.
+pub struct SyncSender<T> {
+    chan: std::sync::Arc<Chan<T>>,
+}
+
+impl<T> Chan<T> {
+    #[track_caller]
+    fn new(capacity: Option<usize>, location: rt::Location) -> std::sync::Arc<Chan<T>> {
+        std::sync::Arc::new(Chan {
+            object: rt::Channel::new(capacity, location),
+            rendezvous: capacity == Some(0),
+            buffer: std::sync::Mutex::new(VecDeque::new()),
+        })
+    }
+
+    fn send(&self, msg: T) -> Result<(), SendError<T>> {
+        if !self.object.send() {
+            return Err(SendError(msg));
+        }
+
+        self.buffer.lock().unwrap().push_back(msg);
+
+        if !self.object.wait_received() {
+            // The receiver was dropped before taking the message, take it
+            // back.
+            let msg = self.buffer.lock().unwrap().pop_back().unwrap();
+            return Err(SendError(msg));
+        }
+
+        Ok(())
+    }
+
+    fn drop_sender(&self) {
+        self.object.drop_sender();
+    }
 }
 
 impl<T> Sender<T> {
     /// Attempts to send a value on this channel, returning it back if it could
     /// not be sent.
-    pub fn send(&self, msg: T) -> Result<(), std::sync::mpsc::SendError<T>> {
-        self.object.send();
-        self.sender.send(msg)
+    pub fn send(&self, msg: T) -> Result<(), SendError<T>> {
+        self.chan.send(msg)
     }
 }
 
 impl<T> Clone for Sender<T> {
     fn clone(&self) -> Sender<T> {
+        self.chan.object.clone_sender();
         Sender {
-            object: std::sync::Arc::clone(&self.object),
-            sender: self.sender.clone(),
+            chan: std::sync::Arc::clone(&self.chan),
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        self.chan.drop_sender();
+    }
+}
+
+impl<T> SyncSender<T> {
+    /// Sends a value on this synchronous channel, blocking while the channel
+    /// is full.
+    ///
+    /// Returns the value back if the receiver has been dropped, including
+    /// while waiting for room in the channel.
+    pub fn send(&self, msg: T) -> Result<(), SendError<T>> {
+        self.chan.send(msg)
+    }
+
+    /// Attempts to send a value on this channel without blocking.
+    ///
+    /// On a channel with a bound of zero, this only succeeds if the receiver
+    /// is currently blocked waiting for a message.
+    pub fn try_send(&self, msg: T) -> Result<(), TrySendError<T>> {
+        match self.chan.object.try_send() {
+            Ok(()) => {
+                self.chan.buffer.lock().unwrap().push_back(msg);
+                Ok(())
+            }
+            Err(rt::TrySendError::Full) => Err(TrySendError::Full(msg)),
+            Err(rt::TrySendError::Disconnected) => Err(TrySendError::Disconnected(msg)),
+        }
+    }
+}
+
+impl<T> Clone for SyncSender<T> {
+    fn clone(&self) -> SyncSender<T> {
+        self.chan.object.clone_sender();
+        SyncSender {
+            chan: std::sync::Arc::clone(&self.chan),
         }
     }
 }
 
+impl<T> Drop for SyncSender<T> {
+    fn drop(&mut self) {
+        self.chan.drop_sender();
+    }
+}
+
 #[derive(Debug)]
 /// Mock implementation of `std::sync::mpsc::Receiver`.
 pub struct Receiver<T> {
-    object: std::sync::Arc<rt::Channel>,
-    receiver: std::sync::mpsc::Receiver<T>,
+    chan: std::sync::Arc<Chan<T>>,
 }
 
 impl<T> Receiver<T> {
     /// Attempts to wait for a value on this receiver, returning an error if the
     /// corresponding channel has hung up.
-    pub fn recv(&self) -> Result<T, std::sync::mpsc::RecvError> {
-        self.object.recv();
-        self.receiver.recv()
+    pub fn recv(&self) -> Result<T, RecvError> {
+        if !self.chan.object.recv() {
+            return Err(RecvError);
+        }
+
+        let msg = self.chan.buffer.lock().unwrap().pop_front().unwrap();
+        Ok(msg)
+    }
+
+    /// Attempts to return a pending value on this receiver without blocking.
+    ///
+    /// Whether a message sent concurrently is already available is explored:
+    /// the call may return `Empty`, then `Disconnected` once all senders are
+    /// dropped.
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        match self.chan.object.try_recv() {
+            Ok(()) => Ok(self.chan.buffer.lock().unwrap().pop_front().unwrap()),
+            Err(rt::TryRecvError::Empty) => Err(TryRecvError::Empty),
+            Err(rt::TryRecvError::Disconnected) => Err(TryRecvError::Disconnected),
+        }
     }
+
+    /// Returns an iterator blocking waiting for messages, ending when the
+    /// channel has hung up.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { rx: self }
+    }
+
+    /// Returns an iterator yielding the messages available without blocking.
+    pub fn try_iter(&self) -> TryIter<'_, T> {
+        TryIter { rx: self }
+    }
+
     /// Attempts to wait for a value on this receiver, returning an error if the
     /// corresponding channel has hung up, or if it waits more than `timeout`.
-    pub fn recv_timeout(
-        &self,
-        _timeout: std::time::Duration,
-    ) -> Result<T, std::sync::mpsc::RecvTimeoutError> {
+    pub fn recv_timeout(&self, _timeout: std::time::Duration) -> Result<T, RecvTimeoutError> {
         unimplemented!("std::sync::mpsc::Receiver::recv_timeout is not supported yet in Loom.")
     }
 }
 
 impl<T> Drop for Receiver<T> {
     fn drop(&mut self) {
-        // Drain the channel.
-        while !self.object.is_empty() {
-            self.recv().unwrap();
+        self.chan.object.drop_receiver();
+
+        // Drop the buffered messages. On a rendezvous channel, the message
+        // belongs to the blocked sender which takes it back.
+        if !self.chan.rendezvous {
+            let msgs = mem::take(&mut *self.chan.buffer.lock().unwrap());
+            drop(msgs);
         }
     }
 }
+
+/// Mock implementation of `std::sync::mpsc::Iter`.
+#[derive(Debug)]
+pub struct Iter<'a, T> {
+    rx: &'a Receiver<T>,
+}
+
+/// Mock implementation of `std::sync::mpsc::TryIter`.
+#[derive(Debug)]
+pub struct TryIter<'a, T> {
+    rx: &'a Receiver<T>,
+}
+
+/// Mock implementation of `std::sync::mpsc::IntoIter`.
+#[derive(Debug)]
+pub struct IntoIter<T> {
+    rx: Receiver<T>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.rx.recv().ok()
+    }
+}
+
+impl<'a, T> Iterator for TryIter<'a, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.rx.try_recv().ok()
+    }
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.rx.recv().ok()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Receiver<T> {
+    type Item = T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T> IntoIterator for Receiver<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter { rx: self }
+    }
+}
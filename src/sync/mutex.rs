@@ -1,13 +1,14 @@
 use crate::rt;
 
 use std::ops;
-use std::sync::{LockResult, TryLockError, TryLockResult};
+use std::sync::{LockResult, PoisonError, TryLockError, TryLockResult};
 
 /// Mock implementation of `std::sync::Mutex`.
 #[derive(Debug)]
 pub struct Mutex<T> {
     object: rt::Mutex,
     data: std::sync::Mutex<T>,
+    _owner: rt::object::Owner,
 }
 
 /// Mock implementation of `std::sync::MutexGuard`.
@@ -19,23 +20,41 @@ pub struct MutexGuard<'a, T> {
 
 impl<T> Mutex<T> {
     /// Creates a new mutex in an unlocked state ready for use.
+    #[track_caller]
     pub fn new(data: T) -> Mutex<T> {
+        let object = rt::Mutex::new(true, location!());
+
         Mutex {
             data: std::sync::Mutex::new(data),
-            object: rt::Mutex::new(true),
+            object,
+            _owner: object.owner(),
         }
     }
 }
 
 impl<T> Mutex<T> {
     /// Acquires a mutex, blocking the current thread until it is able to do so.
+    ///
+    /// Like with `std`, the lock is not fair: a thread arriving after the lock
+    /// was released may acquire it ahead of threads that were waiting, loom
+    /// explores both.
+    ///
+    /// If another thread panicked while holding the mutex, the mutex is
+    /// poisoned and the guard is returned in the error.
+    #[track_caller]
     pub fn lock(&self) -> LockResult<MutexGuard<'_, T>> {
-        self.object.acquire_lock();
+        self.object.acquire_lock(location!());
 
-        Ok(MutexGuard {
+        let guard = MutexGuard {
             lock: self,
-            data: Some(self.data.lock().unwrap()),
-        })
+            data: Some(self.lock_data()),
+        };
+
+        if self.object.is_poisoned() {
+            Err(PoisonError::new(guard))
+        } else {
+            Ok(guard)
+        }
     }
 
     /// Attempts to acquire this lock.
@@ -45,16 +64,54 @@ impl<T> Mutex<T> {
     /// guard is dropped.
     ///
     /// This function does not block.
+    #[track_caller]
     pub fn try_lock(&self) -> TryLockResult<MutexGuard<'_, T>> {
-        if self.object.try_acquire_lock() {
-            Ok(MutexGuard {
+        if self.object.try_acquire_lock(location!()) {
+            let guard = MutexGuard {
                 lock: self,
-                data: Some(self.data.lock().unwrap()),
-            })
+                data: Some(self.lock_data()),
+            };
+
+            if self.object.is_poisoned() {
+                Err(TryLockError::Poisoned(PoisonError::new(guard)))
+            } else {
+                Ok(guard)
+            }
         } else {
             Err(TryLockError::WouldBlock)
         }
     }
+
+    /// Determines whether the mutex is poisoned.
+    pub fn is_poisoned(&self) -> bool {
+        self.object.is_poisoned()
+    }
+
+    /// Clear the poisoned state from a mutex.
+    pub fn clear_poison(&self) {
+        self.object.clear_poison();
+    }
+
+    /// Consumes this mutex, returning the underlying data.
+    pub fn into_inner(self) -> LockResult<T> {
+        let poisoned = self.object.is_poisoned();
+        let data = self
+            .data
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        if poisoned {
+            Err(PoisonError::new(data))
+        } else {
+            Ok(data)
+        }
+    }
+
+    /// The `std` mutex is poisoned along with the loom one, poisoning is
+    /// tracked by the loom mutex.
+    fn lock_data(&self) -> std::sync::MutexGuard<'_, T> {
+        self.data.lock().unwrap_or_else(PoisonError::into_inner)
+    }
 }
 
 impl<T: ?Sized + Default> Default for Mutex<T> {
@@ -78,7 +135,7 @@ impl<'a, T: 'a> MutexGuard<'a, T> {
     }
 
     pub(super) fn reborrow(&mut self) {
-        self.data = Some(self.lock.data.lock().unwrap());
+        self.data = Some(self.lock.lock_data());
     }
 
     pub(super) fn rt(&self) -> &rt::Mutex {
@@ -103,6 +160,11 @@ impl<'a, T> ops::DerefMut for MutexGuard<'a, T> {
 impl<'a, T: 'a> Drop for MutexGuard<'a, T> {
     fn drop(&mut self) {
         self.data = None;
+
+        if rt::panicking() {
+            self.lock.object.poison();
+        }
+
         self.lock.object.release_lock();
     }
 }
@@ -19,9 +19,10 @@ pub struct Notify {
 
 impl Notify {
     /// Create a new `Notify`.
+    #[track_caller]
     pub fn new() -> Notify {
         Notify {
-            object: rt::Notify::new(false, true),
+            object: rt::Notify::new(false, true, location!()),
             waiting: AtomicBool::new(false),
         }
     }
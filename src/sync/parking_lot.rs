@@ -0,0 +1,256 @@
+//! Mock implementation of the `parking_lot` synchronization primitives.
+//!
+//! The types mirror the `parking_lot` API: locks are not poisoned and
+//! acquiring them returns the guard directly. This allows switching a crate
+//! using `parking_lot` to loom with a single `cfg`:
+//!
+//! ```
+//! #[cfg(loom)]
+//! use loom::sync::parking_lot::Mutex;
+//! # /*
+//! #[cfg(not(loom))]
+//! use parking_lot::Mutex;
+//! # */
+//! ```
+//!
+//! Lock guards are shared with [`loom::sync`](crate::sync), upgrading and
+//! downgrading an `RwLock` guard is done with the associated functions of
+//! [`RwLockUpgradableReadGuard`] and [`RwLockWriteGuard`].
+
+use crate::rt;
+use crate::sync;
+
+use std::sync::{PoisonError, TryLockError, TryLockResult};
+use std::time::Duration;
+
+pub use crate::sync::{MutexGuard, RwLockReadGuard, RwLockUpgradableReadGuard, RwLockWriteGuard};
+
+/// Mock implementation of `parking_lot::Mutex`.
+#[derive(Debug)]
+pub struct Mutex<T> {
+    inner: sync::Mutex<T>,
+}
+
+/// Mock implementation of `parking_lot::RwLock`.
+#[derive(Debug)]
+pub struct RwLock<T> {
+    inner: sync::RwLock<T>,
+}
+
+/// Mock implementation of `parking_lot::Condvar`.
+#[derive(Debug)]
+pub struct Condvar {
+    object: rt::Condvar,
+}
+
+/// A type indicating whether a timed wait on a condition variable returned due
+/// to a time out or not.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WaitTimeoutResult(bool);
+
+impl<T> Mutex<T> {
+    /// Creates a new mutex in an unlocked state ready for use.
+    #[track_caller]
+    pub fn new(data: T) -> Mutex<T> {
+        Mutex {
+            inner: sync::Mutex::new(data),
+        }
+    }
+
+    /// Acquires a mutex, blocking the current thread until it is able to do so.
+    #[track_caller]
+    pub fn lock(&self) -> MutexGuard<'_, T> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Attempts to acquire this lock.
+    ///
+    /// If the lock could not be acquired at this time, then `None` is returned.
+    ///
+    /// This function does not block.
+    #[track_caller]
+    pub fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
+        ignore_poison(self.inner.try_lock())
+    }
+
+    /// Consumes this mutex, returning the underlying data.
+    pub fn into_inner(self) -> T {
+        self.inner
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<T: Default> Default for Mutex<T> {
+    #[track_caller]
+    fn default() -> Self {
+        Self::new(Default::default())
+    }
+}
+
+impl<T> From<T> for Mutex<T> {
+    #[track_caller]
+    fn from(t: T) -> Self {
+        Self::new(t)
+    }
+}
+
+impl<T> RwLock<T> {
+    /// Creates a new rwlock in an unlocked state ready for use.
+    #[track_caller]
+    pub fn new(data: T) -> RwLock<T> {
+        RwLock {
+            inner: sync::RwLock::new(data),
+        }
+    }
+
+    /// Locks this rwlock with shared read access, blocking the current
+    /// thread until it can be acquired.
+    #[track_caller]
+    pub fn read(&self) -> RwLockReadGuard<'_, T> {
+        self.inner.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Attempts to acquire this rwlock with shared read access.
+    ///
+    /// This function does not block.
+    #[track_caller]
+    pub fn try_read(&self) -> Option<RwLockReadGuard<'_, T>> {
+        ignore_poison(self.inner.try_read())
+    }
+
+    /// Locks this rwlock with exclusive write access, blocking the current
+    /// thread until it can be acquired.
+    #[track_caller]
+    pub fn write(&self) -> RwLockWriteGuard<'_, T> {
+        self.inner.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Attempts to lock this rwlock with exclusive write access.
+    ///
+    /// This function does not block.
+    #[track_caller]
+    pub fn try_write(&self) -> Option<RwLockWriteGuard<'_, T>> {
+        ignore_poison(self.inner.try_write())
+    }
+
+    /// Locks this rwlock with upgradable read access, blocking the current
+    /// thread until it can be acquired.
+    #[track_caller]
+    pub fn upgradable_read(&self) -> RwLockUpgradableReadGuard<'_, T> {
+        self.inner
+            .upgradable_read()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Attempts to acquire this rwlock with upgradable read access.
+    ///
+    /// This function does not block.
+    #[track_caller]
+    pub fn try_upgradable_read(&self) -> Option<RwLockUpgradableReadGuard<'_, T>> {
+        ignore_poison(self.inner.try_upgradable_read())
+    }
+}
+
+impl<T: Default> Default for RwLock<T> {
+    #[track_caller]
+    fn default() -> Self {
+        Self::new(Default::default())
+    }
+}
+
+impl<T> From<T> for RwLock<T> {
+    #[track_caller]
+    fn from(t: T) -> Self {
+        Self::new(t)
+    }
+}
+
+impl Condvar {
+    /// Creates a new condition variable which is ready to be waited on and notified.
+    #[track_caller]
+    pub fn new() -> Condvar {
+        Condvar {
+            object: rt::Condvar::new(location!()),
+        }
+    }
+
+    /// Blocks the current thread until this condition variable receives a
+    /// notification.
+    ///
+    /// The mutex is unlocked while waiting and locked again before returning.
+    #[track_caller]
+    pub fn wait<T>(&self, guard: &mut MutexGuard<'_, T>) {
+        // Release the RefCell borrow guard allowing another thread to lock the
+        // data
+        guard.unborrow();
+
+        // Wait until notified
+        self.object.wait(guard.rt(), location!());
+
+        // Borrow the mutex guarded data again
+        guard.reborrow();
+    }
+
+    /// Blocks the current thread until this condition variable receives a
+    /// notification and `condition` returns `false`.
+    #[track_caller]
+    pub fn wait_while<T, F>(&self, guard: &mut MutexGuard<'_, T>, mut condition: F)
+    where
+        F: FnMut(&mut T) -> bool,
+    {
+        while condition(&mut *guard) {
+            self.wait(guard);
+        }
+    }
+
+    /// Waits on this condition variable for a notification, timing out after
+    /// the specified duration.
+    ///
+    /// Time is not modeled, the wait never times out.
+    #[track_caller]
+    pub fn wait_for<T>(
+        &self,
+        guard: &mut MutexGuard<'_, T>,
+        _timeout: Duration,
+    ) -> WaitTimeoutResult {
+        self.wait(guard);
+        WaitTimeoutResult(false)
+    }
+
+    /// Wakes up one blocked thread on this condvar.
+    ///
+    /// Returns whether a thread was woken up.
+    pub fn notify_one(&self) -> bool {
+        self.object.notify_one()
+    }
+
+    /// Wakes up all blocked threads on this condvar.
+    ///
+    /// Returns the number of threads woken up.
+    pub fn notify_all(&self) -> usize {
+        self.object.notify_all()
+    }
+}
+
+impl Default for Condvar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WaitTimeoutResult {
+    /// Returns `true` if the wait was known to have timed out.
+    pub fn timed_out(self) -> bool {
+        self.0
+    }
+}
+
+/// `parking_lot` locks are not poisoned, a panic while holding one is ignored.
+fn ignore_poison<G>(res: TryLockResult<G>) -> Option<G> {
+    match res {
+        Ok(guard) => Some(guard),
+        Err(TryLockError::Poisoned(err)) => Some(err.into_inner()),
+        Err(TryLockError::WouldBlock) => None,
+    }
+}
@@ -0,0 +1,100 @@
+use crate::rt;
+
+/// Checks a reference count implemented with atomics.
+///
+/// Reference counted types, such as `Arc`, release the shared value once the
+/// last reference is dropped. The thread freeing the value must synchronize
+/// with every thread that dropped a reference before, typically with a
+/// `Release` decrement followed by an `Acquire` fence. Getting these orderings
+/// wrong is a classic bug.
+///
+/// A `RefCount` lets a type implementing its own reference count opt into the
+/// checks loom performs for its [`Arc`](crate::sync::Arc). It does not
+/// synchronize anything itself, it records the references and panics if:
+///
+/// - the value is freed while a drop of a reference does not happen before
+///   it,
+/// - the value is freed with outstanding references, or freed twice,
+/// - references or the value are leaked at the end of the execution.
+///
+/// # Examples
+///
+/// ```
+/// use loom::sync::atomic::{fence, AtomicUsize};
+/// use loom::sync::RefCount;
+/// use loom::thread;
+///
+/// use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
+/// use std::sync::Arc;
+///
+/// struct Shared {
+///     refs: AtomicUsize,
+///     check: RefCount,
+/// }
+///
+/// fn release(shared: &Shared) {
+///     shared.check.dec();
+///
+///     if shared.refs.fetch_sub(1, Release) == 1 {
+///         fence(Acquire);
+///         shared.check.free();
+///     }
+/// }
+///
+/// loom::model(|| {
+///     let shared = Arc::new(Shared {
+///         refs: AtomicUsize::new(1),
+///         check: RefCount::new(),
+///     });
+///
+///     shared.refs.fetch_add(1, Relaxed);
+///     shared.check.inc();
+///
+///     let th = {
+///         let shared = shared.clone();
+///         thread::spawn(move || release(&shared))
+///     };
+///
+///     release(&shared);
+///     th.join().unwrap();
+/// });
+/// ```
+#[derive(Debug)]
+pub struct RefCount {
+    obj: rt::Arc,
+}
+
+impl RefCount {
+    /// Creates a reference count holding one reference.
+    #[track_caller]
+    pub fn new() -> RefCount {
+        RefCount {
+            obj: rt::Arc::new_checked("RefCount", location!()),
+        }
+    }
+
+    /// Records a new reference, when incrementing the reference count.
+    pub fn inc(&self) {
+        self.obj.checked_ref_inc();
+    }
+
+    /// Records dropping a reference. Call it before decrementing the
+    /// reference count, once the reference is no longer used.
+    #[track_caller]
+    pub fn dec(&self) {
+        self.obj.checked_ref_dec(location!());
+    }
+
+    /// Records freeing the shared value, after the last reference is dropped.
+    #[track_caller]
+    pub fn free(&self) {
+        self.obj.checked_free(location!());
+    }
+}
+
+impl Default for RefCount {
+    #[track_caller]
+    fn default() -> RefCount {
+        RefCount::new()
+    }
+}
@@ -1,13 +1,15 @@
 use crate::rt;
 
+use std::mem;
 use std::ops;
-use std::sync::{LockResult, TryLockError, TryLockResult};
+use std::sync::{LockResult, PoisonError, TryLockError, TryLockResult};
 
 /// Mock implementatoin of `std::sync::RwLock`
 #[derive(Debug)]
 pub struct RwLock<T> {
     object: rt::RwLock,
     data: std::sync::RwLock<T>,
+    _owner: rt::object::Owner,
 }
 
 /// Mock implementation of `std::sync::RwLockReadGuard`
@@ -26,12 +28,24 @@ pub struct RwLockWriteGuard<'a, T> {
     data: Option<std::sync::RwLockWriteGuard<'a, T>>,
 }
 
+/// RAII structure used to release the upgradable read access of a lock when
+/// dropped, modeled after `parking_lot::RwLockUpgradableReadGuard`.
+#[derive(Debug)]
+pub struct RwLockUpgradableReadGuard<'a, T> {
+    lock: &'a RwLock<T>,
+    data: Option<std::sync::RwLockReadGuard<'a, T>>,
+}
+
 impl<T> RwLock<T> {
     /// Creates a new rwlock in an unlocked state ready for use.
+    #[track_caller]
     pub fn new(data: T) -> RwLock<T> {
+        let object = rt::RwLock::new(location!());
+
         RwLock {
             data: std::sync::RwLock::new(data),
-            object: rt::RwLock::new(),
+            object,
+            _owner: object.owner(),
         }
     }
 
@@ -43,12 +57,13 @@ impl<T> RwLock<T> {
     /// lock when this method returns. This method does not provide any
     /// guarantees with respect to the ordering of whether contentious readers
     /// or writers will acquire the lock first.
+    #[track_caller]
     pub fn read(&self) -> LockResult<RwLockReadGuard<'_, T>> {
-        self.object.acquire_read_lock();
+        self.object.acquire_read_lock(location!());
 
-        Ok(RwLockReadGuard {
+        self.check_poison(RwLockReadGuard {
             lock: self,
-            data: Some(self.data.try_read().expect("loom::RwLock state corrupt")),
+            data: Some(self.read_data()),
         })
     }
 
@@ -59,12 +74,14 @@ impl<T> RwLock<T> {
     /// access when it is dropped.
     ///
     /// This function does not block.
+    #[track_caller]
     pub fn try_read(&self) -> TryLockResult<RwLockReadGuard<'_, T>> {
-        if self.object.try_acquire_read_lock() {
-            Ok(RwLockReadGuard {
+        if self.object.try_acquire_read_lock(location!()) {
+            self.check_poison(RwLockReadGuard {
                 lock: self,
-                data: Some(self.data.try_read().expect("loom::RwLock state corrupt")),
+                data: Some(self.read_data()),
             })
+            .map_err(TryLockError::Poisoned)
         } else {
             Err(TryLockError::WouldBlock)
         }
@@ -75,12 +92,13 @@ impl<T> RwLock<T> {
     ///
     /// This function will not return while other writers or other readers
     /// currently have access to the lock.
+    #[track_caller]
     pub fn write(&self) -> LockResult<RwLockWriteGuard<'_, T>> {
-        self.object.acquire_write_lock();
+        self.object.acquire_write_lock(location!());
 
-        Ok(RwLockWriteGuard {
+        self.check_poison(RwLockWriteGuard {
             lock: self,
-            data: Some(self.data.try_write().expect("loom::RwLock state corrupt")),
+            data: Some(self.write_data()),
         })
     }
 
@@ -91,20 +109,103 @@ impl<T> RwLock<T> {
     /// it is dropped.
     ///
     /// This function does not block.
+    #[track_caller]
     pub fn try_write(&self) -> TryLockResult<RwLockWriteGuard<'_, T>> {
-        if self.object.try_acquire_write_lock() {
-            Ok(RwLockWriteGuard {
+        if self.object.try_acquire_write_lock(location!()) {
+            self.check_poison(RwLockWriteGuard {
                 lock: self,
-                data: Some(self.data.try_write().expect("loom::RwLock state corrupt")),
+                data: Some(self.write_data()),
             })
+            .map_err(TryLockError::Poisoned)
         } else {
             Err(TryLockError::WouldBlock)
         }
     }
 
+    /// Locks this rwlock with upgradable read access, blocking the current
+    /// thread until it can be acquired.
+    ///
+    /// Only one thread at a time may hold upgradable read access, though it
+    /// may share the lock with other readers. The guard can later be upgraded
+    /// to exclusive write access with [`RwLockUpgradableReadGuard::upgrade`].
+    #[track_caller]
+    pub fn upgradable_read(&self) -> LockResult<RwLockUpgradableReadGuard<'_, T>> {
+        self.object.acquire_upgradable_read_lock(location!());
+
+        self.check_poison(RwLockUpgradableReadGuard {
+            lock: self,
+            data: Some(self.read_data()),
+        })
+    }
+
+    /// Attempts to acquire this rwlock with upgradable read access.
+    ///
+    /// This function does not block.
+    #[track_caller]
+    pub fn try_upgradable_read(&self) -> TryLockResult<RwLockUpgradableReadGuard<'_, T>> {
+        if self.object.try_acquire_upgradable_read_lock(location!()) {
+            self.check_poison(RwLockUpgradableReadGuard {
+                lock: self,
+                data: Some(self.read_data()),
+            })
+            .map_err(TryLockError::Poisoned)
+        } else {
+            Err(TryLockError::WouldBlock)
+        }
+    }
+
+    /// Determines whether the lock is poisoned, which happens when a thread
+    /// panics while holding write access.
+    pub fn is_poisoned(&self) -> bool {
+        self.object.is_poisoned()
+    }
+
+    /// Clear the poisoned state from a lock.
+    pub fn clear_poison(&self) {
+        self.object.clear_poison();
+    }
+
     /// Consumes this `RwLock`, returning the underlying data.
     pub fn into_inner(self) -> LockResult<T> {
-        unimplemented!()
+        let poisoned = self.object.is_poisoned();
+        let data = self
+            .data
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        if poisoned {
+            Err(PoisonError::new(data))
+        } else {
+            Ok(data)
+        }
+    }
+
+    /// Returns the guard in an error if the lock is poisoned.
+    fn check_poison<G>(&self, guard: G) -> LockResult<G> {
+        if self.object.is_poisoned() {
+            Err(PoisonError::new(guard))
+        } else {
+            Ok(guard)
+        }
+    }
+
+    // The `std` lock is poisoned along with the loom one, poisoning is tracked
+    // by the loom lock.
+
+    fn read_data(&self) -> std::sync::RwLockReadGuard<'_, T> {
+        match self.data.try_read() {
+            Ok(data) => data,
+            Err(TryLockError::Poisoned(err)) => err.into_inner(),
+            Err(TryLockError::WouldBlock) => panic!("loom::RwLock state corrupt"),
+        }
+    }
+
+    fn write_data(&self) -> std::sync::RwLockWriteGuard<'_, T> {
+        match self.data.try_write() {
+            Ok(data) => data,
+            Err(TryLockError::Poisoned(err)) => err.into_inner(),
+            Err(TryLockError::WouldBlock) => panic!("loom::RwLock state corrupt"),
+        }
     }
 }
 
@@ -152,9 +253,117 @@ impl<'a, T> ops::DerefMut for RwLockWriteGuard<'a, T> {
     }
 }
 
+impl<'a, T> RwLockWriteGuard<'a, T> {
+    /// Atomically downgrades a write lock into a read lock without allowing
+    /// any writers to take exclusive access of the lock in the meantime.
+    pub fn downgrade(mut s: Self) -> RwLockReadGuard<'a, T> {
+        let lock = s.lock;
+        s.data = None;
+        mem::forget(s);
+
+        lock.object.downgrade(false);
+
+        RwLockReadGuard {
+            lock,
+            data: Some(lock.read_data()),
+        }
+    }
+
+    /// Atomically downgrades a write lock into an upgradable read lock
+    /// without allowing any writers to take exclusive access of the lock in
+    /// the meantime.
+    pub fn downgrade_to_upgradable(mut s: Self) -> RwLockUpgradableReadGuard<'a, T> {
+        let lock = s.lock;
+        s.data = None;
+        mem::forget(s);
+
+        lock.object.downgrade(true);
+
+        RwLockUpgradableReadGuard {
+            lock,
+            data: Some(lock.read_data()),
+        }
+    }
+}
+
 impl<'a, T: 'a> Drop for RwLockWriteGuard<'a, T> {
     fn drop(&mut self) {
         self.data = None;
+
+        if rt::panicking() {
+            self.lock.object.poison();
+        }
+
         self.lock.object.release_write_lock()
     }
 }
+
+impl<'a, T> RwLockUpgradableReadGuard<'a, T> {
+    /// Atomically upgrades an upgradable read lock into an exclusive write
+    /// lock, blocking the current thread until the other readers release the
+    /// lock.
+    #[track_caller]
+    pub fn upgrade(mut s: Self) -> RwLockWriteGuard<'a, T> {
+        let lock = s.lock;
+        s.data = None;
+        mem::forget(s);
+
+        lock.object.upgrade(location!());
+
+        RwLockWriteGuard {
+            lock,
+            data: Some(lock.write_data()),
+        }
+    }
+
+    /// Tries to atomically upgrade an upgradable read lock into an exclusive
+    /// write lock.
+    ///
+    /// If the access could not be granted at this time, then the current
+    /// guard is returned.
+    #[track_caller]
+    pub fn try_upgrade(mut s: Self) -> Result<RwLockWriteGuard<'a, T>, Self> {
+        let lock = s.lock;
+        s.data = None;
+
+        if lock.object.try_upgrade(location!()) {
+            mem::forget(s);
+
+            Ok(RwLockWriteGuard {
+                lock,
+                data: Some(lock.write_data()),
+            })
+        } else {
+            s.data = Some(lock.read_data());
+            Err(s)
+        }
+    }
+
+    /// Atomically downgrades an upgradable read lock into a shared read lock
+    /// without allowing any writers to take exclusive access of the lock in
+    /// the meantime.
+    pub fn downgrade(mut s: Self) -> RwLockReadGuard<'a, T> {
+        let lock = s.lock;
+        let data = s.data.take();
+        mem::forget(s);
+
+        lock.object.downgrade(false);
+
+        RwLockReadGuard { lock, data }
+    }
+}
+
+impl<'a, T> ops::Deref for RwLockUpgradableReadGuard<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.data.as_ref().unwrap().deref()
+    }
+}
+
+impl<'a, T: 'a> Drop for RwLockUpgradableReadGuard<'a, T> {
+    fn drop(&mut self) {
+        self.data = None;
+        self.lock.object.release_upgradable_read_lock()
+    }
+}
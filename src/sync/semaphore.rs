@@ -0,0 +1,64 @@
+use crate::rt;
+
+/// A counting semaphore.
+///
+/// This type is not part of `std`. It is provided so that code built on a
+/// semaphore, such as bounded queues, can be checked without reimplementing the
+/// semaphore on top of `Mutex` and `Condvar`.
+///
+/// Releasing permits establishes a release / acquire synchronization point
+/// with acquiring them.
+#[derive(Debug)]
+pub struct Semaphore {
+    object: rt::Semaphore,
+}
+
+impl Semaphore {
+    /// Creates a new semaphore with the given number of permits.
+    #[track_caller]
+    pub fn new(permits: usize) -> Semaphore {
+        Semaphore {
+            object: rt::Semaphore::new(permits, location!()),
+        }
+    }
+
+    /// Acquires a permit, blocking the current thread until one is available.
+    pub fn acquire(&self) {
+        self.object.acquire(1);
+    }
+
+    /// Acquires `n` permits, blocking the current thread until they are all
+    /// available.
+    pub fn acquire_many(&self, n: usize) {
+        self.object.acquire(n);
+    }
+
+    /// Attempts to acquire a permit without blocking.
+    ///
+    /// Returns `true` if the permit was acquired.
+    pub fn try_acquire(&self) -> bool {
+        self.object.try_acquire(1)
+    }
+
+    /// Attempts to acquire `n` permits without blocking.
+    ///
+    /// Returns `true` if the permits were acquired.
+    pub fn try_acquire_many(&self, n: usize) -> bool {
+        self.object.try_acquire(n)
+    }
+
+    /// Releases a permit, waking threads waiting to acquire it.
+    pub fn release(&self) {
+        self.object.release(1);
+    }
+
+    /// Releases `n` permits, waking threads waiting to acquire them.
+    pub fn release_many(&self, n: usize) {
+        self.object.release(n);
+    }
+
+    /// Returns the number of permits currently available.
+    pub fn available_permits(&self) -> usize {
+        self.object.available_permits()
+    }
+}
@@ -6,13 +6,17 @@ use crate::rt::{self, Execution};
 
 pub use std::thread::panicking;
 
+use std::cell::{Cell, RefCell};
 use std::marker::PhantomData;
+use std::panic::{self, AssertUnwindSafe};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use std::{fmt, io};
 
 /// Mock implementation of `std::thread::JoinHandle`.
 pub struct JoinHandle<T> {
-    result: Arc<Mutex<Option<std::thread::Result<T>>>>,
+    /// Value returned by the thread, `None` if it panicked.
+    result: Arc<Mutex<Option<T>>>,
     notify: rt::Notify,
     thread: Thread,
 }
@@ -68,7 +72,7 @@ pub struct Builder {
     name: Option<String>,
 }
 
-static CURRENT_THREAD_KEY: LocalKey<Thread> = LocalKey {
+pub(crate) static CURRENT_THREAD_KEY: LocalKey<Thread> = LocalKey {
     init: || unreachable!(),
     _p: PhantomData,
 };
@@ -105,6 +109,11 @@ pub fn current() -> Thread {
 ///
 /// Note that you may only have [`MAX_THREADS`](crate::MAX_THREADS) threads in a given loom tests
 /// _including_ the main thread.
+///
+/// Like with `std`, a panic in the spawned thread is returned by
+/// [`JoinHandle::join`]. The model fails if the thread is never joined, or if
+/// the panic was raised by loom, for example on detecting a data race.
+#[track_caller]
 pub fn spawn<F, T>(f: F) -> JoinHandle<T>
 where
     F: FnOnce() -> T,
@@ -114,6 +123,16 @@ where
     spawn_internal(f, None)
 }
 
+/// Mock implementation of `std::thread::sleep`.
+///
+/// Advances the [virtual clock](crate::time) by `dur`, then yields so that
+/// other threads may run before the sleeping thread continues.
+pub fn sleep(dur: Duration) {
+    rt::time::advance(dur);
+    rt::yield_now();
+}
+
+#[track_caller]
 fn spawn_internal<F, T>(f: F, name: Option<String>) -> JoinHandle<T>
 where
     F: FnOnce() -> T,
@@ -121,21 +140,37 @@ where
     T: 'static,
 {
     let result = Arc::new(Mutex::new(None));
-    let notify = rt::Notify::new(true, false);
+    let location = location!();
+    let notify = rt::Notify::new(true, false, location);
 
     let id = {
         let name = name.clone();
         let result = result.clone();
-        rt::spawn(move || {
-            rt::execution(|execution| {
-                init_current(execution, name);
-            });
+        rt::spawn(
+            move || {
+                rt::execution(|execution| {
+                    init_current(execution, name);
+                });
 
-            *result.lock().unwrap() = Some(Ok(f()));
-            notify.notify();
-        })
+                // Only the closure may be killed by an injected failure, the
+                // thread still notifies the joining thread.
+                rt::set_killable(true);
+                let res = panic::catch_unwind(AssertUnwindSafe(f));
+                rt::set_killable(false);
+
+                match res {
+                    Ok(value) => *result.lock().unwrap() = Some(value),
+                    Err(panic) => rt::thread_panicked(panic),
+                }
+
+                notify.notify();
+            },
+            location,
+        )
     };
 
+    notify.set_notifier(id);
+
     JoinHandle {
         result,
         notify,
@@ -162,27 +197,50 @@ impl Builder {
     }
 
     /// Sets the size of the stack (in bytes) for the new thread.
+    ///
+    /// Loom threads run on stacks sized by the
+    /// [`Runner`](crate::model::Runner) of the model, the size is ignored.
     pub fn stack_size(self, _size: usize) -> Builder {
         self
     }
 
     /// Spawns a new thread by taking ownership of the `Builder`, and returns an
     /// `io::Result` to its `JoinHandle`.
+    ///
+    /// With [`model::Builder::spawn_failures`](crate::model::Builder::spawn_failures),
+    /// loom also explores executions where the thread fails to spawn and an
+    /// error is returned.
+    #[track_caller]
     pub fn spawn<F, T>(self, f: F) -> io::Result<JoinHandle<T>>
     where
         F: FnOnce() -> T,
         F: Send + 'static,
         T: Send + 'static,
     {
+        if rt::spawn_fails() {
+            // `io::Error::other` requires Rust 1.74.
+            #[allow(clippy::io_other_error)]
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "failed to spawn thread (injected by loom)",
+            ));
+        }
+
         Ok(spawn_internal(f, self.name))
     }
 }
 
 impl<T> JoinHandle<T> {
     /// Waits for the associated thread to finish.
+    ///
+    /// Returns the payload of the panic if the thread panicked.
     pub fn join(self) -> std::thread::Result<T> {
         self.notify.wait();
-        self.result.lock().unwrap().take().unwrap()
+
+        match self.result.lock().unwrap().take() {
+            Some(value) => Ok(value),
+            None => Err(rt::take_panic(self.thread.id.id).expect("thread did not complete")),
+        }
     }
 
     /// Gets a handle to the underlying [`Thread`]
@@ -218,7 +276,7 @@ impl<T: 'static> LocalKey<T> {
     where
         F: FnOnce(&T) -> R,
     {
-        let value = match unsafe { self.get() } {
+        let value = match unsafe { self.local() } {
             Some(v) => v?,
             None => {
                 // Init the value out of the `rt::execution`
@@ -228,13 +286,13 @@ impl<T: 'static> LocalKey<T> {
                     execution.threads.local_init(self, value);
                 });
 
-                unsafe { self.get() }.expect("bug")?
+                unsafe { self.local() }.expect("bug")?
             }
         };
         Ok(f(value))
     }
 
-    unsafe fn get(&'static self) -> Option<Result<&T, AccessError>> {
+    unsafe fn local(&'static self) -> Option<Result<&T, AccessError>> {
         unsafe fn transmute_lt<'a, 'b, T>(t: &'a T) -> &'b T {
             std::mem::transmute::<&'a T, &'b T>(t)
         }
@@ -259,6 +317,105 @@ impl<T: 'static> LocalKey<T> {
     }
 }
 
+impl<T: 'static> LocalKey<T> {
+    /// Initializes the thread local with `init` if it is not yet, then calls
+    /// `f` with `init` if it was not used and the value.
+    fn initialize_with<F, R>(&'static self, init: T, f: F) -> R
+    where
+        F: FnOnce(Option<T>, &T) -> R,
+    {
+        let mut init = Some(init);
+
+        let value = match unsafe { self.local() } {
+            Some(v) => v,
+            None => {
+                let value = init.take().unwrap();
+
+                rt::execution(|execution| {
+                    execution.threads.local_init(self, value);
+                });
+
+                unsafe { self.local() }.expect("bug")
+            }
+        }
+        .expect("cannot access a (mock) TLS value during or after it is destroyed");
+
+        f(init, value)
+    }
+}
+
+impl<T: 'static> LocalKey<Cell<T>> {
+    /// Mock implementation of `std::thread::LocalKey::<Cell<T>>::set`.
+    pub fn set(&'static self, value: T) {
+        self.initialize_with(Cell::new(value), |value, cell| {
+            if let Some(value) = value {
+                cell.set(value.into_inner());
+            }
+        })
+    }
+
+    /// Mock implementation of `std::thread::LocalKey::<Cell<T>>::get`.
+    pub fn get(&'static self) -> T
+    where
+        T: Copy,
+    {
+        self.with(Cell::get)
+    }
+
+    /// Mock implementation of `std::thread::LocalKey::<Cell<T>>::take`.
+    pub fn take(&'static self) -> T
+    where
+        T: Default,
+    {
+        self.with(Cell::take)
+    }
+
+    /// Mock implementation of `std::thread::LocalKey::<Cell<T>>::replace`.
+    pub fn replace(&'static self, value: T) -> T {
+        self.with(|cell| cell.replace(value))
+    }
+}
+
+impl<T: 'static> LocalKey<RefCell<T>> {
+    /// Mock implementation of `std::thread::LocalKey::<RefCell<T>>::with_borrow`.
+    pub fn with_borrow<F, R>(&'static self, f: F) -> R
+    where
+        F: FnOnce(&T) -> R,
+    {
+        self.with(|cell| f(&cell.borrow()))
+    }
+
+    /// Mock implementation of `std::thread::LocalKey::<RefCell<T>>::with_borrow_mut`.
+    pub fn with_borrow_mut<F, R>(&'static self, f: F) -> R
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        self.with(|cell| f(&mut cell.borrow_mut()))
+    }
+
+    /// Mock implementation of `std::thread::LocalKey::<RefCell<T>>::set`.
+    pub fn set(&'static self, value: T) {
+        self.initialize_with(RefCell::new(value), |value, cell| {
+            if let Some(value) = value {
+                *cell.borrow_mut() = value.into_inner();
+            }
+        })
+    }
+
+    /// Mock implementation of `std::thread::LocalKey::<RefCell<T>>::take`.
+    pub fn take(&'static self) -> T
+    where
+        T: Default,
+    {
+        self.with(RefCell::take)
+    }
+
+    /// Mock implementation of `std::thread::LocalKey::<RefCell<T>>::replace`.
+    pub fn replace(&'static self, value: T) -> T {
+        self.with(|cell| cell.replace(value))
+    }
+}
+
 impl<T: 'static> fmt::Debug for LocalKey<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.pad("LocalKey { .. }")
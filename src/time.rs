@@ -0,0 +1,117 @@
+//! Mocked versions of [`std::time`] types.
+//!
+//! Time is virtual. Each execution has its own clock which starts at zero and
+//! only moves forward when a thread calls [`advance`] or
+//! [`thread::sleep`](crate::thread::sleep). Reading the clock is a branch
+//! point, so a model explores a thread observing the time both before and
+//! after a concurrent advance. This makes timeout paths reachable without
+//! relying on wall-clock time.
+
+use crate::rt;
+
+use std::ops;
+
+pub use std::time::Duration;
+
+/// Mock implementation of `std::time::Instant`.
+///
+/// An `Instant` is a point on the virtual clock of the execution that created
+/// it. Comparing instants from different executions is meaningless.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Instant(Duration);
+
+/// Moves the virtual clock forward by `dur`.
+///
+/// Threads reading the clock concurrently may observe the time from before or
+/// after the advance.
+pub fn advance(dur: Duration) {
+    rt::time::advance(dur);
+}
+
+impl Instant {
+    /// Returns an instant corresponding to "now" on the virtual clock.
+    pub fn now() -> Instant {
+        Instant(rt::time::now())
+    }
+
+    /// Returns the amount of time elapsed from another instant to this one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `earlier` is later than `self`.
+    pub fn duration_since(&self, earlier: Instant) -> Duration {
+        self.checked_duration_since(earlier)
+            .expect("supplied instant is later than self")
+    }
+
+    /// Returns the amount of time elapsed from another instant to this one,
+    /// or `None` if that instant is later than this one.
+    pub fn checked_duration_since(&self, earlier: Instant) -> Option<Duration> {
+        self.0.checked_sub(earlier.0)
+    }
+
+    /// Returns the amount of time elapsed from another instant to this one,
+    /// or zero duration if that instant is later than this one.
+    pub fn saturating_duration_since(&self, earlier: Instant) -> Duration {
+        self.checked_duration_since(earlier)
+            .unwrap_or_else(|| Duration::from_secs(0))
+    }
+
+    /// Returns the amount of time elapsed since this instant was created.
+    pub fn elapsed(&self) -> Duration {
+        Instant::now().duration_since(*self)
+    }
+
+    /// Returns `Some(t)` where `t` is the time `self + duration` if `t` can be
+    /// represented, `None` otherwise.
+    pub fn checked_add(&self, duration: Duration) -> Option<Instant> {
+        self.0.checked_add(duration).map(Instant)
+    }
+
+    /// Returns `Some(t)` where `t` is the time `self - duration` if `t` can be
+    /// represented, `None` otherwise.
+    ///
+    /// The virtual clock starts at zero, so this returns `None` for instants
+    /// before the start of the execution.
+    pub fn checked_sub(&self, duration: Duration) -> Option<Instant> {
+        self.0.checked_sub(duration).map(Instant)
+    }
+}
+
+impl ops::Add<Duration> for Instant {
+    type Output = Instant;
+
+    fn add(self, other: Duration) -> Instant {
+        self.checked_add(other)
+            .expect("overflow when adding duration to instant")
+    }
+}
+
+impl ops::AddAssign<Duration> for Instant {
+    fn add_assign(&mut self, other: Duration) {
+        *self = *self + other;
+    }
+}
+
+impl ops::Sub<Duration> for Instant {
+    type Output = Instant;
+
+    fn sub(self, other: Duration) -> Instant {
+        self.checked_sub(other)
+            .expect("overflow when subtracting duration from instant")
+    }
+}
+
+impl ops::SubAssign<Duration> for Instant {
+    fn sub_assign(&mut self, other: Duration) {
+        *self = *self - other;
+    }
+}
+
+impl ops::Sub<Instant> for Instant {
+    type Output = Duration;
+
+    fn sub(self, other: Instant) -> Duration {
+        self.duration_since(other)
+    }
+}
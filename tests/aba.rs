@@ -0,0 +1,63 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::model::Builder;
+use loom::sync::atomic::AtomicUsize;
+use loom::sync::Arc;
+use loom::thread;
+
+use std::sync::atomic::Ordering::{AcqRel, Acquire, Release};
+
+/// Reads the value, then tries to replace it, while another thread changes
+/// it and changes it back.
+fn changed_and_changed_back() {
+    let num = Arc::new(AtomicUsize::new(0));
+
+    let th = {
+        let num = num.clone();
+        thread::spawn(move || {
+            num.store(1, Release);
+            num.store(0, Release);
+        })
+    };
+
+    let prev = num.load(Acquire);
+    let _ = num.compare_exchange(prev, 2, AcqRel, Acquire);
+
+    th.join().unwrap();
+}
+
+#[test]
+#[should_panic(expected = "ABA detected")]
+fn aba_is_detected() {
+    let mut builder = Builder::new();
+    builder.detect_aba(true);
+    builder.check(changed_and_changed_back);
+}
+
+#[test]
+fn aba_is_not_detected_when_disabled() {
+    let mut builder = Builder::new();
+    builder.detect_aba = false;
+    builder.check(changed_and_changed_back);
+}
+
+#[test]
+fn own_stores_are_not_aba() {
+    let mut builder = Builder::new();
+    builder.detect_aba(true);
+    builder.check(|| {
+        let num = Arc::new(AtomicUsize::new(0));
+
+        let th = {
+            let num = num.clone();
+            thread::spawn(move || num.load(Acquire))
+        };
+
+        let prev = num.load(Acquire);
+        num.store(1, Release);
+        num.store(prev, Release);
+
+        num.compare_exchange(prev, 2, AcqRel, Acquire).unwrap();
+        th.join().unwrap();
+    });
+}
@@ -0,0 +1,70 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::model::Builder;
+use loom::sync::atomic::AtomicUsize;
+use loom::thread;
+
+use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn after_execution_called_for_each_execution() {
+    let summaries = Arc::new(Mutex::new(vec![]));
+    let record = summaries.clone();
+
+    let mut builder = Builder::new();
+    builder.after_execution(move |summary| {
+        record
+            .lock()
+            .unwrap()
+            .push((summary.iteration, summary.panicked, summary.threads));
+    });
+
+    let stats = builder.check_returning_stats(|| {
+        let num = Arc::new(AtomicUsize::new(0));
+        let num2 = num.clone();
+
+        let th = thread::spawn(move || num2.store(1, Release));
+
+        num.load(Acquire);
+        th.join().unwrap();
+    });
+
+    let summaries = summaries.lock().unwrap();
+
+    assert_eq!(stats.iterations, summaries.len());
+
+    for (i, &(iteration, panicked, threads)) in summaries.iter().enumerate() {
+        assert_eq!(i + 1, iteration);
+        assert!(!panicked);
+        assert_eq!(2, threads);
+    }
+}
+
+#[test]
+fn after_execution_called_on_failure() {
+    let failed = Arc::new(Mutex::new(None));
+    let record = failed.clone();
+
+    let mut builder = Builder::new();
+    builder.after_execution(move |summary| {
+        if summary.panicked {
+            *record.lock().unwrap() = Some(summary.branches);
+        }
+    });
+
+    let res = std::panic::catch_unwind(|| {
+        builder.check(|| {
+            let num = Arc::new(AtomicUsize::new(0));
+            let num2 = num.clone();
+
+            let th = thread::spawn(move || num2.store(1, Relaxed));
+
+            assert_eq!(0, num.load(Relaxed));
+            th.join().unwrap();
+        })
+    });
+
+    assert!(res.is_err());
+    assert!(failed.lock().unwrap().unwrap() > 0);
+}
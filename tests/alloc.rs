@@ -0,0 +1,305 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::alloc::{alloc, alloc_zeroed, dealloc, realloc, Layout, Track};
+use loom::cell::UnsafeCell;
+use loom::model::Builder;
+use loom::sync::atomic::{AtomicPtr, AtomicUsize};
+use loom::thread;
+
+use std::panic;
+use std::ptr;
+use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
+
+#[test]
+#[should_panic(expected = "1 object leaked:\n    `Track<()>`")]
+fn track_leak() {
+    loom::model(|| {
+        std::mem::forget(Track::new(()));
+    });
+}
+
+#[test]
+fn track_leak_reports_allocation_site() {
+    let mut builder = Builder::new();
+    builder.location = true;
+
+    let err = panic::catch_unwind(|| {
+        builder.check(|| {
+            std::mem::forget(Track::new(()));
+        });
+    })
+    .unwrap_err();
+
+    let msg = err.downcast_ref::<String>().unwrap();
+
+    assert!(
+        msg.contains("`Track<()>`, created at tests/alloc.rs:28:"),
+        "{}",
+        msg
+    );
+}
+
+#[test]
+fn realloc_moves_allocation() {
+    loom::model(|| unsafe {
+        let layout = Layout::new::<u32>();
+
+        let ptr = alloc_zeroed(layout);
+        assert_eq!(0, *ptr);
+
+        let ptr = realloc(ptr, layout, 8);
+        dealloc(ptr, Layout::from_size_align(8, layout.align()).unwrap());
+    });
+}
+
+#[test]
+#[should_panic(expected = "1 object leaked:\n    raw allocation")]
+fn realloc_leak() {
+    loom::model(|| unsafe {
+        let layout = Layout::new::<u32>();
+
+        let ptr = alloc(layout);
+        realloc(ptr, layout, 8);
+    });
+}
+
+#[test]
+fn realloc_leak_reports_realloc_site() {
+    let mut builder = Builder::new();
+    builder.location = true;
+
+    let err = panic::catch_unwind(|| {
+        builder.check(|| unsafe {
+            let layout = Layout::new::<u32>();
+
+            let ptr = alloc(layout);
+            realloc(ptr, layout, 8);
+        });
+    })
+    .unwrap_err();
+
+    let msg = err.downcast_ref::<String>().unwrap();
+
+    assert!(
+        msg.contains("raw allocation, created at tests/alloc.rs:76:"),
+        "{}",
+        msg
+    );
+}
+
+#[test]
+fn fail_next_alloc() {
+    loom::model(|| unsafe {
+        let layout = Layout::new::<u64>();
+
+        loom::alloc::fail_next_alloc();
+        assert!(alloc(layout).is_null());
+
+        let ptr = alloc(layout);
+        assert!(!ptr.is_null());
+        dealloc(ptr, layout);
+    });
+}
+
+#[test]
+fn alloc_failures_explores_both_outcomes() {
+    use std::sync::{Arc, Mutex};
+
+    let outcomes = Arc::new(Mutex::new(vec![]));
+    let observed = outcomes.clone();
+
+    let mut builder = Builder::new();
+    builder.alloc_failures = true;
+    builder.check(move || unsafe {
+        let layout = Layout::new::<u64>();
+        let ptr = alloc(layout);
+
+        observed.lock().unwrap().push(ptr.is_null());
+
+        if !ptr.is_null() {
+            dealloc(ptr, layout);
+        }
+    });
+
+    assert_eq!(vec![false, true], *outcomes.lock().unwrap());
+}
+
+#[test]
+#[should_panic(expected = "Use after free: `UnsafeCell` accessed after the raw allocation")]
+fn cell_access_after_dealloc() {
+    loom::model(|| unsafe {
+        let layout = Layout::new::<UnsafeCell<usize>>();
+
+        let cell = alloc(layout) as *mut UnsafeCell<usize>;
+        ptr::write(cell, UnsafeCell::new(0));
+        dealloc(cell as *mut u8, layout);
+
+        (*cell).with(|ptr| *ptr);
+    });
+}
+
+/// The cell is read by a second thread while the allocation is freed only
+/// once the reference count synchronizes with it.
+#[test]
+fn cell_access_before_dealloc() {
+    struct Shared {
+        refs: AtomicUsize,
+        cell: UnsafeCell<usize>,
+    }
+
+    struct Ptr(*mut Shared);
+
+    unsafe impl Send for Ptr {}
+
+    unsafe fn release(shared: *mut Shared) {
+        if (*shared).refs.fetch_sub(1, Release) == 1 {
+            (*shared).refs.load(Acquire);
+            dealloc(shared as *mut u8, Layout::new::<Shared>());
+        }
+    }
+
+    loom::model(|| unsafe {
+        let shared = alloc(Layout::new::<Shared>()) as *mut Shared;
+        ptr::write(
+            shared,
+            Shared {
+                refs: AtomicUsize::new(2),
+                cell: UnsafeCell::new(1),
+            },
+        );
+
+        let ptr = Ptr(shared);
+        let th = thread::spawn(move || {
+            let ptr = ptr;
+            assert_eq!(1, (*ptr.0).cell.with(|ptr| *ptr));
+            release(ptr.0);
+        });
+
+        assert_eq!(1, (*shared).cell.with(|ptr| *ptr));
+        release(shared);
+
+        th.join().unwrap();
+    });
+}
+
+type Node = Track<UnsafeCell<usize>>;
+
+unsafe fn new_node(value: usize) -> *mut Node {
+    let node = alloc(Layout::new::<Node>()) as *mut Node;
+    ptr::write(node, Track::new(UnsafeCell::new(value)));
+    node
+}
+
+unsafe fn free_node(node: *mut Node) {
+    ptr::drop_in_place(node);
+    dealloc(node as *mut u8, Layout::new::<Node>());
+}
+
+/// The node is passed with relaxed atomics, publishing and acquiring it
+/// orders the accesses to its cell.
+#[test]
+fn track_handoff() {
+    use loom::sync::Arc;
+
+    loom::model(|| unsafe {
+        let slot = Arc::new(AtomicPtr::new(ptr::null_mut()));
+        let slot2 = slot.clone();
+
+        let th = thread::spawn(move || {
+            let node = new_node(1);
+            (*node).get_ref().with_mut(|ptr| *ptr = 2);
+
+            (*node).publish();
+            slot2.store(node, Relaxed);
+        });
+
+        let node = slot.load(Relaxed);
+        let acquired = !node.is_null();
+
+        if acquired {
+            (*node).acquire();
+            assert_eq!(2, (*node).get_ref().with(|ptr| *ptr));
+        }
+
+        th.join().unwrap();
+
+        let node = slot.load(Relaxed);
+
+        if !acquired {
+            (*node).acquire();
+        }
+
+        free_node(node);
+    });
+}
+
+#[test]
+#[should_panic(expected = "accessed by thread #0, but it was published by thread #1")]
+fn track_access_without_acquire() {
+    use loom::sync::Arc;
+
+    loom::model(|| unsafe {
+        let slot = Arc::new(AtomicPtr::new(ptr::null_mut()));
+        let slot2 = slot.clone();
+
+        let th = thread::spawn(move || {
+            let node = new_node(1);
+            (*node).publish();
+            slot2.store(node, Release);
+        });
+
+        th.join().unwrap();
+
+        let node = slot.load(Acquire);
+        (*node).get_ref().with(|ptr| *ptr);
+        (*node).acquire();
+        free_node(node);
+    });
+}
+
+#[test]
+#[should_panic(expected = "acquired by thread #0, but it was not published")]
+fn track_acquired_twice() {
+    use loom::sync::Arc;
+
+    loom::model(|| unsafe {
+        let node = new_node(1);
+        (*node).publish();
+
+        let slot = Arc::new(AtomicPtr::new(node));
+        let slot2 = slot.clone();
+
+        // Both threads load the node instead of taking it.
+        let th = thread::spawn(move || {
+            let node = slot2.load(Acquire);
+            (*node).acquire();
+        });
+
+        th.join().unwrap();
+
+        let node = slot.load(Acquire);
+        (*node).acquire();
+        free_node(node);
+    });
+}
+
+#[test]
+#[should_panic(expected = "UnsafeCell<usize>>` accessed after it was dropped")]
+fn track_access_after_drop() {
+    loom::model(|| unsafe {
+        let node = new_node(1);
+        ptr::drop_in_place(node);
+
+        (*node).get_ref();
+    });
+}
+
+#[test]
+#[should_panic(expected = "UnsafeCell<usize>>` dropped twice")]
+fn track_double_free() {
+    loom::model(|| unsafe {
+        let node = new_node(1);
+        ptr::drop_in_place(node);
+        free_node(node);
+    });
+}
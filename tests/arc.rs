@@ -77,3 +77,17 @@ fn detect_mem_leak() {
         std::mem::forget(num);
     });
 }
+
+#[test]
+#[should_panic(
+    expected = "2 objects leaked:\n    `Arc<usize>` with 2 outstanding references\n    `Arc<()>` with 1 outstanding reference"
+)]
+fn report_every_leak() {
+    loom::model(|| {
+        let num = Arc::new(1_usize);
+        std::mem::forget(num.clone());
+        std::mem::forget(num);
+
+        std::mem::forget(Arc::new(()));
+    });
+}
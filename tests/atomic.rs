@@ -80,9 +80,58 @@ fn invalid_unsync_load_relaxed() {
 }
 
 #[test]
-#[ignore]
-#[should_panic]
+#[should_panic(expected = "unsync_load: thread #1")]
+fn invalid_unsync_load_names_racing_threads() {
+    loom::model(|| {
+        let a = Arc::new(AtomicUsize::new(0));
+        let b = a.clone();
+
+        let thread = thread::spawn(move || {
+            unsafe { a.unsync_load() };
+        });
+
+        b.store(1, Relaxed);
+
+        thread.join().unwrap();
+    });
+}
+
+#[test]
+fn unsync_store_before_sharing() {
+    loom::model(|| {
+        let a = Arc::new(AtomicUsize::new(0));
+        unsafe { a.unsync_store(1) };
+
+        let b = a.clone();
+        let thread = thread::spawn(move || b.load(Relaxed));
+
+        assert_eq!(1, thread.join().unwrap());
+        assert_eq!(1, unsafe { a.unsync_load() });
+    });
+}
+
+#[test]
+#[should_panic(expected = "Causality violation")]
+fn invalid_unsync_store() {
+    loom::model(|| {
+        let a = Arc::new(AtomicUsize::new(0));
+        let b = a.clone();
+
+        let thread = thread::spawn(move || {
+            unsafe { a.unsync_store(1) };
+        });
+
+        b.load(Relaxed);
+
+        thread.join().unwrap();
+    });
+}
+
+#[test]
 fn compare_and_swap_reads_old_values() {
+    // A succeeding compare-and-swap is ordered right after the store it reads
+    // in modification order, both cannot read the initial value and leave
+    // their store last.
     loom::model(|| {
         let a = Arc::new(AtomicUsize::new(0));
         let b = Arc::new(AtomicUsize::new(0));
@@ -108,3 +157,240 @@ fn compare_and_swap_reads_old_values() {
         }
     });
 }
+
+#[test]
+fn compare_exchange_failure_reads_stale_values() {
+    use loom::sync::atomic::AtomicBool;
+
+    let outcomes = loom::model_outcomes(|| {
+        let num = Arc::new(AtomicUsize::new(0));
+        let done = Arc::new(AtomicBool::new(false));
+
+        let th = {
+            let (num, done) = (num.clone(), done.clone());
+            thread::spawn(move || {
+                num.store(1, Relaxed);
+                num.store(2, Relaxed);
+                done.store(true, Relaxed);
+            })
+        };
+
+        // Without synchronization, seeing `done` does not hide the older
+        // stores from the failure load.
+        let res = if done.load(Relaxed) {
+            Some(num.compare_exchange(2, 3, Relaxed, Relaxed))
+        } else {
+            None
+        };
+
+        th.join().unwrap();
+        res
+    });
+
+    assert!(outcomes.contains(&Some(Ok(2))));
+    assert!(outcomes.contains(&Some(Err(0))));
+    assert!(outcomes.contains(&Some(Err(1))));
+}
+
+#[test]
+fn swap_bool_reads_stale_values() {
+    use loom::sync::atomic::AtomicBool;
+
+    let outcomes = loom::model_outcomes(|| {
+        let flag = Arc::new(AtomicBool::new(false));
+        let done = Arc::new(AtomicBool::new(false));
+
+        let th = {
+            let (flag, done) = (flag.clone(), done.clone());
+            thread::spawn(move || {
+                flag.store(true, Relaxed);
+                done.store(true, Relaxed);
+            })
+        };
+
+        // The swap is ordered before the store it did not read, which stays
+        // the latest value.
+        let res = if done.load(Relaxed) {
+            Some(flag.swap(false, Relaxed))
+        } else {
+            None
+        };
+
+        th.join().unwrap();
+        (res, flag.load(Relaxed))
+    });
+
+    assert!(outcomes.contains(&(Some(false), true)));
+    assert!(outcomes.contains(&(Some(true), false)));
+    assert!(!outcomes.contains(&(Some(false), false)));
+}
+
+#[test]
+fn swap_ptr_reads_stale_values() {
+    use loom::sync::atomic::{AtomicBool, AtomicPtr};
+
+    let outcomes = loom::model_outcomes(|| {
+        let a = Box::into_raw(Box::new(1));
+        let b = Box::into_raw(Box::new(2));
+
+        let ptr = Arc::new(AtomicPtr::new(std::ptr::null_mut()));
+        let done = Arc::new(AtomicBool::new(false));
+
+        let th = {
+            let (ptr, done) = (ptr.clone(), done.clone());
+            thread::spawn(move || {
+                ptr.store(a, Relaxed);
+                done.store(true, Release);
+            })
+        };
+
+        // Acquiring `done` hides the initial null pointer.
+        let res = if done.load(Acquire) {
+            Some(ptr.swap(b, Relaxed) == a)
+        } else {
+            None
+        };
+
+        th.join().unwrap();
+
+        unsafe {
+            drop(Box::from_raw(a));
+            drop(Box::from_raw(b));
+        }
+
+        res
+    });
+
+    assert_eq!(outcomes, [None, Some(true)].iter().copied().collect());
+}
+
+#[test]
+fn compare_exchange_ptr_returns_previous_value() {
+    use loom::sync::atomic::AtomicPtr;
+
+    loom::model(|| {
+        let a = Box::into_raw(Box::new(1));
+        let b = Box::into_raw(Box::new(2));
+
+        let ptr = Arc::new(AtomicPtr::new(a));
+
+        let th = {
+            let ptr = ptr.clone();
+            thread::spawn(move || ptr.compare_exchange(a, b, AcqRel, Acquire))
+        };
+
+        // Fails reading `a` or `b`, the value it compares against is never
+        // stored.
+        let res = ptr.compare_exchange(std::ptr::null_mut(), b, AcqRel, Acquire);
+        assert!(res == Err(a) || res == Err(b));
+
+        assert_eq!(th.join().unwrap(), Ok(a));
+        assert_eq!(ptr.load(Acquire), b);
+
+        unsafe {
+            drop(Box::from_raw(a));
+            drop(Box::from_raw(b));
+        }
+    });
+}
+
+#[test]
+fn external_release_store_publishes() {
+    use loom::cell::UnsafeCell;
+    use loom::sync::atomic::{AtomicBool, ExternalOp};
+
+    loom::model(|| {
+        let data = loom::sync::Arc::new((UnsafeCell::new(0), AtomicBool::new(false)));
+
+        let th = {
+            let data = data.clone();
+            thread::spawn(move || {
+                data.0.with_mut(|ptr| unsafe { *ptr = 1 });
+                // Foreign code sets the flag
+                data.1.record_external_op(ExternalOp::Store(true), Release);
+            })
+        };
+
+        if data.1.load(Acquire) {
+            assert_eq!(1, data.0.with(|ptr| unsafe { *ptr }));
+        }
+
+        th.join().unwrap();
+    });
+}
+
+#[test]
+#[should_panic]
+fn external_relaxed_load_does_not_synchronize() {
+    use loom::cell::UnsafeCell;
+    use loom::sync::atomic::ExternalOp;
+
+    loom::model(|| {
+        let data = loom::sync::Arc::new((UnsafeCell::new(0), AtomicUsize::new(0)));
+
+        let th = {
+            let data = data.clone();
+            thread::spawn(move || {
+                data.0.with_mut(|ptr| unsafe { *ptr = 1 });
+                data.1.store(1, Release);
+            })
+        };
+
+        // Foreign code checks the flag
+        if data.1.record_external_op(ExternalOp::Load, Relaxed) == 1 {
+            data.0.with(|ptr| unsafe { *ptr });
+        }
+
+        th.join().unwrap();
+    });
+}
+
+#[test]
+fn external_swap_returns_previous_value() {
+    use loom::sync::atomic::ExternalOp;
+
+    loom::model(|| {
+        let num = AtomicUsize::new(1);
+
+        assert_eq!(1, num.record_external_op(ExternalOp::Swap(2), AcqRel));
+        assert_eq!(2, num.load(Relaxed));
+    });
+}
+
+#[test]
+fn pruned_history_keeps_observable_stores() {
+    use loom::sync::atomic::AtomicBool;
+    use std::sync::atomic::AtomicBool as StdAtomicBool;
+
+    loom::lazy_static! {
+        static ref NUM: AtomicUsize = AtomicUsize::new(0);
+        static ref DONE: AtomicBool = AtomicBool::new(false);
+    }
+
+    let observed = Arc::new(StdAtomicBool::new(false));
+    let observed2 = observed.clone();
+
+    loom::model(move || {
+        thread::spawn(|| {
+            NUM.store(1, Relaxed);
+            DONE.store(true, Relaxed);
+        });
+
+        while !DONE.load(Relaxed) {
+            thread::yield_now();
+        }
+
+        // Once the spawned thread is done, the older stores of this thread
+        // cannot be observed anymore and make room for the new stores. The
+        // store of the spawned thread remains observable.
+        for i in 2..10 {
+            NUM.store(i, Relaxed);
+        }
+
+        if NUM.load(Relaxed) == 1 {
+            observed2.store(true, Relaxed);
+        }
+    });
+
+    assert!(observed.load(Relaxed));
+}
@@ -0,0 +1,84 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::sync::{Arc, AtomicCell};
+use loom::thread;
+
+#[test]
+fn is_lock_free() {
+    assert!(AtomicCell::<usize>::is_lock_free());
+    assert!(AtomicCell::<()>::is_lock_free());
+    assert!(!AtomicCell::<[u64; 4]>::is_lock_free());
+}
+
+#[test]
+fn fetch_update_is_atomic() {
+    loom::model(|| {
+        let cell = Arc::new(AtomicCell::new(0_usize));
+
+        let ths: Vec<_> = (0..2)
+            .map(|_| {
+                let cell = cell.clone();
+                thread::spawn(move || {
+                    cell.fetch_update(|v| Some(v + 1)).unwrap();
+                })
+            })
+            .collect();
+
+        for th in ths {
+            th.join().unwrap();
+        }
+
+        assert_eq!(2, cell.load());
+    });
+}
+
+#[test]
+fn load_store_loses_updates() {
+    let outcomes = loom::model_outcomes(|| {
+        let cell = Arc::new(AtomicCell::new(0_u64));
+        let cell2 = cell.clone();
+
+        let th = thread::spawn(move || cell2.store(cell2.load() + 1));
+        cell.store(cell.load() + 1);
+        th.join().unwrap();
+
+        cell.load()
+    });
+
+    assert_eq!(outcomes, [1, 2].iter().cloned().collect());
+}
+
+#[test]
+fn locked_load_store_loses_updates() {
+    let outcomes = loom::model_outcomes(|| {
+        let cell = Arc::new(AtomicCell::new([0_u64; 4]));
+        let cell2 = cell.clone();
+
+        let add = |cell: &AtomicCell<[u64; 4]>| {
+            let mut v = cell.load();
+            v[0] += 1;
+            cell.store(v);
+        };
+
+        let th = thread::spawn(move || add(&cell2));
+        add(&cell);
+        th.join().unwrap();
+
+        cell.load()[0]
+    });
+
+    assert_eq!(outcomes, [1, 2].iter().cloned().collect());
+}
+
+#[test]
+fn compare_exchange() {
+    loom::model(|| {
+        let cell = AtomicCell::new([1_u64; 4]);
+
+        assert_eq!(Err([1; 4]), cell.compare_exchange([0; 4], [2; 4]));
+        assert_eq!(Ok([1; 4]), cell.compare_exchange([1; 4], [2; 4]));
+        assert_eq!([2; 4], cell.swap([3; 4]));
+        assert_eq!([3; 4], cell.take());
+        assert_eq!([0; 4], cell.into_inner());
+    });
+}
@@ -0,0 +1,60 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::sync::atomic::AtomicUsize;
+use loom::sync::Arc;
+use loom::thread;
+
+use std::collections::HashSet;
+use std::sync::atomic::Ordering::SeqCst;
+use std::sync::Mutex;
+
+#[test]
+fn section_is_not_preempted() {
+    loom::model(|| {
+        let num = Arc::new(AtomicUsize::new(0));
+        let num2 = num.clone();
+
+        let th = thread::spawn(move || {
+            loom::atomic_section(|| {
+                let n = num2.load(SeqCst);
+                num2.store(n + 1, SeqCst);
+            })
+        });
+
+        loom::atomic_section(|| {
+            let n = num.load(SeqCst);
+            num.store(n + 1, SeqCst);
+        });
+
+        th.join().unwrap();
+        assert_eq!(2, num.load(SeqCst));
+    });
+}
+
+#[test]
+fn sections_are_explored_in_both_orders() {
+    let outcomes = std::sync::Arc::new(Mutex::new(HashSet::new()));
+    let observed = outcomes.clone();
+
+    loom::model(move || {
+        let a = Arc::new(AtomicUsize::new(0));
+        let b = Arc::new(AtomicUsize::new(0));
+        let (a2, b2) = (a.clone(), b.clone());
+
+        let th = thread::spawn(move || {
+            loom::atomic_section(|| {
+                a2.store(1, SeqCst);
+                b2.store(1, SeqCst);
+            })
+        });
+
+        let seen = loom::atomic_section(|| (a.load(SeqCst), b.load(SeqCst)));
+
+        th.join().unwrap();
+        observed.lock().unwrap().insert(seen);
+    });
+
+    let outcomes = outcomes.lock().unwrap();
+    let expected: HashSet<_> = vec![(0, 0), (1, 1)].into_iter().collect();
+    assert_eq!(expected, *outcomes);
+}
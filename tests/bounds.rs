@@ -0,0 +1,71 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::model::Builder;
+use loom::sync::atomic::AtomicUsize;
+use loom::sync::Arc;
+use loom::thread;
+
+use std::panic;
+use std::sync::atomic::Ordering::SeqCst;
+
+/// Checks threads racing to increment a counter, returning the number of
+/// executions, or `None` if the lost update was found.
+fn lost_update(builder: &Builder) -> Option<usize> {
+    let res = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+        builder.check_returning_stats(|| {
+            let num = Arc::new(AtomicUsize::new(0));
+
+            let ths: Vec<_> = (0..3)
+                .map(|_| {
+                    let num = num.clone();
+                    thread::spawn(move || {
+                        let n = num.load(SeqCst);
+                        num.store(n + 1, SeqCst);
+                    })
+                })
+                .collect();
+
+            for th in ths {
+                th.join().unwrap();
+            }
+
+            assert_eq!(3, num.load(SeqCst));
+        })
+    }));
+
+    res.ok().map(|stats| stats.iterations)
+}
+
+#[test]
+fn zero_bound_explores_one_execution() {
+    let mut builder = Builder::new();
+    builder.context_bound(0);
+    assert_eq!(Some(1), lost_update(&builder));
+
+    let mut builder = Builder::new();
+    builder.delay_bound(0);
+    assert_eq!(Some(1), lost_update(&builder));
+}
+
+#[test]
+fn context_bound_finds_bug() {
+    let mut builder = Builder::new();
+    builder.context_bound(2);
+    assert_eq!(None, lost_update(&builder));
+}
+
+#[test]
+fn delay_bound_finds_bug() {
+    let mut builder = Builder::new();
+    builder.delay_bound(1);
+    assert_eq!(None, lost_update(&builder));
+}
+
+#[test]
+#[should_panic(expected = "only one of")]
+fn conflicting_bounds() {
+    let mut builder = Builder::new();
+    builder.preemption_bound = Some(1);
+    builder.delay_bound(1);
+    builder.check(|| {});
+}
@@ -0,0 +1,125 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::model::{BranchLimit, Builder};
+use loom::sync::atomic::AtomicUsize;
+use loom::sync::Arc;
+use loom::thread;
+
+use std::sync::atomic::Ordering::SeqCst;
+
+fn builder(limit: BranchLimit) -> Builder {
+    let mut builder = Builder::new();
+    builder.max_branches = 20;
+    builder.on_branch_limit(limit);
+    builder
+}
+
+fn many_branches() {
+    let num = Arc::new(AtomicUsize::new(0));
+    let num2 = num.clone();
+
+    let th = thread::spawn(move || {
+        num2.fetch_add(1, SeqCst);
+    });
+
+    num.fetch_add(1, SeqCst);
+    th.join().unwrap();
+
+    for _ in 0..20 {
+        num.fetch_add(1, SeqCst);
+    }
+
+    assert_eq!(22, num.load(SeqCst));
+}
+
+#[test]
+#[should_panic(expected = "Model exeeded maximum number of branches")]
+fn branch_limit_panic() {
+    builder(BranchLimit::Panic).check(many_branches);
+}
+
+#[test]
+fn branch_limit_warn() {
+    let stats = builder(BranchLimit::Warn).check_returning_stats(many_branches);
+
+    assert!(stats.truncated > 0);
+    assert!(stats.iterations >= stats.truncated);
+}
+
+#[test]
+fn branch_limit_grow() {
+    let stats = builder(BranchLimit::Grow).check_returning_stats(many_branches);
+
+    let expect = Builder::new().check_returning_stats(many_branches);
+
+    assert_eq!(stats.truncated, 0);
+    assert_eq!(stats.iterations, expect.iterations);
+}
+
+/// The main thread spins until the other thread stores, which only completes
+/// if the other thread is eventually scheduled.
+fn spin_wait() {
+    let num = Arc::new(AtomicUsize::new(0));
+    let num2 = num.clone();
+
+    let th = thread::spawn(move || {
+        num2.store(1, SeqCst);
+    });
+
+    while num.load(SeqCst) == 0 {}
+
+    th.join().unwrap();
+}
+
+#[test]
+fn weak_fairness_prunes_unfair_spin_wait() {
+    let mut builder = builder(BranchLimit::Panic);
+    builder.weak_fairness(true);
+
+    let stats = builder.check_returning_stats(spin_wait);
+
+    assert!(stats.unfair_pruned > 0);
+    assert_eq!(0, stats.truncated);
+}
+
+#[test]
+#[should_panic(expected = "with a fair schedule")]
+fn weak_fairness_reports_livelock() {
+    let mut builder = builder(BranchLimit::Panic);
+    builder.weak_fairness(true);
+
+    builder.check(|| {
+        let num = Arc::new(AtomicUsize::new(0));
+        let num2 = num.clone();
+
+        let th = thread::spawn(move || {
+            // Waits for a store that never happens
+            while num2.load(SeqCst) != 2 {
+                thread::yield_now();
+            }
+        });
+
+        while num.load(SeqCst) != 1 {
+            thread::yield_now();
+        }
+
+        th.join().unwrap();
+    });
+}
+
+/// Atomic operations that can only read one store do not add a branch to the
+/// path, only scheduling the thread does.
+#[test]
+fn single_store_reads_do_not_branch() {
+    let mut builder = Builder::new();
+    builder.max_branches = 30;
+
+    builder.check(|| {
+        let num = AtomicUsize::new(0);
+
+        for _ in 0..10 {
+            num.fetch_add(1, SeqCst);
+            num.load(SeqCst);
+        }
+    });
+}
@@ -0,0 +1,151 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::cell::{Cell, Ref, RefCell, RefMut};
+use loom::sync::atomic::AtomicBool;
+use loom::sync::Arc;
+use loom::thread;
+
+use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
+
+/// Shares a value between threads, like a type with an incorrect
+/// `unsafe impl Sync` would.
+struct Shared<T>(T);
+
+unsafe impl<T> Sync for Shared<T> {}
+unsafe impl<T> Send for Shared<T> {}
+
+#[test]
+fn cell_single_thread() {
+    loom::model(|| {
+        let cell = Cell::new(1);
+        assert_eq!(1, cell.replace(2));
+        cell.set(cell.get() + 1);
+
+        let other = Cell::new(5);
+        cell.swap(&other);
+
+        assert_eq!(5, cell.take());
+        assert_eq!(0, cell.into_inner());
+        assert_eq!(3, other.get());
+    });
+}
+
+#[test]
+fn cell_synchronized() {
+    loom::model(|| {
+        let shared = Arc::new((Shared(Cell::new(0)), AtomicBool::new(false)));
+
+        let th = {
+            let shared = shared.clone();
+            thread::spawn(move || {
+                (shared.0).0.set(1);
+                shared.1.store(true, Release);
+            })
+        };
+
+        if shared.1.load(Acquire) {
+            assert_eq!(1, (shared.0).0.get());
+        }
+
+        th.join().unwrap();
+    });
+}
+
+#[test]
+#[should_panic(expected = "Concurrent read and write accesses to `Cell`")]
+fn cell_race() {
+    loom::model(|| {
+        let shared = Arc::new((Shared(Cell::new(0)), AtomicBool::new(false)));
+
+        let th = {
+            let shared = shared.clone();
+            thread::spawn(move || {
+                (shared.0).0.set(1);
+                shared.1.store(true, Relaxed);
+            })
+        };
+
+        if shared.1.load(Relaxed) {
+            (shared.0).0.get();
+        }
+
+        th.join().unwrap();
+    });
+}
+
+#[test]
+fn ref_cell_borrow_errors() {
+    loom::model(|| {
+        let cell = RefCell::new(vec![1]);
+
+        {
+            let a = cell.borrow();
+            let b = Ref::clone(&a);
+            assert!(cell.try_borrow_mut().is_err());
+            assert_eq!(a.len() + b.len(), 2);
+        }
+
+        {
+            let mut first = RefMut::map(cell.borrow_mut(), |v| &mut v[0]);
+            *first += 1;
+            assert!(cell.try_borrow().is_err());
+            assert!(cell.try_borrow_mut().is_err());
+        }
+
+        assert_eq!(2, *Ref::map(cell.borrow(), |v| &v[0]));
+        assert_eq!(vec![2], cell.replace(vec![]));
+    });
+}
+
+#[test]
+#[should_panic(expected = "already borrowed")]
+fn ref_cell_borrow_mut_while_borrowed() {
+    loom::model(|| {
+        let cell = RefCell::new(0);
+        let _a = cell.borrow();
+        let _b = cell.borrow_mut();
+    });
+}
+
+#[test]
+fn ref_cell_synchronized() {
+    loom::model(|| {
+        let shared = Arc::new((Shared(RefCell::new(vec![])), AtomicBool::new(false)));
+
+        let th = {
+            let shared = shared.clone();
+            thread::spawn(move || {
+                (shared.0).0.borrow_mut().push(1);
+                shared.1.store(true, Release);
+            })
+        };
+
+        if shared.1.load(Acquire) {
+            assert_eq!(*(shared.0).0.borrow(), [1]);
+        }
+
+        th.join().unwrap();
+    });
+}
+
+#[test]
+#[should_panic(expected = "Concurrent read and write accesses to `RefCell`")]
+fn ref_cell_borrowed_by_other_thread() {
+    loom::model(|| {
+        let shared = Arc::new(Shared(RefCell::new(0)));
+
+        let th = {
+            let shared = shared.clone();
+            thread::spawn(move || {
+                let mut value = shared.0.borrow_mut();
+                thread::yield_now();
+                *value += 1;
+            })
+        };
+
+        // Fails whether or not the other thread holds the borrow
+        let _ = shared.0.try_borrow();
+
+        th.join().unwrap();
+    });
+}
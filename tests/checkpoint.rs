@@ -0,0 +1,289 @@
+#![cfg(feature = "checkpoint")]
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::model::{Builder, CheckpointFormat, Order};
+use loom::sync::atomic::AtomicUsize;
+use loom::thread;
+
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering::Relaxed;
+use std::sync::Arc;
+
+/// Path of a checkpoint file in the temporary directory, removed once the
+/// test completes, even if it fails.
+struct CheckpointFile(PathBuf);
+
+impl Deref for CheckpointFile {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for CheckpointFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+fn checkpoint_file(name: &str) -> CheckpointFile {
+    let path = std::env::temp_dir().join(format!("loom-{}-{}", std::process::id(), name));
+    let _ = std::fs::remove_file(&path);
+    CheckpointFile(path)
+}
+
+/// Runs a small model, returning the number of iterations.
+fn run(builder: &Builder) -> usize {
+    let iterations = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let counter = iterations.clone();
+
+    builder.check(move || {
+        counter.fetch_add(1, Relaxed);
+
+        let num = Arc::new(AtomicUsize::new(0));
+
+        let ths: Vec<_> = (0..2)
+            .map(|_| {
+                let num = num.clone();
+                thread::spawn(move || num.fetch_add(1, Relaxed))
+            })
+            .collect();
+
+        num.load(Relaxed);
+
+        for th in ths {
+            th.join().unwrap();
+        }
+    });
+
+    iterations.load(Relaxed)
+}
+
+fn resume(file: CheckpointFile, format: Option<CheckpointFormat>) {
+    let total = run(&Builder::new());
+
+    let mut builder = Builder::new();
+    builder.checkpoint_file(file.to_str().unwrap());
+    builder.checkpoint_format = format;
+    builder.checkpoint_interval = 1;
+    builder.max_permutations = Some(3);
+
+    // The checkpoint is stored, and the check stopped, before the 3rd
+    // iteration runs.
+    assert_eq!(2, run(&builder));
+    assert!(file.exists());
+
+    builder.max_permutations = None;
+
+    assert_eq!(total - 2, run(&builder));
+}
+
+/// Runs a small model, returning what each iteration observed.
+fn observe(builder: &Builder) -> Vec<(usize, usize, usize)> {
+    let observed = Arc::new(std::sync::Mutex::new(vec![]));
+    let log = observed.clone();
+
+    builder.check(move || {
+        let num = Arc::new(AtomicUsize::new(0));
+
+        let ths: Vec<_> = (0..2)
+            .map(|_| {
+                let num = num.clone();
+                thread::spawn(move || num.fetch_add(1, Relaxed))
+            })
+            .collect();
+
+        let load = num.load(Relaxed);
+        let mut ths = ths.into_iter().map(|th| th.join().unwrap());
+        let first = ths.next().unwrap();
+        let second = ths.next().unwrap();
+
+        log.lock().unwrap().push((load, first, second));
+    });
+
+    let observed = observed.lock().unwrap().clone();
+    observed
+}
+
+#[test]
+fn resume_json_checkpoint() {
+    resume(checkpoint_file("resume.json"), None);
+}
+
+#[test]
+fn resume_binary_checkpoint() {
+    resume(
+        checkpoint_file("resume.ckpt"),
+        Some(CheckpointFormat::Binary),
+    );
+}
+
+#[test]
+fn binary_checkpoint_by_extension() {
+    let file = checkpoint_file("extension.bin");
+
+    let mut builder = Builder::new();
+    builder.checkpoint_file(file.to_str().unwrap());
+    builder.checkpoint_interval = 1;
+    builder.max_permutations = Some(1);
+    run(&builder);
+
+    let contents = std::fs::read(&*file).unwrap();
+    assert!(contents.starts_with(b"LOOMCKPT"));
+}
+
+#[test]
+#[should_panic(expected = "incompatible version of loom")]
+fn reject_incompatible_checkpoint() {
+    let file = checkpoint_file("incompatible.json");
+    std::fs::write(&*file, r#"{"pos":0,"branches":{"entries":[]}}"#).unwrap();
+
+    let mut builder = Builder::new();
+    builder.checkpoint_file(file.to_str().unwrap());
+    run(&builder);
+}
+
+#[test]
+#[should_panic(expected = "is corrupt")]
+fn reject_corrupt_checkpoint() {
+    let file = checkpoint_file("corrupt.bin");
+
+    let mut builder = Builder::new();
+    builder.checkpoint_file(file.to_str().unwrap());
+    builder.checkpoint_interval = 1;
+    builder.max_permutations = Some(1);
+    run(&builder);
+
+    // Keep the header of a checkpoint written by this version of loom: the
+    // magic bytes, then the format version as a varint.
+    let contents = std::fs::read(&*file).unwrap();
+    let version_len = contents[8..].iter().position(|b| b & 0x80 == 0).unwrap() + 1;
+    let mut corrupt = contents[..8 + version_len].to_vec();
+    corrupt.push(0xff);
+    std::fs::write(&*file, corrupt).unwrap();
+
+    builder.max_permutations = None;
+    run(&builder);
+}
+
+#[test]
+fn checkpoint_on_failure() {
+    use std::sync::atomic::AtomicBool;
+
+    static FAIL: AtomicBool = AtomicBool::new(true);
+
+    // Fails on the 5th iteration, as long as `FAIL` is set.
+    fn run(builder: &Builder) -> usize {
+        let iterations = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = iterations.clone();
+
+        builder.check(move || {
+            let i = counter.fetch_add(1, Relaxed) + 1;
+
+            let num = Arc::new(AtomicUsize::new(0));
+
+            let ths: Vec<_> = (0..2)
+                .map(|_| {
+                    let num = num.clone();
+                    thread::spawn(move || num.fetch_add(1, Relaxed))
+                })
+                .collect();
+
+            for th in ths {
+                th.join().unwrap();
+            }
+
+            assert!(i != 5 || !FAIL.load(Relaxed), "failing iteration");
+        });
+
+        iterations.load(Relaxed)
+    }
+
+    let file = checkpoint_file("on-failure.json");
+
+    let mut builder = Builder::new();
+    builder.checkpoint_file(file.to_str().unwrap());
+    builder.checkpoint_on_exit(true);
+
+    let res = std::panic::catch_unwind(|| run(&builder));
+    assert!(res.is_err());
+    assert!(file.exists());
+
+    FAIL.store(false, Relaxed);
+
+    let total = run(&Builder::new());
+
+    // Resumes at the failing iteration
+    assert_eq!(total - 4, run(&builder));
+}
+
+/// Writes a checkpoint with `builder` after 2 iterations, then resumes it
+/// with `resume`. Returns what the iterations of both runs observed.
+fn observe_resumed(
+    name: &str,
+    mut builder: Builder,
+    mut resume: Builder,
+) -> Vec<(usize, usize, usize)> {
+    let file = checkpoint_file(name);
+
+    builder.checkpoint_file(file.to_str().unwrap());
+    builder.checkpoint_interval = 1;
+    builder.max_permutations = Some(3);
+    let mut observed = observe(&builder);
+    assert_eq!(2, observed.len());
+
+    resume.checkpoint_file(file.to_str().unwrap());
+    observed.extend(observe(&resume));
+
+    observed
+}
+
+#[test]
+fn resume_with_checkpoint_strategy() {
+    for &format in &[CheckpointFormat::Json, CheckpointFormat::Binary] {
+        let mut builder = Builder::new();
+        builder.exploration_order = Order::Reverse;
+        builder.preemption_bound = None;
+        builder.context_bound = Some(2);
+        let expected = observe(&builder);
+
+        builder.checkpoint_format = Some(format);
+
+        // The order and bound of the checkpoint are used.
+        let mut resume = Builder::new();
+        resume.checkpoint_format = Some(format);
+
+        assert_eq!(expected, observe_resumed("strategy.ckpt", builder, resume));
+    }
+}
+
+#[test]
+fn resume_random_schedules() {
+    for &format in &[CheckpointFormat::Json, CheckpointFormat::Binary] {
+        let mut builder = Builder::new();
+        builder.random_schedules = Some(10);
+        builder.random_seed = 17;
+        let expected = observe(&builder);
+
+        builder.checkpoint_format = Some(format);
+
+        // The state of the random generator is used, not the seed.
+        let mut resume = Builder::new();
+        resume.random_schedules = Some(8);
+        resume.random_seed = 5;
+        resume.checkpoint_format = Some(format);
+
+        assert_eq!(expected, observe_resumed("random.ckpt", builder, resume));
+    }
+}
+
+#[test]
+#[should_panic(expected = "was written by a random check")]
+fn reject_random_checkpoint_in_exhaustive_check() {
+    let mut builder = Builder::new();
+    builder.random_schedules = Some(10);
+
+    observe_resumed("random-mismatch.ckpt", builder, Builder::new());
+}
@@ -0,0 +1,96 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::explore::choice::{self, Budget, PollBoundary};
+use loom::sync::Arc;
+use loom::thread;
+
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+
+#[test]
+fn take_explores_every_order() {
+    let orders = loom::model_outcomes(|| {
+        let mut ready = vec![0, 1, 2];
+        let mut order = vec![];
+
+        while let Some(task) = choice::take(&mut ready) {
+            order.push(task);
+        }
+
+        order
+    });
+
+    assert_eq!(orders.len(), 6);
+    assert!(orders.iter().all(|order| order.len() == 3));
+}
+
+#[test]
+fn take_from_empty_does_not_branch() {
+    let stats = loom::model::Builder::new().check_returning_stats(|| {
+        let mut ready: Vec<usize> = vec![];
+        assert_eq!(choice::take(&mut ready), None);
+    });
+
+    assert_eq!(stats.iterations, 1);
+}
+
+#[test]
+fn one_of_explores_every_item() {
+    let picked = loom::model_outcomes(|| *choice::one_of(&["a", "b", "c"]));
+
+    assert_eq!(picked, ["a", "b", "c"].iter().copied().collect());
+}
+
+#[test]
+fn budget_explores_every_budget() {
+    let polls = loom::model_outcomes(|| {
+        let mut budget = Budget::explore(3);
+        let mut polls = 0;
+
+        while budget.consume() {
+            polls += 1;
+        }
+
+        assert_eq!(budget.remaining(), 0);
+        polls
+    });
+
+    assert_eq!(polls, [1, 2, 3].iter().copied().collect());
+}
+
+#[test]
+fn poll_boundaries_are_explored_in_every_order() {
+    // The workers only share state loom does not know about.
+    let orders = std::sync::Arc::new(Mutex::new(BTreeSet::new()));
+
+    {
+        let orders = orders.clone();
+
+        loom::model(move || {
+            let boundary = Arc::new(PollBoundary::new());
+            let polled = std::sync::Arc::new(Mutex::new(vec![]));
+
+            let workers: Vec<_> = (0..2)
+                .map(|worker| {
+                    let (boundary, polled) = (boundary.clone(), polled.clone());
+
+                    thread::spawn(move || {
+                        boundary.reached();
+                        polled.lock().unwrap().push(worker);
+                    })
+                })
+                .collect();
+
+            for worker in workers {
+                worker.join().unwrap();
+            }
+
+            let polled = polled.lock().unwrap().clone();
+            orders.lock().unwrap().insert(polled);
+        });
+    }
+
+    let orders = orders.lock().unwrap();
+    assert!(orders.contains(&vec![0, 1]));
+    assert!(orders.contains(&vec![1, 0]));
+}
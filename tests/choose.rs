@@ -0,0 +1,75 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::model::{Builder, Order};
+use loom::sync::atomic::AtomicUsize;
+use loom::sync::Arc;
+use loom::thread;
+
+use std::sync::atomic::Ordering::SeqCst;
+use std::sync::Mutex;
+
+fn explore(order: Order) -> Vec<(usize, bool)> {
+    let outcomes = std::sync::Arc::new(Mutex::new(vec![]));
+    let observed = outcomes.clone();
+
+    let mut builder = Builder::new();
+    builder.exploration_order(order);
+    builder.check(move || {
+        let n = loom::choose(3);
+        let b = loom::branch();
+        observed.lock().unwrap().push((n, b));
+    });
+
+    let outcomes = outcomes.lock().unwrap();
+    outcomes.clone()
+}
+
+#[test]
+fn choose_explores_every_value() {
+    let mut outcomes = explore(Order::Forward);
+    assert_eq!(6, outcomes.len());
+
+    outcomes.sort();
+    outcomes.dedup();
+    assert_eq!(6, outcomes.len());
+}
+
+#[test]
+fn choose_follows_exploration_order() {
+    let forward = explore(Order::Forward);
+    let mut reverse = explore(Order::Reverse);
+
+    assert_eq!((0, false), forward[0]);
+    assert_eq!((2, true), reverse[0]);
+
+    reverse.reverse();
+    assert_eq!(forward, reverse);
+}
+
+#[test]
+#[should_panic(expected = "assertion")]
+fn choose_with_threads() {
+    loom::model(|| {
+        let num = Arc::new(AtomicUsize::new(0));
+        let num2 = num.clone();
+
+        let th = thread::spawn(move || {
+            if loom::branch() {
+                num2.fetch_add(1, SeqCst);
+            }
+        });
+
+        num.fetch_add(1, SeqCst);
+        th.join().unwrap();
+
+        assert_eq!(2, num.load(SeqCst));
+    });
+}
+
+#[test]
+#[should_panic(expected = "cannot choose among zero options")]
+fn choose_zero() {
+    loom::model(|| {
+        loom::choose(0);
+    });
+}
@@ -0,0 +1,148 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use std::sync::atomic::Ordering::Relaxed;
+
+#[test]
+fn corr() {
+    // Two reads of the same thread observe stores in modification order.
+    loom::litmus! {
+        atomics: [x];
+        thread {
+            x.store(1, Relaxed);
+            x.store(2, Relaxed);
+        }
+        thread {
+            (x.load(Relaxed), x.load(Relaxed))
+        }
+        forbid (2, 1);
+        forbid (1, 0);
+        forbid (2, 0);
+        expect (0, 1);
+        expect (1, 2);
+        expect (0, 2);
+    };
+}
+
+#[test]
+fn corr_readers_agree_on_modification_order() {
+    // Stores by different threads are ordered, but both orders are explored.
+    loom::litmus! {
+        atomics: [x];
+        thread {
+            x.store(1, Relaxed);
+        }
+        thread {
+            x.store(2, Relaxed);
+        }
+        thread {
+            (x.load(Relaxed), x.load(Relaxed))
+        }
+        thread {
+            (x.load(Relaxed), x.load(Relaxed))
+        }
+        forbid (1, 2, 2, 1);
+        forbid (2, 1, 1, 2);
+        expect (1, 2, 1, 2);
+        expect (2, 1, 2, 1);
+    };
+}
+
+#[test]
+fn cowr() {
+    // A thread does not read a store older than its own.
+    loom::litmus! {
+        atomics: [x];
+        thread {
+            x.store(1, Relaxed);
+            x.load(Relaxed)
+        }
+        thread {
+            x.store(2, Relaxed);
+        }
+        forbid (0);
+        expect (1);
+        expect (2);
+    };
+}
+
+#[test]
+fn corw() {
+    // A store is later in modification order than the stores its thread read.
+    loom::litmus! {
+        atomics: [x];
+        thread {
+            let r0 = x.load(Relaxed);
+            x.store(1, Relaxed);
+            r0
+        }
+        thread {
+            x.store(2, Relaxed);
+        }
+        thread {
+            (x.load(Relaxed), x.load(Relaxed))
+        }
+        forbid (2, 1, 2);
+        expect (0, 1, 2);
+        expect (0, 2, 1);
+        expect (2, 2, 1);
+    };
+}
+
+#[test]
+fn rmws_read_latest_store() {
+    loom::litmus! {
+        atomics: [x];
+        thread {
+            x.fetch_add(1, Relaxed)
+        }
+        thread {
+            x.fetch_add(1, Relaxed)
+        }
+        forbid (0, 0);
+        expect (0, 1);
+        expect (1, 0);
+    };
+}
+
+#[test]
+fn rmw_is_atomic_with_concurrent_store() {
+    // A store ordered after the store an rmw read is ordered after the rmw.
+    loom::litmus! {
+        atomics: [x];
+        thread {
+            x.swap(1, Relaxed)
+        }
+        thread {
+            x.store(2, Relaxed);
+        }
+        thread {
+            (x.load(Relaxed), x.load(Relaxed))
+        }
+        forbid (0, 2, 1);
+        expect (0, 1, 2);
+        expect (2, 2, 1);
+    };
+}
+
+#[test]
+fn failing_compare_exchange_reads_stale_stores() {
+    // A failing compare-and-exchange is a load, it may read an older store.
+    loom::litmus! {
+        atomics: [x, y];
+        thread {
+            x.store(1, Relaxed);
+            x.store(2, Relaxed);
+            y.store(1, Relaxed);
+        }
+        thread {
+            let y = y.load(Relaxed);
+            match x.compare_exchange(2, 3, Relaxed, Relaxed) {
+                Ok(_) => (y, 3),
+                Err(actual) => (y, actual),
+            }
+        }
+        expect (1, 0);
+        expect (1, 1);
+        expect (1, 3);
+    };
+}
@@ -0,0 +1,72 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::sync::{Arc, Condvar, Mutex};
+use loom::thread;
+
+use std::collections::BTreeSet;
+
+#[derive(Default)]
+struct State {
+    waiting: usize,
+    permits: usize,
+    woken: Vec<usize>,
+}
+
+/// Makes two threads wait on a condvar, the first one before the second, and
+/// wakes them with `notify`. Returns the orders in which they woke up.
+fn wake_orders(notify: fn(&Mutex<State>, &Condvar)) -> BTreeSet<Vec<usize>> {
+    loom::model::model_outcomes(move || {
+        let pair = Arc::new((Mutex::new(State::default()), Condvar::new()));
+        let (lock, cvar) = &*pair;
+
+        let mut ths = vec![];
+
+        for i in 0..2 {
+            let pair = pair.clone();
+            ths.push(thread::spawn(move || {
+                let (lock, cvar) = &*pair;
+                let mut state = lock.lock().unwrap();
+                state.waiting += 1;
+
+                while state.permits == 0 {
+                    state = cvar.wait(state).unwrap();
+                }
+
+                state.permits -= 1;
+                state.woken.push(i);
+            }));
+
+            // Wait for the thread to block on the condvar
+            while lock.lock().unwrap().waiting == i {
+                thread::yield_now();
+            }
+        }
+
+        notify(lock, cvar);
+
+        for th in ths {
+            th.join().unwrap();
+        }
+
+        let woken = lock.lock().unwrap().woken.clone();
+        woken
+    })
+}
+
+#[test]
+fn notify_one_wakes_any_waiter() {
+    let orders = wake_orders(|lock, cvar| {
+        for _ in 0..2 {
+            lock.lock().unwrap().permits = 1;
+            cvar.notify_one();
+
+            // Wait for the woken thread to take the permit
+            while lock.lock().unwrap().permits == 1 {
+                thread::yield_now();
+            }
+        }
+    });
+
+    let both: BTreeSet<_> = vec![vec![0, 1], vec![1, 0]].into_iter().collect();
+    assert_eq!(both, orders);
+}
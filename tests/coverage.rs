@@ -0,0 +1,87 @@
+#![cfg(feature = "coverage")]
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::explore::Coverage;
+use loom::model::Builder;
+use loom::sync::atomic::AtomicUsize;
+use loom::sync::{Arc, Mutex};
+use loom::thread;
+
+use std::path::PathBuf;
+use std::sync::atomic::Ordering::SeqCst;
+
+fn coverage_file(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("loom-{}-{}", std::process::id(), name));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn observe_intermediate() {
+    let num = Arc::new(AtomicUsize::new(0));
+    let num2 = num.clone();
+
+    let th = thread::spawn(move || {
+        num2.store(1, SeqCst);
+        num2.store(2, SeqCst);
+    });
+
+    if num.load(SeqCst) == 1 {
+        loom::explore::mark("intermediate");
+    }
+
+    th.join().unwrap();
+}
+
+fn observe_intermediate_locked() {
+    let num = Arc::new(Mutex::new(0));
+    let num2 = num.clone();
+
+    let th = thread::spawn(move || {
+        let mut num = num2.lock().unwrap();
+        *num = 1;
+        *num = 2;
+    });
+
+    if *num.lock().unwrap() == 1 {
+        loom::explore::mark("intermediate");
+    }
+
+    th.join().unwrap();
+}
+
+#[test]
+fn coverage_file_round_trips() {
+    let path = coverage_file("coverage-round-trip.json");
+
+    let mut builder = Builder::new();
+    builder.profile_branches = true;
+    builder.coverage_file(path.to_str().unwrap());
+
+    let stats = builder.check_returning_stats(observe_intermediate);
+    let coverage = Coverage::load(&path).unwrap();
+
+    assert_eq!(stats.coverage(), coverage);
+    assert!(coverage.marks["intermediate"] > 0);
+    assert!(coverage.branches.contains_key("atomic #0"));
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn added_synchronization_loses_coverage() {
+    let path = coverage_file("coverage-baseline.json");
+
+    let mut builder = Builder::new();
+    builder.coverage_file(path.to_str().unwrap());
+    builder.check(observe_intermediate);
+
+    let baseline = Coverage::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let coverage = Builder::new()
+        .check_returning_stats(observe_intermediate_locked)
+        .coverage();
+
+    assert!(baseline.lost(&baseline).is_empty());
+    assert_eq!(coverage.lost(&baseline), ["mark `intermediate`"]);
+}
@@ -0,0 +1,118 @@
+#![cfg(feature = "custom")]
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::custom::{Action, Object};
+use loom::thread;
+
+use std::collections::BTreeSet;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering::SeqCst;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Write,
+    Read,
+}
+
+impl Action for Op {
+    const ALL: &'static [Op] = &[Op::Write, Op::Read];
+
+    fn is_dependent(self, other: Op) -> bool {
+        self == Op::Write || other == Op::Write
+    }
+}
+
+/// A register modeled directly, without loom atomics.
+struct Register {
+    object: Object<Op>,
+    value: AtomicUsize,
+}
+
+impl Register {
+    fn new() -> Register {
+        Register {
+            object: Object::new("Register"),
+            value: AtomicUsize::new(0),
+        }
+    }
+
+    fn write(&self, value: usize) {
+        self.object.branch(Op::Write);
+        self.value.store(value, SeqCst);
+    }
+
+    fn read(&self) -> usize {
+        self.object.branch(Op::Read);
+        self.value.load(SeqCst)
+    }
+}
+
+#[test]
+fn dependent_actions_are_explored_in_both_orders() {
+    let outcomes = loom::model_outcomes(|| {
+        let register = Arc::new(Register::new());
+
+        let th = {
+            let register = register.clone();
+            thread::spawn(move || register.write(1))
+        };
+
+        let value = register.read();
+        th.join().unwrap();
+        value
+    });
+
+    let expected: BTreeSet<_> = vec![0, 1].into_iter().collect();
+    assert_eq!(expected, outcomes);
+}
+
+#[test]
+fn independent_actions_are_not_permuted() {
+    let iterations = Arc::new(AtomicUsize::new(0));
+    let iterations2 = iterations.clone();
+
+    loom::model(move || {
+        iterations2.fetch_add(1, SeqCst);
+
+        let register = Arc::new(Register::new());
+
+        let th = {
+            let register = register.clone();
+            thread::spawn(move || register.read())
+        };
+
+        register.read();
+        th.join().unwrap();
+    });
+
+    assert_eq!(1, iterations.load(SeqCst));
+}
+
+#[test]
+fn release_acquire_transfers_causality() {
+    loom::model(|| {
+        let object = Arc::new(Object::<Op>::new("Flag"));
+        let data = loom::sync::Arc::new(loom::cell::UnsafeCell::new(0));
+        let ready = Arc::new(AtomicUsize::new(0));
+
+        let th = {
+            let (object, data, ready) = (object.clone(), data.clone(), ready.clone());
+
+            thread::spawn(move || {
+                data.with_mut(|ptr| unsafe { *ptr = 1 });
+                object.branch(Op::Write);
+                object.release();
+                ready.store(1, SeqCst);
+            })
+        };
+
+        object.branch(Op::Read);
+        if ready.load(SeqCst) == 1 {
+            object.acquire();
+            assert_eq!(1, data.with(|ptr| unsafe { *ptr }));
+        }
+
+        th.join().unwrap();
+    });
+}
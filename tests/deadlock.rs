@@ -0,0 +1,101 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::model::Builder;
+use loom::sync::{Arc, Condvar, Mutex};
+use loom::thread;
+
+use std::panic;
+
+#[test]
+#[should_panic(expected = "wait-for cycle: thread #0 -> thread #1 -> thread #0")]
+fn lock_order_inversion() {
+    loom::model(|| {
+        let a = Arc::new(Mutex::new(()));
+        let b = Arc::new(Mutex::new(()));
+
+        let th = {
+            let (a, b) = (a.clone(), b.clone());
+            thread::spawn(move || {
+                let _b = b.lock().unwrap();
+                let _a = a.lock().unwrap();
+            })
+        };
+
+        {
+            let _a = a.lock().unwrap();
+            let _b = b.lock().unwrap();
+        }
+
+        th.join().unwrap();
+    });
+}
+
+#[test]
+#[should_panic(expected = "thread #0: waiting to join thread; blocked by thread #1")]
+fn join_while_holding_lock() {
+    loom::model(|| {
+        let lock = Arc::new(Mutex::new(()));
+
+        let guard = lock.lock().unwrap();
+
+        let th = {
+            let lock = lock.clone();
+            thread::spawn(move || {
+                let _guard = lock.lock().unwrap();
+            })
+        };
+
+        th.join().unwrap();
+        drop(guard);
+    });
+}
+
+#[test]
+#[should_panic(expected = "thread #0: waiting to be notified by Condvar")]
+fn wait_without_notify() {
+    loom::model(|| {
+        let lock = Mutex::new(());
+        let cond = Condvar::new();
+
+        let guard = lock.lock().unwrap();
+        let _guard = cond.wait(guard).unwrap();
+    });
+}
+
+#[test]
+fn report_includes_locations() {
+    let mut builder = Builder::new();
+    builder.location = true;
+
+    let err = panic::catch_unwind(|| {
+        builder.check(|| {
+            let lock = Arc::new(Mutex::new(()));
+
+            let guard = lock.lock().unwrap();
+
+            let th = {
+                let lock = lock.clone();
+                thread::spawn(move || {
+                    let _guard = lock.lock().unwrap();
+                })
+            };
+
+            th.join().unwrap();
+            drop(guard);
+        });
+    })
+    .unwrap_err();
+
+    let msg = err.downcast_ref::<String>().unwrap();
+
+    assert!(
+        msg.contains("thread #1: waiting to lock Mutex created at tests/deadlock.rs:72:"),
+        "{}",
+        msg
+    );
+    assert!(
+        msg.contains("blocked by thread #0 (acquired at tests/deadlock.rs:74:"),
+        "{}",
+        msg
+    );
+}
@@ -0,0 +1,104 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::sync::atomic::AtomicUsize;
+use loom::sync::Arc;
+use loom::thread;
+
+use std::sync::atomic::Ordering::{Relaxed, SeqCst};
+
+#[test]
+#[should_panic(expected = "model is non-deterministic: execution diverged from the recorded path")]
+fn leaking_static_is_detected() {
+    static ITERATIONS: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+    let mut builder = loom::model::Builder::new();
+    builder.check_determinism = true;
+
+    builder.check(|| {
+        let num = Arc::new(AtomicUsize::new(0));
+
+        // Only the first execution takes the extra branch point.
+        if ITERATIONS.fetch_add(1, Relaxed) == 0 {
+            num.load(SeqCst);
+        }
+
+        let num2 = num.clone();
+
+        let th = thread::spawn(move || {
+            num2.fetch_add(1, SeqCst);
+        });
+
+        num.fetch_add(1, SeqCst);
+        th.join().unwrap();
+    });
+}
+
+#[test]
+fn deterministic_model_passes() {
+    let mut builder = loom::model::Builder::new();
+    builder.check_determinism = true;
+
+    builder.check(|| {
+        let num = Arc::new(AtomicUsize::new(0));
+        let num2 = num.clone();
+
+        let th = thread::spawn(move || {
+            num2.fetch_add(1, SeqCst);
+        });
+
+        num.fetch_add(1, SeqCst);
+        th.join().unwrap();
+
+        assert_eq!(2, num.load(SeqCst));
+    });
+}
+
+#[test]
+#[should_panic(expected = "model is not isolated: the first execution took a different branch")]
+fn state_shared_between_executions_is_detected() {
+    let executions = std::sync::Arc::new(std::sync::Mutex::new(0));
+
+    let mut builder = loom::model::Builder::new();
+    builder.check_isolation = true;
+
+    builder.check(move || {
+        let num = Arc::new(AtomicUsize::new(0));
+        let num2 = num.clone();
+
+        let th = thread::spawn(move || {
+            num2.fetch_add(1, SeqCst);
+        });
+
+        num.fetch_add(1, SeqCst);
+        th.join().unwrap();
+
+        // Only the first execution takes the extra branch point, at its end.
+        let mut executions = executions.lock().unwrap();
+
+        if *executions == 0 {
+            num.load(SeqCst);
+        }
+
+        *executions += 1;
+    });
+}
+
+#[test]
+fn isolated_model_passes() {
+    let mut builder = loom::model::Builder::new();
+    builder.check_isolation = true;
+
+    let stats = builder.check_returning_stats(|| {
+        let num = Arc::new(AtomicUsize::new(0));
+        let num2 = num.clone();
+
+        let th = thread::spawn(move || {
+            num2.fetch_add(1, SeqCst);
+        });
+
+        num.fetch_add(1, SeqCst);
+        th.join().unwrap();
+    });
+
+    assert!(stats.iterations > 1);
+}
@@ -0,0 +1,130 @@
+#![cfg(feature = "epoch")]
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::epoch::{self, Atomic, Owned};
+use loom::sync::Arc;
+use loom::thread;
+
+use std::sync::atomic::Ordering::{AcqRel, Acquire};
+
+#[test]
+fn defer_destroy_after_unlink() {
+    loom::model(|| {
+        let ptr = Arc::new(Atomic::new(1));
+        let ptr2 = ptr.clone();
+
+        let th = thread::spawn(move || {
+            let guard = epoch::pin();
+            let shared = ptr2.load(Acquire, &guard);
+
+            if let Some(v) = unsafe { shared.as_ref() } {
+                assert!(*v == 1 || *v == 2);
+            }
+        });
+
+        {
+            let guard = epoch::pin();
+            let prev = ptr.swap(Owned::new(2), AcqRel, &guard);
+            unsafe { guard.defer_destroy(prev) };
+        }
+
+        th.join().unwrap();
+
+        let guard = epoch::pin();
+        let last = ptr.swap(epoch::Shared::null(), AcqRel, &guard);
+        unsafe { guard.defer_destroy(last) };
+    });
+}
+
+#[test]
+#[should_panic(expected = "use after free")]
+fn destroy_while_reachable() {
+    loom::model(|| {
+        let ptr = Arc::new(Atomic::new(1));
+        let ptr2 = ptr.clone();
+
+        let th = thread::spawn(move || {
+            let guard = epoch::pin();
+            let shared = ptr2.load(Acquire, &guard);
+            assert_eq!(1, unsafe { *shared.deref() });
+        });
+
+        {
+            // Bug: the object is destroyed without being unlinked first.
+            let guard = epoch::pin();
+            let shared = ptr.load(Acquire, &guard);
+            unsafe { guard.defer_destroy(shared) };
+        }
+
+        th.join().unwrap();
+
+        let guard = epoch::pin();
+        let _ = ptr.swap(epoch::Shared::null(), AcqRel, &guard);
+    });
+}
+
+#[test]
+#[should_panic(expected = "use after free")]
+fn use_after_free_is_not_returned_by_join() {
+    loom::model(|| {
+        let ptr = Arc::new(Atomic::new(1));
+        let ptr2 = ptr.clone();
+
+        let th = thread::spawn(move || {
+            let guard = epoch::pin();
+            let shared = ptr2.load(Acquire, &guard);
+            assert_eq!(1, unsafe { *shared.deref() });
+        });
+
+        {
+            let guard = epoch::pin();
+            let shared = ptr.load(Acquire, &guard);
+            unsafe { guard.defer_destroy(shared) };
+        }
+
+        // The use after free fails the model even though the result is
+        // ignored.
+        let _ = th.join();
+
+        let guard = epoch::pin();
+        let _ = ptr.swap(epoch::Shared::null(), AcqRel, &guard);
+    });
+}
+
+#[test]
+#[should_panic(expected = "leaked")]
+fn unlinked_without_destroy() {
+    loom::model(|| {
+        let ptr = Atomic::new(1);
+
+        let guard = epoch::pin();
+        let _ = ptr.swap(epoch::Shared::null(), AcqRel, &guard);
+    });
+}
+
+#[test]
+fn guard_keeps_object_alive() {
+    loom::model(|| {
+        let ptr = Arc::new(Atomic::new(1));
+        let ptr2 = ptr.clone();
+
+        let guard = epoch::pin();
+        let shared = ptr.load(Acquire, &guard);
+
+        let th = thread::spawn(move || {
+            let guard = epoch::pin();
+            let prev = ptr2.swap(Owned::new(2), AcqRel, &guard);
+            unsafe { guard.defer_destroy(prev) };
+        });
+
+        th.join().unwrap();
+
+        // Still pinned, the object may not have been destroyed.
+        assert_eq!(1, unsafe { *shared.deref() });
+        drop(guard);
+
+        let guard = epoch::pin();
+        let last = ptr.swap(epoch::Shared::null(), AcqRel, &guard);
+        unsafe { guard.defer_destroy(last) };
+    });
+}
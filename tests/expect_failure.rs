@@ -0,0 +1,87 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::cell::UnsafeCell;
+use loom::model::Builder;
+use loom::sync::atomic::AtomicUsize;
+use loom::sync::Arc;
+use loom::thread;
+
+use std::sync::atomic::Ordering::SeqCst;
+
+fn lost_update() {
+    let num = Arc::new(AtomicUsize::new(0));
+    let num2 = num.clone();
+
+    let th = thread::spawn(move || {
+        let val = num2.load(SeqCst);
+        num2.store(val + 1, SeqCst);
+    });
+
+    let val = num.load(SeqCst);
+    num.store(val + 1, SeqCst);
+
+    th.join().unwrap();
+
+    assert_eq!(2, num.load(SeqCst), "lost update");
+}
+
+#[test]
+fn expected_failure_is_returned() {
+    let failure = Builder::new().check_expect_failure("lost update", lost_update);
+
+    assert!(failure.message.unwrap().contains("lost update"));
+    assert!(failure.iteration > 1);
+    assert!(failure
+        .schedule
+        .iter()
+        .any(|choice| choice.ends_with("switch to thread #1")));
+}
+
+#[test]
+#[should_panic(expected = "model was expected to fail, but all 1 executions succeeded")]
+fn passing_model_fails() {
+    Builder::new().check_expect_failure("", || {});
+}
+
+#[test]
+#[should_panic(expected = "model failed, but not with the expected message")]
+fn unexpected_message_fails() {
+    Builder::new().check_expect_failure("deadlock", lost_update);
+}
+
+fn racy_cell() {
+    let cell = Arc::new(UnsafeCell::new(0));
+    let cell2 = cell.clone();
+
+    let th = thread::spawn(move || cell2.with_mut(|ptr| unsafe { *ptr = 1 }));
+
+    cell.with(|ptr| unsafe { *ptr });
+    th.join().unwrap();
+}
+
+#[test]
+fn failure_is_replayed_with_locations() {
+    let mut builder = Builder::new();
+    builder.locate_failures = true;
+
+    let failure = builder.check_expect_failure("Causality violation", racy_cell);
+    let message = failure.message.unwrap();
+
+    assert!(
+        message.contains("@ tests/expect_failure.rs:"),
+        "{}",
+        message
+    );
+}
+
+#[test]
+fn failure_without_locations() {
+    let failure = Builder::new().check_expect_failure("Causality violation", racy_cell);
+    let message = failure.message.unwrap();
+
+    assert!(
+        message.contains("Run with `LOOM_LOCATION=1`"),
+        "{}",
+        message
+    );
+}
@@ -0,0 +1,41 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::sync::atomic::{self, AtomicUsize};
+use loom::sync::Arc;
+use loom::thread;
+
+use std::sync::atomic::Ordering::{Acquire, Relaxed, Release, SeqCst};
+
+#[test]
+fn explain_returns_the_loaded_value() {
+    loom::model(|| {
+        let num = Arc::new(AtomicUsize::new(0));
+        let num2 = num.clone();
+
+        let th = thread::spawn(move || {
+            num2.store(1, Release);
+            num2.store(2, Relaxed);
+        });
+
+        let v = atomic::explain(|| num.load(Acquire));
+        assert!(v <= 2);
+
+        th.join().unwrap();
+        assert_eq!(2, atomic::explain(|| num.load(SeqCst)));
+    });
+}
+
+#[test]
+fn explain_is_scoped_to_the_closure() {
+    loom::model(|| {
+        let num = AtomicUsize::new(0);
+
+        atomic::explain(|| {
+            num.store(1, Relaxed);
+            atomic::explain(|| num.load(Relaxed));
+            num.load(Relaxed)
+        });
+
+        assert_eq!(1, num.load(Relaxed));
+    });
+}
@@ -0,0 +1,105 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::model::{Builder, Order};
+use loom::sync::atomic::AtomicUsize;
+use loom::sync::Arc;
+use loom::thread;
+
+use std::sync::atomic::Ordering::{Relaxed, SeqCst};
+use std::sync::Arc as StdArc;
+use std::sync::Mutex;
+
+/// Returns the outcome of every execution, in the order they were explored.
+fn explore(order: Order) -> Vec<(usize, usize)> {
+    let outcomes = StdArc::new(Mutex::new(vec![]));
+    let observed = outcomes.clone();
+
+    let mut builder = Builder::new();
+    builder.exploration_order(order);
+    builder.check(move || {
+        let num = Arc::new(AtomicUsize::new(0));
+        let num2 = num.clone();
+
+        let th = thread::spawn(move || {
+            num2.store(1, Relaxed);
+            num2.store(2, Relaxed);
+        });
+
+        let a = num.load(Relaxed);
+        let b = num.load(SeqCst);
+
+        th.join().unwrap();
+
+        observed.lock().unwrap().push((a, b));
+    });
+
+    let outcomes = outcomes.lock().unwrap();
+    outcomes.clone()
+}
+
+fn sorted<T: Ord>(mut outcomes: Vec<T>) -> Vec<T> {
+    outcomes.sort();
+    outcomes
+}
+
+#[test]
+fn reverse_explores_same_executions() {
+    let forward = explore(Order::Forward);
+    let reverse = explore(Order::Reverse);
+
+    assert_ne!(forward, reverse);
+    assert_eq!(forward.first(), reverse.last());
+    assert_eq!(sorted(forward), sorted(reverse));
+}
+
+#[test]
+fn randomized_explores_same_executions() {
+    let forward = explore(Order::Forward);
+    let randomized = explore(Order::RandomizedSeed(42));
+
+    assert_eq!(randomized, explore(Order::RandomizedSeed(42)));
+    assert_eq!(sorted(forward), sorted(randomized));
+}
+
+/// Returns the order in which the threads ran in every execution.
+fn explore_interleavings(prefer_unfair: bool) -> Vec<String> {
+    let outcomes = StdArc::new(Mutex::new(vec![]));
+    let observed = outcomes.clone();
+
+    let mut builder = Builder::new();
+    builder.prefer_unfair_schedules(prefer_unfair);
+    // Sleep sets skip equivalent schedules, which one is explored depends on
+    // the order.
+    builder.sleep_sets = false;
+    builder.check(move || {
+        let log = Arc::new(loom::sync::Mutex::new(String::new()));
+
+        let ths: Vec<_> = ["a", "b", "c"]
+            .iter()
+            .map(|name| {
+                let log = log.clone();
+
+                thread::spawn(move || log.lock().unwrap().push_str(name))
+            })
+            .collect();
+
+        for th in ths {
+            th.join().unwrap();
+        }
+
+        let log = log.lock().unwrap().clone();
+        observed.lock().unwrap().push(log);
+    });
+
+    let outcomes = outcomes.lock().unwrap();
+    outcomes.clone()
+}
+
+#[test]
+fn prefer_unfair_explores_same_executions() {
+    let fair = explore_interleavings(false);
+    let unfair = explore_interleavings(true);
+
+    assert_ne!(fair, unfair);
+    assert_eq!(sorted(fair), sorted(unfair));
+}
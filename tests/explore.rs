@@ -0,0 +1,61 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::model::Builder;
+use loom::sync::atomic::AtomicUsize;
+use loom::sync::{Arc, Mutex};
+use loom::thread;
+
+use std::sync::atomic::Ordering::SeqCst;
+
+#[test]
+fn required_mark_is_hit() {
+    let mut builder = Builder::new();
+    builder.require_marks(&["intermediate"]);
+
+    let stats = builder.check_returning_stats(|| {
+        let num = Arc::new(AtomicUsize::new(0));
+        let num2 = num.clone();
+
+        let th = thread::spawn(move || {
+            num2.store(1, SeqCst);
+            num2.store(2, SeqCst);
+        });
+
+        match num.load(SeqCst) {
+            1 => loom::explore::mark("intermediate"),
+            _ => loom::explore::mark("other"),
+        }
+
+        th.join().unwrap();
+    });
+
+    let intermediate = stats.marks["intermediate"];
+    let other = stats.marks["other"];
+
+    assert!(intermediate > 0);
+    assert_eq!(stats.iterations, intermediate + other);
+}
+
+#[test]
+#[should_panic(expected = "coverage marks [\"intermediate\"] were not hit")]
+fn unreachable_mark_fails() {
+    let mut builder = Builder::new();
+    builder.require_marks(&["intermediate"]);
+
+    builder.check(|| {
+        let num = Arc::new(Mutex::new(0));
+        let num2 = num.clone();
+
+        let th = thread::spawn(move || {
+            let mut num = num2.lock().unwrap();
+            *num = 1;
+            *num = 2;
+        });
+
+        if *num.lock().unwrap() == 1 {
+            loom::explore::mark("intermediate");
+        }
+
+        th.join().unwrap();
+    });
+}
@@ -0,0 +1,58 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::facade::cell::{Cell, RefCell, UnsafeCell};
+use loom::facade::sync::atomic::{AtomicUsize, Ordering::SeqCst};
+use loom::facade::sync::{Arc, Mutex};
+use loom::facade::thread;
+
+loom::facade::lazy_static! {
+    static ref NUM: AtomicUsize = AtomicUsize::new(0);
+}
+
+loom::facade::thread_local! {
+    static LOCAL: UnsafeCell<usize> = UnsafeCell::new(0);
+}
+
+#[test]
+fn same_body_builds_both_ways() {
+    loom::facade::model(|| {
+        let data = Arc::new(Mutex::new(0));
+        let th = {
+            let data = data.clone();
+            thread::spawn(move || {
+                NUM.fetch_add(1, SeqCst);
+                *data.lock().unwrap() += 1;
+            })
+        };
+
+        LOCAL.with(|local| local.with_mut(|v| unsafe { *v += 1 }));
+        *data.lock().unwrap() += 1;
+
+        let count = Cell::new(1);
+        let list = RefCell::new(vec![]);
+        list.borrow_mut().push(count.replace(2));
+        assert_eq!(*list.borrow(), [1]);
+
+        th.join().unwrap();
+
+        assert_eq!(*data.lock().unwrap(), 2);
+        assert!(NUM.load(SeqCst) >= 1);
+    });
+}
+
+#[test]
+#[cfg(loom)]
+fn uses_loom_types() {
+    loom::model(|| {
+        let _: loom::sync::Mutex<()> = loom::facade::sync::Mutex::new(());
+        let _: loom::cell::UnsafeCell<()> = loom::facade::cell::UnsafeCell::new(());
+        let _: loom::cell::RefCell<()> = loom::facade::cell::RefCell::new(());
+    });
+}
+
+#[test]
+#[cfg(not(loom))]
+fn uses_std_types() {
+    let _: std::sync::Mutex<()> = loom::facade::sync::Mutex::new(());
+    loom::facade::model(|| {});
+}
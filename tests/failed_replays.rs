@@ -0,0 +1,61 @@
+#![cfg(target_os = "linux")]
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::model::{Builder, Runner};
+use loom::sync::atomic::AtomicUsize;
+use loom::sync::Arc;
+use loom::thread;
+
+use std::sync::atomic::Ordering::SeqCst;
+
+/// Returns the number of OS threads of the process.
+fn os_threads() -> usize {
+    let status = std::fs::read_to_string("/proc/self/status").unwrap();
+
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("Threads:"))
+        .unwrap()
+        .trim()
+        .parse()
+        .unwrap()
+}
+
+// The only test of this file, so that no other test spawns threads while the
+// threads are counted.
+#[test]
+fn failed_executions_release_os_threads() {
+    let mut builder = Builder::new();
+    builder.minimize = true;
+    builder.runner(Runner::OsThreads {
+        stack_size: 1024 * 1024,
+    });
+
+    let before = os_threads();
+
+    let res = std::panic::catch_unwind(|| {
+        builder.check(|| {
+            let num = Arc::new(AtomicUsize::new(0));
+
+            let ths: Vec<_> = (0..2)
+                .map(|_| {
+                    let num = num.clone();
+                    thread::spawn(move || {
+                        let v = num.load(SeqCst);
+                        num.store(v + 1, SeqCst);
+                    })
+                })
+                .collect();
+
+            for th in ths {
+                th.join().unwrap();
+            }
+
+            // Fails once every thread completed: none is suspended.
+            assert_eq!(2, num.load(SeqCst));
+        });
+    });
+
+    assert!(res.is_err());
+    assert_eq!(before, os_threads());
+}
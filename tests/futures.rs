@@ -2,6 +2,7 @@
 #![deny(warnings, rust_2018_idioms)]
 
 use loom::future::{block_on, AtomicWaker};
+use loom::model::{Builder, MainExit};
 use loom::sync::atomic::AtomicUsize;
 use loom::thread;
 
@@ -90,3 +91,243 @@ fn spurious_poll() {
 
     assert!(actual.load(Acquire));
 }
+
+#[test]
+fn spawn_join() {
+    loom::model(|| {
+        let num = Arc::new(AtomicUsize::new(0));
+        let num2 = num.clone();
+
+        let handle = loom::future::spawn(async move { num2.fetch_add(1, Relaxed) });
+        let prev = num.fetch_add(1, Relaxed);
+
+        let output = block_on(handle);
+
+        assert_eq!(1, prev + output);
+        assert_eq!(2, num.load(Relaxed));
+    });
+}
+
+#[test]
+fn spawn_join_requires_no_running_threads() {
+    let mut builder = Builder::new();
+    builder.main_exit(MainExit::RequireJoined);
+    builder.check(|| {
+        assert_eq!(1, block_on(loom::future::spawn(async { 1 })));
+    });
+}
+
+#[test]
+#[should_panic]
+fn spawn_finds_lost_update() {
+    loom::model(|| {
+        let num = Arc::new(AtomicUsize::new(0));
+
+        let handles: Vec<_> = (0..2)
+            .map(|_| {
+                let num = num.clone();
+
+                loom::future::spawn(async move {
+                    let curr = num.load(Relaxed);
+                    num.store(curr + 1, Relaxed);
+                })
+            })
+            .collect();
+
+        block_on(async move {
+            for handle in handles {
+                handle.await;
+            }
+        });
+
+        assert_eq!(2, num.load(Relaxed));
+    });
+}
+
+#[test]
+fn noop_waker_polls() {
+    use std::future::Future;
+    use std::task::Context;
+
+    loom::model(|| {
+        let waker = loom::future::noop_waker();
+        let mut cx = Context::from_waker(&waker);
+
+        let fut = async { 1 };
+        futures_util::pin_mut!(fut);
+
+        assert_eq!(Poll::Ready(1), fut.as_mut().poll(&mut cx));
+        waker.wake();
+    });
+}
+
+#[test]
+fn count_waker_counts_wakes() {
+    loom::model(|| {
+        let (waker, count) = loom::future::count_waker();
+        let other = waker.clone();
+
+        let th = thread::spawn(move || other.wake());
+
+        waker.wake_by_ref();
+        let seen = count.get();
+        assert!(seen == 1 || seen == 2);
+
+        th.join().unwrap();
+        assert_eq!(2, count.get());
+    });
+}
+
+// The lost-wakeup bug below is found because waking races with the registration
+// of the waker.
+#[test]
+#[should_panic]
+fn lost_wakeup() {
+    use loom::sync::atomic::AtomicBool;
+    use loom::sync::atomic::Ordering::SeqCst;
+    use loom::sync::Mutex;
+
+    loom::model(|| {
+        let ready = Arc::new(AtomicBool::new(false));
+        let registered = Arc::new(Mutex::new(None::<std::task::Waker>));
+
+        let th = {
+            let ready = ready.clone();
+            let registered = registered.clone();
+
+            thread::spawn(move || {
+                // BUG: the waker is taken before the flag is set, a concurrent
+                // registration is never woken.
+                let waker = registered.lock().unwrap().take();
+                ready.store(true, SeqCst);
+
+                if let Some(waker) = waker {
+                    waker.wake();
+                }
+            })
+        };
+
+        let (waker, count) = loom::future::count_waker();
+
+        *registered.lock().unwrap() = Some(waker);
+        let pending = !ready.load(SeqCst);
+
+        th.join().unwrap();
+
+        if pending {
+            assert_eq!(1, count.get(), "lost wake-up");
+        }
+    });
+}
+
+#[test]
+fn mutex_tasks_increment() {
+    use loom::future::Mutex;
+
+    loom::model(|| {
+        let num = Arc::new(Mutex::new(0));
+
+        let th = {
+            let num = num.clone();
+            thread::spawn(move || block_on(async move { *num.lock().await += 1 }))
+        };
+
+        block_on(async {
+            *num.lock().await += 1;
+        });
+
+        th.join().unwrap();
+
+        assert_eq!(2, *num.try_lock().unwrap());
+    });
+}
+
+// A lock future dropped after being woken passes the wake-up on to another
+// waiting task.
+#[test]
+fn mutex_dropped_lock_future_passes_wakeup() {
+    use futures_util::FutureExt;
+    use loom::future::Mutex;
+    use std::task::Context;
+
+    loom::model(|| {
+        let mutex = Arc::new(Mutex::new(()));
+        let guard = mutex.try_lock().unwrap();
+
+        let quitter = {
+            let mutex = mutex.clone();
+
+            thread::spawn(move || {
+                let waker = loom::future::noop_waker();
+                let mut lock = mutex.lock();
+
+                if lock
+                    .poll_unpin(&mut Context::from_waker(&waker))
+                    .is_pending()
+                {
+                    // Gives up on the lock, possibly after being woken.
+                    thread::yield_now();
+                }
+            })
+        };
+
+        let (waker, count) = loom::future::count_waker();
+        let mut lock = mutex.lock();
+
+        assert!(lock
+            .poll_unpin(&mut Context::from_waker(&waker))
+            .is_pending());
+
+        drop(guard);
+        quitter.join().unwrap();
+
+        assert_eq!(1, count.get(), "lost wake-up");
+    });
+}
+
+#[test]
+fn oneshot_send_recv() {
+    use loom::future::oneshot;
+
+    loom::model(|| {
+        let (tx, rx) = oneshot::channel();
+
+        let th = thread::spawn(move || tx.send(1).unwrap());
+
+        assert_eq!(Ok(1), block_on(rx));
+        th.join().unwrap();
+    });
+}
+
+#[test]
+fn oneshot_sender_dropped() {
+    use loom::future::oneshot;
+
+    loom::model(|| {
+        let (tx, rx) = oneshot::channel::<usize>();
+
+        let th = thread::spawn(move || drop(tx));
+
+        assert_eq!(Err(oneshot::Canceled), block_on(rx));
+        th.join().unwrap();
+    });
+}
+
+#[test]
+fn oneshot_receiver_dropped() {
+    use loom::future::oneshot;
+
+    let outcomes = loom::model_outcomes(|| {
+        let (tx, rx) = oneshot::channel();
+
+        let th = thread::spawn(move || drop(rx));
+
+        let res = tx.send(1);
+        th.join().unwrap();
+        res
+    });
+
+    // The value is returned if the receiver is dropped first.
+    assert_eq!(2, outcomes.len());
+    assert!(outcomes.contains(&Err(1)));
+}
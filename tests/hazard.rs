@@ -0,0 +1,82 @@
+#![cfg(feature = "hazard")]
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::cell::UnsafeCell;
+use loom::hazard::{self, HazardPointer};
+use loom::sync::atomic::AtomicPtr;
+use loom::sync::Arc;
+use loom::thread;
+
+use std::sync::atomic::Ordering::{AcqRel, Acquire};
+
+struct Node {
+    value: UnsafeCell<usize>,
+}
+
+fn node(value: usize) -> *mut Node {
+    hazard::alloc(Node {
+        value: UnsafeCell::new(value),
+    })
+}
+
+fn read(node: *mut Node) -> usize {
+    unsafe { (*node).value.with(|value| *value) }
+}
+
+#[test]
+fn retire_after_unlink() {
+    loom::model(|| {
+        let ptr = Arc::new(AtomicPtr::new(node(1)));
+        let ptr2 = ptr.clone();
+
+        let th = thread::spawn(move || {
+            let mut hp = HazardPointer::new();
+            let node = hp.protect(&ptr2);
+
+            let value = read(node);
+            assert!(value == 1 || value == 2);
+        });
+
+        let prev = ptr.swap(node(2), AcqRel);
+        unsafe { hazard::retire(prev) };
+
+        th.join().unwrap();
+
+        unsafe { hazard::retire(ptr.load(Acquire)) };
+        hazard::reclaim();
+    });
+}
+
+#[test]
+#[should_panic(expected = "Use after free")]
+fn access_without_protection() {
+    loom::model(|| {
+        let ptr = Arc::new(AtomicPtr::new(node(1)));
+        let ptr2 = ptr.clone();
+
+        let th = thread::spawn(move || {
+            read(ptr2.load(Acquire));
+        });
+
+        let prev = ptr.swap(node(2), AcqRel);
+        unsafe { hazard::retire(prev) };
+
+        th.join().unwrap();
+
+        unsafe { hazard::retire(ptr.load(Acquire)) };
+    });
+}
+
+#[test]
+#[should_panic(expected = "leaked")]
+fn retired_while_protected_without_reclaim() {
+    loom::model(|| {
+        let ptr = AtomicPtr::new(node(1));
+
+        let mut hp = HazardPointer::new();
+        let node = hp.protect(&ptr);
+
+        unsafe { hazard::retire(node) };
+        drop(hp);
+    });
+}
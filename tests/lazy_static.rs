@@ -0,0 +1,100 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::sync::atomic::AtomicUsize;
+use loom::sync::Arc;
+use loom::thread;
+
+use std::sync::atomic::Ordering::{Relaxed, SeqCst};
+
+#[test]
+fn dependent_statics() {
+    loom::lazy_static! {
+        static ref BASE: Arc<AtomicUsize> = Arc::new(AtomicUsize::new(1));
+        static ref DERIVED: Arc<AtomicUsize> = Arc::new(AtomicUsize::new(BASE.load(Relaxed) + 1));
+    }
+
+    loom::model(|| {
+        assert_eq!(DERIVED.load(Relaxed), 2);
+        assert_eq!(BASE.load(Relaxed), 1);
+    });
+}
+
+#[test]
+fn racing_to_initialize_a_chain() {
+    loom::lazy_static! {
+        static ref INITS: AtomicUsize = AtomicUsize::new(0);
+        static ref FIRST: Arc<usize> = {
+            INITS.fetch_add(1, SeqCst);
+            Arc::new(1)
+        };
+        static ref SECOND: Arc<usize> = {
+            INITS.fetch_add(1, SeqCst);
+            Arc::new(**FIRST + 1)
+        };
+        static ref THIRD: usize = {
+            INITS.fetch_add(1, SeqCst);
+            **SECOND + 1
+        };
+    }
+
+    loom::model(|| {
+        let th = thread::spawn(|| *THIRD);
+
+        assert_eq!(*THIRD, 3);
+        assert_eq!(th.join().unwrap(), 3);
+
+        // Each initializer runs once, the other thread waits for it.
+        assert_eq!(INITS.load(SeqCst), 3);
+    });
+}
+
+#[test]
+fn racing_to_initialize_different_links_of_a_chain() {
+    loom::lazy_static! {
+        static ref FIRST: Arc<usize> = Arc::new(1);
+        static ref SECOND: Arc<usize> = Arc::new(**FIRST + 1);
+    }
+
+    loom::model(|| {
+        let th = thread::spawn(|| **FIRST);
+
+        assert_eq!(**SECOND, 2);
+        assert_eq!(th.join().unwrap(), 1);
+    });
+}
+
+#[test]
+fn static_used_by_drop_of_dependent_static() {
+    struct Guard;
+
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            COUNT.fetch_sub(1, Relaxed);
+        }
+    }
+
+    loom::lazy_static! {
+        static ref COUNT: AtomicUsize = AtomicUsize::new(0);
+        static ref GUARD: Guard = {
+            COUNT.fetch_add(1, Relaxed);
+            Guard
+        };
+    }
+
+    loom::model(|| {
+        let _ = &*GUARD;
+        assert_eq!(COUNT.load(Relaxed), 1);
+    });
+}
+
+#[test]
+#[should_panic(expected = "lazy static accessed recursively")]
+fn recursive_initialization() {
+    loom::lazy_static! {
+        static ref SELF: usize = *SELF + 1;
+    }
+
+    loom::model(|| {
+        let _ = *SELF;
+    });
+}
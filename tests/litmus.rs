@@ -0,0 +1,347 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::sync::atomic::fence;
+
+use std::sync::atomic::Ordering::{AcqRel, Acquire, Relaxed, Release, SeqCst};
+
+#[test]
+fn sb_relaxed() {
+    loom::litmus! {
+        atomics: [x, y];
+        thread {
+            x.store(1, Relaxed);
+            y.load(Relaxed)
+        }
+        thread {
+            y.store(1, Relaxed);
+            x.load(Relaxed)
+        }
+        expect (0, 0);
+        expect (0, 1);
+        expect (1, 0);
+        expect (1, 1);
+    };
+}
+
+#[test]
+fn sb_fences() {
+    loom::litmus! {
+        atomics: [x, y];
+        thread {
+            x.store(1, Relaxed);
+            fence(SeqCst);
+            y.load(Relaxed)
+        }
+        thread {
+            y.store(1, Relaxed);
+            fence(SeqCst);
+            x.load(Relaxed)
+        }
+        forbid (0, 0);
+        expect (0, 1);
+        expect (1, 0);
+        expect (1, 1);
+    };
+}
+
+#[test]
+fn sb_one_fence() {
+    // A single fence does not order the other thread
+    loom::litmus! {
+        atomics: [x, y];
+        thread {
+            x.store(1, Relaxed);
+            fence(SeqCst);
+            y.load(Relaxed)
+        }
+        thread {
+            y.store(1, Relaxed);
+            x.load(Relaxed)
+        }
+        expect (0, 0);
+    };
+}
+
+#[test]
+fn sb_fence_and_seq_cst() {
+    loom::litmus! {
+        atomics: [x, y];
+        thread {
+            x.store(1, SeqCst);
+            y.load(SeqCst)
+        }
+        thread {
+            y.store(1, Relaxed);
+            fence(SeqCst);
+            x.load(Relaxed)
+        }
+        forbid (0, 0);
+    };
+}
+
+#[test]
+fn mp_relaxed() {
+    loom::litmus! {
+        atomics: [data, flag];
+        thread {
+            data.store(1, Relaxed);
+            flag.store(1, Relaxed);
+        }
+        thread {
+            (flag.load(Relaxed), data.load(Relaxed))
+        }
+        expect (1, 0);
+    };
+}
+
+#[test]
+fn mp_fences() {
+    loom::litmus! {
+        atomics: [data, flag];
+        thread {
+            data.store(1, Relaxed);
+            fence(Release);
+            flag.store(1, Relaxed);
+        }
+        thread {
+            let r0 = flag.load(Relaxed);
+            fence(Acquire);
+            (r0, data.load(Relaxed))
+        }
+        forbid (1, 0);
+        expect (0, 0);
+        expect (1, 1);
+    };
+}
+
+#[test]
+fn mp_acq_rel_fences() {
+    loom::litmus! {
+        atomics: [data, flag];
+        thread {
+            data.store(1, Relaxed);
+            fence(AcqRel);
+            flag.store(1, Relaxed);
+        }
+        thread {
+            let r0 = flag.load(Relaxed);
+            fence(AcqRel);
+            (r0, data.load(Relaxed))
+        }
+        forbid (1, 0);
+    };
+}
+
+#[test]
+fn mp_release_fence_acquire_load() {
+    loom::litmus! {
+        atomics: [data, flag];
+        thread {
+            data.store(1, Relaxed);
+            fence(Release);
+            flag.store(1, Relaxed);
+        }
+        thread {
+            (flag.load(Acquire), data.load(Relaxed))
+        }
+        forbid (1, 0);
+    };
+}
+
+#[test]
+fn iriw_syncs() {
+    loom::litmus! {
+        atomics: [x, y];
+        thread {
+            x.store(1, SeqCst);
+        }
+        thread {
+            y.store(1, SeqCst);
+        }
+        thread {
+            (x.load(SeqCst), y.load(SeqCst))
+        }
+        thread {
+            (y.load(SeqCst), x.load(SeqCst))
+        }
+        forbid (1, 0, 1, 0);
+    };
+}
+
+#[test]
+fn iriw_fences() {
+    loom::litmus! {
+        atomics: [x, y];
+        thread {
+            x.store(1, Relaxed);
+        }
+        thread {
+            y.store(1, Relaxed);
+        }
+        thread {
+            let r0 = x.load(Relaxed);
+            fence(SeqCst);
+            (r0, y.load(Relaxed))
+        }
+        thread {
+            let r0 = y.load(Relaxed);
+            fence(SeqCst);
+            (r0, x.load(Relaxed))
+        }
+        forbid (1, 0, 1, 0);
+    };
+}
+
+#[test]
+#[should_panic(expected = "forbidden outcome")]
+fn forbidden_outcome_fails() {
+    loom::litmus! {
+        atomics: [x, y];
+        thread {
+            x.store(1, Relaxed);
+            y.load(Relaxed)
+        }
+        thread {
+            y.store(1, Relaxed);
+            x.load(Relaxed)
+        }
+        forbid (0, 0);
+    };
+}
+
+#[test]
+#[should_panic(expected = "never observed expected outcome")]
+fn expected_outcome_fails() {
+    loom::litmus! {
+        atomics: [x];
+        thread {
+            x.store(1, Relaxed);
+        }
+        thread {
+            x.load(Relaxed)
+        }
+        expect (2);
+    };
+}
+
+#[test]
+fn two_threads_allowed_outcomes() {
+    loom::litmus::two_threads(
+        |t| {
+            t.x.store(1, Relaxed);
+            fence(SeqCst);
+            t.y.load(Relaxed)
+        },
+        |t| {
+            t.y.store(1, Relaxed);
+            fence(SeqCst);
+            t.x.load(Relaxed)
+        },
+    )
+    .allowed_outcomes(vec![(0, 1), (1, 0), (1, 1)]);
+}
+
+#[test]
+fn three_threads_outcomes() {
+    let outcomes = loom::litmus::three_threads(
+        |t| t.x.store(1, Release),
+        |t| {
+            let r0 = t.x.load(Acquire);
+            t.y.store(r0, Release);
+        },
+        |t| (t.y.load(Acquire), t.x.load(Relaxed)),
+    )
+    .outcomes();
+
+    assert!(outcomes.contains(&((), (), (1, 1))));
+    assert!(!outcomes.contains(&((), (), (1, 0))));
+}
+
+#[test]
+#[should_panic(expected = "never observed allowed outcomes [((), 2)]")]
+fn allowed_outcome_not_observed_fails() {
+    loom::litmus::two_threads(|t| t.x.store(1, SeqCst), |t| t.x.load(SeqCst))
+        .allowed_outcomes(vec![((), 0), ((), 1), ((), 2)]);
+}
+
+#[test]
+#[should_panic(expected = "observed outcome ((), 1), which is not allowed")]
+fn outcome_not_allowed_fails() {
+    loom::litmus::two_threads(|t| t.x.store(1, SeqCst), |t| t.x.load(SeqCst))
+        .allowed_outcomes(vec![((), 0)]);
+}
+
+#[test]
+fn swap_relaxed_reads_stale_values() {
+    // The swap is ordered right after the store it reads, before the stores
+    // it did not observe.
+    loom::litmus! {
+        atomics: [x, y];
+        thread {
+            x.store(1, Relaxed);
+            x.store(2, Relaxed);
+            y.store(1, Relaxed);
+        }
+        thread {
+            let r0 = y.load(Relaxed);
+            (r0, x.swap(3, Relaxed), x.load(Relaxed))
+        }
+        forbid (1, 0, 0);
+        forbid (1, 2, 2);
+        expect (1, 0, 1);
+        expect (1, 0, 3);
+        expect (1, 1, 2);
+        expect (1, 2, 3);
+    };
+}
+
+#[test]
+fn swap_acquire_hides_stale_values() {
+    loom::litmus! {
+        atomics: [x, y];
+        thread {
+            x.store(1, Relaxed);
+            x.store(2, Relaxed);
+            y.store(1, Release);
+        }
+        thread {
+            let r0 = y.load(Acquire);
+            (r0, x.swap(3, Relaxed))
+        }
+        forbid (1, 0);
+        forbid (1, 1);
+        expect (1, 2);
+    };
+}
+
+#[test]
+fn sb_swaps_relaxed() {
+    loom::litmus! {
+        atomics: [x, y];
+        thread {
+            x.swap(1, Relaxed);
+            y.load(Relaxed)
+        }
+        thread {
+            y.swap(1, Relaxed);
+            x.load(Relaxed)
+        }
+        expect (0, 0);
+    };
+}
+
+#[test]
+fn sb_swaps_seq_cst() {
+    loom::litmus! {
+        atomics: [x, y];
+        thread {
+            x.swap(1, SeqCst);
+            y.load(SeqCst)
+        }
+        thread {
+            y.swap(1, SeqCst);
+            x.load(SeqCst)
+        }
+        forbid (0, 0);
+    };
+}
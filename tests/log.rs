@@ -0,0 +1,83 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::model::Builder;
+use loom::sync::atomic::AtomicUsize;
+use loom::sync::{Arc, Condvar, Mutex, RwLock};
+use loom::thread;
+
+use std::sync::atomic::Ordering::{Acquire, Release, SeqCst};
+
+fn primitives() {
+    let num = Arc::new(AtomicUsize::new(0));
+    let lock = Arc::new((Mutex::new(false), Condvar::new()));
+    let rwlock = Arc::new(RwLock::new(0));
+
+    let th = {
+        let (num, lock, rwlock) = (num.clone(), lock.clone(), rwlock.clone());
+
+        thread::spawn(move || {
+            num.store(1, Release);
+            *rwlock.write().unwrap() += 1;
+
+            *lock.0.lock().unwrap() = true;
+            lock.1.notify_one();
+        })
+    };
+
+    num.fetch_add(1, SeqCst);
+    num.load(Acquire);
+    drop(rwlock.read().unwrap());
+
+    let mut done = lock.0.lock().unwrap();
+
+    while !*done {
+        done = lock.1.wait(done).unwrap();
+    }
+
+    drop(done);
+    th.join().unwrap();
+}
+
+#[test]
+fn log_filtered_targets() {
+    let mut builder = Builder::new();
+    builder.log = true;
+    builder.log_filter = "debug,atomic=trace,condvar=off".to_string();
+    builder.check(primitives);
+}
+
+#[test]
+fn log_failing_executions() {
+    let mut builder = Builder::new();
+    builder.log = true;
+    builder.log_filter = "trace".to_string();
+    builder.log_failing = true;
+
+    builder.check_expect_failure("observed", || {
+        let num = Arc::new(AtomicUsize::new(0));
+        let num2 = num.clone();
+
+        let th = thread::spawn(move || num2.store(1, SeqCst));
+
+        assert_eq!(0, num.load(SeqCst), "observed");
+        th.join().unwrap();
+    });
+}
+
+#[test]
+#[should_panic(expected = "invalid `LOOM_LOG` target `atomics`")]
+fn log_invalid_target() {
+    let mut builder = Builder::new();
+    builder.log = true;
+    builder.log_filter = "atomics=debug".to_string();
+    builder.check(|| {});
+}
+
+#[test]
+#[should_panic(expected = "invalid `LOOM_LOG` level `verbose`")]
+fn log_invalid_level() {
+    let mut builder = Builder::new();
+    builder.log = true;
+    builder.log_filter = "verbose".to_string();
+    builder.check(|| {});
+}
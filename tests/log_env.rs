@@ -0,0 +1,25 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::model::Builder;
+
+// The only test of this file, as it sets the environment of the process.
+#[test]
+fn loom_log_values() {
+    for (value, filter) in &[
+        ("1", "info"),
+        ("", "info"),
+        ("true", "info"),
+        ("yes", "info"),
+        ("debug", "debug"),
+        ("debug,mutex=off", "debug,mutex=off"),
+        ("atomic=trace", "atomic=trace"),
+    ] {
+        std::env::set_var("LOOM_LOG", value);
+
+        let builder = Builder::new();
+        assert!(builder.log);
+        assert_eq!(*filter, builder.log_filter, "LOOM_LOG={}", value);
+    }
+
+    std::env::remove_var("LOOM_LOG");
+}
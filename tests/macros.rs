@@ -0,0 +1,38 @@
+#![cfg(feature = "macros")]
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::facade::sync::atomic::{AtomicUsize, Ordering::SeqCst};
+use loom::facade::sync::{Arc, Mutex};
+use loom::facade::thread;
+
+#[loom::test]
+fn without_settings() {
+    let num = Arc::new(AtomicUsize::new(0));
+    let th = {
+        let num = num.clone();
+        thread::spawn(move || num.fetch_add(1, SeqCst))
+    };
+    num.fetch_add(1, SeqCst);
+    th.join().unwrap();
+    assert_eq!(2, num.load(SeqCst));
+}
+
+#[loom::test(preemption_bound = 2, max_branches = 10_000, check_determinism = true)]
+fn with_settings() {
+    let data = Arc::new(Mutex::new(0));
+    let th = {
+        let data = data.clone();
+        thread::spawn(move || *data.lock().unwrap() += 1)
+    };
+    *data.lock().unwrap() += 1;
+    th.join().unwrap();
+    assert_eq!(2, *data.lock().unwrap());
+}
+
+#[loom::test(max_permutations = 1)]
+#[should_panic(expected = "checked")]
+fn panics_propagate() {
+    let th = thread::spawn(|| {});
+    th.join().unwrap();
+    panic!("checked");
+}
@@ -0,0 +1,42 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::model::{Builder, MainExit};
+use loom::sync::{Arc, Notify};
+use loom::thread;
+
+#[test]
+#[should_panic(expected = "the model returned while threads were still running: thread #1")]
+fn require_joined_fails_on_running_thread() {
+    let mut builder = Builder::new();
+    builder.main_exit(MainExit::RequireJoined);
+    builder.check(|| {
+        thread::spawn(thread::yield_now);
+    });
+}
+
+#[test]
+fn require_joined_accepts_joined_threads() {
+    let mut builder = Builder::new();
+    builder.main_exit(MainExit::RequireJoined);
+    builder.check(|| {
+        thread::spawn(thread::yield_now).join().unwrap();
+    });
+}
+
+#[test]
+fn terminate_unwinds_running_threads() {
+    let mut builder = Builder::new();
+    builder.main_exit(MainExit::Terminate);
+    builder.check(|| {
+        let notify = Arc::new(Notify::new());
+        let notify2 = notify.clone();
+
+        // Never notified, the thread is blocked when the model returns. Its
+        // `Arc` is dropped while it unwinds.
+        thread::spawn(move || loop {
+            notify2.wait();
+        });
+
+        thread::yield_now();
+    });
+}
@@ -0,0 +1,72 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::model::{Builder, MemoryModel};
+use loom::sync::atomic::AtomicUsize;
+use loom::sync::Arc;
+use loom::thread;
+
+use std::collections::BTreeSet;
+use std::sync::atomic::Ordering::{Acquire, Relaxed};
+use std::sync::Mutex;
+
+/// Checks store buffering with relaxed accesses, returning the number of
+/// executions and the values the two loads read.
+fn store_buffering(memory_model: MemoryModel) -> (usize, BTreeSet<(usize, usize)>) {
+    let outcomes = std::sync::Arc::new(Mutex::new(BTreeSet::new()));
+
+    let mut builder = Builder::new();
+    builder.memory_model(memory_model);
+
+    let stats = {
+        let outcomes = outcomes.clone();
+
+        builder.check_returning_stats(move || {
+            let x = Arc::new(AtomicUsize::new(0));
+            let y = Arc::new(AtomicUsize::new(0));
+
+            let th = {
+                let (x, y) = (x.clone(), y.clone());
+                thread::spawn(move || {
+                    x.store(1, Relaxed);
+                    y.load(Relaxed)
+                })
+            };
+
+            y.store(1, Relaxed);
+            let r1 = x.load(Relaxed);
+            let r0 = th.join().unwrap();
+
+            outcomes.lock().unwrap().insert((r0, r1));
+        })
+    };
+
+    let outcomes = outcomes.lock().unwrap().clone();
+    (stats.iterations, outcomes)
+}
+
+#[test]
+fn weak_memory_model_reads_stale_values() {
+    let (_, outcomes) = store_buffering(MemoryModel::Weak);
+
+    assert!(outcomes.contains(&(0, 0)));
+}
+
+#[test]
+fn seq_cst_memory_model_only_interleaves() {
+    let (weak, _) = store_buffering(MemoryModel::Weak);
+    let (seq_cst, outcomes) = store_buffering(MemoryModel::SeqCst);
+
+    assert_eq!(outcomes, [(0, 1), (1, 0), (1, 1)].iter().copied().collect());
+    assert!(seq_cst < weak, "{} < {}", seq_cst, weak);
+}
+
+#[test]
+#[should_panic(expected = "invalid memory ordering `Acquire` for an atomic store")]
+fn seq_cst_memory_model_checks_orderings() {
+    let mut builder = Builder::new();
+    builder.memory_model(MemoryModel::SeqCst);
+
+    builder.check(|| {
+        AtomicUsize::new(0).store(1, Acquire);
+    });
+}
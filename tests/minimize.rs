@@ -0,0 +1,84 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::model::Builder;
+use loom::sync::atomic::AtomicUsize;
+use loom::sync::Arc;
+use loom::thread;
+
+use std::alloc::Layout;
+use std::panic;
+use std::sync::atomic::Ordering::SeqCst;
+
+fn saw_two() {
+    let num = Arc::new(AtomicUsize::new(0));
+    let num2 = num.clone();
+
+    let th = thread::spawn(move || {
+        for i in 1..=3 {
+            num2.store(i, SeqCst);
+        }
+    });
+
+    let seen: Vec<_> = (0..3).map(|_| num.load(SeqCst)).collect();
+
+    th.join().unwrap();
+
+    if seen.contains(&2) {
+        panic!("saw 2\nseen = {:?}", seen);
+    }
+}
+
+fn check(minimize: bool) -> String {
+    let mut builder = Builder::new();
+    builder.minimize = minimize;
+    builder.random_schedules = Some(100);
+    builder.random_seed = 1;
+
+    let err = panic::catch_unwind(|| builder.check(saw_two)).unwrap_err();
+    err.downcast_ref::<String>().unwrap().clone()
+}
+
+#[test]
+fn minimize_random_failure() {
+    let original = check(false);
+    let minimized = check(true);
+
+    assert!(original.starts_with("saw 2\n"), "{}", original);
+    assert!(minimized.starts_with("saw 2\n"), "{}", minimized);
+
+    // Fewer thread switches, the main thread reads 2 twice instead of moving
+    // on to 3.
+    assert_eq!(original, "saw 2\nseen = [0, 2, 3]");
+    assert_eq!(minimized, "saw 2\nseen = [0, 2, 2]");
+}
+
+#[test]
+fn minimize_alloc_failure() {
+    use std::sync::atomic::AtomicUsize;
+
+    static FAILED: AtomicUsize = AtomicUsize::new(0);
+
+    let mut builder = Builder::new();
+    builder.alloc_failures = true;
+    builder.minimize = true;
+
+    let err = panic::catch_unwind(|| {
+        builder.check(|| unsafe {
+            let layout = Layout::new::<u64>();
+            let ptr = loom::alloc::alloc(layout);
+
+            if ptr.is_null() {
+                FAILED.fetch_add(1, SeqCst);
+                panic!("allocation failed");
+            }
+
+            loom::alloc::dealloc(ptr, layout);
+        })
+    })
+    .unwrap_err();
+
+    assert_eq!("allocation failed", *err.downcast_ref::<&str>().unwrap());
+
+    // The replays of the minimization inject the failure as well.
+    assert!(FAILED.load(SeqCst) > 1);
+}
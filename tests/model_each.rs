@@ -0,0 +1,78 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::sync::atomic::AtomicUsize;
+use loom::sync::Arc;
+use loom::thread;
+
+use std::sync::atomic::Ordering::{Relaxed, SeqCst};
+
+fn racy_add(n: &usize) {
+    let num = Arc::new(AtomicUsize::new(0));
+    let num2 = num.clone();
+
+    let th = thread::spawn(move || {
+        let v = num2.load(SeqCst);
+        num2.store(v + 1, SeqCst);
+    });
+
+    for _ in 0..*n {
+        let v = num.load(SeqCst);
+        num.store(v + 1, SeqCst);
+    }
+
+    th.join().unwrap();
+
+    assert_eq!(n + 1, num.load(SeqCst), "lost update");
+}
+
+#[test]
+fn checks_each_input() {
+    static SUM: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+    loom::model_each(vec![1, 10, 100], |&n| {
+        SUM.fetch_add(n, Relaxed);
+    });
+
+    // Each input is checked with a single execution.
+    assert_eq!(111, SUM.load(Relaxed));
+}
+
+#[test]
+#[should_panic(expected = "lost update")]
+fn fails_on_racy_input() {
+    loom::model_each(vec![0, 1], racy_add);
+}
+
+#[test]
+fn shrinks_failing_input() {
+    static LAST: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+    let shrink = |&n: &usize| vec![n / 2, n.saturating_sub(1)];
+
+    let res = std::panic::catch_unwind(|| {
+        loom::model_each_shrinking(vec![0, 20], shrink, |n| {
+            LAST.store(*n, Relaxed);
+            racy_add(n);
+        });
+    });
+
+    assert!(res.is_err());
+
+    // The last input checked is the smallest one losing an update.
+    assert_eq!(1, LAST.load(Relaxed));
+}
+
+#[test]
+fn passing_inputs_are_not_shrunk() {
+    static CHECKED: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+    loom::model_each_shrinking(
+        vec![1, 2],
+        |_| -> Vec<usize> { panic!("shrunk a passing input") },
+        |_| {
+            CHECKED.fetch_add(1, Relaxed);
+        },
+    );
+
+    assert_eq!(2, CHECKED.load(Relaxed));
+}
@@ -1,6 +1,11 @@
-use loom::sync::mpsc::channel;
+use loom::cell::UnsafeCell;
+use loom::sync::atomic::{AtomicBool, Ordering::Relaxed};
+use loom::sync::mpsc::{channel, sync_channel, SendError, TryRecvError, TrySendError};
+use loom::sync::Arc;
 use loom::thread;
 
+use std::collections::HashSet;
+
 #[test]
 fn basic_sequential_usage() {
     loom::model(|| {
@@ -87,3 +92,252 @@ fn drop_receiver() {
         assert_eq!(r.recv().unwrap(), 1);
     });
 }
+
+#[test]
+#[should_panic(expected = "channel with 1 unreceived message")]
+fn unreceived_message_leaks() {
+    loom::model(|| {
+        let (s, r) = channel();
+        s.send(1).unwrap();
+        std::mem::forget(r);
+    });
+}
+
+#[test]
+fn sync_channel_send_blocks_when_full() {
+    loom::model(|| {
+        let (s, r) = sync_channel(1);
+        let done = Arc::new(AtomicBool::new(false));
+        let done2 = done.clone();
+        thread::spawn(move || {
+            s.send(1).unwrap();
+            s.send(2).unwrap();
+            done2.store(true, Relaxed);
+        });
+        assert!(!done.load(Relaxed));
+        assert_eq!(r.recv().unwrap(), 1);
+        assert_eq!(r.recv().unwrap(), 2);
+    });
+}
+
+#[test]
+fn sync_channel_rendezvous() {
+    loom::model(|| {
+        let (s, r) = sync_channel(0);
+        let done = Arc::new(AtomicBool::new(false));
+        let done2 = done.clone();
+        thread::spawn(move || {
+            s.send(1).unwrap();
+            done2.store(true, Relaxed);
+        });
+        assert!(!done.load(Relaxed));
+        assert_eq!(r.recv().unwrap(), 1);
+    });
+}
+
+#[test]
+fn try_send_explores_full() {
+    let outcomes = std::sync::Arc::new(std::sync::Mutex::new(HashSet::new()));
+    let outcomes2 = outcomes.clone();
+
+    loom::model(move || {
+        let (s, r) = sync_channel(1);
+        s.send(1).unwrap();
+        let th = thread::spawn(move || (r.recv().unwrap(), r));
+        let outcome = match s.try_send(2) {
+            Ok(()) => "ok",
+            Err(TrySendError::Full(2)) => "full",
+            Err(e) => panic!("unexpected {:?}", e),
+        };
+        outcomes2.lock().unwrap().insert(outcome);
+        assert_eq!(th.join().unwrap().0, 1);
+    });
+
+    let outcomes = outcomes.lock().unwrap();
+    assert!(outcomes.contains("ok"));
+    assert!(outcomes.contains("full"));
+}
+
+#[test]
+fn try_send_disconnected() {
+    loom::model(|| {
+        let (s, r) = sync_channel(1);
+        drop(r);
+        assert_eq!(s.try_send(1), Err(TrySendError::Disconnected(1)));
+    });
+}
+
+#[test]
+fn dropping_receiver_wakes_blocked_sender() {
+    for bound in 0..2 {
+        loom::model(move || {
+            let (s, r) = sync_channel(bound);
+            let th = thread::spawn(move || {
+                for i in 0..2 {
+                    if let Err(SendError(msg)) = s.send(i) {
+                        assert_eq!(msg, i);
+                        return;
+                    }
+                }
+            });
+            drop(r);
+            th.join().unwrap();
+        });
+    }
+}
+
+#[test]
+fn disconnect_synchronizes_with_receiver() {
+    loom::model(|| {
+        let data = Arc::new(UnsafeCell::new(0));
+        let data2 = data.clone();
+        let (s, r) = channel::<()>();
+        thread::spawn(move || {
+            data2.with_mut(|v| unsafe { *v = 1 });
+            drop(s);
+        });
+        assert!(r.recv().is_err());
+        assert_eq!(data.with(|v| unsafe { *v }), 1);
+    });
+}
+
+#[test]
+fn disconnect_synchronizes_with_sender() {
+    loom::model(|| {
+        let data = Arc::new(UnsafeCell::new(0));
+        let data2 = data.clone();
+        let (s, r) = sync_channel(0);
+        let th = thread::spawn(move || {
+            if s.send(()).is_err() {
+                assert_eq!(data2.with(|v| unsafe { *v }), 1);
+            }
+        });
+        data.with_mut(|v| unsafe { *v = 1 });
+        drop(r);
+        th.join().unwrap();
+    });
+}
+
+#[test]
+fn try_recv_explores_empty() {
+    let outcomes = loom::model::model_outcomes(|| {
+        let (s, r) = channel();
+        let th = thread::spawn(move || s.send(1).unwrap());
+
+        let outcome = r.try_recv();
+        th.join().unwrap();
+
+        if outcome.is_err() {
+            assert_eq!(r.recv(), Ok(1));
+        }
+
+        format!("{:?}", outcome)
+    });
+
+    let expected = ["Err(Empty)", "Ok(1)"];
+    assert_eq!(outcomes.into_iter().collect::<Vec<_>>(), expected);
+}
+
+#[test]
+fn try_recv_disconnected_synchronizes() {
+    let outcomes = loom::model::model_outcomes(|| {
+        let data = Arc::new(UnsafeCell::new(0));
+        let data2 = data.clone();
+        let (s, r) = channel::<()>();
+
+        let th = thread::spawn(move || {
+            data2.with_mut(|v| unsafe { *v = 1 });
+            drop(s);
+        });
+
+        let outcome = r.try_recv();
+
+        if outcome == Err(TryRecvError::Disconnected) {
+            assert_eq!(data.with(|v| unsafe { *v }), 1);
+        }
+
+        th.join().unwrap();
+        format!("{:?}", outcome)
+    });
+
+    let expected = ["Err(Disconnected)", "Err(Empty)"];
+    assert_eq!(outcomes.into_iter().collect::<Vec<_>>(), expected);
+}
+
+#[test]
+fn try_recv_takes_rendezvous_message() {
+    loom::model(|| {
+        let (s, r) = sync_channel(0);
+        let th = thread::spawn(move || s.send(1).unwrap());
+
+        loop {
+            match r.try_recv() {
+                Ok(msg) => {
+                    assert_eq!(msg, 1);
+                    break;
+                }
+                Err(TryRecvError::Empty) => thread::yield_now(),
+                Err(TryRecvError::Disconnected) => panic!("message lost"),
+            }
+        }
+
+        th.join().unwrap();
+    });
+}
+
+#[test]
+fn iter_receives_until_disconnected() {
+    loom::model(|| {
+        let (s, r) = channel();
+        let s2 = s.clone();
+
+        thread::spawn(move || s.send(1).unwrap());
+        thread::spawn(move || s2.send(2).unwrap());
+
+        let mut msgs: Vec<_> = r.iter().collect();
+        msgs.sort_unstable();
+        assert_eq!(msgs, [1, 2]);
+    });
+}
+
+#[test]
+fn into_iter_receives_until_disconnected() {
+    loom::model(|| {
+        let (s, r) = sync_channel(1);
+
+        let th = thread::spawn(move || {
+            let mut sum = 0;
+
+            for msg in r {
+                sum += msg;
+            }
+
+            sum
+        });
+
+        for i in 1..3 {
+            s.send(i).unwrap();
+        }
+
+        drop(s);
+        assert_eq!(th.join().unwrap(), 3);
+    });
+}
+
+#[test]
+fn try_iter_yields_available_messages() {
+    loom::model(|| {
+        let (s, r) = channel();
+        let th = thread::spawn(move || {
+            s.send(1).unwrap();
+            s.send(2).unwrap();
+        });
+
+        let first: Vec<_> = r.try_iter().collect();
+        th.join().unwrap();
+        let rest: Vec<_> = r.try_iter().collect();
+
+        let all: Vec<_> = first.iter().chain(&rest).copied().collect();
+        assert_eq!(all, [1, 2]);
+    });
+}
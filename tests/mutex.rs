@@ -1,10 +1,12 @@
 #![deny(warnings, rust_2018_idioms)]
 
 use loom::cell::UnsafeCell;
+use loom::model::Builder;
 use loom::sync::atomic::AtomicUsize;
 use loom::sync::Mutex;
 use loom::thread;
 
+use std::panic;
 use std::rc::Rc;
 use std::sync::atomic::Ordering::SeqCst;
 
@@ -67,3 +69,38 @@ fn mutex_establishes_seq_cst() {
         }
     });
 }
+
+#[test]
+#[should_panic(expected = "thread #0 attempted to acquire a Mutex it already holds")]
+fn mutex_double_lock() {
+    loom::model(|| {
+        let lock = Mutex::new(());
+
+        let _first = lock.lock().unwrap();
+        let _second = lock.lock().unwrap();
+    });
+}
+
+#[test]
+fn mutex_double_lock_reports_acquisition() {
+    let mut builder = Builder::new();
+    builder.location = true;
+
+    let err = panic::catch_unwind(|| {
+        builder.check(|| {
+            let lock = Mutex::new(());
+
+            let _first = lock.lock().unwrap();
+            let _second = lock.lock().unwrap();
+        });
+    })
+    .unwrap_err();
+
+    let msg = err.downcast_ref::<String>().unwrap();
+
+    assert!(
+        msg.contains("acquired: thread #0 @ tests/mutex.rs:93:"),
+        "{}",
+        msg
+    );
+}
@@ -0,0 +1,80 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::sync::atomic::AtomicBool;
+use loom::sync::{Arc, Mutex};
+use loom::thread;
+
+use std::collections::BTreeSet;
+use std::sync::atomic::Ordering::SeqCst;
+
+/// The main thread holds the lock while the other thread blocks on it, then
+/// releases it and immediately locks it again.
+#[test]
+fn releasing_thread_can_barge() {
+    let outcomes = loom::model::model_outcomes(|| {
+        let lock = Arc::new(Mutex::new(vec![]));
+        let blocked = Arc::new(AtomicBool::new(false));
+
+        let mut guard = lock.lock().unwrap();
+
+        let th = {
+            let lock = lock.clone();
+            let blocked = blocked.clone();
+            thread::spawn(move || {
+                blocked.store(true, SeqCst);
+                lock.lock().unwrap().push("waiter");
+            })
+        };
+
+        while !blocked.load(SeqCst) {
+            thread::yield_now();
+        }
+
+        guard.push("first");
+        drop(guard);
+        lock.lock().unwrap().push("barger");
+
+        th.join().unwrap();
+
+        let order = lock.lock().unwrap().clone();
+        order
+    });
+
+    let expected: BTreeSet<_> = vec![
+        vec!["first", "barger", "waiter"],
+        vec!["first", "waiter", "barger"],
+    ]
+    .into_iter()
+    .collect();
+
+    assert_eq!(expected, outcomes);
+}
+
+/// Two threads wait for the lock, any of them may acquire it first once it is
+/// released.
+#[test]
+fn any_waiter_acquires() {
+    let outcomes = loom::model::model_outcomes(|| {
+        let lock = Arc::new(Mutex::new(vec![]));
+        let guard = lock.lock().unwrap();
+
+        let ths: Vec<_> = (0..2)
+            .map(|i| {
+                let lock = lock.clone();
+                thread::spawn(move || lock.lock().unwrap().push(i))
+            })
+            .collect();
+
+        drop(guard);
+
+        for th in ths {
+            th.join().unwrap();
+        }
+
+        let order = lock.lock().unwrap().clone();
+        order
+    });
+
+    let expected: BTreeSet<_> = vec![vec![0, 1], vec![1, 0]].into_iter().collect();
+    assert_eq!(expected, outcomes);
+}
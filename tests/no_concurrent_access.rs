@@ -0,0 +1,87 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::model::Builder;
+use loom::sync::atomic::AtomicUsize;
+use loom::sync::{Arc, Mutex};
+use loom::thread;
+
+use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
+
+fn locked_counter(hint: bool) -> usize {
+    let stats = Builder::new().check_returning_stats(move || {
+        let num = Arc::new(AtomicUsize::new(0));
+        let lock = Arc::new(Mutex::new(()));
+
+        if hint {
+            num.no_concurrent_access();
+        }
+
+        let ths: Vec<_> = (0..2)
+            .map(|_| {
+                let num = num.clone();
+                let lock = lock.clone();
+
+                thread::spawn(move || {
+                    let _guard = lock.lock().unwrap();
+                    let n = num.load(Relaxed);
+                    num.store(n + 1, Relaxed);
+                })
+            })
+            .collect();
+
+        for th in ths {
+            th.join().unwrap();
+        }
+
+        assert_eq!(2, num.load(Relaxed));
+    });
+
+    stats.iterations
+}
+
+#[test]
+fn hint_reduces_executions() {
+    assert!(locked_counter(true) < locked_counter(false));
+}
+
+#[test]
+fn hint_allows_synchronized_handoff() {
+    loom::model(|| {
+        let num = Arc::new(AtomicUsize::new(0));
+        let flag = Arc::new(AtomicUsize::new(0));
+        num.no_concurrent_access();
+
+        let th = {
+            let num = num.clone();
+            let flag = flag.clone();
+
+            thread::spawn(move || {
+                num.store(1, Relaxed);
+                flag.store(1, Release);
+            })
+        };
+
+        if flag.load(Acquire) == 1 {
+            assert_eq!(1, num.load(Relaxed));
+        }
+
+        th.join().unwrap();
+    });
+}
+
+#[test]
+#[should_panic(expected = "marked `no_concurrent_access`")]
+fn concurrent_access_is_detected() {
+    loom::model(|| {
+        let num = Arc::new(AtomicUsize::new(0));
+        num.no_concurrent_access();
+
+        let th = {
+            let num = num.clone();
+            thread::spawn(move || num.store(1, Relaxed))
+        };
+
+        num.load(Relaxed);
+        th.join().unwrap();
+    });
+}
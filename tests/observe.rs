@@ -0,0 +1,140 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::model::{Builder, ExecutionSummary};
+use loom::observe::{BranchKind, ExecutionObserver};
+use loom::sync::atomic::AtomicUsize;
+use loom::thread;
+
+use std::sync::atomic::Ordering::{Relaxed, SeqCst};
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Event {
+    Spawned(usize, usize),
+    Switched(usize, usize),
+    Terminated(usize),
+    Created(usize, &'static str),
+    Branch(usize, usize, BranchKind),
+    Finished(usize, bool),
+}
+
+#[derive(Clone, Default)]
+struct Recorder(Arc<Mutex<Vec<Event>>>);
+
+impl Recorder {
+    /// Returns the events of each execution.
+    fn executions(&self) -> Vec<Vec<Event>> {
+        let events = self.0.lock().unwrap();
+
+        events
+            .split_inclusive(|event| matches!(event, Event::Finished(..)))
+            .map(|events| events.to_vec())
+            .collect()
+    }
+}
+
+impl ExecutionObserver for Recorder {
+    fn thread_spawned(&mut self, thread: usize, parent: usize) {
+        self.0.lock().unwrap().push(Event::Spawned(thread, parent));
+    }
+
+    fn thread_switched(&mut self, from: usize, to: usize) {
+        self.0.lock().unwrap().push(Event::Switched(from, to));
+    }
+
+    fn thread_terminated(&mut self, thread: usize) {
+        self.0.lock().unwrap().push(Event::Terminated(thread));
+    }
+
+    fn object_created(&mut self, thread: usize, _object: usize, kind: &'static str) {
+        self.0.lock().unwrap().push(Event::Created(thread, kind));
+    }
+
+    fn branch(&mut self, thread: usize, position: usize, kind: BranchKind) {
+        self.0
+            .lock()
+            .unwrap()
+            .push(Event::Branch(thread, position, kind));
+    }
+
+    fn execution_finished(&mut self, summary: &ExecutionSummary) {
+        self.0
+            .lock()
+            .unwrap()
+            .push(Event::Finished(summary.branches, summary.panicked));
+    }
+}
+
+#[test]
+fn observer_sees_every_execution() {
+    let recorder = Recorder::default();
+
+    let mut builder = Builder::new();
+    builder.instrument(Box::new(recorder.clone()));
+
+    let stats = builder.check_returning_stats(|| {
+        let num = loom::sync::Arc::new(AtomicUsize::new(0));
+        let num2 = num.clone();
+
+        let th = thread::spawn(move || num2.store(1, SeqCst));
+
+        num.load(SeqCst);
+        th.join().unwrap();
+    });
+
+    let executions = recorder.executions();
+    assert_eq!(executions.len(), stats.iterations);
+
+    for events in &executions {
+        let branches = match events.last() {
+            Some(Event::Finished(branches, false)) => *branches,
+            event => panic!("unexpected last event {:?}", event),
+        };
+
+        // Every branch point is reported once, in order.
+        let positions: Vec<_> = events
+            .iter()
+            .filter_map(|event| match event {
+                Event::Branch(_, position, _) => Some(*position),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(positions, (0..branches).collect::<Vec<_>>());
+
+        assert!(events.contains(&Event::Spawned(1, 0)));
+        assert!(events.contains(&Event::Terminated(0)));
+        assert!(events.contains(&Event::Terminated(1)));
+        assert!(events.contains(&Event::Created(0, "atomic")));
+        assert!(events.contains(&Event::Created(0, "arc")));
+    }
+
+    // Some execution runs the spawned thread before the main thread loads.
+    assert!(executions
+        .iter()
+        .any(|events| events.contains(&Event::Switched(0, 1))));
+}
+
+#[test]
+fn observer_sees_load_branches() {
+    let recorder = Recorder::default();
+
+    let mut builder = Builder::new();
+    builder.instrument(Box::new(recorder.clone()));
+
+    builder.check(|| {
+        let num = loom::sync::Arc::new(AtomicUsize::new(0));
+        let num2 = num.clone();
+
+        let th = thread::spawn(move || num2.store(1, Relaxed));
+
+        num.store(2, Relaxed);
+        num.load(Relaxed);
+        th.join().unwrap();
+    });
+
+    assert!(recorder
+        .executions()
+        .iter()
+        .flatten()
+        .any(|event| matches!(event, Event::Branch(0, _, BranchKind::Load))));
+}
@@ -0,0 +1,56 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::sync::atomic::{fence, AtomicUsize, Ordering};
+
+#[test]
+#[should_panic(expected = "invalid memory ordering `Release` for an atomic load")]
+fn release_load() {
+    loom::model(|| {
+        AtomicUsize::new(0).load(Ordering::Release);
+    });
+}
+
+#[test]
+#[should_panic(expected = "invalid memory ordering `Acquire` for an atomic store")]
+fn acquire_store() {
+    loom::model(|| {
+        AtomicUsize::new(0).store(1, Ordering::Acquire);
+    });
+}
+
+#[test]
+#[should_panic(
+    expected = "invalid memory ordering `AcqRel` for the failure of a compare-and-exchange"
+)]
+fn acq_rel_failure_ordering() {
+    loom::model(|| {
+        let _ = AtomicUsize::new(0).compare_exchange(0, 1, Ordering::AcqRel, Ordering::AcqRel);
+    });
+}
+
+#[test]
+#[should_panic(expected = "invalid memory ordering `Relaxed` for a fence")]
+fn relaxed_fence() {
+    loom::model(|| {
+        fence(Ordering::Relaxed);
+    });
+}
+
+#[test]
+fn read_modify_write_accepts_every_ordering() {
+    loom::model(|| {
+        let num = AtomicUsize::new(0);
+
+        for &order in &[
+            Ordering::Relaxed,
+            Ordering::Acquire,
+            Ordering::Release,
+            Ordering::AcqRel,
+            Ordering::SeqCst,
+        ] {
+            num.fetch_add(1, order);
+            num.swap(1, order);
+            let _ = num.compare_exchange(1, 2, order, Ordering::Relaxed);
+        }
+    });
+}
@@ -0,0 +1,89 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::model::Builder;
+use loom::sync::atomic::AtomicUsize;
+use loom::thread;
+
+use std::collections::BTreeSet;
+use std::sync::atomic::Ordering::{Relaxed, SeqCst};
+use std::sync::Arc;
+
+#[test]
+fn model_outcomes_collects_all_values() {
+    let outcomes = loom::model_outcomes(|| {
+        let num = Arc::new(AtomicUsize::new(0));
+
+        let ths: Vec<_> = (1..3)
+            .map(|i| {
+                let num = num.clone();
+                thread::spawn(move || num.store(i, Relaxed))
+            })
+            .collect();
+
+        for th in ths {
+            th.join().unwrap();
+        }
+
+        num.load(Relaxed)
+    });
+
+    assert_eq!(outcomes, [1, 2].iter().cloned().collect::<BTreeSet<_>>());
+}
+
+#[test]
+fn check_outcomes_single_value() {
+    let outcomes = Builder::new().check_outcomes(|| {
+        let num = Arc::new(AtomicUsize::new(0));
+        let num2 = num.clone();
+
+        thread::spawn(move || num2.fetch_add(1, SeqCst))
+            .join()
+            .unwrap();
+
+        num.fetch_add(1, SeqCst) + 1
+    });
+
+    assert_eq!(outcomes.into_iter().collect::<Vec<_>>(), vec![2]);
+}
+
+#[test]
+fn check_mut_accumulates_across_executions() {
+    let mut executions = 0;
+    let mut values = BTreeSet::new();
+
+    let stats = Builder::new().check_returning_stats(|| {
+        let num = Arc::new(AtomicUsize::new(0));
+        let num2 = num.clone();
+        thread::spawn(move || num2.store(1, Relaxed));
+        num.load(Relaxed);
+    });
+
+    Builder::new().check_mut(
+        || {
+            let num = Arc::new(AtomicUsize::new(0));
+            let num2 = num.clone();
+            thread::spawn(move || num2.store(1, Relaxed));
+            num.load(Relaxed)
+        },
+        |value| {
+            executions += 1;
+            values.insert(value);
+        },
+    );
+
+    assert_eq!(executions, stats.iterations);
+    assert_eq!(values, [0, 1].iter().cloned().collect::<BTreeSet<_>>());
+}
+
+#[test]
+#[should_panic(expected = "model returned different values across executions")]
+fn model_returning_nondeterministic_result() {
+    loom::model_returning(|| {
+        let num = Arc::new(AtomicUsize::new(0));
+        let num2 = num.clone();
+        let th = thread::spawn(move || num2.store(1, Relaxed));
+        let val = num.load(Relaxed);
+        th.join().unwrap();
+        val
+    });
+}
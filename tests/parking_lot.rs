@@ -0,0 +1,68 @@
+#![cfg(feature = "parking_lot")]
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::sync::parking_lot::{Condvar, Mutex, RwLock, RwLockUpgradableReadGuard};
+use loom::sync::Arc;
+use loom::thread;
+
+use std::time::Duration;
+
+#[test]
+fn mutex_lock_returns_guard() {
+    loom::model(|| {
+        let lock = Arc::new(Mutex::new(0));
+        let c_lock = lock.clone();
+
+        let th = thread::spawn(move || *c_lock.lock() += 1);
+
+        *lock.lock() += 1;
+        th.join().unwrap();
+
+        assert_eq!(2, *lock.lock());
+        assert!(lock.try_lock().is_some());
+    });
+}
+
+#[test]
+fn condvar_wait_in_place() {
+    loom::model(|| {
+        let pair = Arc::new((Mutex::new(false), Condvar::new()));
+        let c_pair = pair.clone();
+
+        thread::spawn(move || {
+            let (lock, cvar) = &*c_pair;
+            *lock.lock() = true;
+            cvar.notify_one();
+        });
+
+        let (lock, cvar) = &*pair;
+        let mut ready = lock.lock();
+
+        while !*ready {
+            let res = cvar.wait_for(&mut ready, Duration::from_secs(1));
+            assert!(!res.timed_out());
+        }
+    });
+}
+
+#[test]
+fn rwlock_upgradable_read() {
+    loom::model(|| {
+        let lock = Arc::new(RwLock::new(0));
+        let c_lock = lock.clone();
+
+        let th = thread::spawn(move || {
+            let n = c_lock.upgradable_read();
+
+            if *n == 0 {
+                *RwLockUpgradableReadGuard::upgrade(n) = 1;
+            }
+        });
+
+        let v = *lock.read();
+        th.join().unwrap();
+
+        assert!(v <= 1);
+        assert_eq!(1, *lock.read());
+    });
+}
@@ -0,0 +1,111 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::sync::{Arc, Condvar, Mutex, RwLock};
+use loom::thread;
+
+use std::collections::BTreeSet;
+
+#[test]
+fn panic_while_holding_mutex_poisons() {
+    loom::model(|| {
+        let lock = Arc::new(Mutex::new(0));
+        let lock2 = lock.clone();
+
+        let th = thread::spawn(move || {
+            let mut guard = lock2.lock().unwrap();
+            *guard += 1;
+            panic!("boom");
+        });
+
+        assert!(th.join().is_err());
+        assert!(lock.is_poisoned());
+
+        // The data is still accessible through the error.
+        let guard = lock.lock().unwrap_err().into_inner();
+        assert_eq!(1, *guard);
+        drop(guard);
+
+        lock.clear_poison();
+        assert!(lock.lock().is_ok());
+    });
+}
+
+#[test]
+fn poisoning_races_with_lock() {
+    let outcomes = loom::model::model_outcomes(|| {
+        let lock = Arc::new(Mutex::new(()));
+        let lock2 = lock.clone();
+
+        let th = thread::spawn(move || {
+            let _guard = lock2.lock().unwrap();
+            panic!("boom");
+        });
+
+        let poisoned = lock.lock().is_err();
+        let _ = th.join();
+        poisoned
+    });
+
+    let expected: BTreeSet<_> = vec![false, true].into_iter().collect();
+    assert_eq!(outcomes, expected);
+}
+
+#[test]
+fn read_guard_does_not_poison() {
+    loom::model(|| {
+        let lock = Arc::new(RwLock::new(0));
+        let lock2 = lock.clone();
+
+        let th = thread::spawn(move || {
+            let _guard = lock2.read().unwrap();
+            panic!("boom");
+        });
+        assert!(th.join().is_err());
+        assert!(!lock.is_poisoned());
+
+        let lock2 = lock.clone();
+        let th = thread::spawn(move || {
+            let _guard = lock2.write().unwrap();
+            panic!("boom");
+        });
+        assert!(th.join().is_err());
+        assert!(lock.read().is_err());
+    });
+}
+
+#[test]
+fn condvar_waiter_sees_poison() {
+    loom::model(|| {
+        let pair = Arc::new((Mutex::new(false), Condvar::new()));
+        let pair2 = pair.clone();
+
+        let th = thread::spawn(move || {
+            let (lock, cvar) = &*pair2;
+            let mut ready = lock.lock().unwrap();
+            *ready = true;
+            cvar.notify_one();
+            panic!("boom");
+        });
+
+        let (lock, cvar) = &*pair;
+        let mut res = lock.lock();
+
+        loop {
+            let guard = match res {
+                Ok(guard) => guard,
+                // The thread panicked before we got the lock.
+                Err(_) => break,
+            };
+
+            if *guard {
+                // Woken up while the thread still held the lock, it panicked
+                // before releasing it.
+                panic!("guard observed without poison");
+            }
+
+            res = cvar.wait(guard);
+        }
+
+        assert!(th.join().is_err());
+    });
+}
@@ -0,0 +1,93 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::model::Builder;
+use loom::sync::atomic::AtomicUsize;
+use loom::thread;
+
+use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
+use std::sync::{Arc, Mutex};
+
+#[test]
+#[should_panic]
+fn random_schedules_find_bug() {
+    let mut builder = Builder::new();
+    builder.random_schedules(1_000, 1);
+
+    builder.check(|| {
+        let num = Arc::new(AtomicUsize::new(0));
+
+        let ths: Vec<_> = (0..2)
+            .map(|_| {
+                let num = num.clone();
+                thread::spawn(move || {
+                    let curr = num.load(Acquire);
+                    num.store(curr + 1, Release);
+                })
+            })
+            .collect();
+
+        for th in ths {
+            th.join().unwrap();
+        }
+
+        assert_eq!(2, num.load(Relaxed));
+    });
+}
+
+#[test]
+fn random_schedules_run_n_iterations() {
+    let iterations = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let counter = iterations.clone();
+
+    let mut builder = Builder::new();
+    builder.random_schedules(25, 7);
+
+    builder.check(move || {
+        counter.fetch_add(1, Relaxed);
+
+        let num = Arc::new(AtomicUsize::new(0));
+        let num2 = num.clone();
+
+        let th = thread::spawn(move || num2.store(1, Release));
+
+        num.load(Acquire);
+        th.join().unwrap();
+    });
+
+    assert_eq!(25, iterations.load(Relaxed));
+}
+
+#[test]
+fn random_schedules_are_reproducible() {
+    fn run(seed: u64) -> Vec<usize> {
+        let observed = Arc::new(Mutex::new(vec![]));
+        let record = observed.clone();
+
+        let mut builder = Builder::new();
+        builder.random_schedules(50, seed);
+
+        builder.check(move || {
+            let num = Arc::new(AtomicUsize::new(0));
+
+            let ths: Vec<_> = (0..2)
+                .map(|i| {
+                    let num = num.clone();
+                    thread::spawn(move || num.store(i + 1, Relaxed))
+                })
+                .collect();
+
+            let val = num.load(Relaxed);
+
+            for th in ths {
+                th.join().unwrap();
+            }
+
+            record.lock().unwrap().push(val);
+        });
+
+        let observed = observed.lock().unwrap().clone();
+        observed
+    }
+
+    assert_eq!(run(1234), run(1234));
+}
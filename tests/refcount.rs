@@ -0,0 +1,103 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::sync::atomic::{fence, AtomicUsize};
+use loom::sync::RefCount;
+use loom::thread;
+
+use std::sync::atomic::Ordering::{self, Acquire, Relaxed, Release};
+use std::sync::Arc;
+
+struct Shared {
+    refs: AtomicUsize,
+    check: RefCount,
+}
+
+impl Shared {
+    fn new() -> Arc<Shared> {
+        Arc::new(Shared {
+            refs: AtomicUsize::new(1),
+            check: RefCount::new(),
+        })
+    }
+
+    fn acquire(&self) {
+        self.refs.fetch_add(1, Relaxed);
+        self.check.inc();
+    }
+
+    fn release(&self, dec: Ordering, fenced: bool) {
+        self.check.dec();
+
+        if self.refs.fetch_sub(1, dec) == 1 {
+            if fenced {
+                fence(Acquire);
+            }
+
+            self.check.free();
+        }
+    }
+}
+
+fn check(dec: Ordering, fenced: bool) {
+    loom::model(move || {
+        let shared = Shared::new();
+        shared.acquire();
+
+        let th = {
+            let shared = shared.clone();
+            thread::spawn(move || shared.release(dec, fenced))
+        };
+
+        shared.release(dec, fenced);
+        th.join().unwrap();
+    });
+}
+
+#[test]
+fn release_decrement_and_acquire_fence() {
+    check(Release, true);
+}
+
+#[test]
+#[should_panic(
+    expected = "Causality violation: `RefCount` freed concurrently with dropping a reference"
+)]
+fn relaxed_decrement() {
+    check(Relaxed, true);
+}
+
+#[test]
+#[should_panic(
+    expected = "Causality violation: `RefCount` freed concurrently with dropping a reference"
+)]
+fn missing_acquire_fence() {
+    check(Release, false);
+}
+
+#[test]
+#[should_panic(expected = "`RefCount` never freed")]
+fn never_freed() {
+    loom::model(|| {
+        let check = RefCount::new();
+        check.dec();
+    });
+}
+
+#[test]
+#[should_panic(expected = "`RefCount` with 1 outstanding reference")]
+fn leaked_reference() {
+    loom::model(|| {
+        let _check = RefCount::new();
+    });
+}
+
+#[test]
+#[should_panic(expected = "`RefCount` freed with 1 outstanding reference")]
+fn freed_with_outstanding_reference() {
+    loom::model(|| {
+        let check = RefCount::new();
+        check.inc();
+        check.dec();
+        check.free();
+    });
+}
@@ -0,0 +1,87 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::cell::UnsafeCell;
+use loom::model::Builder;
+use loom::sync::atomic::{fence, AtomicUsize};
+use loom::sync::{Arc, Condvar, Mutex, RwLock};
+use loom::thread;
+
+use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
+
+#[test]
+fn dropped_objects_do_not_count_toward_max_objects() {
+    let mut builder = Builder::new();
+    builder.max_objects = Some(6);
+
+    builder.check(|| {
+        let shared = Arc::new(AtomicUsize::new(0));
+        let th = {
+            let shared = shared.clone();
+
+            thread::spawn(move || {
+                for i in 0..10 {
+                    let lock = Mutex::new(i);
+                    *lock.lock().unwrap() += 1;
+                    shared.fetch_add(1, Relaxed);
+                }
+            })
+        };
+
+        for i in 0..10 {
+            let num = AtomicUsize::new(i);
+            num.fetch_add(1, Relaxed);
+
+            let lock = RwLock::new(i);
+            drop(lock.read().unwrap());
+
+            let _condvar = Condvar::new();
+        }
+
+        th.join().unwrap();
+        assert_eq!(10, shared.load(Relaxed));
+    });
+}
+
+#[test]
+fn released_objects_are_counted_in_stats() {
+    let stats = Builder::new().check_returning_stats(|| {
+        for i in 0..3 {
+            let lock = Mutex::new(i);
+            drop(lock.lock().unwrap());
+        }
+    });
+
+    assert_eq!(3, stats.max_objects.mutexes);
+}
+
+#[test]
+fn dropped_atomic_still_synchronizes_with_fences() {
+    loom::model(|| {
+        let data = Arc::new(UnsafeCell::new(0));
+
+        // Dropping a loom `Arc` synchronizes, a `std` one does not.
+        let flag = std::sync::Arc::new(AtomicUsize::new(0));
+
+        let th = {
+            let data = data.clone();
+            let flag = flag.clone();
+
+            thread::spawn(move || {
+                data.with_mut(|ptr| unsafe { *ptr = 1 });
+                flag.store(1, Release);
+            })
+        };
+
+        let ready = flag.load(Relaxed) == 1;
+
+        // May drop the last reference to the atomic before the fence.
+        drop(flag);
+
+        if ready {
+            fence(Acquire);
+            assert_eq!(1, data.with(|ptr| unsafe { *ptr }));
+        }
+
+        th.join().unwrap();
+    });
+}
@@ -0,0 +1,147 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::sync::atomic::fence;
+
+use std::sync::atomic::Ordering::{AcqRel, Acquire, Relaxed, Release};
+
+#[test]
+fn rmw_continues_release_sequence() {
+    loom::litmus! {
+        atomics: [data, x];
+        thread {
+            data.store(1, Relaxed);
+            x.store(1, Release);
+        }
+        thread {
+            x.fetch_add(1, Relaxed)
+        }
+        thread {
+            (x.load(Acquire), data.load(Relaxed))
+        }
+        forbid (1, 2, 0);
+        expect (1, 2, 1);
+    };
+}
+
+#[test]
+fn rmw_chain_across_threads() {
+    loom::litmus! {
+        atomics: [data, x];
+        thread {
+            data.store(1, Relaxed);
+            x.store(1, Release);
+        }
+        thread {
+            x.fetch_add(1, Relaxed)
+        }
+        thread {
+            x.fetch_add(1, Relaxed)
+        }
+        thread {
+            (x.load(Acquire), data.load(Relaxed))
+        }
+        forbid (1, 2, 3, 0);
+        forbid (2, 1, 3, 0);
+        expect (1, 2, 3, 1);
+    };
+}
+
+#[test]
+fn rmw_continues_release_sequence_to_acquire_fence() {
+    loom::litmus! {
+        atomics: [data, x];
+        thread {
+            data.store(1, Relaxed);
+            x.store(1, Release);
+        }
+        thread {
+            x.swap(2, Relaxed)
+        }
+        thread {
+            let r0 = x.load(Relaxed);
+            fence(Acquire);
+            (r0, data.load(Relaxed))
+        }
+        forbid (1, 2, 0);
+    };
+}
+
+#[test]
+fn release_rmw_continues_release_sequence() {
+    // The RMW heads its own release sequence, without ending the one it reads
+    // from.
+    loom::litmus! {
+        atomics: [data, other, x];
+        thread {
+            data.store(1, Relaxed);
+            x.store(1, Release);
+        }
+        thread {
+            other.store(1, Relaxed);
+            x.fetch_add(1, AcqRel)
+        }
+        thread {
+            (x.load(Acquire), data.load(Relaxed), other.load(Relaxed))
+        }
+        forbid (1, 2, 0, 0);
+        forbid (1, 2, 0, 1);
+        forbid (1, 2, 1, 0);
+        expect (1, 2, 1, 1);
+    };
+}
+
+#[test]
+fn failed_compare_exchange_does_not_continue_release_sequence() {
+    loom::litmus! {
+        atomics: [data, x];
+        thread {
+            data.store(1, Relaxed);
+            x.store(1, Release);
+        }
+        thread {
+            let _ = x.compare_exchange(2, 3, Relaxed, Relaxed);
+            x.store(2, Relaxed);
+        }
+        thread {
+            (x.load(Acquire), data.load(Relaxed))
+        }
+        expect (2, 0);
+    };
+}
+
+#[test]
+fn store_by_another_thread_ends_release_sequence() {
+    loom::litmus! {
+        atomics: [data, x];
+        thread {
+            data.store(1, Relaxed);
+            x.store(1, Release);
+        }
+        thread {
+            let r0 = x.load(Relaxed);
+            x.store(2, Relaxed);
+            r0
+        }
+        thread {
+            (x.load(Acquire), data.load(Relaxed))
+        }
+        expect (1, 2, 0);
+    };
+}
+
+#[test]
+fn store_by_same_thread_does_not_continue_release_sequence() {
+    // Since C++20, release sequences only continue through RMWs.
+    loom::litmus! {
+        atomics: [data, x];
+        thread {
+            data.store(1, Relaxed);
+            x.store(1, Release);
+            x.store(2, Relaxed);
+        }
+        thread {
+            (x.load(Acquire), data.load(Relaxed))
+        }
+        expect (2, 0);
+    };
+}
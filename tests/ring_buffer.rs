@@ -0,0 +1,284 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::cell::UnsafeCell;
+use loom::sync::atomic::{AtomicU32, AtomicU8};
+use loom::sync::Arc;
+use loom::thread;
+
+use std::sync::atomic::Ordering::{AcqRel, Acquire, Relaxed, Release};
+
+/// Number of slots of the ring buffers, a power of two so that masking the
+/// indices stays correct when they wrap around.
+const CAPACITY: u32 = 2;
+
+/// Returns head and tail indices of an empty ring buffer, `before` increments
+/// away from wrapping around.
+fn indices_before_wrap(before: u32) -> (AtomicU32, AtomicU32) {
+    let start = 0u32.wrapping_sub(before);
+    (AtomicU32::new(start), AtomicU32::new(start))
+}
+
+fn slot(index: u32) -> usize {
+    (index & (CAPACITY - 1)) as usize
+}
+
+/// Single producer, single consumer ring buffer.
+struct Spsc {
+    head: AtomicU32,
+    tail: AtomicU32,
+    slots: [UnsafeCell<usize>; CAPACITY as usize],
+}
+
+impl Spsc {
+    fn new() -> Spsc {
+        let (head, tail) = indices_before_wrap(1);
+
+        Spsc {
+            head,
+            tail,
+            slots: [UnsafeCell::new(0), UnsafeCell::new(0)],
+        }
+    }
+
+    fn push(&self, value: usize) -> bool {
+        let tail = self.tail.load(Relaxed);
+        let head = self.head.load(Acquire);
+
+        if tail.wrapping_sub(head) == CAPACITY {
+            return false;
+        }
+
+        self.slots[slot(tail)].with_mut(|ptr| unsafe { *ptr = value });
+        self.tail.store(tail.wrapping_add(1), Release);
+        true
+    }
+
+    fn pop(&self) -> Option<usize> {
+        let head = self.head.load(Relaxed);
+        let tail = self.tail.load(Acquire);
+
+        if head == tail {
+            return None;
+        }
+
+        let value = self.slots[slot(head)].with(|ptr| unsafe { *ptr });
+        self.head.store(head.wrapping_add(1), Release);
+        Some(value)
+    }
+}
+
+#[test]
+fn spsc_across_wraparound() {
+    loom::model(|| {
+        let buffer = Arc::new(Spsc::new());
+
+        let producer = {
+            let buffer = buffer.clone();
+            thread::spawn(move || {
+                for value in 1..=3 {
+                    while !buffer.push(value) {
+                        thread::yield_now();
+                    }
+                }
+            })
+        };
+
+        for expected in 1..=3 {
+            loop {
+                match buffer.pop() {
+                    Some(value) => {
+                        assert_eq!(expected, value);
+                        break;
+                    }
+                    None => thread::yield_now(),
+                }
+            }
+        }
+
+        producer.join().unwrap();
+
+        // Both indices wrapped around
+        assert_eq!(2, buffer.head.load(Relaxed));
+        assert_eq!(2, buffer.tail.load(Relaxed));
+    });
+}
+
+#[test]
+#[should_panic]
+fn spsc_relaxed_tail_across_wraparound() {
+    loom::model(|| {
+        let (head, tail) = indices_before_wrap(1);
+        let buffer = Arc::new(Spsc {
+            head,
+            tail,
+            slots: [UnsafeCell::new(0), UnsafeCell::new(0)],
+        });
+
+        let producer = {
+            let buffer = buffer.clone();
+            thread::spawn(move || {
+                buffer.slots[slot(u32::MAX)].with_mut(|ptr| unsafe { *ptr = 1 });
+                // Missing `Release`
+                buffer.tail.fetch_add(1, Relaxed);
+            })
+        };
+
+        if buffer.tail.load(Acquire) == 0 {
+            buffer.slots[slot(u32::MAX)].with(|ptr| assert_eq!(1, unsafe { *ptr }));
+        }
+
+        producer.join().unwrap();
+    });
+}
+
+/// Multiple producers claim slots by incrementing the tail, each slot has a
+/// sequence number telling whether it holds a value.
+struct Mpsc {
+    tail: AtomicU32,
+    head: AtomicU32,
+    sequences: [AtomicU32; CAPACITY as usize],
+    slots: [UnsafeCell<usize>; CAPACITY as usize],
+}
+
+impl Mpsc {
+    fn new() -> Mpsc {
+        let (head, tail) = indices_before_wrap(1);
+        let start = 0u32.wrapping_sub(1);
+
+        // Each slot starts with the sequence of the first index mapping to it
+        let sequence = |slot: u32| start.wrapping_add(slot.wrapping_sub(start) & (CAPACITY - 1));
+
+        Mpsc {
+            tail,
+            head,
+            sequences: [AtomicU32::new(sequence(0)), AtomicU32::new(sequence(1))],
+            slots: [UnsafeCell::new(0), UnsafeCell::new(0)],
+        }
+    }
+
+    fn push(&self, value: usize) -> bool {
+        let mut tail = self.tail.load(Relaxed);
+
+        loop {
+            let sequence = self.sequences[slot(tail)].load(Acquire);
+
+            if sequence != tail {
+                // The slot still holds the value of the previous lap
+                return false;
+            }
+
+            match self
+                .tail
+                .compare_exchange(tail, tail.wrapping_add(1), Relaxed, Relaxed)
+            {
+                Ok(_) => break,
+                Err(actual) => tail = actual,
+            }
+        }
+
+        self.slots[slot(tail)].with_mut(|ptr| unsafe { *ptr = value });
+        self.sequences[slot(tail)].store(tail.wrapping_add(1), Release);
+        true
+    }
+
+    fn pop(&self) -> Option<usize> {
+        let head = self.head.load(Relaxed);
+
+        if self.sequences[slot(head)].load(Acquire) != head.wrapping_add(1) {
+            return None;
+        }
+
+        let value = self.slots[slot(head)].with(|ptr| unsafe { *ptr });
+        self.sequences[slot(head)].store(head.wrapping_add(CAPACITY), Release);
+        self.head.store(head.wrapping_add(1), Relaxed);
+        Some(value)
+    }
+}
+
+#[test]
+fn mpsc_across_wraparound() {
+    let mut builder = loom::model::Builder::new();
+    builder.preemption_bound = Some(2);
+
+    builder.check(|| {
+        let buffer = Arc::new(Mpsc::new());
+
+        let producers: Vec<_> = (1..=2)
+            .map(|value| {
+                let buffer = buffer.clone();
+                thread::spawn(move || {
+                    while !buffer.push(value) {
+                        thread::yield_now();
+                    }
+                })
+            })
+            .collect();
+
+        let mut sum = 0;
+        for _ in 0..2 {
+            loop {
+                match buffer.pop() {
+                    Some(value) => {
+                        sum += value;
+                        break;
+                    }
+                    None => thread::yield_now(),
+                }
+            }
+        }
+
+        assert_eq!(3, sum);
+
+        for producer in producers {
+            producer.join().unwrap();
+        }
+
+        assert_eq!(1, buffer.tail.load(Relaxed));
+    });
+}
+
+#[test]
+fn fetch_add_wraps() {
+    let outcomes = loom::model_outcomes(|| {
+        let num = Arc::new(AtomicU32::new(u32::MAX));
+
+        let th = {
+            let num = num.clone();
+            thread::spawn(move || num.fetch_add(1, AcqRel))
+        };
+
+        let prev = num.fetch_add(1, AcqRel);
+        let other = th.join().unwrap();
+
+        assert_eq!(1, num.load(Relaxed));
+        (prev.min(other), prev.max(other))
+    });
+
+    assert_eq!(1, outcomes.len());
+    assert!(outcomes.contains(&(0, u32::MAX)));
+}
+
+#[test]
+fn wrapped_values_are_distinct_stores() {
+    let outcomes = loom::model_outcomes(|| {
+        let num = Arc::new(AtomicU8::new(0));
+
+        let th = {
+            let num = num.clone();
+            thread::spawn(move || {
+                num.fetch_add(128, Relaxed);
+                // Wraps around to the initial value
+                num.fetch_add(128, Relaxed);
+            })
+        };
+
+        let loads = (num.load(Relaxed), num.load(Relaxed), num.load(Relaxed));
+
+        th.join().unwrap();
+        loads
+    });
+
+    // The initial value and the wrapped around store are told apart.
+    assert!(outcomes.contains(&(0, 128, 0)));
+    assert!(!outcomes.contains(&(128, 0, 128)));
+}
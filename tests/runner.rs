@@ -0,0 +1,91 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::model::{Builder, Runner};
+use loom::sync::atomic::AtomicUsize;
+use loom::sync::Arc;
+use loom::thread;
+
+use std::sync::atomic::Ordering::SeqCst;
+
+fn incr(runner: Runner) {
+    let mut builder = Builder::new();
+    builder.runner(runner);
+
+    builder.check(|| {
+        let num = Arc::new(AtomicUsize::new(0));
+
+        let ths: Vec<_> = (0..2)
+            .map(|_| {
+                let num = num.clone();
+                thread::spawn(move || {
+                    let v = num.load(SeqCst);
+                    num.store(v + 1, SeqCst);
+                })
+            })
+            .collect();
+
+        for th in ths {
+            th.join().unwrap();
+        }
+
+        assert_eq!(2, num.load(SeqCst));
+    });
+}
+
+#[test]
+fn os_threads_explore_all() {
+    let mut builder = Builder::new();
+    builder.runner(Runner::OsThreads {
+        stack_size: 1024 * 1024,
+    });
+
+    let outcomes = builder.check_outcomes(|| {
+        let num = Arc::new(AtomicUsize::new(0));
+        let num2 = num.clone();
+
+        let th = thread::spawn(move || num2.swap(1, SeqCst));
+        let prev = num.swap(2, SeqCst);
+
+        (prev, th.join().unwrap())
+    });
+
+    assert_eq!(outcomes.len(), 2);
+}
+
+#[test]
+#[should_panic]
+fn os_threads_race() {
+    incr(Runner::OsThreads {
+        stack_size: 1024 * 1024,
+    });
+}
+
+#[test]
+#[should_panic]
+fn fibers_race() {
+    incr(Runner::Fibers {
+        stack_size: 64 * 1024,
+    });
+}
+
+#[test]
+fn fibers_large_stack() {
+    fn recurse(n: usize) -> usize {
+        let buf = [n; 64];
+        if n == 0 {
+            0
+        } else {
+            buf[n % 64] + recurse(n - 1)
+        }
+    }
+
+    let mut builder = Builder::new();
+    builder.runner(Runner::Fibers {
+        stack_size: 4 * 1024 * 1024,
+    });
+
+    builder.check(|| {
+        let th = thread::spawn(|| recurse(2_000));
+        assert_eq!(recurse(2_000), th.join().unwrap());
+    });
+}
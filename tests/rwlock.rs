@@ -1,4 +1,4 @@
-use loom::sync::{Arc, RwLock};
+use loom::sync::{Arc, RwLock, RwLockUpgradableReadGuard, RwLockWriteGuard};
 use loom::thread;
 
 #[test]
@@ -74,3 +74,92 @@ fn rwlock_try_write() {
         assert!(lock.try_write().is_err());
     });
 }
+
+#[test]
+fn rwlock_downgrade_lets_readers_in() {
+    loom::model(|| {
+        let lock = Arc::new(RwLock::new(0));
+        let c_lock = lock.clone();
+
+        let mut n = lock.write().unwrap();
+
+        let th = thread::spawn(move || *c_lock.read().unwrap());
+
+        *n = 1;
+        let n = RwLockWriteGuard::downgrade(n);
+
+        // The reader is able to make progress while the read lock is held.
+        assert_eq!(1, th.join().unwrap());
+        assert_eq!(1, *n);
+    });
+}
+
+#[test]
+fn rwlock_upgrade_waits_for_readers() {
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering::Relaxed;
+
+    static UPGRADED_FIRST: AtomicUsize = AtomicUsize::new(0);
+
+    loom::model(|| {
+        let lock = Arc::new(RwLock::new(0));
+        let c_lock = lock.clone();
+
+        let th = thread::spawn(move || {
+            let n = c_lock.read().unwrap();
+            let v = *n;
+            thread::yield_now();
+            assert_eq!(v, *n);
+            v
+        });
+
+        let n = lock.upgradable_read().unwrap();
+        let mut n = RwLockUpgradableReadGuard::upgrade(n);
+        *n = 1;
+        drop(n);
+
+        if th.join().unwrap() == 1 {
+            UPGRADED_FIRST.fetch_add(1, Relaxed);
+        }
+    });
+
+    assert!(UPGRADED_FIRST.load(Relaxed) > 0);
+}
+
+#[test]
+fn rwlock_single_upgradable_reader() {
+    loom::model(|| {
+        let lock = RwLock::new(1);
+
+        let n = lock.upgradable_read().unwrap();
+        assert!(lock.try_upgradable_read().is_err());
+        assert!(lock.try_read().is_ok());
+
+        let n = RwLockUpgradableReadGuard::try_upgrade(n).ok().unwrap();
+        assert!(lock.try_read().is_err());
+
+        let n = RwLockWriteGuard::downgrade_to_upgradable(n);
+        let _n = RwLockUpgradableReadGuard::downgrade(n);
+        assert!(lock.try_upgradable_read().is_ok());
+    });
+}
+
+#[test]
+#[should_panic(expected = "deadlock")]
+fn rwlock_upgrade_while_reading_deadlocks() {
+    loom::model(|| {
+        let lock = Arc::new(RwLock::new(1));
+        let c_lock = lock.clone();
+
+        let th = thread::spawn(move || {
+            let _r = c_lock.read().unwrap();
+            let _w = c_lock.write().unwrap();
+        });
+
+        let n = lock.upgradable_read().unwrap();
+        let _n = RwLockUpgradableReadGuard::upgrade(n);
+        drop(_n);
+
+        th.join().unwrap();
+    });
+}
@@ -0,0 +1,94 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::model::{Builder, Schedule};
+use loom::sync::atomic::AtomicUsize;
+use loom::sync::Arc;
+use loom::thread;
+
+use std::sync::atomic::Ordering::SeqCst;
+
+fn lost_update() {
+    let num = Arc::new(AtomicUsize::new(0));
+    let num2 = num.clone();
+
+    let th = thread::spawn(move || {
+        let val = num2.load(SeqCst);
+        num2.store(val + 1, SeqCst);
+    });
+
+    let val = num.load(SeqCst);
+    num.store(val + 1, SeqCst);
+
+    th.join().unwrap();
+    assert_eq!(2, num.load(SeqCst), "lost update");
+}
+
+#[test]
+#[should_panic(expected = "lost update")]
+fn failing_schedule_is_replayed() {
+    let failure = Builder::new().check_expect_failure("lost update", lost_update);
+
+    Builder::new().check_schedule(&failure.choices, lost_update);
+}
+
+#[test]
+fn default_schedule_runs_threads_in_turn() {
+    // The main thread keeps running until it blocks on the join.
+    Builder::new().check_schedule(&Schedule::new(), lost_update);
+}
+
+#[test]
+fn schedule_is_parsed_from_failure() {
+    let failure = Builder::new().check_expect_failure("lost update", lost_update);
+
+    let schedule: Schedule = failure.schedule.join("\n").parse().unwrap();
+
+    assert_eq!(schedule, failure.choices);
+    assert_eq!(
+        schedule.to_string().lines().collect::<Vec<_>>(),
+        failure.schedule
+    );
+}
+
+#[test]
+fn invalid_choice_is_not_parsed() {
+    let err = "branch 1: switch to thread #x"
+        .parse::<Schedule>()
+        .unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        "invalid schedule choice `branch 1: switch to thread #x`"
+    );
+}
+
+#[test]
+fn choose_follows_schedule() {
+    let mut schedule = Schedule::new();
+    schedule.choose(0, 2).choose(1, 1);
+
+    Builder::new().check_schedule(&schedule, || {
+        assert_eq!(loom::choose(3), 2);
+        assert!(loom::branch());
+    });
+}
+
+#[test]
+#[should_panic(expected = "branch 0 could not switch to thread #2")]
+fn impossible_choice_fails() {
+    let mut schedule = Schedule::new();
+    schedule.switch_to(0, 2);
+
+    Builder::new().check_schedule(&schedule, || {
+        thread::spawn(|| {}).join().unwrap();
+    });
+}
+
+#[test]
+#[should_panic(expected = "ended after 1 branch points, before branch 3")]
+fn choice_past_the_end_fails() {
+    let mut schedule = Schedule::new();
+    schedule.choose(3, 1);
+
+    Builder::new().check_schedule(&schedule, || {});
+}
@@ -0,0 +1,123 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::cell::UnsafeCell;
+use loom::sync::atomic::AtomicUsize;
+use loom::sync::Semaphore;
+use loom::thread;
+
+use std::sync::atomic::Ordering::Relaxed;
+use std::sync::Arc;
+
+#[test]
+fn semaphore_mutual_exclusion() {
+    loom::model(|| {
+        let state = Arc::new((Semaphore::new(1), UnsafeCell::new(0)));
+
+        let ths: Vec<_> = (0..2)
+            .map(|_| {
+                let state = state.clone();
+
+                thread::spawn(move || {
+                    state.0.acquire();
+                    state.1.with_mut(|v| unsafe { *v += 1 });
+                    state.0.release();
+                })
+            })
+            .collect();
+
+        for th in ths {
+            th.join().unwrap();
+        }
+
+        state.0.acquire();
+        assert_eq!(2, state.1.with(|v| unsafe { *v }));
+    });
+}
+
+#[test]
+#[should_panic]
+fn semaphore_too_many_permits() {
+    loom::model(|| {
+        let state = Arc::new((Semaphore::new(2), UnsafeCell::new(0)));
+
+        let ths: Vec<_> = (0..2)
+            .map(|_| {
+                let state = state.clone();
+
+                thread::spawn(move || {
+                    state.0.acquire();
+                    state.1.with_mut(|v| unsafe { *v += 1 });
+                    state.0.release();
+                })
+            })
+            .collect();
+
+        for th in ths {
+            th.join().unwrap();
+        }
+    });
+}
+
+#[test]
+fn semaphore_release_acquire_synchronizes() {
+    loom::model(|| {
+        let state = Arc::new((Semaphore::new(0), UnsafeCell::new(0)));
+        let state2 = state.clone();
+
+        thread::spawn(move || {
+            state2.1.with_mut(|v| unsafe { *v = 1 });
+            state2.0.release();
+        });
+
+        state.0.acquire();
+        assert_eq!(1, state.1.with(|v| unsafe { *v }));
+    });
+}
+
+#[test]
+fn semaphore_acquire_many() {
+    loom::model(|| {
+        let sem = Arc::new(Semaphore::new(0));
+        let num = Arc::new(AtomicUsize::new(0));
+
+        let ths: Vec<_> = (0..2)
+            .map(|_| {
+                let sem = sem.clone();
+                let num = num.clone();
+
+                thread::spawn(move || {
+                    num.fetch_add(1, Relaxed);
+                    sem.release();
+                })
+            })
+            .collect();
+
+        sem.acquire_many(2);
+        assert_eq!(2, num.load(Relaxed));
+        assert_eq!(0, sem.available_permits());
+
+        for th in ths {
+            th.join().unwrap();
+        }
+    });
+}
+
+#[test]
+fn semaphore_try_acquire() {
+    loom::model(|| {
+        let sem = Arc::new(Semaphore::new(1));
+        let sem2 = sem.clone();
+
+        let th = thread::spawn(move || sem2.try_acquire());
+
+        let acquired = sem.try_acquire();
+        let other = th.join().unwrap();
+
+        // Exactly one thread acquires the permit
+        assert!(acquired ^ other);
+        assert!(!sem.try_acquire_many(1));
+
+        sem.release_many(2);
+        assert!(sem.try_acquire_many(2));
+    });
+}
@@ -0,0 +1,85 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::model::Builder;
+use loom::sync::atomic::AtomicUsize;
+use loom::sync::{Arc, Mutex};
+use loom::thread;
+
+use std::collections::BTreeSet;
+use std::sync::atomic::Ordering::SeqCst;
+
+/// Explores threads pushing to a shared log, returning the number of
+/// executions and the distinct outcomes.
+fn explore(sleep_sets: bool) -> (usize, BTreeSet<String>) {
+    let outcomes = std::sync::Arc::new(std::sync::Mutex::new(BTreeSet::new()));
+    let observed = outcomes.clone();
+
+    let mut builder = Builder::new();
+    builder.sleep_sets = sleep_sets;
+
+    let stats = builder.check_returning_stats(move || {
+        let log = Arc::new(Mutex::new(String::new()));
+        let counters: Vec<_> = (0..3).map(|_| Arc::new(AtomicUsize::new(0))).collect();
+
+        let ths: Vec<_> = ["a", "b", "c"]
+            .iter()
+            .zip(counters.iter().cloned())
+            .map(|(name, counter)| {
+                let log = log.clone();
+
+                thread::spawn(move || {
+                    // Independent of the other threads
+                    counter.fetch_add(1, SeqCst);
+                    log.lock().unwrap().push_str(name);
+                })
+            })
+            .collect();
+
+        for th in ths {
+            th.join().unwrap();
+        }
+
+        let log = log.lock().unwrap().clone();
+        observed.lock().unwrap().insert(log);
+    });
+
+    let outcomes = outcomes.lock().unwrap().clone();
+    (stats.iterations, outcomes)
+}
+
+#[test]
+fn sleep_sets_explore_fewer_executions() {
+    let (with, with_outcomes) = explore(true);
+    let (without, without_outcomes) = explore(false);
+
+    assert!(with < without, "{} >= {}", with, without);
+    assert_eq!(6, with_outcomes.len());
+    assert_eq!(with_outcomes, without_outcomes);
+}
+
+#[test]
+#[should_panic]
+fn sleep_sets_find_lost_update() {
+    let mut builder = Builder::new();
+    builder.sleep_sets = true;
+
+    builder.check(|| {
+        let num = Arc::new(AtomicUsize::new(0));
+
+        let ths: Vec<_> = (0..2)
+            .map(|_| {
+                let num = num.clone();
+                thread::spawn(move || {
+                    let n = num.load(SeqCst);
+                    num.store(n + 1, SeqCst);
+                })
+            })
+            .collect();
+
+        for th in ths {
+            th.join().unwrap();
+        }
+
+        assert_eq!(2, num.load(SeqCst));
+    });
+}
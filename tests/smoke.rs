@@ -1,5 +1,6 @@
 #![deny(warnings, rust_2018_idioms)]
 
+use loom::model::Builder;
 use loom::sync::atomic::AtomicUsize;
 use loom::thread;
 
@@ -43,3 +44,29 @@ fn checks_fail() {
         assert_eq!(2, buggy_inc.num.load(Relaxed));
     });
 }
+
+#[test]
+#[should_panic(expected = "loom models cannot be nested")]
+fn nested_models_fail() {
+    loom::model(|| {
+        loom::model(|| {});
+    });
+}
+
+#[test]
+fn builder_can_be_created_in_models() {
+    loom::model(|| {
+        let mut builder = Builder::new();
+        builder.max_threads = 2;
+    });
+}
+
+#[test]
+fn nested_models_fail_in_spawned_threads() {
+    loom::model(|| {
+        let err = thread::spawn(|| loom::model(|| {})).join().unwrap_err();
+        let msg = err.downcast_ref::<&str>().unwrap();
+
+        assert!(msg.contains("loom models cannot be nested"));
+    });
+}
@@ -0,0 +1,129 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::model::Builder;
+use loom::sync::atomic::AtomicUsize;
+use loom::sync::{Arc, Mutex};
+use loom::thread;
+
+use std::sync::atomic::Ordering::{Relaxed, SeqCst};
+
+#[test]
+fn setup_runs_once() {
+    let setups = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let iterations = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+
+    let s = setups.clone();
+    let i = iterations.clone();
+
+    Builder::new().check_with_snapshot(
+        move || {
+            s.fetch_add(1, Relaxed);
+
+            let num = Arc::new(AtomicUsize::new(0));
+            for _ in 0..3 {
+                num.fetch_add(1, SeqCst);
+            }
+            num
+        },
+        move |num| {
+            i.fetch_add(1, Relaxed);
+
+            let num2 = num.clone();
+            let th = thread::spawn(move || num2.fetch_add(1, SeqCst));
+            num.fetch_add(1, SeqCst);
+
+            th.join().unwrap();
+            assert_eq!(5, num.load(SeqCst));
+        },
+    );
+
+    assert_eq!(1, setups.load(Relaxed));
+    assert!(iterations.load(Relaxed) > 1);
+}
+
+#[test]
+#[should_panic]
+fn race_after_setup() {
+    Builder::new().check_with_snapshot(
+        || {
+            let num = Arc::new(AtomicUsize::new(0));
+            num.store(1, SeqCst);
+            num
+        },
+        |num| {
+            let num2 = num.clone();
+            let th = thread::spawn(move || {
+                let v = num2.load(SeqCst);
+                num2.store(v + 1, SeqCst);
+            });
+
+            let v = num.load(SeqCst);
+            num.store(v + 1, SeqCst);
+
+            th.join().unwrap();
+            assert_eq!(3, num.load(SeqCst));
+        },
+    );
+}
+
+#[test]
+fn setup_reruns_on_branches() {
+    let setups = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let s = setups.clone();
+
+    Builder::new().check_with_snapshot(
+        move || {
+            s.fetch_add(1, Relaxed);
+
+            let choice = loom::model::choose(3);
+            (choice, Arc::new(AtomicUsize::new(choice)))
+        },
+        |(choice, num)| {
+            let num2 = num.clone();
+            let th = thread::spawn(move || num2.fetch_add(1, SeqCst));
+            num.fetch_add(1, SeqCst);
+
+            th.join().unwrap();
+            assert_eq!(choice + 2, num.load(SeqCst));
+        },
+    );
+
+    assert_eq!(3, setups.load(Relaxed));
+}
+
+/// A state whose `Clone` implementation copies the contents of its mutex.
+struct Values(Arc<Mutex<Vec<usize>>>);
+
+impl Clone for Values {
+    fn clone(&self) -> Values {
+        let values = self.0.lock().unwrap().clone();
+        Values(Arc::new(Mutex::new(values)))
+    }
+}
+
+fn push_from_two_threads(values: Arc<Mutex<Vec<usize>>>) {
+    let values2 = values.clone();
+    let th = thread::spawn(move || values2.lock().unwrap().push(1));
+    values.lock().unwrap().push(2);
+
+    th.join().unwrap();
+
+    let values = values.lock().unwrap();
+    assert!(values.len() == 2, "state leaked: {:?}", *values);
+}
+
+#[test]
+fn deep_copied_state_is_restored() {
+    Builder::new().check_with_snapshot(
+        || Values(Arc::new(Mutex::new(vec![]))),
+        |values| push_from_two_threads(values.0),
+    );
+}
+
+/// Cloning an `Arc` shares the contents of the mutex with the snapshot: loom
+/// does not restore them, and the executions observe each other's changes.
+#[test]
+#[should_panic(expected = "state leaked")]
+fn shared_state_leaks_across_executions() {
+    Builder::new().check_with_snapshot(|| Arc::new(Mutex::new(vec![])), push_from_two_threads);
+}
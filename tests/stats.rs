@@ -0,0 +1,219 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::model::Builder;
+use loom::sync::atomic::AtomicUsize;
+use loom::thread;
+
+use std::sync::atomic::Ordering::{Acquire, Relaxed, Release};
+use std::sync::Arc;
+
+#[test]
+fn stats_exhaustive() {
+    let iterations = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let counter = iterations.clone();
+
+    let stats = Builder::new().check_returning_stats(move || {
+        counter.fetch_add(1, Relaxed);
+
+        let num = Arc::new(AtomicUsize::new(0));
+
+        let ths: Vec<_> = (0..2)
+            .map(|_| {
+                let num = num.clone();
+                thread::spawn(move || num.fetch_add(1, Relaxed))
+            })
+            .collect();
+
+        for th in ths {
+            th.join().unwrap();
+        }
+    });
+
+    assert_eq!(iterations.load(Relaxed), stats.iterations);
+    assert_eq!(stats.iterations, stats.unique_schedules);
+    assert_eq!(3, stats.max_threads);
+    assert_eq!(0, stats.preemption_pruned);
+}
+
+#[test]
+fn stats_max_threads_counts_live_threads() {
+    let stats = Builder::new().check_returning_stats(|| {
+        for _ in 0..2 {
+            thread::spawn(|| {}).join().unwrap();
+        }
+    });
+
+    assert_eq!(2, stats.max_threads);
+}
+
+#[test]
+fn stats_random_schedules_repeat() {
+    let mut builder = Builder::new();
+    builder.random_schedules(100, 3);
+
+    let stats = builder.check_returning_stats(|| {
+        let num = Arc::new(AtomicUsize::new(0));
+        let num2 = num.clone();
+
+        let th = thread::spawn(move || num2.store(1, Release));
+
+        num.load(Acquire);
+        th.join().unwrap();
+    });
+
+    assert_eq!(100, stats.iterations);
+    assert!(stats.unique_schedules > 1);
+    assert!(stats.unique_schedules < stats.iterations);
+}
+
+#[test]
+fn stats_preemption_pruned() {
+    let mut builder = Builder::new();
+    builder.preemption_bound = Some(1);
+
+    let stats = builder.check_returning_stats(|| {
+        let num = Arc::new(AtomicUsize::new(0));
+
+        let ths: Vec<_> = (0..2)
+            .map(|_| {
+                let num = num.clone();
+                thread::spawn(move || {
+                    for _ in 0..2 {
+                        num.fetch_add(1, Relaxed);
+                    }
+                })
+            })
+            .collect();
+
+        for th in ths {
+            th.join().unwrap();
+        }
+    });
+
+    assert!(stats.preemption_pruned > 0);
+}
+
+#[test]
+fn stats_object_counts() {
+    use loom::sync::Mutex;
+
+    let stats = Builder::new().check_returning_stats(|| {
+        let num = Arc::new(AtomicUsize::new(0));
+        let lock = loom::sync::Arc::new(Mutex::new(()));
+
+        let th = thread::spawn(move || {
+            drop(lock.lock().unwrap());
+            num.fetch_add(1, Relaxed);
+        });
+
+        th.join().unwrap();
+    });
+
+    assert_eq!(1, stats.max_objects.atomics);
+    assert_eq!(1, stats.max_objects.mutexes);
+    assert_eq!(1, stats.max_objects.arcs);
+    assert!(stats.max_objects.total >= 3);
+}
+
+#[test]
+fn stats_alloc_bytes() {
+    use loom::alloc::{alloc, dealloc, Layout};
+
+    let stats = Builder::new().check_returning_stats(|| unsafe {
+        let layout = Layout::from_size_align(16, 8).unwrap();
+        let a = alloc(layout);
+        let b = alloc(layout);
+        dealloc(a, layout);
+        dealloc(b, layout);
+
+        let c = alloc(layout);
+        dealloc(c, layout);
+    });
+
+    assert_eq!(32, stats.max_alloc_bytes);
+}
+
+#[test]
+#[should_panic(expected = "more than the maximum of 2")]
+fn max_objects_exceeded() {
+    let mut builder = Builder::new();
+    builder.max_objects = Some(2);
+
+    builder.check(|| {
+        let nums: Vec<_> = (0..3).map(|_| AtomicUsize::new(0)).collect();
+
+        thread::spawn(move || {
+            for num in &nums {
+                num.load(Relaxed);
+            }
+        })
+        .join()
+        .unwrap();
+    });
+}
+
+#[test]
+#[should_panic(expected = "raise `max_alloc_bytes`")]
+fn max_alloc_bytes_exceeded() {
+    use loom::alloc::{alloc, dealloc, Layout};
+
+    let mut builder = Builder::new();
+    builder.max_alloc_bytes = Some(16);
+
+    builder.check(|| unsafe {
+        let layout = Layout::from_size_align(32, 8).unwrap();
+        dealloc(alloc(layout), layout);
+    });
+}
+
+#[test]
+fn stats_branch_profile() {
+    let mut builder = Builder::new();
+    builder.profile_branches = true;
+
+    let stats = builder.check_returning_stats(|| {
+        let hot = Arc::new(AtomicUsize::new(0));
+        let cold = AtomicUsize::new(0);
+
+        let ths: Vec<_> = (0..2)
+            .map(|_| {
+                let hot = hot.clone();
+                thread::spawn(move || hot.fetch_add(1, Relaxed))
+            })
+            .collect();
+
+        cold.store(1, Relaxed);
+        hot.load(Acquire);
+
+        for th in ths {
+            th.join().unwrap();
+        }
+    });
+
+    let hottest = &stats.branch_profile[0];
+    assert_eq!("atomic", hottest.kind);
+    assert!(hottest.branches > 0);
+    assert_eq!(file!(), hottest.location.unwrap().file());
+
+    // Only the main thread accesses `cold`, it causes no branches.
+    let atomics = stats
+        .branch_profile
+        .iter()
+        .filter(|object| object.kind == "atomic");
+    assert_eq!(1, atomics.count());
+}
+
+#[test]
+fn stats_branch_profile_disabled() {
+    let stats = Builder::new().check_returning_stats(|| {
+        let num = Arc::new(AtomicUsize::new(0));
+        let num2 = num.clone();
+
+        thread::spawn(move || num2.store(1, Release))
+            .join()
+            .unwrap();
+        num.load(Acquire);
+    });
+
+    assert!(stats.branch_profile.is_empty());
+}
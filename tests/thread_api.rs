@@ -103,3 +103,62 @@ fn thread_names() {
         let _ = th.join();
     })
 }
+
+#[test]
+fn builder_spawn_failures() {
+    use std::sync::{Arc, Mutex};
+
+    let outcomes = Arc::new(Mutex::new(vec![]));
+    let observed = outcomes.clone();
+
+    let mut builder = loom::model::Builder::new();
+    builder.spawn_failures = true;
+    builder.check(move || {
+        let res = thread::Builder::new()
+            .name("worker".to_string())
+            .stack_size(16 * 1024)
+            .spawn(|| 1);
+
+        let joined = res.map(|th| th.join().unwrap());
+        observed.lock().unwrap().push(joined.is_ok());
+
+        if let Ok(value) = joined {
+            assert_eq!(1, value);
+        }
+    });
+
+    assert_eq!(vec![true, false], *outcomes.lock().unwrap());
+}
+
+#[test]
+#[should_panic(expected = "2 threads were spawned, 1 of them still alive, and `max_threads` is 2")]
+fn spawn_exceeds_max_threads() {
+    let mut builder = loom::model::Builder::new();
+    builder.max_threads = 2;
+
+    builder.check(|| {
+        thread::spawn(|| {}).join().unwrap();
+        thread::spawn(|| {}).join().unwrap();
+    });
+}
+
+#[test]
+#[should_panic(expected = "`max_threads` must be between 1 and")]
+fn max_threads_above_limit() {
+    let mut builder = loom::model::Builder::new();
+    builder.max_threads = loom::MAX_THREADS + 1;
+
+    builder.check(|| {});
+}
+
+#[test]
+fn loom_type_used_from_std_thread_is_reported() {
+    loom::model(|| {
+        let panic = std::thread::spawn(|| loom::sync::atomic::AtomicUsize::new(0))
+            .join()
+            .unwrap_err();
+
+        let message = panic.downcast_ref::<&str>().unwrap();
+        assert!(message.contains("`std::thread::spawn`"), "{}", message);
+    });
+}
@@ -0,0 +1,107 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::model::Builder;
+use loom::sync::atomic::AtomicUsize;
+use loom::sync::{Arc, Condvar, Mutex};
+use loom::thread;
+
+use std::sync::atomic::Ordering::{Relaxed, SeqCst};
+
+#[test]
+fn killed_thread_poisons_lock() {
+    static KILLED: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+    let mut builder = Builder::new();
+    builder.inject_thread_failure(true);
+
+    builder.check(|| {
+        let num = Arc::new(Mutex::new(0));
+        let num2 = num.clone();
+
+        let th = thread::spawn(move || {
+            *num2.lock().unwrap() += 1;
+        });
+
+        match th.join() {
+            Ok(()) => assert_eq!(1, *num.lock().unwrap()),
+            Err(panic) => {
+                assert_eq!(
+                    Some(&"thread killed by an injected failure"),
+                    panic.downcast_ref::<&str>()
+                );
+
+                // Killed either before locking or while holding the lock.
+                if let Err(poisoned) = num.lock() {
+                    assert_eq!(1, *poisoned.into_inner());
+                }
+
+                KILLED.fetch_add(1, SeqCst);
+            }
+        }
+    });
+
+    assert!(KILLED.load(SeqCst) > 0);
+}
+
+#[test]
+fn killed_thread_need_not_be_joined() {
+    let mut builder = Builder::new();
+    builder.inject_thread_failure(true);
+
+    builder.check(|| {
+        let num = Arc::new(AtomicUsize::new(0));
+        let num2 = num.clone();
+
+        thread::spawn(move || {
+            num2.store(1, Relaxed);
+        });
+
+        num.load(Relaxed);
+    });
+}
+
+#[test]
+#[should_panic(expected = "thread #1 was killed by an injected failure")]
+fn killed_thread_orphans_allocation() {
+    let mut builder = Builder::new();
+    builder.inject_thread_failure(true);
+
+    builder.check(|| {
+        let num = Arc::new(AtomicUsize::new(0));
+        let num2 = num.clone();
+
+        let th = thread::spawn(move || unsafe {
+            let layout = loom::alloc::Layout::new::<usize>();
+            let ptr = loom::alloc::alloc(layout);
+            num2.store(1, Relaxed);
+            loom::alloc::dealloc(ptr, layout);
+        });
+
+        let _ = th.join();
+    });
+}
+
+#[test]
+#[should_panic(expected = "thread #1: killed by an injected failure")]
+fn killed_thread_leaves_waiter_blocked() {
+    let mut builder = Builder::new();
+    builder.inject_thread_failure(true);
+
+    builder.check(|| {
+        let pair = Arc::new((Mutex::new(false), Condvar::new()));
+        let pair2 = pair.clone();
+
+        thread::spawn(move || {
+            let (lock, cvar) = &*pair2;
+            *lock.lock().unwrap() = true;
+            cvar.notify_one();
+        });
+
+        let (lock, cvar) = &*pair;
+        let mut ready = lock.lock().unwrap();
+
+        while !*ready {
+            ready = cvar.wait(ready).unwrap();
+        }
+    });
+}
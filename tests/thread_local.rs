@@ -1,6 +1,6 @@
 #![deny(warnings, rust_2018_idioms)]
 use loom::thread;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::sync::atomic::{AtomicUsize, Ordering};
 
 #[test]
@@ -107,3 +107,73 @@ fn drop() {
     // should also be dropped.
     assert_eq!(DROPS.load(Ordering::Acquire), 3);
 }
+
+#[test]
+fn const_initializer() {
+    loom::thread_local! {
+        static A: Cell<usize> = const { Cell::new(1) };
+        static B: RefCell<Vec<usize>> = const { RefCell::new(Vec::new()) }
+    }
+
+    loom::model(|| {
+        assert_eq!(A.get(), 1);
+        B.with_borrow_mut(|v| v.push(2));
+        B.with_borrow(|v| assert_eq!(v[..], [2]));
+    });
+}
+
+#[test]
+fn cell_and_refcell_accessors() {
+    loom::thread_local! {
+        static CELL: Cell<usize> = Cell::new(1);
+        static REF_CELL: RefCell<usize> = panic!("set without initializing");
+    }
+
+    loom::model(|| {
+        let th = thread::spawn(|| {
+            CELL.set(2);
+            assert_eq!(CELL.replace(3), 2);
+            assert_eq!(CELL.take(), 3);
+            assert_eq!(CELL.get(), 0);
+
+            REF_CELL.set(4);
+            assert_eq!(REF_CELL.replace(5), 4);
+            assert_eq!(REF_CELL.take(), 5);
+        });
+
+        // Each thread has its own value.
+        assert_eq!(CELL.get(), 1);
+        th.join().unwrap();
+        assert_eq!(CELL.get(), 1);
+    });
+}
+
+#[test]
+fn destruction_orders_are_explored() {
+    static ACCESSED: AtomicUsize = AtomicUsize::new(0);
+    static DESTROYED: AtomicUsize = AtomicUsize::new(0);
+
+    struct AccessOther;
+
+    impl Drop for AccessOther {
+        fn drop(&mut self) {
+            match OTHER.try_with(|_| ()) {
+                Ok(()) => ACCESSED.fetch_add(1, Ordering::Relaxed),
+                Err(_) => DESTROYED.fetch_add(1, Ordering::Relaxed),
+            };
+        }
+    }
+
+    loom::thread_local! {
+        static LOCAL: AccessOther = AccessOther;
+        static OTHER: Cell<usize> = Cell::new(0);
+    }
+
+    loom::model(|| {
+        LOCAL.with(|_| ());
+        OTHER.set(1);
+    });
+
+    assert_eq!(ACCESSED.load(Ordering::Relaxed), 1);
+    assert_eq!(DESTROYED.load(Ordering::Relaxed), 1);
+}
@@ -0,0 +1,63 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::cell::UnsafeCell;
+use loom::sync::Arc;
+use loom::thread;
+
+#[test]
+fn join_returns_panic() {
+    loom::model(|| {
+        let th = thread::spawn(|| {
+            if true {
+                panic!("boom");
+            }
+        });
+
+        let panic = th.join().unwrap_err();
+        assert_eq!(Some(&"boom"), panic.downcast_ref::<&str>());
+    });
+}
+
+#[test]
+fn panic_with_waiting_joiner() {
+    loom::model(|| {
+        let num = Arc::new(loom::sync::atomic::AtomicUsize::new(0));
+        let num2 = num.clone();
+
+        let th = thread::spawn(move || {
+            num2.store(1, std::sync::atomic::Ordering::SeqCst);
+            panic!("boom");
+        });
+
+        num.load(std::sync::atomic::Ordering::SeqCst);
+        assert!(th.join().is_err());
+    });
+}
+
+#[test]
+#[should_panic(expected = "boom")]
+fn unjoined_panic_fails_model() {
+    loom::model(|| {
+        thread::spawn(|| {
+            panic!("boom");
+        });
+    });
+}
+
+#[test]
+#[should_panic]
+fn loom_failures_are_not_returned() {
+    loom::model(|| {
+        let cell = Arc::new(UnsafeCell::new(0));
+        let cell2 = cell.clone();
+
+        let th = thread::spawn(move || {
+            cell2.with_mut(|v| unsafe { *v = 1 });
+        });
+
+        cell.with_mut(|v| unsafe { *v = 2 });
+
+        // The data race fails the model even though the result is ignored.
+        let _ = th.join();
+    });
+}
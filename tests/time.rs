@@ -0,0 +1,78 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::sync::atomic::AtomicBool;
+use loom::thread;
+use loom::time::{self, Duration, Instant};
+
+use std::collections::BTreeSet;
+use std::sync::atomic::Ordering::{Acquire, Release};
+use std::sync::Arc;
+
+#[test]
+fn clock_starts_still() {
+    loom::model(|| {
+        let start = Instant::now();
+
+        assert_eq!(Duration::from_secs(0), start.elapsed());
+
+        time::advance(Duration::from_millis(5));
+
+        assert_eq!(Duration::from_millis(5), start.elapsed());
+        assert!(Instant::now() > start);
+        assert_eq!(start + Duration::from_millis(5), Instant::now());
+    });
+}
+
+#[test]
+fn timeout_is_explored() {
+    let outcomes = loom::model_outcomes(|| {
+        let done = Arc::new(AtomicBool::new(false));
+        let deadline = Instant::now() + Duration::from_millis(10);
+
+        let th = {
+            let done = done.clone();
+            thread::spawn(move || {
+                time::advance(Duration::from_millis(10));
+                done.store(true, Release);
+            })
+        };
+
+        let done = done.load(Acquire);
+        let timed_out = Instant::now() >= deadline;
+
+        th.join().unwrap();
+
+        (done, timed_out)
+    });
+
+    // Observing the store means observing the time that passed before it.
+    let expect: BTreeSet<_> = vec![(false, false), (false, true), (true, true)]
+        .into_iter()
+        .collect();
+
+    assert_eq!(expect, outcomes);
+}
+
+#[test]
+fn sleep_in_backoff_loop() {
+    loom::model(|| {
+        let ready = Arc::new(AtomicBool::new(false));
+        let start = Instant::now();
+
+        let th = {
+            let ready = ready.clone();
+            thread::spawn(move || ready.store(true, Release))
+        };
+
+        let mut backoff = Duration::from_millis(1);
+
+        while !ready.load(Acquire) {
+            thread::sleep(backoff);
+            backoff *= 2;
+        }
+
+        assert!(start.elapsed() < backoff);
+
+        th.join().unwrap();
+    });
+}
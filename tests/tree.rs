@@ -0,0 +1,157 @@
+#![deny(warnings, rust_2018_idioms)]
+
+use loom::explore::{ExplorationTree, TreeNode};
+use loom::model::Builder;
+use loom::observe::BranchKind;
+use loom::sync::atomic::AtomicUsize;
+use loom::sync::Arc;
+use loom::thread;
+
+use std::sync::atomic::Ordering::{Relaxed, SeqCst};
+
+fn record(builder: &mut Builder, f: impl Fn() + Sync + Send + 'static) -> (usize, ExplorationTree) {
+    builder.record_tree = true;
+
+    let stats = builder.check_returning_stats(f);
+    (stats.iterations, stats.tree.unwrap())
+}
+
+/// Returns the number of options of `nodes[index]` leading to another branch
+/// point.
+fn children(nodes: &[TreeNode], index: usize) -> usize {
+    let depth = nodes[index].depth;
+
+    nodes[index + 1..]
+        .iter()
+        .take_while(|node| node.depth > depth)
+        .filter(|node| node.depth == depth + 1)
+        .count()
+}
+
+#[test]
+fn every_execution_ends_at_a_leaf() {
+    let (iterations, tree) = record(&mut Builder::new(), || {
+        let num = Arc::new(AtomicUsize::new(0));
+        let num2 = num.clone();
+
+        let th = thread::spawn(move || {
+            num2.store(1, Relaxed);
+            num2.store(2, Relaxed);
+        });
+
+        num.load(Relaxed);
+        th.join().unwrap();
+    });
+
+    let nodes = &tree.nodes;
+    assert_eq!(nodes[0].depth, 0);
+    assert!(nodes.iter().any(|node| node.kind == BranchKind::Load));
+
+    // Each execution ends at a leaf, other nodes branch once per option
+    // explored.
+    let mut leaves = 0;
+
+    for i in 0..nodes.len() {
+        match children(nodes, i) {
+            0 => leaves += 1,
+            n => assert_eq!(n, nodes[i].explored, "{}", tree),
+        }
+    }
+
+    assert_eq!(leaves, iterations, "{}", tree);
+
+    for node in nodes {
+        assert_eq!(
+            node.explored + node.dpor_pruned + node.sleep_pruned + node.bound_pruned,
+            node.options,
+            "{:?}",
+            node
+        );
+    }
+}
+
+#[test]
+fn independent_threads_are_pruned() {
+    let (iterations, tree) = record(&mut Builder::new(), || {
+        let a = Arc::new(AtomicUsize::new(0));
+        let b = Arc::new(AtomicUsize::new(0));
+
+        let th = thread::spawn(move || a.store(1, SeqCst));
+        b.store(1, SeqCst);
+
+        th.join().unwrap();
+    });
+
+    let pruned: usize = tree
+        .nodes
+        .iter()
+        .map(|node| node.dpor_pruned + node.sleep_pruned)
+        .sum();
+
+    assert_eq!(iterations, 1);
+    assert!(pruned > 0, "{}", tree);
+    assert!(tree.nodes.iter().all(|node| node.bound_pruned == 0));
+}
+
+#[test]
+fn preemption_bound_prunes() {
+    let mut builder = Builder::new();
+    builder.preemption_bound = Some(0);
+
+    let (_, tree) = record(&mut builder, || {
+        let num = Arc::new(AtomicUsize::new(0));
+        let num2 = num.clone();
+
+        let th = thread::spawn(move || {
+            num2.fetch_add(1, SeqCst);
+        });
+
+        num.fetch_add(1, SeqCst);
+        th.join().unwrap();
+    });
+
+    assert!(
+        tree.nodes.iter().any(|node| node.bound_pruned > 0),
+        "{}",
+        tree
+    );
+}
+
+#[test]
+fn tree_is_written_to_file() {
+    let path = std::env::temp_dir().join(format!("loom-tree-{}.txt", std::process::id()));
+
+    let mut builder = Builder::new();
+    builder.tree_file(path.to_str().unwrap());
+
+    let stats = builder.check_returning_stats(|| {
+        let num = Arc::new(AtomicUsize::new(0));
+        let num2 = num.clone();
+
+        let th = thread::spawn(move || num2.store(1, SeqCst));
+        num.load(SeqCst);
+
+        th.join().unwrap();
+    });
+
+    let contents = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let tree = stats.tree.unwrap();
+    assert_eq!(contents, tree.to_string());
+    assert_eq!(contents.lines().count(), tree.nodes.len());
+    assert!(contents.starts_with("0 S "), "{}", contents);
+}
+
+#[test]
+fn random_schedules_are_not_recorded() {
+    let mut builder = Builder::new();
+    builder.record_tree = true;
+    builder.random_schedules(3, 1);
+
+    let stats = builder.check_returning_stats(|| {
+        thread::spawn(|| {}).join().unwrap();
+    });
+
+    assert!(stats.tree.is_none());
+}
@@ -1,9 +1,11 @@
 #![deny(warnings, rust_2018_idioms)]
 
 use loom::cell::UnsafeCell;
+use loom::model::Builder;
 use loom::sync::atomic::AtomicUsize;
 use loom::thread;
 
+use std::panic;
 use std::sync::atomic::Ordering::{Acquire, Release};
 use std::sync::Arc;
 
@@ -333,3 +335,55 @@ fn unsafe_cell_access_after_sync() {
         }
     });
 }
+
+#[test]
+#[should_panic(expected = "Concurrent write accesses to `UnsafeCell`")]
+fn unsafe_cell_race_within_closure() {
+    loom::model(|| {
+        let s1 = Arc::new((AtomicUsize::new(0), UnsafeCell::new(0)));
+        let s2 = s1.clone();
+
+        let th = thread::spawn(move || {
+            s1.1.with_mut(|_| s1.0.store(1, Release));
+        });
+
+        s2.1.with_mut(|_| s2.0.store(2, Release));
+
+        th.join().unwrap();
+    });
+}
+
+#[test]
+fn unsafe_cell_race_reports_both_accesses() {
+    let mut builder = Builder::new();
+    builder.location = true;
+
+    let err = panic::catch_unwind(|| {
+        builder.check(|| {
+            let s1 = Arc::new(UnsafeCell::new(0));
+            let s2 = s1.clone();
+
+            let th = thread::spawn(move || {
+                s1.with_mut(|ptr| unsafe { *ptr = 1 });
+            });
+
+            s2.with(|ptr| unsafe { *ptr });
+
+            th.join().unwrap();
+        });
+    })
+    .unwrap_err();
+
+    let msg = err.downcast_ref::<String>().unwrap();
+
+    assert!(
+        msg.contains("read: thread #0 @ tests/unsafe_cell.rs:370:"),
+        "{}",
+        msg
+    );
+    assert!(
+        msg.contains("write: thread #1 @ tests/unsafe_cell.rs:367:"),
+        "{}",
+        msg
+    );
+}
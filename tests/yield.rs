@@ -1,9 +1,10 @@
 #![deny(warnings, rust_2018_idioms)]
 
+use loom::model::YieldMode;
 use loom::sync::atomic::AtomicUsize;
 use loom::thread;
 
-use std::sync::atomic::Ordering::Relaxed;
+use std::sync::atomic::Ordering::{Relaxed, SeqCst};
 use std::sync::Arc;
 
 #[test]
@@ -27,3 +28,136 @@ fn yield_completes() {
         }
     });
 }
+
+/// Two threads taking turns, each spinning with `yield_now` until its turn.
+fn ping_pong(builder: loom::model::Builder) -> usize {
+    builder
+        .check_returning_stats(|| {
+            let turn = Arc::new(loom::sync::atomic::AtomicUsize::new(0));
+
+            let th = {
+                let turn = turn.clone();
+                thread::spawn(move || {
+                    for round in 0..2 {
+                        while turn.load(Relaxed) != 2 * round + 1 {
+                            thread::yield_now();
+                        }
+                        turn.store(2 * round + 2, Relaxed);
+                    }
+                })
+            };
+
+            for round in 0..2 {
+                while turn.load(Relaxed) != 2 * round {
+                    thread::yield_now();
+                }
+                turn.store(2 * round + 1, Relaxed);
+            }
+
+            th.join().unwrap();
+            assert_eq!(4, turn.load(Relaxed));
+        })
+        .iterations
+}
+
+#[test]
+fn max_yields_bounds_yield_loops() {
+    let all = ping_pong(loom::model::Builder::new());
+
+    let mut builder = loom::model::Builder::new();
+    builder.max_yields = Some(0);
+    let bounded = ping_pong(builder);
+
+    assert!(bounded < all, "{} < {}", bounded, all);
+}
+
+#[test]
+fn preemptive_yields_count_against_the_bound() {
+    let mut builder = loom::model::Builder::new();
+    builder.preemption_bound = Some(1);
+    let all = ping_pong(builder);
+
+    let mut builder = loom::model::Builder::new();
+    builder.preemption_bound = Some(1);
+    builder.preemptive_yields = true;
+    let bounded = ping_pong(builder);
+
+    assert!(bounded < all, "{} < {}", bounded, all);
+}
+
+/// Spins with `yield_now` until a spawned thread stores.
+fn spin_until_stored() {
+    let stored = Arc::new(AtomicUsize::new(0));
+
+    {
+        let stored = stored.clone();
+        thread::spawn(move || {
+            stored.store(1, SeqCst);
+        });
+    }
+
+    while stored.load(SeqCst) == 0 {
+        thread::yield_now();
+    }
+}
+
+#[test]
+#[should_panic(expected = "maximum number of branches")]
+fn preemption_yields_keep_running_the_yielding_thread() {
+    let mut builder = loom::model::Builder::new();
+    builder.yield_mode = YieldMode::Preemption;
+    builder.max_branches = 20;
+
+    builder.check(spin_until_stored);
+}
+
+#[test]
+fn preemption_yields_explore_more_schedules() {
+    let mut builder = loom::model::Builder::new();
+    builder.max_branches = 20;
+    let heuristic = builder.check_returning_stats(spin_until_stored);
+
+    let mut builder = loom::model::Builder::new();
+    builder.yield_mode = YieldMode::Preemption;
+    builder.max_branches = 20;
+    builder.weak_fairness = true;
+    let preemption = builder.check_returning_stats(spin_until_stored);
+
+    assert!(preemption.unfair_pruned > 0);
+    assert!(
+        preemption.iterations > heuristic.iterations,
+        "{} > {}",
+        preemption.iterations,
+        heuristic.iterations
+    );
+}
+
+#[test]
+fn round_robin_yields_explore_fewer_schedules() {
+    fn spin_until_both_stored(builder: &loom::model::Builder) -> usize {
+        builder
+            .check_returning_stats(|| {
+                let stored = Arc::new(AtomicUsize::new(0));
+
+                for _ in 0..2 {
+                    let stored = stored.clone();
+                    thread::spawn(move || {
+                        stored.fetch_add(1, SeqCst);
+                    });
+                }
+
+                while stored.load(SeqCst) != 2 {
+                    thread::yield_now();
+                }
+            })
+            .iterations
+    }
+
+    let all = spin_until_both_stored(&loom::model::Builder::new());
+
+    let mut builder = loom::model::Builder::new();
+    builder.yield_mode = YieldMode::RoundRobin;
+    let round_robin = spin_until_both_stored(&builder);
+
+    assert!(round_robin < all, "{} < {}", round_robin, all);
+}